@@ -0,0 +1,517 @@
+"""
+MeDUSA Analytics Service
+
+Summarizes device readings (tremor analysis records) without returning
+individual rows to the client.
+
+Key Features:
+- Bucketing of readings into fixed aggregation periods
+- Per-metric statistics (mean, median, standard deviation, p95, min, max)
+- Contiguous rollups over a date range for a device or patient (empty buckets included)
+- Trend classification by least-squares slope, with per-reading-type polarity
+- IQR-based anomaly detection of a new reading against the device's recent readings
+- Readings below a signal quality threshold (or from devices overdue for
+  calibration) are flagged low_quality and left out of aggregates and trends
+"""
+
+import os
+import math
+from dataclasses import dataclass, field
+from datetime import datetime, timezone, timedelta
+from enum import Enum
+from itertools import groupby
+from typing import Any, Dict, List, Optional, Tuple
+
+import db
+
+# Numeric fields of a tremor analysis record that are summarized
+READING_METRIC_KEYS = [
+    "tremor_index",
+    "dominant_frequency",
+    "rms_value",
+    "signal_quality",
+    "tremor_power",
+    "total_power",
+]
+
+DEFAULT_READING_TYPE = "tremor"
+
+# Readings whose signal_quality (0-1) is below this are flagged low_quality
+READING_QUALITY_THRESHOLD = float(os.environ.get("READING_QUALITY_THRESHOLD", "0.6"))
+# signal_quality multiplier for readings of a device overdue for calibration;
+# readings without a signal_quality count as 1.0 before it is applied
+CALIBRATION_OVERDUE_QUALITY_FACTOR = float(os.environ.get("CALIBRATION_OVERDUE_QUALITY_FACTOR", "0.75"))
+
+
+class AggregationPeriod(Enum):
+    """Bucket size used when aggregating readings."""
+    FIVE_MINUTES = "five_minutes"  # Real-time monitoring
+    HOURLY = "hourly"
+    DAILY = "daily"
+    WEEKLY = "weekly"
+    MONTHLY = "monthly"
+
+
+def period_start(ts: datetime, period: AggregationPeriod) -> datetime:
+    """Truncate a UTC timestamp to the start of its aggregation bucket."""
+    ts = ts.astimezone(timezone.utc)
+    if period == AggregationPeriod.FIVE_MINUTES:
+        return ts.replace(minute=ts.minute - ts.minute % 5, second=0, microsecond=0)
+    if period == AggregationPeriod.HOURLY:
+        return ts.replace(minute=0, second=0, microsecond=0)
+    day = ts.replace(hour=0, minute=0, second=0, microsecond=0)
+    if period == AggregationPeriod.DAILY:
+        return day
+    if period == AggregationPeriod.WEEKLY:
+        return day - timedelta(days=day.weekday())  # ISO weeks start on Monday
+    return day.replace(day=1)
+
+
+def period_end(start: datetime, period: AggregationPeriod) -> datetime:
+    """Exclusive end of the bucket starting at start."""
+    if period == AggregationPeriod.FIVE_MINUTES:
+        return start + timedelta(minutes=5)
+    if period == AggregationPeriod.HOURLY:
+        return start + timedelta(hours=1)
+    if period == AggregationPeriod.DAILY:
+        return start + timedelta(days=1)
+    if period == AggregationPeriod.WEEKLY:
+        return start + timedelta(weeks=1)
+    if start.month == 12:
+        return start.replace(year=start.year + 1, month=1)
+    return start.replace(month=start.month + 1)
+
+
+def reading_time(reading: Dict[str, Any]) -> Optional[datetime]:
+    """Extract the timestamp of a reading (unix seconds or ISO-8601 string)."""
+    ts = reading.get("timestamp")
+    if isinstance(ts, (int, float)):
+        return datetime.fromtimestamp(ts, timezone.utc)
+    if isinstance(ts, str):
+        try:
+            parsed = datetime.fromisoformat(ts.replace("Z", "+00:00"))
+            return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+        except ValueError:
+            return None
+    return None
+
+
+def percentile(sorted_values: List[float], pct: float) -> float:
+    """Linear-interpolated percentile of an already sorted list."""
+    if not sorted_values:
+        raise ValueError("percentile of empty list")
+    if len(sorted_values) == 1:
+        return sorted_values[0]
+    rank = (len(sorted_values) - 1) * pct / 100.0
+    lower = math.floor(rank)
+    upper = math.ceil(rank)
+    if lower == upper:
+        return sorted_values[lower]
+    weight = rank - lower
+    return sorted_values[lower] * (1 - weight) + sorted_values[upper] * weight
+
+
+def compute_stats(values: List[float]) -> Dict[str, float]:
+    """Compute summary statistics for a non-empty list of values."""
+    ordered = sorted(values)
+    count = len(ordered)
+    mean = sum(ordered) / count
+    variance = sum((v - mean) ** 2 for v in ordered) / count
+    return {
+        "mean": mean,
+        "median": percentile(ordered, 50),
+        "stdDev": math.sqrt(variance),
+        "p95": percentile(ordered, 95),
+        "min": ordered[0],
+        "max": ordered[-1],
+    }
+
+
+def _metric_value(reading: Dict[str, Any], key: str) -> Optional[float]:
+    value = reading.get(key)
+    if isinstance(value, (int, float)) and not isinstance(value, bool):
+        return float(value)
+    return None
+
+
+# -------- Reading quality
+
+def score_reading_quality(item: Dict[str, Any], calibration_overdue: bool = False) -> Dict[str, Any]:
+    """
+    Down-score a sensor item of a device overdue for calibration and set
+    low_quality when its quality ends up below READING_QUALITY_THRESHOLD.
+    The item is updated in place and returned.
+    """
+    quality = _metric_value(item, "signal_quality")
+    if calibration_overdue:
+        if quality is not None:
+            item["signal_quality"] = quality = quality * CALIBRATION_OVERDUE_QUALITY_FACTOR
+        else:
+            quality = CALIBRATION_OVERDUE_QUALITY_FACTOR
+    if quality is not None and quality < READING_QUALITY_THRESHOLD:
+        item["low_quality"] = True
+    return item
+
+
+def is_low_quality(reading: Dict[str, Any]) -> bool:
+    """Whether a reading is left out of aggregates (flagged at ingestion, or stored before flagging)."""
+    if reading.get("low_quality"):
+        return True
+    quality = _metric_value(reading, "signal_quality")
+    return quality is not None and quality < READING_QUALITY_THRESHOLD
+
+
+def partition_by_quality(readings: List[Dict[str, Any]]) -> Tuple[List[Dict[str, Any]], List[Dict[str, Any]]]:
+    """Split readings into (usable, low_quality), keeping their order."""
+    usable, low = [], []
+    for reading in readings:
+        (low if is_low_quality(reading) else usable).append(reading)
+    return usable, low
+
+
+def aggregate_readings(
+    readings: List[Dict[str, Any]],
+    period: AggregationPeriod,
+    metric_keys: Optional[List[str]] = None
+) -> List[Dict[str, Any]]:
+    """
+    Bucket readings by period and reading type and summarize each bucket.
+
+    Readings without a parseable timestamp and low-quality readings are
+    ignored. Buckets are returned in chronological order.
+
+    Returns:
+        List of {periodStart, periodEnd, readingType, count, stats}
+        where stats maps metric key -> {mean, median, stdDev, p95, min, max}
+    """
+    keys = metric_keys or READING_METRIC_KEYS
+
+    def bucket_key(reading: Dict[str, Any]):
+        start = period_start(reading_time(reading), period)
+        return start, reading.get("reading_type", DEFAULT_READING_TYPE)
+
+    timed = [r for r in partition_by_quality(readings)[0] if reading_time(r) is not None]
+    timed.sort(key=bucket_key)
+
+    aggregated = []
+    for (start, reading_type), group in groupby(timed, key=bucket_key):
+        bucket = list(group)
+        stats = {}
+        for key in keys:
+            values = [
+                float(r[key]) for r in bucket
+                if isinstance(r.get(key), (int, float)) and not isinstance(r.get(key), bool)
+            ]
+            if values:
+                stats[key] = compute_stats(values)
+        aggregated.append({
+            "periodStart": start.isoformat(),
+            "periodEnd": period_end(start, period).isoformat(),
+            "readingType": reading_type,
+            "count": len(bucket),
+            "stats": stats,
+        })
+    return aggregated
+
+
+# -------- Rollups
+
+# Upper bound on buckets per rollup (e.g. ~3.5 days of five-minute buckets)
+MAX_ROLLUP_BUCKETS = 1000
+
+
+def bucket_starts(start: datetime, end: datetime, period: AggregationPeriod) -> List[datetime]:
+    """Start of every bucket overlapping [start, end], oldest first."""
+    starts = []
+    current = period_start(start, period)
+    while current <= end:
+        starts.append(current)
+        if len(starts) > MAX_ROLLUP_BUCKETS:
+            raise ValueError(f"Date range spans more than {MAX_ROLLUP_BUCKETS} {period.value} buckets")
+        current = period_end(current, period)
+    return starts
+
+
+def rollup_readings(
+    readings: List[Dict[str, Any]],
+    period: AggregationPeriod,
+    start: datetime,
+    end: datetime,
+    metric_keys: Optional[List[str]] = None
+) -> List[Dict[str, Any]]:
+    """
+    Summarize readings into one bucket per period between start and end.
+
+    Unlike aggregate_readings, every bucket in the range is returned; buckets
+    without readings have count 0 and null min/max/avg, so charts can show
+    gaps instead of interpolating over them. Readings outside the range,
+    without a timestamp or of low quality are ignored.
+
+    Returns:
+        List of {periodStart, periodEnd, count, metrics} where metrics maps
+        metric key -> {count, min, max, avg}
+    """
+    keys = metric_keys or READING_METRIC_KEYS
+    starts = bucket_starts(start, end, period)
+    grouped: Dict[datetime, List[Dict[str, Any]]] = {s: [] for s in starts}
+    for reading in partition_by_quality(readings)[0]:
+        ts = reading_time(reading)
+        if ts is None or ts < start or ts > end:
+            continue
+        grouped[period_start(ts, period)].append(reading)
+
+    # Report every metric that occurs anywhere in the range, in every bucket
+    present = [k for k in keys if any(_metric_value(r, k) is not None for b in grouped.values() for r in b)]
+
+    rollups = []
+    for bucket_start in starts:
+        bucket = grouped[bucket_start]
+        metrics = {}
+        for key in present:
+            values = [v for v in (_metric_value(r, key) for r in bucket) if v is not None]
+            metrics[key] = {
+                "count": len(values),
+                "min": min(values) if values else None,
+                "max": max(values) if values else None,
+                "avg": sum(values) / len(values) if values else None,
+            }
+        rollups.append({
+            "periodStart": bucket_start.isoformat(),
+            "periodEnd": period_end(bucket_start, period).isoformat(),
+            "count": len(bucket),
+            "metrics": metrics,
+        })
+    return rollups
+
+
+def get_reading_rollups(
+    period: AggregationPeriod,
+    start: datetime,
+    end: datetime,
+    device_id: Optional[str] = None,
+    patient_id: Optional[str] = None,
+    reading_type: Optional[str] = None
+) -> List[Dict[str, Any]]:
+    """
+    Fetch a device's or a patient's readings in [start, end] and roll them up.
+
+    Raises:
+        ValueError: if neither or both of device_id / patient_id are given,
+            start is after end, or the range has too many buckets
+    """
+    if (device_id is None) == (patient_id is None):
+        raise ValueError("Exactly one of device_id or patient_id is required")
+    if start > end:
+        raise ValueError("start must be before end")
+
+    start_time, end_time = int(start.timestamp()), int(end.timestamp())
+    if device_id is not None:
+        readings = db.get_device_readings(device_id, start_time, end_time)
+    else:
+        readings = db.get_patient_readings(patient_id, start_time, end_time)
+    if reading_type:
+        readings = [r for r in readings if r.get("reading_type", DEFAULT_READING_TYPE) == reading_type]
+    return rollup_readings(readings, period, start, end)
+
+
+# -------- Trends
+
+MIN_TREND_POINTS = 3
+# Fitted change over the whole series, relative to its mean, below which
+# the series counts as stable
+STABLE_CHANGE_RATIO = 0.05
+
+
+class TrendDirection(Enum):
+    IMPROVING = "improving"
+    STABLE = "stable"
+    DECLINING = "declining"
+    INSUFFICIENT = "insufficient"
+
+
+class ImprovementPolarity(Enum):
+    """Which way a metric has to move to count as improving."""
+    LOWER_IS_BETTER = "lower_is_better"
+    HIGHER_IS_BETTER = "higher_is_better"
+
+
+# Polarity per reading type; types not listed default to LOWER_IS_BETTER
+TREND_POLARITY = {
+    "tremor": ImprovementPolarity.LOWER_IS_BETTER,
+    "blood_pressure": ImprovementPolarity.LOWER_IS_BETTER,
+    "heart_rate": ImprovementPolarity.LOWER_IS_BETTER,
+    "temperature": ImprovementPolarity.LOWER_IS_BETTER,
+    "spo2": ImprovementPolarity.HIGHER_IS_BETTER,
+}
+
+
+@dataclass
+class TrendPoint:
+    timestamp: float  # unix seconds
+    value: float
+
+
+@dataclass
+class TrendData:
+    direction: TrendDirection
+    count: int
+    average: Optional[float] = None
+    min: Optional[float] = None
+    max: Optional[float] = None
+    slope_per_day: Optional[float] = None
+    points: List[TrendPoint] = field(default_factory=list)
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "direction": self.direction.value,
+            "count": self.count,
+            "average": self.average,
+            "min": self.min,
+            "max": self.max,
+            "slopePerDay": self.slope_per_day,
+            "points": [{"timestamp": p.timestamp, "value": p.value} for p in self.points],
+        }
+
+
+def polarity_for(reading_type: str) -> ImprovementPolarity:
+    return TREND_POLARITY.get(reading_type, ImprovementPolarity.LOWER_IS_BETTER)
+
+
+def linear_slope(points: List[TrendPoint]) -> Optional[float]:
+    """Least-squares slope in value units per second; None if all timestamps are equal."""
+    n = len(points)
+    mean_x = sum(p.timestamp for p in points) / n
+    mean_y = sum(p.value for p in points) / n
+    sxx = sum((p.timestamp - mean_x) ** 2 for p in points)
+    if sxx == 0:
+        return None
+    return sum((p.timestamp - mean_x) * (p.value - mean_y) for p in points) / sxx
+
+
+def compute_trend(
+    points: List[TrendPoint],
+    polarity: ImprovementPolarity = ImprovementPolarity.LOWER_IS_BETTER,
+    min_points: int = MIN_TREND_POINTS
+) -> TrendData:
+    """
+    Summarize a series and classify its direction.
+
+    The series is stable when the fitted change across its time span is
+    within STABLE_CHANGE_RATIO of the mean; otherwise the sign of the slope
+    combined with the polarity decides improving vs declining.
+    """
+    ordered = sorted(points, key=lambda p: p.timestamp)
+    if len(ordered) < min_points:
+        return TrendData(direction=TrendDirection.INSUFFICIENT, count=len(ordered), points=ordered)
+
+    values = [p.value for p in ordered]
+    average = sum(values) / len(values)
+    trend = TrendData(
+        direction=TrendDirection.INSUFFICIENT,
+        count=len(ordered),
+        average=average,
+        min=min(values),
+        max=max(values),
+        points=ordered,
+    )
+
+    slope = linear_slope(ordered)
+    if slope is None:
+        return trend
+    trend.slope_per_day = slope * 86400
+
+    span = ordered[-1].timestamp - ordered[0].timestamp
+    change_ratio = abs(slope * span) / (abs(average) or 1.0)
+    if change_ratio <= STABLE_CHANGE_RATIO:
+        trend.direction = TrendDirection.STABLE
+    elif (slope < 0) == (polarity == ImprovementPolarity.LOWER_IS_BETTER):
+        trend.direction = TrendDirection.IMPROVING
+    else:
+        trend.direction = TrendDirection.DECLINING
+    return trend
+
+
+def trend_for_metric(readings: List[Dict[str, Any]], metric: str, reading_type: str = DEFAULT_READING_TYPE) -> TrendData:
+    """Build TrendPoints for one metric of a reading list (low-quality readings excluded) and compute its trend."""
+    points = []
+    for reading in partition_by_quality(readings)[0]:
+        ts, value = reading_time(reading), reading.get(metric)
+        if ts is not None and isinstance(value, (int, float)) and not isinstance(value, bool):
+            points.append(TrendPoint(timestamp=ts.timestamp(), value=float(value)))
+    return compute_trend(points, polarity_for(reading_type))
+
+
+# ========================================
+# Anomaly Detection
+# ========================================
+
+ANOMALY_WINDOW = 20  # recent readings the new one is compared against
+ANOMALY_IQR_MULTIPLIER = 3.0
+MIN_ANOMALY_HISTORY = 4  # quartiles of fewer readings are meaningless
+
+# Sensor item fields that are not measurements
+NON_METRIC_FIELDS = {"device_id", "patient_id", "timestamp", "reading_type", "unit", "is_flagged", "anomalies", "location"}
+
+
+@dataclass
+class AnomalyAlert:
+    """A value outside [Q1 - k*IQR, Q3 + k*IQR] of the device's recent readings."""
+    key: str
+    value: float
+    expected_range: Tuple[float, float]
+    z_score: Optional[float]  # None when the recent readings have no spread
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "key": self.key,
+            "value": self.value,
+            "expectedRange": list(self.expected_range),
+            "zScore": self.z_score,
+        }
+
+
+def _numeric(value: Any) -> Optional[float]:
+    if isinstance(value, bool) or not isinstance(value, (int, float)):
+        return None
+    return float(value)
+
+
+class AnomalyDetector:
+    """Flags statistical outliers that may still be inside clinical thresholds (e.g. sudden spikes)."""
+
+    def __init__(self, window: int = ANOMALY_WINDOW, iqr_multiplier: float = ANOMALY_IQR_MULTIPLIER,
+                 min_history: int = MIN_ANOMALY_HISTORY):
+        self.window = window
+        self.iqr_multiplier = iqr_multiplier
+        self.min_history = min_history
+
+    def detect_anomalies(self, recent_readings: List[Dict[str, Any]], new_reading: Dict[str, Any]) -> List[AnomalyAlert]:
+        """
+        Compare each numeric value of new_reading with the same key in the
+        last `window` recent_readings (oldest first).
+
+        Returns:
+            One AnomalyAlert per out-of-range key (empty if there is too little history)
+        """
+        history = recent_readings[-self.window:]
+        alerts = []
+        for key, raw in new_reading.items():
+            value = _numeric(raw)
+            if key in NON_METRIC_FIELDS or value is None:
+                continue
+            past = sorted(v for v in (_numeric(r.get(key)) for r in history) if v is not None)
+            if len(past) < self.min_history:
+                continue
+            q1, q3 = percentile(past, 25), percentile(past, 75)
+            spread = self.iqr_multiplier * (q3 - q1)
+            low, high = q1 - spread, q3 + spread
+            if low <= value <= high:
+                continue
+            stats = compute_stats(past)
+            z_score = (value - stats["mean"]) / stats["stdDev"] if stats["stdDev"] else None
+            alerts.append(AnomalyAlert(key=key, value=value, expected_range=(low, high), z_score=z_score))
+        return alerts
+
+
+# Global anomaly detector instance
+anomaly_detector = AnomalyDetector()
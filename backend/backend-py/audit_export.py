@@ -0,0 +1,131 @@
+"""
+MeDUSA Audit Log Export
+
+Ships audit logs to S3 as NDJSON for SIEM / Athena ingestion. Runs on an
+EventBridge schedule (hourly); each run exports the entries written since
+the previous one.
+
+Layout (under S3_PREFIX_AUDIT_EXPORTS, default audit-exports/):
+    audit-exports/date=<YYYY-MM-DD>/audit-<window end>.ndjson
+    one audit entry per line, hash chain fields included
+
+Key Features:
+- Incremental: the end of the last exported window is stored as a system
+  setting (the high-water mark); the next run exports (mark, now - lag].
+  The first run starts AUDIT_EXPORT_INITIAL_DAYS back
+- AUDIT_EXPORT_LAG_SECONDS leaves room for entries still being written
+- Partitioned by the entry's UTC day (the date-index GSI), one object per
+  day per run; each day is streamed page by page into a multipart upload,
+  so windows of any size fit in memory
+- The mark advances after each uploaded day, so a failed run resumes where
+  it stopped
+- Every export is audited as DATA_EXPORT / export_audit_logs
+"""
+
+import os
+import json
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, Iterable, Iterator, List, Optional
+
+import db
+import storage
+from audit_service import audit_service, AuditEventType
+
+NDJSON_CONTENT_TYPE = "application/x-ndjson"
+HIGH_WATER_MARK_SETTING_KEY = "audit_export_high_water_mark"
+AUDIT_EXPORT_LAG_SECONDS = int(os.environ.get("AUDIT_EXPORT_LAG_SECONDS", "60"))
+AUDIT_EXPORT_INITIAL_DAYS = int(os.environ.get("AUDIT_EXPORT_INITIAL_DAYS", "1"))
+
+
+def make_audit_export_key(day: str, window_end: datetime) -> str:
+    return f"{storage.PAUDIT_EXPORT}date={day}/audit-{window_end.strftime('%Y%m%dT%H%M%SZ')}.ndjson"
+
+
+def get_high_water_mark() -> Optional[datetime]:
+    """End of the last exported window, or None before the first export."""
+    setting = db.get_system_setting(HIGH_WATER_MARK_SETTING_KEY)
+    return datetime.fromisoformat(setting["value"]) if setting and setting.get("value") else None
+
+
+def _set_high_water_mark(mark: datetime) -> None:
+    db.put_system_setting(HIGH_WATER_MARK_SETTING_KEY, mark.isoformat(), "system")
+
+
+def _days(start: datetime, end: datetime) -> List[str]:
+    day, last = start.date(), end.date()
+    days = []
+    while day <= last:
+        days.append(day.isoformat())
+        day += timedelta(days=1)
+    return days
+
+
+def _window_pages(day: str, start: datetime, end: datetime) -> Iterator[List[Dict[str, Any]]]:
+    """Pages of the day's entries within (start, end]; empty pages are skipped."""
+    start_iso, end_iso = start.isoformat(), end.isoformat()
+    for page in db.iter_audit_log_pages_by_date(day, start_iso, end_iso):
+        entries = [e for e in page if start_iso < e.get("timestamp", "") <= end_iso]
+        if entries:
+            yield entries
+
+
+def ndjson_chunks(pages: Iterable[List[Dict[str, Any]]], counter: Dict[str, int]) -> Iterator[bytes]:
+    """Render pages of entries as NDJSON, one chunk per page; counter["entries"] counts the lines."""
+    for page in pages:
+        counter["entries"] += len(page)
+        yield b"".join(json.dumps(e, default=str, sort_keys=True).encode("utf-8") + b"\n" for e in page)
+
+
+def export_audit_logs(start: datetime, end: datetime) -> Dict[str, Any]:
+    """
+    Export the entries written in (start, end] and advance the high-water mark to end.
+
+    Returns:
+        {"start", "end", "entries", "files": [{"key", "day", "entries", "sizeBytes"}]}
+    """
+    files = []
+    for day in _days(start, end):
+        pages = _window_pages(day, start, end)
+        first = next(pages, None)
+        if first is not None:
+            counter = {"entries": 0}
+            key = make_audit_export_key(day, end)
+
+            def _pages():
+                yield first
+                yield from pages
+
+            size = storage.multipart_upload(key, ndjson_chunks(_pages(), counter), NDJSON_CONTENT_TYPE)
+            files.append({"key": key, "day": day, "entries": counter["entries"], "sizeBytes": size})
+            print(f"[AuditExport] {key}: {counter['entries']} entries, {size} bytes")
+        day_end = datetime.fromisoformat(day).replace(tzinfo=timezone.utc) + timedelta(days=1)
+        _set_high_water_mark(min(end, day_end))
+
+    result = {"start": start.isoformat(), "end": end.isoformat(),
+              "entries": sum(f["entries"] for f in files), "files": files}
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_EXPORT,
+        user_id="system",
+        user_role="system",
+        resource_type="audit_logs",
+        action="export_audit_logs",
+        details={"start": result["start"], "end": result["end"], "entries": result["entries"],
+                 "keys": [f["key"] for f in files]}
+    )
+    return result
+
+
+def export_since_high_water_mark(now: Optional[datetime] = None) -> Dict[str, Any]:
+    """Export everything written since the last run (or AUDIT_EXPORT_INITIAL_DAYS on the first)."""
+    end = (now or datetime.now(timezone.utc)) - timedelta(seconds=AUDIT_EXPORT_LAG_SECONDS)
+    start = get_high_water_mark() or end - timedelta(days=AUDIT_EXPORT_INITIAL_DAYS)
+    if start >= end:
+        return {"start": start.isoformat(), "end": end.isoformat(), "entries": 0, "files": []}
+    return export_audit_logs(start, end)
+
+
+def run(event, context):
+    """EventBridge Scheduler entry point."""
+    result = export_since_high_water_mark()
+    print(f"[AuditExport] {result['entries']} entries in {len(result['files'])} file(s)")
+    return result
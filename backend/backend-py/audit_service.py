@@ -8,18 +8,33 @@ Key Features:
 - Security event logging (authentication, authorization, data access)
 - Structured JSON log format for CloudWatch analysis
 - PII protection (sensitive data masking)
-- Tamper-evident timestamps
+- Tamper-evident hash chains, one per organization and UTC day (chainId),
+  so writers of different tenants and days never contend: every entry
+  stores the SHA-256 of its predecessor (prev_hash) and of its own
+  canonical serialization (entry_hash), plus a per-chain sequence number;
+  verify_chain() detects modified, inserted and deleted entries. event_hash,
+  the first EVENT_HASH_LENGTH hex digits of entry_hash, is kept for
+  existing consumers
+- An entry that cannot be chained (the head kept moving) is still stored,
+  hashed but unlinked and flagged chainStatus=unchained
 - Event categorization for compliance reporting
+- Entries expire after AUDIT_LOG_RETENTION_DAYS (default 2555, 7 years for
+  HIPAA) via the table's ttl attribute; the date attribute (YYYY-MM-DD)
+  keys the date-index GSI for per-day queries
 """
 
 import os
 import json
 import time
 import hashlib
+from dataclasses import dataclass
 from datetime import datetime, timezone, timedelta
-from typing import Optional, Dict, Any, List
+from decimal import Decimal
+from typing import Optional, Dict, Any, Iterable, List
 from enum import Enum
 
+from request_context import DEFAULT_ORGANIZATION_ID, current_organization_id, current_request_id
+
 
 class AuditEventType(Enum):
     """
@@ -30,6 +45,7 @@ class AuditEventType(Enum):
     AUTH_LOGIN_FAILURE = "AUTH_LOGIN_FAILURE"
     AUTH_LOGOUT = "AUTH_LOGOUT"
     AUTH_TOKEN_REFRESH = "AUTH_TOKEN_REFRESH"
+    AUTH_SESSION_REVOKED = "AUTH_SESSION_REVOKED"
     AUTH_MFA_CHALLENGE = "AUTH_MFA_CHALLENGE"
     AUTH_MFA_SUCCESS = "AUTH_MFA_SUCCESS"
     AUTH_MFA_FAILURE = "AUTH_MFA_FAILURE"
@@ -53,17 +69,29 @@ class AuditEventType(Enum):
     DATA_UPDATE = "DATA_UPDATE"
     DATA_DELETE = "DATA_DELETE"
     DATA_EXPORT = "DATA_EXPORT"
+    DATA_IMPORT = "DATA_IMPORT"
+    REPORT_DOWNLOADED = "REPORT_DOWNLOADED"
+    REPORT_DELETED = "REPORT_DELETED"
+    DATA_PURGE = "DATA_PURGE"
     
     # Patient Data Events
     PATIENT_DATA_ACCESS = "PATIENT_DATA_ACCESS"
     PATIENT_PROFILE_UPDATE = "PATIENT_PROFILE_UPDATE"
     PATIENT_ASSIGNMENT = "PATIENT_ASSIGNMENT"
+    PATIENT_DELETED = "PATIENT_DELETED"
     
     # Device Events
     DEVICE_REGISTER = "DEVICE_REGISTER"
+    DEVICE_UPDATE = "DEVICE_UPDATE"
+    DEVICE_CALIBRATED = "DEVICE_CALIBRATED"
+    DEVICE_MAINTENANCE_SCHEDULED = "DEVICE_MAINTENANCE_SCHEDULED"
+    DEVICE_MAINTENANCE_COMPLETED = "DEVICE_MAINTENANCE_COMPLETED"
     DEVICE_BIND = "DEVICE_BIND"
     DEVICE_UNBIND = "DEVICE_UNBIND"
     DEVICE_DATA_RECEIVED = "DEVICE_DATA_RECEIVED"
+    DEVICE_CONNECTED = "DEVICE_CONNECTED"
+    DEVICE_DISCONNECTED = "DEVICE_DISCONNECTED"
+    DEVICE_LOW_BATTERY = "DEVICE_LOW_BATTERY"
     
     # Session Events
     SESSION_CREATE = "SESSION_CREATE"
@@ -74,9 +102,115 @@ class AuditEventType(Enum):
     SECURITY_INVALID_TOKEN = "SECURITY_INVALID_TOKEN"
     SECURITY_SUSPICIOUS_ACTIVITY = "SECURITY_SUSPICIOUS_ACTIVITY"
     
+    # Consent Events
+    CONSENT_GRANTED = "CONSENT_GRANTED"
+    CONSENT_REVOKED = "CONSENT_REVOKED"
+    
+    # Alert Events
+    ALERT_NOTIFICATION_FAILED = "ALERT_NOTIFICATION_FAILED"
+    
     # System Events
     SYSTEM_ERROR = "SYSTEM_ERROR"
     SYSTEM_CONFIG_CHANGE = "SYSTEM_CONFIG_CHANGE"
+    MAINTENANCE_MODE_ENABLED = "MAINTENANCE_MODE_ENABLED"
+    MAINTENANCE_MODE_DISABLED = "MAINTENANCE_MODE_DISABLED"
+
+
+# Entries are kept this long, then removed by DynamoDB TTL
+AUDIT_LOG_RETENTION_DAYS = int(os.environ.get("AUDIT_LOG_RETENTION_DAYS", "2555"))
+
+# prev_hash of the first entry of the chain
+GENESIS_HASH = "0" * 64
+# Attempts to append when other writers keep moving the chain head
+AUDIT_CHAIN_MAX_ATTEMPTS = 5
+# Length of the legacy event_hash (a truncated entry_hash)
+EVENT_HASH_LENGTH = 16
+# Not covered by entry_hash
+_UNHASHED_FIELDS = ("entry_hash", "event_hash")
+# chainStatus of entries stored outside their chain
+UNCHAINED = "unchained"
+
+
+def audit_chain_id(organization_id: Optional[str], day: str) -> str:
+    """The chain an entry of an organization (None: unscoped) written on a UTC day (YYYY-MM-DD) belongs to"""
+    return f"{organization_id or DEFAULT_ORGANIZATION_ID}#{day}"
+
+
+def _canonical_value(value: Any) -> Any:
+    # DynamoDB hands numbers back as Decimal; hash them as the int/float that was written
+    if isinstance(value, Decimal):
+        return int(value) if value % 1 == 0 else float(value)
+    if isinstance(value, dict):
+        return {k: _canonical_value(v) for k, v in value.items()}
+    if isinstance(value, list):
+        return [_canonical_value(v) for v in value]
+    return value
+
+
+def canonical_serialization(entry: Dict[str, Any]) -> bytes:
+    """
+    The bytes entry_hash is computed over: every attribute except entry_hash
+    and event_hash (derived from it), keys sorted, no whitespace. Top-level
+    None values are left out because unset index attributes are not stored.
+    """
+    data = {k: _canonical_value(v) for k, v in entry.items() if k not in _UNHASHED_FIELDS and v is not None}
+    return json.dumps(data, sort_keys=True, separators=(",", ":"), default=str).encode("utf-8")
+
+
+def compute_entry_hash(entry: Dict[str, Any]) -> str:
+    """SHA-256 (hex) of the entry's canonical serialization; covers prev_hash and sequence."""
+    return hashlib.sha256(canonical_serialization(entry)).hexdigest()
+
+
+@dataclass
+class ChainVerification:
+    """Result of verify_chain; error and logId describe the first broken link."""
+    valid: bool
+    checked: int
+    error: Optional[str] = None
+    logId: Optional[str] = None
+
+
+def verify_chain(
+    logs: Iterable[Dict[str, Any]],
+    previous_hash: str = GENESIS_HASH,
+    head_hash: Optional[str] = None
+) -> ChainVerification:
+    """
+    Verify a run of audit entries of one chain (any order; sorted by sequence).
+    Unchained entries are only checked against their own entry_hash.
+
+    Args:
+        logs: The entries, from the one following previous_hash onwards
+        previous_hash: entry_hash of the entry before the first one
+            (GENESIS_HASH when verifying from the start of the chain)
+        head_hash: The chain head's entry_hash; when given, the last entry
+            must be the head, so deleting the newest entries is detected
+
+    Detects:
+        - modification: an entry no longer matches its entry_hash
+        - insertion / deletion: a prev_hash that does not match the
+          preceding entry, or a gap in the sequence numbers
+    """
+    logs = list(logs)
+    for entry in logs:
+        if entry.get("chainStatus") == UNCHAINED and compute_entry_hash(entry) != entry.get("entry_hash"):
+            return ChainVerification(False, 0, "unchained entry does not match its entry_hash", entry.get("logId"))
+    entries = sorted((e for e in logs if e.get("chainStatus") != UNCHAINED), key=lambda e: int(e.get("sequence", 0)))
+    expected_prev, expected_seq = previous_hash, None
+    for checked, entry in enumerate(entries):
+        log_id = entry.get("logId")
+        sequence = int(entry.get("sequence", 0))
+        if expected_seq is not None and sequence != expected_seq:
+            return ChainVerification(False, checked, f"sequence {sequence} follows {expected_seq - 1}", log_id)
+        if entry.get("prev_hash") != expected_prev:
+            return ChainVerification(False, checked, "prev_hash does not match the preceding entry", log_id)
+        if compute_entry_hash(entry) != entry.get("entry_hash"):
+            return ChainVerification(False, checked, "entry does not match its entry_hash", log_id)
+        expected_prev, expected_seq = entry["entry_hash"], sequence + 1
+    if head_hash is not None and expected_prev != head_hash:
+        return ChainVerification(False, len(entries), "chain does not end at the chain head")
+    return ChainVerification(True, len(entries))
 
 
 class AuditSeverity(Enum):
@@ -98,7 +232,7 @@ class AuditService:
     - Consistent JSON format for CloudWatch Logs Insights
     - PII protection through data masking
     - Event correlation via request IDs
-    - Tamper-evident timestamps with hash chains
+    - Tamper-evident hash chains per organization and day across all writers (see verify_chain)
     """
     
     # Fields that should be masked for PII protection
@@ -123,7 +257,6 @@ class AuditService:
         """
         self.service_name = service_name
         self.environment = os.environ.get("ENVIRONMENT", "production")
-        self._last_hash = None
     
     def _mask_sensitive_data(self, data: Dict[str, Any]) -> Dict[str, Any]:
         """
@@ -160,24 +293,39 @@ class AuditService:
                 
         return masked
     
-    def _generate_event_hash(self, event_data: Dict[str, Any]) -> str:
+    def _append_to_chain(self, audit_entry: Dict[str, Any]) -> None:
         """
-        Generate a hash for event integrity verification.
-        Creates a chain with previous hash for tamper evidence.
-        
-        Args:
-            event_data: The event data to hash
-            
-        Returns:
-            SHA-256 hash string
+        Link the entry to the head of its chain and store it.
+
+        The head only advances if nobody appended in between; otherwise the
+        entry is re-linked to the new head and the write retried. After
+        AUDIT_CHAIN_MAX_ATTEMPTS lost races the entry is stored unchained
+        rather than dropped.
         """
-        hash_input = json.dumps(event_data, sort_keys=True, default=str)
-        if self._last_hash:
-            hash_input = self._last_hash + hash_input
-        
-        event_hash = hashlib.sha256(hash_input.encode()).hexdigest()[:16]
-        self._last_hash = event_hash
-        return event_hash
+        import db
+        chain_id = audit_entry["chainId"]
+        for _ in range(AUDIT_CHAIN_MAX_ATTEMPTS):
+            head = db.get_audit_chain_head(chain_id)
+            audit_entry["sequence"] = (head["sequence"] + 1) if head else 1
+            audit_entry["prev_hash"] = head["entry_hash"] if head else GENESIS_HASH
+            self._hash(audit_entry)
+            try:
+                db.append_audit_log(audit_entry, head["entry_hash"] if head else None)
+                return
+            except db.ConditionFailedError:
+                continue
+        print(f"[AUDIT_CHAIN] Head of {chain_id} kept moving after {AUDIT_CHAIN_MAX_ATTEMPTS} attempts; storing unchained")
+        audit_entry.pop("sequence", None)
+        audit_entry.pop("prev_hash", None)
+        audit_entry["chainStatus"] = UNCHAINED
+        self._hash(audit_entry)
+        if not db.put_audit_log(audit_entry):
+            raise RuntimeError(f"failed to store unchained audit entry {audit_entry['logId']}")
+
+    @staticmethod
+    def _hash(audit_entry: Dict[str, Any]) -> None:
+        audit_entry["entry_hash"] = compute_entry_hash(audit_entry)
+        audit_entry["event_hash"] = audit_entry["entry_hash"][:EVENT_HASH_LENGTH]
     
     def _get_severity_for_event(self, event_type: AuditEventType) -> AuditSeverity:
         """
@@ -201,6 +349,7 @@ class AuditService:
             AuditEventType.AUTH_MFA_FAILURE,
             AuditEventType.AUTHZ_ACCESS_DENIED,
             AuditEventType.SECURITY_INVALID_TOKEN,
+            AuditEventType.ALERT_NOTIFICATION_FAILED,
             AuditEventType.SYSTEM_ERROR,
         }
         
@@ -208,7 +357,13 @@ class AuditService:
         warning_events = {
             AuditEventType.SECURITY_RATE_LIMIT_EXCEEDED,
             AuditEventType.DATA_DELETE,
+            AuditEventType.DATA_PURGE,
+            AuditEventType.PATIENT_DELETED,
             AuditEventType.DEVICE_UNBIND,
+            AuditEventType.DEVICE_LOW_BATTERY,
+            AuditEventType.MAINTENANCE_MODE_ENABLED,
+            AuditEventType.CONSENT_GRANTED,
+            AuditEventType.CONSENT_REVOKED,
         }
         
         if event_type in critical_events:
@@ -247,7 +402,7 @@ class AuditService:
             action: Specific action being performed
             outcome: Outcome of the action (success/failure/denied)
             details: Additional event-specific details
-            request_id: Correlation ID for request tracking
+            request_id: Correlation ID for request tracking (default: the current request's ID)
             ip_address: Client IP address
             user_agent: Client user agent string
             severity: Override automatic severity determination
@@ -278,6 +433,7 @@ class AuditService:
             "environment": self.environment,
             "timestamp": timestamp.isoformat(),
             "timestamp_unix": int(timestamp.timestamp() * 1000),  # Milliseconds
+            "date": timestamp.strftime("%Y-%m-%d"),  # date-index partition key
             
             # Event classification - flattened for GSI
             "eventType": event_type.value,
@@ -299,19 +455,19 @@ class AuditService:
             "details": self._mask_sensitive_data(details) if details else None,
             
             # Correlation and integrity
-            "requestId": request_id,
+            "requestId": request_id or current_request_id(),
             
-            # TTL for automatic cleanup (90 days retention)
-            "ttl": int((timestamp + timedelta(days=90)).timestamp())
+            # Tenant of the request (None outside one) and the hash chain it joins
+            "organizationId": current_organization_id(),
+            "chainId": audit_chain_id(current_organization_id(), timestamp.strftime("%Y-%m-%d")),
+            
+            # TTL for automatic cleanup after the retention period
+            "ttl": int((timestamp + timedelta(days=AUDIT_LOG_RETENTION_DAYS)).timestamp())
         }
         
-        # Add event hash for integrity verification
-        audit_entry["event_hash"] = self._generate_event_hash(audit_entry)
-        
-        # Store in DynamoDB
+        # Chain to the previous entry of its chain (sequence, prev_hash, entry_hash, event_hash) and store in DynamoDB
         try:
-            import db
-            db.put_audit_log(audit_entry)
+            self._append_to_chain(audit_entry)
         except Exception as e:
             print(f"[AUDIT_STORE_ERROR] Failed to store audit log: {e}")
         
@@ -329,7 +485,8 @@ class AuditService:
         user_role: str,
         ip_address: Optional[str] = None,
         user_agent: Optional[str] = None,
-        request_id: Optional[str] = None
+        request_id: Optional[str] = None,
+        geo: Optional[Dict[str, Any]] = None
     ):
         """Log successful login event."""
         return self.log_event(
@@ -338,6 +495,7 @@ class AuditService:
             user_role=user_role,
             action="login",
             outcome="success",
+            details={"geo": geo} if geo else None,
             ip_address=ip_address,
             user_agent=user_agent,
             request_id=request_id
@@ -345,18 +503,19 @@ class AuditService:
     
     def log_login_failure(
         self,
-        email: str,
+        email: Optional[str],
         reason: str,
         ip_address: Optional[str] = None,
         user_agent: Optional[str] = None,
-        request_id: Optional[str] = None
+        request_id: Optional[str] = None,
+        geo: Optional[Dict[str, Any]] = None
     ):
         """Log failed login attempt."""
         return self.log_event(
             event_type=AuditEventType.AUTH_LOGIN_FAILURE,
             action="login",
             outcome="failure",
-            details={"email": email, "reason": reason},
+            details={"email": email, "reason": reason, "geo": geo},
             ip_address=ip_address,
             user_agent=user_agent,
             request_id=request_id
@@ -1,9 +1,11 @@
-import os, time, jwt, pyotp
+import os, re, time, uuid, jwt, pyotp
+from datetime import datetime, timezone, timedelta
 from argon2 import PasswordHasher
 from argon2.exceptions import VerifyMismatchError
 from fastapi import Request, HTTPException
 from fastapi.responses import JSONResponse
-from typing import Dict, Any
+from typing import Dict, Any, Optional
+from request_context import DEFAULT_ORGANIZATION_ID, organization_scope
 
 # Security: JWT_SECRET must be set in environment - no fallback for production safety
 JWT_SECRET = os.environ.get("JWT_SECRET")
@@ -11,20 +13,109 @@ if not JWT_SECRET:
     raise ValueError("JWT_SECRET environment variable must be set")
 JWT_EXPIRE_SECONDS = int(os.environ.get("JWT_EXPIRE_SECONDS", "3600"))
 REFRESH_TTL_SECONDS = int(os.environ.get("REFRESH_TTL_SECONDS", str(7*24*3600)))
+
+def _per_role_seconds(prefix: str) -> Dict[str, int]:
+    """Collect per-role overrides such as JWT_EXPIRE_SECONDS_PATIENT=900 into {"patient": 900}."""
+    return {
+        key[len(prefix) + 1:].lower(): int(value)
+        for key, value in os.environ.items()
+        if key.startswith(prefix + "_") and value.strip()
+    }
+
+# Optional per-role lifetimes; roles without an override use the global values
+JWT_EXPIRE_SECONDS_BY_ROLE = _per_role_seconds("JWT_EXPIRE_SECONDS")
+REFRESH_TTL_SECONDS_BY_ROLE = _per_role_seconds("REFRESH_TTL_SECONDS")
 MFA_TEMP_TOKEN_SECONDS = 300  # 5 minutes for MFA challenge
+# Clock skew tolerated between services when checking exp and iat
+JWT_LEEWAY_SECONDS = int(os.environ.get("JWT_LEEWAY_SECONDS", "30"))
+# iss / aud of every token; a token minted for another environment (other
+# issuer or audience) is refused
+JWT_ISSUER = os.environ.get("JWT_ISSUER", "medusa-api")
+JWT_AUDIENCE = os.environ.get("JWT_AUDIENCE", "medusa")
+
+def _registered_claims(now: int, exp: int) -> Dict[str, Any]:
+    return {"iss": JWT_ISSUER, "aud": JWT_AUDIENCE, "iat": now, "nbf": now, "exp": exp}
+
+# Argon2id cost parameters (defaults match argon2-cffi); raising these
+# upgrades existing hashes on the user's next successful login
+ARGON2_TIME_COST = int(os.environ.get("ARGON2_TIME_COST", "3"))
+ARGON2_MEMORY_COST = int(os.environ.get("ARGON2_MEMORY_COST", "65536"))
+ARGON2_PARALLELISM = int(os.environ.get("ARGON2_PARALLELISM", "4"))
 
 # Initialize Argon2id hasher
-ph = PasswordHasher()
+ph = PasswordHasher(
+    time_cost=ARGON2_TIME_COST,
+    memory_cost=ARGON2_MEMORY_COST,
+    parallelism=ARGON2_PARALLELISM
+)
+
+_ARGON2_PARAMS = re.compile(r"^\$argon2id\$v=\d+\$m=(\d+),t=(\d+),p=(\d+)\$")
 
 def hash_pw(pw: str) -> str:
     return ph.hash(pw)
 
+def needs_rehash(hashed: str) -> bool:
+    """
+    Check whether a stored hash was made with weaker parameters than the
+    current config (lower memory/time cost or parallelism, or not argon2id).
+    """
+    match = _ARGON2_PARAMS.match(hashed or "")
+    if not match:
+        return True
+    memory_cost, time_cost, parallelism = (int(g) for g in match.groups())
+    return (
+        memory_cost < ARGON2_MEMORY_COST
+        or time_cost < ARGON2_TIME_COST
+        or parallelism < ARGON2_PARALLELISM
+    )
+
 def verify_pw(pw: str, hashed: str) -> bool:
     try:
         return ph.verify(hashed, pw)
     except (VerifyMismatchError, Exception):
         return False
 
+# Number of previous password hashes kept in user["passwordHistory"]; a new
+# password may match neither the current one nor any of these (0 disables)
+PASSWORD_HISTORY_SIZE = int(os.environ.get("PASSWORD_HISTORY_SIZE", "5"))
+
+# Password rotation: passwords older than PASSWORD_MAX_AGE_DAYS (0 disables)
+# are expired. Login still succeeds with passwordExpired=true so the client
+# can force a password change, unless PASSWORD_EXPIRY_BLOCK hard-blocks it.
+PASSWORD_MAX_AGE_DAYS = int(os.environ.get("PASSWORD_MAX_AGE_DAYS", "0"))
+PASSWORD_EXPIRY_BLOCK = os.environ.get("PASSWORD_EXPIRY_BLOCK", "false").lower() == "true"
+
+def is_password_expired(user: Dict[str, Any], now: Optional[datetime] = None) -> bool:
+    """Check whether the user's password is older than PASSWORD_MAX_AGE_DAYS (accounts never changed count from createdAt)."""
+    changed = user.get("passwordChangedAt") or user.get("createdAt")
+    if PASSWORD_MAX_AGE_DAYS <= 0 or not changed:
+        return False
+    changed_at = datetime.fromisoformat(changed.replace("Z", "+00:00"))
+    if changed_at.tzinfo is None:
+        changed_at = changed_at.replace(tzinfo=timezone.utc)
+    return (now or datetime.now(timezone.utc)) - changed_at > timedelta(days=PASSWORD_MAX_AGE_DAYS)
+
+def is_password_reused(pw: str, user: Dict[str, Any]) -> bool:
+    """Check pw against the user's current password and password history."""
+    if PASSWORD_HISTORY_SIZE <= 0:
+        return False
+    hashes = [user.get("password")] + list(user.get("passwordHistory") or [])[:PASSWORD_HISTORY_SIZE]
+    return any(h and verify_pw(pw, h) for h in hashes)
+
+def password_change_fields(pw: str, user: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    User fields for setting a new password: the new hash, the replaced
+    hash pushed onto passwordHistory (newest first, trimmed) and passwordChangedAt.
+    """
+    history = list(user.get("passwordHistory") or [])
+    if user.get("password"):
+        history.insert(0, user["password"])
+    return {
+        "password": hash_pw(pw),
+        "passwordHistory": history[:max(PASSWORD_HISTORY_SIZE, 0)],
+        "passwordChangedAt": datetime.now(timezone.utc).isoformat(),
+    }
+
 # ========== MFA (TOTP) Functions ==========
 
 def generate_mfa_secret() -> str:
@@ -49,14 +140,36 @@ def issue_temp_token(sub: str, role: str) -> str:
     """
     now = int(time.time())
     return jwt.encode(
-        {"sub": sub, "role": role, "exp": now + MFA_TEMP_TOKEN_SECONDS, "scope": "mfa_pending"},
+        {"sub": sub, "role": role, "scope": "mfa_pending", **_registered_claims(now, now + MFA_TEMP_TOKEN_SECONDS)},
         JWT_SECRET, algorithm="HS256"
     )
 
+def decode_token(token: str) -> Dict[str, Any]:
+    """
+    Decode and verify a token signed with JWT_SECRET.
+
+    iss and aud must match JWT_ISSUER / JWT_AUDIENCE. exp, nbf and iat are
+    checked with the same JWT_LEEWAY_SECONDS tolerance: a token is accepted
+    until leeway seconds after exp, and refused if nbf or iat is more than
+    leeway seconds in the future.
+
+    Raises:
+        jwt.ExpiredSignatureError, jwt.ImmatureSignatureError, jwt.InvalidTokenError
+    """
+    claims = jwt.decode(
+        token, JWT_SECRET, algorithms=["HS256"], leeway=JWT_LEEWAY_SECONDS,
+        issuer=JWT_ISSUER, audience=JWT_AUDIENCE,
+        options={"require": ["exp", "iss", "aud"]}
+    )
+    iat = claims.get("iat")
+    if iat is not None and iat > time.time() + JWT_LEEWAY_SECONDS:
+        raise jwt.ImmatureSignatureError("The token is not yet valid (iat)")
+    return claims
+
 def verify_temp_token(token: str) -> Dict[str, Any]:
     """Verify a temporary MFA token and check scope."""
     try:
-        claims = jwt.decode(token, JWT_SECRET, algorithms=["HS256"])
+        claims = decode_token(token)
         if claims.get("scope") != "mfa_pending":
             raise HTTPException(status_code=401, detail={"code": "AUTH_INVALID", "message": "invalid token scope"})
         return claims
@@ -67,35 +180,66 @@ def verify_temp_token(token: str) -> Dict[str, Any]:
 
 # ========== Token Functions ==========
 
-def issue_tokens(sub: str, role: str) -> Dict[str, Any]:
+def access_ttl_for(role: Optional[str]) -> int:
+    return JWT_EXPIRE_SECONDS_BY_ROLE.get((role or "").lower(), JWT_EXPIRE_SECONDS)
+
+def refresh_ttl_for(role: Optional[str]) -> int:
+    return REFRESH_TTL_SECONDS_BY_ROLE.get((role or "").lower(), REFRESH_TTL_SECONDS)
+
+def issue_tokens(sub: str, role: str, session_id: Optional[str] = None,
+                 organization_id: Optional[str] = None) -> Dict[str, Any]:
     """
     Issue access and refresh tokens
     Returns dict with camelCase keys to match API v3 Documentation
+
+    session_id (sid claim) identifies a login session across refresh token
+    rotations; a new one is generated when not given. organization_id (org
+    claim) is the user's tenant. A custom role is passed in its stored
+    encoding (rbac.CustomRole.as_str), so the role claim carries its
+    permissions and RBAC checks need no role lookup.
     """
     now = int(time.time())
+    refresh_jti = uuid.uuid4().hex
+    session_id = session_id or uuid.uuid4().hex
+    org = organization_id or DEFAULT_ORGANIZATION_ID
+    access_ttl, refresh_ttl = access_ttl_for(role), refresh_ttl_for(role)
     access = jwt.encode(
-        {"sub": sub, "role": role, "exp": now + JWT_EXPIRE_SECONDS},
+        {"sub": sub, "role": role, "sid": session_id, "org": org, **_registered_claims(now, now + access_ttl)},
         JWT_SECRET, algorithm="HS256"
     )
     refresh = jwt.encode(
-        {"sub": sub, "role": role, "exp": now + REFRESH_TTL_SECONDS, "typ": "refresh"},
+        {"sub": sub, "role": role, "typ": "refresh", "jti": refresh_jti, "sid": session_id, "org": org,
+         **_registered_claims(now, now + refresh_ttl)},
         JWT_SECRET, algorithm="HS256"
     )
     # API v3 uses camelCase: accessJwt, refreshToken, expiresIn
+    # refreshJti / sessionId / refreshExpiresAt are for server-side session tracking and not returned to clients
     return {
         "accessJwt": access,
         "refreshToken": refresh,
-        "expiresIn": JWT_EXPIRE_SECONDS
+        "refreshJti": refresh_jti,
+        "sessionId": session_id,
+        "organizationId": org,
+        "refreshExpiresAt": now + refresh_ttl,
+        "expiresIn": access_ttl
     }
 
 def verify_jwt(token: str) -> Dict[str, Any]:
     try:
-        return jwt.decode(token, JWT_SECRET, algorithms=["HS256"])
+        return decode_token(token)
     except jwt.ExpiredSignatureError:
         raise HTTPException(status_code=401, detail={"code":"AUTH_EXPIRED","message":"token expired"})
     except Exception:
         raise HTTPException(status_code=401, detail={"code":"AUTH_INVALID","message":"invalid token"})
 
+def decode_refresh_token(token: str) -> Optional[Dict[str, Any]]:
+    """Decode a refresh token; None if invalid, expired or not a refresh token."""
+    try:
+        claims = decode_token(token)
+    except Exception:
+        return None
+    return claims if claims.get("typ") == "refresh" else None
+
 OPEN_PATH_SUFFIXES = [
     "/admin/health", 
     "/auth/login", 
@@ -107,6 +251,8 @@ OPEN_PATH_SUFFIXES = [
     "/auth/request-verification",  # Request verification code
     "/auth/send-verification-code",  # Legacy - keep for compatibility
     "/auth/send-password-reset-code",  # Legacy
+    "/auth/request-otp",  # SMS password reset code
+    "/auth/verify-otp",
     "/current-session",  # Allow Pi devices to poll for current session
     "/security/nonce"    # Nonce endpoint for replay protection
 ]
@@ -124,4 +270,5 @@ async def auth_middleware(request: Request, call_next):
         return JSONResponse(status_code=401, content={"code":"AUTH_REQUIRED","message":"missing bearer token"})
     claims = verify_jwt(bearer.removeprefix("Bearer ").strip())
     request.state.claims = claims
-    return await call_next(request)
+    with organization_scope(claims.get("org") or DEFAULT_ORGANIZATION_ID):
+        return await call_next(request)
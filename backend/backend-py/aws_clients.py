@@ -0,0 +1,60 @@
+"""
+MeDUSA AWS Clients
+
+boto3 clients and resources shared by every module of a Lambda container.
+
+Creating a client loads the service model and builds a connection pool
+(tens of milliseconds); a warm invocation that reuses one skips that and
+keeps its pooled HTTPS connections open. Module-level state lives as long
+as the container, so a client created during one invocation is reused by
+all later ones.
+
+Key Features:
+- get_client(service, **kwargs) / get_resource(service, **kwargs): one
+  instance per (service, kwargs) per container
+- All of them share one botocore Config: AWS_MAX_POOL_CONNECTIONS (default
+  10) pooled connections with TCP keep-alive
+- Configuration (environment variables, region) is read when a client is
+  first created. Lambda environment variables only change with a new
+  deployment, which starts new containers, so caching them is safe
+- reset_clients() drops every cached client (tests)
+"""
+
+import os
+import threading
+from typing import Any, Dict, Tuple
+
+import boto3
+from botocore.config import Config
+
+AWS_MAX_POOL_CONNECTIONS = int(os.environ.get("AWS_MAX_POOL_CONNECTIONS", "10"))
+
+CLIENT_CONFIG = Config(max_pool_connections=AWS_MAX_POOL_CONNECTIONS, tcp_keepalive=True)
+
+_clients: Dict[Tuple[str, str, Tuple[Tuple[str, Any], ...]], Any] = {}
+_lock = threading.Lock()
+
+
+def _cached(kind: str, service_name: str, kwargs: Dict[str, Any]):
+    key = (kind, service_name, tuple(sorted(kwargs.items())))
+    with _lock:
+        if key not in _clients:
+            factory = boto3.client if kind == "client" else boto3.resource
+            _clients[key] = factory(service_name, config=CLIENT_CONFIG, **kwargs)
+        return _clients[key]
+
+
+def get_client(service_name: str, **kwargs):
+    """The container's boto3 client for service_name with these arguments (e.g. region_name, endpoint_url)."""
+    return _cached("client", service_name, kwargs)
+
+
+def get_resource(service_name: str, **kwargs):
+    """The container's boto3 resource for service_name with these arguments."""
+    return _cached("resource", service_name, kwargs)
+
+
+def reset_clients() -> None:
+    """Forget every cached client so the next call creates a new one (for tests)."""
+    with _lock:
+        _clients.clear()
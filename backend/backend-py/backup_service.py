@@ -0,0 +1,242 @@
+"""
+MeDUSA Backup Service
+
+Full backups of the API's DynamoDB tables to S3, and restores from them.
+
+Layout (under S3_PREFIX_BACKUPS, default backups/):
+    backups/<timestamp>/<table_name>.jsonl   one item per line, DynamoDB JSON
+    backups/<timestamp>/manifest.json        BackupManifest
+
+- run: admin Lambda handler. {"action": "backup"} (also the nightly
+       schedule's event) or {"action": "restore", "manifestKey": "..."}
+
+Key Features:
+- Every table configured through a DDB_TABLE_* variable is backed up; each
+  is scanned page by page (ExclusiveStartKey), so tables of any size fit
+- Items are kept in DynamoDB JSON (typed attribute values), so a restore
+  writes back exactly what was read, numbers and binary values included
+- The manifest lists each table's record count, file size and SHA-256;
+  restore verifies the checksum before writing anything of that table
+- Files and manifest are written with storage.upload_sensitive
+  (SENSITIVE_ENCRYPTION_MODE)
+- Restore writes to the tables currently configured for each DDB_TABLE_*
+  variable, so a backup can be restored into another environment
+"""
+
+import os
+import json
+import base64
+import hashlib
+from dataclasses import asdict, dataclass, field
+from datetime import datetime, timezone
+from typing import Any, Dict, Iterator, List, Optional
+
+import aws_clients
+import db
+import storage
+from retry import with_retry
+
+BACKUP_CONTENT_TYPE = "application/x-ndjson"
+MANIFEST_FILENAME = "manifest.json"
+BATCH_WRITE_SIZE = 25  # DynamoDB BatchWriteItem limit
+MAX_UNPROCESSED_RETRIES = 5
+
+
+class BackupError(Exception):
+    """Raised when a backup cannot be restored as recorded in its manifest."""
+
+
+@dataclass
+class BackupTableEntry:
+    envVar: str
+    tableName: str
+    key: str
+    recordCount: int
+    sizeBytes: int
+    sha256: str
+
+
+@dataclass
+class BackupManifest:
+    backupId: str
+    createdAt: str
+    tables: List[BackupTableEntry] = field(default_factory=list)
+
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+    @classmethod
+    def from_dict(cls, data: Dict[str, Any]) -> "BackupManifest":
+        return cls(
+            backupId=data["backupId"],
+            createdAt=data["createdAt"],
+            tables=[BackupTableEntry(**t) for t in data.get("tables", [])],
+        )
+
+
+@dataclass
+class RestoreReport:
+    manifestKey: str
+    restored: Dict[str, int] = field(default_factory=dict)  # target table -> items written
+    errors: Dict[str, str] = field(default_factory=dict)  # DDB_TABLE_* variable -> error
+
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+
+def configured_tables() -> Dict[str, str]:
+    """DDB_TABLE_* variable -> table name, for every configured table."""
+    return {
+        name: value
+        for name, value in sorted(os.environ.items())
+        if name.startswith("DDB_TABLE_") and value.strip()
+    }
+
+
+# boto3 returns binary attribute values as bytes; JSON holds them as base64
+def _encode_value(value: Dict[str, Any]) -> Dict[str, Any]:
+    (type_, inner), = value.items()
+    if type_ == "B":
+        return {"B": base64.b64encode(inner).decode()}
+    if type_ == "BS":
+        return {"BS": [base64.b64encode(b).decode() for b in inner]}
+    if type_ == "L":
+        return {"L": [_encode_value(v) for v in inner]}
+    if type_ == "M":
+        return {"M": {k: _encode_value(v) for k, v in inner.items()}}
+    return value
+
+
+def _decode_value(value: Dict[str, Any]) -> Dict[str, Any]:
+    (type_, inner), = value.items()
+    if type_ == "B":
+        return {"B": base64.b64decode(inner)}
+    if type_ == "BS":
+        return {"BS": [base64.b64decode(b) for b in inner]}
+    if type_ == "L":
+        return {"L": [_decode_value(v) for v in inner]}
+    if type_ == "M":
+        return {"M": {k: _decode_value(v) for k, v in inner.items()}}
+    return value
+
+
+def encode_item(item: Dict[str, Any]) -> str:
+    return json.dumps({k: _encode_value(v) for k, v in item.items()}, sort_keys=True)
+
+
+def decode_item(line: str) -> Dict[str, Any]:
+    return {k: _decode_value(v) for k, v in json.loads(line).items()}
+
+
+class BackupService:
+    """Backs up and restores the configured DynamoDB tables."""
+
+    def __init__(self, client=None):
+        self._client = client
+
+    def _dynamodb(self):
+        """Created lazily so importing this module needs no AWS region."""
+        if self._client is None:
+            self._client = aws_clients.get_client("dynamodb", endpoint_url=db.DYNAMODB_ENDPOINT_URL)
+        return self._client
+
+    def scan_table(self, table_name: str) -> Iterator[Dict[str, Any]]:
+        """Every item of the table, one scan page at a time."""
+        kwargs: Dict[str, Any] = {"TableName": table_name}
+        while True:
+            resp = self._dynamodb().scan(**kwargs)
+            yield from resp.get("Items", [])
+            if "LastEvaluatedKey" not in resp:
+                return
+            kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+    def create_full_backup(self, now: Optional[datetime] = None) -> BackupManifest:
+        """Write every configured table and the manifest under backups/<timestamp>/."""
+        now = now or datetime.now(timezone.utc)
+        backup_id = now.strftime("%Y%m%dT%H%M%SZ")
+        prefix = f"{storage.PBACKUP}{backup_id}/"
+        manifest = BackupManifest(backupId=backup_id, createdAt=now.isoformat())
+
+        for env_var, table_name in configured_tables().items():
+            lines = [encode_item(item) for item in self.scan_table(table_name)]
+            body = "".join(line + "\n" for line in lines).encode("utf-8")
+            key = f"{prefix}{table_name}.jsonl"
+            storage.upload_sensitive(key, body, BACKUP_CONTENT_TYPE)
+            manifest.tables.append(BackupTableEntry(
+                envVar=env_var,
+                tableName=table_name,
+                key=key,
+                recordCount=len(lines),
+                sizeBytes=len(body),
+                sha256=hashlib.sha256(body).hexdigest(),
+            ))
+            print(f"[Backup] {table_name}: {len(lines)} item(s), {len(body)} bytes")
+
+        storage.upload_sensitive(
+            f"{prefix}{MANIFEST_FILENAME}",
+            json.dumps(manifest.to_dict(), indent=2).encode("utf-8"),
+            "application/json"
+        )
+        return manifest
+
+    def _write_items(self, table_name: str, items: List[Dict[str, Any]]) -> None:
+        for start in range(0, len(items), BATCH_WRITE_SIZE):
+            request = {table_name: [{"PutRequest": {"Item": item}} for item in items[start:start + BATCH_WRITE_SIZE]]}
+            for _ in range(MAX_UNPROCESSED_RETRIES + 1):
+                resp = with_retry(lambda: self._dynamodb().batch_write_item(RequestItems=request))
+                request = resp.get("UnprocessedItems") or {}
+                if not request:
+                    break
+            else:
+                raise BackupError(f"{table_name}: items left unprocessed after {MAX_UNPROCESSED_RETRIES} retries")
+
+    def restore_from_backup(self, manifest_key: str) -> RestoreReport:
+        """
+        Put every item of the backup back into the configured tables.
+
+        A table whose file fails its checksum (or record count) is skipped
+        and reported in errors; other tables are still restored. Items are
+        written with PutItem semantics, overwriting items with the same key.
+        """
+        manifest = BackupManifest.from_dict(json.loads(storage.download_sensitive(manifest_key)))
+        targets = configured_tables()
+        report = RestoreReport(manifestKey=manifest_key)
+
+        for entry in manifest.tables:
+            target = targets.get(entry.envVar)
+            if not target:
+                report.errors[entry.envVar] = "table not configured in this environment"
+                continue
+            try:
+                body = storage.download_sensitive(entry.key)
+                if hashlib.sha256(body).hexdigest() != entry.sha256:
+                    raise BackupError("checksum mismatch")
+                items = [decode_item(line) for line in body.decode("utf-8").splitlines() if line]
+                if len(items) != entry.recordCount:
+                    raise BackupError(f"expected {entry.recordCount} records, found {len(items)}")
+                self._write_items(target, items)
+                report.restored[target] = len(items)
+            except Exception as e:
+                print(f"[Backup] Restore of {entry.tableName} into {target} failed: {e}")
+                report.errors[entry.envVar] = str(e)
+        return report
+
+
+# Global backup service instance
+backup_service = BackupService()
+
+
+def run(event, context):
+    """Admin Lambda handler: create a backup (default) or restore one."""
+    event = event or {}
+    action = event.get("action", "backup")
+    if action == "backup":
+        manifest = backup_service.create_full_backup()
+        return {"action": "backup", "manifestKey": f"{storage.PBACKUP}{manifest.backupId}/{MANIFEST_FILENAME}",
+                "manifest": manifest.to_dict()}
+    if action == "restore":
+        manifest_key = event.get("manifestKey")
+        if not manifest_key:
+            raise ValueError("restore requires manifestKey")
+        return {"action": "restore", "report": backup_service.restore_from_backup(manifest_key).to_dict()}
+    raise ValueError(f"Unknown backup action: {action}")
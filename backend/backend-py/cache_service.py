@@ -0,0 +1,99 @@
+"""
+Cache Service for MeDUSA
+Read-aside caching of hot-path DynamoDB reads backed by ElastiCache Redis.
+
+Key Features:
+- JSON-serialized values with per-key TTL
+- Disabled (always cache miss) when REDIS_URL is not configured
+- Cache failures never fail the request; they degrade to a miss
+"""
+
+import os
+import json
+from decimal import Decimal
+from typing import Any, Optional
+
+REDIS_URL = os.environ.get("REDIS_URL") or None
+CACHE_DEFAULT_TTL_SECONDS = int(os.environ.get("CACHE_DEFAULT_TTL_SECONDS", "300"))
+
+
+def _json_default(value: Any):
+    """Serialize DynamoDB Decimals back to int/float"""
+    if isinstance(value, Decimal):
+        return int(value) if value == value.to_integral_value() else float(value)
+    raise TypeError(f"Object of type {type(value).__name__} is not JSON serializable")
+
+
+class CacheService:
+    """
+    Thin wrapper around a Redis client.
+    When no Redis URL is configured every read is a miss and writes are no-ops.
+    """
+
+    def __init__(self, redis_url: Optional[str] = None):
+        self.redis_url = redis_url
+        self.client = None
+
+        if redis_url:
+            try:
+                import redis
+                self.client = redis.Redis.from_url(
+                    redis_url,
+                    socket_timeout=1,
+                    socket_connect_timeout=1,
+                    decode_responses=True
+                )
+                print("[CacheService] Redis cache enabled")
+            except Exception as e:
+                print(f"[CacheService] WARNING: Redis unavailable, caching disabled: {e}")
+                self.client = None
+
+    @property
+    def enabled(self) -> bool:
+        return self.client is not None
+
+    def get(self, key: str) -> Optional[Any]:
+        """
+        Get a cached value.
+
+        Returns:
+            Deserialized value, or None on cache miss / cache disabled
+        """
+        if not self.client:
+            return None
+        try:
+            raw = self.client.get(key)
+            return json.loads(raw) if raw is not None else None
+        except Exception as e:
+            print(f"[CacheService] Error reading {key}: {e}")
+            return None
+
+    def set(self, key: str, value: Any, ttl_secs: int = CACHE_DEFAULT_TTL_SECONDS) -> None:
+        """Store a value under key with a TTL in seconds"""
+        if not self.client:
+            return
+        try:
+            self.client.set(key, json.dumps(value, default=_json_default), ex=ttl_secs)
+        except Exception as e:
+            print(f"[CacheService] Error writing {key}: {e}")
+
+    def invalidate(self, key: str) -> None:
+        """Remove a key from the cache"""
+        if not self.client:
+            return
+        try:
+            self.client.delete(key)
+        except Exception as e:
+            print(f"[CacheService] Error invalidating {key}: {e}")
+
+
+def user_key(user_id: str) -> str:
+    return f"user:{user_id}"
+
+
+def user_email_key(email: str) -> str:
+    return f"user_email:{email}"
+
+
+# Global cache service instance
+cache_service = CacheService(REDIS_URL)
@@ -0,0 +1,160 @@
+"""
+MeDUSA Device Calibration Service
+
+Records sensor calibrations and tracks when a device is next due.
+
+Key Features:
+- Calibration history per device
+- Reference vs. measured values produce a result (pass/fail, offset, correction factor)
+- Passed calibrations store a correction that ingestion applies to later readings
+- Overdue detection (devices past their due date produce low-quality readings)
+- DEVICE_CALIBRATED audit events including calibration parameters
+"""
+
+import os
+import math
+import uuid
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, List, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+
+CALIBRATION_INTERVAL_DAYS = int(os.environ.get("CALIBRATION_INTERVAL_DAYS", "180"))
+# Max relative error of a corrected value against its reference for a calibration to pass
+CALIBRATION_TOLERANCE = float(os.environ.get("CALIBRATION_TOLERANCE", "0.05"))
+
+
+class CalibrationError(ValueError):
+    """Raised when calibration values cannot produce a result."""
+
+
+def _parse_iso(value: str) -> datetime:
+    parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+
+
+def next_due_from(calibrated_at: datetime) -> datetime:
+    """Default due date for the next calibration."""
+    return calibrated_at + timedelta(days=CALIBRATION_INTERVAL_DAYS)
+
+
+def is_calibration_due(device: Dict[str, Any], now: Optional[datetime] = None) -> bool:
+    """
+    Check whether a device is overdue for calibration.
+
+    Devices without a recorded due date (registered before calibration
+    tracking) are not considered overdue.
+    """
+    due_at = device.get("calibrationDueAt")
+    if not due_at:
+        return False
+    try:
+        return _parse_iso(due_at) <= (now or datetime.now(timezone.utc))
+    except ValueError:
+        return False
+
+
+def compute_calibration_result(
+    reference_values: Dict[str, float],
+    measured_values: Dict[str, float],
+    tolerance: float = CALIBRATION_TOLERANCE
+) -> Dict[str, Any]:
+    """
+    Compare measured values with reference values.
+
+    The correction factor is the least-squares scale (through the origin)
+    mapping measured onto reference values; the calibration passes when every
+    corrected value is within tolerance of its reference.
+
+    Raises:
+        CalibrationError: if the values share no keys or are not usable
+    """
+    keys = sorted(set(reference_values) & set(measured_values))
+    if not keys:
+        raise CalibrationError("referenceValues and measuredValues must share at least one key")
+    pairs = [(float(reference_values[k]), float(measured_values[k])) for k in keys]
+    if not all(math.isfinite(r) and math.isfinite(m) for r, m in pairs):
+        raise CalibrationError("calibration values must be finite numbers")
+
+    measured_sq = sum(m * m for _, m in pairs)
+    if measured_sq == 0:
+        raise CalibrationError("measuredValues must not all be zero")
+    factor = sum(r * m for r, m in pairs) / measured_sq
+    offset = sum(r - m for r, m in pairs) / len(pairs)
+    passed = factor > 0 and all(
+        abs(r - m * factor) <= tolerance * (abs(r) if r else 1.0) for r, m in pairs
+    )
+    return {"passed": passed, "offset": round(offset, 6), "correctionFactor": round(factor, 6)}
+
+
+def record_calibration(
+    device_id: str,
+    performed_by: str,
+    performed_by_role: str,
+    reference_values: Dict[str, float],
+    measured_values: Dict[str, float],
+    calibrated_at: Optional[datetime] = None,
+    next_due: Optional[datetime] = None,
+    notes: Optional[str] = None,
+    certificate_url: Optional[str] = None
+) -> Dict[str, Any]:
+    """
+    Record a calibration for a device.
+
+    A passed calibration moves the device's due date forward and replaces
+    the correction applied to its readings; a failed one is only recorded.
+
+    Returns:
+        The stored calibration record
+
+    Raises:
+        CalibrationError: if the values cannot produce a result
+    """
+    result = compute_calibration_result(reference_values, measured_values)
+    calibrated_at = calibrated_at or datetime.now(timezone.utc)
+    next_due = next_due or next_due_from(calibrated_at)
+
+    calibration = {
+        "id": f"cal_{uuid.uuid4().hex[:12]}",
+        "deviceId": device_id,
+        "calibratedBy": performed_by,
+        "calibratedAt": calibrated_at.isoformat(),
+        "nextDue": next_due.isoformat(),
+        "referenceValues": reference_values,
+        "measuredValues": measured_values,
+        "calibrationResult": result,
+        "notes": notes,
+        "certificateUrl": certificate_url,
+    }
+    db.put_calibration(calibration)
+    if result["passed"]:
+        db.update_device(device_id, {
+            "lastCalibratedAt": calibration["calibratedAt"],
+            "calibrationDueAt": calibration["nextDue"],
+            "calibrationCorrection": {
+                "correctionFactor": result["correctionFactor"],
+                "keys": sorted(set(reference_values) & set(measured_values)),
+            },
+        })
+
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_CALIBRATED,
+        user_id=performed_by,
+        user_role=performed_by_role,
+        device_id=device_id,
+        action="calibrate",
+        details={"calibrationId": calibration["id"], "result": result, "nextDue": calibration["nextDue"]}
+    )
+    return calibration
+
+
+def get_calibration_history(device_id: str, limit: int = 50) -> List[Dict[str, Any]]:
+    """Calibration history for a device, newest first."""
+    return db.get_calibrations(device_id, limit)
+
+
+def get_latest_calibration(device_id: str) -> Optional[Dict[str, Any]]:
+    """Most recent calibration of a device, or None."""
+    history = db.get_calibrations(device_id, limit=1)
+    return history[0] if history else None
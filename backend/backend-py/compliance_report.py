@@ -0,0 +1,248 @@
+"""
+MeDUSA Compliance Report
+
+HIPAA access report for compliance officers, built from the audit log of a
+date range: who accessed which patient data, security incidents and failed
+sign-ins.
+
+Key Features:
+- Access events: PATIENT_DATA_ACCESS, DATA_EXPORT, REPORT_DOWNLOADED,
+  AUTHZ_ACCESS_DENIED and SECURITY_SUSPICIOUS_ACTIVITY
+- Data Access Summary: one row per user and resource with the access count
+  and the last access, most accessed first
+- Security Incidents: entries of WARNING severity or higher, newest first
+- Failed Authentication Attempts: login / MFA failures grouped by IP
+  address and user (user id, else the masked email of the attempt)
+- CSV (sections one after another) or PDF output; CSV cells that a
+  spreadsheet would run as a formula (e.g. a failed login's email
+  "=HYPERLINK(...)") are prefixed with '
+"""
+
+import csv
+import io
+from dataclasses import dataclass, field
+from datetime import datetime, timezone
+from typing import Any, Dict, Iterable, List, Optional, Tuple
+
+import db
+from audit_service import AuditEventType, AuditSeverity
+from report_pdf import (
+    ReportGenerator, ReportParameters, PDF_CONTENT_TYPE, MARGIN, LINE_HEIGHT, TITLE_SIZE, _PdfCanvas, _write_pdf
+)
+
+REPORT_TYPE_COMPLIANCE = "compliance"
+COMPLIANCE_REPORT_FORMATS = ("csv", "pdf")
+
+ACCESS_EVENT_TYPES = frozenset({
+    AuditEventType.PATIENT_DATA_ACCESS.value,
+    AuditEventType.DATA_EXPORT.value,
+    AuditEventType.REPORT_DOWNLOADED.value,
+    AuditEventType.AUTHZ_ACCESS_DENIED.value,
+    AuditEventType.SECURITY_SUSPICIOUS_ACTIVITY.value,
+})
+FAILED_AUTH_EVENT_TYPES = frozenset({
+    AuditEventType.AUTH_LOGIN_FAILURE.value,
+    AuditEventType.AUTH_MFA_FAILURE.value,
+})
+INCIDENT_SEVERITIES = frozenset({
+    AuditSeverity.WARNING.value,
+    AuditSeverity.ERROR.value,
+    AuditSeverity.CRITICAL.value,
+})
+
+AUDIT_PAGE_SIZE = 1000
+
+
+@dataclass
+class AccessSummaryRow:
+    user_id: str
+    user_role: str
+    resource_type: str
+    resource_id: str
+    access_count: int
+    last_accessed: str
+
+
+@dataclass
+class SecurityIncident:
+    timestamp: str
+    severity: str
+    event_type: str
+    user_id: str
+    ip_address: str
+    resource: str
+
+
+@dataclass
+class FailedAuthRow:
+    ip_address: str
+    user: str
+    attempts: int
+    last_attempt: str
+
+
+@dataclass
+class ComplianceReportData:
+    start: datetime
+    end: datetime
+    access_summary: List[AccessSummaryRow] = field(default_factory=list)
+    incidents: List[SecurityIncident] = field(default_factory=list)
+    failed_auth: List[FailedAuthRow] = field(default_factory=list)
+
+
+def _value(entry: Dict[str, Any], key: str) -> str:
+    return entry.get(key) or "-"
+
+
+def build_compliance_report(logs: Iterable[Dict[str, Any]], start: datetime, end: datetime) -> ComplianceReportData:
+    """Group the audit entries of [start, end] into the report's sections."""
+    access: Dict[Tuple[str, str, str], Dict[str, Any]] = {}
+    failed: Dict[Tuple[str, str], Dict[str, Any]] = {}
+    incidents: List[SecurityIncident] = []
+
+    for entry in logs:
+        timestamp = entry.get("timestamp") or entry.get("sk") or ""
+        event_type = entry.get("eventType")
+
+        if event_type in ACCESS_EVENT_TYPES:
+            key = (_value(entry, "userId"), _value(entry, "resourceType"), _value(entry, "resourceId"))
+            group = access.setdefault(key, {"count": 0, "last": "", "role": _value(entry, "userRole")})
+            group["count"] += 1
+            group["last"] = max(group["last"], timestamp)
+
+        if event_type in FAILED_AUTH_EVENT_TYPES:
+            user = entry.get("userId") or (entry.get("details") or {}).get("email") or "-"
+            group = failed.setdefault((_value(entry, "ipAddress"), user), {"count": 0, "last": ""})
+            group["count"] += 1
+            group["last"] = max(group["last"], timestamp)
+
+        if entry.get("severity") in INCIDENT_SEVERITIES:
+            resource = "/".join(v for v in (entry.get("resourceType"), entry.get("resourceId")) if v) or "-"
+            incidents.append(SecurityIncident(
+                timestamp=timestamp,
+                severity=entry["severity"],
+                event_type=event_type or "-",
+                user_id=_value(entry, "userId"),
+                ip_address=_value(entry, "ipAddress"),
+                resource=resource,
+            ))
+
+    return ComplianceReportData(
+        start=start,
+        end=end,
+        access_summary=sorted(
+            (AccessSummaryRow(user, g["role"], resource_type, resource_id, g["count"], g["last"])
+             for (user, resource_type, resource_id), g in access.items()),
+            key=lambda r: (-r.access_count, r.user_id, r.resource_type, r.resource_id)
+        ),
+        incidents=sorted(incidents, key=lambda i: i.timestamp, reverse=True),
+        failed_auth=sorted(
+            (FailedAuthRow(ip, user, g["count"], g["last"]) for (ip, user), g in failed.items()),
+            key=lambda r: (-r.attempts, r.ip_address, r.user)
+        ),
+    )
+
+
+def load_audit_logs(start: datetime, end: datetime) -> List[Dict[str, Any]]:
+    """Every audit entry written in [start, end]."""
+    items, token = [], None
+    while True:
+        page = db.get_audit_logs(
+            start_time=start.isoformat(), end_time=end.isoformat(), limit=AUDIT_PAGE_SIZE, next_token=token
+        )
+        items.extend(page.items)
+        token = page.next_cursor
+        if not token:
+            return items
+
+
+def load_compliance_report(start: datetime, end: datetime) -> ComplianceReportData:
+    return build_compliance_report(load_audit_logs(start, end), start, end)
+
+
+# -------- Output
+
+ACCESS_SUMMARY_COLUMNS = ("User", "Role", "Resource type", "Resource", "Access count", "Last accessed")
+INCIDENT_COLUMNS = ("Time", "Severity", "Event", "User", "IP address", "Resource")
+FAILED_AUTH_COLUMNS = ("IP address", "User", "Attempts", "Last attempt")
+
+
+def _sections(data: ComplianceReportData) -> List[Tuple[str, Tuple[str, ...], List[List[Any]]]]:
+    return [
+        ("Data Access Summary", ACCESS_SUMMARY_COLUMNS, [
+            [r.user_id, r.user_role, r.resource_type, r.resource_id, r.access_count, r.last_accessed]
+            for r in data.access_summary
+        ]),
+        ("Security Incidents", INCIDENT_COLUMNS, [
+            [i.timestamp, i.severity, i.event_type, i.user_id, i.ip_address, i.resource] for i in data.incidents
+        ]),
+        ("Failed Authentication Attempts", FAILED_AUTH_COLUMNS, [
+            [r.ip_address, r.user, r.attempts, r.last_attempt] for r in data.failed_auth
+        ]),
+    ]
+
+
+_FORMULA_PREFIXES = ("=", "+", "-", "@", "\t", "\r")
+
+
+def _csv_cell(value: Any) -> Any:
+    """Neutralize text a spreadsheet would evaluate; the "-" placeholder stays as is."""
+    if isinstance(value, str) and len(value) > 1 and value.startswith(_FORMULA_PREFIXES):
+        return "'" + value
+    return value
+
+
+class CsvComplianceReportGenerator(ReportGenerator):
+    """Each section as a title row, a header row and its rows, separated by a blank line."""
+
+    content_type = "text/csv"
+    extension = "csv"
+
+    def generate(self, data: ComplianceReportData, parameters: ReportParameters) -> bytes:
+        out = io.StringIO()
+        writer = csv.writer(out)
+        writer.writerow(["Compliance Report", data.start.isoformat(), data.end.isoformat()])
+        writer.writerow(["Generated by", parameters.generated_by, parameters.generated_at.isoformat()])
+        for title, columns, rows in _sections(data):
+            writer.writerow([])
+            writer.writerow([title])
+            writer.writerow(columns)
+            writer.writerows([_csv_cell(cell) for cell in row] for row in rows)
+        return out.getvalue().encode("utf-8")
+
+
+class PdfComplianceReportGenerator(ReportGenerator):
+    """Compliance report as a multi-page PDF."""
+
+    content_type = PDF_CONTENT_TYPE
+    extension = "pdf"
+
+    # Column widths per section, filling the A4 text width
+    WIDTHS = {
+        "Data Access Summary": (95, 55, 80, 110, 70, 85),
+        "Security Incidents": (95, 60, 120, 80, 70, 70),
+        "Failed Authentication Attempts": (120, 175, 60, 140),
+    }
+
+    def generate(self, data: ComplianceReportData, parameters: ReportParameters) -> bytes:
+        canvas = _PdfCanvas()
+        canvas.new_page()
+        canvas.y -= TITLE_SIZE
+        canvas.text(MARGIN, canvas.y, "Compliance Report", TITLE_SIZE, bold=True)
+        canvas.y -= LINE_HEIGHT
+        canvas.line(f"Period: {_format_day(data.start)} - {_format_day(data.end)}")
+        canvas.line(f"Generated by {parameters.generated_by} on {parameters.generated_at.strftime('%Y-%m-%d %H:%M UTC')}")
+        for title, columns, rows in _sections(data):
+            canvas.heading(title)
+            canvas.table(list(zip(columns, self.WIDTHS[title])), rows, empty=f"No {title.lower()} in this period")
+        return _write_pdf(canvas.pages)
+
+
+def _format_day(ts: datetime) -> str:
+    return ts.astimezone(timezone.utc).strftime("%Y-%m-%d %H:%M")
+
+
+def generator_for(format: str) -> Optional[ReportGenerator]:
+    """Generator for a COMPLIANCE_REPORT_FORMATS format, or None if unsupported."""
+    generator = {"csv": CsvComplianceReportGenerator, "pdf": PdfComplianceReportGenerator}.get(format)
+    return generator() if generator else None
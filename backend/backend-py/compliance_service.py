@@ -0,0 +1,100 @@
+"""
+MeDUSA Compliance Service
+
+HIPAA safeguards applied on top of RBAC when patient data is read.
+
+Key Features:
+- Minimum Necessary Rule: each role sees only the fields it needs
+  - patient:    their own demographics, diagnosis and readings, not clinician notes
+  - technician: device and signal fields, never diagnosis or medication fields
+  - doctor / admin: the full medical record
+- Access frequency check: more than MAX_RESOURCE_ACCESSES_PER_HOUR reads of
+  one patient's data by one user (counted from PATIENT_DATA_ACCESS audit
+  records) is refused as an excessive access pattern
+"""
+
+import os
+from datetime import datetime, timedelta, timezone
+from typing import Any, Dict, Iterable, List, Optional
+
+import db
+
+MAX_RESOURCE_ACCESSES_PER_HOUR = int(os.environ.get("MAX_RESOURCE_ACCESSES_PER_HOUR", "100"))
+
+# Fields revealing a diagnosis or treatment
+DIAGNOSIS_FIELDS = frozenset({"diagnosis", "severity", "notes", "is_parkinsonian", "isParkinsonian"})
+MEDICATION_FIELDS = frozenset({
+    "medications", "medicationId", "dosage", "frequency", "prescribedBy", "medication_id", "prescribed_by",
+})
+# Clinician-only fields hidden from the patient themselves
+CLINICIAN_ONLY_FIELDS = frozenset({"notes", "doctorId", "prescribedBy", "prescribed_by"})
+
+FULL_ACCESS_ROLES = frozenset({"doctor", "admin"})
+
+# Fields each restricted role may not see; full-access roles see everything
+_RESTRICTED_FIELDS = {
+    "patient": CLINICIAN_ONLY_FIELDS,
+    "technician": DIAGNOSIS_FIELDS | MEDICATION_FIELDS,
+}
+
+PATIENT_DATA_ACCESS_EVENT = "PATIENT_DATA_ACCESS"
+
+
+class ComplianceError(PermissionError):
+    """Raised when a data operation would violate a HIPAA safeguard."""
+
+
+class ComplianceService:
+    """Minimum necessary and access frequency checks."""
+
+    def check_minimum_necessary(self, requester_role: Optional[str], fields_requested: Iterable[str]) -> List[str]:
+        """
+        Reduce the requested fields to those the role may see.
+
+        Returns:
+            Permitted fields, in request order
+
+        Raises:
+            ComplianceError: if the role has no patient data access at all
+        """
+        fields = list(fields_requested)
+        if requester_role in FULL_ACCESS_ROLES:
+            return fields
+        if requester_role not in _RESTRICTED_FIELDS:
+            raise ComplianceError(f"Role {requester_role!r} may not access patient data")
+        restricted = _RESTRICTED_FIELDS[requester_role]
+        return [f for f in fields if f not in restricted]
+
+    def filter_record(self, requester_role: Optional[str], record: Dict[str, Any]) -> Dict[str, Any]:
+        """The record without the fields the role may not see."""
+        permitted = self.check_minimum_necessary(requester_role, record.keys())
+        return {k: record[k] for k in permitted}
+
+    def count_recent_accesses(self, user_id: str, resource_id: str, window_hours: int, stop_after: int) -> int:
+        """PATIENT_DATA_ACCESS audit records of the user for the resource in the window (counting stops at stop_after)."""
+        start_time = (datetime.now(timezone.utc) - timedelta(hours=window_hours)).isoformat()
+        count, next_token = 0, None
+        while True:
+            page = db.get_audit_logs(user_id=user_id, start_time=start_time, limit=200, next_token=next_token)
+            count += sum(
+                1 for log in page.items
+                if log.get("eventType") == PATIENT_DATA_ACCESS_EVENT and log.get("resourceId") == resource_id
+            )
+            next_token = page.next_cursor
+            if count >= stop_after or not page.has_more or not next_token:
+                return count
+
+    def check_access_frequency(self, user_id: str, resource_id: str, window_hours: int = 1) -> None:
+        """
+        Refuse access when the user has read the resource too often recently.
+
+        Raises:
+            ComplianceError: "Excessive access pattern detected"
+        """
+        limit = MAX_RESOURCE_ACCESSES_PER_HOUR * window_hours
+        if self.count_recent_accesses(user_id, resource_id, window_hours, stop_after=limit + 1) > limit:
+            raise ComplianceError("Excessive access pattern detected")
+
+
+# Global compliance service instance
+compliance_service = ComplianceService()
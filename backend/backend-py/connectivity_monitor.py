@@ -0,0 +1,104 @@
+"""
+MeDUSA Connectivity Monitor
+
+EventBridge Scheduler handler (every 15 minutes). Finds devices whose
+last battery report, within LOW_BATTERY_WINDOW_MINUTES, is below
+LOW_BATTERY_THRESHOLD_PERCENT, pushes a notification to the assigned
+patient and sends a critical alert to the assigned doctor.
+
+Key Features:
+- One alert per low-battery episode (lowBatteryAlertedAt is recorded after
+  alerting and cleared when the device reports a level above the threshold)
+- Every alert is audited as DEVICE_LOW_BATTERY / low_battery_alert
+- Notification failures are logged; the device is alerted again next run
+"""
+
+import os
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+from device_connection_service import LOW_BATTERY_THRESHOLD_PERCENT
+from notification_service import notification_service
+
+LOW_BATTERY_WINDOW_MINUTES = int(os.environ.get("LOW_BATTERY_WINDOW_MINUTES", "60"))
+
+
+def alert_low_battery(device: Dict[str, Any], threshold_percent: float = LOW_BATTERY_THRESHOLD_PERCENT) -> bool:
+    """
+    Notify the patient and doctor of one low-battery device.
+
+    Returns:
+        True if at least one notification was accepted
+    """
+    level = device["batteryLevel"]
+    patient_id = device.get("patientId")
+    profile = (db.get_patient_profile(patient_id) if patient_id else None) or {}
+    name = device.get("name") or device["id"]
+    data = {"type": "LOW_BATTERY", "deviceId": device["id"], "batteryLevel": level}
+
+    pushed = notification_service.push_to_user(
+        patient_id,
+        "Device battery low",
+        f"{name} is at {level}% battery. Please charge it to keep monitoring active.",
+        data,
+    )
+    reason = f"battery {level}% < {threshold_percent:g}%"
+    published = notification_service.send_critical_alert(
+        patient_id, device["id"], "battery", {"batteryLevel": level}, reason
+    )
+    doctor_pushed = notification_service.push_to_user(
+        profile.get("doctorId"), "Patient device battery low", f"{name}: {reason}", {**data, "patientId": patient_id}
+    )
+
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_LOW_BATTERY,
+        user_id="system",
+        user_role="system",
+        device_id=device["id"],
+        patient_id=patient_id,
+        action="low_battery_alert",
+        details={"batteryLevel": level, "thresholdPercent": threshold_percent,
+                 "batteryUpdatedAt": device.get("batteryUpdatedAt"), "doctorId": profile.get("doctorId"),
+                 "patientPushes": pushed, "doctorPushes": doctor_pushed, "alertPublished": published}
+    )
+    return bool(pushed or published or doctor_pushed)
+
+
+def check_low_battery(
+    now: Optional[datetime] = None,
+    threshold_percent: float = LOW_BATTERY_THRESHOLD_PERCENT
+) -> Dict[str, int]:
+    """
+    Alert on devices that reported a low battery within the last LOW_BATTERY_WINDOW_MINUTES.
+
+    Returns:
+        {"alerted": n, "skipped": n, "failed": n}; skipped devices were already alerted
+    """
+    now = now or datetime.now(timezone.utc)
+    since = (now - timedelta(minutes=LOW_BATTERY_WINDOW_MINUTES)).isoformat()
+    counts = {"alerted": 0, "skipped": 0, "failed": 0}
+
+    for device in db.get_low_battery_devices(threshold_percent, since):
+        if device.get("lowBatteryAlertedAt"):
+            counts["skipped"] += 1
+            continue
+        try:
+            sent = alert_low_battery(device, threshold_percent)
+        except Exception as e:
+            print(f"[ConnectivityMonitor] Failed to alert low battery of {device['id']}: {e}")
+            sent = False
+        if not sent:
+            counts["failed"] += 1
+            continue
+        db.update_device(device["id"], {"lowBatteryAlertedAt": now.isoformat()})
+        counts["alerted"] += 1
+    return counts
+
+
+def run(event, context):
+    """EventBridge Scheduler entry point."""
+    result = check_low_battery()
+    print(f"[ConnectivityMonitor] {result}")
+    return result
@@ -0,0 +1,143 @@
+"""
+MeDUSA Consent Service
+
+Tracks patient consent for data use (HIPAA) as an append-only history.
+
+Key Features:
+- Standard consent types plus custom:<name> types
+- Each grant or revocation is a new record; the newest record of a type decides
+- Consents may expire (expiresAt)
+- CONSENT_GRANTED / CONSENT_REVOKED audit events at WARNING severity
+- require_consent() gates data-sharing operations
+"""
+
+import re
+import uuid
+from datetime import datetime, timezone
+from enum import Enum
+from typing import Any, Dict, List, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+
+CUSTOM_CONSENT_PREFIX = "custom:"
+_CUSTOM_NAME = re.compile(r"^[a-z0-9_\-]{1,64}$")
+
+
+class ConsentType(str, Enum):
+    DATA_SHARING = "data_sharing"
+    RESEARCH_PARTICIPATION = "research_participation"
+    TELEHEALTH_SERVICES = "telehealth_services"
+    THIRD_PARTY_ACCESS = "third_party_access"
+
+
+class ConsentRequiredError(Exception):
+    """Raised when an operation needs a consent the patient has not (or no longer) given."""
+
+    def __init__(self, patient_id: str, consent_type: str):
+        super().__init__(f"Patient {patient_id} has no active {consent_type} consent")
+        self.patient_id = patient_id
+        self.consent_type = consent_type
+
+
+def normalize_consent_type(value: str) -> str:
+    """
+    Validate a consent type: one of ConsentType or custom:<name>
+    (name of lowercase letters, digits, _ and -).
+
+    Raises:
+        ValueError: for unknown types
+    """
+    value = value.strip().lower()
+    if value in {t.value for t in ConsentType}:
+        return value
+    if value.startswith(CUSTOM_CONSENT_PREFIX) and _CUSTOM_NAME.match(value[len(CUSTOM_CONSENT_PREFIX):]):
+        return value
+    allowed = ", ".join(t.value for t in ConsentType)
+    raise ValueError(f"consentType must be one of {allowed} or {CUSTOM_CONSENT_PREFIX}<name>")
+
+
+def _parse_iso(value: str) -> datetime:
+    parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+
+
+def is_active(record: Dict[str, Any], now: Optional[datetime] = None) -> bool:
+    """A granted, unexpired consent record."""
+    if not record.get("granted"):
+        return False
+    expires_at = record.get("expiresAt")
+    return not expires_at or _parse_iso(expires_at) > (now or datetime.now(timezone.utc))
+
+
+def record_consent(
+    patient_id: str,
+    consent_type: str,
+    granted: bool,
+    granted_by: str,
+    granted_by_role: str,
+    expires_at: Optional[datetime] = None,
+    witness: Optional[str] = None,
+    document_url: Optional[str] = None,
+    ip_address: Optional[str] = None
+) -> Dict[str, Any]:
+    """
+    Record that a patient granted (or revoked) a consent.
+
+    Raises:
+        ValueError: for an unknown consent type
+    """
+    consent_type = normalize_consent_type(consent_type)
+    record = {
+        "id": str(uuid.uuid4()),
+        "patientId": patient_id,
+        "consentType": consent_type,
+        "granted": granted,
+        "grantedAt": datetime.now(timezone.utc).isoformat(),
+        "expiresAt": expires_at.isoformat() if expires_at else None,
+        "grantedBy": granted_by,
+        "witness": witness,
+        "documentUrl": document_url,
+    }
+    stored = db.put_consent(record)
+
+    audit_service.log_event(
+        event_type=AuditEventType.CONSENT_GRANTED if granted else AuditEventType.CONSENT_REVOKED,
+        user_id=granted_by,
+        user_role=granted_by_role,
+        resource_type="consent",
+        resource_id=record["id"],
+        action="grant_consent" if granted else "revoke_consent",
+        ip_address=ip_address,
+        details={"patientId": patient_id, "consentType": consent_type, "expiresAt": record["expiresAt"]}
+    )
+    return stored
+
+
+def get_active_consent(
+    patient_id: str,
+    consent_type: str,
+    now: Optional[datetime] = None
+) -> Optional[Dict[str, Any]]:
+    """The patient's current consent of a type, or None if never given, revoked or expired."""
+    history = db.get_consents(patient_id, normalize_consent_type(consent_type))
+    if not history:
+        return None
+    latest = history[0]
+    return latest if is_active(latest, now) else None
+
+
+def list_consents(patient_id: str) -> List[Dict[str, Any]]:
+    """Full consent history of a patient, newest first."""
+    return db.get_consents(patient_id)
+
+
+def require_consent(patient_id: str, consent_type: str) -> Dict[str, Any]:
+    """
+    Raises:
+        ConsentRequiredError: if the patient has no active consent of the type
+    """
+    consent = get_active_consent(patient_id, consent_type)
+    if consent is None:
+        raise ConsentRequiredError(patient_id, normalize_consent_type(consent_type))
+    return consent
@@ -0,0 +1,88 @@
+"""
+MeDUSA CORS configuration
+
+Shared by the FastAPI app (CORSMiddleware) and the Lambda handlers that
+build API Gateway proxy responses themselves (device_data_ingest).
+
+Key Features:
+- ALLOWED_ORIGINS env var, comma-separated; empty or "*" allows any origin
+- add_cors_headers for raw Lambda proxy responses (success and error paths)
+- handle_options answers OPTIONS preflight with 200 and the CORS headers
+- Disallowed origins get no CORS headers, so browsers block the response
+"""
+
+import os
+from typing import Any, Dict, List, Optional
+
+CORS_ALLOW_METHODS = ["GET", "POST", "PUT", "DELETE", "OPTIONS", "PATCH"]
+CORS_ALLOW_HEADERS = [
+    "Content-Type",
+    "Authorization",
+    "Accept",
+    "Origin",
+    "X-Requested-With",
+    "Access-Control-Request-Method",
+    "Access-Control-Request-Headers",
+    "X-Request-Nonce",
+    "X-Request-Timestamp",
+    "X-Request-Signature",
+    "X-Confirmation-Token",
+    "Idempotency-Key",
+    "X-Request-Id",
+    "X-Device-Id",
+    "X-Api-Key",
+    "X-Device-Hardware-Id",
+    "X-Device-Timestamp",
+    "X-Device-Signature",
+]
+CORS_MAX_AGE_SECONDS = 600  # Cache preflight for 10 minutes
+
+
+def allowed_origins_from_env(value: Optional[str] = None) -> List[str]:
+    """Parse ALLOWED_ORIGINS; unset or empty means any origin (["*"])."""
+    raw = os.environ.get("ALLOWED_ORIGINS", "") if value is None else value
+    origins = [origin.strip() for origin in raw.split(",") if origin.strip()]
+    return origins or ["*"]
+
+
+ALLOWED_ORIGINS = allowed_origins_from_env()
+
+
+def is_origin_allowed(origin: Optional[str], allowed_origins: List[str]) -> bool:
+    return "*" in allowed_origins or (origin is not None and origin in allowed_origins)
+
+
+def cors_headers(origin: Optional[str], allowed_origins: List[str]) -> Dict[str, str]:
+    """CORS response headers for a request from origin, or {} if it is not allowed."""
+    if not is_origin_allowed(origin, allowed_origins):
+        return {}
+    headers = {
+        "Access-Control-Allow-Methods": ",".join(CORS_ALLOW_METHODS),
+        "Access-Control-Allow-Headers": ",".join(CORS_ALLOW_HEADERS),
+        "Access-Control-Max-Age": str(CORS_MAX_AGE_SECONDS),
+    }
+    if "*" in allowed_origins:
+        headers["Access-Control-Allow-Origin"] = "*"
+    else:
+        headers["Access-Control-Allow-Origin"] = origin
+        headers["Vary"] = "Origin"
+    return headers
+
+
+def request_origin(event: Dict[str, Any]) -> Optional[str]:
+    """Origin header of an API Gateway proxy event."""
+    for key, value in (event.get("headers") or {}).items():
+        if key.lower() == "origin":
+            return value
+    return None
+
+
+def add_cors_headers(response: Dict[str, Any], origin: Optional[str], allowed_origins: List[str] = None) -> Dict[str, Any]:
+    """Return an API Gateway proxy response with the CORS headers for origin added."""
+    headers = cors_headers(origin, ALLOWED_ORIGINS if allowed_origins is None else allowed_origins)
+    return {**response, "headers": {**(response.get("headers") or {}), **headers}}
+
+
+def handle_options(origin: Optional[str], allowed_origins: List[str] = None) -> Dict[str, Any]:
+    """Response to an OPTIONS preflight request."""
+    return add_cors_headers({"statusCode": 200, "headers": {}, "body": ""}, origin, allowed_origins)
@@ -0,0 +1,85 @@
+"""
+MeDUSA Crypto Service
+
+Integrity signatures for generated artifacts such as report files, and
+numeric one-time codes (SMS password reset).
+
+Key Features:
+- HMAC-SHA-256 signatures, hex encoded
+- Dedicated report signing key (Secrets Manager medusa/report-signing,
+  key report_signing_key), never the JWT secret
+- Constant-time verification
+- OTPs drawn from the OS CSPRNG with rejection sampling (no modulo bias)
+- OTPs stored only as Argon2id hashes, with cheaper parameters than passwords
+  (they are short-lived and attempt-limited)
+"""
+
+import os
+import hmac
+import hashlib
+from typing import Optional
+
+from argon2 import PasswordHasher
+from argon2.exceptions import VerificationError, InvalidHashError
+
+REPORT_SIGNING_KEY = os.environ.get("REPORT_SIGNING_KEY")
+
+OTP_DEFAULT_LENGTH = 6
+# Largest multiple of 10 that fits in a byte; bytes at or above it are redrawn
+_OTP_BYTE_LIMIT = 250
+
+_otp_hasher = PasswordHasher(time_cost=1, memory_cost=4096, parallelism=1)
+
+
+class SigningKeyMissingError(RuntimeError):
+    """Raised when signing is attempted without a configured key."""
+
+
+class CryptoService:
+    """Signs and verifies data with the report signing key."""
+
+    def __init__(self, signing_key: Optional[str] = REPORT_SIGNING_KEY):
+        self._key = signing_key.encode() if signing_key else None
+
+    def _require_key(self) -> bytes:
+        if not self._key:
+            raise SigningKeyMissingError("REPORT_SIGNING_KEY environment variable must be set")
+        return self._key
+
+    def sign_data(self, data: bytes) -> str:
+        """Return the hex HMAC-SHA-256 of data."""
+        return hmac.new(self._require_key(), data, hashlib.sha256).hexdigest()
+
+    def verify_signature(self, data: bytes, signature: Optional[str]) -> bool:
+        """Check a hex signature produced by sign_data."""
+        if not signature:
+            return False
+        return hmac.compare_digest(self.sign_data(data), signature.strip().lower())
+
+    def generate_otp(self, length: int = OTP_DEFAULT_LENGTH) -> str:
+        """Return a numeric one-time code of length digits, each uniformly distributed."""
+        if length < 1:
+            raise ValueError("OTP length must be positive")
+        digits = []
+        while len(digits) < length:
+            for byte in os.urandom(length - len(digits)):
+                if byte < _OTP_BYTE_LIMIT:
+                    digits.append(str(byte % 10))
+        return "".join(digits[:length])
+
+    def hash_otp(self, otp: str) -> str:
+        """Argon2id hash of an OTP for storage."""
+        return _otp_hasher.hash(otp)
+
+    def verify_otp(self, otp: str, otp_hash: Optional[str]) -> bool:
+        """Check an OTP against a hash from hash_otp."""
+        if not otp or not otp_hash:
+            return False
+        try:
+            return _otp_hasher.verify(otp_hash, otp)
+        except (VerificationError, InvalidHashError):
+            return False
+
+
+# Global crypto service instance
+crypto_service = CryptoService()
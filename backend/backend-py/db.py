@@ -1,10 +1,17 @@
 import os
 import time
 import secrets
-from typing import Optional, Dict, Any, List, Tuple
+from typing import Optional, Dict, Any, List, Set, Tuple
 from decimal import Decimal
-import boto3
 from boto3.dynamodb.conditions import Key, Attr
+from botocore.exceptions import ClientError
+from cache_service import cache_service, user_key, user_email_key
+from dynamo_update import DynamoUpdateBuilder, VersionConflictError, ConditionFailedError
+from pagination import PaginatedResult, decode_cursor, encode_cursor
+from sanitize import sanitize_table_name
+from retry import with_retry, backoff_delay_ms
+from request_context import DEFAULT_ORGANIZATION_ID, current_organization_id
+import aws_clients
 
 def _pose_pk(patient_id: str) -> str:
     return f"POSE#{patient_id}"
@@ -12,17 +19,39 @@ def _pose_pk(patient_id: str) -> str:
 def _pose_sk(pose_id: str) -> str:
     return f"POSE#{pose_id}"
 
+def _to_decimal(value: Any) -> Any:
+    """Recursively convert floats to Decimal for DynamoDB writes"""
+    if isinstance(value, float):
+        return Decimal(str(value))
+    if isinstance(value, dict):
+        return {k: _to_decimal(v) for k, v in value.items()}
+    if isinstance(value, list):
+        return [_to_decimal(v) for v in value]
+    return value
+
+def _from_decimal(value: Any) -> Any:
+    """Recursively convert DynamoDB Decimals back to int/float"""
+    if isinstance(value, Decimal):
+        return int(value) if value % 1 == 0 else float(value)
+    if isinstance(value, dict):
+        return {k: _from_decimal(v) for k, v in value.items()}
+    if isinstance(value, list):
+        return [_from_decimal(v) for v in value]
+    return value
+
 USE_MEMORY = os.environ.get("USE_MEMORY", "false").lower() == "true"
+# DynamoDB Local / LocalStack endpoint for offline integration tests; unset in AWS
+DYNAMODB_ENDPOINT_URL = os.environ.get("DYNAMODB_ENDPOINT_URL") or None
 VERIFICATION_CODE_TTL = 600  # 10 minutes
 
 # In-memory store for verification codes (development)
 _verification_codes: Dict[str, Dict[str, Any]] = {}
 
 if not USE_MEMORY:
-    ddb = boto3.resource("dynamodb")
+    ddb = aws_clients.get_resource("dynamodb", endpoint_url=DYNAMODB_ENDPOINT_URL)
 
     def _table_with_schema(env_var: str):
-        table = ddb.Table(os.environ[env_var])
+        table = ddb.Table(sanitize_table_name(os.environ[env_var]))
         pk_attr = "id"
         sk_attr = None
         try:
@@ -52,6 +81,17 @@ if not USE_MEMORY:
     T_MESSAGES, MESSAGES_PK_ATTR, MESSAGES_SK_ATTR = _table_with_schema("DDB_TABLE_MESSAGES")
     T_SYMPTOMS, SYMPTOMS_PK_ATTR, SYMPTOMS_SK_ATTR = _table_with_schema("DDB_TABLE_SYMPTOMS")
     T_REPORTS, REPORTS_PK_ATTR, REPORTS_SK_ATTR = _table_with_schema("DDB_TABLE_REPORTS")
+    T_CALIBRATIONS, CALIBRATIONS_PK_ATTR, CALIBRATIONS_SK_ATTR = _table_with_schema("DDB_TABLE_CALIBRATIONS")
+    T_MEDICATIONS, MEDICATIONS_PK_ATTR, MEDICATIONS_SK_ATTR = _table_with_schema("DDB_TABLE_MEDICATIONS")
+    T_SENSOR_DATA, SENSOR_PK_ATTR, SENSOR_SK_ATTR = _table_with_schema("DDB_TABLE_SENSOR_DATA")
+    T_CONSENTS, CONSENTS_PK_ATTR, CONSENTS_SK_ATTR = _table_with_schema("DDB_TABLE_CONSENTS")
+    T_REPORT_SCHEDULES, REPORT_SCHEDULES_PK_ATTR, REPORT_SCHEDULES_SK_ATTR = _table_with_schema("DDB_TABLE_REPORT_SCHEDULES")
+    T_MAINTENANCE, MAINTENANCE_PK_ATTR, MAINTENANCE_SK_ATTR = _table_with_schema("DDB_TABLE_MAINTENANCE")
+    T_IDEMPOTENCY = ddb.Table(sanitize_table_name(os.environ["DDB_TABLE_IDEMPOTENCY"]))
+    T_ORGANIZATIONS = ddb.Table(sanitize_table_name(os.environ["DDB_TABLE_ORGANIZATIONS"]))
+    T_DEVICE_API_KEYS = ddb.Table(sanitize_table_name(os.environ["DDB_TABLE_DEVICE_API_KEYS"]))
+    T_PUSH_TOKENS = ddb.Table(sanitize_table_name(os.environ["DDB_TABLE_PUSH_TOKENS"]))
+    T_ROLES = ddb.Table(sanitize_table_name(os.environ["DDB_TABLE_ROLES"]))
 
     USERS_SINGLE_TABLE = _is_pk_sk(USERS_PK_ATTR, USERS_SK_ATTR)
     REFRESH_SINGLE_TABLE = _is_pk_sk(REFRESH_PK_ATTR, REFRESH_SK_ATTR)
@@ -61,6 +101,16 @@ if not USE_MEMORY:
     SESSIONS_SINGLE_TABLE = _is_pk_sk(SESSIONS_PK_ATTR, SESSIONS_SK_ATTR)
     TREMOR_SINGLE_TABLE = _is_pk_sk(TREMOR_PK_ATTR, TREMOR_SK_ATTR)
 
+    def _scan_all(table, **scan_kwargs) -> List[Dict[str, Any]]:
+        """Scan a table following LastEvaluatedKey until exhausted"""
+        items = []
+        while True:
+            resp = table.scan(**scan_kwargs)
+            items.extend(resp.get("Items", []))
+            if "LastEvaluatedKey" not in resp:
+                return items
+            scan_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
     def _user_key(user_id: str) -> Dict[str, str]:
         if USERS_SINGLE_TABLE:
             return {
@@ -86,10 +136,22 @@ else:
     _sessions: Dict[str, Dict[str,Any]] = {}
     _tremor_analysis: List[Dict[str,Any]] = []
     _audit_logs: List[Dict[str,Any]] = []
+    _audit_chain_head: Dict[str, Dict[str,Any]] = {}
     _system_settings: Dict[str, Dict[str,Any]] = {}
     _messages: List[Dict[str,Any]] = []
     _symptoms: List[Dict[str,Any]] = []
     _reports: List[Dict[str,Any]] = []
+    _calibrations: List[Dict[str,Any]] = []
+    _medications: List[Dict[str,Any]] = []
+    _sensor_data: List[Dict[str,Any]] = []
+    _idempotency: Dict[str, Dict[str,Any]] = {}
+    _consents: List[Dict[str,Any]] = []
+    _report_schedules: Dict[str, Dict[str,Any]] = {}
+    _maintenance_events: List[Dict[str,Any]] = []
+    _organizations: Dict[str, Dict[str,Any]] = {}
+    _device_api_keys: Dict[str, Dict[str,Any]] = {}
+    _push_tokens: Dict[str, Dict[str,Any]] = {}
+    _roles: Dict[Tuple[str, str], Dict[str,Any]] = {}
     USERS_SINGLE_TABLE = False
     REFRESH_SINGLE_TABLE = False
     POSES_SINGLE_TABLE = False
@@ -106,6 +168,12 @@ else:
     MESSAGES_PK_ATTR, MESSAGES_SK_ATTR = "conversationId", "messageId"
     SYMPTOMS_PK_ATTR, SYMPTOMS_SK_ATTR = "patientId", "recordId"
     REPORTS_PK_ATTR, REPORTS_SK_ATTR = "reportId", None
+    CALIBRATIONS_PK_ATTR, CALIBRATIONS_SK_ATTR = "deviceId", "calibratedAt"
+    MEDICATIONS_PK_ATTR, MEDICATIONS_SK_ATTR = "patientId", "medicationId"
+    SENSOR_PK_ATTR, SENSOR_SK_ATTR = "device_id", "timestamp"
+    CONSENTS_PK_ATTR, CONSENTS_SK_ATTR = "patientId", "consentKey"
+    REPORT_SCHEDULES_PK_ATTR, REPORT_SCHEDULES_SK_ATTR = "id", None
+    MAINTENANCE_PK_ATTR, MAINTENANCE_SK_ATTR = "deviceId", "scheduledAt"
 
     def _user_key(user_id: str) -> Dict[str,str]:
         return {"id": user_id}
@@ -113,6 +181,112 @@ else:
     def _refresh_key(token: str) -> Dict[str,str]:
         return {"token": token}
 
+def _query_all(table, **query_kwargs) -> List[Dict[str, Any]]:
+    """Query a table or index following LastEvaluatedKey until exhausted"""
+    items = []
+    while True:
+        resp = table.query(**query_kwargs)
+        items.extend(resp.get("Items", []))
+        if "LastEvaluatedKey" not in resp:
+            return items
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+def _filtered_page(op, limit: int, start_key: Optional[Dict[str, Any]] = None, **kwargs) -> Tuple[List[Dict[str, Any]], Optional[Dict[str, Any]]]:
+    """
+    One page of up to limit items from a query/scan (op) with a FilterExpression.
+    Limit counts evaluated items, so keep reading until the page is full;
+    never asking for more than the remaining slots keeps LastEvaluatedKey exact.
+
+    Returns:
+        (items, LastEvaluatedKey or None)
+    """
+    items: List[Dict[str, Any]] = []
+    while True:
+        if start_key:
+            kwargs["ExclusiveStartKey"] = start_key
+        resp = with_retry(lambda: op(Limit=limit - len(items), **kwargs))
+        items.extend(resp.get("Items", []))
+        start_key = resp.get("LastEvaluatedKey")
+        if not start_key or len(items) >= limit:
+            return items, start_key
+
+# -------- Organization (tenant) scoping
+# Tenant-owned rows (users, patient profiles, devices, reports, symptoms,
+# medications, consents, sessions, poses, calibrations, maintenance events,
+# report schedules and audit logs) carry organizationId. Inside an
+# authenticated request, reads only return rows of the caller's organization
+# (in DynamoDB as a FilterExpression, so other tenants' rows never leave the
+# table) and new rows are stamped with it; outside one (workers, login) access
+# is unscoped. Readings are written by the ingestion pipeline outside any
+# request and are only reached through a scoped device or patient.
+
+def organization_of(item: Dict[str, Any]) -> str:
+    return item.get("organizationId") or DEFAULT_ORGANIZATION_ID
+
+def _in_scope(item: Optional[Dict[str, Any]]) -> Optional[Dict[str, Any]]:
+    """The item, or None when it belongs to another organization than the caller's"""
+    org = current_organization_id()
+    if item is None or org is None or organization_of(item) == org:
+        return item
+    return None
+
+def _scoped(items) -> List[Dict[str, Any]]:
+    org = current_organization_id()
+    return [i for i in items if org is None or organization_of(i) == org]
+
+def _stamp_organization(item: Dict[str, Any]) -> Dict[str, Any]:
+    if not item.get("organizationId"):
+        item["organizationId"] = current_organization_id() or DEFAULT_ORGANIZATION_ID
+    return item
+
+def _organization_filter():
+    """Scan filter for the caller's organization (None: unscoped); legacy rows without organizationId are in the default one"""
+    org = current_organization_id()
+    if org is None:
+        return None
+    condition = Attr("organizationId").eq(org)
+    if org == DEFAULT_ORGANIZATION_ID:
+        condition = condition | Attr("organizationId").not_exists()
+    return condition
+
+def _with_organization_filter(kwargs: Dict[str, Any]) -> Dict[str, Any]:
+    """Query/scan kwargs with the caller's organization added to the FilterExpression"""
+    org_filter = _organization_filter()
+    if org_filter is not None:
+        existing = kwargs.get("FilterExpression")
+        kwargs["FilterExpression"] = org_filter if existing is None else existing & org_filter
+    return kwargs
+
+def _organization_condition(condition):
+    """A ConditionExpression that additionally requires the item to be in the caller's organization"""
+    org_filter = _organization_filter()
+    return condition if org_filter is None else condition & org_filter
+
+# Credentials and one-time codes (password hashes, MFA secrets, OTP hashes and
+# counters, ...) are matched by name so new secret fields are covered too.
+# Timestamps such as passwordChangedAt are not secret.
+USER_SECRET_FIELD_MARKERS = ("password", "secret", "otp", "hash", "token", "jti")
+
+def _is_user_secret_field(name: str) -> bool:
+    lowered = name.lower()
+    return not name.endswith("At") and any(marker in lowered for marker in USER_SECRET_FIELD_MARKERS)
+
+def _without_user_secrets(user: Optional[Dict[str,Any]]) -> Optional[Dict[str,Any]]:
+    """User record without secret fields; this is all that is ever cached"""
+    if user is None:
+        return None
+    return {k: v for k, v in user.items() if not _is_user_secret_field(k)}
+
+def _invalidate_user_cache(user_id: str, *emails: Optional[str]):
+    """Drop cached copies of a user keyed by id and by every known email"""
+    cached = cache_service.get(user_key(user_id))
+    known = set(e for e in emails if e)
+    if cached and cached.get("email"):
+        known.add(cached["email"])
+    cache_service.invalidate(user_key(user_id))
+    for email in known:
+        cache_service.invalidate(user_email_key(email))
+
 def put_user(u: Dict[str,Any]):
     if USE_MEMORY:
         _users[u["id"]] = u
@@ -120,30 +294,133 @@ def put_user(u: Dict[str,Any]):
     item = dict(u)
     if USERS_SINGLE_TABLE:
         item.update(_user_key(u["id"]))
-    T_USERS.put_item(Item=item)
+    with_retry(lambda: T_USERS.put_item(Item=item))
+    _invalidate_user_cache(u["id"], u.get("email"))
+
+# Registration writes an EMAIL#<email> guard item next to the user in one
+# transaction so two concurrent sign-ups cannot both claim an email. Guard
+# items carry no "email" attribute, so they never appear in the email-index.
+EMAIL_GUARD_PREFIX = "EMAIL#"
+
+class EmailTakenError(ConditionFailedError):
+    """Raised by create_user when the email (or user id) is already registered."""
+
+    def __init__(self, email: str):
+        super().__init__(f"Email {email} is already registered")
+        self.email = email
+
+def _email_guard_key(email: str) -> Dict[str,str]:
+    if USERS_SINGLE_TABLE:
+        return {USERS_PK_ATTR: f"{EMAIL_GUARD_PREFIX}{email}", USERS_SK_ATTR: "EMAIL"}
+    return {USERS_PK_ATTR: f"{EMAIL_GUARD_PREFIX}{email}"}
+
+def _not_email_guard():
+    """Scan filter that skips email guard items"""
+    return ~Attr(USERS_PK_ATTR).begins_with(EMAIL_GUARD_PREFIX)
+
+def create_user(u: Dict[str,Any]) -> None:
+    """
+    Insert a new user, atomically claiming its email (TransactWriteItems).
+
+    Raises:
+        EmailTakenError: if the email guard or the user id already exists
+    """
+    _stamp_organization(u)
+    if USE_MEMORY:
+        if u["id"] in _users or any(x.get("email") == u["email"] for x in _users.values()):
+            raise EmailTakenError(u["email"])
+        _users[u["id"]] = u
+        return
+    item = dict(u)
+    if USERS_SINGLE_TABLE:
+        item.update(_user_key(u["id"]))
+    guard = {**_email_guard_key(u["email"]), "userId": u["id"]}
+    condition = "attribute_not_exists(#pk)"
+    names = {"#pk": USERS_PK_ATTR}
+    try:
+        with_retry(lambda: ddb.meta.client.transact_write_items(TransactItems=[
+            {"Put": {"TableName": T_USERS.name, "Item": item,
+                     "ConditionExpression": condition, "ExpressionAttributeNames": names}},
+            {"Put": {"TableName": T_USERS.name, "Item": guard,
+                     "ConditionExpression": condition, "ExpressionAttributeNames": names}},
+        ]))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "TransactionCanceledException":
+            raise EmailTakenError(u["email"])
+        raise
+    _invalidate_user_cache(u["id"], u.get("email"))
+
+def _delete_email_guard(email: Optional[str]) -> None:
+    if USE_MEMORY or not email:
+        return
+    try:
+        with_retry(lambda: T_USERS.delete_item(Key=_email_guard_key(email)))
+    except Exception as e:
+        print(f"[db] Error deleting email guard for {email}: {e}")
 
-def get_user_by_email(email: str) -> Optional[Dict[str,Any]]:
+def get_user_by_email(email: str, include_secrets: bool = False) -> Optional[Dict[str,Any]]:
+    """
+    Look up a user by email.
+    Secret fields are only returned with include_secrets=True, which always
+    reads DynamoDB because the cache never holds them.
+    """
     if USE_MEMORY:
-        return next((u for u in _users.values() if u["email"]==email), None)
+        user = next((u for u in _users.values() if u["email"]==email), None)
+        return user if include_secrets else _without_user_secrets(user)
+    if not include_secrets:
+        cached = cache_service.get(user_email_key(email))
+        if cached is not None:
+            return cached
     resp = T_USERS.query(IndexName="email-index",
                          KeyConditionExpression=Key("email").eq(email),
                          Limit=1)
     items = resp.get("Items", [])
-    return items[0] if items else None
+    if not items:
+        return None
+    cache_service.set(user_email_key(email), _without_user_secrets(items[0]))
+    return items[0] if include_secrets else _without_user_secrets(items[0])
 
-def get_user(user_id: str) -> Optional[Dict[str,Any]]:
+def get_user(user_id: str, include_secrets: bool = False) -> Optional[Dict[str,Any]]:
+    """
+    Get a user by id.
+    Secret fields are only returned with include_secrets=True, which always
+    reads DynamoDB because the cache never holds them.
+    """
     if USE_MEMORY:
-        return _users.get(user_id)
+        user = _in_scope(_users.get(user_id))
+        return user if include_secrets else _without_user_secrets(user)
+    if not include_secrets:
+        cached = cache_service.get(user_key(user_id))
+        if cached is not None:
+            return _in_scope(cached)
     resp = T_USERS.get_item(Key=_user_key(user_id))
-    return resp.get("Item")
+    item = resp.get("Item")
+    if item:
+        cache_service.set(user_key(user_id), _without_user_secrets(item))
+    return _in_scope(item if include_secrets else _without_user_secrets(item))
+
+def delete_user(user_id: str) -> bool:
+    """Hard-delete a user record (and its email guard, freeing the email)"""
+    if USE_MEMORY:
+        return _users.pop(user_id, None) is not None
+    try:
+        resp = with_retry(lambda: T_USERS.delete_item(Key=_user_key(user_id), ReturnValues="ALL_OLD"))
+        _delete_email_guard(resp.get("Attributes", {}).get("email"))
+        _invalidate_user_cache(user_id)
+        return True
+    except Exception as e:
+        print(f"[db] Error deleting user {user_id}: {e}")
+        return False
 
-def list_users(role: Optional[str] = None, limit: int = 50, next_token: Optional[str] = None) -> Tuple[List[Dict[str,Any]], Optional[str]]:
+def list_users(role: Optional[str] = None, limit: int = 50, next_token: Optional[str] = None) -> PaginatedResult[Dict[str,Any]]:
     """
     List users with optional role filter.
-    Returns (users_list, next_token)
+    
+    Raises:
+        ValueError: if next_token is not a valid cursor
     """
     if USE_MEMORY:
-        users = list(_users.values())
+        users = _scoped(_users.values())
         if role:
             users = [u for u in users if u.get("role") == role]
         # Simple pagination for in-memory
@@ -156,32 +433,16 @@ def list_users(role: Optional[str] = None, limit: int = 50, next_token: Optional
         end_idx = start_idx + limit
         result = users[start_idx:end_idx]
         new_token = result[-1]["id"] if len(result) == limit and end_idx < len(users) else None
-        return result, new_token
+        return PaginatedResult.with_cursor(result, new_token, total=len(users))
     
     # DynamoDB scan with optional filter
-    scan_kwargs = {"Limit": limit}
+    scan_kwargs = {"FilterExpression": _not_email_guard()}
     
     if role:
         scan_kwargs["FilterExpression"] = Attr("role").eq(role)
     
-    if next_token:
-        scan_kwargs["ExclusiveStartKey"] = _user_key(next_token)
-    
-    resp = T_USERS.scan(**scan_kwargs)
-    items = resp.get("Items", [])
-    
-    # Get next token from LastEvaluatedKey
-    last_key = resp.get("LastEvaluatedKey")
-    new_next_token = None
-    if last_key:
-        # Extract the user id from the key
-        if USERS_SINGLE_TABLE:
-            pk_value = last_key.get(USERS_PK_ATTR, "")
-            new_next_token = pk_value.replace("USER#", "") if pk_value.startswith("USER#") else pk_value
-        else:
-            new_next_token = last_key.get(USERS_PK_ATTR)
-    
-    return items, new_next_token
+    items, last_key = _filtered_page(T_USERS.scan, limit, decode_cursor(next_token), **_with_organization_filter(scan_kwargs))
+    return PaginatedResult.from_dynamo(items, last_key)
 
 def update_user(user_id: str, updates: Dict[str,Any]) -> bool:
     """
@@ -198,49 +459,88 @@ def update_user(user_id: str, updates: Dict[str,Any]) -> bool:
             return True
         return False
     
-    # Build update expression for DynamoDB
-    update_parts = []
-    remove_parts = []
-    expr_attr_names = {}
-    expr_attr_values = {}
-    
-    for i, (key, value) in enumerate(updates.items()):
-        attr_name = f"#attr{i}"
-        expr_attr_names[attr_name] = key
-        
-        if value is None:
-            remove_parts.append(attr_name)
-        else:
-            attr_value = f":val{i}"
-            update_parts.append(f"{attr_name} = {attr_value}")
-            expr_attr_values[attr_value] = value
-    
-    update_expression = ""
-    if update_parts:
-        update_expression += "SET " + ", ".join(update_parts)
-    if remove_parts:
-        if update_expression:
-            update_expression += " "
-        update_expression += "REMOVE " + ", ".join(remove_parts)
-    
-    if not update_expression:
+    builder = DynamoUpdateBuilder().set_all(_to_decimal(updates))
+    if builder.is_empty():
         return True
     
     try:
-        update_kwargs = {
-            "Key": _user_key(user_id),
-            "UpdateExpression": update_expression,
-            "ExpressionAttributeNames": expr_attr_names
-        }
-        if expr_attr_values:
-            update_kwargs["ExpressionAttributeValues"] = expr_attr_values
-            
-        T_USERS.update_item(**update_kwargs)
+        with_retry(lambda: T_USERS.update_item(Key=_user_key(user_id), **builder.build()))
+        _invalidate_user_cache(user_id, updates.get("email"))
         return True
     except Exception as e:
         print(f"[db] Error updating user {user_id}: {e}")
         return False
 
+def consume_otp_attempt(user_id: str, max_attempts: int) -> Optional[int]:
+    """
+    Atomically count one SMS code attempt against the user before the code is checked,
+    so parallel guesses cannot get past max_attempts.
+
+    Returns:
+        The attempt number, or None if max_attempts were already used
+    """
+    if USE_MEMORY:
+        user = _users.get(user_id)
+        if not user or not user.get("otpHash") or int(user.get("otpAttempts", 0)) >= max_attempts:
+            return None
+        user["otpAttempts"] = int(user.get("otpAttempts", 0)) + 1
+        return user["otpAttempts"]
+    try:
+        resp = with_retry(lambda: T_USERS.update_item(
+            Key=_user_key(user_id),
+            UpdateExpression="ADD otpAttempts :one",
+            ConditionExpression="attribute_exists(otpHash) AND (attribute_not_exists(otpAttempts) OR otpAttempts < :max)",
+            ExpressionAttributeValues={":one": 1, ":max": max_attempts},
+            ReturnValues="UPDATED_NEW",
+        ))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            return None
+        raise
+    return int(resp["Attributes"]["otpAttempts"])
+
+def update_user_fields(user_id: str, fields: Dict[str,Any], expected_version: Optional[int] = None) -> bool:
+    """
+    Apply only the changed fields to a user (see dynamo_update.diff_user)
+    and bump its version counter.
+    When expected_version is given the write only succeeds if the stored
+    version still matches; otherwise VersionConflictError is raised.
+    """
+    builder = DynamoUpdateBuilder().set_all(fields).with_version(expected_version)
+    if USE_MEMORY:
+        if user_id not in _users:
+            return False
+        builder.apply_to(_users[user_id])
+        return True
+    
+    try:
+        with_retry(lambda: T_USERS.update_item(Key=_user_key(user_id), **builder.build()))
+        _invalidate_user_cache(user_id, fields.get("email"))
+        return True
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            raise VersionConflictError(expected_version)
+        print(f"[db] Error updating user fields {user_id}: {e}")
+        return False
+    except Exception as e:
+        print(f"[db] Error updating user fields {user_id}: {e}")
+        return False
+
+def erase_user_fields(user_id: str, fields: Dict[str,Any]) -> bool:
+    """
+    Overwrite or remove (None) personal fields of a user.
+    Unlike update_user_fields this also drops cache entries under the
+    previous email when the email is replaced.
+    """
+    previous = get_user(user_id)
+    if not update_user_fields(user_id, fields):
+        return False
+    if not USE_MEMORY and previous:
+        _invalidate_user_cache(user_id, previous.get("email"))
+        if "email" in fields and fields["email"] != previous.get("email"):
+            _delete_email_guard(previous.get("email"))
+    return True
+
 def save_refresh(token: str, sess: Dict[str,Any]):
     if USE_MEMORY:
         _refresh[token] = sess
@@ -248,13 +548,51 @@ def save_refresh(token: str, sess: Dict[str,Any]):
     item = {"token": token, **sess}
     if REFRESH_SINGLE_TABLE:
         item.update(_refresh_key(token))
-    T_REFRESH.put_item(Item=item)
+    with_retry(lambda: T_REFRESH.put_item(Item=item))
 
 def take_refresh(token: str) -> Optional[Dict[str,Any]]:
     if USE_MEMORY:
         return _refresh.pop(token, None)
-    key = _refresh_key(token)
-    resp = T_REFRESH.get_item(Key=key)
+    # Delete-and-return in one call so a token can only be taken once
+    resp = with_retry(lambda: T_REFRESH.delete_item(Key=_refresh_key(token), ReturnValues="ALL_OLD"))
+    return resp.get("Attributes")
+
+def _user_refresh_items(user_id: str) -> List[Dict[str,Any]]:
+    if USE_MEMORY:
+        return [{"token": t, **sess} for t, sess in _refresh.items() if sess.get("userId") == user_id]
+    return _query_all(T_REFRESH, IndexName="userId-index",
+                      KeyConditionExpression=Key("userId").eq(user_id))
+
+def _delete_refresh_items(items: List[Dict[str,Any]]) -> int:
+    if USE_MEMORY:
+        for item in items:
+            _refresh.pop(item["token"], None)
+        return len(items)
+    with T_REFRESH.batch_writer() as batch:
+        for item in items:
+            batch.delete_item(Key=_refresh_key(item["token"]))
+    return len(items)
+
+def list_refresh_sessions(user_id: str) -> List[Dict[str,Any]]:
+    """Unexpired refresh token records of a user (one per active session)"""
+    now = int(time.time())
+    items = [i for i in _user_refresh_items(user_id) if int(i.get("expiresAt", 0)) > now]
+    return [_from_decimal(i) for i in items]
+
+def has_refresh_session(user_id: str, session_id: str) -> bool:
+    """Whether a session still holds an unexpired refresh token"""
+    return any(s.get("sessionId") == session_id for s in list_refresh_sessions(user_id))
+
+def revoke_refresh_session(user_id: str, session_id: str) -> int:
+    """Delete the refresh token(s) of one session"""
+    items = [i for i in _user_refresh_items(user_id) if i.get("sessionId") == session_id]
+    return _delete_refresh_items(items)
+
+def revoke_user_refresh_tokens(user_id: str, except_session_id: Optional[str] = None) -> int:
+    """Delete every stored refresh token of a user (sign out all sessions), optionally keeping one session"""
+    items = [i for i in _user_refresh_items(user_id)
+             if except_session_id is None or i.get("sessionId") != except_session_id]
+    return _delete_refresh_items(items)
 
 
 # ========== Verification Code Functions ==========
@@ -278,15 +616,15 @@ def save_verification_code(email: str, code: str, code_type: str = "registration
         return True
     
     try:
-        nonces_table = ddb.Table(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod"))
-        nonces_table.put_item(Item={
+        nonces_table = ddb.Table(sanitize_table_name(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod")))
+        with_retry(lambda: nonces_table.put_item(Item={
             "nonce": f"VERIFY#{email}#{code_type}",  # Unique key per email+type
             "code": code,
             "email": email,
             "type": code_type,
             "created_at": int(time.time()),
             "ttl": int(time.time()) + VERIFICATION_CODE_TTL  # Auto-delete after 10 min
-        })
+        }))
         return True
     except Exception as e:
         print(f"[db] Error saving verification code: {e}")
@@ -313,7 +651,7 @@ def verify_and_consume_code(email: str, code: str, code_type: str = "registratio
         return True
     
     try:
-        nonces_table = ddb.Table(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod"))
+        nonces_table = ddb.Table(sanitize_table_name(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod")))
         key = {"nonce": f"VERIFY#{email}#{code_type}"}
         
         # Get the stored code
@@ -326,7 +664,7 @@ def verify_and_consume_code(email: str, code: str, code_type: str = "registratio
         
         # Check if expired (extra safety, TTL should handle this)
         if item.get("ttl", 0) < int(time.time()):
-            nonces_table.delete_item(Key=key)
+            with_retry(lambda: nonces_table.delete_item(Key=key))
             print(f"[db] Verification code expired for {email}")
             return False
         
@@ -336,7 +674,7 @@ def verify_and_consume_code(email: str, code: str, code_type: str = "registratio
             return False
         
         # Code is valid - consume it (delete)
-        nonces_table.delete_item(Key=key)
+        with_retry(lambda: nonces_table.delete_item(Key=key))
         print(f"[db] Verification code consumed for {email}")
         return True
         
@@ -368,7 +706,7 @@ def has_pending_verification(email: str, code_type: str = "registration", min_ag
         return code_age < min_age_seconds
     
     try:
-        nonces_table = ddb.Table(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod"))
+        nonces_table = ddb.Table(sanitize_table_name(os.environ.get("DDB_TABLE_NONCES", "medusa-nonces-prod")))
         key = {"nonce": f"VERIFY#{email}#{code_type}"}
         resp = nonces_table.get_item(Key=key)
         item = resp.get("Item")
@@ -382,28 +720,22 @@ def has_pending_verification(email: str, code_type: str = "registration", min_ag
         return code_age < min_age_seconds
     except Exception:
         return False
-        T_REFRESH.delete_item(Key=key)
-    return item
 
-def list_poses_by_patient(pid: str, limit:int=50, next_token=None) -> Tuple[List[Dict[str,Any]], Any]:
+def list_poses_by_patient(pid: str, limit:int=50, next_token: Optional[str] = None) -> PaginatedResult[Dict[str,Any]]:
     if USE_MEMORY:
-        items = [p for p in _poses if p["patientId"]==pid]
-        return items[:limit], None
+        items = [p for p in _scoped(_poses) if p["patientId"]==pid]
+        return PaginatedResult(items[:limit], total=len(items), has_more=len(items) > limit)
     if POSES_SINGLE_TABLE:
         key_expr = Key(POSES_PK_ATTR).eq(_pose_pk(pid))
-        kw = {
-            "KeyConditionExpression": key_expr,
-            "Limit": limit
-        }
+        kw = {"KeyConditionExpression": key_expr}
     else:
         # Direct query using patientId as HASH key (no GSI needed)
-        kw = {"KeyConditionExpression":Key("patientId").eq(pid),
-              "Limit":limit}
-    if next_token: kw["ExclusiveStartKey"] = next_token
-    resp = T_POSES.query(**kw)
-    return resp.get("Items", []), resp.get("LastEvaluatedKey")
+        kw = {"KeyConditionExpression":Key("patientId").eq(pid)}
+    items, last_key = _filtered_page(T_POSES.query, limit, decode_cursor(next_token), **_with_organization_filter(kw))
+    return PaginatedResult.from_dynamo(items, last_key)
 
 def create_pose(p: Dict[str,Any]):
+    _stamp_organization(p)
     if USE_MEMORY:
         _poses.append(p)
         return
@@ -412,7 +744,7 @@ def create_pose(p: Dict[str,Any]):
         item[POSES_PK_ATTR] = _pose_pk(p["patientId"])
         if POSES_SK_ATTR:
             item[POSES_SK_ATTR] = _pose_sk(p["id"])
-    T_POSES.put_item(Item=item)
+    with_retry(lambda: T_POSES.put_item(Item=item))
 
 # ========================================
 # Device Operations
@@ -420,27 +752,78 @@ def create_pose(p: Dict[str,Any]):
 
 def create_device(device: Dict[str, Any]) -> None:
     """Create a new device"""
+    _stamp_organization(device)
     if USE_MEMORY:
         _devices.append(device)
         return
-    T_DEVICES.put_item(Item=device)
+    with_retry(lambda: T_DEVICES.put_item(Item=device))
 
 def get_device(device_id: str) -> Optional[Dict[str, Any]]:
     """Get device by ID"""
     if USE_MEMORY:
         for d in _devices:
             if d["id"] == device_id:
-                return d
+                return _in_scope(d)
         return None
     resp = T_DEVICES.get_item(Key={"id": device_id})
-    return resp.get("Item")
+    return _in_scope(_from_decimal(resp.get("Item")))
+
+# -------- Bulk reads
+BATCH_GET_MAX_KEYS = 100  # BatchGetItem limit per request
+BATCH_GET_MAX_ATTEMPTS = 5  # calls per chunk while UnprocessedKeys remain
+
+def _batch_get(table, keys: List[Dict[str, Any]]) -> List[Dict[str, Any]]:
+    """
+    Fetch items by primary key with BatchGetItem, 100 keys per call.
+    UnprocessedKeys (throttled partitions) are re-requested with backoff.
+
+    Raises:
+        RuntimeError: if keys are still unprocessed after BATCH_GET_MAX_ATTEMPTS calls
+    """
+    items: List[Dict[str, Any]] = []
+    for start in range(0, len(keys), BATCH_GET_MAX_KEYS):
+        request = {table.name: {"Keys": keys[start:start + BATCH_GET_MAX_KEYS]}}
+        for attempt in range(BATCH_GET_MAX_ATTEMPTS):
+            resp = with_retry(lambda: ddb.batch_get_item(RequestItems=request))
+            items.extend(resp.get("Responses", {}).get(table.name, []))
+            request = resp.get("UnprocessedKeys") or {}
+            if not request:
+                break
+            time.sleep(backoff_delay_ms(attempt) / 1000)
+        else:
+            raise RuntimeError(f"BatchGetItem on {table.name} left keys unprocessed")
+    return items
+
+def get_users_bulk(user_ids: List[str]) -> Dict[str, Dict[str, Any]]:
+    """Get several users in as few reads as possible; missing ids are absent from the result"""
+    ids = list(dict.fromkeys(user_ids))
+    if USE_MEMORY:
+        return {uid: _without_user_secrets(_users[uid]) for uid in ids if uid in _users and _in_scope(_users[uid])}
+    users: Dict[str, Dict[str, Any]] = {}
+    for uid in ids:
+        cached = cache_service.get(user_key(uid))
+        if cached is not None:
+            users[uid] = cached
+    misses = [uid for uid in ids if uid not in users]
+    for item in _batch_get(T_USERS, [_user_key(uid) for uid in misses]):
+        users[item["id"]] = _without_user_secrets(item)
+        cache_service.set(user_key(item["id"]), users[item["id"]])
+    return {uid: user for uid, user in users.items() if _in_scope(user)}
+
+def get_devices_bulk(device_ids: List[str]) -> Dict[str, Dict[str, Any]]:
+    """Get several devices in as few reads as possible; missing ids are absent from the result"""
+    ids = set(device_ids)
+    if USE_MEMORY:
+        return {d["id"]: d for d in _scoped(_devices) if d["id"] in ids}
+    items = _batch_get(T_DEVICES, [{"id": device_id} for device_id in sorted(ids)])
+    return {item["id"]: _from_decimal(item) for item in _scoped(items)}
 
 def get_device_by_mac(mac_address: str) -> Optional[Dict[str, Any]]:
     """Get device by MAC address"""
     if USE_MEMORY:
         for d in _devices:
             if d["macAddress"] == mac_address:
-                return d
+                return _in_scope(d)
         return None
     resp = T_DEVICES.query(
         IndexName="macAddress-index",
@@ -448,25 +831,35 @@ def get_device_by_mac(mac_address: str) -> Optional[Dict[str, Any]]:
         Limit=1
     )
     items = resp.get("Items", [])
-    return items[0] if items else None
+    return _in_scope(items[0]) if items else None
 
 def get_devices_by_patient(patient_id: str) -> List[Dict[str, Any]]:
     """Get all devices for a patient (personal devices only)"""
     if USE_MEMORY:
-        return [d for d in _devices if d.get("patientId") == patient_id]
+        return [d for d in _scoped(_devices) if d.get("patientId") == patient_id]
     # No index for patientId anymore (devices are in shared pool)
     # Use scan with filter for personal devices
-    resp = T_DEVICES.scan(
-        FilterExpression=Attr("patientId").eq(patient_id)
-    )
-    return resp.get("Items", [])
+    return _scan_all(T_DEVICES, **_with_organization_filter({"FilterExpression": Attr("patientId").eq(patient_id)}))
 
 def get_all_devices() -> List[Dict[str, Any]]:
-    """Get all devices (admin only)"""
+    """Get all devices of the caller's organization (admin only)"""
+    if USE_MEMORY:
+        return _scoped(_devices)
+    return _scan_all(T_DEVICES, **_with_organization_filter({}))
+
+def get_low_battery_devices(threshold_percent: float, reported_since: str) -> List[Dict[str, Any]]:
+    """Devices whose battery level is below the threshold, reported at or after reported_since (ISO-8601)"""
     if USE_MEMORY:
-        return _devices
-    resp = T_DEVICES.scan()
-    return resp.get("Items", [])
+        return [
+            d for d in _scoped(_devices)
+            if d.get("batteryLevel") is not None and d["batteryLevel"] < threshold_percent
+            and (d.get("batteryUpdatedAt") or "") >= reported_since
+        ]
+    items = _scan_all(T_DEVICES, **_with_organization_filter({
+        "FilterExpression": Attr("batteryLevel").lt(_to_decimal(threshold_percent))
+        & Attr("batteryUpdatedAt").gte(reported_since)
+    }))
+    return [_from_decimal(d) for d in items]
 
 def update_device(device_id: str, updates: Dict[str, Any]) -> None:
     """Update device fields"""
@@ -489,14 +882,14 @@ def update_device(device_id: str, updates: Dict[str, Any]) -> None:
         attr_value = f":val{i}"
         update_expr += f"{attr_name} = {attr_value}"
         expr_attr_names[attr_name] = key
-        expr_attr_values[attr_value] = value
+        expr_attr_values[attr_value] = _to_decimal(value)
     
-    T_DEVICES.update_item(
+    with_retry(lambda: T_DEVICES.update_item(
         Key={"id": device_id},
         UpdateExpression=update_expr,
         ExpressionAttributeNames=expr_attr_names,
         ExpressionAttributeValues=expr_attr_values
-    )
+    ))
 
 def delete_device(device_id: str) -> None:
     """Delete a device"""
@@ -504,7 +897,7 @@ def delete_device(device_id: str) -> None:
         global _devices
         _devices = [d for d in _devices if d["id"] != device_id]
         return
-    T_DEVICES.delete_item(Key={"id": device_id})
+    with_retry(lambda: T_DEVICES.delete_item(Key={"id": device_id}))
 
 # ========================================
 # Patient Profile Operations
@@ -512,67 +905,50 @@ def delete_device(device_id: str) -> None:
 
 def create_patient_profile(profile: Dict[str, Any]) -> None:
     """Create a patient profile"""
+    _stamp_organization(profile)
     if USE_MEMORY:
         _patient_profiles[profile["userId"]] = profile
         return
-    T_PATIENT_PROFILES.put_item(Item=profile)
+    with_retry(lambda: T_PATIENT_PROFILES.put_item(Item=profile))
 
 def get_patient_profile(user_id: str) -> Optional[Dict[str, Any]]:
     """Get patient profile by user ID"""
     if USE_MEMORY:
-        return _patient_profiles.get(user_id)
+        return _in_scope(_patient_profiles.get(user_id))
     resp = T_PATIENT_PROFILES.get_item(Key={"userId": user_id})
-    return resp.get("Item")
+    return _in_scope(resp.get("Item"))
 
 def get_patients_by_doctor(doctor_id: str) -> List[Dict[str, Any]]:
     """Get all patients assigned to a doctor"""
     if USE_MEMORY:
-        return [p for p in _patient_profiles.values() if p.get("doctorId") == doctor_id]
+        return [p for p in _scoped(_patient_profiles.values()) if p.get("doctorId") == doctor_id]
     
     try:
-        resp = T_PATIENT_PROFILES.query(
-            IndexName="doctorId-index",
-            KeyConditionExpression=Key("doctorId").eq(doctor_id)
-        )
-        return resp.get("Items", [])
+        return _query_all(T_PATIENT_PROFILES, **_with_organization_filter({
+            "IndexName": "doctorId-index",
+            "KeyConditionExpression": Key("doctorId").eq(doctor_id),
+        }))
     except Exception as e:
         print(f"Error querying patients by doctor: {e}")
         return []
 
 def get_all_patient_profiles() -> List[Dict[str, Any]]:
-    """Get all patient profiles (admin only)"""
+    """Get all patient profiles of the caller's organization (admin only)"""
     if USE_MEMORY:
-        return list(_patient_profiles.values())
-    resp = T_PATIENT_PROFILES.scan()
-    return resp.get("Items", [])
+        return _scoped(_patient_profiles.values())
+    return _scan_all(T_PATIENT_PROFILES, **_with_organization_filter({}))
 
 def update_patient_profile(user_id: str, updates: Dict[str, Any]) -> None:
-    """Update patient profile fields"""
+    """Update patient profile fields (None removes a field)"""
     if USE_MEMORY:
         if user_id in _patient_profiles:
-            _patient_profiles[user_id].update(updates)
+            DynamoUpdateBuilder().set_all(updates).apply_to(_patient_profiles[user_id])
         return
     
-    # Build update expression
-    update_expr = "SET "
-    expr_attr_values = {}
-    expr_attr_names = {}
-    
-    for i, (key, value) in enumerate(updates.items()):
-        if i > 0:
-            update_expr += ", "
-        attr_name = f"#attr{i}"
-        attr_value = f":val{i}"
-        update_expr += f"{attr_name} = {attr_value}"
-        expr_attr_names[attr_name] = key
-        expr_attr_values[attr_value] = value
-    
-    T_PATIENT_PROFILES.update_item(
-        Key={"userId": user_id},
-        UpdateExpression=update_expr,
-        ExpressionAttributeNames=expr_attr_names,
-        ExpressionAttributeValues=expr_attr_values
-    )
+    builder = DynamoUpdateBuilder().set_all(_to_decimal(updates))
+    if builder.is_empty():
+        return
+    with_retry(lambda: T_PATIENT_PROFILES.update_item(Key={"userId": user_id}, **builder.build()))
 
 def delete_patient_profile(user_id: str) -> None:
     """Delete a patient profile"""
@@ -580,7 +956,7 @@ def delete_patient_profile(user_id: str) -> None:
         if user_id in _patient_profiles:
             del _patient_profiles[user_id]
         return
-    T_PATIENT_PROFILES.delete_item(Key={"userId": user_id})
+    with_retry(lambda: T_PATIENT_PROFILES.delete_item(Key={"userId": user_id}))
 
 # ========================================
 # Session Operations (Device-Patient Dynamic Binding)
@@ -588,30 +964,30 @@ def delete_patient_profile(user_id: str) -> None:
 
 def create_session(session: Dict[str, Any]) -> None:
     """Create a measurement session"""
+    _stamp_organization(session)
     if USE_MEMORY:
         _sessions[session["sessionId"]] = session
         return
-    T_SESSIONS.put_item(Item=session)
+    with_retry(lambda: T_SESSIONS.put_item(Item=session))
 
 def get_session(session_id: str) -> Optional[Dict[str, Any]]:
     """Get session by ID"""
     if USE_MEMORY:
-        return _sessions.get(session_id)
+        return _in_scope(_sessions.get(session_id))
     resp = T_SESSIONS.get_item(Key={SESSIONS_PK_ATTR: session_id})
-    return resp.get("Item")
+    return _in_scope(resp.get("Item"))
 
 def get_session_by_id(session_id: str) -> Optional[Dict[str,Any]]:
     if USE_MEMORY:
-        return _sessions.get(session_id)
+        return _in_scope(_sessions.get(session_id))
     resp = T_SESSIONS.get_item(Key={SESSIONS_PK_ATTR: session_id})
-    return resp.get("Item")
+    return _in_scope(resp.get("Item"))
 
-from datetime import datetime, timezone
+from datetime import datetime, timezone, timedelta
 
-def get_tremor_analysis(patient_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None, limit: int = 100) -> Tuple[List[Dict[str,Any]], int]:
+def get_tremor_analysis(patient_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None, limit: int = 100) -> PaginatedResult[Dict[str,Any]]:
     """
-    Query tremor analysis data for a patient.
-    Returns (items, count)
+    Query tremor analysis data for a patient (latest first).
     """
     if USE_MEMORY:
         # Simple memory implementation
@@ -621,7 +997,7 @@ def get_tremor_analysis(patient_id: str, start_time: Optional[int] = None, end_t
         if end_time:
             items = [t for t in items if t.get("timestamp", 0) <= end_time]
         items.sort(key=lambda x: x.get("timestamp", 0), reverse=True)
-        return items[:limit], len(items)
+        return PaginatedResult(items[:limit], total=len(items), has_more=len(items) > limit)
 
     key_condition = Key(TREMOR_PK_ATTR).eq(patient_id)
     
@@ -645,35 +1021,314 @@ def get_tremor_analysis(patient_id: str, start_time: Optional[int] = None, end_t
             Limit=limit
         )
         items = resp.get("Items", [])
-        count = resp.get("Count", 0)
         
         # Post-processing
         for item in items:
-            # Convert Decimals to float/int
-            for k, v in item.items():
-                if isinstance(v, Decimal):
-                    if v % 1 == 0:
-                        item[k] = int(v)
-                    else:
-                        item[k] = float(v)
-            
-            # Convert timestamp string back to int for API response model
-            if "timestamp" in item and isinstance(item["timestamp"], str):
-                try:
-                    # Parse ISO string to timestamp
-                    dt = datetime.fromisoformat(item["timestamp"].replace("Z", "+00:00"))
-                    item["timestamp"] = int(dt.timestamp())
-                except Exception:
-                    pass # Keep as is if parsing fails
+            _normalize_tremor_item(item)
                         
-        return items, count
+        return PaginatedResult.from_dynamo(items, resp.get("LastEvaluatedKey"))
     except Exception as e:
         print(f"Error querying tremor analysis: {e}")
-        return [], 0
+        return PaginatedResult.empty()
+
+def batch_write_device_readings(readings: List[Dict[str,Any]]) -> int:
+    """
+    Store raw device readings in the sensor data table (keyed by device_id/timestamp).
+    Numeric values are converted to Decimal. Returns the number of items written.
+    """
+    if USE_MEMORY:
+        _sensor_data.extend(readings)
+        return len(readings)
+    def _write():
+        # Overwriting by key makes re-sending the whole batch safe
+        with T_SENSOR_DATA.batch_writer(overwrite_by_pkeys=[SENSOR_PK_ATTR, SENSOR_SK_ATTR]) as batch:
+            for reading in readings:
+                batch.put_item(Item=_to_decimal(reading))
+    with_retry(_write)
+    return len(readings)
+
+def get_recent_sensor_readings(device_id: str, reading_type: str, limit: int = 20) -> List[Dict[str,Any]]:
+    """
+    The device's latest stored raw readings of one type, oldest first.
+    """
+    if USE_MEMORY:
+        items = [r for r in _sensor_data if r.get("device_id") == device_id and r.get("reading_type") == reading_type]
+        return sorted(items, key=lambda r: r.get("timestamp", 0))[-limit:] if limit > 0 else []
+
+    query_kwargs = {
+        "KeyConditionExpression": Key(SENSOR_PK_ATTR).eq(device_id),
+        "FilterExpression": Attr("reading_type").eq(reading_type),
+        "ScanIndexForward": False,
+    }
+    items: List[Dict[str,Any]] = []
+    while len(items) < limit:
+        resp = with_retry(lambda: T_SENSOR_DATA.query(Limit=limit - len(items), **query_kwargs))
+        items.extend(_from_decimal(i) for i in resp.get("Items", []))
+        if "LastEvaluatedKey" not in resp:
+            break
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+    return list(reversed(items))
+
+def get_sensor_readings(device_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None) -> List[Dict[str,Any]]:
+    """
+    The device's stored raw readings within a time range (unix seconds), oldest first.
+    """
+    if USE_MEMORY:
+        items = [r for r in _sensor_data if r.get("device_id") == device_id]
+        if start_time:
+            items = [r for r in items if r.get("timestamp", 0) >= start_time]
+        if end_time:
+            items = [r for r in items if r.get("timestamp", 0) <= end_time]
+        return sorted(items, key=lambda r: r.get("timestamp", 0))
+
+    key_condition = Key(SENSOR_PK_ATTR).eq(device_id)
+    if start_time and end_time:
+        key_condition = key_condition & Key(SENSOR_SK_ATTR).between(start_time, end_time)
+    elif start_time:
+        key_condition = key_condition & Key(SENSOR_SK_ATTR).gte(start_time)
+    elif end_time:
+        key_condition = key_condition & Key(SENSOR_SK_ATTR).lte(end_time)
+
+    query_kwargs = {"KeyConditionExpression": key_condition, "ScanIndexForward": True}
+    items: List[Dict[str,Any]] = []
+    while True:
+        resp = with_retry(lambda: T_SENSOR_DATA.query(**query_kwargs))
+        items.extend(_from_decimal(i) for i in resp.get("Items", []))
+        if "LastEvaluatedKey" not in resp:
+            break
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+    return items
+
+def _normalize_tremor_item(item: Dict[str,Any]) -> None:
+    """Convert Decimals to float/int and ISO timestamps to unix seconds"""
+    for k, v in item.items():
+        if isinstance(v, Decimal):
+            if v % 1 == 0:
+                item[k] = int(v)
+            else:
+                item[k] = float(v)
+    
+    # Convert timestamp string back to int for API response model
+    if "timestamp" in item and isinstance(item["timestamp"], str):
+        try:
+            # Parse ISO string to timestamp
+            dt = datetime.fromisoformat(item["timestamp"].replace("Z", "+00:00"))
+            item["timestamp"] = int(dt.timestamp())
+        except Exception:
+            pass # Keep as is if parsing fails
+
+def get_patient_readings(patient_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None) -> List[Dict[str,Any]]:
+    """
+    Get every tremor analysis reading for a patient within a time range.
+    Unlike get_tremor_analysis this follows pagination; results are oldest first.
+    """
+    if USE_MEMORY:
+        items = [t for t in _tremor_analysis if t.get("patient_id") == patient_id]
+        if start_time:
+            items = [t for t in items if t.get("timestamp", 0) >= start_time]
+        if end_time:
+            items = [t for t in items if t.get("timestamp", 0) <= end_time]
+        return sorted(items, key=lambda x: x.get("timestamp", 0))
+
+    key_condition = Key(TREMOR_PK_ATTR).eq(patient_id)
+    start_iso = datetime.fromtimestamp(start_time, timezone.utc).isoformat().replace("+00:00", "Z") if start_time else None
+    end_iso = datetime.fromtimestamp(end_time, timezone.utc).isoformat().replace("+00:00", "Z") if end_time else None
+    if start_iso and end_iso:
+        key_condition = key_condition & Key(TREMOR_SK_ATTR).between(start_iso, end_iso)
+    elif start_iso:
+        key_condition = key_condition & Key(TREMOR_SK_ATTR).gte(start_iso)
+    elif end_iso:
+        key_condition = key_condition & Key(TREMOR_SK_ATTR).lte(end_iso)
+
+    try:
+        query_kwargs = {"KeyConditionExpression": key_condition, "ScanIndexForward": True}
+        items = []
+        while True:
+            resp = T_TREMOR_ANALYSIS.query(**query_kwargs)
+            items.extend(resp.get("Items", []))
+            if "LastEvaluatedKey" not in resp:
+                break
+            query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+        for item in items:
+            _normalize_tremor_item(item)
+        return items
+    except Exception as e:
+        print(f"Error querying patient readings: {e}")
+        return []
+
+# GSI on the tremor table (device_id HASH, timestamp RANGE) so a device's
+# readings are queried rather than scanned out of the patient-keyed table
+TREMOR_DEVICE_INDEX = "device_id-timestamp-index"
+
+def _tremor_iso(ts: int) -> str:
+    return datetime.fromtimestamp(ts, timezone.utc).isoformat().replace("+00:00", "Z")
+
+def _device_readings_query(device_id: str, start_time: Optional[int], end_time: Optional[int]) -> Dict[str,Any]:
+    condition = Key("device_id").eq(device_id)
+    if start_time and end_time:
+        condition = condition & Key("timestamp").between(_tremor_iso(start_time), _tremor_iso(end_time))
+    elif start_time:
+        condition = condition & Key("timestamp").gte(_tremor_iso(start_time))
+    elif end_time:
+        condition = condition & Key("timestamp").lte(_tremor_iso(end_time))
+    return {"IndexName": TREMOR_DEVICE_INDEX, "KeyConditionExpression": condition}
+
+def get_device_readings(device_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None) -> List[Dict[str,Any]]:
+    """
+    Get all tremor analysis readings produced by a device within a time range.
+    Timestamps are unix seconds; results are sorted oldest first.
+    DynamoDB errors propagate to the caller.
+    """
+    if USE_MEMORY:
+        items = [t for t in _tremor_analysis if t.get("device_id") == device_id]
+        if start_time:
+            items = [t for t in items if t.get("timestamp", 0) >= start_time]
+        if end_time:
+            items = [t for t in items if t.get("timestamp", 0) <= end_time]
+        return sorted(items, key=lambda x: x.get("timestamp", 0))
+
+    items = _query_all(T_TREMOR_ANALYSIS, **_device_readings_query(device_id, start_time, end_time))
+    for item in items:
+        _normalize_tremor_item(item)
+    return sorted(items, key=lambda x: x.get("timestamp", 0) if isinstance(x.get("timestamp"), int) else 0)
+
+
+def iter_device_reading_pages(device_id: str, start_time: Optional[int] = None, end_time: Optional[int] = None):
+    """
+    Yield a device's tremor analysis readings one DynamoDB page at a time, so
+    callers can stream large ranges without holding them all in memory.
+    Pages are oldest first.
+    """
+    if USE_MEMORY:
+        yield get_device_readings(device_id, start_time, end_time)
+        return
+
+    query_kwargs = _device_readings_query(device_id, start_time, end_time)
+    while True:
+        resp = with_retry(lambda: T_TREMOR_ANALYSIS.query(**query_kwargs))
+        items = resp.get("Items", [])
+        for item in items:
+            _normalize_tremor_item(item)
+        if items:
+            yield items
+        if "LastEvaluatedKey" not in resp:
+            return
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+# ============== Calibrations ==============
+
+def put_calibration(calibration: Dict[str,Any]) -> None:
+    """Store a device calibration record"""
+    _stamp_organization(calibration)
+    if USE_MEMORY:
+        _calibrations.append(calibration)
+        return
+    try:
+        with_retry(lambda: T_CALIBRATIONS.put_item(Item=_to_decimal(calibration)))
+    except Exception as e:
+        print(f"Error storing calibration: {e}")
+        raise
+
+def get_calibrations(device_id: str, limit: int = 50) -> List[Dict[str,Any]]:
+    """Get calibration records for a device, newest first"""
+    if USE_MEMORY:
+        items = [c for c in _scoped(_calibrations) if c.get("deviceId") == device_id]
+        items.sort(key=lambda c: c.get("calibratedAt", ""), reverse=True)
+        return items[:limit]
+    try:
+        items, _ = _filtered_page(T_CALIBRATIONS.query, limit, **_with_organization_filter({
+            "KeyConditionExpression": Key(CALIBRATIONS_PK_ATTR).eq(device_id),
+            "ScanIndexForward": False,
+        }))
+        return [_from_decimal(i) for i in items]
+    except Exception as e:
+        print(f"Error getting calibrations: {e}")
+        return []
+
+
+# ============== Device Maintenance ==============
+
+# pendingStatus is only set on open events, so the pending-index holds just those
+MAINTENANCE_PENDING_STATUS = "PENDING"
+
+def _maintenance_item(event: Dict[str, Any]) -> Dict[str, Any]:
+    item = {k: v for k, v in event.items() if k != "pendingStatus" and v is not None}
+    if not item.get("completedAt"):
+        item["pendingStatus"] = MAINTENANCE_PENDING_STATUS
+    return item
+
+def _maintenance_out(item: Dict[str, Any]) -> Dict[str, Any]:
+    return {k: v for k, v in _from_decimal(item).items() if k != "pendingStatus"}
+
+def put_maintenance_event(event: Dict[str, Any]) -> Dict[str, Any]:
+    """Create or replace a maintenance event (keyed by deviceId + scheduledAt)"""
+    item = _stamp_organization(_maintenance_item(event))
+    if USE_MEMORY:
+        _maintenance_events[:] = [e for e in _maintenance_events
+                                  if (e["deviceId"], e["scheduledAt"]) != (item["deviceId"], item["scheduledAt"])]
+        _maintenance_events.append(item)
+    else:
+        with_retry(lambda: T_MAINTENANCE.put_item(Item=_to_decimal(item)))
+    return _maintenance_out(item)
+
+def get_maintenance_events(device_id: str, pending_only: bool = False) -> List[Dict[str, Any]]:
+    """Maintenance events of a device, earliest scheduled first"""
+    if USE_MEMORY:
+        items = [e for e in _scoped(_maintenance_events) if e.get("deviceId") == device_id]
+    else:
+        items = _query_all(T_MAINTENANCE, **_with_organization_filter({
+            "KeyConditionExpression": Key(MAINTENANCE_PK_ATTR).eq(device_id)
+        }))
+    if pending_only:
+        items = [e for e in items if e.get("pendingStatus") == MAINTENANCE_PENDING_STATUS]
+    return sorted((_maintenance_out(i) for i in items), key=lambda e: e.get("scheduledAt", ""))
+
+def get_pending_maintenance(start_iso: str, end_iso: str) -> List[Dict[str, Any]]:
+    """Open maintenance events of all devices scheduled in [start_iso, end_iso], earliest first"""
+    if USE_MEMORY:
+        items = [e for e in _scoped(_maintenance_events)
+                 if e.get("pendingStatus") == MAINTENANCE_PENDING_STATUS and start_iso <= e.get("scheduledAt", "") <= end_iso]
+    else:
+        items = _query_all(T_MAINTENANCE, **_with_organization_filter({
+            "IndexName": "pending-index",
+            "KeyConditionExpression": Key("pendingStatus").eq(MAINTENANCE_PENDING_STATUS)
+                                      & Key("scheduledAt").between(start_iso, end_iso),
+        }))
+    return sorted((_maintenance_out(i) for i in items), key=lambda e: e.get("scheduledAt", ""))
+
+
+# ============== Table Maintenance ==============
+
+def enable_table_ttl(table_name: str, ttl_attribute: str = "ttl") -> bool:
+    """
+    Turn on Time To Live for a table that was created without it (template.yaml
+    enables it for new stacks). Safe to run repeatedly.
+
+    Returns:
+        True if TTL was enabled now, False if it already was on this attribute
 
+    Raises:
+        ValueError: if TTL is already enabled on a different attribute
+    """
+    if USE_MEMORY:
+        return False
+    client = ddb.meta.client
+    current = client.describe_time_to_live(TableName=table_name).get("TimeToLiveDescription", {})
+    if current.get("TimeToLiveStatus") in ("ENABLED", "ENABLING"):
+        if current.get("AttributeName") != ttl_attribute:
+            raise ValueError(f"{table_name} already uses TTL attribute {current.get('AttributeName')!r}")
+        return False
+    with_retry(lambda: client.update_time_to_live(
+        TableName=table_name,
+        TimeToLiveSpecification={"Enabled": True, "AttributeName": ttl_attribute}
+    ))
+    return True
 
 # ============== Audit Logs ==============
 
+# Audit attributes used as GSI keys; DynamoDB rejects them as NULL, so they are omitted when unset
+AUDIT_INDEX_KEYS = ("eventType", "userId", "requestId")
+
 def put_audit_log(log: Dict[str, Any]) -> bool:
     """Store an audit log entry"""
     if USE_MEMORY:
@@ -684,25 +1339,85 @@ def put_audit_log(log: Dict[str, Any]) -> bool:
         return True
     
     try:
-        T_AUDIT_LOGS.put_item(Item=log)
+        with_retry(lambda: T_AUDIT_LOGS.put_item(Item={k: v for k, v in log.items() if v is not None or k not in AUDIT_INDEX_KEYS}))
         return True
     except Exception as e:
         print(f"Error storing audit log: {e}")
         return False
 
 
+# Heads of the audit hash chains (entry_hash and sequence of each chain's
+# newest entry), one per chainId. They live in the audit table under their
+# own partition, so they never show up in AUDIT#ALL queries or the GSIs.
+def audit_chain_head_key(chain_id: str) -> Dict[str, str]:
+    return {"pk": "AUDIT#CHAIN", "sk": f"HEAD#{chain_id}"}
+
+def get_audit_chain_head(chain_id: str) -> Optional[Dict[str, Any]]:
+    """A chain's head ({"entry_hash", "sequence"}), or None before its first entry"""
+    if USE_MEMORY:
+        head = _audit_chain_head.get(chain_id)
+        return dict(head) if head else None
+    resp = with_retry(lambda: T_AUDIT_LOGS.get_item(Key=audit_chain_head_key(chain_id), ConsistentRead=True))
+    item = resp.get("Item")
+    return {k: v for k, v in _from_decimal(item).items() if k not in ("pk", "sk")} if item else None
+
+def append_audit_log(log: Dict[str, Any], expected_prev_hash: Optional[str]) -> None:
+    """
+    Store a chained audit entry and advance its chain's head (log["chainId"]) to it, atomically.
+
+    Raises:
+        ConditionFailedError: if the head is no longer expected_prev_hash
+            (another writer appended first); re-read the head and retry
+    """
+    head = {"entry_hash": log["entry_hash"], "sequence": log["sequence"]}
+    if USE_MEMORY:
+        if _audit_chain_head.get(log["chainId"], {}).get("entry_hash") != expected_prev_hash:
+            raise ConditionFailedError("audit chain head moved")
+        _audit_logs.insert(0, log)
+        if len(_audit_logs) > 10000:
+            _audit_logs.pop()
+        _audit_chain_head[log["chainId"]] = head
+        return
+    
+    item = _to_decimal({k: v for k, v in log.items() if v is not None or k not in AUDIT_INDEX_KEYS})
+    names = {"#hash": "entry_hash", "#seq": "sequence"}
+    values = {":hash": head["entry_hash"], ":seq": head["sequence"]}
+    if expected_prev_hash is None:
+        head_condition = "attribute_not_exists(#pk)"
+        names["#pk"] = "pk"
+    else:
+        head_condition = "#hash = :prev"
+        values[":prev"] = expected_prev_hash
+    try:
+        with_retry(lambda: ddb.meta.client.transact_write_items(TransactItems=[
+            {"Put": {"TableName": T_AUDIT_LOGS.name, "Item": item,
+                     "ConditionExpression": "attribute_not_exists(#pk)", "ExpressionAttributeNames": {"#pk": "pk"}}},
+            {"Update": {"TableName": T_AUDIT_LOGS.name, "Key": audit_chain_head_key(log["chainId"]),
+                        "UpdateExpression": "SET #hash = :hash, #seq = :seq",
+                        "ConditionExpression": head_condition,
+                        "ExpressionAttributeNames": names, "ExpressionAttributeValues": values}},
+        ]))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "TransactionCanceledException":
+            raise ConditionFailedError("audit chain head moved")
+        raise
+
+
 def get_audit_logs(
     event_type: Optional[str] = None,
     user_id: Optional[str] = None,
     severity: Optional[str] = None,
     start_time: Optional[str] = None,
     end_time: Optional[str] = None,
+    request_id: Optional[str] = None,
     limit: int = 100,
     next_token: Optional[str] = None
-) -> Tuple[List[Dict[str, Any]], Optional[str]]:
+) -> PaginatedResult[Dict[str, Any]]:
     """Query audit logs with optional filters"""
     if USE_MEMORY:
-        items = _audit_logs.copy()
+        items = _scoped(_audit_logs)
+        if request_id:
+            items = [i for i in items if i.get("requestId") == request_id]
         if event_type:
             items = [i for i in items if i.get("eventType") == event_type]
         if user_id:
@@ -713,11 +1428,23 @@ def get_audit_logs(
             items = [i for i in items if i.get("sk", "") >= start_time]
         if end_time:
             items = [i for i in items if i.get("sk", "") <= end_time]
-        return items[:limit], None
+        return PaginatedResult(items[:limit], total=len(items), has_more=len(items) > limit)
     
     try:
         # Use GSI based on filter
-        if event_type:
+        if request_id:
+            key_condition = Key("requestId").eq(request_id)
+            if start_time and end_time:
+                key_condition = key_condition & Key("sk").between(start_time, end_time)
+            elif start_time:
+                key_condition = key_condition & Key("sk").gte(start_time)
+            
+            params = {
+                "IndexName": "requestId-index",
+                "KeyConditionExpression": key_condition,
+                "ScanIndexForward": False,
+            }
+        elif event_type:
             key_condition = Key("eventType").eq(event_type)
             if start_time and end_time:
                 key_condition = key_condition & Key("sk").between(start_time, end_time)
@@ -728,7 +1455,6 @@ def get_audit_logs(
                 "IndexName": "eventType-index",
                 "KeyConditionExpression": key_condition,
                 "ScanIndexForward": False,
-                "Limit": limit
             }
         elif user_id:
             key_condition = Key("userId").eq(user_id)
@@ -741,9 +1467,15 @@ def get_audit_logs(
                 "IndexName": "userId-index",
                 "KeyConditionExpression": key_condition,
                 "ScanIndexForward": False,
-                "Limit": limit
             }
-        else:
+        elif start_time and end_time and start_time[:10] == end_time[:10]:
+            # Range within one day: that day's date-index partition
+            params = {
+                "IndexName": "date-index",
+                "KeyConditionExpression": Key("date").eq(start_time[:10]) & Key("timestamp").between(start_time, end_time),
+                "ScanIndexForward": False,
+            }
+        else:
             # Scan all logs (use partition key ALL for all logs)
             key_condition = Key("pk").eq("AUDIT#ALL")
             if start_time and end_time:
@@ -754,20 +1486,13 @@ def get_audit_logs(
             params = {
                 "KeyConditionExpression": key_condition,
                 "ScanIndexForward": False,
-                "Limit": limit
             }
         
-        if next_token:
-            import json
-            import base64
-            params["ExclusiveStartKey"] = json.loads(base64.b64decode(next_token).decode())
-        
         # Add severity filter if specified
         if severity:
             params["FilterExpression"] = Attr("severity").eq(severity)
         
-        resp = T_AUDIT_LOGS.query(**params)
-        items = resp.get("Items", [])
+        items, last_key = _filtered_page(T_AUDIT_LOGS.query, limit, decode_cursor(next_token), **_with_organization_filter(params))
         
         # Convert Decimals
         for item in items:
@@ -775,20 +1500,87 @@ def get_audit_logs(
                 if isinstance(v, Decimal):
                     item[k] = int(v) if v % 1 == 0 else float(v)
         
-        # Encode next token
-        last_key = resp.get("LastEvaluatedKey")
-        token = None
-        if last_key:
-            import json
-            import base64
-            token = base64.b64encode(json.dumps(last_key).encode()).decode()
-        
-        return items, token
+        return PaginatedResult.from_dynamo(items, last_key)
     except Exception as e:
         print(f"Error querying audit logs: {e}")
-        return [], None
+        return PaginatedResult.empty()
+
 
 
+def get_audit_logs_by_date(
+    day: str,
+    start_time: Optional[str] = None,
+    end_time: Optional[str] = None,
+    limit: int = 100,
+    next_token: Optional[str] = None
+) -> PaginatedResult[Dict[str, Any]]:
+    """
+    Audit logs of one UTC day (YYYY-MM-DD), newest first, optionally narrowed
+    to ISO timestamps within that day. Queries the date-index GSI.
+    """
+    if USE_MEMORY:
+        items = [i for i in _scoped(_audit_logs) if i.get("date") == day]
+        if start_time:
+            items = [i for i in items if i.get("timestamp", "") >= start_time]
+        if end_time:
+            items = [i for i in items if i.get("timestamp", "") <= end_time]
+        items.sort(key=lambda i: i.get("timestamp", ""), reverse=True)
+        return PaginatedResult(items[:limit], total=len(items), has_more=len(items) > limit)
+
+    key_condition = Key("date").eq(day)
+    if start_time and end_time:
+        key_condition = key_condition & Key("timestamp").between(start_time, end_time)
+    elif start_time:
+        key_condition = key_condition & Key("timestamp").gte(start_time)
+    elif end_time:
+        key_condition = key_condition & Key("timestamp").lte(end_time)
+    params = {"IndexName": "date-index", "KeyConditionExpression": key_condition, "ScanIndexForward": False}
+    items, last_key = _filtered_page(T_AUDIT_LOGS.query, limit, decode_cursor(next_token), **_with_organization_filter(params))
+    return PaginatedResult.from_dynamo([_from_decimal(i) for i in items], last_key)
+
+
+def iter_audit_log_pages_by_date(day: str, start_time: Optional[str] = None, end_time: Optional[str] = None):
+    """
+    Yield the audit logs of one UTC day (YYYY-MM-DD) one date-index page at a
+    time, oldest first, optionally narrowed to ISO timestamps within that day
+    (inclusive), so callers can stream a busy day without holding it in memory.
+    """
+    if USE_MEMORY:
+        items = [i for i in _scoped(_audit_logs) if i.get("date") == day
+                 and (not start_time or i.get("timestamp", "") >= start_time)
+                 and (not end_time or i.get("timestamp", "") <= end_time)]
+        yield sorted(items, key=lambda i: i.get("timestamp", ""))
+        return
+
+    key_condition = Key("date").eq(day)
+    if start_time and end_time:
+        key_condition = key_condition & Key("timestamp").between(start_time, end_time)
+    elif start_time:
+        key_condition = key_condition & Key("timestamp").gte(start_time)
+    elif end_time:
+        key_condition = key_condition & Key("timestamp").lte(end_time)
+    params = _with_organization_filter({"IndexName": "date-index", "KeyConditionExpression": key_condition, "ScanIndexForward": True})
+    while True:
+        resp = with_retry(lambda: T_AUDIT_LOGS.query(**params))
+        items = [_from_decimal(i) for i in resp.get("Items", [])]
+        if items:
+            yield items
+        if "LastEvaluatedKey" not in resp:
+            return
+        params["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+
+def get_audit_logs_by_request_id(request_id: str) -> List[Dict[str, Any]]:
+    """All audit logs written while handling one request, oldest first"""
+    if USE_MEMORY:
+        return sorted((i for i in _scoped(_audit_logs) if i.get("requestId") == request_id), key=lambda i: i.get("sk", ""))
+    
+    return [_from_decimal(i) for i in _query_all(T_AUDIT_LOGS, **_with_organization_filter({
+        "IndexName": "requestId-index",
+        "KeyConditionExpression": Key("requestId").eq(request_id),
+        "ScanIndexForward": True,
+    }))]
+
 # ============== System Settings ==============
 
 def get_system_setting(key: str) -> Optional[Dict[str, Any]]:
@@ -830,12 +1622,12 @@ def put_system_setting(key: str, value: Any, updated_by: str) -> bool:
         return True
     
     try:
-        T_SYSTEM_SETTINGS.put_item(Item={
+        with_retry(lambda: T_SYSTEM_SETTINGS.put_item(Item={
             "settingKey": key,
             "value": value,
             "updatedAt": datetime.now(timezone.utc).isoformat(),
             "updatedBy": updated_by
-        })
+        }))
         return True
     except Exception as e:
         print(f"Error updating system setting: {e}")
@@ -861,18 +1653,18 @@ def create_conversation(conversation_id: str, participants: List[str], created_b
     
     # For DynamoDB, we store conversation metadata with messageId = "METADATA"
     try:
-        T_MESSAGES.put_item(Item={
+        with_retry(lambda: T_MESSAGES.put_item(Item={
             **conversation,
             "messageId": "METADATA",
             "participantId": participants[0]  # Primary participant for indexing
-        })
+        }))
         # Also create index entries for other participants
         for pid in participants[1:]:
-            T_MESSAGES.put_item(Item={
+            with_retry(lambda: T_MESSAGES.put_item(Item={
                 **conversation,
                 "messageId": "METADATA",
                 "participantId": pid
-            })
+            }))
         return conversation
     except Exception as e:
         print(f"Error creating conversation: {e}")
@@ -914,10 +1706,10 @@ def send_message(conversation_id: str, sender_id: str, content: str, message_typ
         return message
     
     try:
-        T_MESSAGES.put_item(Item={
+        with_retry(lambda: T_MESSAGES.put_item(Item={
             **message,
             "participantId": sender_id  # For indexing
-        })
+        }))
         return message
     except Exception as e:
         print(f"Error sending message: {e}")
@@ -959,13 +1751,14 @@ def create_symptom_record(patient_id: str, record: Dict[str, Any]) -> Dict[str,
         "createdAt": datetime.now(timezone.utc).isoformat(),
         **record
     }
+    _stamp_organization(symptom)
     
     if USE_MEMORY:
         _symptoms.append(symptom)
         return symptom
     
     try:
-        T_SYMPTOMS.put_item(Item=symptom)
+        with_retry(lambda: T_SYMPTOMS.put_item(Item=symptom))
         return symptom
     except Exception as e:
         print(f"Error creating symptom record: {e}")
@@ -975,17 +1768,16 @@ def create_symptom_record(patient_id: str, record: Dict[str, Any]) -> Dict[str,
 def get_symptom_records(patient_id: str, limit: int = 50) -> List[Dict[str, Any]]:
     """Get symptom records for a patient"""
     if USE_MEMORY:
-        items = [s for s in _symptoms if s.get("patientId") == patient_id]
+        items = [s for s in _scoped(_symptoms) if s.get("patientId") == patient_id]
         items.sort(key=lambda x: x.get("createdAt", ""), reverse=True)
         return items[:limit]
     
     try:
-        resp = T_SYMPTOMS.query(
-            KeyConditionExpression=Key("patientId").eq(patient_id),
-            ScanIndexForward=False,
-            Limit=limit
-        )
-        return resp.get("Items", [])
+        items, _ = _filtered_page(T_SYMPTOMS.query, limit, **_with_organization_filter({
+            "KeyConditionExpression": Key("patientId").eq(patient_id),
+            "ScanIndexForward": False,
+        }))
+        return items
     except Exception as e:
         print(f"Error getting symptom records: {e}")
         return []
@@ -999,31 +1791,244 @@ def delete_symptom_record(patient_id: str, record_id: str) -> bool:
         return True
     
     try:
-        T_SYMPTOMS.delete_item(Key={"patientId": patient_id, "recordId": record_id})
+        with_retry(lambda: T_SYMPTOMS.delete_item(Key={"patientId": patient_id, "recordId": record_id}))
         return True
     except Exception as e:
         print(f"Error deleting symptom record: {e}")
         return False
 
 
+# ============== Medications ==============
+
+def create_medication(patient_id: str, medication: Dict[str, Any]) -> Dict[str, Any]:
+    """Add a medication to a patient"""
+    now = datetime.now(timezone.utc).isoformat()
+    item = {
+        **medication,
+        "patientId": patient_id,
+        "medicationId": f"MED-{secrets.token_hex(6).upper()}",
+        "isActive": True,
+        "createdAt": now,
+        "updatedAt": now
+    }
+    _stamp_organization(item)
+    
+    if USE_MEMORY:
+        _medications.append(item)
+        return item
+    
+    with_retry(lambda: T_MEDICATIONS.put_item(Item={k: v for k, v in item.items() if v is not None}))
+    return item
+
+
+def get_medications(patient_id: str, active_only: bool = False) -> List[Dict[str, Any]]:
+    """Get a patient's medications, oldest first"""
+    if USE_MEMORY:
+        items = [m for m in _scoped(_medications) if m.get("patientId") == patient_id]
+    else:
+        try:
+            items = _query_all(T_MEDICATIONS, **_with_organization_filter({
+                "KeyConditionExpression": Key("patientId").eq(patient_id)
+            }))
+        except Exception as e:
+            print(f"Error getting medications: {e}")
+            return []
+    
+    if active_only:
+        items = [m for m in items if m.get("isActive", True)]
+    return sorted(items, key=lambda m: m.get("createdAt", ""))
+
+
+def get_medication(patient_id: str, medication_id: str) -> Optional[Dict[str, Any]]:
+    """Get a single medication"""
+    if USE_MEMORY:
+        for m in _medications:
+            if m.get("patientId") == patient_id and m.get("medicationId") == medication_id:
+                return _in_scope(m)
+        return None
+    
+    try:
+        resp = T_MEDICATIONS.get_item(Key={"patientId": patient_id, "medicationId": medication_id})
+        return _in_scope(resp.get("Item"))
+    except Exception as e:
+        print(f"Error getting medication: {e}")
+        return None
+
+
+def update_medication(patient_id: str, medication_id: str, updates: Dict[str, Any]) -> Optional[Dict[str, Any]]:
+    """Update medication fields (None removes a field); returns the updated item"""
+    updates = {**updates, "updatedAt": datetime.now(timezone.utc).isoformat()}
+    builder = DynamoUpdateBuilder().set_all(updates)
+    
+    if USE_MEMORY:
+        medication = get_medication(patient_id, medication_id)
+        if not medication:
+            return None
+        builder.apply_to(medication)
+        return medication
+    
+    try:
+        resp = with_retry(lambda: T_MEDICATIONS.update_item(
+            Key={"patientId": patient_id, "medicationId": medication_id},
+            ConditionExpression=_organization_condition(Attr("medicationId").exists()),
+            ReturnValues="ALL_NEW",
+            **builder.build()
+        ))
+        return resp.get("Attributes")
+    except Exception as e:
+        print(f"Error updating medication: {e}")
+        return None
+
+
+# ============== Consents ==============
+
+def put_consent(record: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Store a consent record. Records are append-only; the sort key
+    consentType#grantedAt#id keeps each type's history in time order.
+    """
+    item = _stamp_organization({**record, "consentKey": f"{record['consentType']}#{record['grantedAt']}#{record['id']}"})
+    if USE_MEMORY:
+        _consents.append(item)
+        return item
+    with_retry(lambda: T_CONSENTS.put_item(Item={k: v for k, v in item.items() if v is not None}))
+    return item
+
+
+def get_consents(patient_id: str, consent_type: Optional[str] = None) -> List[Dict[str, Any]]:
+    """Consent records of a patient (optionally of one type), newest first"""
+    if USE_MEMORY:
+        items = [c for c in _scoped(_consents) if c.get("patientId") == patient_id
+                 and (consent_type is None or c.get("consentType") == consent_type)]
+    else:
+        condition = Key(CONSENTS_PK_ATTR).eq(patient_id)
+        if consent_type is not None:
+            condition = condition & Key(CONSENTS_SK_ATTR).begins_with(f"{consent_type}#")
+        items = _query_all(T_CONSENTS, **_with_organization_filter({"KeyConditionExpression": condition}))
+    return sorted((_from_decimal(i) for i in items), key=lambda c: c.get("grantedAt", ""), reverse=True)
+
+
+# ============== Report Schedules ==============
+
+# Only active schedules carry dueStatus, so the due-index is sparse
+SCHEDULE_DUE_STATUS = "ACTIVE"
+
+def _schedule_item(schedule: Dict[str, Any]) -> Dict[str, Any]:
+    item = {k: v for k, v in schedule.items() if k != "dueStatus"}
+    if item.get("isActive"):
+        item["dueStatus"] = SCHEDULE_DUE_STATUS
+    return item
+
+def _schedule_out(item: Dict[str, Any]) -> Dict[str, Any]:
+    return {k: v for k, v in _from_decimal(item).items() if k != "dueStatus"}
+
+def put_report_schedule(schedule: Dict[str, Any]) -> Dict[str, Any]:
+    """Create or replace a report schedule"""
+    item = _stamp_organization(_schedule_item(schedule))
+    if USE_MEMORY:
+        _report_schedules[item["id"]] = item
+    else:
+        with_retry(lambda: T_REPORT_SCHEDULES.put_item(Item=_to_decimal({k: v for k, v in item.items() if v is not None})))
+    return _schedule_out(item)
+
+def get_report_schedule(schedule_id: str) -> Optional[Dict[str, Any]]:
+    if USE_MEMORY:
+        item = _report_schedules.get(schedule_id)
+    else:
+        item = T_REPORT_SCHEDULES.get_item(Key={REPORT_SCHEDULES_PK_ATTR: schedule_id}).get("Item")
+    item = _in_scope(item)
+    return _schedule_out(item) if item else None
+
+def list_report_schedules(created_by: Optional[str] = None) -> List[Dict[str, Any]]:
+    """Schedules created by a user (or all schedules), oldest first"""
+    if USE_MEMORY:
+        items = [s for s in _scoped(_report_schedules.values()) if created_by is None or s.get("createdBy") == created_by]
+    elif created_by is not None:
+        items = _query_all(T_REPORT_SCHEDULES, **_with_organization_filter({
+            "IndexName": "createdBy-index",
+            "KeyConditionExpression": Key("createdBy").eq(created_by),
+        }))
+    else:
+        items = _scan_all(T_REPORT_SCHEDULES, **_with_organization_filter({}))
+    return sorted((_schedule_out(i) for i in items), key=lambda s: s.get("createdAt", ""))
+
+def get_due_report_schedules(now_iso: str) -> List[Dict[str, Any]]:
+    """Active schedules whose nextRunAt <= now_iso, earliest first"""
+    if USE_MEMORY:
+        items = [s for s in _report_schedules.values()
+                 if s.get("dueStatus") == SCHEDULE_DUE_STATUS and s.get("nextRunAt", "") <= now_iso]
+    else:
+        items = []
+        query_kwargs = {
+            "IndexName": "due-index",
+            "KeyConditionExpression": Key("dueStatus").eq(SCHEDULE_DUE_STATUS) & Key("nextRunAt").lte(now_iso),
+        }
+        while True:
+            resp = T_REPORT_SCHEDULES.query(**query_kwargs)
+            items.extend(resp.get("Items", []))
+            if "LastEvaluatedKey" not in resp:
+                break
+            query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+    return sorted((_schedule_out(i) for i in items), key=lambda s: s.get("nextRunAt", ""))
+
+def update_report_schedule_run(schedule_id: str, last_run_at: str, next_run_at: str) -> None:
+    """Record a run and move the schedule to its next run time"""
+    if USE_MEMORY:
+        if schedule_id in _report_schedules:
+            _report_schedules[schedule_id].update({"lastRunAt": last_run_at, "nextRunAt": next_run_at})
+        return
+    with_retry(lambda: T_REPORT_SCHEDULES.update_item(
+        Key={REPORT_SCHEDULES_PK_ATTR: schedule_id},
+        UpdateExpression="SET lastRunAt = :last, nextRunAt = :next",
+        ExpressionAttributeValues={":last": last_run_at, ":next": next_run_at}
+    ))
+
+def delete_report_schedule(schedule_id: str) -> bool:
+    if USE_MEMORY:
+        return _report_schedules.pop(schedule_id, None) is not None
+    resp = with_retry(lambda: T_REPORT_SCHEDULES.delete_item(Key={REPORT_SCHEDULES_PK_ATTR: schedule_id}, ReturnValues="ALL_OLD"))
+    return "Attributes" in resp
+
+
 # ============== Reports ==============
 
+def _report_expiry_iso(value: datetime) -> str:
+    """The format expiresAt is stored and compared in: UTC, to the second, with a Z suffix"""
+    if value.tzinfo is None:
+        value = value.replace(tzinfo=timezone.utc)
+    return value.astimezone(timezone.utc).isoformat(timespec="seconds").replace("+00:00", "Z")
+
+def _with_expires_on(fields: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Normalize expiresAt and add expiresOn (its UTC day), the partition key of
+    the expiresAt-index GSI. The index is range-queried on expiresAt as a
+    string, so every value must have the same format.
+    """
+    try:
+        expiry = datetime.fromisoformat(str(fields["expiresAt"]).replace("Z", "+00:00"))
+    except (KeyError, ValueError):
+        return fields
+    if expiry.tzinfo is None:
+        expiry = expiry.replace(tzinfo=timezone.utc)
+    return {**fields, "expiresAt": _report_expiry_iso(expiry),
+            "expiresOn": expiry.astimezone(timezone.utc).date().isoformat()}
+
 def create_report(report: Dict[str, Any]) -> Dict[str, Any]:
     """Create a new report"""
     report_id = f"RPT-{secrets.token_hex(6).upper()}"
-    report_data = {
+    report_data = _with_expires_on(_stamp_organization({
         "reportId": report_id,
         "createdAt": datetime.now(timezone.utc).isoformat(),
         "status": "pending",
         **report
-    }
+    }))
     
     if USE_MEMORY:
         _reports.append(report_data)
         return report_data
     
     try:
-        T_REPORTS.put_item(Item=report_data)
+        with_retry(lambda: T_REPORTS.put_item(Item=report_data))
         return report_data
     except Exception as e:
         print(f"Error creating report: {e}")
@@ -1037,7 +2042,7 @@ def get_reports(
 ) -> List[Dict[str, Any]]:
     """Get reports with optional filters"""
     if USE_MEMORY:
-        items = _reports.copy()
+        items = _scoped(_reports)
         if patient_id:
             items = [r for r in items if r.get("patientId") == patient_id]
         if author_id:
@@ -1047,49 +2052,112 @@ def get_reports(
     
     try:
         if patient_id:
-            resp = T_REPORTS.query(
-                IndexName="patientId-index",
-                KeyConditionExpression=Key("patientId").eq(patient_id),
-                ScanIndexForward=False,
-                Limit=limit
-            )
+            items, _ = _filtered_page(T_REPORTS.query, limit, **_with_organization_filter({
+                "IndexName": "patientId-index",
+                "KeyConditionExpression": Key("patientId").eq(patient_id),
+                "ScanIndexForward": False,
+            }))
         elif author_id:
-            resp = T_REPORTS.query(
-                IndexName="authorId-index",
-                KeyConditionExpression=Key("authorId").eq(author_id),
-                ScanIndexForward=False,
-                Limit=limit
-            )
+            items, _ = _filtered_page(T_REPORTS.query, limit, **_with_organization_filter({
+                "IndexName": "authorId-index",
+                "KeyConditionExpression": Key("authorId").eq(author_id),
+                "ScanIndexForward": False,
+            }))
         else:
-            resp = T_REPORTS.scan(Limit=limit)
+            items, _ = _filtered_page(T_REPORTS.scan, limit, **_with_organization_filter({}))
         
-        return resp.get("Items", [])
+        return items
     except Exception as e:
         print(f"Error getting reports: {e}")
         return []
 
 
+def _report_access_filter(patient_id: str, requester_id: str, requester_role: str):
+    """
+    FilterExpression limiting a patient's reports to what the requester may see
+    (None: everything). Applied in DynamoDB so hidden reports never leave the table.
+    - Patient: reports not marked visibleToPatient=false
+    - Doctor: every report of an assigned patient, otherwise only reports they wrote
+    - Admin: everything
+    """
+    if requester_role == "admin":
+        return None
+    if requester_role == "doctor":
+        profile = get_patient_profile(patient_id)
+        if profile and profile.get("doctorId") == requester_id:
+            return None
+        return Attr("authorId").eq(requester_id)
+    return Attr("visibleToPatient").not_exists() | Attr("visibleToPatient").eq(True)
+
+def report_accessible_by(report: Dict[str, Any], requester_id: str, requester_role: str) -> bool:
+    """Whether the requester may see a report (same rules as _report_access_filter)"""
+    if requester_role == "admin":
+        return True
+    if requester_role == "doctor":
+        profile = get_patient_profile(report.get("patientId"))
+        return bool(profile and profile.get("doctorId") == requester_id) or report.get("authorId") == requester_id
+    return report.get("patientId") == requester_id and report.get("visibleToPatient", True) is not False
+
+def list_reports_for_patient(
+    patient_id: str,
+    requester_id: str,
+    requester_role: str,
+    limit: int = 50,
+    cursor: Optional[str] = None
+) -> PaginatedResult[Dict[str, Any]]:
+    """
+    One page of a patient's reports visible to the requester, newest first.
+    
+    Raises:
+        ValueError: if cursor is not a valid cursor
+    """
+    if USE_MEMORY:
+        items = [r for r in _scoped(_reports) if r.get("patientId") == patient_id
+                 and report_accessible_by(r, requester_id, requester_role)]
+        items.sort(key=lambda r: r.get("createdAt", ""), reverse=True)
+        start = 0
+        if cursor:
+            last = decode_cursor(cursor)
+            start = next((i + 1 for i, r in enumerate(items) if r["reportId"] == last.get("reportId")), len(items))
+        page = items[start:start + limit]
+        more = start + limit < len(items)
+        return PaginatedResult.with_cursor(page, encode_cursor({"reportId": page[-1]["reportId"]}) if more and page else None)
+    
+    query_kwargs = {
+        "IndexName": "patientId-index",
+        "KeyConditionExpression": Key("patientId").eq(patient_id),
+        "ScanIndexForward": False,
+    }
+    access_filter = _report_access_filter(patient_id, requester_id, requester_role)
+    if access_filter is not None:
+        query_kwargs["FilterExpression"] = access_filter
+    items, start_key = _filtered_page(T_REPORTS.query, limit, decode_cursor(cursor), **_with_organization_filter(query_kwargs))
+    return PaginatedResult.from_dynamo([_from_decimal(i) for i in items], start_key)
+
+
 def get_report(report_id: str) -> Optional[Dict[str, Any]]:
     """Get a single report by ID"""
     if USE_MEMORY:
         for r in _reports:
             if r.get("reportId") == report_id:
-                return r
+                return _in_scope(r)
         return None
     
     try:
         resp = T_REPORTS.get_item(Key={"reportId": report_id})
-        return resp.get("Item")
+        return _in_scope(resp.get("Item"))
     except Exception as e:
         print(f"Error getting report: {e}")
         return None
 
 
 def update_report(report_id: str, updates: Dict[str, Any]) -> Optional[Dict[str, Any]]:
-    """Update a report"""
+    """Update a report of the caller's organization; None when there is none with the id"""
+    if updates.get("expiresAt"):
+        updates = _with_expires_on(updates)
     if USE_MEMORY:
         for i, r in enumerate(_reports):
-            if r.get("reportId") == report_id:
+            if r.get("reportId") == report_id and _in_scope(r):
                 _reports[i].update(updates)
                 _reports[i]["updatedAt"] = datetime.now(timezone.utc).isoformat()
                 return _reports[i]
@@ -1105,13 +2173,14 @@ def update_report(report_id: str, updates: Dict[str, Any]) -> Optional[Dict[str,
         expr_values = {f":{k}": v for k, v in updates.items()}
         expr_values[":updatedAt"] = datetime.now(timezone.utc).isoformat()
         
-        resp = T_REPORTS.update_item(
+        resp = with_retry(lambda: T_REPORTS.update_item(
             Key={"reportId": report_id},
             UpdateExpression=update_expr,
+            ConditionExpression=_organization_condition(Attr("reportId").exists()),
             ExpressionAttributeNames=expr_names,
             ExpressionAttributeValues=expr_values,
             ReturnValues="ALL_NEW"
-        )
+        ))
         return resp.get("Attributes")
     except Exception as e:
         print(f"Error updating report: {e}")
@@ -1119,22 +2188,188 @@ def update_report(report_id: str, updates: Dict[str, Any]) -> Optional[Dict[str,
 
 
 def delete_report(report_id: str) -> bool:
-    """Delete a report"""
+    """Delete a report of the caller's organization; False when there is none with the id"""
     if USE_MEMORY:
         global _reports
-        _reports = [r for r in _reports if r.get("reportId") != report_id]
-        return True
+        before = len(_reports)
+        _reports = [r for r in _reports if r.get("reportId") != report_id or not _in_scope(r)]
+        return len(_reports) < before
     
     try:
-        T_REPORTS.delete_item(Key={"reportId": report_id})
+        with_retry(lambda: T_REPORTS.delete_item(
+            Key={"reportId": report_id},
+            ConditionExpression=_organization_condition(Attr("reportId").exists())
+        ))
         return True
     except Exception as e:
         print(f"Error deleting report: {e}")
         return False
 
 
+def scan_expired_reports(now_iso: str, limit: int = 100, cursor: Optional[str] = None) -> PaginatedResult[Dict[str, Any]]:
+    """
+    One page of reports whose expiresAt <= now_iso (ISO strings compare
+    chronologically when both are UTC). Items deleted while paging do not
+    affect the cursor, so cleanup can delete as it goes.
+    
+    Raises:
+        ValueError: if cursor is not a valid cursor
+    """
+    try:
+        now_iso = _report_expiry_iso(datetime.fromisoformat(now_iso.replace("Z", "+00:00")))
+    except ValueError:
+        pass
+    if USE_MEMORY:
+        items = sorted((r for r in _reports if r.get("expiresAt") and r["expiresAt"] <= now_iso),
+                       key=lambda r: r["reportId"])
+        if cursor:
+            last = decode_cursor(cursor).get("reportId", "")
+            items = [r for r in items if r["reportId"] > last]
+        page = items[:limit]
+        more = len(items) > limit
+        return PaginatedResult.with_cursor(page, encode_cursor({"reportId": page[-1]["reportId"]}) if more else None)
+    
+    scan_kwargs = {
+        "FilterExpression": Attr("expiresAt").exists() & Attr("expiresAt").lte(now_iso),
+    }
+    start_key = decode_cursor(cursor)
+    items: List[Dict[str, Any]] = []
+    while True:
+        if start_key:
+            scan_kwargs["ExclusiveStartKey"] = start_key
+        resp = with_retry(lambda: T_REPORTS.scan(Limit=limit - len(items), **scan_kwargs))
+        items.extend(_from_decimal(i) for i in resp.get("Items", []))
+        start_key = resp.get("LastEvaluatedKey")
+        if not start_key or len(items) >= limit:
+            break
+    return PaginatedResult.from_dynamo(items, start_key)
+
+
+def get_reports_expiring_soon(within_hours: int, now: Optional[datetime] = None) -> List[Dict[str, Any]]:
+    """
+    Reports whose expiresAt falls between now and now + within_hours, soonest
+    first. Queries the expiresAt-index GSI (expiresOn, expiresAt) once per day
+    of the window with a BETWEEN condition.
+    """
+    now = now or datetime.now(timezone.utc)
+    end = now + timedelta(hours=within_hours)
+    start_iso, end_iso = _report_expiry_iso(now), _report_expiry_iso(end)
+    if USE_MEMORY:
+        items = [dict(r) for r in _reports if r.get("expiresAt") and start_iso <= r["expiresAt"] <= end_iso]
+        return sorted(items, key=lambda r: r["expiresAt"])
+
+    items = []
+    day = now.date()
+    while day <= end.date():
+        query_kwargs = {
+            "IndexName": "expiresAt-index",
+            "KeyConditionExpression": Key("expiresOn").eq(day.isoformat()) & Key("expiresAt").between(start_iso, end_iso),
+        }
+        while True:
+            resp = with_retry(lambda: T_REPORTS.query(**query_kwargs))
+            items.extend(_from_decimal(i) for i in resp.get("Items", []))
+            if "LastEvaluatedKey" not in resp:
+                break
+            query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+        day += timedelta(days=1)
+    return items
+
+
+def backfill_report_expires_on() -> int:
+    """
+    Normalize expiresAt and add expiresOn on reports stored without it
+    (written before the expiresAt-index existed), so that
+    get_reports_expiring_soon finds them. Returns the number updated.
+    """
+    if USE_MEMORY:
+        updated = 0
+        for report in _reports:
+            if report.get("expiresAt") and "expiresOn" not in report:
+                fields = _with_expires_on({"expiresAt": report["expiresAt"]})
+                if "expiresOn" in fields:
+                    report.update(fields)
+                    updated += 1
+        return updated
+
+    scan_kwargs = {
+        "FilterExpression": Attr("expiresAt").exists() & Attr("expiresOn").not_exists(),
+        "ProjectionExpression": "reportId, expiresAt",
+    }
+    updated = 0
+    while True:
+        resp = with_retry(lambda: T_REPORTS.scan(**scan_kwargs))
+        for item in resp.get("Items", []):
+            fields = _with_expires_on({"expiresAt": item["expiresAt"]})
+            if "expiresOn" not in fields:
+                print(f"[Reports] Cannot index report {item['reportId']}: invalid expiresAt {item['expiresAt']!r}")
+                continue
+            try:
+                with_retry(lambda: T_REPORTS.update_item(
+                    Key={"reportId": item["reportId"]},
+                    UpdateExpression="SET expiresAt = :at, expiresOn = :on",
+                    # Skip reports whose expiry changed (or that were deleted) since the scan
+                    ConditionExpression=Attr("expiresAt").eq(item["expiresAt"]),
+                    ExpressionAttributeValues={":at": fields["expiresAt"], ":on": fields["expiresOn"]},
+                ))
+                updated += 1
+            except ClientError as e:
+                if e.response.get("Error", {}).get("Code") != "ConditionalCheckFailedException":
+                    raise
+        if "LastEvaluatedKey" not in resp:
+            return updated
+        scan_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+
+def get_all_reports() -> List[Dict[str, Any]]:
+    """Get every report (admin statistics)"""
+    if USE_MEMORY:
+        return _scoped(_reports)
+    try:
+        return [_from_decimal(r) for r in _scan_all(T_REPORTS, **_with_organization_filter({}))]
+    except Exception as e:
+        print(f"Error scanning reports: {e}")
+        return []
+
+
 # ============== Admin Dashboard Stats ==============
 
+def get_all_users() -> List[Dict[str, Any]]:
+    """Get every user record of the caller's organization (admin statistics)"""
+    if USE_MEMORY:
+        return _scoped(_users.values())
+    try:
+        return _scan_all(T_USERS, **_with_organization_filter({"FilterExpression": _not_email_guard()}))
+    except Exception as e:
+        print(f"Error scanning users: {e}")
+        return []
+
+def get_reading_timestamps_since(start_time: int, patient_ids: Optional[Set[str]] = None) -> List[int]:
+    """Unix timestamps of all readings (of the given patients, None: everyone's) recorded at or after start_time"""
+    if USE_MEMORY:
+        return [t["timestamp"] for t in _tremor_analysis if t.get("timestamp", 0) >= start_time
+                and (patient_ids is None or t.get("patient_id") in patient_ids)]
+    start_iso = datetime.fromtimestamp(start_time, timezone.utc).isoformat().replace("+00:00", "Z")
+    try:
+        items = _scan_all(
+            T_TREMOR_ANALYSIS,
+            FilterExpression=Attr(TREMOR_SK_ATTR).gte(start_iso),
+            ProjectionExpression="#pk, #ts",
+            ExpressionAttributeNames={"#pk": TREMOR_PK_ATTR, "#ts": TREMOR_SK_ATTR}
+        )
+        timestamps = []
+        for item in items:
+            # Readings carry no organizationId; they belong to the patient they are keyed by
+            if patient_ids is not None and item.get(TREMOR_PK_ATTR) not in patient_ids:
+                continue
+            _normalize_tremor_item(item)
+            if isinstance(item.get("timestamp"), int):
+                timestamps.append(item["timestamp"])
+        return timestamps
+    except Exception as e:
+        print(f"Error scanning reading timestamps: {e}")
+        return []
+
+
 def get_dashboard_stats() -> Dict[str, Any]:
     """Get dashboard statistics for admin"""
     if USE_MEMORY:
@@ -1151,7 +2386,7 @@ def get_dashboard_stats() -> Dict[str, Any]:
     
     try:
         # Count users by role
-        users_resp = T_USERS.scan(Select="COUNT")
+        users_resp = T_USERS.scan(Select="COUNT", FilterExpression=_not_email_guard())
         total_users = users_resp.get("Count", 0)
         
         # Count devices
@@ -1176,3 +2411,534 @@ def get_dashboard_stats() -> Dict[str, Any]:
     except Exception as e:
         print(f"Error getting dashboard stats: {e}")
         return {}
+
+
+# ============== Data Purge (Right to Erasure) ==============
+
+def _purge_by_partition(table, pk_attr: str, sk_attr: Optional[str], pk_value: str, index_name: Optional[str] = None, index_attr: Optional[str] = None) -> int:
+    """
+    Hard-delete every item in a partition (or GSI partition) that belongs to
+    the caller's organization. Queries only the key attributes, then deletes
+    via BatchWriteItem. Deleting already-missing items is a no-op, so retries are safe.
+    """
+    key_attrs = [pk_attr] + ([sk_attr] if sk_attr else [])
+    names = {f"#k{i}": a for i, a in enumerate(key_attrs)}
+    query_kwargs = _with_organization_filter({
+        "KeyConditionExpression": Key(index_attr or pk_attr).eq(pk_value),
+        "ProjectionExpression": ", ".join(names.keys()),
+        "ExpressionAttributeNames": names,
+    })
+    if index_name:
+        query_kwargs["IndexName"] = index_name
+
+    keys = []
+    while True:
+        resp = table.query(**query_kwargs)
+        keys.extend({a: item[a] for a in key_attrs} for item in resp.get("Items", []))
+        if "LastEvaluatedKey" not in resp:
+            break
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+    with table.batch_writer() as batch:
+        for key in keys:
+            batch.delete_item(Key=key)
+    return len(keys)
+
+def purge_patient_readings(patient_id: str) -> int:
+    """
+    Delete all tremor analysis readings under the patient's partition.
+    Readings carry no organizationId: callers check the patient is in their organization.
+    """
+    if USE_MEMORY:
+        before = len(_tremor_analysis)
+        _tremor_analysis[:] = [t for t in _tremor_analysis if t.get("patient_id") != patient_id]
+        return before - len(_tremor_analysis)
+    return _purge_by_partition(T_TREMOR_ANALYSIS, TREMOR_PK_ATTR, TREMOR_SK_ATTR, patient_id)
+
+def purge_patient_reports(patient_id: str) -> int:
+    """Delete all reports about a patient"""
+    if USE_MEMORY:
+        before = len(_reports)
+        _reports[:] = [r for r in _reports if r.get("patientId") != patient_id or not _in_scope(r)]
+        return before - len(_reports)
+    return _purge_by_partition(T_REPORTS, "reportId", None, patient_id,
+                               index_name="patientId-index", index_attr="patientId")
+
+def purge_patient_symptoms(patient_id: str) -> int:
+    """Delete all symptom records of a patient"""
+    if USE_MEMORY:
+        before = len(_symptoms)
+        _symptoms[:] = [r for r in _symptoms if r.get("patientId") != patient_id or not _in_scope(r)]
+        return before - len(_symptoms)
+    return _purge_by_partition(T_SYMPTOMS, "patientId", "recordId", patient_id)
+
+def purge_patient_poses(patient_id: str) -> int:
+    """Delete all pose records of a patient"""
+    if USE_MEMORY:
+        before = len(_poses)
+        _poses[:] = [p for p in _poses if p.get("patientId") != patient_id or not _in_scope(p)]
+        return before - len(_poses)
+    pk_value = _pose_pk(patient_id) if POSES_SINGLE_TABLE else patient_id
+    return _purge_by_partition(T_POSES, POSES_PK_ATTR, POSES_SK_ATTR, pk_value)
+
+def purge_patient_medications(patient_id: str) -> int:
+    """Delete all medication records of a patient"""
+    if USE_MEMORY:
+        before = len(_medications)
+        _medications[:] = [m for m in _medications if m.get("patientId") != patient_id or not _in_scope(m)]
+        return before - len(_medications)
+    return _purge_by_partition(T_MEDICATIONS, "patientId", "medicationId", patient_id)
+
+def set_device_assignment(
+    device_id: str,
+    patient_id: Optional[str],
+    expected_patient_id: Optional[str],
+    history_entry: Dict[str, Any],
+    add_to_profile: Optional[str] = None,
+    remove_from_profile: Optional[str] = None
+) -> None:
+    """
+    Atomically (TransactWriteItems) set a device's patientId, append to its
+    assignmentHistory and add/remove the device in the patients' profile
+    assignedDevices sets.
+
+    The write only succeeds if the device's current patientId is still
+    expected_patient_id (None: unassigned) and the named profiles exist;
+    otherwise ConditionFailedError is raised and nothing is changed.
+    """
+    now = history_entry["at"]
+    if USE_MEMORY:
+        device = next((d for d in _devices if d["id"] == device_id), None)
+        profiles = [p for p in (add_to_profile, remove_from_profile) if p]
+        if (device is None or device.get("patientId") != expected_patient_id
+                or any(p not in _patient_profiles for p in profiles)):
+            raise ConditionFailedError(f"Assignment of device {device_id} changed concurrently")
+        device.update({"patientId": patient_id, "updatedAt": now})
+        device.setdefault("assignmentHistory", []).append(history_entry)
+        if add_to_profile:
+            assigned = _patient_profiles[add_to_profile].setdefault("assignedDevices", [])
+            if device_id not in assigned:
+                assigned.append(device_id)
+        if remove_from_profile:
+            assigned = _patient_profiles[remove_from_profile].get("assignedDevices", [])
+            _patient_profiles[remove_from_profile]["assignedDevices"] = [d for d in assigned if d != device_id]
+        return
+
+    if expected_patient_id is None:
+        device_condition = "attribute_exists(id) AND (attribute_not_exists(patientId) OR patientId = :null)"
+        device_condition_values = {":null": None}
+    else:
+        device_condition = "patientId = :expected"
+        device_condition_values = {":expected": expected_patient_id}
+    items = [{"Update": {
+        "TableName": T_DEVICES.name,
+        "Key": {"id": device_id},
+        "UpdateExpression": "SET patientId = :pid, updatedAt = :now, "
+                            "assignmentHistory = list_append(if_not_exists(assignmentHistory, :empty), :entry)",
+        "ConditionExpression": device_condition,
+        "ExpressionAttributeValues": {
+            ":pid": patient_id, ":now": now, ":empty": [], ":entry": [history_entry], **device_condition_values
+        },
+    }}]
+    # assignedDevices is a string set so ADD/DELETE need no list index
+    for profile_id, action in ((add_to_profile, "ADD"), (remove_from_profile, "DELETE")):
+        if profile_id:
+            items.append({"Update": {
+                "TableName": T_PATIENT_PROFILES.name,
+                "Key": {"userId": profile_id},
+                "UpdateExpression": f"{action} assignedDevices :device",
+                "ConditionExpression": "attribute_exists(userId)",
+                "ExpressionAttributeValues": {":device": {device_id}},
+            }})
+    try:
+        with_retry(lambda: ddb.meta.client.transact_write_items(TransactItems=items))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "TransactionCanceledException":
+            raise ConditionFailedError(f"Assignment of device {device_id} changed concurrently")
+        raise
+
+def unbind_patient_devices(patient_id: str) -> int:
+    """Remove the patient association from every device bound to them"""
+    devices = get_devices_by_patient(patient_id)
+    for device in devices:
+        update_device(device["id"], {"patientId": None, "updatedAt": datetime.now(timezone.utc).isoformat()})
+    return len(devices)
+
+# TransactWriteItems accepts at most 100 actions; two are the user and profile updates
+MAX_DEACTIVATION_DEVICES = 98
+
+def deactivate_patient(patient_id: str, device_ids: List[str], deleted_by: str, has_profile: bool = False) -> None:
+    """
+    Mark a patient inactive and unassign their devices in one transaction.
+    Each device update is conditional on it still belonging to the patient;
+    with has_profile the devices also leave the profile's assignedDevices.
+
+    Raises:
+        ConditionFailedError: if a device was reassigned concurrently
+    """
+    now = datetime.now(timezone.utc).isoformat()
+    if USE_MEMORY:
+        user = _users.get(patient_id)
+        if user:
+            user.update({"isActive": False, "deletedAt": now, "deletedBy": deleted_by})
+        for device in _devices:
+            if device["id"] in device_ids and device.get("patientId") == patient_id:
+                device["patientId"] = None
+                device["updatedAt"] = now
+        if has_profile and patient_id in _patient_profiles:
+            assigned = _patient_profiles[patient_id].get("assignedDevices", [])
+            _patient_profiles[patient_id]["assignedDevices"] = [d for d in assigned if d not in device_ids]
+        return
+
+    if len(device_ids) > MAX_DEACTIVATION_DEVICES:
+        raise ValueError(f"Cannot unassign more than {MAX_DEACTIVATION_DEVICES} devices in one transaction")
+    items = [{"Update": {
+        "TableName": T_USERS.name,
+        "Key": _user_key(patient_id),
+        "UpdateExpression": "SET isActive = :false, deletedAt = :now, deletedBy = :by",
+        "ExpressionAttributeValues": {":false": False, ":now": now, ":by": deleted_by},
+    }}]
+    for device_id in device_ids:
+        items.append({"Update": {
+            "TableName": T_DEVICES.name,
+            "Key": {"id": device_id},
+            "UpdateExpression": "REMOVE patientId SET updatedAt = :now",
+            "ConditionExpression": "patientId = :pid",
+            "ExpressionAttributeValues": {":pid": patient_id, ":now": now},
+        }})
+    if has_profile and device_ids:
+        items.append({"Update": {
+            "TableName": T_PATIENT_PROFILES.name,
+            "Key": {"userId": patient_id},
+            "UpdateExpression": "DELETE assignedDevices :devices",
+            "ConditionExpression": "attribute_exists(userId)",
+            "ExpressionAttributeValues": {":devices": set(device_ids)},
+        }})
+    try:
+        with_retry(lambda: ddb.meta.client.transact_write_items(TransactItems=items))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "TransactionCanceledException":
+            raise ConditionFailedError(f"Devices of patient {patient_id} changed concurrently")
+        raise
+    _invalidate_user_cache(patient_id)
+
+def flag_patient_readings(patient_id: str) -> int:
+    """
+    Soft-delete the patient's tremor analysis readings (is_flagged = true).
+    Flagging an already-flagged reading is a no-op, so retries are safe.
+    """
+    flagged_at = datetime.now(timezone.utc).isoformat()
+    if USE_MEMORY:
+        readings = [t for t in _tremor_analysis if t.get("patient_id") == patient_id]
+        for reading in readings:
+            reading.setdefault("flaggedAt", flagged_at)
+            reading["is_flagged"] = True
+        return len(readings)
+
+    query_kwargs = {
+        "KeyConditionExpression": Key(TREMOR_PK_ATTR).eq(patient_id),
+        "ProjectionExpression": "#pk, #sk",
+        "ExpressionAttributeNames": {"#pk": TREMOR_PK_ATTR, "#sk": TREMOR_SK_ATTR},
+    }
+    flagged = 0
+    while True:
+        resp = T_TREMOR_ANALYSIS.query(**query_kwargs)
+        for item in resp.get("Items", []):
+            key = {TREMOR_PK_ATTR: item[TREMOR_PK_ATTR], TREMOR_SK_ATTR: item[TREMOR_SK_ATTR]}
+            with_retry(lambda: T_TREMOR_ANALYSIS.update_item(
+                Key=key,
+                UpdateExpression="SET is_flagged = :true, flaggedAt = if_not_exists(flaggedAt, :now)",
+                ExpressionAttributeValues={":true": True, ":now": flagged_at},
+            ))
+            flagged += 1
+        if "LastEvaluatedKey" not in resp:
+            break
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+    return flagged
+
+# -------- Device request signing keys
+def put_device_api_key(hardware_id: str, device_id: str, secret: str) -> Dict[str, Any]:
+    """Register (or rotate) the pre-shared signing secret of a device's hardware id"""
+    item = {
+        "hardwareId": hardware_id,
+        "deviceId": device_id,
+        "secret": secret,
+        "issuedAt": datetime.now(timezone.utc).isoformat(),
+    }
+    if USE_MEMORY:
+        _device_api_keys[hardware_id] = item
+        return dict(item)
+    with_retry(lambda: T_DEVICE_API_KEYS.put_item(Item=item))
+    return dict(item)
+
+def get_device_api_key(hardware_id: str) -> Optional[Dict[str, Any]]:
+    if USE_MEMORY:
+        item = _device_api_keys.get(hardware_id)
+        return dict(item) if item else None
+    return T_DEVICE_API_KEYS.get_item(Key={"hardwareId": hardware_id}, ConsistentRead=True).get("Item")
+
+def delete_device_api_key(hardware_id: str) -> None:
+    if USE_MEMORY:
+        _device_api_keys.pop(hardware_id, None)
+        return
+    with_retry(lambda: T_DEVICE_API_KEYS.delete_item(Key={"hardwareId": hardware_id}))
+
+# -------- Push notification tokens
+def put_push_token(user_id: str, token: str, platform: str, device_name: Optional[str] = None) -> Dict[str, Any]:
+    """
+    Register a mobile device token for a user. Registering a token the user
+    already has refreshes it and keeps its tokenId.
+    """
+    existing = next((t for t in get_push_tokens_for_user(user_id) if t["token"] == token), None)
+    now = datetime.now(timezone.utc).isoformat()
+    item = {
+        "tokenId": existing["tokenId"] if existing else f"ptk_{secrets.token_hex(8)}",
+        "userId": user_id,
+        "token": token,
+        "platform": platform,
+        "deviceName": device_name,
+        "createdAt": existing["createdAt"] if existing else now,
+        "updatedAt": now,
+    }
+    item = {k: v for k, v in item.items() if v is not None}
+    if USE_MEMORY:
+        _push_tokens[item["tokenId"]] = item
+        return dict(item)
+    with_retry(lambda: T_PUSH_TOKENS.put_item(Item=item))
+    return dict(item)
+
+def get_push_tokens_for_user(user_id: str) -> List[Dict[str, Any]]:
+    """A user's registered device tokens, oldest first"""
+    if USE_MEMORY:
+        items = [dict(t) for t in _push_tokens.values() if t["userId"] == user_id]
+    else:
+        query_kwargs = {"IndexName": "userId-index", "KeyConditionExpression": Key("userId").eq(user_id)}
+        items = []
+        while True:
+            resp = T_PUSH_TOKENS.query(**query_kwargs)
+            items.extend(resp.get("Items", []))
+            if "LastEvaluatedKey" not in resp:
+                break
+            query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+    return sorted(items, key=lambda t: t.get("createdAt", ""))
+
+def delete_push_token(user_id: str, token_id: str) -> bool:
+    """Remove one of a user's tokens; False if the user has no token with that id"""
+    if USE_MEMORY:
+        item = _push_tokens.get(token_id)
+        if not item or item["userId"] != user_id:
+            return False
+        del _push_tokens[token_id]
+        return True
+    try:
+        with_retry(lambda: T_PUSH_TOKENS.delete_item(
+            Key={"tokenId": token_id},
+            ConditionExpression=Attr("userId").eq(user_id)
+        ))
+        return True
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            return False
+        raise
+
+# -------- Idempotency keys
+def claim_idempotency_key(key: str, fingerprint: str, expires_at: int, locked_until: int) -> Optional[Dict[str,Any]]:
+    """
+    Atomically claim an idempotency key (conditional put). An in-progress
+    claim whose lease (lockedUntil) has run out, e.g. because its Lambda
+    timed out before completing or releasing it, can be claimed again.
+
+    Returns:
+        None if the key was claimed by this call, otherwise the existing record
+    """
+    now = int(time.time())
+    record = {"key": key, "status": "in_progress", "fingerprint": fingerprint,
+              "createdAt": now, "expiresAt": expires_at, "lockedUntil": locked_until}
+    if USE_MEMORY:
+        existing = _idempotency.get(key)
+        if existing and existing["expiresAt"] > now and (
+            existing["status"] == "completed" or existing.get("lockedUntil", expires_at) > now
+        ):
+            return dict(existing)
+        _idempotency[key] = record
+        return None
+    try:
+        # Expired records may linger until the TTL sweeper removes them
+        with_retry(lambda: T_IDEMPOTENCY.put_item(
+            Item=record,
+            ConditionExpression=(
+                "attribute_not_exists(#k) OR expiresAt <= :now"
+                " OR (#s = :in_progress AND lockedUntil <= :now)"
+            ),
+            ExpressionAttributeNames={"#k": "key", "#s": "status"},
+            ExpressionAttributeValues={":now": now, ":in_progress": "in_progress"},
+        ))
+        return None
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") != "ConditionalCheckFailedException":
+            raise
+    item = T_IDEMPOTENCY.get_item(Key={"key": key}, ConsistentRead=True).get("Item")
+    return _from_decimal(item) if item else None
+
+def complete_idempotency_key(key: str, result: str, locked_until: int) -> bool:
+    """
+    Store the serialized result of the operation that claimed the key.
+
+    Returns:
+        False if the claim (identified by its lockedUntil) was lost to another request
+    """
+    if USE_MEMORY:
+        record = _idempotency.get(key)
+        if not record or record["status"] != "in_progress" or record.get("lockedUntil") != locked_until:
+            return False
+        record.update({"status": "completed", "result": result})
+        record.pop("lockedUntil", None)
+        return True
+    try:
+        with_retry(lambda: T_IDEMPOTENCY.update_item(
+            Key={"key": key},
+            UpdateExpression="SET #s = :s, #r = :r REMOVE lockedUntil",
+            ConditionExpression="#s = :in_progress AND lockedUntil = :locked",
+            ExpressionAttributeNames={"#s": "status", "#r": "result"},
+            ExpressionAttributeValues={":s": "completed", ":r": result,
+                                       ":in_progress": "in_progress", ":locked": locked_until},
+        ))
+        return True
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            return False
+        raise
+
+def release_idempotency_key(key: str, locked_until: int) -> None:
+    """Drop a claim whose operation failed so the client can retry; a claim already taken over is kept"""
+    if USE_MEMORY:
+        record = _idempotency.get(key)
+        if record and record["status"] == "in_progress" and record.get("lockedUntil") == locked_until:
+            del _idempotency[key]
+        return
+    try:
+        with_retry(lambda: T_IDEMPOTENCY.delete_item(
+            Key={"key": key},
+            ConditionExpression="#s = :in_progress AND lockedUntil = :locked",
+            ExpressionAttributeNames={"#s": "status"},
+            ExpressionAttributeValues={":in_progress": "in_progress", ":locked": locked_until},
+        ))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") != "ConditionalCheckFailedException":
+            raise
+
+# ============== Organizations ==============
+
+class OrganizationExistsError(ConditionFailedError):
+    """Raised by create_organization when the id or subdomain is taken."""
+
+def _organization_key(organization_id: str) -> Dict[str, str]:
+    return {"pk": f"ORG#{organization_id}", "sk": "ORGANIZATION"}
+
+def create_organization(org: Dict[str, Any]) -> None:
+    """
+    Insert an organization.
+
+    Raises:
+        OrganizationExistsError: if the id or subdomain is already used
+    """
+    if USE_MEMORY:
+        if org["id"] in _organizations or any(o["subdomain"] == org["subdomain"] for o in _organizations.values()):
+            raise OrganizationExistsError(org["subdomain"])
+        _organizations[org["id"]] = org
+        return
+    if get_organization_by_subdomain(org["subdomain"]):
+        raise OrganizationExistsError(org["subdomain"])
+    try:
+        with_retry(lambda: T_ORGANIZATIONS.put_item(
+            Item={**org, **_organization_key(org["id"])},
+            ConditionExpression="attribute_not_exists(pk)"
+        ))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            raise OrganizationExistsError(org["id"])
+        raise
+
+def get_organization(organization_id: str) -> Optional[Dict[str, Any]]:
+    if USE_MEMORY:
+        return _organizations.get(organization_id)
+    item = T_ORGANIZATIONS.get_item(Key=_organization_key(organization_id)).get("Item")
+    return {k: v for k, v in item.items() if k not in ("pk", "sk")} if item else None
+
+def get_organization_by_subdomain(subdomain: str) -> Optional[Dict[str, Any]]:
+    if USE_MEMORY:
+        return next((o for o in _organizations.values() if o["subdomain"] == subdomain), None)
+    items = T_ORGANIZATIONS.query(
+        IndexName="subdomain-index",
+        KeyConditionExpression=Key("subdomain").eq(subdomain),
+        Limit=1
+    ).get("Items", [])
+    return {k: v for k, v in items[0].items() if k not in ("pk", "sk")} if items else None
+
+def get_users_by_organization(organization_id: str) -> List[Dict[str, Any]]:
+    """Every user of an organization (regardless of the caller's scope)"""
+    if USE_MEMORY:
+        return [u for u in _users.values() if organization_of(u) == organization_id]
+    if organization_id == DEFAULT_ORGANIZATION_ID:
+        # Users created before multi-tenancy have no organizationId and are not in the index
+        condition = Attr("organizationId").eq(organization_id) | Attr("organizationId").not_exists()
+        return _scan_all(T_USERS, FilterExpression=condition & _not_email_guard())
+    query_kwargs = {
+        "IndexName": "organizationId-index",
+        "KeyConditionExpression": Key("organizationId").eq(organization_id),
+    }
+    items = []
+    while True:
+        resp = T_USERS.query(**query_kwargs)
+        items.extend(resp.get("Items", []))
+        if "LastEvaluatedKey" not in resp:
+            return items
+        query_kwargs["ExclusiveStartKey"] = resp["LastEvaluatedKey"]
+
+# ============== Custom Roles ==============
+
+class RoleExistsError(ConditionFailedError):
+    """Raised by create_role when the name is taken."""
+
+# Roles are keyed by (organizationId, name): each organization defines its own
+
+def _role_organization() -> str:
+    return current_organization_id() or DEFAULT_ORGANIZATION_ID
+
+def create_role(role: Dict[str, Any]) -> None:
+    """
+    Insert a custom role definition ({"name", "permissions", ...}) into the
+    caller's organization.
+
+    Raises:
+        RoleExistsError: if the organization has a role with the name
+    """
+    role = {**role, "organizationId": _role_organization()}
+    if USE_MEMORY:
+        key = (role["organizationId"], role["name"])
+        if key in _roles:
+            raise RoleExistsError(role["name"])
+        _roles[key] = role
+        return
+    try:
+        with_retry(lambda: T_ROLES.put_item(Item=role, ConditionExpression="attribute_not_exists(#n)",
+                                            ExpressionAttributeNames={"#n": "name"}))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") == "ConditionalCheckFailedException":
+            raise RoleExistsError(role["name"])
+        raise
+
+def get_role(name: str) -> Optional[Dict[str, Any]]:
+    """A custom role of the caller's organization"""
+    if USE_MEMORY:
+        role = _roles.get((_role_organization(), name))
+        return dict(role) if role else None
+    return T_ROLES.get_item(Key={"organizationId": _role_organization(), "name": name}).get("Item")
+
+def list_roles() -> List[Dict[str, Any]]:
+    """Every custom role of the caller's organization, by name"""
+    if USE_MEMORY:
+        roles = [dict(r) for r in _roles.values() if r["organizationId"] == _role_organization()]
+    else:
+        roles = _query_all(T_ROLES, KeyConditionExpression=Key("organizationId").eq(_role_organization()))
+    return sorted(roles, key=lambda r: r["name"])
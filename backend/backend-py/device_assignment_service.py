@@ -0,0 +1,161 @@
+"""
+MeDUSA Device Assignment Service
+
+Assigns devices to patients, keeping the device's patientId and the
+patient profile's assignedDevices in step.
+
+Key Features:
+- Device and profile updated in one DynamoDB transaction (both or neither)
+- A device assigned to another patient is only moved with force=True
+- Every assignment change is appended to the device's assignmentHistory
+- DEVICE_BIND / DEVICE_UNBIND audit events (a forced move emits both)
+"""
+
+from datetime import datetime, timezone
+from typing import Any, Dict, List, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+from dynamo_update import ConditionFailedError
+
+
+class DeviceAssignmentError(Exception):
+    """Base class for assignment failures."""
+
+
+class DeviceNotFoundError(DeviceAssignmentError):
+    def __init__(self, device_id: str):
+        super().__init__(f"Device {device_id} not found")
+        self.device_id = device_id
+
+
+class PatientNotFoundError(DeviceAssignmentError):
+    def __init__(self, patient_id: str):
+        super().__init__(f"Patient {patient_id} not found")
+        self.patient_id = patient_id
+
+
+class DeviceAlreadyAssignedError(DeviceAssignmentError):
+    """The device belongs to another patient and force was not set."""
+
+    def __init__(self, device_id: str, current_patient_id: str):
+        super().__init__(f"Device {device_id} is already assigned to another patient")
+        self.device_id = device_id
+        self.current_patient_id = current_patient_id
+
+
+class AssignmentConflictError(DeviceAssignmentError):
+    """The device's assignment changed between read and write."""
+
+
+def _history_entry(action: str, patient_id: str, user_id: str, previous_patient_id: Optional[str] = None) -> Dict[str, Any]:
+    entry = {
+        "action": action,
+        "patientId": patient_id,
+        "by": user_id,
+        "at": datetime.now(timezone.utc).isoformat(),
+    }
+    if previous_patient_id:
+        entry["previousPatientId"] = previous_patient_id
+    return entry
+
+
+def _profile_id(patient_id: Optional[str]) -> Optional[str]:
+    """Patients get a profile once a doctor takes them on; before that only the device side is kept."""
+    return patient_id if patient_id and db.get_patient_profile(patient_id) else None
+
+
+def _write(device_id: str, patient_id: Optional[str], expected: Optional[str], entry: Dict[str, Any],
+           add_to: Optional[str], remove_from: Optional[str]) -> None:
+    try:
+        db.set_device_assignment(device_id, patient_id, expected, entry,
+                                 add_to_profile=_profile_id(add_to), remove_from_profile=_profile_id(remove_from))
+    except ConditionFailedError as e:
+        raise AssignmentConflictError(str(e))
+
+
+def assign_device_to_patient(
+    device_id: str,
+    patient_id: str,
+    user_id: str,
+    user_role: str,
+    force: bool = False
+) -> Dict[str, Any]:
+    """
+    Assign a device to a patient (no-op if it already is).
+
+    Raises:
+        DeviceNotFoundError, PatientNotFoundError
+        DeviceAlreadyAssignedError: assigned to another patient and not force
+        AssignmentConflictError: the assignment changed concurrently
+    """
+    device = db.get_device(device_id)
+    if not device:
+        raise DeviceNotFoundError(device_id)
+    patient = db.get_user(patient_id)
+    if not patient or patient.get("role") != "patient":
+        raise PatientNotFoundError(patient_id)
+
+    current = device.get("patientId")
+    if current == patient_id:
+        return device
+    if current and not force:
+        raise DeviceAlreadyAssignedError(device_id, current)
+
+    entry = _history_entry("reassign" if current else "assign", patient_id, user_id, current)
+    _write(device_id, patient_id, current, entry, add_to=patient_id, remove_from=current)
+
+    if current:
+        audit_service.log_device_event(
+            event_type=AuditEventType.DEVICE_UNBIND,
+            user_id=user_id,
+            user_role=user_role,
+            device_id=device_id,
+            patient_id=current,
+            action="unassign",
+            details={"reassignedTo": patient_id, "forced": True}
+        )
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_BIND,
+        user_id=user_id,
+        user_role=user_role,
+        device_id=device_id,
+        patient_id=patient_id,
+        action="assign",
+        details={"previousPatientId": current} if current else None
+    )
+    return db.get_device(device_id)
+
+
+def unassign_device(device_id: str, user_id: str, user_role: str) -> Dict[str, Any]:
+    """
+    Remove a device from its patient (no-op if unassigned).
+
+    Raises:
+        DeviceNotFoundError, AssignmentConflictError
+    """
+    device = db.get_device(device_id)
+    if not device:
+        raise DeviceNotFoundError(device_id)
+    current = device.get("patientId")
+    if not current:
+        return device
+
+    _write(device_id, None, current, _history_entry("unassign", current, user_id), add_to=None, remove_from=current)
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_UNBIND,
+        user_id=user_id,
+        user_role=user_role,
+        device_id=device_id,
+        patient_id=current,
+        action="unassign"
+    )
+    return db.get_device(device_id)
+
+
+def get_assignment_history(device_id: str) -> List[Dict[str, Any]]:
+    """Assignment changes of a device, oldest first."""
+    device = db.get_device(device_id)
+    if not device:
+        raise DeviceNotFoundError(device_id)
+    return device.get("assignmentHistory", [])
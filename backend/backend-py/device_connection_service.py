@@ -0,0 +1,164 @@
+"""
+MeDUSA Device Connection Service
+
+Tracks how a device is connected (bluetooth, wifi or usb), its signal
+strength and when it last reported.
+
+Key Features:
+- Connection info stored on the device item ("connection" attribute)
+- DEVICE_CONNECTED / DEVICE_DISCONNECTED audit events on state changes only
+- Devices silent for longer than DEVICE_STALE_SECONDS count as disconnected
+  ("stale") even without an explicit disconnect
+- A report from a stale device is a reconnect and is audited again
+- Reports may carry the battery level (batteryLevel / batteryUpdatedAt);
+  recovering above LOW_BATTERY_THRESHOLD_PERCENT re-arms the low-battery alert
+"""
+
+import os
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+
+CONNECTION_TYPES = ["bluetooth", "wifi", "usb"]
+DEVICE_STALE_SECONDS = int(os.environ.get("DEVICE_STALE_SECONDS", "300"))
+LOW_BATTERY_THRESHOLD_PERCENT = float(os.environ.get("LOW_BATTERY_THRESHOLD_PERCENT", "20.0"))
+
+STATE_CONNECTED = "connected"
+STATE_STALE = "stale"
+STATE_DISCONNECTED = "disconnected"
+
+
+class DeviceConnectionError(ValueError):
+    """Raised when a connection report is invalid."""
+
+
+def _parse_iso(value: str) -> datetime:
+    parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+
+
+def get_connection_state(
+    device: Dict[str, Any],
+    now: Optional[datetime] = None,
+    stale_seconds: int = DEVICE_STALE_SECONDS
+) -> Dict[str, Any]:
+    """
+    Current connection state of a device item.
+
+    Returns:
+        {"deviceId", "state", "connected", "connectionType", "signalStrength",
+         "lastConnectedAt", "lastReportedAt", "disconnectedAt"}
+    """
+    info = device.get("connection") or {}
+    state = STATE_DISCONNECTED
+    if info.get("connected"):
+        last_reported = _parse_iso(info["lastReportedAt"])
+        now = now or datetime.now(timezone.utc)
+        state = STATE_CONNECTED if now - last_reported <= timedelta(seconds=stale_seconds) else STATE_STALE
+    return {
+        "deviceId": device["id"],
+        "state": state,
+        "connected": state == STATE_CONNECTED,
+        "connectionType": info.get("connectionType"),
+        "signalStrength": info.get("signalStrength"),
+        "lastConnectedAt": info.get("lastConnectedAt"),
+        "lastReportedAt": info.get("lastReportedAt"),
+        "disconnectedAt": info.get("disconnectedAt"),
+    }
+
+
+def record_connection(
+    device: Dict[str, Any],
+    connection_type: str,
+    user_id: str,
+    user_role: str,
+    signal_strength: Optional[int] = None,
+    now: Optional[datetime] = None,
+    battery_level: Optional[int] = None
+) -> Dict[str, Any]:
+    """
+    Record that a device is connected (also used for periodic reports).
+
+    A device that was disconnected or stale, or that switched connection
+    type, emits DEVICE_CONNECTED; repeated reports only refresh the info.
+
+    Returns:
+        The new connection state
+
+    Raises:
+        DeviceConnectionError: for an unknown connection type
+    """
+    if connection_type not in CONNECTION_TYPES:
+        raise DeviceConnectionError(f"connectionType must be one of {', '.join(CONNECTION_TYPES)}")
+    now = now or datetime.now(timezone.utc)
+    previous = get_connection_state(device, now)
+    is_new = previous["state"] != STATE_CONNECTED or previous["connectionType"] != connection_type
+
+    info = {
+        "connected": True,
+        "connectionType": connection_type,
+        "signalStrength": signal_strength,
+        "lastConnectedAt": now.isoformat() if is_new else previous["lastConnectedAt"],
+        "lastReportedAt": now.isoformat(),
+        "disconnectedAt": None,
+    }
+    updates = {"connection": info, "status": "online", "lastSeen": now.isoformat()}
+    if battery_level is not None:
+        updates["batteryLevel"] = battery_level
+        updates["batteryUpdatedAt"] = now.isoformat()
+        if battery_level >= LOW_BATTERY_THRESHOLD_PERCENT and device.get("lowBatteryAlertedAt"):
+            updates["lowBatteryAlertedAt"] = None
+    device = {**device, **updates}
+    db.update_device(device["id"], updates)
+
+    if is_new:
+        audit_service.log_device_event(
+            event_type=AuditEventType.DEVICE_CONNECTED,
+            user_id=user_id,
+            user_role=user_role,
+            device_id=device["id"],
+            patient_id=device.get("patientId"),
+            action="connect",
+            details={"connectionType": connection_type, "signalStrength": signal_strength,
+                     "previousState": previous["state"]}
+        )
+    return get_connection_state(device, now)
+
+
+def record_disconnection(
+    device: Dict[str, Any],
+    user_id: str,
+    user_role: str,
+    reason: str = "explicit",
+    now: Optional[datetime] = None
+) -> Dict[str, Any]:
+    """
+    Record that a device disconnected. Disconnecting an already
+    disconnected device changes nothing and is not audited.
+
+    Returns:
+        The new connection state
+    """
+    now = now or datetime.now(timezone.utc)
+    info = device.get("connection") or {}
+    if not info.get("connected"):
+        return get_connection_state(device, now)
+
+    previous = get_connection_state(device, now)
+    info = {**info, "connected": False, "disconnectedAt": now.isoformat()}
+    device = {**device, "connection": info, "status": "offline"}
+    db.update_device(device["id"], {"connection": info, "status": "offline"})
+
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_DISCONNECTED,
+        user_id=user_id,
+        user_role=user_role,
+        device_id=device["id"],
+        patient_id=device.get("patientId"),
+        action="disconnect",
+        details={"connectionType": info.get("connectionType"), "reason": reason,
+                 "previousState": previous["state"], "lastReportedAt": info.get("lastReportedAt")}
+    )
+    return get_connection_state(device, now)
@@ -0,0 +1,425 @@
+"""
+MeDUSA Device Data Ingestion
+
+Two Lambda handlers that decouple device upload latency from storage:
+
+- ingest:  API Gateway handler. Authenticates the device by API key or by an
+           HMAC request signature (request_signing), accepts a batch of readings, enqueues it on the readings SQS FIFO queue and
+           returns 202 immediately.
+- process: SQS handler. Validates each reading, batch-writes valid readings to
+           the sensor data table and emits ReadingsIngested / ReadingsFlagged
+           metrics. Failed messages are reported back to SQS and end up in the
+           dead-letter queue after the queue's maxReceiveCount.
+
+Key Features:
+- Per-device ordering (MessageGroupId = device id)
+- Partial batch failures so one bad message does not retry the whole batch
+- Readings failing validate_reading are dropped and counted, never stored
+- Stored readings crossing alert thresholds are published via notification_service;
+  critical ones are pushed to the patient's doctor's mobile devices
+- Readings that are statistical outliers for their device (IQR) are stored with
+  is_flagged=true and sent as critical alerts
+- Readings of a device overdue for calibration are down-scored; readings below
+  the quality threshold are stored with low_quality=true
+- ECG waveform samples are offloaded to S3 at ingest (storage.upload_device_data);
+  queue messages and sensor items only carry the summary metrics and waveform_key
+"""
+
+import os
+import hmac
+import json
+import time
+import uuid
+import hashlib
+from typing import Any, Dict, List, Optional, Tuple
+
+import aws_clients
+import db
+import storage
+from request_signing import verify_device_request, is_signed_request, event_body, DeviceSignatureError
+from cors import add_cors_headers, handle_options, request_origin
+from security_headers import add_security_headers
+from idempotency_service import idempotent, IdempotencyError
+from reading_validation import (
+    validate_reading, validate_location, validate_waveform, ReadingValidationError, CANONICAL_UNITS
+)
+from notification_service import notification_service, classify_reading, Alert, AlertSeverity
+from analytics_service import anomaly_detector, score_reading_quality, ANOMALY_WINDOW
+from calibration_service import is_calibration_due
+from metrics_service import MetricsEmitter
+
+READINGS_QUEUE_URL = os.environ.get("READINGS_QUEUE_URL")
+METRICS_NAMESPACE = os.environ.get("INGEST_METRICS_NAMESPACE", "MeDUSA/Ingestion")
+
+MAX_READINGS_PER_REQUEST = 500
+# Checked before parsing; batches are larger than the API's MAX_BODY_BYTES allows
+MAX_INGEST_BODY_BYTES = int(os.environ.get("MAX_INGEST_BODY_BYTES", str(4 * 1024 * 1024)))
+READINGS_PER_MESSAGE = 100  # keeps each SQS message well below the 256 KB limit
+SQS_SEND_BATCH_SIZE = 10
+DEFAULT_READING_TYPE = "accelerometer"
+WAVEFORM_READING_TYPES = {"ecg"}
+
+_sqs = None
+
+
+def _sqs_client():
+    """Created lazily so importing this module (e.g. from main) needs no AWS region."""
+    global _sqs
+    if _sqs is None:
+        _sqs = aws_clients.get_client("sqs")
+    return _sqs
+
+
+def hash_device_api_key(api_key: str) -> str:
+    """Device API keys are stored as SHA-256 hashes on the device record."""
+    return hashlib.sha256(api_key.encode()).hexdigest()
+
+
+def authenticate_device(device_id: Optional[str], api_key: Optional[str]) -> Optional[Dict[str, Any]]:
+    """Return the device record if the API key matches, else None."""
+    if not device_id or not api_key:
+        return None
+    device = db.get_device(device_id)
+    stored = (device or {}).get("apiKeyHash")
+    if not stored or not hmac.compare_digest(stored, hash_device_api_key(api_key)):
+        return None
+    return device
+
+
+def authenticate_request(event: Dict[str, Any], headers: Dict[str, str]) -> Optional[Dict[str, Any]]:
+    """
+    The calling device: by HMAC signature when the request is signed,
+    otherwise by X-Device-Id / X-Api-Key.
+
+    Raises:
+        DeviceSignatureError: for a signed request that fails verification
+    """
+    if is_signed_request(headers):
+        return db.get_device(verify_device_request(event))
+    return authenticate_device(headers.get("x-device-id"), headers.get("x-api-key"))
+
+
+def _response(status: int, body: Dict[str, Any]) -> Dict[str, Any]:
+    return {
+        "statusCode": status,
+        "headers": {"Content-Type": "application/json"},
+        "body": json.dumps(body),
+    }
+
+
+def _error(status: int, code: str, message: str) -> Dict[str, Any]:
+    return _response(status, {"code": code, "message": message})
+
+
+def _chunks(items: List[Any], size: int) -> List[List[Any]]:
+    return [items[i:i + size] for i in range(0, len(items), size)]
+
+
+def enqueue_readings(device: Dict[str, Any], readings: List[Dict[str, Any]]) -> str:
+    """
+    Split a batch into SQS messages and send them in order.
+
+    Returns:
+        The batch id shared by all messages of this upload
+    """
+    batch_id = uuid.uuid4().hex
+    messages = []
+    for index, chunk in enumerate(_chunks(readings, READINGS_PER_MESSAGE)):
+        body = json.dumps({
+            "batchId": batch_id,
+            "deviceId": device["id"],
+            "patientId": device.get("patientId"),
+            "calibration": device.get("calibrationCorrection"),
+            "calibrationOverdue": is_calibration_due(device),
+            "receivedAt": int(time.time()),
+            "readings": chunk,
+        })
+        messages.append({
+            "Id": str(index),
+            "MessageBody": body,
+            "MessageGroupId": device["id"],
+            "MessageDeduplicationId": f"{batch_id}-{index}",
+        })
+
+    for entries in _chunks(messages, SQS_SEND_BATCH_SIZE):
+        resp = _sqs_client().send_message_batch(QueueUrl=READINGS_QUEUE_URL, Entries=entries)
+        if resp.get("Failed"):
+            raise RuntimeError(f"Failed to enqueue {len(resp['Failed'])} reading messages")
+    return batch_id
+
+
+def ingest(event, context):
+    """
+    POST /api/v1/devices/readings/ingest
+
+    Headers: X-Device-Id and X-Api-Key, or the request_signing headers
+             (X-Device-Hardware-Id, X-Device-Timestamp, X-Device-Signature);
+             and Idempotency-Key (required; retries return the original batchId)
+    Body: {"readings": [{"timestamp": 1735689600, "readingType": "accelerometer",
+                         "values": {"accel_x": 0.1, "accel_y": 0.2, "accel_z": 9.8}}]}
+          optional per reading: "unit" (e.g. "g"; defaults to the type's canonical unit);
+          "waveform" (ECG only: list of samples in mV, stored in S3)
+
+    Every response, including errors and OPTIONS preflight, carries the CORS and security headers.
+    """
+    origin = request_origin(event)
+    if event.get("httpMethod") == "OPTIONS":
+        return add_security_headers(handle_options(origin))
+    return add_security_headers(add_cors_headers(_ingest(event), origin))
+
+
+def _ingest(event) -> Dict[str, Any]:
+    headers = {k.lower(): v for k, v in (event.get("headers") or {}).items()}
+    try:
+        device = authenticate_request(event, headers)
+    except DeviceSignatureError as e:
+        return _error(401, e.code, e.message)
+    if not device:
+        return _error(401, "DEVICE_AUTH_INVALID", "Invalid device credentials")
+
+    raw_body = event_body(event) or "{}"
+    if len(raw_body.encode("utf-8")) > MAX_INGEST_BODY_BYTES:
+        return _error(413, "PAYLOAD_TOO_LARGE", f"Request body must be at most {MAX_INGEST_BODY_BYTES} bytes")
+    try:
+        body = json.loads(raw_body)
+    except json.JSONDecodeError:
+        return _error(400, "INVALID_JSON", "Request body must be JSON")
+
+    readings = body.get("readings") if isinstance(body, dict) else None
+    if not isinstance(readings, list) or not readings:
+        return _error(400, "VALIDATION_ERROR", "readings must be a non-empty list")
+    if len(readings) > MAX_READINGS_PER_REQUEST:
+        return _error(413, "BATCH_TOO_LARGE", f"At most {MAX_READINGS_PER_REQUEST} readings per request")
+    if not all(isinstance(r, dict) for r in readings):
+        return _error(400, "VALIDATION_ERROR", "each reading must be an object")
+
+    def _enqueue():
+        return {"accepted": len(readings), "batchId": enqueue_readings(device, offload_waveforms(device, readings))}
+
+    try:
+        result = idempotent(
+            headers.get("idempotency-key"), _enqueue, scope=f"ingest:{device['id']}", payload=readings, required=True
+        )
+    except IdempotencyError as e:
+        return _error(e.status_code, e.code, e.message)
+    except ReadingValidationError as e:
+        return _error(400, "VALIDATION_ERROR", str(e))
+    except Exception as e:
+        print(f"[Ingest] Failed to enqueue readings for {device['id']}: {e}")
+        return _error(503, "INGEST_UNAVAILABLE", "Readings could not be queued, retry later")
+
+    return _response(202, result)
+
+
+def _reading_timestamp(reading: Dict[str, Any]) -> int:
+    timestamp = reading.get("timestamp")
+    if isinstance(timestamp, bool) or not isinstance(timestamp, (int, float)) or timestamp <= 0:
+        raise ReadingValidationError("timestamp must be a positive unix time")
+    return int(timestamp)
+
+
+def offload_waveforms(device: Dict[str, Any], readings: List[Dict[str, Any]]) -> List[Dict[str, Any]]:
+    """
+    Upload the waveform samples of ECG readings to S3 and replace them with
+    the object's key (waveformKey), so queue messages and sensor items stay
+    small. Payloads go to the bucket of the patient's data residency.
+
+    Raises:
+        ReadingValidationError: for malformed samples or a waveform on another reading type
+    """
+    if not any("waveform" in r for r in readings):
+        return readings
+    patient = db.get_user(device["patientId"]) if device.get("patientId") else None
+    residency = (patient or {}).get("dataResidency")
+
+    offloaded = []
+    for reading in readings:
+        if "waveform" not in reading:
+            offloaded.append(reading)
+            continue
+        reading_type = reading.get("readingType") or DEFAULT_READING_TYPE
+        if reading_type not in WAVEFORM_READING_TYPES:
+            raise ReadingValidationError(f"{reading_type} readings cannot carry a waveform")
+        timestamp = _reading_timestamp(reading)
+        values = reading.get("values") if isinstance(reading.get("values"), dict) else {}
+        key = storage.upload_device_data(device["id"], reading_type, timestamp, {
+            "deviceId": device["id"],
+            "timestamp": timestamp,
+            "readingType": reading_type,
+            "unit": "mV",
+            "sampleRateHz": values.get("sample_rate_hz"),
+            "samples": validate_waveform(reading["waveform"]),
+        }, residency=residency)
+        offloaded.append({**{k: v for k, v in reading.items() if k != "waveform"}, "waveformKey": key})
+    return offloaded
+
+
+def to_sensor_item(
+    device_id: str,
+    patient_id: Optional[str],
+    reading: Dict[str, Any],
+    calibration: Optional[Dict[str, Any]] = None,
+    calibration_overdue: bool = False
+) -> Dict[str, Any]:
+    """
+    Validate one reading, convert it to its canonical unit, apply the device
+    calibration and flatten it into a sensor data item. A GPS location is
+    kept as a nested "location" map; the item's quality is scored with
+    analytics_service.score_reading_quality. An ECG reading keeps the S3 key
+    of its offloaded waveform as "waveform_key".
+
+    Raises:
+        ReadingValidationError: if the reading is malformed or out of range
+    """
+    timestamp = _reading_timestamp(reading)
+    reading_type = reading.get("readingType") or DEFAULT_READING_TYPE
+    values = reading.get("values")
+    values = validate_reading(reading_type, values, calibration, unit=reading.get("unit"))
+    location = validate_location(reading.get("location"))
+    item = {
+        **values,
+        "device_id": device_id,
+        "timestamp": timestamp,
+        "patient_id": patient_id or "UNASSIGNED",
+        "reading_type": reading_type,
+    }
+    if reading_type in CANONICAL_UNITS:
+        item["unit"] = CANONICAL_UNITS[reading_type]
+    if location is not None:
+        item["location"] = location
+    if "waveform" in reading:
+        raise ReadingValidationError("waveform samples must be uploaded through device ingestion")
+    waveform_key = reading.get("waveformKey")
+    if waveform_key is not None:
+        if reading_type not in WAVEFORM_READING_TYPES or not storage.is_device_data_key(waveform_key, device_id):
+            raise ReadingValidationError("waveformKey does not belong to this device")
+        item["waveform_key"] = waveform_key
+    return score_reading_quality(item, calibration_overdue)
+
+
+def process_message(message: Dict[str, Any]) -> Tuple[int, int]:
+    """
+    Validate and store the readings of one queue message.
+
+    Returns:
+        (ingested, flagged)
+    """
+    items, flagged = [], 0
+    for reading in message.get("readings", []):
+        try:
+            items.append(to_sensor_item(
+                message["deviceId"], message.get("patientId"), reading, message.get("calibration"),
+                bool(message.get("calibrationOverdue"))
+            ))
+        except ReadingValidationError as e:
+            flagged += 1
+            print(f"[Ingest] Dropping reading from {message['deviceId']}: {e}")
+    if items:
+        flag_anomalies(items)
+        db.batch_write_device_readings(items)
+        notify_alerts(items)
+    return len(items), flagged
+
+
+def flag_anomalies(items: List[Dict[str, Any]]) -> int:
+    """
+    Mark items that are outliers against the device's recent readings of the
+    same type (stored ones plus the earlier items of this batch).
+
+    Returns:
+        Number of items flagged
+    """
+    histories: Dict[Tuple[str, str], List[Dict[str, Any]]] = {}
+    flagged = 0
+    for item in sorted(items, key=lambda i: i["timestamp"]):
+        key = (item["device_id"], item["reading_type"])
+        if key not in histories:
+            try:
+                histories[key] = db.get_recent_sensor_readings(*key, limit=ANOMALY_WINDOW)
+            except Exception as e:
+                print(f"[Ingest] Anomaly detection skipped for {item['device_id']}: {e}")
+                histories[key] = []
+        history = histories[key]
+        anomalies = anomaly_detector.detect_anomalies(history, item)
+        if anomalies:
+            item["is_flagged"] = True
+            item["anomalies"] = [a.to_dict() for a in anomalies]
+            flagged += 1
+        history.append(item)
+    return flagged
+
+
+def notify_alerts(items: List[Dict[str, Any]]) -> int:
+    """
+    Publish alerts for stored sensor items that are anomalous (always critical)
+    or cross a threshold; critical ones are also pushed to the patient's doctor.
+    Best effort: notification problems never fail ingestion.
+
+    Returns:
+        Number of alerts published
+    """
+    published = 0
+    for item in items:
+        try:
+            if item.get("anomalies"):
+                severity = AlertSeverity.CRITICAL
+                reason = "anomaly: " + ", ".join(
+                    f"{a['key']} {a['value']:g} outside [{a['expectedRange'][0]:g}, {a['expectedRange'][1]:g}]"
+                    for a in item["anomalies"]
+                )
+            else:
+                match = classify_reading(item["reading_type"], item)
+                if not match:
+                    continue
+                severity, reason = match
+            alert = Alert(
+                patient_id=item["patient_id"],
+                device_id=item["device_id"],
+                reading_type=item["reading_type"],
+                reading=item,
+                severity=severity,
+                reason=reason,
+            )
+            if notification_service.notify(alert):
+                published += 1
+            notification_service.push_critical_alert(alert)
+        except Exception as e:
+            print(f"[Ingest] Alert notification skipped for {item.get('device_id')}: {e}")
+    return published
+
+
+def emit_metrics(ingested: int, flagged: int) -> None:
+    """Publish counts as a CloudWatch Embedded Metric Format log line."""
+    MetricsEmitter(METRICS_NAMESPACE).put_metrics({
+        "ReadingsIngested": (ingested, "Count"),
+        "ReadingsFlagged": (flagged, "Count"),
+    })
+
+
+def process(event, context):
+    """
+    SQS batch handler; returns the messages to retry (ReportBatchItemFailures).
+
+    FIFO ordering: once a message of a device fails, the rest of that
+    device's messages in the batch are returned unprocessed as well.
+    """
+    failures = []
+    failed_groups = set()
+    total_ingested = total_flagged = 0
+    for record in event.get("Records", []):
+        group = record.get("attributes", {}).get("MessageGroupId")
+        if group in failed_groups:
+            failures.append({"itemIdentifier": record["messageId"]})
+            continue
+        try:
+            ingested, flagged = process_message(json.loads(record["body"]))
+            total_ingested += ingested
+            total_flagged += flagged
+        except Exception as e:
+            print(f"[Ingest] Failed to process message {record.get('messageId')}: {e}")
+            failures.append({"itemIdentifier": record["messageId"]})
+            if group:
+                failed_groups.add(group)
+
+    emit_metrics(total_ingested, total_flagged)
+    return {"batchItemFailures": failures}
@@ -0,0 +1,184 @@
+"""
+MeDUSA Device Maintenance Service
+
+Tracks scheduled device maintenance (calibration, software updates,
+inspections, cleaning) for regulatory compliance.
+
+Key Features:
+- Maintenance events per device, keyed by device and scheduled time
+- Types: calibration, software_update, hardware_inspection,
+  cleaning_and_disinfection, or custom (with a free-text customType)
+- Completing an event with nextMaintenanceDue schedules the follow-up
+- Upcoming open maintenance across all devices (reminder Lambda)
+- DEVICE_MAINTENANCE_SCHEDULED / DEVICE_MAINTENANCE_COMPLETED audit events
+"""
+
+import os
+import uuid
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, List, Optional
+
+import db
+from audit_service import audit_service, AuditEventType
+
+MAINTENANCE_TYPE_CALIBRATION = "calibration"
+MAINTENANCE_TYPE_SOFTWARE_UPDATE = "software_update"
+MAINTENANCE_TYPE_HARDWARE_INSPECTION = "hardware_inspection"
+MAINTENANCE_TYPE_CLEANING = "cleaning_and_disinfection"
+MAINTENANCE_TYPE_CUSTOM = "custom"
+MAINTENANCE_TYPES = [
+    MAINTENANCE_TYPE_CALIBRATION,
+    MAINTENANCE_TYPE_SOFTWARE_UPDATE,
+    MAINTENANCE_TYPE_HARDWARE_INSPECTION,
+    MAINTENANCE_TYPE_CLEANING,
+    MAINTENANCE_TYPE_CUSTOM,
+]
+
+MAINTENANCE_REMINDER_DAYS = int(os.environ.get("MAINTENANCE_REMINDER_DAYS", "7"))
+
+
+class MaintenanceError(ValueError):
+    """Raised when a maintenance event cannot be scheduled or completed."""
+
+
+def _utc_iso(value: datetime) -> str:
+    # Stored as UTC ISO strings so the sort key orders chronologically
+    value = value if value.tzinfo else value.replace(tzinfo=timezone.utc)
+    return value.astimezone(timezone.utc).isoformat()
+
+
+def maintenance_label(event: Dict[str, Any]) -> str:
+    """Human-readable maintenance type (the customType of custom events)."""
+    if event.get("maintenanceType") == MAINTENANCE_TYPE_CUSTOM:
+        return event.get("customType") or MAINTENANCE_TYPE_CUSTOM
+    return event.get("maintenanceType", "").replace("_", " ")
+
+
+def schedule_maintenance(
+    device_id: str,
+    maintenance_type: str,
+    scheduled_at: datetime,
+    created_by: str,
+    created_by_role: str,
+    custom_type: Optional[str] = None,
+    assigned_to: Optional[str] = None,
+    notes: Optional[str] = None
+) -> Dict[str, Any]:
+    """
+    Schedule maintenance for a device.
+
+    Args:
+        assigned_to: User ID of the technician notified before the due date
+
+    Raises:
+        MaintenanceError: for an unknown type, a custom event without customType,
+            or a slot already taken on this device
+    """
+    if maintenance_type not in MAINTENANCE_TYPES:
+        raise MaintenanceError(f"maintenanceType must be one of {', '.join(MAINTENANCE_TYPES)}")
+    if maintenance_type == MAINTENANCE_TYPE_CUSTOM and not (custom_type or "").strip():
+        raise MaintenanceError("customType is required for custom maintenance")
+
+    scheduled_iso = _utc_iso(scheduled_at)
+    if any(e["scheduledAt"] == scheduled_iso for e in db.get_maintenance_events(device_id)):
+        raise MaintenanceError("Maintenance is already scheduled for this device at that time")
+
+    event = db.put_maintenance_event({
+        "id": f"mnt_{uuid.uuid4().hex[:12]}",
+        "deviceId": device_id,
+        "maintenanceType": maintenance_type,
+        "customType": custom_type.strip() if maintenance_type == MAINTENANCE_TYPE_CUSTOM else None,
+        "scheduledAt": scheduled_iso,
+        "assignedTo": assigned_to,
+        "notes": notes,
+        "createdBy": created_by,
+        "createdAt": datetime.now(timezone.utc).isoformat(),
+    })
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_MAINTENANCE_SCHEDULED,
+        user_id=created_by,
+        user_role=created_by_role,
+        device_id=device_id,
+        action="schedule_maintenance",
+        details={"maintenanceId": event["id"], "maintenanceType": maintenance_type,
+                 "scheduledAt": scheduled_iso, "assignedTo": assigned_to}
+    )
+    return event
+
+
+def get_maintenance_event(device_id: str, event_id: str) -> Optional[Dict[str, Any]]:
+    return next((e for e in db.get_maintenance_events(device_id) if e.get("id") == event_id), None)
+
+
+def complete_maintenance(
+    device_id: str,
+    event_id: str,
+    performed_by: str,
+    performed_by_role: str,
+    completed_at: Optional[datetime] = None,
+    notes: Optional[str] = None,
+    next_maintenance_due: Optional[datetime] = None
+) -> Optional[Dict[str, Any]]:
+    """
+    Mark a maintenance event as done. With next_maintenance_due, a follow-up
+    event of the same type and technician is scheduled.
+
+    Returns:
+        The completed event, or None if the device has no such event
+
+    Raises:
+        MaintenanceError: if the event was already completed or the follow-up slot is taken
+    """
+    event = get_maintenance_event(device_id, event_id)
+    if not event:
+        return None
+    if event.get("completedAt"):
+        raise MaintenanceError("Maintenance event is already completed")
+    if next_maintenance_due and any(
+        e["scheduledAt"] == _utc_iso(next_maintenance_due) for e in db.get_maintenance_events(device_id)
+    ):
+        raise MaintenanceError("Maintenance is already scheduled for this device at nextMaintenanceDue")
+
+    event.update({
+        "completedAt": _utc_iso(completed_at or datetime.now(timezone.utc)),
+        "performedBy": performed_by,
+        "nextMaintenanceDue": _utc_iso(next_maintenance_due) if next_maintenance_due else None,
+    })
+    if notes is not None:
+        event["notes"] = notes
+    event = db.put_maintenance_event(event)
+
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_MAINTENANCE_COMPLETED,
+        user_id=performed_by,
+        user_role=performed_by_role,
+        device_id=device_id,
+        action="complete_maintenance",
+        details={"maintenanceId": event_id, "maintenanceType": event["maintenanceType"],
+                 "completedAt": event["completedAt"], "nextMaintenanceDue": event.get("nextMaintenanceDue")}
+    )
+    if next_maintenance_due:
+        schedule_maintenance(
+            device_id,
+            event["maintenanceType"],
+            next_maintenance_due,
+            created_by=performed_by,
+            created_by_role=performed_by_role,
+            custom_type=event.get("customType"),
+            assigned_to=event.get("assignedTo")
+        )
+    return event
+
+
+def get_device_maintenance(device_id: str, pending_only: bool = False) -> List[Dict[str, Any]]:
+    """Maintenance schedule of a device, earliest first."""
+    return db.get_maintenance_events(device_id, pending_only=pending_only)
+
+
+def get_upcoming_maintenance(
+    now: Optional[datetime] = None,
+    days: int = MAINTENANCE_REMINDER_DAYS
+) -> List[Dict[str, Any]]:
+    """Open maintenance of all devices scheduled within the next `days` days."""
+    now = now or datetime.now(timezone.utc)
+    return db.get_pending_maintenance(_utc_iso(now), _utc_iso(now + timedelta(days=days)))
@@ -0,0 +1,63 @@
+"""
+DynamoDB error mapping
+
+Turns botocore ClientErrors raised by DynamoDB into HTTP errors that say
+what went wrong instead of a generic 500.
+
+Key Features:
+- ConditionalCheckFailedException -> 409 CONFLICT
+- ResourceNotFoundException -> 404 NOT_FOUND
+- ProvisionedThroughputExceededException -> 429 RATE_LIMITED (Retry-After: 5)
+- ValidationException -> 400 BAD_REQUEST
+- Anything else -> 500 DATABASE_ERROR
+- Errors from other AWS services (S3, SES) are left alone
+"""
+
+from typing import Dict, Optional, Tuple
+
+from botocore.exceptions import ClientError
+from fastapi import HTTPException
+from fastapi.responses import JSONResponse
+
+THROTTLE_RETRY_AFTER_SECONDS = 5
+
+DYNAMO_OPERATIONS = {
+    "GetItem", "PutItem", "UpdateItem", "DeleteItem", "Query", "Scan",
+    "BatchGetItem", "BatchWriteItem", "TransactGetItems", "TransactWriteItems",
+}
+
+# DynamoDB error code -> (HTTP status, API error code)
+DYNAMO_ERROR_STATUS: Dict[str, Tuple[int, str]] = {
+    "ConditionalCheckFailedException": (409, "CONFLICT"),
+    "ResourceNotFoundException": (404, "NOT_FOUND"),
+    "ProvisionedThroughputExceededException": (429, "RATE_LIMITED"),
+    "ValidationException": (400, "BAD_REQUEST"),
+}
+
+
+def is_dynamo_error(err: BaseException) -> bool:
+    """Whether err is a ClientError from a DynamoDB operation"""
+    return isinstance(err, ClientError) and err.operation_name in DYNAMO_OPERATIONS
+
+
+def map_dynamo_error(err: ClientError, context: str) -> HTTPException:
+    """
+    HTTPException for a DynamoDB ClientError.
+
+    Args:
+        err: The error raised by boto3
+        context: What was being done, prefixed to the message (e.g. "Updating report")
+    """
+    error = err.response.get("Error", {})
+    status, code = DYNAMO_ERROR_STATUS.get(error.get("Code"), (500, "DATABASE_ERROR"))
+    headers: Optional[Dict[str, str]] = None
+    if status == 429:
+        headers = {"Retry-After": str(THROTTLE_RETRY_AFTER_SECONDS)}
+    message = f"{context}: {error.get('Message') or error.get('Code') or 'database error'}"
+    return HTTPException(status, detail={"code": code, "message": message}, headers=headers)
+
+
+def dynamo_error_response(err: ClientError, context: str = "Database request failed") -> JSONResponse:
+    """Response for a DynamoDB error no endpoint handled (app-wide exception handler)"""
+    mapped = map_dynamo_error(err, context)
+    return JSONResponse(status_code=mapped.status_code, content={"detail": mapped.detail}, headers=mapped.headers)
@@ -0,0 +1,170 @@
+"""
+DynamoDB UpdateItem expression builder
+
+Builds minimal SET/REMOVE update expressions for only the attributes that
+changed, with optional optimistic locking on a numeric version attribute.
+"""
+
+from typing import Any, Dict, Iterable, List, Optional
+
+VERSION_FIELD = "version"
+
+# Attributes that identify a user and must never be rewritten by a diff
+USER_IMMUTABLE_FIELDS = {"id", "createdAt", VERSION_FIELD}
+
+
+class ConditionFailedError(Exception):
+    """Raised when an update's condition does not hold."""
+
+
+class VersionConflictError(ConditionFailedError):
+    """Raised when an optimistic-locking version check fails."""
+
+    def __init__(self, expected_version: Optional[int]):
+        super().__init__(f"Item was modified concurrently (expected version {expected_version})")
+        self.expected_version = expected_version
+
+
+class DynamoUpdateBuilder:
+    """
+    Accumulates update clauses and renders UpdateItem keyword arguments.
+
+    Usage:
+        builder = DynamoUpdateBuilder().set("name", "Ann").remove("nickname")
+        table.update_item(Key=key, **builder.build())
+    """
+
+    def __init__(self):
+        self._set_parts: List[str] = []
+        self._remove_parts: List[str] = []
+        self._conditions: List[str] = []
+        self._names: Dict[str, str] = {}
+        self._values: Dict[str, Any] = {}
+        self._fields: Dict[str, Any] = {}
+        self._version: Optional[tuple] = None
+        self._expected: Dict[str, Any] = {}
+        self._counter = 0
+
+    def _name(self, field: str) -> str:
+        for placeholder, name in self._names.items():
+            if name == field:
+                return placeholder
+        placeholder = f"#attr{len(self._names)}"
+        self._names[placeholder] = field
+        return placeholder
+
+    def _value(self, value: Any) -> str:
+        placeholder = f":val{self._counter}"
+        self._counter += 1
+        self._values[placeholder] = value
+        return placeholder
+
+    def set(self, field: str, value: Any) -> "DynamoUpdateBuilder":
+        """SET field = value (a None value removes the attribute)."""
+        if value is None:
+            return self.remove(field)
+        self._set_parts.append(f"{self._name(field)} = {self._value(value)}")
+        self._fields[field] = value
+        return self
+
+    def remove(self, field: str) -> "DynamoUpdateBuilder":
+        """REMOVE field."""
+        self._remove_parts.append(self._name(field))
+        self._fields[field] = None
+        return self
+
+    def set_all(self, fields: Dict[str, Any]) -> "DynamoUpdateBuilder":
+        for field, value in fields.items():
+            self.set(field, value)
+        return self
+
+    def with_version(self, expected_version: Optional[int], field: str = VERSION_FIELD) -> "DynamoUpdateBuilder":
+        """
+        Increment the version attribute and, if expected_version is given,
+        only apply the update when the stored version still matches.
+        Items written before versioning are treated as version 0.
+        """
+        name = self._name(field)
+        zero, one = self._value(0), self._value(1)
+        self._set_parts.append(f"{name} = if_not_exists({name}, {zero}) + {one}")
+        if expected_version is not None:
+            expected = self._value(expected_version)
+            if expected_version == 0:
+                self._conditions.append(f"(attribute_not_exists({name}) OR {name} = {expected})")
+            else:
+                self._conditions.append(f"{name} = {expected}")
+        self._version = (field, expected_version)
+        return self
+
+    def when_equals(self, field: str, expected: Any) -> "DynamoUpdateBuilder":
+        """Only apply the update if field currently equals expected."""
+        self._conditions.append(f"{self._name(field)} = {self._value(expected)}")
+        self._expected[field] = expected
+        return self
+
+    def is_empty(self) -> bool:
+        return not self._set_parts and not self._remove_parts
+
+    def build(self) -> Dict[str, Any]:
+        """Render UpdateItem keyword arguments (without Key)."""
+        clauses = []
+        if self._set_parts:
+            clauses.append("SET " + ", ".join(self._set_parts))
+        if self._remove_parts:
+            clauses.append("REMOVE " + ", ".join(self._remove_parts))
+        kwargs: Dict[str, Any] = {
+            "UpdateExpression": " ".join(clauses),
+            "ExpressionAttributeNames": dict(self._names),
+        }
+        if self._values:
+            kwargs["ExpressionAttributeValues"] = dict(self._values)
+        if self._conditions:
+            kwargs["ConditionExpression"] = " AND ".join(self._conditions)
+        return kwargs
+
+    def apply_to(self, item: Dict[str, Any]) -> None:
+        """
+        Apply the same update to an in-memory item (USE_MEMORY mode).
+
+        Raises:
+            ConditionFailedError: if a when_equals condition does not hold
+            VersionConflictError: if the version condition does not hold
+        """
+        for field, expected in self._expected.items():
+            if item.get(field) != expected:
+                raise ConditionFailedError(f"{field} does not match the expected value")
+        version = self._version
+        if version:
+            field, expected = version
+            if expected is not None and item.get(field, 0) != expected:
+                raise VersionConflictError(expected)
+        for field, value in self._fields.items():
+            if value is None:
+                item.pop(field, None)
+            else:
+                item[field] = value
+        if version:
+            item[version[0]] = item.get(version[0], 0) + 1
+
+
+def diff_item(old: Dict[str, Any], new: Dict[str, Any], ignore: Iterable[str] = ()) -> Dict[str, Any]:
+    """
+    Compute the changed attributes between two versions of an item.
+
+    Returns:
+        {field: new_value} for every changed field; removed fields map to None
+    """
+    ignored = set(ignore)
+    changes = {}
+    for field, value in new.items():
+        if field not in ignored and old.get(field) != value:
+            changes[field] = value
+    for field in old:
+        if field not in ignored and field not in new:
+            changes[field] = None
+    return changes
+
+
+def diff_user(old: Dict[str, Any], new: Dict[str, Any]) -> Dict[str, Any]:
+    """Changed user fields, excluding identity and version attributes."""
+    return diff_item(old, new, ignore=USER_IMMUTABLE_FIELDS)
@@ -3,9 +3,10 @@ Email service for sending verification codes
 Supports multiple email providers (AWS SES, SendGrid, SMTP)
 """
 import os
-import boto3
 from botocore.exceptions import ClientError
 
+import aws_clients
+
 class EmailService:
     """
     Email service for sending verification emails
@@ -24,7 +25,7 @@ class EmailService:
             try:
                 # Use SES_REGION if provided, otherwise use AWS_REGION (auto-provided by Lambda)
                 region = os.environ.get("SES_REGION") or os.environ.get("AWS_REGION", "us-east-1")
-                self.ses_client = boto3.client('ses', region_name=region)
+                self.ses_client = aws_clients.get_client('ses', region_name=region)
                 print(f"[EmailService] AWS SES initialized in region: {region}")
             except Exception as e:
                 print(f"[EmailService] Failed to initialize AWS SES: {e}")
@@ -0,0 +1,121 @@
+"""
+MeDUSA Storage Encryption
+
+Selects how objects written to S3 are encrypted.
+
+Modes (S3_ENCRYPTION_MODE for all uploads, SENSITIVE_ENCRYPTION_MODE for
+sensitive objects such as backups, which defaults to the former):
+- sse-s3:   S3-managed keys (AES256), the previous behaviour
+- sse-kms:  SSE-KMS with the customer-managed key KMS_KEY_ARN
+- envelope: client-side envelope encryption (sensitive objects only). A
+            data key is generated per object with KMS; the payload is
+            encrypted locally with AES-256-GCM and the KMS-wrapped data key
+            is stored in the object's metadata. The object is additionally
+            stored with SSE-KMS.
+
+Key Features:
+- sse_params() / presign_sse_fields() give the S3 parameters for a mode;
+  buckets in another region pass that region's key as key_arn
+- The S3 key is bound into the KMS encryption context and the GCM
+  associated data, so a ciphertext copied to another key fails to decrypt
+- Lazy KMS client; misconfiguration (KMS mode without a key) raises early
+"""
+
+import os
+import base64
+from typing import Any, Dict, List, Optional, Tuple
+
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+
+import aws_clients
+
+MODE_SSE_S3 = "sse-s3"
+MODE_SSE_KMS = "sse-kms"
+MODE_ENVELOPE = "envelope"
+ENCRYPTION_MODES = (MODE_SSE_S3, MODE_SSE_KMS, MODE_ENVELOPE)
+
+KMS_KEY_ARN = os.environ.get("KMS_KEY_ARN") or None
+S3_ENCRYPTION_MODE = os.environ.get("S3_ENCRYPTION_MODE", MODE_SSE_S3).strip().lower()
+SENSITIVE_ENCRYPTION_MODE = os.environ.get("SENSITIVE_ENCRYPTION_MODE", S3_ENCRYPTION_MODE).strip().lower()
+
+# Object metadata written for envelope-encrypted objects
+ENVELOPE_ALGORITHM = "AES-256-GCM"
+META_ALGORITHM = "medusa-encryption"
+META_WRAPPED_KEY = "medusa-wrapped-key"
+NONCE_BYTES = 12
+
+_kms = None
+
+
+def _kms_client():
+    """Created lazily so importing this module needs no AWS region."""
+    global _kms
+    if _kms is None:
+        _kms = aws_clients.get_client("kms")
+    return _kms
+
+
+class EncryptionConfigError(RuntimeError):
+    """Raised when an encryption mode is unknown or lacks its KMS key."""
+
+
+def _require_key(mode: str, key_arn: Optional[str] = None) -> str:
+    key_arn = key_arn or KMS_KEY_ARN
+    if not key_arn:
+        raise EncryptionConfigError(f"Encryption mode {mode} requires KMS_KEY_ARN")
+    return key_arn
+
+
+def sse_params(mode: Optional[str] = None, key_arn: Optional[str] = None) -> Dict[str, str]:
+    """
+    put_object / create_multipart_upload server-side encryption parameters
+    (default: S3_ENCRYPTION_MODE with KMS_KEY_ARN).
+    """
+    mode = mode or S3_ENCRYPTION_MODE
+    if mode == MODE_SSE_S3:
+        return {"ServerSideEncryption": "AES256"}
+    if mode in (MODE_SSE_KMS, MODE_ENVELOPE):
+        return {"ServerSideEncryption": "aws:kms", "SSEKMSKeyId": _require_key(mode, key_arn)}
+    raise EncryptionConfigError(f"Unknown encryption mode {mode!r}; expected one of {', '.join(ENCRYPTION_MODES)}")
+
+
+def presign_sse_fields(mode: Optional[str] = None, key_arn: Optional[str] = None) -> Tuple[Dict[str, str], List[Any]]:
+    """Presigned POST fields and matching conditions enforcing the same encryption."""
+    header_names = {
+        "ServerSideEncryption": "x-amz-server-side-encryption",
+        "SSEKMSKeyId": "x-amz-server-side-encryption-aws-kms-key-id",
+    }
+    fields = {header_names[name]: value for name, value in sse_params(mode, key_arn).items()}
+    return fields, [{name: value} for name, value in fields.items()]
+
+
+def envelope_encrypt(plaintext: bytes, object_key: str) -> Tuple[bytes, Dict[str, str]]:
+    """
+    Encrypt with a fresh KMS data key.
+
+    Returns:
+        (nonce + ciphertext, S3 metadata holding the wrapped data key)
+    """
+    data_key = _kms_client().generate_data_key(
+        KeyId=_require_key(MODE_ENVELOPE),
+        KeySpec="AES_256",
+        EncryptionContext={"s3Key": object_key},
+    )
+    nonce = os.urandom(NONCE_BYTES)
+    ciphertext = AESGCM(data_key["Plaintext"]).encrypt(nonce, plaintext, object_key.encode())
+    metadata = {
+        META_ALGORITHM: ENVELOPE_ALGORITHM,
+        META_WRAPPED_KEY: base64.b64encode(data_key["CiphertextBlob"]).decode(),
+    }
+    return nonce + ciphertext, metadata
+
+
+def envelope_decrypt(body: bytes, metadata: Dict[str, str], object_key: str) -> bytes:
+    """Unwrap the data key with KMS and decrypt an envelope_encrypt() payload."""
+    if metadata.get(META_ALGORITHM) != ENVELOPE_ALGORITHM:
+        raise ValueError(f"Object {object_key} is not envelope encrypted")
+    data_key = _kms_client().decrypt(
+        CiphertextBlob=base64.b64decode(metadata[META_WRAPPED_KEY]),
+        EncryptionContext={"s3Key": object_key},
+    )["Plaintext"]
+    return AESGCM(data_key).decrypt(body[:NONCE_BYTES], body[NONCE_BYTES:], object_key.encode())
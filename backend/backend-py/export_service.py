@@ -0,0 +1,268 @@
+"""
+MeDUSA Patient Data Export Service
+
+Assembles a patient's records into a downloadable bundle for subject access
+requests (GDPR Art. 15 / HIPAA right of access), and any user's records for
+data portability requests (GDPR Art. 20).
+
+Key Features:
+- Single JSON document with patient, devices, medications, readings and reports
+- User exports with the account, patient profile, readings, reports and audit trail
+- Optional ZIP bundle with the JSON plus a readings CSV
+- Upload to a dedicated S3 prefix, returned as a presigned download URL
+- Device reading exports (CSV / JSON Lines) streamed page by page into an
+  S3 multipart upload, so large ranges never exceed Lambda memory or the
+  6 MB response limit
+"""
+
+import csv
+import io
+import json
+import zipfile
+from datetime import datetime, timezone
+from decimal import Decimal
+from typing import Any, Dict, Iterable, Iterator, List, Optional
+
+import db
+import storage
+from analytics_service import READING_METRIC_KEYS
+
+EXPORT_URL_TTL_SECONDS = 3600
+USER_EXPORT_URL_TTL_SECONDS = 86400
+
+# Account fields included in exports. Anything else on the user record
+# (credentials, MFA and one-time codes, internal keys) is left out, so new
+# fields stay out of exports until they are added here.
+EXPORTABLE_USER_FIELDS = (
+    "id", "email", "name", "firstName", "lastName", "role", "phone",
+    "specialty", "license", "licenseNumber", "department", "hospital",
+    "settings", "preferences", "emailVerified", "mfaEnabled", "isActive",
+    "organizationId", "dataResidency", "createdAt", "updatedAt",
+    "passwordChangedAt", "lastLoginAt", "lastLoginIp", "lastLoginGeo",
+)
+
+EXPORT_FORMATS = ("json", "zip")
+READING_EXPORT_FORMATS = ("csv", "jsonl")
+READING_EXPORT_CONTENT_TYPES = {"csv": "text/csv", "jsonl": "application/x-ndjson"}
+
+# Streamed CSV needs its header before the first row, so columns are fixed;
+# JSON Lines keeps every field of a reading
+READING_CSV_COLUMNS = ["timestamp", "device_id", "patient_id"] + READING_METRIC_KEYS
+
+
+def _json_default(value: Any):
+    if isinstance(value, Decimal):
+        return int(value) if value % 1 == 0 else float(value)
+    if isinstance(value, datetime):
+        return value.isoformat()
+    raise TypeError(f"Object of type {type(value).__name__} is not JSON serializable")
+
+
+def _readings_csv(readings: List[Dict[str, Any]]) -> str:
+    """Render readings as CSV with a stable, union-of-keys header."""
+    columns: List[str] = []
+    for reading in readings:
+        for key in reading:
+            if key not in columns:
+                columns.append(key)
+    buffer = io.StringIO()
+    writer = csv.DictWriter(buffer, fieldnames=columns, extrasaction="ignore")
+    writer.writeheader()
+    for reading in readings:
+        writer.writerow(reading)
+    return buffer.getvalue()
+
+
+def to_csv_row(values: List[Any]) -> bytes:
+    """One CSV line (with terminator) for a list of cell values."""
+    buffer = io.StringIO()
+    csv.writer(buffer).writerow(["" if v is None else v for v in values])
+    return buffer.getvalue().encode("utf-8")
+
+
+def reading_export_chunks(pages: Iterable[List[Dict[str, Any]]], export_format: str, counter: Dict[str, int]) -> Iterator[bytes]:
+    """
+    Lazily render pages of readings as CSV or JSON Lines, one chunk per page.
+    counter["readings"] is incremented as readings are rendered.
+    """
+    if export_format == "csv":
+        yield to_csv_row(READING_CSV_COLUMNS)
+    for page in pages:
+        if export_format == "csv":
+            chunk = b"".join(to_csv_row([r.get(c) for c in READING_CSV_COLUMNS]) for r in page)
+        else:
+            chunk = b"".join(json.dumps(r, default=_json_default).encode("utf-8") + b"\n" for r in page)
+        counter["readings"] += len(page)
+        yield chunk
+
+
+def _exportable_user(user: Dict[str, Any]) -> Dict[str, Any]:
+    return {k: user[k] for k in EXPORTABLE_USER_FIELDS if k in user}
+
+
+class ExportService:
+    """Builds and publishes patient data exports."""
+
+    def build_patient_export(
+        self,
+        patient_id: str,
+        start_time: Optional[int] = None,
+        end_time: Optional[int] = None
+    ) -> Dict[str, Any]:
+        """
+        Collect all records belonging to a patient.
+
+        Readings are limited to the given time range (unix seconds);
+        all other records are exported in full.
+        """
+        patient = _exportable_user(db.get_user(patient_id) or {})
+
+        return {
+            "exportedAt": datetime.now(timezone.utc).isoformat(),
+            "patientId": patient_id,
+            "dateRange": {"start": start_time, "end": end_time},
+            "patient": patient,
+            "profile": db.get_patient_profile(patient_id),
+            "devices": db.get_devices_by_patient(patient_id),
+            "medications": db.get_medications(patient_id),
+            "readings": db.get_patient_readings(patient_id, start_time, end_time),
+            "reports": db.get_reports(patient_id=patient_id, limit=1000),
+        }
+
+    def export_patient_data(
+        self,
+        patient_id: str,
+        start_time: Optional[int] = None,
+        end_time: Optional[int] = None,
+        export_format: str = "json"
+    ) -> Dict[str, Any]:
+        """
+        Build the export, upload it to S3 and return a presigned URL.
+
+        Returns:
+            {fileKey, downloadUrl, expiresIn, format, recordCounts}
+        """
+        if export_format not in EXPORT_FORMATS:
+            raise ValueError(f"Unsupported export format: {export_format}")
+
+        export = self.build_patient_export(patient_id, start_time, end_time)
+        document = json.dumps(export, default=_json_default, indent=2).encode("utf-8")
+
+        if export_format == "zip":
+            buffer = io.BytesIO()
+            with zipfile.ZipFile(buffer, "w", zipfile.ZIP_DEFLATED) as bundle:
+                bundle.writestr("export.json", document)
+                bundle.writestr("readings.csv", _readings_csv(export["readings"]))
+            body, content_type = buffer.getvalue(), "application/zip"
+        else:
+            body, content_type = document, "application/json"
+
+        key = storage.make_export_key(patient_id, export_format)
+        storage.upload_bytes(key, body, content_type)
+
+        return {
+            "fileKey": key,
+            "downloadUrl": storage.presign_download(key, ttl_sec=EXPORT_URL_TTL_SECONDS),
+            "expiresIn": EXPORT_URL_TTL_SECONDS,
+            "format": export_format,
+            "recordCounts": {
+                "devices": len(export["devices"]),
+                "medications": len(export["medications"]),
+                "readings": len(export["readings"]),
+                "reports": len(export["reports"]),
+            },
+        }
+
+    def _audit_logs_for_user(self, user_id: str) -> List[Dict[str, Any]]:
+        items, token = [], None
+        while True:
+            page = db.get_audit_logs(user_id=user_id, limit=1000, next_token=token)
+            items.extend(page.items)
+            token = page.next_cursor
+            if not token:
+                return items
+
+    def build_user_export(self, user_id: str) -> Dict[str, Any]:
+        """
+        Collect everything stored about a user account.
+
+        Reports include those the user authored and, for patients, those
+        written about them.
+        """
+        user = db.get_user(user_id) or {}
+        profile = db.get_patient_profile(user_id)
+        is_patient = user.get("role") == "patient" or profile is not None
+
+        reports = {r["reportId"]: r for r in db.get_reports(author_id=user_id, limit=1000)}
+        if is_patient:
+            reports.update({r["reportId"]: r for r in db.get_reports(patient_id=user_id, limit=1000)})
+
+        return {
+            "exportedAt": datetime.now(timezone.utc).isoformat(),
+            "userId": user_id,
+            "user": _exportable_user(user),
+            "patient": profile,
+            "readings": db.get_patient_readings(user_id) if is_patient else [],
+            "reports": list(reports.values()),
+            "auditLogs": self._audit_logs_for_user(user_id),
+        }
+
+    def export_user_data(self, user_id: str) -> Dict[str, Any]:
+        """
+        Build a user export, upload it to S3 and return a 24 hour presigned URL.
+
+        Returns:
+            {fileKey, downloadUrl, expiresIn, recordCounts}
+        """
+        export = self.build_user_export(user_id)
+        document = json.dumps(export, default=_json_default, indent=2).encode("utf-8")
+
+        key = storage.make_export_key(f"users/{user_id}", "json")
+        storage.upload_bytes(key, document, "application/json")
+
+        return {
+            "fileKey": key,
+            "downloadUrl": storage.presign_download(key, ttl_sec=USER_EXPORT_URL_TTL_SECONDS),
+            "expiresIn": USER_EXPORT_URL_TTL_SECONDS,
+            "recordCounts": {
+                "readings": len(export["readings"]),
+                "reports": len(export["reports"]),
+                "auditLogs": len(export["auditLogs"]),
+            },
+        }
+
+    def export_device_readings(
+        self,
+        device_id: str,
+        start_time: Optional[int] = None,
+        end_time: Optional[int] = None,
+        export_format: str = "csv"
+    ) -> Dict[str, Any]:
+        """
+        Stream a device's readings into S3 as they are read and return a presigned URL.
+
+        Returns:
+            {fileKey, downloadUrl, expiresIn, format, readingCount, sizeBytes}
+        """
+        if export_format not in READING_EXPORT_FORMATS:
+            raise ValueError(f"Unsupported export format: {export_format}")
+
+        counter = {"readings": 0}
+        pages = db.iter_device_reading_pages(device_id, start_time, end_time)
+        key = storage.make_export_key(f"devices/{device_id}", export_format)
+        size = storage.multipart_upload(
+            key, reading_export_chunks(pages, export_format, counter), READING_EXPORT_CONTENT_TYPES[export_format]
+        )
+
+        return {
+            "fileKey": key,
+            "downloadUrl": storage.presign_download(key, ttl_sec=EXPORT_URL_TTL_SECONDS),
+            "expiresIn": EXPORT_URL_TTL_SECONDS,
+            "format": export_format,
+            "readingCount": counter["readings"],
+            "sizeBytes": size,
+        }
+
+
+# Global export service instance
+export_service = ExportService()
@@ -0,0 +1,173 @@
+"""
+MeDUSA FHIR Converter
+
+Expresses stored device readings (sensor data items) as FHIR R4
+Observation resources for interoperability with EHR systems.
+
+Key Features:
+- LOINC codes per reading type (LOINC_CODE_MAP) and per value key
+  (VALUE_CODE_MAP); types and keys without a LOINC code use the local
+  CodeSystems under FHIR_BASE_URL
+- Single-value vitals (heart rate, SpO2, temperature, weight) use
+  valueQuantity; multi-value readings (blood pressure, ECG summaries,
+  accelerometer axes, tremor metrics) use component
+- Quantities carry UCUM units
+- ECG waveform samples stay in S3 and are not embedded
+"""
+
+import os
+import re
+from datetime import datetime, timezone
+from typing import Any, Dict, List, Optional, Tuple
+
+from analytics_service import NON_METRIC_FIELDS
+from models import (
+    FhirBundle, FhirBundleEntry, FhirCodeableConcept, FhirCoding, FhirObservation, FhirObservationComponent,
+    FhirQuantity, FhirReference
+)
+
+FHIR_BASE_URL = os.environ.get("FHIR_BASE_URL", "https://medusa.local/fhir").rstrip("/")
+
+LOINC_SYSTEM = "http://loinc.org"
+UCUM_SYSTEM = "http://unitsofmeasure.org"
+CATEGORY_SYSTEM = "http://terminology.hl7.org/CodeSystem/observation-category"
+
+# reading_type -> (LOINC code, display)
+LOINC_CODE_MAP: Dict[str, Tuple[str, str]] = {
+    "heart_rate": ("8867-4", "Heart rate"),
+    "blood_pressure": ("85354-9", "Blood pressure panel with all children optional"),
+    "spo2": ("59408-5", "Oxygen saturation in Arterial blood by Pulse oximetry"),
+    "temperature": ("8310-5", "Body temperature"),
+    "weight": ("29463-7", "Body weight"),
+    "ecg": ("11524-6", "EKG study"),
+}
+
+# value key -> (LOINC code, display)
+VALUE_CODE_MAP: Dict[str, Tuple[str, str]] = {
+    "bpm": ("8867-4", "Heart rate"),
+    "systolic": ("8480-6", "Systolic blood pressure"),
+    "diastolic": ("8462-4", "Diastolic blood pressure"),
+    "spo2": ("59408-5", "Oxygen saturation in Arterial blood by Pulse oximetry"),
+    "celsius": ("8310-5", "Body temperature"),
+    "kg": ("29463-7", "Body weight"),
+    "heart_rate_bpm": ("8867-4", "Heart rate"),
+    "pr_interval_ms": ("8625-6", "P-R Interval"),
+    "qrs_duration_ms": ("8633-0", "QRS duration"),
+    "qt_interval_ms": ("8634-8", "Q-T interval"),
+}
+
+# value key -> (unit shown, UCUM code); keys not listed are dimensionless
+VALUE_UNITS: Dict[str, Tuple[str, str]] = {
+    "bpm": ("beats/minute", "/min"),
+    "heart_rate_bpm": ("beats/minute", "/min"),
+    "systolic": ("mmHg", "mm[Hg]"),
+    "diastolic": ("mmHg", "mm[Hg]"),
+    "spo2": ("%", "%"),
+    "celsius": ("degC", "Cel"),
+    "kg": ("kg", "kg"),
+    "accel_x": ("m/s^2", "m/s2"),
+    "accel_y": ("m/s^2", "m/s2"),
+    "accel_z": ("m/s^2", "m/s2"),
+    "magnitude": ("m/s^2", "m/s2"),
+    "gyro_x": ("deg/s", "deg/s"),
+    "gyro_y": ("deg/s", "deg/s"),
+    "gyro_z": ("deg/s", "deg/s"),
+    "dominant_frequency": ("Hz", "Hz"),
+    "sample_rate_hz": ("Hz", "Hz"),
+    "duration_s": ("s", "s"),
+    "pr_interval_ms": ("ms", "ms"),
+    "qrs_duration_ms": ("ms", "ms"),
+    "qt_interval_ms": ("ms", "ms"),
+}
+
+# reading_type -> the value key reported as valueQuantity
+PRIMARY_VALUE_KEYS = {"heart_rate": "bpm", "spo2": "spo2", "temperature": "celsius", "weight": "kg"}
+
+VITAL_SIGN_TYPES = {"heart_rate", "blood_pressure", "spo2", "temperature", "weight"}
+
+_INVALID_ID_CHARS = re.compile(r"[^A-Za-z0-9\-.]")
+
+
+def observation_id(reading: Dict[str, Any]) -> str:
+    """Stable Observation id of a reading (FHIR ids allow [A-Za-z0-9-.], at most 64 characters)."""
+    raw = f"{reading['device_id']}-{reading['timestamp']}-{reading.get('reading_type', 'reading')}"
+    return _INVALID_ID_CHARS.sub("-", raw)[:64]
+
+
+def _category(reading_type: str) -> FhirCodeableConcept:
+    if reading_type in VITAL_SIGN_TYPES:
+        code, display = "vital-signs", "Vital Signs"
+    elif reading_type == "ecg":
+        code, display = "procedure", "Procedure"
+    else:
+        code, display = "activity", "Activity"
+    return FhirCodeableConcept(coding=[FhirCoding(system=CATEGORY_SYSTEM, code=code, display=display)])
+
+
+def _concept(code_map: Dict[str, Tuple[str, str]], key: str, local_system: str) -> FhirCodeableConcept:
+    if key in code_map:
+        code, display = code_map[key]
+        return FhirCodeableConcept(coding=[FhirCoding(system=LOINC_SYSTEM, code=code, display=display)], text=display)
+    return FhirCodeableConcept(coding=[FhirCoding(system=local_system, code=key)], text=key.replace("_", " "))
+
+
+def _quantity(key: str, value: float) -> FhirQuantity:
+    if key not in VALUE_UNITS:
+        return FhirQuantity(value=value)
+    unit, ucum = VALUE_UNITS[key]
+    return FhirQuantity(value=value, unit=unit, system=UCUM_SYSTEM, code=ucum)
+
+
+def _values(reading: Dict[str, Any]) -> Dict[str, float]:
+    return {
+        key: value for key, value in reading.items()
+        if key not in NON_METRIC_FIELDS and isinstance(value, (int, float)) and not isinstance(value, bool)
+    }
+
+
+def to_fhir_observation(reading: Dict[str, Any], patient_id: str, base_url: str = FHIR_BASE_URL) -> FhirObservation:
+    """
+    Convert a stored sensor item into an Observation.
+
+    base_url is the FHIR server base; it prefixes the local CodeSystems used
+    for reading types and value keys that have no LOINC code.
+    """
+    base_url = base_url.rstrip("/")
+    reading_type = reading.get("reading_type", "accelerometer")
+    values = _values(reading)
+    primary = PRIMARY_VALUE_KEYS.get(reading_type)
+
+    value_quantity = _quantity(primary, values.pop(primary)) if primary in values else None
+    components = [
+        FhirObservationComponent(
+            code=_concept(VALUE_CODE_MAP, key, f"{base_url}/CodeSystem/reading-value"),
+            valueQuantity=_quantity(key, value),
+        )
+        for key, value in values.items()
+    ]
+
+    return FhirObservation(
+        id=observation_id(reading),
+        category=[_category(reading_type)],
+        code=_concept(LOINC_CODE_MAP, reading_type, f"{base_url}/CodeSystem/reading-type"),
+        subject=FhirReference(reference=f"Patient/{patient_id}"),
+        device=FhirReference(reference=f"Device/{reading['device_id']}"),
+        effectiveDateTime=datetime.fromtimestamp(reading["timestamp"], timezone.utc).isoformat(),
+        valueQuantity=value_quantity,
+        component=components or None,
+    )
+
+
+def to_fhir_bundle(
+    readings: List[Dict[str, Any]],
+    patient_id: str,
+    base_url: str = FHIR_BASE_URL,
+    total: Optional[int] = None
+) -> FhirBundle:
+    """searchset Bundle of the readings' Observations (total defaults to the number of readings)."""
+    base_url = base_url.rstrip("/")
+    observations = [to_fhir_observation(r, patient_id, base_url) for r in readings]
+    return FhirBundle(
+        total=len(observations) if total is None else total,
+        entry=[FhirBundleEntry(fullUrl=f"{base_url}/Observation/{o.id}", resource=o) for o in observations],
+    )
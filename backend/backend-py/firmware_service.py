@@ -11,6 +11,7 @@ Compliant with FDA cybersecurity requirements for software updates.
 """
 
 import os
+import re
 import json
 import time
 import hashlib
@@ -28,6 +29,47 @@ from cryptography.exceptions import InvalidSignature
 from cryptography.x509 import load_pem_x509_certificate
 
 
+# Semantic version: MAJOR.MINOR.PATCH with optional pre-release / build metadata
+SEMVER_PATTERN = re.compile(
+    r'^(0|[1-9]\d*)\.(0|[1-9]\d*)\.(0|[1-9]\d*)'
+    r'(?:-[0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*)?'
+    r'(?:\+[0-9A-Za-z-]+(?:\.[0-9A-Za-z-]+)*)?$'
+)
+
+
+def parse_firmware_version(version: Optional[str]) -> Optional[Tuple[int, int, int]]:
+    """
+    Parse a semantic firmware version string.
+    
+    Returns:
+        (major, minor, patch) tuple, or None if the string is not valid semver
+    """
+    if not version:
+        return None
+    match = SEMVER_PATTERN.match(version.strip())
+    if not match:
+        return None
+    return int(match.group(1)), int(match.group(2)), int(match.group(3))
+
+
+def is_valid_firmware_version(version: Optional[str]) -> bool:
+    """Check that a firmware version is a valid semantic version."""
+    return parse_firmware_version(version) is not None
+
+
+def is_firmware_downgrade(new_version: str, current_version: Optional[str]) -> bool:
+    """
+    Check whether moving from current_version to new_version is a downgrade.
+    
+    An unparseable current version (legacy devices) never blocks an update.
+    """
+    new_parts = parse_firmware_version(new_version)
+    current_parts = parse_firmware_version(current_version)
+    if new_parts is None or current_parts is None:
+        return False
+    return new_parts < current_parts
+
+
 class FirmwareStatus(Enum):
     """Firmware verification status codes."""
     VALID = "valid"
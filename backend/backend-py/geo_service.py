@@ -0,0 +1,223 @@
+"""
+MeDUSA Login Geolocation
+
+Resolves where a login comes from and flags "impossible travel": two
+successful logins of one user whose distance apart could not have been
+covered in the time between them.
+
+Key Features:
+- Pluggable resolver (GEOIP_PROVIDER): "none" (default), "cloudfront"
+  (CloudFront-Viewer-* headers) or "ipapi" (HTTP lookup at GEOIP_API_URL);
+  set_geo_resolver() installs any other, e.g. a self-hosted MaxMind database
+- Lookups are cached per IP for GEO_CACHE_TTL_SECONDS (24 hours)
+- Geographic access controls: ALLOWED_COUNTRIES and BLOCK_VPN
+- Fail-open: resolver errors or unknown locations never block a login
+- Great-circle (haversine) distance and implied speed between logins
+- Short hops are ignored, geo-IP is only accurate to a few hundred km
+"""
+
+import os
+import json
+import math
+import ipaddress
+import urllib.request
+from dataclasses import dataclass, asdict
+from datetime import datetime
+from typing import Any, Callable, Dict, FrozenSet, Mapping, Optional
+
+from cache_service import cache_service
+
+EARTH_RADIUS_KM = 6371.0
+
+GEOIP_PROVIDER = os.environ.get("GEOIP_PROVIDER", "none").lower()
+# Faster than a commercial flight including airport time
+IMPOSSIBLE_TRAVEL_SPEED_KMH = float(os.environ.get("IMPOSSIBLE_TRAVEL_SPEED_KMH", "900"))
+IMPOSSIBLE_TRAVEL_MIN_DISTANCE_KM = float(os.environ.get("IMPOSSIBLE_TRAVEL_MIN_DISTANCE_KM", "500"))
+
+# "{ip}" is replaced by the client address; the response must be ipapi.co-style JSON
+GEOIP_API_URL = os.environ.get("GEOIP_API_URL", "https://ipapi.co/{ip}/json/")
+GEOIP_API_TIMEOUT_SECONDS = float(os.environ.get("GEOIP_API_TIMEOUT_SECONDS", "2"))
+GEO_CACHE_TTL_SECONDS = int(os.environ.get("GEO_CACHE_TTL_SECONDS", str(24 * 3600)))
+
+
+def _parse_countries(value: Optional[str]) -> Optional[FrozenSet[str]]:
+    countries = frozenset(c.strip().upper() for c in (value or "").split(",") if c.strip())
+    return countries or None
+
+
+# ISO 3166-1 alpha-2 codes logins are accepted from; unset allows every country
+ALLOWED_COUNTRIES = _parse_countries(os.environ.get("ALLOWED_COUNTRIES"))
+BLOCK_VPN = os.environ.get("BLOCK_VPN", "false").lower() == "true"
+
+
+@dataclass
+class GeoPoint:
+    """Approximate location of an IP address."""
+    latitude: float
+    longitude: float
+    country_code: Optional[str] = None
+    city: Optional[str] = None
+    region: Optional[str] = None
+    is_vpn: bool = False
+
+    def to_dict(self) -> Dict[str, Any]:
+        return asdict(self)
+
+    @classmethod
+    def from_dict(cls, data: Optional[Mapping[str, Any]]) -> Optional["GeoPoint"]:
+        if not data or data.get("latitude") is None or data.get("longitude") is None:
+            return None
+        return cls(
+            latitude=float(data["latitude"]),
+            longitude=float(data["longitude"]),
+            country_code=data.get("country_code"),
+            city=data.get("city"),
+            region=data.get("region"),
+            is_vpn=bool(data.get("is_vpn", False)),
+        )
+
+
+# (ip, request headers) -> GeoPoint or None when unknown
+GeoResolver = Callable[[Optional[str], Mapping[str, str]], Optional[GeoPoint]]
+
+
+def null_resolver(ip: Optional[str], headers: Mapping[str, str]) -> Optional[GeoPoint]:
+    return None
+
+
+def cloudfront_resolver(ip: Optional[str], headers: Mapping[str, str]) -> Optional[GeoPoint]:
+    """Location from the CloudFront-Viewer-* headers (enable them in the origin request policy)."""
+    try:
+        return GeoPoint(
+            latitude=float(headers["cloudfront-viewer-latitude"]),
+            longitude=float(headers["cloudfront-viewer-longitude"]),
+            country_code=headers.get("cloudfront-viewer-country"),
+            city=headers.get("cloudfront-viewer-city"),
+            region=headers.get("cloudfront-viewer-country-region"),
+        )
+    except (KeyError, TypeError, ValueError):
+        return None
+
+
+def _is_public_ip(ip: Optional[str]) -> bool:
+    try:
+        return ipaddress.ip_address(ip).is_global
+    except (TypeError, ValueError):
+        return False
+
+
+def ipapi_resolver(ip: Optional[str], headers: Mapping[str, str]) -> Optional[GeoPoint]:
+    """Location from an ipapi.co-compatible HTTP API; private addresses are not looked up."""
+    if not _is_public_ip(ip):
+        return None
+    with urllib.request.urlopen(GEOIP_API_URL.format(ip=ip), timeout=GEOIP_API_TIMEOUT_SECONDS) as resp:
+        data = json.loads(resp.read())
+    if data.get("error") or data.get("latitude") is None or data.get("longitude") is None:
+        return None
+    security = data.get("security") or {}
+    return GeoPoint(
+        latitude=float(data["latitude"]),
+        longitude=float(data["longitude"]),
+        country_code=data.get("country_code"),
+        city=data.get("city"),
+        region=data.get("region"),
+        is_vpn=bool(security.get("vpn") or security.get("proxy") or data.get("vpn")),
+    )
+
+
+_RESOLVERS: Dict[str, GeoResolver] = {
+    "none": null_resolver,
+    "cloudfront": cloudfront_resolver,
+    "ipapi": ipapi_resolver,
+}
+
+_resolver: GeoResolver = _RESOLVERS.get(GEOIP_PROVIDER, null_resolver)
+
+
+def set_geo_resolver(resolver: GeoResolver) -> None:
+    """Install a different resolver (e.g. a MaxMind database lookup)."""
+    global _resolver
+    _resolver = resolver
+
+
+def geo_ip_key(ip: str) -> str:
+    return f"geo_ip:{ip}"
+
+
+def resolve_location(ip: Optional[str], headers: Mapping[str, str]) -> Optional[GeoPoint]:
+    """Location of a client, or None if unknown or the resolver failed."""
+    if ip:
+        cached = GeoPoint.from_dict(cache_service.get(geo_ip_key(ip)))
+        if cached:
+            return cached
+    try:
+        location = _resolver(ip, {k.lower(): v for k, v in headers.items()})
+    except Exception as e:
+        print(f"[GeoService] Location lookup failed for {ip}: {e}")
+        return None
+    if ip and location:
+        cache_service.set(geo_ip_key(ip), location.to_dict(), ttl_secs=GEO_CACHE_TTL_SECONDS)
+    return location
+
+
+def location_denial_reason(location: Optional[GeoPoint]) -> Optional[str]:
+    """
+    Why a login from this location is refused ("vpn" or "country"), or None
+    if it is allowed. Unknown locations are allowed (fail-open).
+    """
+    if location is None:
+        return None
+    if BLOCK_VPN and location.is_vpn:
+        return "vpn"
+    if ALLOWED_COUNTRIES and (location.country_code or "").upper() not in ALLOWED_COUNTRIES:
+        return "country"
+    return None
+
+
+def haversine_km(a: GeoPoint, b: GeoPoint) -> float:
+    """Great-circle distance between two points in km."""
+    lat1, lat2 = math.radians(a.latitude), math.radians(b.latitude)
+    dlat = lat2 - lat1
+    dlon = math.radians(b.longitude - a.longitude)
+    h = math.sin(dlat / 2) ** 2 + math.cos(lat1) * math.cos(lat2) * math.sin(dlon / 2) ** 2
+    return 2 * EARTH_RADIUS_KM * math.asin(min(1.0, math.sqrt(h)))
+
+
+@dataclass
+class TravelAssessment:
+    """Movement between two logins."""
+    distance_km: float
+    elapsed_hours: float
+    speed_kmh: Optional[float]  # None when both logins happened at the same instant
+    impossible: bool
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "distanceKm": round(self.distance_km, 1),
+            "elapsedHours": round(self.elapsed_hours, 3),
+            "speedKmh": round(self.speed_kmh, 1) if self.speed_kmh is not None else None,
+        }
+
+
+def assess_travel(
+    previous: GeoPoint,
+    previous_at: datetime,
+    current: GeoPoint,
+    current_at: datetime,
+    max_speed_kmh: float = IMPOSSIBLE_TRAVEL_SPEED_KMH,
+    min_distance_km: float = IMPOSSIBLE_TRAVEL_MIN_DISTANCE_KM
+) -> TravelAssessment:
+    """
+    Compare two login locations. Travel is impossible when the distance is at
+    least min_distance_km and the implied speed exceeds max_speed_kmh.
+    """
+    distance = haversine_km(previous, current)
+    elapsed_hours = max(0.0, (current_at - previous_at).total_seconds() / 3600)
+    speed = distance / elapsed_hours if elapsed_hours > 0 else None
+    too_fast = speed is None or speed > max_speed_kmh
+    return TravelAssessment(
+        distance_km=distance,
+        elapsed_hours=elapsed_hours,
+        speed_kmh=speed,
+        impossible=distance >= min_distance_km and too_fast,
+    )
@@ -0,0 +1,150 @@
+"""
+MeDUSA Idempotency Service
+
+Lets clients safely retry resource-creating POSTs by sending an
+Idempotency-Key header: the first request runs, repeats get its result.
+
+Key Features:
+- Keys claimed with a conditional put, so concurrent duplicates are serialized
+- Stored results expire via DynamoDB TTL (default 24 hours)
+- Reusing a key with a different payload is rejected
+- Failed operations release their key so the client can retry
+- In-progress claims hold a lease (lockedUntil); a claim left behind by a
+  crashed or timed-out request can be taken over once it runs out
+- Endpoints may require the header (required=True); a missing key is a 400
+"""
+
+import os
+import json
+import time
+import hashlib
+from dataclasses import dataclass, field
+from typing import Any, Callable, Optional, Union
+
+import db
+
+IDEMPOTENCY_HEADER = "Idempotency-Key"
+IDEMPOTENCY_TTL_SECONDS = int(os.environ.get("IDEMPOTENCY_TTL_SECONDS", "86400"))
+# Longer than the API Lambda timeout, so a live request never loses its claim
+IDEMPOTENCY_LEASE_SECONDS = int(os.environ.get("IDEMPOTENCY_LEASE_SECONDS", "60"))
+MAX_KEY_LENGTH = 255
+
+
+class IdempotencyError(Exception):
+    """Base class; carries the HTTP status and error code to return."""
+    status_code = 400
+    code = "IDEMPOTENCY_ERROR"
+
+    def __init__(self, message: str):
+        super().__init__(message)
+        self.message = message
+
+
+class InvalidIdempotencyKeyError(IdempotencyError):
+    status_code = 400
+    code = "INVALID_IDEMPOTENCY_KEY"
+
+
+class MissingIdempotencyKeyError(IdempotencyError):
+    """The endpoint requires an Idempotency-Key header and none was sent."""
+    status_code = 400
+    code = "IDEMPOTENCY_KEY_REQUIRED"
+
+
+class IdempotencyKeyInUseError(IdempotencyError):
+    """Another request with the same key is still being processed."""
+    status_code = 409
+    code = "IDEMPOTENCY_KEY_IN_USE"
+
+
+class IdempotencyKeyMismatchError(IdempotencyError):
+    """The key was already used for a different request payload."""
+    status_code = 422
+    code = "IDEMPOTENCY_KEY_MISMATCH"
+
+
+def fingerprint(payload: Any) -> str:
+    """Stable hash of a request payload."""
+    return hashlib.sha256(json.dumps(payload, sort_keys=True, default=str).encode()).hexdigest()
+
+
+@dataclass
+class FirstCall:
+    """The key was claimed by this request; run the operation, then complete or release storage_key."""
+    storage_key: str
+    locked_until: int = field(default=0, compare=False)
+
+
+@dataclass
+class Duplicate:
+    """The key was already used for the same payload; replay cached_response."""
+    cached_response: Any
+
+
+def check_or_store(key: str, scope: str, payload: Any = None) -> Union[FirstCall, Duplicate]:
+    """
+    Claim (scope, key) for this request or return the stored result of its first use.
+
+    Raises:
+        InvalidIdempotencyKeyError, IdempotencyKeyInUseError, IdempotencyKeyMismatchError
+    """
+    key = key.strip()
+    if not key or len(key) > MAX_KEY_LENGTH:
+        raise InvalidIdempotencyKeyError(f"{IDEMPOTENCY_HEADER} must be 1-{MAX_KEY_LENGTH} characters")
+
+    storage_key = f"{scope}:{key}"
+    request_fingerprint = fingerprint(payload)
+    now = int(time.time())
+    locked_until = now + IDEMPOTENCY_LEASE_SECONDS
+    existing = db.claim_idempotency_key(
+        storage_key, request_fingerprint, now + IDEMPOTENCY_TTL_SECONDS, locked_until
+    )
+    if existing is None:
+        return FirstCall(storage_key, locked_until)
+    if existing.get("fingerprint") != request_fingerprint:
+        raise IdempotencyKeyMismatchError(f"{IDEMPOTENCY_HEADER} was already used for a different request")
+    if existing.get("status") != "completed":
+        raise IdempotencyKeyInUseError("A request with this idempotency key is still in progress")
+    return Duplicate(json.loads(existing["result"]))
+
+
+def idempotent(
+    key: Optional[str],
+    op: Callable[[], Any],
+    scope: str,
+    payload: Any = None,
+    required: bool = False
+) -> Any:
+    """
+    Run op at most once per (scope, key) and return its result.
+
+    Args:
+        key: Client-supplied idempotency key; None runs op unconditionally
+        op: Zero-argument callable returning a JSON-serializable result
+        scope: Namespace for the key (e.g. "report:<user id>") so keys of
+               different clients and endpoints never collide
+        payload: Request payload; a repeated key must carry the same payload
+        required: Reject requests without a key instead of running op
+
+    Raises:
+        MissingIdempotencyKeyError, InvalidIdempotencyKeyError,
+        IdempotencyKeyInUseError, IdempotencyKeyMismatchError
+    """
+    if key is None:
+        if required:
+            raise MissingIdempotencyKeyError(f"{IDEMPOTENCY_HEADER} header is required")
+        return op()
+
+    outcome = check_or_store(key, scope, payload)
+    if isinstance(outcome, Duplicate):
+        return outcome.cached_response
+
+    try:
+        result = op()
+    except BaseException:
+        db.release_idempotency_key(outcome.storage_key, outcome.locked_until)
+        raise
+    if not db.complete_idempotency_key(outcome.storage_key, json.dumps(result, default=str), outcome.locked_until):
+        # The lease ran out and a retry took the key over; its result is the one replayed
+        print(f"[Idempotency] Lease on {outcome.storage_key} expired before the operation completed")
+    return result
@@ -0,0 +1,132 @@
+"""
+MeDUSA IoT Service
+
+Wraps AWS IoT Core for devices that talk MQTT directly instead of
+uploading readings over HTTPS.
+
+Key Features:
+- One IoT Thing per device (thing name: IOT_THING_PREFIX + device id)
+- Device certificates issued from a device-generated CSR (private key never leaves the device)
+- Certificates attached to IOT_POLICY_NAME and to the device's Thing
+- Device shadow read / desired-state update via the IoT data plane (IOT_ENDPOINT)
+"""
+
+import os
+import json
+from typing import Any, Dict
+
+from botocore.exceptions import ClientError
+
+import aws_clients
+
+IOT_ENDPOINT = os.environ.get("IOT_ENDPOINT", "")  # e.g. xxxxxxxx-ats.iot.us-east-1.amazonaws.com
+IOT_POLICY_NAME = os.environ.get("IOT_POLICY_NAME", "medusa-device-policy")
+IOT_THING_PREFIX = os.environ.get("IOT_THING_PREFIX", "medusa-")
+
+
+class IoTNotConfiguredError(RuntimeError):
+    """Raised when a data-plane call is made without IOT_ENDPOINT."""
+
+
+def _error_code(e: ClientError) -> str:
+    return e.response.get("Error", {}).get("Code", "")
+
+
+class IoTService:
+    """Provisions devices in AWS IoT Core and manages their shadows."""
+
+    def __init__(
+        self,
+        endpoint: str = IOT_ENDPOINT,
+        policy_name: str = IOT_POLICY_NAME,
+        thing_prefix: str = IOT_THING_PREFIX
+    ):
+        self.endpoint = endpoint
+        self.policy_name = policy_name
+        self.thing_prefix = thing_prefix
+        self._iot = None
+        self._iot_data = None
+
+    def _client(self):
+        if self._iot is None:
+            self._iot = aws_clients.get_client("iot")
+        return self._iot
+
+    def _data_client(self):
+        if self._iot_data is None:
+            if not self.endpoint:
+                raise IoTNotConfiguredError("IOT_ENDPOINT must be set for device shadow access")
+            endpoint = self.endpoint if self.endpoint.startswith("https://") else f"https://{self.endpoint}"
+            self._iot_data = aws_clients.get_client("iot-data", endpoint_url=endpoint)
+        return self._iot_data
+
+    def thing_name(self, device_id: str) -> str:
+        return f"{self.thing_prefix}{device_id}"
+
+    def create_device_thing(self, device_id: str, device_type: str) -> str:
+        """
+        Create the IoT Thing for a device (idempotent).
+
+        Returns:
+            The Thing ARN
+        """
+        name = self.thing_name(device_id)
+        try:
+            resp = self._client().create_thing(
+                thingName=name,
+                attributePayload={"attributes": {"deviceId": device_id, "deviceType": device_type}}
+            )
+            return resp["thingArn"]
+        except ClientError as e:
+            if _error_code(e) != "ResourceAlreadyExistsException":
+                raise
+            return self._client().describe_thing(thingName=name)["thingArn"]
+
+    def register_device_certificate(self, device_id: str, csr_pem: str) -> str:
+        """
+        Issue an active certificate for a device's CSR, attach it to the
+        device policy and the device's Thing.
+
+        Returns:
+            The certificate PEM to install on the device
+        """
+        cert = self._client().create_certificate_from_csr(
+            certificateSigningRequest=csr_pem,
+            setAsActive=True
+        )
+        arn = cert["certificateArn"]
+        try:
+            self._client().attach_policy(policyName=self.policy_name, target=arn)
+            self._client().attach_thing_principal(thingName=self.thing_name(device_id), principal=arn)
+        except Exception:
+            # Don't leave an active certificate that isn't bound to the device
+            self._client().update_certificate(certificateId=cert["certificateId"], newStatus="INACTIVE")
+            raise
+        print(f"[IoTService] Issued certificate {cert['certificateId']} for device {device_id}")
+        return cert["certificatePem"]
+
+    def get_device_shadow(self, device_id: str) -> Dict[str, Any]:
+        """
+        Read the device's classic shadow document.
+
+        Returns:
+            The shadow document, or {} if the device has no shadow yet
+        """
+        try:
+            resp = self._data_client().get_thing_shadow(thingName=self.thing_name(device_id))
+        except ClientError as e:
+            if _error_code(e) == "ResourceNotFoundException":
+                return {}
+            raise
+        return json.loads(resp["payload"].read())
+
+    def update_device_shadow(self, device_id: str, desired_state: Dict[str, Any]) -> None:
+        """Set the desired state the device should converge to."""
+        self._data_client().update_thing_shadow(
+            thingName=self.thing_name(device_id),
+            payload=json.dumps({"state": {"desired": desired_state}}).encode()
+        )
+
+
+# Global IoT service instance
+iot_service = IoTService()
@@ -1,6 +1,6 @@
-import os, sys, uuid, time, secrets
-from datetime import datetime, timezone
-from typing import Optional
+import os, sys, uuid, time, secrets, asyncio
+from datetime import datetime, date, timezone, timedelta
+from typing import Optional, Dict, Any, List, Tuple
 
 # Set UTF-8 encoding for Lambda environment
 os.environ['PYTHONIOENCODING'] = 'utf-8'
@@ -12,32 +12,97 @@ if os.path.isdir(_vendored) and _vendored not in sys.path:
     sys.path.insert(0, _vendored)
 
 from fastapi import FastAPI, Request, HTTPException
+from fastapi.exceptions import RequestValidationError
 from fastapi.middleware.cors import CORSMiddleware
-from fastapi.responses import RedirectResponse
+from cors import ALLOWED_ORIGINS, CORS_ALLOW_METHODS, CORS_ALLOW_HEADERS, CORS_MAX_AGE_SECONDS
+from fastapi.responses import RedirectResponse, JSONResponse
+from botocore.exceptions import ClientError
 from mangum import Mangum
-from pydantic import BaseModel
+from pydantic import BaseModel, ValidationError
 
 from models import (
     LoginReq, LoginRes, RegisterReq, RegisterRes, 
-    RefreshReq, RefreshRes, ResetPasswordReq, SendVerificationCodeReq,
+    RefreshReq, RefreshRes, AuthSession, ResetPasswordReq, ChangePasswordReq, SendVerificationCodeReq,
+    RequestOtpReq, VerifyOtpReq,
     RequestVerificationReq,
-    UserOut, PoseCreateReq, PresignReq, PresignRes,
-    Pose, PosePage, Report, ReportPage,
-    DeviceRegisterReq, DeviceUpdateReq, Device, DevicePage, DeviceBindReq,
-    PatientProfileCreateReq, PatientProfileUpdateReq, PatientProfile, PatientWithProfile, PatientPage,
+    UserOut, UserPreferences, PushTokenReq, PushToken, ProfilePictureRes, PoseCreateReq, PresignReq, PresignRes, ReadingImportReq, ReadingImportRes,
+    Pose, PosePage, Report, ReportPage, ReportSummary, ReportScheduleCreateReq, ReportSchedule, ReportSchedulePage,
+    DeviceRegisterReq, DeviceUpdateReq, Device, DevicePage, DeviceBindReq, DeviceAssignReq, DeviceAssignment,
+    CalibrationCreateReq, DeviceCalibrationRecord, MaintenanceModeReq,
+    MaintenanceEvent, MaintenanceScheduleReq, MaintenanceCompleteReq,
+    DeviceConnectionReq, DeviceConnectionInfo,
+    PatientProfileCreateReq, PatientProfileUpdateReq, PatientProfile, PatientWithProfile,
     SessionCreateReq, SessionUpdateReq, Session, SessionWithDetails, SessionPage,
-    TremorResponse, AssignPatientReq, DoctorPatientsRes
+    MedicationReq, Medication, MedicationPage, InteractionWarning, MedicationInteractionsRes, UserAnonymizationResult, PatientDeletionSummary, ConsentReq, ConsentRecord,
+    TimelineEvent, TimelineRes, OrganizationCreateReq, Organization, RoleCreateReq, RoleDefinition,
+    TremorResponse, ReadingSummaryRes, ReadingStreamRes, ReadingRollupRes, ReadingMapRes, ReadingMapFeature, ReadingWaveformRes, FhirObservation, FhirBundle, AssignPatientReq, DoctorPatientsRes
 )
 from auth import (
-    auth_middleware, issue_tokens, verify_pw, hash_pw,
+    auth_middleware, issue_tokens, decode_refresh_token, verify_pw, hash_pw, needs_rehash,
+    is_password_reused, password_change_fields, is_password_expired, PASSWORD_EXPIRY_BLOCK,
     generate_mfa_secret, verify_mfa_code, get_mfa_provisioning_uri,
     issue_temp_token, verify_temp_token
 )
-from password_validator import PasswordValidator
+from password_validator import validate_password_strength
 from email_service import EmailService
-from rbac import require_role, get_user_id, get_user_role
+from rbac import (
+    require_role, get_user_id, get_user_role, get_organization_id, role_name, CustomRole,
+    CUSTOM_ROLE_NAME_PATTERN, ROLE_PERMISSIONS, READINGS_READ, READINGS_EXPORT
+)
 from audit_service import audit_service, AuditEventType
 from replay_protection import nonce_service, require_nonce, get_nonce_endpoint
+from firmware_service import is_valid_firmware_version, is_firmware_downgrade
+from analytics_service import (
+    AggregationPeriod, aggregate_readings, get_reading_rollups, partition_by_quality, NON_METRIC_FIELDS
+)
+from validators import calculate_age, normalize_phone_e164, normalize_case
+from consent_service import (
+    record_consent, list_consents, require_consent, is_active, ConsentType, ConsentRequiredError
+)
+from validation_errors import validation_error_response
+from dynamo_errors import is_dynamo_error, map_dynamo_error, dynamo_error_response
+from request_context import request_id_middleware
+from request_limits import body_size_middleware
+from security_headers import security_headers_middleware
+from response_compression import compression_middleware
+from metrics_service import metrics, AUTH_SUCCESS, AUTH_FAILURE, DB_OPERATION_DURATION, REPORT_GENERATION_DURATION
+from maintenance_service import maintenance_service, maintenance_middleware, MAINTENANCE_SETTING_KEY
+from calibration_service import (
+    is_calibration_due, next_due_from, record_calibration, get_calibration_history,
+    get_latest_calibration, CalibrationError
+)
+from device_connection_service import (
+    record_connection, record_disconnection, get_connection_state, DeviceConnectionError
+)
+from device_maintenance_service import (
+    schedule_maintenance, complete_maintenance, get_device_maintenance, MaintenanceError
+)
+from export_service import export_service, EXPORT_FORMATS, READING_EXPORT_FORMATS
+import report_scheduler
+import readings_import
+from report_cleanup import is_report_expired
+import report_pdf
+import compliance_report
+import fhir_converter
+from device_assignment_service import (
+    assign_device_to_patient, unassign_device, get_assignment_history,
+    DeviceNotFoundError, PatientNotFoundError, DeviceAlreadyAssignedError, AssignmentConflictError
+)
+from dynamo_update import diff_user, VersionConflictError, ConditionFailedError
+from stats_service import stats_service
+from compliance_service import compliance_service, ComplianceError
+import medication_interactions
+from organization_service import organization_service, OrganizationError, SubscriptionTier, is_platform_admin
+from purge_service import purge_service, issue_confirmation_token, verify_confirmation_token
+from timeline_service import get_patient_timeline
+from device_data_ingest import hash_device_api_key
+from crypto_service import crypto_service
+from notification_service import notification_service
+from geo_service import GeoPoint, resolve_location, assess_travel, location_denial_reason
+from idempotency_service import idempotent, IdempotencyError, IDEMPOTENCY_HEADER
+from pagination import PaginatedResponse, create_paginated_response, paginate_list, parse_pagination_params
+from query_params import QueryParams, QueryParamError, parse_date_range_params
+from sanitize import sanitize_identifier
 import db
 import storage
 
@@ -49,37 +114,76 @@ email_service = EmailService()
 # CORS - properly configured for web clients
 # Production: Set ALLOWED_ORIGINS env var to restrict origins (comma-separated)
 # Development: Defaults to * but logs a warning
-allowed_origins_env = os.environ.get("ALLOWED_ORIGINS", "")
-if not allowed_origins_env:
+if not os.environ.get("ALLOWED_ORIGINS", "").strip():
     print("[WARNING] ALLOWED_ORIGINS not set - using * (not recommended for production)")
-    allowed_origins = ["*"]
-else:
-    allowed_origins = [origin.strip() for origin in allowed_origins_env.split(",") if origin.strip()]
 
-app.add_middleware(
-    CORSMiddleware,
-    allow_origins=allowed_origins,
-    allow_methods=["GET", "POST", "PUT", "DELETE", "OPTIONS", "PATCH"],
-    allow_headers=[
-        "Content-Type", 
-        "Authorization", 
-        "Accept",
-        "Origin",
-        "X-Requested-With",
-        "Access-Control-Request-Method",
-        "Access-Control-Request-Headers",
-        "X-Request-Nonce",
-        "X-Request-Timestamp",
-        "X-Request-Signature"
-    ],
-    allow_credentials=True, # Allow cookies/auth headers
-    max_age=600  # Cache preflight for 10 minutes
-)
+@app.exception_handler(RequestValidationError)
+async def _validation_error_handler(request: Request, exc: RequestValidationError):
+    return validation_error_response(exc.errors())
+
+@app.exception_handler(ClientError)
+async def _client_error_handler(request: Request, exc: ClientError):
+    if is_dynamo_error(exc):
+        return dynamo_error_response(exc)
+    print(f"[AWS] Unhandled {exc.operation_name} error: {exc}")
+    return JSONResponse(status_code=500, content={"detail": {"code": "INTERNAL_ERROR", "message": "Internal server error"}})
+
+@app.exception_handler(storage.DataResidencyError)
+async def _data_residency_error_handler(request: Request, exc: storage.DataResidencyError):
+    print(f"[Storage] {exc}")
+    return JSONResponse(status_code=500, content={"detail": {"code": "RESIDENCY_NOT_CONFIGURED", "message": "Storage for the patient's data residency is not configured"}})
+
+@app.exception_handler(storage.StorageIntegrityError)
+async def _storage_integrity_error_handler(request: Request, exc: storage.StorageIntegrityError):
+    print(f"[Storage] {exc}")
+    return JSONResponse(status_code=500, content={"detail": {"code": "STORAGE_INTEGRITY_ERROR", "message": "Stored file failed its integrity check"}})
+
+def _server_error(e: Exception, code: str) -> HTTPException:
+    """DynamoDB errors mapped to their meaning (conflict, throttling, ...); anything else a 500 with code"""
+    if is_dynamo_error(e):
+        return map_dynamo_error(e, code.replace("_", " ").capitalize())
+    return HTTPException(500, detail={"code": code, "message": str(e)})
+
+# Innermost: compresses the handler's response body once it is final
+@app.middleware("http")
+async def _compression_mw(request: Request, call_next):
+    return await compression_middleware(request, call_next)
+
+# Middleware registered later runs first: auth sets request.state.claims before the maintenance gate
+@app.middleware("http")
+async def _maintenance_mw(request: Request, call_next):
+    return await maintenance_middleware(request, call_next)
 
 @app.middleware("http")
 async def _auth_mw(request: Request, call_next):
     return await auth_middleware(request, call_next)
 
+# Oversized bodies are refused before auth and before any handler parses them
+@app.middleware("http")
+async def _body_size_mw(request: Request, call_next):
+    return await body_size_middleware(request, call_next)
+
+# Wraps every middleware that can answer early, so their error responses get the headers too
+@app.middleware("http")
+async def _security_headers_mw(request: Request, call_next):
+    return await security_headers_middleware(request, call_next)
+
+# Registered after the other http middleware so the request ID is set before any of them audits
+@app.middleware("http")
+async def _request_id_mw(request: Request, call_next):
+    return await request_id_middleware(request, call_next)
+
+# Outermost: the 401/413/503 answered early by the middleware above need CORS headers too,
+# or browsers hide them from the web client
+app.add_middleware(
+    CORSMiddleware,
+    allow_origins=ALLOWED_ORIGINS,
+    allow_methods=CORS_ALLOW_METHODS,
+    allow_headers=CORS_ALLOW_HEADERS,
+    allow_credentials=True, # Allow cookies/auth headers
+    max_age=CORS_MAX_AGE_SECONDS
+)
+
 # -------- CORS Preflight Handler
 @app.options("/{path:path}")
 async def options_handler(path: str):
@@ -112,8 +216,23 @@ def test_email(email: str):
     }
 
 
+def _pagination_params(limit: Optional[int], offset: Optional[int] = None):
+    """Validate list query parameters, 400 on out-of-range values"""
+    try:
+        return parse_pagination_params(limit, offset)
+    except QueryParamError as e:
+        raise HTTPException(400, detail={"code": "INVALID_PAGINATION", "message": str(e), "param": e.param})
+
+
+def _idempotent(request: Request, scope: str, payload: Any, op, required: bool = False):
+    """Run op once per Idempotency-Key header value, replaying the stored result on retries (400 if required and absent)"""
+    try:
+        return idempotent(request.headers.get(IDEMPOTENCY_HEADER), op, scope=scope, payload=payload, required=required)
+    except IdempotencyError as e:
+        raise HTTPException(e.status_code, detail={"code": e.code, "message": e.message})
+
 # -------- Admin - Audit Logs
-@app.get("/api/v1/admin/audit-logs")
+@app.get("/api/v1/admin/audit-logs", response_model=PaginatedResponse[dict])
 @require_role("admin")
 async def get_audit_logs(
     request: Request,
@@ -122,6 +241,7 @@ async def get_audit_logs(
     severity: Optional[str] = None,
     startTime: Optional[str] = None,
     endTime: Optional[str] = None,
+    correlationId: Optional[str] = None,
     limit: int = 100,
     nextToken: Optional[str] = None
 ):
@@ -134,16 +254,19 @@ async def get_audit_logs(
     - severity: Filter by severity (INFO, WARNING, ERROR, CRITICAL)
     - startTime: ISO timestamp for start of range
     - endTime: ISO timestamp for end of range
+    - correlationId: Filter by request ID (see /admin/audit/trace for a full trace)
     - limit: Maximum number of logs to return (default 100)
-    - nextToken: Pagination token
+    - nextToken: Pagination cursor (nextCursor of the previous page)
     """
+    limit, _ = _pagination_params(limit)
     try:
-        logs, next_token = db.get_audit_logs(
+        page = db.get_audit_logs(
             event_type=eventType,
             user_id=userId,
             severity=severity,
             start_time=startTime,
             end_time=endTime,
+            request_id=correlationId,
             limit=limit,
             next_token=nextToken
         )
@@ -155,16 +278,35 @@ async def get_audit_logs(
             user_role=get_user_role(request),
             resource_type="audit_logs",
             action="query",
-            details={"filters": {"eventType": eventType, "userId": userId, "severity": severity}}
+            details={"filters": {"eventType": eventType, "userId": userId, "severity": severity, "correlationId": correlationId}}
         )
         
-        return {
-            "items": logs,
-            "count": len(logs),
-            "nextToken": next_token
-        }
+        return create_paginated_response(page, limit)
+    except Exception as e:
+        raise _server_error(e, "AUDIT_QUERY_FAILED")
+
+
+@app.get("/api/v1/admin/audit/trace/{request_id}")
+@require_role("admin")
+async def trace_audit_request(request: Request, request_id: str):
+    """
+    All audit events recorded while handling one request, oldest first (Admin only).
+    request_id is the API Gateway request ID (x-amzn-RequestId / X-Request-Id response header).
+    """
+    try:
+        events = db.get_audit_logs_by_request_id(request_id)
     except Exception as e:
-        raise HTTPException(500, detail={"code": "AUDIT_QUERY_FAILED", "message": str(e)})
+        raise _server_error(e, "AUDIT_QUERY_FAILED")
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_READ,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        resource_type="audit_logs",
+        action="trace",
+        details={"traceRequestId": request_id}
+    )
+    return {"success": True, "requestId": request_id, "items": events, "count": len(events)}
 
 
 # -------- Admin - Dashboard Stats
@@ -178,7 +320,20 @@ async def get_admin_dashboard_stats(request: Request):
         stats = db.get_dashboard_stats()
         return {"success": True, "data": stats}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "STATS_FAILED", "message": str(e)})
+        raise _server_error(e, "STATS_FAILED")
+
+
+@app.get("/api/v1/admin/stats")
+@require_role("admin")
+async def get_admin_stats(request: Request):
+    """
+    Aggregate platform statistics (Admin only).
+    Returned as a flat JSON object; cached for five minutes.
+    """
+    try:
+        return stats_service.get_stats()
+    except Exception as e:
+        raise _server_error(e, "STATS_FAILED")
 
 
 # -------- Admin - System Settings
@@ -204,7 +359,7 @@ async def get_system_settings(request: Request):
                 settings[key] = default_value
         return {"success": True, "data": settings}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SETTINGS_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "SETTINGS_FETCH_FAILED")
 
 
 @app.put("/api/v1/admin/settings")
@@ -218,6 +373,9 @@ async def update_system_settings(request: Request):
         user_id = get_user_id(request)
         
         for key, value in body.items():
+            if key == MAINTENANCE_SETTING_KEY:
+                maintenance_service.set_enabled(value, user_id, get_user_role(request))
+                continue
             db.put_system_setting(key, value, user_id)
         
         # Log this admin action
@@ -232,7 +390,24 @@ async def update_system_settings(request: Request):
         
         return {"success": True, "message": "Settings updated successfully"}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SETTINGS_UPDATE_FAILED", "message": str(e)})
+        raise _server_error(e, "SETTINGS_UPDATE_FAILED")
+
+
+@app.put("/api/v1/admin/maintenance")
+@require_role("admin")
+async def set_maintenance_mode(body: MaintenanceModeReq, request: Request):
+    """
+    Turn maintenance mode on or off (Admin only).
+    While on, non-admin requests get 503 MAINTENANCE_MODE.
+    """
+    status = maintenance_service.set_enabled(
+        body.enabled,
+        get_user_id(request),
+        get_user_role(request),
+        message=body.message,
+        ip_address=request.client.host if request.client else None
+    )
+    return {"success": True, "data": status}
 
 
 # -------- Security - Nonce Endpoint
@@ -309,8 +484,88 @@ def request_verification(req: RequestVerificationReq):
     return {"success": True, "message": "Verification code sent to email", "expiresIn": 600}
 
 
+def _save_refresh_session(tokens: Dict[str, Any], user_id: str, role: str, request: Optional[Request],
+                          created_at: Optional[int] = None) -> None:
+    now = int(time.time())
+    db.save_refresh(
+        tokens["refreshToken"],  # API v3 uses camelCase
+        {
+            "userId": user_id,
+            "role": role,
+            "expiresAt": tokens["refreshExpiresAt"],
+            "sessionId": tokens["sessionId"],
+            "jti": tokens["refreshJti"],
+            "organizationId": tokens["organizationId"],
+            # Login-time metadata is carried across rotations
+            "createdAt": created_at or now,
+            "refreshedAt": now,
+            "ipAddress": request.client.host if request and request.client else None,
+            "userAgent": request.headers.get("user-agent") if request else None,
+        }
+    )
+
+def _start_session(user_id: str, role: str, request: Optional[Request] = None,
+                   organization_id: Optional[str] = None) -> Dict[str, Any]:
+    """Issue tokens after a successful login, starting a new session"""
+    tokens = issue_tokens(user_id, role, organization_id=organization_id)
+    _save_refresh_session(tokens, user_id, role, request)
+    return tokens
+
+def _handle_refresh_token_reuse(user: Dict[str, Any], request: Request) -> None:
+    """
+    A refresh token was presented that was already rotated away while its
+    session is still active, which means it was replayed (possibly stolen).
+    Sign the user out everywhere.
+    """
+    revoked = db.revoke_user_refresh_tokens(user["id"])
+    audit_service.log_event(
+        event_type=AuditEventType.SECURITY_SUSPICIOUS_ACTIVITY,
+        user_id=user["id"],
+        user_role=user.get("role"),
+        action="refresh_token_reuse",
+        outcome="failure",
+        ip_address=request.client.host if request.client else None,
+        user_agent=request.headers.get("user-agent"),
+        details={"revokedSessions": revoked}
+    )
+    raise HTTPException(401, detail={"code":"AUTH_INVALID","message":"refresh token invalid"})
+
+USER_ROLES = ("patient", "doctor", "admin")
+
+# Roles open to public self-registration; the others are created by an admin via POST /api/v1/admin/users
+SELF_REGISTRATION_ROLES = tuple(
+    r.strip().lower() for r in os.environ.get("SELF_REGISTRATION_ROLES", "patient").split(",") if r.strip()
+)
+
+def _self_registration_role(requested: Optional[str]) -> str:
+    """The requested role (default patient) if it may be self-registered; 400 for unknown roles, 403 otherwise."""
+    role = requested or "patient"
+    if role not in USER_ROLES:
+        raise HTTPException(
+            400,
+            detail={"code": "INVALID_ROLE", "message": f"Role must be one of: {', '.join(USER_ROLES)}"}
+        )
+    # Admin accounts are only created by existing admins or seeded during initial deployment
+    if role == "admin":
+        raise HTTPException(
+            403,
+            detail={
+                "code": "ADMIN_RESTRICTED",
+                "message": "Admin accounts cannot be self-registered. Contact system administrator."
+            }
+        )
+    if role not in SELF_REGISTRATION_ROLES:
+        raise HTTPException(
+            403,
+            detail={
+                "code": "ROLE_RESTRICTED",
+                "message": f"{role.capitalize()} accounts are created by an administrator. Contact system administrator."
+            }
+        )
+    return role
+
 @app.post("/api/v1/auth/register", response_model=RegisterRes, status_code=201)
-def register(req: RegisterReq):
+def register(req: RegisterReq, request: Request):
     """
     Register new user - requires email verification code.
     
@@ -320,50 +575,28 @@ def register(req: RegisterReq):
     """
     email = req.email.lower().strip()
     
+    # Checked before the verification code is consumed, so a refused role can be retried
+    role = _self_registration_role(req.role)
+    
     # Verify the email verification code
     if not db.verify_and_consume_code(email, req.verificationCode, "registration"):
         raise HTTPException(400, detail={"code": "INVALID_CODE", "message": "Invalid or expired verification code"})
     
-    # Double-check email is not taken (race condition protection)
+    # Fast path; concurrent sign-ups are caught by create_user's email guard
     existing = db.get_user_by_email(email)
     if existing:
         raise HTTPException(409, detail={"code": "EMAIL_TAKEN", "message": "Email is already registered"})
     
-    # Validate password strength
-    is_valid, error_msg = PasswordValidator.validate(req.password)
-    if not is_valid:
-        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": error_msg})
-    
     uid = f"usr_{uuid.uuid4().hex[:8]}"
     
-    # API v3: role is required in request, default to patient if not provided
-    role = req.role.lower() if req.role else "patient"
-    
-    # Security: Restrict admin role registration
-    # Admin accounts can only be created by existing admins via separate endpoint
-    # or the first admin is seeded during initial deployment
-    if role == "admin":
-        raise HTTPException(
-            403, 
-            detail={
-                "code": "ADMIN_RESTRICTED", 
-                "message": "Admin accounts cannot be self-registered. Contact system administrator."
-            }
-        )
-    
-    # Validate role is one of the allowed values
-    allowed_roles = ["patient", "doctor"]
-    if role not in allowed_roles:
-        raise HTTPException(
-            400, 
-            detail={
-                "code": "INVALID_ROLE", 
-                "message": f"Role must be one of: {', '.join(allowed_roles)}"
-            }
-        )
+    # Validate password strength (stricter for medical staff)
+    strength = validate_password_strength(req.password, [email], role)
+    if not strength.is_valid:
+        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": strength.message, "errors": strength.errors})
     
     # Generate MFA secret at registration time (mandatory for medical system)
     mfa_secret = generate_mfa_secret()
+    now = datetime.now(timezone.utc).isoformat()
     
     user = {
         "id": uid,
@@ -374,9 +607,13 @@ def register(req: RegisterReq):
         "emailVerified": True,  # Email is verified through the code
         "mfaSecret": mfa_secret,  # MFA is enabled from the start
         "mfaEnabled": True,
-        "createdAt": datetime.now(timezone.utc).isoformat()
+        "createdAt": now,
+        "passwordChangedAt": now
     }
-    db.put_user(user)
+    try:
+        db.create_user(user)
+    except db.EmailTakenError:
+        raise HTTPException(409, detail={"code": "EMAIL_TAKEN", "message": "Email is already registered"})
     
     # Send welcome email with MFA secret
     try:
@@ -387,15 +624,7 @@ def register(req: RegisterReq):
         # Don't fail registration if email fails - user can still use the MFA secret from response
     
     # Generate tokens
-    tokens = issue_tokens(uid, user["role"])
-    db.save_refresh(
-        tokens["refreshToken"],  # API v3 uses camelCase
-        {
-            "userId": uid, 
-            "role": user["role"], 
-            "expiresAt": int(time.time()) + int(os.environ.get("REFRESH_TTL_SECONDS", "604800"))
-        }
-    )
+    tokens = _start_session(uid, user["role"], request, db.organization_of(user))
     
     # Log successful registration with MFA enabled
     audit_service.log_event(
@@ -412,7 +641,81 @@ def register(req: RegisterReq):
         mfaSecret=mfa_secret  # Include MFA secret in response for immediate setup
     )
 
-@app.post("/api/v1/auth/login")
+# Refuse logins without MFA that imply impossible travel (otherwise they are only audited)
+IMPOSSIBLE_TRAVEL_STEP_UP = os.environ.get("IMPOSSIBLE_TRAVEL_STEP_UP", "false").lower() == "true"
+
+def _login_geo(request: Request, email: Optional[str], endpoint: str) -> Optional[GeoPoint]:
+    """
+    Resolve the client's location for the auth audit trail and refuse logins
+    from outside ALLOWED_COUNTRIES or, with BLOCK_VPN, through a VPN.
+    """
+    client_ip = request.client.host if request.client else None
+    location = resolve_location(client_ip, request.headers)
+    reason = location_denial_reason(location)
+    if reason:
+        audit_service.log_login_failure(
+            email=email,
+            reason="vpn_blocked" if reason == "vpn" else "country_not_allowed",
+            ip_address=client_ip,
+            user_agent=request.headers.get("user-agent"),
+            geo=location.to_dict()
+        )
+        _auth_metric(endpoint, "location_not_permitted")
+        raise HTTPException(403, detail={"code": "LOCATION_NOT_PERMITTED", "message": "Access not permitted from your location"})
+    return location
+
+def _check_login_location(u: Dict[str, Any], request: Request, mfa_verified: bool, location: Optional[GeoPoint] = None) -> None:
+    """
+    Compare a successful login's location with the user's previous login
+    and record it as the new last login. Impossible travel is audited as
+    suspicious activity; with IMPOSSIBLE_TRAVEL_STEP_UP a login that did not
+    pass MFA is refused and the previous location kept.
+    """
+    client_ip = request.client.host if request.client else None
+    now = datetime.now(timezone.utc)
+    location = location or resolve_location(client_ip, request.headers)
+    previous = GeoPoint.from_dict(u.get("lastLoginGeo"))
+    if location and previous and u.get("lastLoginAt"):
+        previous_at = datetime.fromisoformat(u["lastLoginAt"].replace("Z", "+00:00"))
+        travel = assess_travel(previous, previous_at, location, now)
+        if travel.impossible:
+            blocked = IMPOSSIBLE_TRAVEL_STEP_UP and not mfa_verified
+            audit_service.log_event(
+                event_type=AuditEventType.SECURITY_SUSPICIOUS_ACTIVITY,
+                user_id=u["id"],
+                user_role=u.get("role"),
+                resource_type="user",
+                resource_id=u["id"],
+                action="impossible_travel",
+                outcome="denied" if blocked else "success",
+                ip_address=client_ip,
+                details={
+                    "previousIp": u.get("lastLoginIp"),
+                    "previousCountry": previous.country_code,
+                    "country": location.country_code,
+                    "mfaVerified": mfa_verified,
+                    **travel.to_dict()
+                }
+            )
+            if blocked:
+                raise HTTPException(403, detail={"code": "STEP_UP_REQUIRED", "message": "Sign-in from an unusual location; enable two-factor authentication or reset your password"})
+    
+    updates = {"lastLoginIp": client_ip, "lastLoginAt": now.isoformat()}
+    if location:
+        updates["lastLoginGeo"] = location.to_dict()
+    db.update_user(u["id"], updates)
+
+LOGIN_ENDPOINT = "/api/v1/auth/login"
+MFA_LOGIN_ENDPOINT = "/api/v1/auth/mfa/login"
+
+def _auth_metric(endpoint: str, failure_reason: Optional[str] = None) -> None:
+    """Count an authentication outcome (no-op unless METRICS_NAMESPACE is set)"""
+    if failure_reason:
+        metrics.count(AUTH_FAILURE, Endpoint=endpoint, Reason=failure_reason)
+    else:
+        metrics.count(AUTH_SUCCESS, Endpoint=endpoint)
+
+@app.post(LOGIN_ENDPOINT)
 def login(req: LoginReq, request: Request):
     """
     Login user - API v3 compliant
@@ -423,18 +726,42 @@ def login(req: LoginReq, request: Request):
     # Extract client info for audit logging
     client_ip = request.client.host if request.client else None
     user_agent = request.headers.get("user-agent")
+    location = _login_geo(request, req.email, LOGIN_ENDPOINT)
+    geo = location.to_dict() if location else None
     
-    u = db.get_user_by_email(req.email)
+    with metrics.timer(DB_OPERATION_DURATION, Operation="get_user_by_email", Endpoint=LOGIN_ENDPOINT):
+        u = db.get_user_by_email(req.email, include_secrets=True)
     if not u or not verify_pw(req.password, u["password"]):
         # Log failed login attempt
         audit_service.log_login_failure(
             email=req.email,
             reason="invalid_credentials",
             ip_address=client_ip,
-            user_agent=user_agent
+            user_agent=user_agent,
+            geo=geo
         )
+        _auth_metric(LOGIN_ENDPOINT, "invalid_credentials")
         raise HTTPException(401, detail={"code":"AUTH_INVALID","message":"invalid credentials"})
     
+    # Upgrade the stored hash if the Argon2 parameters were raised since it was created
+    if needs_rehash(u["password"]):
+        try:
+            db.update_user(u["id"], {"password": hash_pw(req.password)})
+        except Exception as e:
+            print(f"[LOGIN] Password rehash failed for {u['id']}: {e}")
+    
+    password_expired = is_password_expired(u)
+    if password_expired and PASSWORD_EXPIRY_BLOCK:
+        audit_service.log_login_failure(
+            email=req.email,
+            reason="password_expired",
+            ip_address=client_ip,
+            user_agent=user_agent,
+            geo=geo
+        )
+        _auth_metric(LOGIN_ENDPOINT, "password_expired")
+        raise HTTPException(403, detail={"code": "PASSWORD_EXPIRED", "message": "Password has expired, reset it to sign in"})
+    
     # Check if MFA is enabled for this user
     if u.get("mfaEnabled") and u.get("mfaSecret"):
         # Generate temporary token for MFA challenge
@@ -444,33 +771,30 @@ def login(req: LoginReq, request: Request):
         audit_service.log_event(
             event_type=AuditEventType.MFA_CHALLENGE,
             user_id=u["id"],
-            details={"ip_address": client_ip}
+            details={"ip_address": client_ip, "geo": geo}
         )
+        metrics.count("MfaChallengeIssued", Endpoint=LOGIN_ENDPOINT)
         
         return {
             "mfaRequired": True,
             "tempToken": temp_token,
-            "message": "MFA verification required"
+            "message": "MFA verification required",
+            "passwordExpired": password_expired
         }
     
     # No MFA - generate tokens directly
-    tokens = issue_tokens(u["id"], u["role"])
-    db.save_refresh(
-        tokens["refreshToken"],  # API v3 uses camelCase
-        {
-            "userId": u["id"], 
-            "role": u["role"], 
-            "expiresAt": int(time.time()) + int(os.environ.get("REFRESH_TTL_SECONDS","604800"))
-        }
-    )
+    _check_login_location(u, request, mfa_verified=False, location=location)
+    tokens = _start_session(u["id"], u["role"], request, db.organization_of(u))
     
     # Log successful login
     audit_service.log_login_success(
         user_id=u["id"],
         user_role=u["role"],
         ip_address=client_ip,
-        user_agent=user_agent
+        user_agent=user_agent,
+        geo=geo
     )
+    _auth_metric(LOGIN_ENDPOINT)
     
     # API v3: Return flat response with accessJwt, refreshToken, expiresIn, and user info
     return LoginRes(
@@ -480,9 +804,10 @@ def login(req: LoginReq, request: Request):
         user={
             "id": u["id"],
             "email": u["email"],
-            "role": u["role"],
+            "role": role_name(u["role"]),
             "name": u.get("name", u["email"].split("@")[0])
-        }
+        },
+        passwordExpired=password_expired
     )
 
 
@@ -500,7 +825,7 @@ class MfaSetupRes(BaseModel):
 class MfaVerifyReq(BaseModel):
     code: str
 
-@app.post("/api/v1/auth/mfa/login", response_model=LoginRes)
+@app.post(MFA_LOGIN_ENDPOINT, response_model=LoginRes)
 def mfa_login(req: MfaLoginReq, request: Request):
     """
     Complete MFA login with TOTP code.
@@ -513,10 +838,14 @@ def mfa_login(req: MfaLoginReq, request: Request):
     # Verify temp token
     claims = verify_temp_token(req.tempToken)
     user_id = claims["sub"]
+    location = _login_geo(request, None, MFA_LOGIN_ENDPOINT)
+    geo = location.to_dict() if location else None
     
     # Get user and verify MFA code
-    u = db.get_user(user_id)
+    with metrics.timer(DB_OPERATION_DURATION, Operation="get_user", Endpoint=MFA_LOGIN_ENDPOINT):
+        u = db.get_user(user_id, include_secrets=True)
     if not u:
+        _auth_metric(MFA_LOGIN_ENDPOINT, "user_not_found")
         raise HTTPException(401, detail={"code": "AUTH_INVALID", "message": "user not found"})
     
     mfa_secret = u.get("mfaSecret")
@@ -528,33 +857,34 @@ def mfa_login(req: MfaLoginReq, request: Request):
         audit_service.log_event(
             event_type=AuditEventType.MFA_FAILURE,
             user_id=user_id,
-            details={"ip_address": client_ip, "reason": "invalid_code"}
+            details={"ip_address": client_ip, "reason": "invalid_code", "geo": geo}
         )
+        _auth_metric(MFA_LOGIN_ENDPOINT, "invalid_mfa_code")
         raise HTTPException(401, detail={"code": "MFA_INVALID", "message": "invalid MFA code"})
     
+    password_expired = is_password_expired(u)
+    if password_expired and PASSWORD_EXPIRY_BLOCK:
+        _auth_metric(MFA_LOGIN_ENDPOINT, "password_expired")
+        raise HTTPException(403, detail={"code": "PASSWORD_EXPIRED", "message": "Password has expired, reset it to sign in"})
+    
     # MFA verified - issue full tokens
-    tokens = issue_tokens(u["id"], u["role"])
-    db.save_refresh(
-        tokens["refreshToken"],
-        {
-            "userId": u["id"], 
-            "role": u["role"], 
-            "expiresAt": int(time.time()) + int(os.environ.get("REFRESH_TTL_SECONDS","604800"))
-        }
-    )
+    _check_login_location(u, request, mfa_verified=True, location=location)
+    tokens = _start_session(u["id"], u["role"], request, db.organization_of(u))
     
     # Log successful MFA login
     audit_service.log_event(
         event_type=AuditEventType.MFA_SUCCESS,
         user_id=u["id"],
-        details={"ip_address": client_ip}
+        details={"ip_address": client_ip, "geo": geo}
     )
     audit_service.log_login_success(
         user_id=u["id"],
         user_role=u["role"],
         ip_address=client_ip,
-        user_agent=user_agent
+        user_agent=user_agent,
+        geo=geo
     )
+    _auth_metric(MFA_LOGIN_ENDPOINT)
     
     return LoginRes(
         accessJwt=tokens["accessJwt"],
@@ -563,9 +893,10 @@ def mfa_login(req: MfaLoginReq, request: Request):
         user={
             "id": u["id"],
             "email": u["email"],
-            "role": u["role"],
+            "role": role_name(u["role"]),
             "name": u.get("name", u["email"].split("@")[0])
-        }
+        },
+        passwordExpired=password_expired
     )
 
 
@@ -617,7 +948,7 @@ def mfa_verify(req: MfaVerifyReq, request: Request):
     Requires the 6-digit code from authenticator app to confirm setup.
     """
     user_id = get_user_id(request)
-    u = db.get_user(user_id)
+    u = db.get_user(user_id, include_secrets=True)
     if not u:
         raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "user not found"})
     
@@ -684,7 +1015,7 @@ def mfa_status(request: Request):
     Get MFA status for the current user.
     """
     user_id = get_user_id(request)
-    u = db.get_user(user_id)
+    u = db.get_user(user_id, include_secrets=True)
     if not u:
         raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "user not found"})
     
@@ -694,27 +1025,37 @@ def mfa_status(request: Request):
     }
 
 @app.post("/api/v1/auth/refresh", response_model=RefreshRes)
-def refresh(req: RefreshReq):
+def refresh(req: RefreshReq, request: Request):
     """
     Refresh access token - API v3 compliant
     Returns flat response with accessJwt and refreshToken
     """
     # API v3 uses camelCase for refreshToken in request
     refresh_token = req.refreshToken
+    claims = decode_refresh_token(refresh_token)
+    user = db.get_user(claims["sub"]) if claims else None
+    if not user:
+        raise HTTPException(401, detail={"code":"AUTH_INVALID","message":"refresh token invalid"})
+    if user.get("isActive") is False:
+        db.revoke_user_refresh_tokens(user["id"])
+        raise HTTPException(401, detail={"code":"ACCOUNT_DISABLED","message":"account is deactivated"})
+    
+    # Refresh tokens are single-use. A token that is gone while its session
+    # (sid) still holds a newer one was already rotated: treat it as replayed.
     sess = db.take_refresh(refresh_token)
-    if not sess or sess.get("expiresAt",0) < int(time.time()):
+    if not sess:
+        session_id = claims.get("sid")
+        if session_id and db.has_refresh_session(user["id"], session_id):
+            _handle_refresh_token_reuse(user, request)
+        raise HTTPException(401, detail={"code":"AUTH_INVALID","message":"refresh token invalid"})
+    if sess.get("expiresAt",0) < int(time.time()):
         raise HTTPException(401, detail={"code":"AUTH_INVALID","message":"refresh token invalid"})
     
-    # Generate new tokens
-    tokens = issue_tokens(sess["userId"], sess["role"])
-    db.save_refresh(
-        tokens["refreshToken"],  # API v3 uses camelCase
-        {
-            "userId": sess["userId"], 
-            "role": sess["role"], 
-            "expiresAt": int(time.time()) + int(os.environ.get("REFRESH_TTL_SECONDS","604800"))
-        }
-    )
+    # Generate new tokens within the same session, with the user's current role
+    # (an admin may have changed it since the session started)
+    tokens = issue_tokens(user["id"], user["role"], session_id=sess.get("sessionId"),
+                          organization_id=db.organization_of(user))
+    _save_refresh_session(tokens, user["id"], user["role"], request, created_at=sess.get("createdAt"))
     
     # API v3: Return flat response with accessJwt and refreshToken
     return RefreshRes(
@@ -733,6 +1074,71 @@ def logout(req: RefreshReq):
     # API v3 doc shows 204, but returning 200 with success message
     return {"success": True, "message": "Successfully logged out"}
 
+def _current_session_id(request: Request) -> Optional[str]:
+    claims = getattr(request.state, "claims", None) or {}
+    return claims.get("sid")
+
+def _audit_session_revoked(request: Request, user_id: str, session_id: Optional[str], revoked: int) -> None:
+    audit_service.log_event(
+        event_type=AuditEventType.AUTH_SESSION_REVOKED,
+        user_id=user_id,
+        user_role=get_user_role(request),
+        resource_type="session",
+        resource_id=session_id,
+        action="revoke_session" if session_id else "revoke_other_sessions",
+        outcome="success",
+        ip_address=request.client.host if request.client else None,
+        user_agent=request.headers.get("user-agent"),
+        details={"revokedSessions": revoked}
+    )
+
+@app.get("/api/v1/auth/sessions", response_model=List[AuthSession])
+def list_sessions(request: Request):
+    """
+    List the caller's active sessions (unexpired refresh tokens).
+    
+    Sessions are identified by a stable sessionId rather than the refresh
+    token jti, which changes on every rotation.
+    """
+    user_id = get_user_id(request)
+    current = _current_session_id(request)
+    sessions = [
+        AuthSession(
+            sessionId=s["sessionId"],
+            issuedAt=s.get("createdAt"),
+            lastRefreshedAt=s.get("refreshedAt"),
+            expiresAt=s["expiresAt"],
+            ipAddress=s.get("ipAddress"),
+            userAgent=s.get("userAgent"),
+            current=s["sessionId"] == current
+        )
+        for s in db.list_refresh_sessions(user_id) if s.get("sessionId")
+    ]
+    return sorted(sessions, key=lambda s: s.issuedAt or 0, reverse=True)
+
+@app.delete("/api/v1/auth/sessions/{session_id}")
+def revoke_session(session_id: str, request: Request):
+    """
+    Sign out one of the caller's sessions; its refresh token stops working.
+    
+    Access tokens are stateless, so one already issued to that session stays
+    valid until it expires (JWT_EXPIRE_SECONDS or the per-role override).
+    """
+    user_id = get_user_id(request)
+    revoked = db.revoke_refresh_session(user_id, session_id)
+    if not revoked:
+        raise HTTPException(404, detail={"code": "SESSION_NOT_FOUND", "message": "session not found"})
+    _audit_session_revoked(request, user_id, session_id, revoked)
+    return {"success": True, "revoked": revoked}
+
+@app.delete("/api/v1/auth/sessions")
+def revoke_other_sessions(request: Request):
+    """Sign out every session of the caller except the one making this request"""
+    user_id = get_user_id(request)
+    revoked = db.revoke_user_refresh_tokens(user_id, except_session_id=_current_session_id(request))
+    _audit_session_revoked(request, user_id, None, revoked)
+    return {"success": True, "revoked": revoked}
+
 @app.post("/api/v1/auth/reset-password", status_code=200)
 def reset_password(req: ResetPasswordReq):
     """
@@ -741,6 +1147,7 @@ def reset_password(req: ResetPasswordReq):
     Flow:
     1. Call /auth/request-verification with type="password_reset"
     2. Receive code via email
+       (or: /auth/request-otp, then /auth/verify-otp with the SMS code)
     3. Submit new password with the verification code
     """
     email = req.email.lower().strip()
@@ -750,18 +1157,20 @@ def reset_password(req: ResetPasswordReq):
         raise HTTPException(400, detail={"code": "INVALID_CODE", "message": "Invalid or expired verification code"})
     
     # Find user by email
-    user = db.get_user_by_email(email)
+    user = db.get_user_by_email(email, include_secrets=True)
     if not user:
         raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Account not found"})
     
-    # Validate password strength (enhanced validation)
-    is_valid, error_msg = PasswordValidator.validate(req.newPassword)
-    if not is_valid:
-        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": error_msg})
+    # Validate password strength (stricter for medical staff)
+    strength = validate_password_strength(req.newPassword, [email, user.get("name")], user.get("role", "patient"))
+    if not strength.is_valid:
+        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": strength.message, "errors": strength.errors})
+    
+    if is_password_reused(req.newPassword, user):
+        raise HTTPException(400, detail={"code": "PASSWORD_REUSED", "message": "Password was used recently, choose a different one"})
     
     # Update password
-    user["password"] = hash_pw(req.newPassword)
-    db.put_user(user)
+    db.update_user(user["id"], password_change_fields(req.newPassword, user))
     
     # Log password reset
     audit_service.log_event(
@@ -772,6 +1181,136 @@ def reset_password(req: ResetPasswordReq):
     
     return {"success": True, "message": "Password reset successful"}
 
+# -------- SMS one-time codes (alternative to the emailed password reset code)
+OTP_TTL_SECONDS = 300
+OTP_RESEND_SECONDS = 60
+OTP_MAX_ATTEMPTS = 5
+# After OTP_MAX_ATTEMPTS wrong codes no new code is sent for this long; resends do not reset the count
+OTP_LOCKOUT_SECONDS = 3600
+
+_OTP_SENT_MESSAGE = "If the account has a phone number, a code has been sent by SMS"
+
+@app.post("/api/v1/auth/request-otp", status_code=200)
+def request_otp(req: RequestOtpReq):
+    """
+    Send a one-time code by SMS to the account's phone number.
+    The response does not reveal whether the account exists, has a phone,
+    or was sent a code: unknown, throttled and locked requests get the same 200.
+    """
+    email = req.email.lower().strip()
+    sent = {"success": True, "message": _OTP_SENT_MESSAGE, "expiresIn": OTP_TTL_SECONDS}
+    user = db.get_user_by_email(email, include_secrets=True)
+    now = int(time.time())
+    if not user or not user.get("phone"):
+        status = "otp_no_phone" if user else "email_not_found"
+    elif int(user.get("otpLockedUntil", 0)) > now:
+        status = "otp_locked"
+    elif now - int(user.get("otpIssuedAt", 0)) < OTP_RESEND_SECONDS:
+        status = "otp_throttled"
+    else:
+        status = None
+    if status:
+        audit_service.log_event(
+            event_type=AuditEventType.AUTH_PASSWORD_RESET,
+            user_id=user["id"] if user else None,
+            details={"email": email, "status": status}
+        )
+        return sent
+    
+    # otpAttempts is kept, so resending does not grant more guesses
+    otp = crypto_service.generate_otp()
+    db.update_user(user["id"], {
+        "otpHash": crypto_service.hash_otp(otp),
+        "otpIssuedAt": now,
+        "otpExpiresAt": now + OTP_TTL_SECONDS,
+        "otpLockedUntil": None,
+    })
+    if not notification_service.send_sms(user["phone"], f"Your MeDUSA password reset code is {otp}. It expires in {OTP_TTL_SECONDS // 60} minutes."):
+        db.update_user(user["id"], {"otpHash": None, "otpIssuedAt": None, "otpExpiresAt": None})
+        raise HTTPException(500, detail={"code": "SMS_FAILED", "message": "Failed to send code by SMS"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.AUTH_PASSWORD_RESET,
+        user_id=user["id"],
+        details={"email": email, "status": "otp_sent"}
+    )
+    return sent
+
+@app.post("/api/v1/auth/verify-otp", status_code=200)
+def verify_otp(req: VerifyOtpReq):
+    """
+    Exchange an SMS code for a password reset verification code, which is
+    then submitted to /auth/reset-password as verificationCode.
+    The SMS code is single use. OTP_MAX_ATTEMPTS wrong tries, counted across
+    resends, discard the code and block new ones for OTP_LOCKOUT_SECONDS.
+    """
+    email = req.email.lower().strip()
+    invalid = HTTPException(400, detail={"code": "INVALID_CODE", "message": "Invalid or expired code"})
+    user = db.get_user_by_email(email, include_secrets=True)
+    if not user or not user.get("otpHash"):
+        raise invalid
+    
+    now = int(time.time())
+    if int(user.get("otpExpiresAt", 0)) < now:
+        db.update_user(user["id"], {"otpHash": None, "otpExpiresAt": None})
+        raise invalid
+    # The attempt is counted before the code is checked, so parallel guesses share the cap
+    attempt = db.consume_otp_attempt(user["id"], OTP_MAX_ATTEMPTS)
+    if attempt is None:
+        raise invalid
+    if not crypto_service.verify_otp(req.otp.strip(), user["otpHash"]):
+        if attempt >= OTP_MAX_ATTEMPTS:
+            db.update_user(user["id"], {"otpHash": None, "otpExpiresAt": None, "otpAttempts": None,
+                                        "otpLockedUntil": now + OTP_LOCKOUT_SECONDS})
+        audit_service.log_event(
+            event_type=AuditEventType.AUTH_PASSWORD_RESET,
+            user_id=user["id"],
+            outcome="failure",
+            details={"email": email, "status": "otp_locked" if attempt >= OTP_MAX_ATTEMPTS else "otp_invalid"}
+        )
+        raise invalid
+    
+    db.update_user(user["id"], {"otpHash": None, "otpExpiresAt": None, "otpAttempts": None})
+    code = db.generate_verification_code()
+    if not db.save_verification_code(email, code, "password_reset"):
+        raise HTTPException(500, detail={"code": "INTERNAL_ERROR", "message": "Failed to generate verification code"})
+    audit_service.log_event(
+        event_type=AuditEventType.AUTH_PASSWORD_RESET,
+        user_id=user["id"],
+        details={"email": email, "status": "otp_verified"}
+    )
+    return {"success": True, "verificationCode": code, "expiresIn": db.VERIFICATION_CODE_TTL}
+
+@app.post("/api/v1/auth/change-password", status_code=200)
+def change_password(req: ChangePasswordReq, request: Request):
+    """
+    Change the caller's password. Other sessions are signed out.
+    """
+    user_id = get_user_id(request)
+    user = db.get_user(user_id, include_secrets=True)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "user not found"})
+    if not verify_pw(req.currentPassword, user.get("password")):
+        raise HTTPException(401, detail={"code": "INVALID_CREDENTIALS", "message": "Current password is incorrect"})
+    
+    strength = validate_password_strength(req.newPassword, [user.get("email"), user.get("name")], user.get("role", "patient"))
+    if not strength.is_valid:
+        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": strength.message, "errors": strength.errors})
+    if is_password_reused(req.newPassword, user):
+        raise HTTPException(400, detail={"code": "PASSWORD_REUSED", "message": "Password was used recently, choose a different one"})
+    
+    db.update_user(user_id, password_change_fields(req.newPassword, user))
+    revoked = db.revoke_user_refresh_tokens(user_id, except_session_id=_current_session_id(request))
+    
+    audit_service.log_event(
+        event_type=AuditEventType.AUTH_PASSWORD_CHANGE,
+        user_id=user_id,
+        user_role=user.get("role"),
+        ip_address=request.client.host if request.client else None,
+        details={"sessionsRevoked": revoked}
+    )
+    return {"success": True, "message": "Password changed"}
+
 @app.post("/api/v1/auth/send-verification-code", status_code=200)
 def send_verification_code(req: SendVerificationCodeReq):
     """
@@ -818,10 +1357,11 @@ def send_password_reset_code(req: SendVerificationCodeReq):
 # -------- Admin User Management (Admin-only)
 
 class CreateAdminReq(BaseModel):
-    """Request to create an admin account (admin-only)"""
+    """Request to create an admin or staff account (admin-only)"""
     email: str
     password: str
     name: Optional[str] = None
+    role: str = "admin"  # any of USER_ROLES or a custom role; privileged roles cannot self-register
 
 class CreateAdminRes(BaseModel):
     """Response for admin creation"""
@@ -831,14 +1371,30 @@ class CreateAdminRes(BaseModel):
     mfaSecret: str
     message: str
 
+def _assignable_role(requested: str) -> str:
+    """
+    The stored role for an admin-assigned role name: built-in roles as is, custom
+    roles of the caller's organization with their permissions copied on; 400 otherwise.
+    """
+    role = requested.strip().lower()
+    if role in USER_ROLES:
+        return role
+    custom_role = db.get_role(role)
+    if not custom_role:
+        raise HTTPException(400, detail={
+            "code": "INVALID_ROLE",
+            "message": f"Role must be one of: {', '.join(USER_ROLES)}, or a custom role from POST /api/v1/admin/roles"
+        })
+    return CustomRole(role, tuple(custom_role["permissions"])).as_str()
+
 @app.post("/api/v1/admin/users", response_model=CreateAdminRes, status_code=201)
 @require_role("admin")
 async def create_admin_user(req: CreateAdminReq, request: Request):
     """
     Create a new admin or doctor account (Admin only).
     
-    This is the only way to create admin accounts.
-    Regular users cannot self-register as admin.
+    This is the only way to create admin accounts, and to create accounts
+    for roles outside SELF_REGISTRATION_ROLES (doctors by default).
     
     Flow:
     1. Existing admin calls this endpoint
@@ -846,6 +1402,8 @@ async def create_admin_user(req: CreateAdminReq, request: Request):
     3. New user logs in with provided credentials
     """
     email = req.email.lower().strip()
+    role = (req.role or "admin").strip().lower()
+    stored_role = _assignable_role(role)
     
     # Check if email is already registered
     existing = db.get_user_by_email(email)
@@ -853,30 +1411,35 @@ async def create_admin_user(req: CreateAdminReq, request: Request):
         raise HTTPException(409, detail={"code": "EMAIL_TAKEN", "message": "Email is already registered"})
     
     # Validate password strength
-    is_valid, error_msg = PasswordValidator.validate(req.password)
-    if not is_valid:
-        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": error_msg})
+    strength = validate_password_strength(req.password, [email, req.name], role)
+    if not strength.is_valid:
+        raise HTTPException(400, detail={"code": "INVALID_PASSWORD", "message": strength.message, "errors": strength.errors})
     
     uid = f"usr_{uuid.uuid4().hex[:8]}"
     mfa_secret = generate_mfa_secret()
+    now = datetime.now(timezone.utc).isoformat()
     
     user = {
         "id": uid,
         "email": email,
-        "role": "admin",
+        "role": stored_role,
         "name": req.name or email.split('@')[0],
         "password": hash_pw(req.password),
         "emailVerified": True,
         "mfaSecret": mfa_secret,
         "mfaEnabled": True,
-        "createdAt": datetime.now(timezone.utc).isoformat(),
+        "createdAt": now,
+        "passwordChangedAt": now,
         "createdBy": get_user_id(request)  # Track who created this admin
     }
-    db.put_user(user)
+    try:
+        db.create_user(user)
+    except db.EmailTakenError:
+        raise HTTPException(409, detail={"code": "EMAIL_TAKEN", "message": "Email is already registered"})
     
     # Send welcome email with MFA secret
     try:
-        email_service.send_welcome_with_mfa(email, mfa_secret, "admin")
+        email_service.send_welcome_with_mfa(email, mfa_secret, role)
     except Exception as e:
         print(f"[CreateAdmin] Warning: Failed to send welcome email: {e}")
     
@@ -888,6 +1451,7 @@ async def create_admin_user(req: CreateAdminReq, request: Request):
             "action": "admin_user_created",
             "new_user_id": uid,
             "new_user_email": email,
+            "new_user_role": role,
             "created_by": get_user_id(request)
         }
     )
@@ -895,12 +1459,12 @@ async def create_admin_user(req: CreateAdminReq, request: Request):
     return CreateAdminRes(
         userId=uid,
         email=email,
-        role="admin",
+        role=role,
         mfaSecret=mfa_secret,
-        message="Admin account created successfully. MFA setup required on first login."
+        message=f"{role.capitalize()} account created successfully. MFA setup required on first login."
     )
 
-@app.get("/api/v1/admin/users")
+@app.get("/api/v1/admin/users", response_model=PaginatedResponse[dict])
 @require_role("admin")
 async def list_users(request: Request, role: Optional[str] = None, limit: int = 50, nextToken: Optional[str] = None):
     """
@@ -908,25 +1472,26 @@ async def list_users(request: Request, role: Optional[str] = None, limit: int =
     
     Optional filter by role: admin, doctor, patient
     """
+    limit, _ = _pagination_params(limit)
     try:
-        users, next_token = db.list_users(role=role, limit=limit, next_token=nextToken)
-        return {
-            "items": [
+        page = db.list_users(role=role, limit=limit, next_token=nextToken)
+        page.items = [
                 {
                     "id": u["id"],
                     "email": u["email"],
-                    "role": u["role"],
+                    "role": role_name(u["role"]),
                     "name": u.get("name"),
                     "emailVerified": u.get("emailVerified", False),
                     "mfaEnabled": u.get("mfaEnabled", False),
                     "createdAt": u.get("createdAt")
                 }
-                for u in users
-            ],
-            "nextToken": next_token
-        }
+                for u in page.items
+            ]
+        return create_paginated_response(page, limit)
+    except ValueError as e:
+        raise HTTPException(400, detail={"code": "INVALID_PAGINATION", "message": str(e)})
     except Exception as e:
-        raise HTTPException(500, detail={"code": "LIST_USERS_FAILED", "message": str(e)})
+        raise _server_error(e, "LIST_USERS_FAILED")
 
 
 # -------- Me
@@ -938,36 +1503,171 @@ def me(request: Request):
     if not u:
         raise HTTPException(404, detail={"code":"USER_NOT_FOUND","message":"user not found"})
     return UserOut(
-        id=u["id"], email=u["email"], role=u["role"],
-        name=u.get("name"), createdAt=datetime.fromisoformat(u["createdAt"])
+        id=u["id"], email=u["email"], role=role_name(u["role"]),
+        name=u.get("name"), createdAt=datetime.fromisoformat(u["createdAt"]),
+        profilePictureUrl=_profile_picture_url(u),
+        preferences=_user_preferences(u)
     )
 
-# -------- Files (S3)
-@app.post("/api/v1/files/presign", response_model=PresignRes)
-def files_presign(req: PresignReq, request: Request):
-    if req.scope not in ("pose","report"):
-        raise HTTPException(400, detail={"code":"SCOPE_INVALID","message":"scope must be pose or report"})
-    claims = getattr(request.state, "claims", {})
-    owner = req.patientId or claims.get("sub")
-    key = storage.make_file_key(req.scope, owner, req.filename)
-    post = storage.presign_upload(key, req.contentType, ttl_sec=900)
-    # Return a simple shape (compatible with your FE): uploadUrl + key
-    return PresignRes(uploadUrl=post["url"], fileKey=key, expiresIn=900)
+def _user_preferences(user: Dict[str, Any]) -> UserPreferences:
+    """
+    Stored preferences with defaults for unset fields. A stored value that is
+    no longer valid (e.g. a language since removed from SUPPORTED_LANGUAGES)
+    falls back to its default instead of failing the request.
+    """
+    valid = {}
+    for key, value in (user.get("preferences") or {}).items():
+        try:
+            UserPreferences(**{key: value})
+            valid[key] = value
+        except ValidationError:
+            pass
+    return UserPreferences(**valid)
 
-@app.get("/api/v1/files/{fileKey:path}")
-def files_get(fileKey: str):
-    url = storage.presign_download(fileKey, ttl_sec=300)
-    return RedirectResponse(url)
+# -------- Profile pictures
+PROFILE_PICTURE_URL_TTL_SECONDS = 3600
 
-# -------- Poses
-@app.get("/api/v1/poses", response_model=PosePage)
-@require_role("admin", "doctor", "patient")
-async def poses_list(patientId: str, request: Request, nextToken: Optional[str] = None):
-    try:
-        # RBAC: Check ownership
-        user_id = get_user_id(request)
-        user_role = get_user_role(request)
-        
+def _profile_picture_url(user: Dict[str, Any]) -> Optional[str]:
+    key = user.get("profilePictureKey")
+    if not key:
+        return None
+    return storage.presign_download(key, ttl_sec=PROFILE_PICTURE_URL_TTL_SECONDS, residency=user.get("dataResidency"))
+
+def _get_own_user(request: Request, user_id: str) -> Dict[str, Any]:
+    """Users manage their own picture; admins anyone's"""
+    if get_user_role(request) != "admin" and user_id != get_user_id(request):
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: You can only change your own profile picture"})
+    user = db.get_user(user_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    return user
+
+@app.post("/api/v1/users/{user_id}/profile-picture", response_model=ProfilePictureRes)
+@require_role("patient", "doctor", "admin")
+async def upload_profile_picture(request: Request, user_id: str):
+    """
+    Upload a profile picture as the raw request body (Content-Type image/jpeg
+    or image/png, at most MAX_PROFILE_PICTURE_BYTES). Replaces any existing one.
+    """
+    user = _get_own_user(request, user_id)
+    content_type = (request.headers.get("content-type") or "").split(";")[0].strip().lower()
+    declared = request.headers.get("content-length")
+    if declared and declared.isdigit() and int(declared) > storage.MAX_PROFILE_PICTURE_BYTES:
+        raise HTTPException(413, detail={"code": "PICTURE_TOO_LARGE", "message": f"Picture must be at most {storage.MAX_PROFILE_PICTURE_BYTES} bytes"})
+    content = await request.body()
+    try:
+        key = storage.upload_profile_picture(user_id, content, content_type, residency=user.get("dataResidency"))
+    except ValueError as e:
+        code = 413 if len(content) > storage.MAX_PROFILE_PICTURE_BYTES else 415
+        raise HTTPException(code, detail={"code": "PICTURE_INVALID", "message": str(e)})
+    
+    previous = user.get("profilePictureKey")
+    if previous and previous != key:
+        storage.delete_object(previous, residency=user.get("dataResidency"))
+    db.update_user(user_id, {"profilePictureKey": key})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_UPDATE,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        resource_type="profile_picture",
+        resource_id=user_id,
+        action="upload",
+        details={"contentType": content_type, "size": len(content)}
+    )
+    return ProfilePictureRes(
+        profilePictureUrl=_profile_picture_url({**user, "profilePictureKey": key}),
+        expiresIn=PROFILE_PICTURE_URL_TTL_SECONDS
+    )
+
+@app.delete("/api/v1/users/{user_id}/profile-picture")
+@require_role("patient", "doctor", "admin")
+async def delete_profile_picture(request: Request, user_id: str):
+    user = _get_own_user(request, user_id)
+    key = user.get("profilePictureKey")
+    if not key:
+        raise HTTPException(404, detail={"code": "PICTURE_NOT_FOUND", "message": "User has no profile picture"})
+    storage.delete_object(key, residency=user.get("dataResidency"))
+    db.update_user(user_id, {"profilePictureKey": None})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_DELETE,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        resource_type="profile_picture",
+        resource_id=user_id,
+        action="delete"
+    )
+    return {"success": True, "message": "Profile picture deleted"}
+
+# -------- Files (S3)
+MAX_FILENAME_LENGTH = 255
+
+def _data_residency(user_id: Optional[str]) -> Optional[str]:
+    """
+    Where a patient's files are stored (user attribute dataResidency, see
+    storage.DATA_RESIDENCY_TARGETS); None means the default bucket.
+    """
+    user = db.get_user(user_id) if user_id else None
+    return (user or {}).get("dataResidency")
+
+@app.post("/api/v1/files/presign", response_model=PresignRes)
+def files_presign(req: PresignReq, request: Request):
+    if req.scope not in ("pose","report","import"):
+        raise HTTPException(400, detail={"code":"SCOPE_INVALID","message":"scope must be pose, report or import"})
+    if req.scope == "import" and get_user_role(request) != "admin":
+        raise HTTPException(403, detail={"code":"FORBIDDEN","message":"Only admins can upload imports"})
+    if not req.filename or len(req.filename) > MAX_FILENAME_LENGTH:
+        raise HTTPException(400, detail={"code":"FILENAME_INVALID","message":f"filename must be 1-{MAX_FILENAME_LENGTH} characters"})
+    claims = getattr(request.state, "claims", {})
+    owner = req.patientId or claims.get("sub")
+    try:
+        # The owner becomes a key segment; ids never contain separators
+        sanitize_identifier(owner)
+    except ValueError:
+        raise HTTPException(400, detail={"code":"PATIENT_ID_INVALID","message":"patientId is not a valid id"})
+    key = storage.make_file_key(req.scope, owner, req.filename)
+    post = storage.presign_upload(key, req.contentType, ttl_sec=900, residency=_data_residency(owner))
+    # Return a simple shape (compatible with your FE): uploadUrl + key
+    return PresignRes(uploadUrl=post["url"], fileKey=key, expiresIn=900)
+
+@app.get("/api/v1/files/{fileKey:path}")
+def files_get(fileKey: str):
+    url = storage.presign_download(fileKey, ttl_sec=300, residency=_data_residency(storage.key_owner(fileKey)))
+    return RedirectResponse(url)
+
+@app.post("/api/v1/admin/readings/import", response_model=ReadingImportRes)
+@require_role("admin")
+async def import_readings(req: ReadingImportReq, request: Request):
+    """
+    Bulk import historical readings from a CSV/NDJSON file uploaded via
+    /files/presign (scope "import"). Bad rows are skipped and reported by line number.
+    """
+    if not req.fileKey.startswith(storage.PIMPORT) or ".." in req.fileKey:
+        raise HTTPException(400, detail={"code": "INVALID_FILE_KEY", "message": "fileKey must be an uploaded import file"})
+    fmt = req.format or readings_import.detect_format(req.fileKey)
+    if fmt not in readings_import.IMPORT_FORMATS:
+        raise HTTPException(400, detail={"code": "INVALID_FORMAT", "message": "format must be csv or ndjson"})
+    
+    try:
+        summary = readings_import.import_readings_file(req.fileKey, fmt, get_user_id(request), get_user_role(request))
+    except readings_import.ImportRowError as e:
+        raise HTTPException(400, detail={"code": "INVALID_IMPORT_FILE", "message": str(e)})
+    except UnicodeDecodeError:
+        raise HTTPException(400, detail={"code": "INVALID_IMPORT_FILE", "message": "File must be UTF-8 encoded"})
+    except Exception as e:
+        raise _server_error(e, "IMPORT_FAILED")
+    return ReadingImportRes(**summary)
+
+# -------- Poses
+@app.get("/api/v1/poses", response_model=PosePage)
+@require_role("admin", "doctor", "patient")
+async def poses_list(patientId: str, request: Request, nextToken: Optional[str] = None):
+    try:
+        # RBAC: Check ownership
+        user_id = get_user_id(request)
+        user_role = get_user_role(request)
+        
         if user_role == "patient" and patientId != user_id:
              raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: You can only view your own poses"})
         
@@ -977,7 +1677,7 @@ async def poses_list(patientId: str, request: Request, nextToken: Optional[str]
              if profile and profile.get("doctorId") != user_id:
                  raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: Patient not assigned to you"})
 
-        items, nt = db.list_poses_by_patient(patientId, next_token=nextToken)
+        page = db.list_poses_by_patient(patientId, next_token=nextToken)
         # Create Pose models properly without duplicating createdAt
         poses = [
             Pose(
@@ -985,14 +1685,14 @@ async def poses_list(patientId: str, request: Request, nextToken: Optional[str]
                 patientId=i["patientId"],
                 fileKey=i["fileKey"],
                 createdAt=datetime.fromisoformat(i["createdAt"])
-            ) for i in items
+            ) for i in page.items
         ]
-        return PosePage(items=poses, nextToken=nt["id"] if isinstance(nt, dict) and "id" in nt else None)
+        return PosePage(items=poses, nextToken=page.next_cursor)
     except Exception as e:
         import traceback
         print(f"[ERROR] poses_list failed: {str(e)}")
         print(traceback.format_exc())
-        raise HTTPException(500, detail={"code":"POSE_LIST_FAILED","message":str(e)})
+        raise _server_error(e, "POSE_LIST_FAILED")
 
 @app.post("/api/v1/poses", response_model=PosePage)
 @require_role("admin", "doctor", "patient")
@@ -1012,27 +1712,32 @@ async def poses_create(body: PoseCreateReq, request: Request):
         if user_role == "patient" and pid != user_id:
              raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: You can only create poses for yourself"})
              
-        created_time = datetime.now(timezone.utc)
-        rec = {
-            "id": f"pose_{uuid.uuid4().hex[:8]}",
-            "patientId": pid,
-            "fileKey": body.fileKey,
-            "createdAt": created_time.isoformat()
-        }
-        db.create_pose(rec)
-        # Create Pose model with datetime object (not string)
-        pose = Pose(
-            id=rec["id"],
-            patientId=rec["patientId"],
-            fileKey=rec["fileKey"],
-            createdAt=created_time
-        )
-        return PosePage(items=[pose], nextToken=None)
+        def _create():
+            created_time = datetime.now(timezone.utc)
+            rec = {
+                "id": f"pose_{uuid.uuid4().hex[:8]}",
+                "patientId": pid,
+                "fileKey": body.fileKey,
+                "createdAt": created_time.isoformat()
+            }
+            db.create_pose(rec)
+            # Create Pose model with datetime object (not string)
+            pose = Pose(
+                id=rec["id"],
+                patientId=rec["patientId"],
+                fileKey=rec["fileKey"],
+                createdAt=created_time
+            )
+            return PosePage(items=[pose], nextToken=None).model_dump(mode="json")
+
+        return _idempotent(request, f"pose:{user_id}", body.model_dump(), _create)
+    except HTTPException:
+        raise
     except Exception as e:
         import traceback
         print(f"[ERROR] poses_create failed: {str(e)}")
         print(traceback.format_exc())
-        raise HTTPException(500, detail={"code":"POSE_CREATE_FAILED","message":str(e)})
+        raise _server_error(e, "POSE_CREATE_FAILED")
 
 @app.get("/api/v1/poses/{poseId}", response_model=Pose)
 def pose_get(poseId: str):
@@ -1054,7 +1759,7 @@ async def patient_pose_list(userId: str, request: Request):
             if profile and profile.get("doctorId") != current_user_id:
                 raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
 
-    items, _ = db.list_poses_by_patient(userId)
+    items = db.list_poses_by_patient(userId).items
     # Create Pose models properly
     poses = [
         Pose(
@@ -1077,6 +1782,12 @@ async def register_device(body: DeviceRegisterReq, request: Request):
     """
     user_id = get_user_id(request)
     
+    if not is_valid_firmware_version(body.firmwareVersion):
+        raise HTTPException(
+            400,
+            detail={"code": "INVALID_FIRMWARE_VERSION", "message": "Firmware version must be a semantic version (e.g. 1.2.3)"}
+        )
+    
     # Check if device already exists (by MAC address)
     existing = db.get_device_by_mac(body.macAddress)
     if existing:
@@ -1099,6 +1810,13 @@ async def register_device(body: DeviceRegisterReq, request: Request):
         "status": "offline",
         "batteryLevel": 100,
         "firmwareVersion": body.firmwareVersion,
+        "firmwareHistory": [{
+            "version": body.firmwareVersion,
+            "previousVersion": None,
+            "changedAt": now.isoformat(),
+            "changedBy": user_id
+        }],
+        "calibrationDueAt": next_due_from(now).isoformat(),  # Factory calibration
         "lastSeen": now.isoformat(),
         "createdAt": now.isoformat(),
         "updatedAt": now.isoformat()
@@ -1116,6 +1834,11 @@ async def register_device(body: DeviceRegisterReq, request: Request):
         status=device_data["status"],
         batteryLevel=device_data["batteryLevel"],
         firmwareVersion=device_data["firmwareVersion"],
+        firmwareHistory=device_data.get("firmwareHistory", []),
+        lastCalibratedAt=device_data.get("lastCalibratedAt"),
+        calibrationDueAt=device_data.get("calibrationDueAt"),
+        batteryUpdatedAt=device_data.get("batteryUpdatedAt"),
+        calibrationDue=is_calibration_due(device_data),
         lastSeen=now,
         createdAt=now,
         updatedAt=now
@@ -1142,6 +1865,11 @@ async def get_my_devices(request: Request):
             status=d["status"],
             batteryLevel=d["batteryLevel"],
             firmwareVersion=d["firmwareVersion"],
+            firmwareHistory=d.get("firmwareHistory", []),
+            lastCalibratedAt=d.get("lastCalibratedAt"),
+            calibrationDueAt=d.get("calibrationDueAt"),
+            batteryUpdatedAt=d.get("batteryUpdatedAt"),
+            calibrationDue=is_calibration_due(d),
             lastSeen=datetime.fromisoformat(d["lastSeen"]),
             createdAt=datetime.fromisoformat(d["createdAt"]),
             updatedAt=datetime.fromisoformat(d["updatedAt"])
@@ -1169,6 +1897,11 @@ async def get_all_devices_endpoint(request: Request):
             status=d["status"],
             batteryLevel=d["batteryLevel"],
             firmwareVersion=d["firmwareVersion"],
+            firmwareHistory=d.get("firmwareHistory", []),
+            lastCalibratedAt=d.get("lastCalibratedAt"),
+            calibrationDueAt=d.get("calibrationDueAt"),
+            batteryUpdatedAt=d.get("batteryUpdatedAt"),
+            calibrationDue=is_calibration_due(d),
             lastSeen=datetime.fromisoformat(d["lastSeen"]),
             createdAt=datetime.fromisoformat(d["createdAt"]),
             updatedAt=datetime.fromisoformat(d["updatedAt"])
@@ -1206,6 +1939,12 @@ async def get_device_endpoint(device_id: str, request: Request):
         status=device_data["status"],
         batteryLevel=device_data["batteryLevel"],
         firmwareVersion=device_data["firmwareVersion"],
+        firmwareHistory=device_data.get("firmwareHistory", []),
+        lastCalibratedAt=device_data.get("lastCalibratedAt"),
+        calibrationDueAt=device_data.get("calibrationDueAt"),
+        batteryUpdatedAt=device_data.get("batteryUpdatedAt"),
+        calibrationDue=is_calibration_due(device_data),
+        maintenanceSchedule=get_device_maintenance(device_id, pending_only=True),
         lastSeen=datetime.fromisoformat(device_data["lastSeen"]),
         createdAt=datetime.fromisoformat(device_data["createdAt"]),
         updatedAt=datetime.fromisoformat(device_data["updatedAt"])
@@ -1234,16 +1973,54 @@ async def update_device_endpoint(device_id: str, body: DeviceUpdateReq, request:
         updates["name"] = body.name
     if body.batteryLevel is not None:
         updates["batteryLevel"] = body.batteryLevel
+        updates["batteryUpdatedAt"] = updates["updatedAt"]
     if body.status is not None:
         updates["status"] = body.status
-    if body.firmwareVersion is not None:
+    firmware_change = None
+    current_version = device_data.get("firmwareVersion")
+    if body.firmwareVersion is not None and body.firmwareVersion != current_version:
+        if not is_valid_firmware_version(body.firmwareVersion):
+            raise HTTPException(
+                400,
+                detail={"code": "INVALID_FIRMWARE_VERSION", "message": "Firmware version must be a semantic version (e.g. 1.2.3)"}
+            )
+        if is_firmware_downgrade(body.firmwareVersion, current_version) and not body.allowDowngrade:
+            raise HTTPException(
+                409,
+                detail={
+                    "code": "FIRMWARE_DOWNGRADE",
+                    "message": f"Firmware {body.firmwareVersion} is older than installed {current_version}; set allowDowngrade to proceed"
+                }
+            )
+        firmware_change = {
+            "version": body.firmwareVersion,
+            "previousVersion": current_version,
+            "changedAt": updates["updatedAt"],
+            "changedBy": user_id
+        }
         updates["firmwareVersion"] = body.firmwareVersion
+        updates["firmwareHistory"] = list(device_data.get("firmwareHistory", [])) + [firmware_change]
     
     # Update last seen time
     updates["lastSeen"] = datetime.now(timezone.utc).isoformat()
     
     db.update_device(device_id, updates)
     
+    if firmware_change:
+        audit_service.log_event(
+            event_type=AuditEventType.DEVICE_UPDATE,
+            user_id=user_id,
+            user_role=get_user_role(request),
+            resource_type="device",
+            resource_id=device_id,
+            action="firmware_update",
+            details={
+                "oldFirmwareVersion": current_version,
+                "newFirmwareVersion": body.firmwareVersion,
+                "downgrade": is_firmware_downgrade(body.firmwareVersion, current_version)
+            }
+        )
+    
     # Get updated device
     updated_device = db.get_device(device_id)
     
@@ -1257,6 +2034,11 @@ async def update_device_endpoint(device_id: str, body: DeviceUpdateReq, request:
         status=updated_device["status"],
         batteryLevel=updated_device["batteryLevel"],
         firmwareVersion=updated_device["firmwareVersion"],
+        firmwareHistory=updated_device.get("firmwareHistory", []),
+        lastCalibratedAt=updated_device.get("lastCalibratedAt"),
+        calibrationDueAt=updated_device.get("calibrationDueAt"),
+        batteryUpdatedAt=updated_device.get("batteryUpdatedAt"),
+        calibrationDue=is_calibration_due(updated_device),
         lastSeen=datetime.fromisoformat(updated_device["lastSeen"]),
         createdAt=datetime.fromisoformat(updated_device["createdAt"]),
         updatedAt=datetime.fromisoformat(updated_device["updatedAt"])
@@ -1283,6 +2065,218 @@ async def delete_device_endpoint(device_id: str, request: Request):
     
     return {"success": True, "message": "Device deleted successfully"}
 
+@app.post("/api/v1/devices/{device_id}/api-key", status_code=201)
+@require_role("admin")
+async def issue_device_api_key(device_id: str, request: Request):
+    """
+    Issue (or rotate) the API key a device uses for reading ingestion (Admin only)
+    The key is returned once; only its hash is stored.
+    """
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    
+    api_key = secrets.token_urlsafe(32)
+    db.update_device(device_id, {
+        "apiKeyHash": hash_device_api_key(api_key),
+        "apiKeyIssuedAt": datetime.now(timezone.utc).isoformat()
+    })
+    
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_UPDATE,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        device_id=device_id,
+        action="issue_api_key"
+    )
+    
+    return {"deviceId": device_id, "apiKey": api_key}
+
+@app.post("/api/v1/devices/{device_id}/signing-key", status_code=201)
+@require_role("admin")
+async def issue_device_signing_key(device_id: str, request: Request):
+    """
+    Issue (or rotate) the pre-shared secret a device uses to HMAC-sign its
+    requests (Admin only). The device is identified by its hardware id (MAC
+    address); the secret is returned once.
+    """
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    hardware_id = device_data.get("macAddress")
+    if not hardware_id:
+        raise HTTPException(400, detail={"code": "HARDWARE_ID_MISSING", "message": "Device has no hardware id (MAC address)"})
+    
+    secret = secrets.token_hex(32)
+    db.put_device_api_key(hardware_id, device_id, secret)
+    
+    audit_service.log_device_event(
+        event_type=AuditEventType.DEVICE_UPDATE,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        device_id=device_id,
+        action="issue_signing_key"
+    )
+    
+    return {"deviceId": device_id, "hardwareId": hardware_id, "secret": secret}
+
+def _calibration_record(item: Dict[str, Any]) -> DeviceCalibrationRecord:
+    # Records stored before calibration results were tracked have no id/result
+    return DeviceCalibrationRecord(
+        id=item.get("id") or item["calibratedAt"],
+        deviceId=item["deviceId"],
+        calibratedBy=item.get("calibratedBy") or item.get("performedBy"),
+        calibrationDate=datetime.fromisoformat(item["calibratedAt"]),
+        nextDueDate=datetime.fromisoformat(item["nextDue"]),
+        referenceValues=item.get("referenceValues", {}),
+        measuredValues=item.get("measuredValues", {}),
+        calibrationResult=item.get("calibrationResult"),
+        notes=item.get("notes"),
+        certificateUrl=item.get("certificateUrl")
+    )
+
+def _get_visible_device(device_id: str, request: Request) -> Dict[str, Any]:
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    role = get_user_role(request)
+    if role == "patient" and device_data.get("patientId") != get_user_id(request):
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    # Custom roles only see the devices of patients assigned to them
+    if role not in USER_ROLES:
+        if not device_data.get("patientId"):
+            raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+        _check_patient_access(get_user_id(request), role, device_data["patientId"])
+    return device_data
+
+@app.post("/api/v1/devices/{device_id}/calibrations", response_model=DeviceCalibrationRecord, status_code=201)
+@require_role("admin")
+async def create_calibration(device_id: str, body: CalibrationCreateReq, request: Request):
+    """
+    Record a device calibration (Admin only)
+    A passed calibration resets the due date and sets the correction applied to new readings.
+    """
+    if not db.get_device(device_id):
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    try:
+        calibration = record_calibration(
+            device_id,
+            performed_by=get_user_id(request),
+            performed_by_role=get_user_role(request),
+            reference_values=body.referenceValues,
+            measured_values=body.measuredValues,
+            calibrated_at=body.calibrationDate,
+            next_due=body.nextDueDate,
+            notes=body.notes,
+            certificate_url=body.certificateUrl
+        )
+    except CalibrationError as e:
+        raise HTTPException(400, detail={"code": "INVALID_CALIBRATION", "message": str(e)})
+    return _calibration_record(calibration)
+
+@app.get("/api/v1/devices/{device_id}/calibrations", response_model=List[DeviceCalibrationRecord])
+@require_role("patient", "doctor", "admin")
+async def list_calibrations(device_id: str, request: Request, limit: int = 50):
+    """Calibration history of a device, newest first"""
+    _get_visible_device(device_id, request)
+    limit = max(1, min(limit, 200))
+    return [_calibration_record(c) for c in get_calibration_history(device_id, limit)]
+
+@app.get("/api/v1/devices/{device_id}/calibrations/latest", response_model=DeviceCalibrationRecord)
+@require_role("patient", "doctor", "admin")
+async def latest_calibration(device_id: str, request: Request):
+    """Most recent calibration of a device"""
+    _get_visible_device(device_id, request)
+    calibration = get_latest_calibration(device_id)
+    if not calibration:
+        raise HTTPException(404, detail={"code": "CALIBRATION_NOT_FOUND", "message": "Device has no calibrations"})
+    return _calibration_record(calibration)
+
+@app.put("/api/v1/devices/{device_id}/connection", response_model=DeviceConnectionInfo)
+@require_role("patient", "admin")
+async def report_device_connection(device_id: str, body: DeviceConnectionReq, request: Request):
+    """
+    Report that a device is connected (Patient: own devices, Admin)
+    Repeat while connected; without a report for DEVICE_STALE_SECONDS the device counts as disconnected.
+    """
+    device_data = _get_visible_device(device_id, request)
+    try:
+        return record_connection(
+            device_data,
+            body.connectionType,
+            user_id=get_user_id(request),
+            user_role=get_user_role(request),
+            signal_strength=body.signalStrength,
+            battery_level=body.batteryLevel
+        )
+    except DeviceConnectionError as e:
+        raise HTTPException(400, detail={"code": "INVALID_CONNECTION", "message": str(e)})
+
+@app.delete("/api/v1/devices/{device_id}/connection", response_model=DeviceConnectionInfo)
+@require_role("patient", "admin")
+async def report_device_disconnection(device_id: str, request: Request):
+    """Report that a device disconnected (Patient: own devices, Admin)"""
+    device_data = _get_visible_device(device_id, request)
+    return record_disconnection(device_data, user_id=get_user_id(request), user_role=get_user_role(request))
+
+@app.get("/api/v1/devices/{device_id}/connection", response_model=DeviceConnectionInfo)
+@require_role("patient", "doctor", "admin")
+async def get_device_connection(device_id: str, request: Request):
+    """Current connection state of a device (connected, stale or disconnected)"""
+    return get_connection_state(_get_visible_device(device_id, request))
+
+@app.post("/api/v1/devices/{device_id}/maintenance", response_model=MaintenanceEvent, status_code=201)
+@require_role("admin")
+async def create_maintenance(device_id: str, body: MaintenanceScheduleReq, request: Request):
+    """
+    Schedule device maintenance (Admin only)
+    The assigned technician is reminded MAINTENANCE_REMINDER_DAYS before it is due.
+    """
+    if not db.get_device(device_id):
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    if body.assignedTo and not db.get_user(body.assignedTo):
+        raise HTTPException(400, detail={"code": "INVALID_ASSIGNEE", "message": "Assigned technician not found"})
+    try:
+        return schedule_maintenance(
+            device_id,
+            body.maintenanceType,
+            body.scheduledAt,
+            created_by=get_user_id(request),
+            created_by_role=get_user_role(request),
+            custom_type=body.customType,
+            assigned_to=body.assignedTo,
+            notes=body.notes
+        )
+    except MaintenanceError as e:
+        raise HTTPException(400, detail={"code": "INVALID_MAINTENANCE", "message": str(e)})
+
+@app.get("/api/v1/devices/{device_id}/maintenance", response_model=List[MaintenanceEvent])
+@require_role("patient", "doctor", "admin")
+async def list_maintenance(device_id: str, request: Request, pending: bool = False):
+    """Maintenance schedule of a device, earliest first (pending=true: open events only)"""
+    _get_visible_device(device_id, request)
+    return get_device_maintenance(device_id, pending_only=pending)
+
+@app.post("/api/v1/devices/{device_id}/maintenance/{maintenance_id}/complete", response_model=MaintenanceEvent)
+@require_role("admin")
+async def complete_maintenance_endpoint(device_id: str, maintenance_id: str, body: MaintenanceCompleteReq, request: Request):
+    """Record completed maintenance (Admin only); nextMaintenanceDue schedules the follow-up"""
+    try:
+        event = complete_maintenance(
+            device_id,
+            maintenance_id,
+            performed_by=get_user_id(request),
+            performed_by_role=get_user_role(request),
+            completed_at=body.completedAt,
+            notes=body.notes,
+            next_maintenance_due=body.nextMaintenanceDue
+        )
+    except MaintenanceError as e:
+        raise HTTPException(409, detail={"code": "MAINTENANCE_CONFLICT", "message": str(e)})
+    if not event:
+        raise HTTPException(404, detail={"code": "MAINTENANCE_NOT_FOUND", "message": "Maintenance event not found"})
+    return event
+
 @app.get("/api/v1/patients/{patient_id}/devices", response_model=DevicePage)
 @require_role("doctor", "admin")
 async def get_patient_devices(patient_id: str, request: Request):
@@ -1301,6 +2295,11 @@ async def get_patient_devices(patient_id: str, request: Request):
             status=d["status"],
             batteryLevel=d["batteryLevel"],
             firmwareVersion=d["firmwareVersion"],
+            firmwareHistory=d.get("firmwareHistory", []),
+            lastCalibratedAt=d.get("lastCalibratedAt"),
+            calibrationDueAt=d.get("calibrationDueAt"),
+            batteryUpdatedAt=d.get("batteryUpdatedAt"),
+            calibrationDue=is_calibration_due(d),
             lastSeen=datetime.fromisoformat(d["lastSeen"]),
             createdAt=datetime.fromisoformat(d["createdAt"]),
             updatedAt=datetime.fromisoformat(d["updatedAt"])
@@ -1309,15 +2308,450 @@ async def get_patient_devices(patient_id: str, request: Request):
     
     return DevicePage(items=devices, nextToken=None)
 
+def _query_param_error(e: QueryParamError) -> HTTPException:
+    return HTTPException(400, detail={"code": e.code, "message": str(e), "param": e.param})
+
+def _parse_date_param(value: Optional[str], name: str) -> Optional[int]:
+    """Parse an ISO-8601 date/datetime query parameter into unix seconds"""
+    try:
+        parsed = QueryParams({name: value}).get_datetime(name)
+    except QueryParamError as e:
+        raise _query_param_error(e)
+    return int(parsed.timestamp()) if parsed else None
+
+def _date_range_params(start_date: Optional[str], end_date: Optional[str]) -> Tuple[Optional[int], Optional[int]]:
+    """Parse an optional start_date / end_date pair into unix seconds, 400 on malformed or inverted ranges"""
+    try:
+        start, end = parse_date_range_params(start_date, end_date)
+    except QueryParamError as e:
+        raise _query_param_error(e)
+    return (int(start.timestamp()) if start else None, int(end.timestamp()) if end else None)
+
+@app.get("/api/v1/devices/{device_id}/readings/summary", response_model=ReadingSummaryRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_device_readings_summary(
+    device_id: str,
+    request: Request,
+    period: str = "hourly",
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None
+):
+    """
+    Summarize device readings per aggregation period
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    
+    try:
+        aggregation_period = AggregationPeriod(period)
+    except ValueError:
+        allowed = ", ".join(p.value for p in AggregationPeriod)
+        raise HTTPException(400, detail={"code": "INVALID_PERIOD", "message": f"period must be one of: {allowed}"})
+    
+    start_time, end_time = _date_range_params(start_date, end_date)
+    
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    
+    # RBAC: Patient can only view their own devices
+    if user_role == "patient" and device_data.get("patientId") != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    readings = db.get_device_readings(device_id, start_time, end_time)
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_READ,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="device_readings",
+        resource_id=device_id,
+        action="summary",
+        details={"period": aggregation_period.value, "readingCount": len(readings)}
+    )
+    
+    return ReadingSummaryRes(
+        deviceId=device_id,
+        period=aggregation_period.value,
+        totalReadings=len(readings),
+        lowQualityReadings=len(partition_by_quality(readings)[1]),
+        items=aggregate_readings(readings, aggregation_period)
+    )
+
+# Long-poll hold time; stays below the 29 s API Gateway integration timeout (Lambda Timeout: 30)
+READINGS_STREAM_MAX_WAIT_SECONDS = int(os.environ.get("READINGS_STREAM_MAX_WAIT_SECONDS", "20"))
+READINGS_STREAM_POLL_INTERVAL_SECONDS = float(os.environ.get("READINGS_STREAM_POLL_INTERVAL_SECONDS", "2"))
+
+@app.get("/api/v1/devices/{device_id}/readings/stream", response_model=ReadingStreamRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def stream_device_readings(device_id: str, request: Request, since: int, wait: int = READINGS_STREAM_MAX_WAIT_SECONDS):
+    """
+    Long-poll for live readings
+    - Returns readings with timestamp > since (unix seconds) as soon as any exist
+    - Otherwise holds for up to `wait` seconds (capped at READINGS_STREAM_MAX_WAIT_SECONDS), then returns no items
+    - Patient: Only for their own devices; Doctor/Admin: Any device
+    - Each poll is a Query of the device index from since onwards, run off the event loop
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    if since < 0 or wait < 0:
+        raise HTTPException(400, detail={"code": "INVALID_PARAMETER", "message": "since and wait must not be negative"})
+    
+    _get_visible_device(device_id, request)
+    
+    deadline = time.monotonic() + min(wait, READINGS_STREAM_MAX_WAIT_SECONDS)
+    while True:
+        polled = await asyncio.to_thread(db.get_device_readings, device_id, since)
+        readings = [r for r in polled if r.get("timestamp", 0) > since]
+        remaining = deadline - time.monotonic()
+        if readings or remaining <= 0:
+            break
+        await asyncio.sleep(min(READINGS_STREAM_POLL_INTERVAL_SECONDS, remaining))
+    
+    # Empty polls are not audited; they carry no patient data
+    if readings:
+        audit_service.log_event(
+            event_type=AuditEventType.DATA_READ,
+            user_id=user_id,
+            user_role=user_role,
+            resource_type="device_readings",
+            resource_id=device_id,
+            action="stream",
+            details={"since": since, "readingCount": len(readings)}
+        )
+    
+    return ReadingStreamRes(
+        deviceId=device_id,
+        items=readings,
+        since=max((r["timestamp"] for r in readings), default=since)
+    )
+
+@app.get("/api/v1/devices/{device_id}/readings/export")
+@require_role("patient", "doctor", "admin", permission=READINGS_EXPORT)
+async def export_device_readings(
+    device_id: str,
+    request: Request,
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    format: str = "csv"
+):
+    """
+    Export device readings as CSV or JSON Lines (format=csv|jsonl).
+    The file is streamed to S3 and returned as a presigned download URL.
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    
+    format = normalize_case(format)
+    if format not in READING_EXPORT_FORMATS:
+        raise HTTPException(400, detail={"code": "INVALID_FORMAT", "message": f"format must be one of: {', '.join(READING_EXPORT_FORMATS)}"})
+    start_time, end_time = _date_range_params(start_date, end_date)
+    
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    if user_role == "patient" and device_data.get("patientId") != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    try:
+        result = export_service.export_device_readings(device_id, start_time, end_time, format)
+    except Exception as e:
+        raise _server_error(e, "EXPORT_FAILED")
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_EXPORT,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="device_readings",
+        resource_id=device_id,
+        action="export",
+        details={"format": format, "readingCount": result["readingCount"], "fileKey": result["fileKey"]}
+    )
+    return {"success": True, **result}
+
+@app.get("/api/v1/devices/{device_id}/readings/{timestamp}/waveform", response_model=ReadingWaveformRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_reading_waveform(device_id: str, timestamp: int, request: Request):
+    """
+    Raw ECG waveform of one reading, loaded from S3 on demand
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    _get_visible_device(device_id, request)
+    reading = next(
+        (r for r in db.get_sensor_readings(device_id, timestamp, timestamp) if r.get("waveform_key")), None
+    )
+    if not reading:
+        raise HTTPException(404, detail={"code": "WAVEFORM_NOT_FOUND", "message": "No waveform stored for this reading"})
+    
+    # Stored in the residency of the patient the reading was taken for
+    patient_id = reading.get("patient_id")
+    payload = storage.download_device_data(reading["waveform_key"], residency=_data_residency(patient_id))
+    
+    audit_service.log_patient_data_access(
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        patient_id=patient_id,
+        data_type="ecg_waveform"
+    )
+    return payload
+
+class FhirJSONResponse(JSONResponse):
+    media_type = "application/fhir+json"
+
+@app.get(
+    "/api/v1/devices/{device_id}/readings/{timestamp}/fhir",
+    response_model=FhirObservation, response_model_exclude_none=True, response_class=FhirJSONResponse
+)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_reading_fhir(device_id: str, timestamp: int, request: Request, reading_type: Optional[str] = None):
+    """
+    One device reading as a FHIR R4 Observation (reading_type picks one of several readings at the same time)
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    _get_visible_device(device_id, request)
+    readings = [
+        r for r in db.get_sensor_readings(device_id, timestamp, timestamp)
+        if r.get("timestamp") == timestamp and (reading_type is None or r.get("reading_type") == reading_type)
+    ]
+    if not readings:
+        raise HTTPException(404, detail={"code": "READING_NOT_FOUND", "message": "Reading not found"})
+    if len(readings) > 1:
+        raise HTTPException(400, detail={"code": "READING_TYPE_REQUIRED", "message": "Several readings share this timestamp, pass reading_type"})
+    reading = readings[0]
+    patient_id = reading.get("patient_id")
+    if not patient_id or patient_id == "UNASSIGNED":
+        raise HTTPException(422, detail={"code": "READING_UNASSIGNED", "message": "Reading is not linked to a patient"})
+    
+    _record_patient_data_access(request, patient_id, "fhir_observation")
+    return fhir_converter.to_fhir_observation(reading, patient_id)
+
+DEFAULT_READINGS_MAP_DAYS = 7
+
+def _reading_map_feature(reading: Dict[str, Any]) -> ReadingMapFeature:
+    """GeoJSON Point feature for a stored sensor reading with a location"""
+    location = reading["location"]
+    coordinates = [location["longitude"], location["latitude"]]
+    if location.get("altitude_m") is not None:
+        coordinates.append(location["altitude_m"])
+    values = {
+        key: value for key, value in reading.items()
+        if key not in NON_METRIC_FIELDS and isinstance(value, (int, float)) and not isinstance(value, bool)
+    }
+    return ReadingMapFeature(
+        geometry={"coordinates": coordinates},
+        properties={
+            "reading_type": reading.get("reading_type", "accelerometer"),
+            "values": values,
+            "timestamp": reading["timestamp"],
+            "accuracy_meters": location.get("accuracy_meters"),
+        }
+    )
+
+@app.get("/api/v1/devices/{device_id}/readings/map", response_model=ReadingMapRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_device_readings_map(
+    device_id: str,
+    request: Request,
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    reading_type: Optional[str] = None
+):
+    """
+    Readings with a GPS location as a GeoJSON FeatureCollection
+    - Defaults to the last DEFAULT_READINGS_MAP_DAYS days; readings without a location are left out
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    
+    end_time = _parse_date_param(end_date, "end_date") or int(time.time())
+    start_time = _parse_date_param(start_date, "start_date") or end_time - DEFAULT_READINGS_MAP_DAYS * 86400
+    if start_time > end_time:
+        raise HTTPException(400, detail={"code": "INVALID_DATE_RANGE", "message": "start_date must be before end_date"})
+    
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    if user_role == "patient" and device_data.get("patientId") != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    try:
+        readings = db.get_sensor_readings(device_id, start_time, end_time)
+    except Exception as e:
+        raise _server_error(e, "READINGS_QUERY_FAILED")
+    features = [
+        _reading_map_feature(r) for r in readings
+        if r.get("location") and (not reading_type or r.get("reading_type") == reading_type)
+    ]
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_READ,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="device_readings",
+        resource_id=device_id,
+        action="map",
+        details={"readingType": reading_type, "featureCount": len(features)}
+    )
+    
+    return ReadingMapRes(features=features)
+
+DEFAULT_ROLLUP_DAYS = 30
+
+def _reading_rollups(
+    request: Request,
+    period: str,
+    start_date: Optional[str],
+    end_date: Optional[str],
+    reading_type: Optional[str],
+    device_id: Optional[str] = None,
+    patient_id: Optional[str] = None
+) -> ReadingRollupRes:
+    """Validate rollup query parameters, build the rollup and audit the read"""
+    try:
+        aggregation_period = AggregationPeriod(period)
+    except ValueError:
+        allowed = ", ".join(p.value for p in AggregationPeriod)
+        raise HTTPException(400, detail={"code": "INVALID_PERIOD", "message": f"period must be one of: {allowed}"})
+    
+    end_time = _parse_date_param(end_date, "end_date")
+    end = datetime.fromtimestamp(end_time, timezone.utc) if end_time is not None else datetime.now(timezone.utc)
+    start_time = _parse_date_param(start_date, "start_date")
+    start = datetime.fromtimestamp(start_time, timezone.utc) if start_time is not None else end - timedelta(days=DEFAULT_ROLLUP_DAYS)
+    if start > end:
+        raise HTTPException(400, detail={"code": "INVALID_DATE_RANGE", "message": "start_date must be before end_date"})
+    
+    try:
+        items = get_reading_rollups(
+            aggregation_period, start, end,
+            device_id=device_id, patient_id=patient_id, reading_type=reading_type
+        )
+    except ValueError as e:
+        raise HTTPException(400, detail={"code": "INVALID_DATE_RANGE", "message": str(e)})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_READ,
+        user_id=get_user_id(request),
+        user_role=get_user_role(request),
+        resource_type="device_readings" if device_id else "patient_readings",
+        resource_id=device_id or patient_id,
+        action="rollup",
+        details={"period": aggregation_period.value, "buckets": len(items)}
+    )
+    
+    return ReadingRollupRes(
+        deviceId=device_id,
+        patientId=patient_id,
+        period=aggregation_period.value,
+        startDate=start.isoformat(),
+        endDate=end.isoformat(),
+        items=items
+    )
+
+@app.get("/api/v1/devices/{device_id}/readings/rollup", response_model=ReadingRollupRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_device_readings_rollup(
+    device_id: str,
+    request: Request,
+    period: str = "daily",
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    reading_type: Optional[str] = None
+):
+    """
+    Device readings bucketed per period over a date range (default: last 30 days).
+    Every bucket is returned; buckets without readings have count 0.
+    - Patient: Only for their own devices
+    - Doctor/Admin: Any device
+    """
+    device_data = db.get_device(device_id)
+    if not device_data:
+        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    
+    # RBAC: Patient can only view their own devices
+    if get_user_role(request) == "patient" and device_data.get("patientId") != get_user_id(request):
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    return _reading_rollups(request, period, start_date, end_date, reading_type, device_id=device_id)
+
+@app.get("/api/v1/patients/{patient_id}/readings/rollup", response_model=ReadingRollupRes)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_patient_readings_rollup(
+    patient_id: str,
+    request: Request,
+    period: str = "daily",
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    reading_type: Optional[str] = None
+):
+    """
+    Patient readings (all devices) bucketed per period over a date range (default: last 30 days).
+    Every bucket is returned; buckets without readings have count 0.
+    """
+    _check_patient_access(get_user_id(request), get_user_role(request), patient_id)
+    _record_patient_data_access(request, patient_id, "reading_rollup")
+    return _reading_rollups(request, period, start_date, end_date, reading_type, patient_id=patient_id)
+
+FHIR_DEFAULT_DAYS = 30
+FHIR_DEFAULT_LIMIT = 100
+FHIR_MAX_LIMIT = 1000
+
+@app.get(
+    "/api/v1/patients/{patient_id}/readings/fhir",
+    response_model=FhirBundle, response_model_exclude_none=True, response_class=FhirJSONResponse
+)
+@require_role("patient", "doctor", "admin", permission=READINGS_READ)
+async def get_patient_readings_fhir(
+    patient_id: str,
+    request: Request,
+    reading_type: Optional[str] = None,
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    limit: Optional[str] = None
+):
+    """
+    Readings of the patient's devices as a FHIR R4 searchset Bundle of Observations, newest first.
+    Defaults to the last 30 days; total counts every match, entry holds at most limit (default 100).
+    """
+    _check_patient_access(get_user_id(request), get_user_role(request), patient_id)
+    try:
+        limit = QueryParams({"limit": limit}).get_int_range("limit", 1, FHIR_MAX_LIMIT, default=FHIR_DEFAULT_LIMIT)
+    except QueryParamError as e:
+        raise _query_param_error(e)
+    start_time, end_time = _date_range_params(start_date, end_date)
+    end_time = end_time if end_time is not None else int(time.time())
+    start_time = start_time if start_time is not None else end_time - FHIR_DEFAULT_DAYS * 86400
+    if start_time > end_time:
+        raise HTTPException(400, detail={"code": "INVALID_DATE_RANGE", "message": "start_date must be before end_date"})
+    
+    readings = [
+        r
+        for device in db.get_devices_by_patient(patient_id)
+        for r in db.get_sensor_readings(device["id"], start_time, end_time)
+        if r.get("patient_id") == patient_id and (reading_type is None or r.get("reading_type") == reading_type)
+    ]
+    readings.sort(key=lambda r: r.get("timestamp", 0), reverse=True)
+    
+    _record_patient_data_access(request, patient_id, "fhir_observations")
+    return fhir_converter.to_fhir_bundle(readings[:limit], patient_id, total=len(readings))
+
 # -------- Patients
-@app.get("/api/v1/patients", response_model=PatientPage)
+@app.get("/api/v1/patients", response_model=PaginatedResponse[PatientWithProfile])
 @require_role("doctor", "admin")
-async def get_patients(request: Request):
+async def get_patients(request: Request, limit: int = 50, offset: int = 0):
     """
     Get patients list (Doctor, Admin only)
     - Doctor: Returns only their patients
     - Admin: Returns all patients
     """
+    limit, offset = _pagination_params(limit, offset)
     user_id = get_user_id(request)
     user_role = get_user_role(request)
     
@@ -1327,10 +2761,12 @@ async def get_patients(request: Request):
     else:  # admin
         profiles = db.get_all_patient_profiles()
     
-    # Enrich with user data
+    # Enrich with user data (only for the requested page)
+    page = paginate_list(profiles, limit, offset)
+    users = db.get_users_bulk([profile["userId"] for profile in page.items])
     patients = []
-    for profile in profiles:
-        user = db.get_user(profile["userId"])
+    for profile in page.items:
+        user = users.get(profile["userId"])
         if user:
             patients.append(PatientWithProfile(
                 userId=user["id"],
@@ -1344,86 +2780,472 @@ async def get_patients(request: Request):
                 updatedAt=datetime.fromisoformat(profile["updatedAt"])
             ))
     
-    return PatientPage(items=patients, nextToken=None)
+    page.items = patients
+    return create_paginated_response(page, limit, offset)
 
 @app.get("/api/v1/patients/{user_id}", response_model=PatientWithProfile)
 @require_role("doctor", "admin")
-async def get_patient_detail(user_id: str, request: Request):
+async def get_patient_detail(user_id: str, request: Request):
+    """
+    Get patient details (Doctor, Admin only)
+    - Doctor: Can only view their own patients
+    - Admin: Can view all patients
+    """
+    current_user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    
+    # Get patient profile
+    profile = db.get_patient_profile(user_id)
+    if not profile:
+        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient profile not found"})
+    
+    # RBAC: Doctor can only view their own patients
+    if user_role == "doctor" and profile.get("doctorId") != current_user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    # Get user data
+    user = db.get_user(user_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    
+    _record_patient_data_access(request, user_id, "patient_profile")
+    return PatientWithProfile(**_minimum_necessary(request, {
+        "userId": user["id"],
+        "email": user["email"],
+        "name": user.get("name"),
+        "role": user["role"],
+        "diagnosis": profile.get("diagnosis"),
+        "severity": profile.get("severity", "mild"),
+        "notes": profile.get("notes"),
+        "createdAt": datetime.fromisoformat(profile["createdAt"]),
+        "updatedAt": datetime.fromisoformat(profile["updatedAt"])
+    }))
+
+@app.put("/api/v1/patients/{user_id}/notes", response_model=PatientProfile)
+@require_role("doctor")
+async def update_patient_notes(user_id: str, body: PatientProfileUpdateReq, request: Request):
+    """
+    Update patient notes (Doctor only)
+    Doctor can only update notes for their own patients
+    """
+    doctor_id = get_user_id(request)
+    
+    # Get patient profile
+    profile = db.get_patient_profile(user_id)
+    if not profile:
+        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient profile not found"})
+    
+    # RBAC: Doctor can only update their own patients
+    if profile.get("doctorId") != doctor_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    # Build updates
+    updates = {"updatedAt": datetime.now(timezone.utc).isoformat()}
+    if body.diagnosis is not None:
+        updates["diagnosis"] = body.diagnosis
+    if body.severity is not None:
+        updates["severity"] = body.severity
+    if body.notes is not None:
+        updates["notes"] = body.notes
+    if body.dateOfBirth is not None:
+        updates["dateOfBirth"] = body.dateOfBirth.isoformat()
+    if body.heightCm is not None:
+        updates["heightCm"] = body.heightCm
+    if body.weightKg is not None:
+        updates["weightKg"] = body.weightKg
+    if body.phone is not None:
+        updates["phone"] = body.phone
+    if body.emergencyContact is not None:
+        updates["emergencyContact"] = body.emergencyContact.model_dump()
+    
+    db.update_patient_profile(user_id, updates)
+    
+    # Get updated profile
+    return _patient_profile_out(db.get_patient_profile(user_id))
+
+def _patient_profile_out(profile: Dict[str, Any]) -> PatientProfile:
+    date_of_birth = date.fromisoformat(profile["dateOfBirth"]) if profile.get("dateOfBirth") else None
+    return PatientProfile(
+        userId=profile["userId"],
+        doctorId=profile["doctorId"],
+        diagnosis=profile.get("diagnosis"),
+        severity=profile.get("severity", "mild"),
+        notes=profile.get("notes"),
+        dateOfBirth=date_of_birth,
+        age=calculate_age(date_of_birth) if date_of_birth else None,
+        heightCm=profile.get("heightCm"),
+        weightKg=profile.get("weightKg"),
+        phone=profile.get("phone"),
+        emergencyContact=profile.get("emergencyContact"),
+        assignedDevices=sorted(profile.get("assignedDevices") or []),
+        createdAt=datetime.fromisoformat(profile["createdAt"]),
+        updatedAt=datetime.fromisoformat(profile["updatedAt"])
+    )
+
+def _check_patient_access(user_id: str, user_role: str, patient_id: str) -> None:
+    """
+    Enforce patient-scoped access:
+    - Patient: only themselves
+    - Doctor and custom roles: only patients assigned to them
+    - Admin: any patient of their organization
+    """
+    if user_role == "patient" and patient_id != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: You can only access your own data"})
+    
+    # Users and profiles of other organizations are not found (db scoping)
+    if user_role == "admin" and not db.get_user(patient_id):
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    
+    if user_role not in ("patient", "admin"):
+        profile = db.get_patient_profile(patient_id)
+        if not profile or profile.get("doctorId") != user_id:
+            raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: Patient not assigned to you"})
+
+def _record_patient_data_access(request: Request, patient_id: str, data_type: str) -> None:
+    """
+    Refuse excessive reads of one patient's data (HIPAA access monitoring),
+    then audit the access so later checks count it.
+    """
+    user_id, user_role = get_user_id(request), get_user_role(request)
+    try:
+        compliance_service.check_access_frequency(user_id, patient_id)
+    except ComplianceError as e:
+        audit_service.log_access_denied(
+            user_id=user_id, user_role=user_role, resource_type=data_type,
+            resource_id=patient_id, required_role="within access frequency limit"
+        )
+        raise HTTPException(403, detail={"code": "EXCESSIVE_ACCESS", "message": str(e)})
+    audit_service.log_patient_data_access(user_id=user_id, user_role=user_role, patient_id=patient_id, data_type=data_type)
+
+def _minimum_necessary(request: Request, record: Dict[str, Any]) -> Dict[str, Any]:
+    """Drop the fields the caller's role may not see (HIPAA Minimum Necessary Rule)."""
+    try:
+        return compliance_service.filter_record(get_user_role(request), record)
+    except ComplianceError as e:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": str(e)})
+
+@app.post("/api/v1/patients/{patient_id}/export")
+@require_role("patient", "doctor")
+async def export_patient_data(
+    patient_id: str,
+    request: Request,
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    format: str = "json"
+):
+    """
+    Export a patient's records (subject access request)
+    - Patient: Only their own data
+    - Doctor: Only patients assigned to them
+    Returns a presigned URL to the uploaded bundle
+    """
+    current_user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    _check_patient_access(current_user_id, user_role, patient_id)
+    
+    format = normalize_case(format)
+    if format not in EXPORT_FORMATS:
+        raise HTTPException(400, detail={"code": "INVALID_FORMAT", "message": f"format must be one of: {', '.join(EXPORT_FORMATS)}"})
+    
+    start_time = _parse_date_param(start_date, "start_date")
+    end_time = _parse_date_param(end_date, "end_date")
+    
+    if not db.get_user(patient_id):
+        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient not found"})
+    
+    try:
+        result = export_service.export_patient_data(patient_id, start_time, end_time, format)
+    except Exception as e:
+        print(f"[Export] Failed to export data for {patient_id}: {e}")
+        raise HTTPException(500, detail={"code": "EXPORT_FAILED", "message": "Failed to export patient data"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_EXPORT,
+        user_id=current_user_id,
+        user_role=user_role,
+        resource_type="patient",
+        resource_id=patient_id,
+        action="export",
+        details={"format": format, "fileKey": result["fileKey"], "recordCounts": result["recordCounts"]}
+    )
+    
+    return {"success": True, "data": result}
+
+# -------- User Data Export (GDPR Art. 20)
+@app.get("/api/v1/users/{user_id}/data-export")
+@require_role("patient", "doctor", "admin")
+async def export_user_data(user_id: str, request: Request):
+    """
+    Export all data held about a user as a single JSON document
+    - Users: Only their own data
+    - Admin: Any user
+    Returns a presigned URL valid for 24 hours
+    """
+    current_user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    if user_role != "admin" and user_id != current_user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied: You can only export your own data"})
+    
+    if not db.get_user(user_id):
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    
+    try:
+        result = export_service.export_user_data(user_id)
+    except Exception as e:
+        print(f"[Export] Failed to export data for user {user_id}: {e}")
+        raise HTTPException(500, detail={"code": "EXPORT_FAILED", "message": "Failed to export user data"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_EXPORT,
+        user_id=current_user_id,
+        user_role=user_role,
+        resource_type="user",
+        resource_id=user_id,
+        action="data_export",
+        details={"fileKey": result["fileKey"], "recordCounts": result["recordCounts"]}
+    )
+    
+    return {"success": True, "data": result}
+
+# -------- Patient Medications
+@app.get("/api/v1/patients/{patient_id}/medications", response_model=MedicationPage)
+@require_role("patient", "doctor", "admin")
+async def list_medications(patient_id: str, request: Request, active_only: bool = True):
+    """
+    List a patient's medications (active ones only by default)
+    """
+    _check_patient_access(get_user_id(request), get_user_role(request), patient_id)
+    
+    items = db.get_medications(patient_id, active_only=active_only)
+    return MedicationPage(items=[Medication(**m) for m in items], count=len(items))
+
+# -------- Patient Consents (HIPAA)
+@app.post("/api/v1/patients/{patient_id}/consents", response_model=ConsentRecord, status_code=201)
+@require_role("patient", "doctor", "admin")
+async def create_consent(patient_id: str, body: ConsentReq, request: Request):
+    """
+    Grant or revoke a consent (patient for themselves; doctor/admin on the patient's behalf).
+    Consents are append-only: revoking records a new entry with granted=false.
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    _check_patient_access(user_id, user_role, patient_id)
+    
+    patient = db.get_user(patient_id)
+    if not patient or patient.get("role") != "patient":
+        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient not found"})
+    
+    try:
+        record = record_consent(
+            patient_id,
+            body.consentType,
+            body.granted,
+            granted_by=user_id,
+            granted_by_role=user_role,
+            expires_at=body.expiresAt,
+            witness=body.witness,
+            document_url=body.documentUrl,
+            ip_address=request.client.host if request.client else None
+        )
+    except ValueError as e:
+        raise HTTPException(400, detail={"code": "INVALID_CONSENT_TYPE", "message": str(e)})
+    return ConsentRecord(**record, active=is_active(record))
+
+@app.get("/api/v1/patients/{patient_id}/consents", response_model=List[ConsentRecord])
+@require_role("patient", "doctor", "admin")
+async def get_consents(patient_id: str, request: Request):
+    """Consent history of a patient, newest first; `active` marks each type's current consent"""
+    _check_patient_access(get_user_id(request), get_user_role(request), patient_id)
+    
+    seen_types = set()
+    records = []
+    for c in list_consents(patient_id):
+        current = c["consentType"] not in seen_types
+        seen_types.add(c["consentType"])
+        records.append(ConsentRecord(**c, active=current and is_active(c)))
+    return records
+
+def _require_data_sharing_consent(patient_id: Optional[str]) -> None:
+    """403 CONSENT_REQUIRED unless the patient has an active data sharing consent"""
+    if not patient_id:
+        return
+    try:
+        require_consent(patient_id, ConsentType.DATA_SHARING.value)
+    except ConsentRequiredError as e:
+        raise HTTPException(403, detail={"code": "CONSENT_REQUIRED", "message": str(e)})
+
+@app.get("/api/v1/patients/{patient_id}/medications/interactions", response_model=MedicationInteractionsRes)
+@require_role("patient", "doctor", "admin")
+async def list_medication_interactions(patient_id: str, request: Request):
+    """
+    Interaction warnings between a patient's active medications
+    """
+    user_id = get_user_id(request)
+    _check_patient_access(user_id, get_user_role(request), patient_id)
+    warnings = medication_interactions.check_interactions(db.get_medications(patient_id, active_only=True))
+    return MedicationInteractionsRes(
+        patientId=patient_id,
+        items=[InteractionWarning(**w.to_dict()) for w in warnings],
+        count=len(warnings)
+    )
+
+def _check_new_medication_interactions(
+    patient_id: str, body: MedicationReq, override: bool, replaces: Optional[str] = None
+) -> List[Dict[str, Any]]:
+    """
+    Critical interactions the new (or replacing) medication would introduce.
+    Raises 409 MEDICATION_INTERACTION unless overridden or blocking is disabled;
+    otherwise returns them for the audit record.
+    """
+    current = [m for m in db.get_medications(patient_id, active_only=True) if m["medicationId"] != replaces]
+    candidate = {**body.model_dump(), "medicationId": replaces, "isActive": True}
+    critical = [
+        w.to_dict() for w in medication_interactions.critical_interactions(
+            medication_interactions.check_interactions(current + [candidate])
+        )
+        if replaces in w.medication_ids
+    ]
+    if critical and not override and medication_interactions.BLOCK_CRITICAL_INTERACTIONS:
+        raise HTTPException(409, detail={
+            "code": "MEDICATION_INTERACTION",
+            "message": "Medication has a critical interaction with an active medication; "
+                       "resubmit with override_interactions=true to prescribe anyway",
+            "interactions": critical
+        })
+    return critical
+
+@app.post("/api/v1/patients/{patient_id}/medications", response_model=Medication, status_code=201)
+@require_role("doctor", "admin")
+async def add_medication(patient_id: str, body: MedicationReq, request: Request, override_interactions: bool = False):
+    """
+    Add a single medication to a patient (Doctor, Admin only)
+    - Refused with 409 when it interacts critically with an active medication,
+      unless override_interactions=true
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    _check_patient_access(user_id, user_role, patient_id)
+    
+    patient = db.get_user(patient_id)
+    if not patient or patient.get("role") != "patient":
+        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient not found"})
+    
+    overridden = _check_new_medication_interactions(patient_id, body, override_interactions)
+    medication = db.create_medication(patient_id, {**body.model_dump(), "prescribedBy": user_id})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="medication",
+        resource_id=medication["medicationId"],
+        action="create",
+        details={"patientId": patient_id, "name": body.name, "overriddenInteractions": overridden}
+    )
+    
+    return Medication(**medication)
+
+@app.put("/api/v1/patients/{patient_id}/medications/{medication_id}", response_model=Medication)
+@require_role("doctor", "admin")
+async def replace_medication(patient_id: str, medication_id: str, body: MedicationReq, request: Request,
+                             override_interactions: bool = False):
     """
-    Get patient details (Doctor, Admin only)
-    - Doctor: Can only view their own patients
-    - Admin: Can view all patients
+    Replace a single medication (Doctor, Admin only)
+    - Same interaction check as adding one
+    - Discontinued medications are part of the history and cannot be edited;
+      prescribe them again with POST instead
     """
-    current_user_id = get_user_id(request)
+    user_id = get_user_id(request)
     user_role = get_user_role(request)
+    _check_patient_access(user_id, user_role, patient_id)
     
-    # Get patient profile
-    profile = db.get_patient_profile(user_id)
-    if not profile:
-        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient profile not found"})
-    
-    # RBAC: Doctor can only view their own patients
-    if user_role == "doctor" and profile.get("doctorId") != current_user_id:
-        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    existing = db.get_medication(patient_id, medication_id)
+    if not existing:
+        raise HTTPException(404, detail={"code": "MEDICATION_NOT_FOUND", "message": "Medication not found"})
+    if not existing.get("isActive", True):
+        raise HTTPException(409, detail={"code": "MEDICATION_DISCONTINUED", "message": "Discontinued medications cannot be edited"})
     
-    # Get user data
-    user = db.get_user(user_id)
-    if not user:
-        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    overridden = _check_new_medication_interactions(patient_id, body, override_interactions, replaces=medication_id)
+    updated = db.update_medication(patient_id, medication_id, {**body.model_dump(), "prescribedBy": user_id})
+    if not updated:
+        raise HTTPException(500, detail={"code": "MEDICATION_UPDATE_FAILED", "message": "Failed to update medication"})
     
-    return PatientWithProfile(
-        userId=user["id"],
-        email=user["email"],
-        name=user.get("name"),
-        role=user["role"],
-        diagnosis=profile.get("diagnosis"),
-        severity=profile.get("severity", "mild"),
-        notes=profile.get("notes"),
-        createdAt=datetime.fromisoformat(profile["createdAt"]),
-        updatedAt=datetime.fromisoformat(profile["updatedAt"])
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_UPDATE,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="medication",
+        resource_id=medication_id,
+        action="update",
+        details={"patientId": patient_id, "overriddenInteractions": overridden}
     )
+    
+    return Medication(**updated)
 
-@app.put("/api/v1/patients/{user_id}/notes", response_model=PatientProfile)
-@require_role("doctor")
-async def update_patient_notes(user_id: str, body: PatientProfileUpdateReq, request: Request):
+@app.delete("/api/v1/patients/{patient_id}/medications/{medication_id}")
+@require_role("doctor", "admin")
+async def discontinue_medication(patient_id: str, medication_id: str, request: Request):
     """
-    Update patient notes (Doctor only)
-    Doctor can only update notes for their own patients
+    Discontinue a medication (Doctor, Admin only)
+    Soft delete: the record is kept with isActive=false for the medical history
     """
-    doctor_id = get_user_id(request)
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    _check_patient_access(user_id, user_role, patient_id)
     
-    # Get patient profile
-    profile = db.get_patient_profile(user_id)
-    if not profile:
-        raise HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient profile not found"})
+    if not db.get_medication(patient_id, medication_id):
+        raise HTTPException(404, detail={"code": "MEDICATION_NOT_FOUND", "message": "Medication not found"})
     
-    # RBAC: Doctor can only update their own patients
-    if profile.get("doctorId") != doctor_id:
-        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    db.update_medication(patient_id, medication_id, {
+        "isActive": False,
+        "discontinuedAt": datetime.now(timezone.utc).isoformat(),
+        "discontinuedBy": user_id
+    })
     
-    # Build updates
-    updates = {"updatedAt": datetime.now(timezone.utc).isoformat()}
-    if body.diagnosis is not None:
-        updates["diagnosis"] = body.diagnosis
-    if body.severity is not None:
-        updates["severity"] = body.severity
-    if body.notes is not None:
-        updates["notes"] = body.notes
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_DELETE,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="medication",
+        resource_id=medication_id,
+        action="discontinue",
+        details={"patientId": patient_id}
+    )
     
-    db.update_patient_profile(user_id, updates)
+    return {"success": True, "message": "Medication discontinued"}
+
+# -------- Patient Timeline
+TIMELINE_DEFAULT_DAYS = 30
+TIMELINE_MAX_LIMIT = 500
+
+@app.get("/api/v1/patients/{patient_id}/timeline", response_model=TimelineRes)
+@require_role("patient", "doctor", "admin")
+async def get_timeline(
+    patient_id: str,
+    request: Request,
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None,
+    limit: int = 50
+):
+    """
+    Unified patient history: readings, medication changes, device
+    assignments and the patient's audit events, newest first.
+    Defaults to the last 30 days.
+    """
+    _check_patient_access(get_user_id(request), get_user_role(request), patient_id)
     
-    # Get updated profile
-    updated_profile = db.get_patient_profile(user_id)
+    if limit < 1 or limit > TIMELINE_MAX_LIMIT:
+        raise HTTPException(400, detail={"code": "INVALID_LIMIT", "message": f"limit must be between 1 and {TIMELINE_MAX_LIMIT}"})
     
-    return PatientProfile(
-        userId=updated_profile["userId"],
-        doctorId=updated_profile["doctorId"],
-        diagnosis=updated_profile.get("diagnosis"),
-        severity=updated_profile.get("severity", "mild"),
-        notes=updated_profile.get("notes"),
-        createdAt=datetime.fromisoformat(updated_profile["createdAt"]),
-        updatedAt=datetime.fromisoformat(updated_profile["updatedAt"])
-    )
+    end_ts = _parse_date_param(end_date, "end_date")
+    end = datetime.fromtimestamp(end_ts, timezone.utc) if end_ts is not None else datetime.now(timezone.utc)
+    start_ts = _parse_date_param(start_date, "start_date")
+    start = datetime.fromtimestamp(start_ts, timezone.utc) if start_ts is not None else end - timedelta(days=TIMELINE_DEFAULT_DAYS)
+    if start > end:
+        raise HTTPException(400, detail={"code": "INVALID_DATE_RANGE", "message": "start_date must be before end_date"})
+    
+    events = await get_patient_timeline(patient_id, start, end, limit)
+    return TimelineRes(patientId=patient_id, items=[TimelineEvent(**e) for e in events], count=len(events))
 
 @app.get("/api/v1/me/profile", response_model=PatientProfile)
 @require_role("patient")
@@ -1437,15 +3259,7 @@ async def get_my_profile(request: Request):
     if not profile:
         raise HTTPException(404, detail={"code": "PROFILE_NOT_FOUND", "message": "Patient profile not found"})
     
-    return PatientProfile(
-        userId=profile["userId"],
-        doctorId=profile["doctorId"],
-        diagnosis=profile.get("diagnosis"),
-        severity=profile.get("severity", "mild"),
-        notes=profile.get("notes"),
-        createdAt=datetime.fromisoformat(profile["createdAt"]),
-        updatedAt=datetime.fromisoformat(profile["updatedAt"])
-    )
+    return _patient_profile_out(profile)
 
 # -------- Sessions (Device-Patient Dynamic Binding)
 @app.post("/api/v1/sessions", response_model=Session)
@@ -1685,10 +3499,12 @@ async def get_sessions(request: Request, status: Optional[str] = None):
         all_sessions = [s for s in all_sessions if s.get("status") == status]
     
     # Enrich with details
+    devices = db.get_devices_bulk([session["deviceId"] for session in all_sessions])
+    users = db.get_users_bulk([session["patientId"] for session in all_sessions])
     sessions_with_details = []
     for session in all_sessions:
-        device = db.get_device(session["deviceId"])
-        patient = db.get_user(session["patientId"])
+        device = devices.get(session["deviceId"])
+        patient = users.get(session["patientId"])
         
         sessions_with_details.append(SessionWithDetails(
             sessionId=session["sessionId"],
@@ -1719,14 +3535,77 @@ async def bind_device(body: DeviceBindReq, request: Request):
     if body.patientId != user_id:
         raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Cannot bind device to another patient"})
     
-    device_data = db.get_device(body.deviceId)
-    if not device_data:
-        raise HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
-        
-    db.update_device(body.deviceId, {"patientId": user_id, "updatedAt": datetime.now(timezone.utc).isoformat()})
+    try:
+        assign_device_to_patient(body.deviceId, user_id, user_id, "patient")
+    except Exception as e:
+        raise _assignment_http_error(e)
     
     return {"success": True, "message": "Device bound to patient successfully"}
 
+def _assignment_http_error(e: Exception) -> Exception:
+    """Map device assignment errors to HTTP errors (others pass through)"""
+    if isinstance(e, DeviceNotFoundError):
+        return HTTPException(404, detail={"code": "DEVICE_NOT_FOUND", "message": "Device not found"})
+    if isinstance(e, PatientNotFoundError):
+        return HTTPException(404, detail={"code": "PATIENT_NOT_FOUND", "message": "Patient not found"})
+    if isinstance(e, DeviceAlreadyAssignedError):
+        return HTTPException(409, detail={"code": "DEVICE_ALREADY_ASSIGNED", "message": str(e)})
+    if isinstance(e, AssignmentConflictError):
+        return HTTPException(409, detail={"code": "ASSIGNMENT_CONFLICT", "message": "Device assignment changed, please retry"})
+    return e
+
+@app.post("/api/v1/devices/{device_id}/assign")
+@require_role("doctor", "admin")
+async def assign_device(device_id: str, body: DeviceAssignReq, request: Request):
+    """
+    Assign a device to a patient (updates the device and the patient's assignedDevices together)
+    - 409 DEVICE_ALREADY_ASSIGNED if it belongs to another patient, unless force=true
+    - Doctor: only their own patients, and force only moves devices from their own patients
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    _check_patient_access(user_id, user_role, body.patientId)
+    
+    device_data = db.get_device(device_id)
+    current = (device_data or {}).get("patientId")
+    if body.force and current and current != body.patientId:
+        _check_patient_access(user_id, user_role, current)
+    
+    try:
+        device = assign_device_to_patient(device_id, body.patientId, user_id, user_role, force=body.force)
+    except Exception as e:
+        raise _assignment_http_error(e)
+    return {"success": True, "data": {"deviceId": device_id, "patientId": device.get("patientId")}}
+
+@app.post("/api/v1/devices/{device_id}/unassign")
+@require_role("doctor", "admin")
+async def unassign_device_endpoint(device_id: str, request: Request):
+    """
+    Remove a device from its patient
+    - Doctor: only devices of their own patients
+    """
+    user_id = get_user_id(request)
+    user_role = get_user_role(request)
+    
+    device_data = db.get_device(device_id)
+    if device_data and device_data.get("patientId"):
+        _check_patient_access(user_id, user_role, device_data["patientId"])
+    
+    try:
+        unassign_device(device_id, user_id, user_role)
+    except Exception as e:
+        raise _assignment_http_error(e)
+    return {"success": True, "data": {"deviceId": device_id, "patientId": None}}
+
+@app.get("/api/v1/devices/{device_id}/assignments", response_model=List[DeviceAssignment])
+@require_role("doctor", "admin")
+async def list_device_assignments(device_id: str, request: Request):
+    """Assignment history of a device, oldest first"""
+    try:
+        return [DeviceAssignment(**a) for a in get_assignment_history(device_id)]
+    except Exception as e:
+        raise _assignment_http_error(e)
+
 # -------- Doctor Endpoints
 @app.get("/api/v1/doctor/patients", response_model=DoctorPatientsRes)
 @require_role("doctor")
@@ -1738,10 +3617,11 @@ async def get_doctor_patients(request: Request, doctor_id: str):
         
     profiles = db.get_patients_by_doctor(doctor_id)
     
+    users = db.get_users_bulk([p["userId"] for p in profiles if p.get("userId")])
     patients = []
     for p in profiles:
         pid = p.get("userId")
-        user = db.get_user(pid)
+        user = users.get(pid)
         if user:
             patients.append({
                 "patient_id": pid,
@@ -1793,8 +3673,7 @@ async def assign_patient(request: Request, body: AssignPatientReq):
         import traceback
         print(f"Error assigning patient: {str(e)}")
         print(traceback.format_exc())
-        # Return the actual error for debugging
-        raise HTTPException(500, detail=f"Internal Server Error: {str(e)}")
+        raise _server_error(e, "PATIENT_ASSIGN_FAILED")
 
 
 # -------- Symptoms
@@ -1818,7 +3697,7 @@ async def get_symptoms(request: Request, patientId: Optional[str] = None, limit:
         records = db.get_symptom_records(target_patient, limit)
         return {"success": True, "items": records, "count": len(records)}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SYMPTOMS_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "SYMPTOMS_FETCH_FAILED")
 
 
 @app.post("/api/v1/symptoms")
@@ -1831,21 +3710,26 @@ async def create_symptom(request: Request):
     
     try:
         body = await request.json()
-        record = db.create_symptom_record(user_id, body)
-        
-        # Log this action
-        audit_service.log_event(
-            event_type=AuditEventType.DATA_CREATE,
-            user_id=user_id,
-            user_role="patient",
-            resource_type="symptom",
-            resource_id=record.get("recordId"),
-            action="create"
-        )
-        
-        return {"success": True, "data": record}
+
+        def _create():
+            record = db.create_symptom_record(user_id, body)
+
+            # Log this action
+            audit_service.log_event(
+                event_type=AuditEventType.DATA_CREATE,
+                user_id=user_id,
+                user_role="patient",
+                resource_type="symptom",
+                resource_id=record.get("recordId"),
+                action="create"
+            )
+            return {"success": True, "data": record}
+
+        return _idempotent(request, f"symptom:{user_id}", body, _create)
+    except HTTPException:
+        raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SYMPTOM_CREATE_FAILED", "message": str(e)})
+        raise _server_error(e, "SYMPTOM_CREATE_FAILED")
 
 
 @app.delete("/api/v1/symptoms/{record_id}")
@@ -1879,40 +3763,365 @@ async def delete_symptom(request: Request, record_id: str):
         
         return {"success": success}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SYMPTOM_DELETE_FAILED", "message": str(e)})
+        raise _server_error(e, "SYMPTOM_DELETE_FAILED")
+
+
+# -------- Reports
+@app.get("/api/v1/reports")
+@require_role("patient", "doctor", "admin")
+async def get_reports(
+    request: Request,
+    patientId: Optional[str] = None,
+    limit: int = 50
+):
+    """
+    Get reports. Patients see their own, doctors see their patients', admins see all.
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    
+    try:
+        if role == "patient":
+            reports = db.list_reports_for_patient(user_id, user_id, role, limit=limit).items
+        elif role == "doctor":
+            if patientId:
+                reports = db.get_reports(patient_id=patientId, limit=limit)
+            else:
+                reports = db.get_reports(author_id=user_id, limit=limit)
+        else:  # admin
+            if patientId:
+                reports = db.get_reports(patient_id=patientId, limit=limit)
+            else:
+                reports = db.get_reports(limit=limit)
+        
+        return {"success": True, "items": reports, "count": len(reports)}
+    except Exception as e:
+        raise _server_error(e, "REPORTS_FETCH_FAILED")
+
+
+@app.get("/api/v1/patients/{patient_id}/reports", response_model=PaginatedResponse[ReportSummary])
+@require_role("patient", "doctor", "admin")
+async def list_patient_reports(request: Request, patient_id: str, limit: int = 50, nextToken: Optional[str] = None):
+    """
+    List a patient's reports, newest first.
+    - Patient: own reports, except those hidden from the patient
+    - Doctor: all reports of assigned patients, otherwise only reports they wrote
+    - Admin: all
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    if role == "patient" and patient_id != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    limit, _ = _pagination_params(limit)
+    
+    try:
+        page = db.list_reports_for_patient(patient_id, user_id, role, limit=limit, cursor=nextToken)
+    except ValueError as e:
+        raise HTTPException(400, detail={"code": "INVALID_PAGINATION", "message": str(e)})
+    except Exception as e:
+        raise _server_error(e, "REPORTS_FETCH_FAILED")
+    page.items = [ReportSummary(**{k: r.get(k) for k in ReportSummary.model_fields if r.get(k) is not None})
+                  for r in page.items]
+    return create_paginated_response(page, limit)
+
+
+@app.post("/api/v1/patients/{patient_id}/reports/summary", status_code=201)
+@require_role("doctor", "admin")
+async def generate_patient_summary_report(request: Request, patient_id: str):
+    """
+    Generate a patient summary PDF (cover page, vital trends, medications,
+    devices, recent readings) and store it as a signed report (Doctor/Admin only).
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    _check_patient_access(user_id, role, patient_id)
+    if not db.get_user(patient_id):
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    _require_data_sharing_consent(patient_id)
+    
+    try:
+        with metrics.timer(REPORT_GENERATION_DURATION, Endpoint="/api/v1/patients/{patient_id}/reports/summary"):
+            author = db.get_user(user_id) or {}
+            generator = report_pdf.PdfReportGenerator(logo=report_pdf.load_logo())
+            data = report_pdf.load_patient_summary_data(patient_id)
+            pdf = generator.generate(
+                data, report_pdf.ReportParameters(generated_by=author.get("name") or author.get("email") or user_id)
+            )
+            file_key = storage.make_file_key("report", user_id, f"{patient_id}_summary.{generator.extension}")
+            storage.upload_bytes(file_key, pdf, generator.content_type, residency=_data_residency(patient_id))
+            report = db.create_report({
+                "patientId": patient_id,
+                "authorId": user_id,
+                "authorRole": role,
+                "type": report_pdf.REPORT_TYPE_PATIENT_SUMMARY,
+                "title": "Patient Summary",
+                "format": generator.extension,
+                "fileKey": file_key,
+                "pageCount": report_pdf.count_pages(pdf),
+                "readingCount": len(data.readings),
+                "lowQualityReadings": data.low_quality_count,
+                "lowQualityRatio": data.low_quality_ratio,
+                "status": "completed",
+                "signature": crypto_service.sign_data(pdf),
+                "signedAt": datetime.now(timezone.utc).isoformat(),
+            })
+    except HTTPException:
+        raise
+    except Exception as e:
+        raise _server_error(e, "REPORT_GENERATION_FAILED")
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=user_id,
+        user_role=role,
+        resource_type="report",
+        resource_id=report.get("reportId"),
+        action="generate_summary",
+        details={"patientId": patient_id, "pageCount": report["pageCount"]}
+    )
+    notification_service.push_report_ready(report)
+    return {"success": True, "data": report}
+
+
+@app.post("/api/v1/admin/reports/compliance", status_code=201)
+@require_role("admin")
+async def generate_compliance_report(
+    request: Request,
+    start_date: str,
+    end_date: str,
+    format: str = "csv"
+):
+    """
+    Generate a HIPAA compliance report (data access summary, security incidents,
+    failed sign-ins) from the audit log of [start_date, end_date] and store it as
+    a signed report, format=csv|pdf (Admin only).
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+
+    format = normalize_case(format)
+    generator = compliance_report.generator_for(format)
+    if not generator:
+        raise HTTPException(400, detail={"code": "INVALID_FORMAT", "message": f"format must be one of: {', '.join(compliance_report.COMPLIANCE_REPORT_FORMATS)}"})
+    start_time, end_time = _date_range_params(start_date, end_date)
+    if start_time is None or end_time is None:
+        raise HTTPException(400, detail={"code": "INVALID_DATE", "message": "start_date and end_date are required"})
+    start = datetime.fromtimestamp(start_time, timezone.utc)
+    end = datetime.fromtimestamp(end_time, timezone.utc)
+
+    try:
+        with metrics.timer(REPORT_GENERATION_DURATION, Endpoint="/api/v1/admin/reports/compliance"):
+            author = db.get_user(user_id) or {}
+            data = compliance_report.load_compliance_report(start, end)
+            content = generator.generate(
+                data, report_pdf.ReportParameters(generated_by=author.get("name") or author.get("email") or user_id)
+            )
+            file_key = storage.make_file_key(
+                "report", user_id, f"compliance_{start:%Y%m%d}_{end:%Y%m%d}.{generator.extension}"
+            )
+            storage.upload_bytes(file_key, content, generator.content_type)
+            report = db.create_report({
+                "authorId": user_id,
+                "authorRole": role,
+                "type": compliance_report.REPORT_TYPE_COMPLIANCE,
+                "title": "Compliance Report",
+                "format": generator.extension,
+                "fileKey": file_key,
+                "periodStart": start.isoformat(),
+                "periodEnd": end.isoformat(),
+                "status": "completed",
+                "signature": crypto_service.sign_data(content),
+                "signedAt": datetime.now(timezone.utc).isoformat(),
+            })
+    except HTTPException:
+        raise
+    except Exception as e:
+        raise _server_error(e, "REPORT_GENERATION_FAILED")
+
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=user_id,
+        user_role=role,
+        resource_type="report",
+        resource_id=report.get("reportId"),
+        action="generate_compliance",
+        details={
+            "format": format,
+            "accessRows": len(data.access_summary),
+            "incidents": len(data.incidents),
+            "failedAuthRows": len(data.failed_auth),
+        }
+    )
+    notification_service.push_report_ready(report)
+    return {"success": True, "data": report}
+
+
+# Declared before /reports/{report_id} so "schedules" is not taken for a report id
+@app.post("/api/v1/reports/schedules", response_model=ReportSchedule, status_code=201)
+@require_role("doctor", "admin")
+async def create_report_schedule(body: ReportScheduleCreateReq, request: Request):
+    """
+    Schedule a recurring report (e.g. weekly patient summary).
+    - Doctor: only for patients assigned to them
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    patient_id = body.reportTemplate["patientId"]
+    _check_patient_access(user_id, role, patient_id)
+    _require_data_sharing_consent(patient_id)
+    
+    schedule = report_scheduler.create_schedule(
+        body.reportTemplate,
+        body.frequency.model_dump(),
+        created_by=user_id,
+        created_by_role=role,
+        start_at=body.startAt
+    )
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=user_id,
+        user_role=role,
+        resource_type="report_schedule",
+        resource_id=schedule["id"],
+        action="create",
+        details={"patientId": patient_id, "frequency": schedule["frequency"], "nextRunAt": schedule["nextRunAt"]}
+    )
+    return ReportSchedule(**schedule)
+
+
+@app.get("/api/v1/reports/schedules", response_model=ReportSchedulePage)
+@require_role("doctor", "admin")
+async def list_report_schedules(request: Request):
+    """
+    List report schedules. Doctors see their own, admins see all.
+    """
+    created_by = None if get_user_role(request) == "admin" else get_user_id(request)
+    items = report_scheduler.list_schedules(created_by)
+    return ReportSchedulePage(items=[ReportSchedule(**s) for s in items], count=len(items))
+
+
+@app.delete("/api/v1/reports/schedules/{schedule_id}")
+@require_role("doctor", "admin")
+async def delete_report_schedule(request: Request, schedule_id: str):
+    """
+    Delete a report schedule (doctors only their own). Reports already generated are kept.
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    
+    schedule = db.get_report_schedule(schedule_id)
+    if not schedule:
+        raise HTTPException(404, detail={"code": "SCHEDULE_NOT_FOUND", "message": "Report schedule not found"})
+    if role != "admin" and schedule.get("createdBy") != user_id:
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    
+    report_scheduler.delete_schedule(schedule_id)
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_DELETE,
+        user_id=user_id,
+        user_role=role,
+        resource_type="report_schedule",
+        resource_id=schedule_id,
+        action="delete"
+    )
+    return {"success": True}
+
+
+def _sign_report_file(fields: Dict[str, Any], patient_id: Optional[str] = None) -> None:
+    """Sign the S3 file referenced by fields["fileKey"] (if any) into fields["signature"]"""
+    fields.pop("signature", None)
+    fields.pop("signedAt", None)
+    if not fields.get("fileKey"):
+        return
+    try:
+        data = storage.download_bytes(fields["fileKey"], residency=_data_residency(patient_id or fields.get("patientId")))
+    except Exception as e:
+        print(f"[Reports] Unable to read report file {fields['fileKey']}: {e}")
+        raise HTTPException(400, detail={"code": "REPORT_FILE_NOT_FOUND", "message": "Report file has not been uploaded"})
+    fields["signature"] = crypto_service.sign_data(data)
+    fields["signedAt"] = datetime.now(timezone.utc).isoformat()
+
+REPORT_DOWNLOAD_URL_TTL_SECONDS = 300
+
+def _get_accessible_report(report_id: str, user_id: str, role: str) -> Dict[str, Any]:
+    report = db.get_report(report_id)
+    if not report:
+        raise HTTPException(404, detail={"code": "REPORT_NOT_FOUND", "message": "Report not found"})
+    if not db.report_accessible_by(report, user_id, role):
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Access denied"})
+    return report
+
+
+@app.get("/api/v1/reports/{report_id}/download")
+@require_role("patient", "doctor", "admin")
+async def download_report(request: Request, report_id: str):
+    """
+    Redirect to a short-lived presigned URL for the report file; X-Report-Signature
+    carries its HMAC-SHA-256 so clients can verify the downloaded bytes.
+    403 without access to the report, 410 once the report has expired.
+    """
+    user_id = get_user_id(request)
+    role = get_user_role(request)
+    report = _get_accessible_report(report_id, user_id, role)
+    if is_report_expired(report):
+        raise HTTPException(410, detail={"code": "REPORT_EXPIRED", "message": "Report has expired"})
+    if not report.get("fileKey"):
+        raise HTTPException(404, detail={"code": "REPORT_FILE_NOT_FOUND", "message": "Report has no file"})
+    
+    url = storage.presign_download(
+        report["fileKey"], ttl_sec=REPORT_DOWNLOAD_URL_TTL_SECONDS, residency=_data_residency(report.get("patientId"))
+    )
+    audit_service.log_event(
+        event_type=AuditEventType.REPORT_DOWNLOADED,
+        user_id=user_id,
+        user_role=role,
+        resource_type="report",
+        resource_id=report_id,
+        action="download",
+        ip_address=request.client.host if request.client else None,
+        details={"patientId": report.get("patientId"), "urlExpiresIn": REPORT_DOWNLOAD_URL_TTL_SECONDS}
+    )
+    headers = {"X-Report-Signature": report["signature"]} if report.get("signature") else {}
+    return RedirectResponse(url, headers=headers)
 
 
-# -------- Reports
-@app.get("/api/v1/reports")
+@app.get("/api/v1/reports/{report_id}/verify")
 @require_role("patient", "doctor", "admin")
-async def get_reports(
-    request: Request,
-    patientId: Optional[str] = None,
-    limit: int = 50
-):
+async def verify_report(request: Request, report_id: str):
     """
-    Get reports. Patients see their own, doctors see their patients', admins see all.
+    Check the stored report file still matches its signature (content is not returned).
     """
     user_id = get_user_id(request)
     role = get_user_role(request)
+    report = _get_accessible_report(report_id, user_id, role)
+    if not report.get("fileKey") or not report.get("signature"):
+        raise HTTPException(409, detail={"code": "REPORT_NOT_SIGNED", "message": "Report has no signed file"})
     
     try:
-        if role == "patient":
-            reports = db.get_reports(patient_id=user_id, limit=limit)
-        elif role == "doctor":
-            if patientId:
-                reports = db.get_reports(patient_id=patientId, limit=limit)
-            else:
-                reports = db.get_reports(author_id=user_id, limit=limit)
-        else:  # admin
-            if patientId:
-                reports = db.get_reports(patient_id=patientId, limit=limit)
-            else:
-                reports = db.get_reports(limit=limit)
-        
-        return {"success": True, "items": reports, "count": len(reports)}
+        data = storage.download_bytes(report["fileKey"], residency=_data_residency(report.get("patientId")))
+    except storage.StorageIntegrityError as e:
+        # Corrupted in storage: cannot match the signature either
+        print(f"[Reports] {e}")
+        data = None
     except Exception as e:
-        raise HTTPException(500, detail={"code": "REPORTS_FETCH_FAILED", "message": str(e)})
+        print(f"[Reports] Unable to read report file {report['fileKey']}: {e}")
+        raise HTTPException(404, detail={"code": "REPORT_FILE_NOT_FOUND", "message": "Report file is missing"})
+    
+    valid = data is not None and crypto_service.verify_signature(data, report["signature"])
+    if not valid:
+        audit_service.log_event(
+            event_type=AuditEventType.SECURITY_SUSPICIOUS_ACTIVITY,
+            user_id=user_id,
+            user_role=role,
+            resource_type="report",
+            resource_id=report_id,
+            action="verify_signature",
+            outcome="failure",
+            details={"fileKey": report["fileKey"]}
+        )
+    
+    return {"success": True, "data": {"reportId": report_id, "valid": valid, "signedAt": report.get("signedAt")}}
 
 
 @app.get("/api/v1/reports/{report_id}")
@@ -1930,14 +4139,14 @@ async def get_report(request: Request, report_id: str):
             raise HTTPException(404, detail="Report not found")
         
         # Access control
-        if role == "patient" and report.get("patientId") != user_id:
+        if not db.report_accessible_by(report, user_id, role):
             raise HTTPException(403, detail="Access denied")
         
         return {"success": True, "data": report}
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "REPORT_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "REPORT_FETCH_FAILED")
 
 
 @app.post("/api/v1/reports")
@@ -1945,6 +4154,8 @@ async def get_report(request: Request, report_id: str):
 async def create_report(request: Request):
     """
     Create a new report (Doctor/Admin only).
+
+    Requires an Idempotency-Key header; a retry with the same key returns the original report.
     """
     user_id = get_user_id(request)
     role = get_user_role(request)
@@ -1953,23 +4164,32 @@ async def create_report(request: Request):
         body = await request.json()
         body["authorId"] = user_id
         body["authorRole"] = role
-        
-        report = db.create_report(body)
-        
-        audit_service.log_event(
-            event_type=AuditEventType.DATA_CREATE,
-            user_id=user_id,
-            user_role=role,
-            resource_type="report",
-            resource_id=report.get("reportId"),
-            action="create",
-            details={"patientId": body.get("patientId"), "type": body.get("type")}
-        )
-        
-        return {"success": True, "data": report}
+        _require_data_sharing_consent(body.get("patientId"))
+
+        def _create():
+            with metrics.timer(REPORT_GENERATION_DURATION, Endpoint="/api/v1/reports"):
+                _sign_report_file(body)
+                report = db.create_report(body)
+
+            audit_service.log_event(
+                event_type=AuditEventType.DATA_CREATE,
+                user_id=user_id,
+                user_role=role,
+                resource_type="report",
+                resource_id=report.get("reportId"),
+                action="create",
+                details={"patientId": body.get("patientId"), "type": body.get("type")}
+            )
+            return {"success": True, "data": report}
+
+        return _idempotent(request, f"report:{user_id}", body, _create, required=True)
+    except HTTPException:
+        raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "REPORT_CREATE_FAILED", "message": str(e)})
+        raise _server_error(e, "REPORT_CREATE_FAILED")
+
 
+REPORT_IMMUTABLE_FIELDS = ("reportId", "authorId", "organizationId")
 
 @app.put("/api/v1/reports/{report_id}")
 @require_role("doctor", "admin")
@@ -1982,6 +4202,15 @@ async def update_report(request: Request, report_id: str):
     
     try:
         body = await request.json()
+        # Identity and ownership are fixed at creation
+        for field in REPORT_IMMUTABLE_FIELDS:
+            body.pop(field, None)
+        _require_data_sharing_consent(body.get("patientId"))
+        if "fileKey" in body:
+            _sign_report_file(body, patient_id=body.get("patientId") or (db.get_report(report_id) or {}).get("patientId"))
+        else:
+            body.pop("signature", None)
+            body.pop("signedAt", None)
         report = db.update_report(report_id, body)
         
         if not report:
@@ -1991,7 +4220,7 @@ async def update_report(request: Request, report_id: str):
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "REPORT_UPDATE_FAILED", "message": str(e)})
+        raise _server_error(e, "REPORT_UPDATE_FAILED")
 
 
 @app.delete("/api/v1/reports/{report_id}")
@@ -2004,21 +4233,23 @@ async def delete_report(request: Request, report_id: str):
     role = get_user_role(request)
     
     try:
-        success = db.delete_report(report_id)
+        if not db.delete_report(report_id):
+            raise HTTPException(404, detail="Report not found")
         
-        if success:
-            audit_service.log_event(
-                event_type=AuditEventType.DATA_DELETE,
-                user_id=user_id,
-                user_role=role,
-                resource_type="report",
-                resource_id=report_id,
-                action="delete"
-            )
+        audit_service.log_event(
+            event_type=AuditEventType.DATA_DELETE,
+            user_id=user_id,
+            user_role=role,
+            resource_type="report",
+            resource_id=report_id,
+            action="delete"
+        )
         
-        return {"success": success}
+        return {"success": True}
+    except HTTPException:
+        raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "REPORT_DELETE_FAILED", "message": str(e)})
+        raise _server_error(e, "REPORT_DELETE_FAILED")
 
 
 # -------- Messages
@@ -2034,7 +4265,7 @@ async def get_conversations(request: Request, limit: int = 50):
         conversations = db.get_conversations(user_id, limit)
         return {"success": True, "items": conversations, "count": len(conversations)}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "CONVERSATIONS_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "CONVERSATIONS_FETCH_FAILED")
 
 
 @app.get("/api/v1/messages/conversations/{conversation_id}")
@@ -2054,7 +4285,7 @@ async def get_conversation_messages(
         messages = db.get_messages(conversation_id, limit, before)
         return {"success": True, "items": messages, "count": len(messages)}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "MESSAGES_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "MESSAGES_FETCH_FAILED")
 
 
 @app.post("/api/v1/messages/conversations")
@@ -2076,7 +4307,7 @@ async def create_conversation(request: Request):
         
         return {"success": True, "data": conversation}
     except Exception as e:
-        raise HTTPException(500, detail={"code": "CONVERSATION_CREATE_FAILED", "message": str(e)})
+        raise _server_error(e, "CONVERSATION_CREATE_FAILED")
 
 
 @app.post("/api/v1/messages")
@@ -2102,7 +4333,7 @@ async def send_message(request: Request):
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "MESSAGE_SEND_FAILED", "message": str(e)})
+        raise _server_error(e, "MESSAGE_SEND_FAILED")
 
 
 # -------- User Profile
@@ -2124,21 +4355,22 @@ async def get_user_profile(request: Request):
             "id": user.get("id"),
             "email": user.get("email"),
             "name": user.get("name"),
-            "role": user.get("role"),
+            "role": role_name(user.get("role")),
             "phone": user.get("phone"),
             "specialty": user.get("specialty"),
             "license": user.get("license"),
             "department": user.get("department"),
             "hospital": user.get("hospital"),
             "createdAt": user.get("createdAt"),
-            "mfaEnabled": user.get("mfaEnabled", False)
+            "mfaEnabled": user.get("mfaEnabled", False),
+            "preferences": _user_preferences(user).model_dump()
         }
         
         return {"success": True, "data": safe_user}
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "PROFILE_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "PROFILE_FETCH_FAILED")
 
 
 @app.put("/api/v1/profile")
@@ -2159,15 +4391,18 @@ async def update_user_profile(request: Request):
         
         if not updates:
             raise HTTPException(400, detail="No valid fields to update")
+        if updates.get("phone"):
+            try:
+                updates["phone"] = normalize_phone_e164(updates["phone"])
+            except ValueError as e:
+                raise HTTPException(400, detail={"code": "INVALID_PHONE", "message": str(e)})
         
         # Update user
         user = db.get_user(user_id)
         if not user:
             raise HTTPException(404, detail="User not found")
         
-        user.update(updates)
-        user["updatedAt"] = datetime.now(timezone.utc).isoformat()
-        db.put_user(user)
+        db.update_user(user_id, {**updates, "updatedAt": datetime.now(timezone.utc).isoformat()})
         
         audit_service.log_event(
             event_type=AuditEventType.DATA_UPDATE,
@@ -2183,7 +4418,7 @@ async def update_user_profile(request: Request):
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "PROFILE_UPDATE_FAILED", "message": str(e)})
+        raise _server_error(e, "PROFILE_UPDATE_FAILED")
 
 
 # -------- User Settings (per-user preferences)
@@ -2217,7 +4452,7 @@ async def get_user_settings(request: Request):
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SETTINGS_FETCH_FAILED", "message": str(e)})
+        raise _server_error(e, "SETTINGS_FETCH_FAILED")
 
 
 @app.put("/api/v1/settings/user")
@@ -2237,17 +4472,95 @@ async def update_user_settings(request: Request):
             raise HTTPException(404, detail="User not found")
         
         # Update settings
-        settings = user.get("settings", {})
-        settings.update(body)
-        user["settings"] = settings
-        user["updatedAt"] = datetime.now(timezone.utc).isoformat()
-        db.put_user(user)
+        settings = {**user.get("settings", {}), **body}
+        db.update_user(user_id, {"settings": settings, "updatedAt": datetime.now(timezone.utc).isoformat()})
         
         return {"success": True, "message": "Settings updated successfully"}
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "SETTINGS_UPDATE_FAILED", "message": str(e)})
+        raise _server_error(e, "SETTINGS_UPDATE_FAILED")
+
+
+# -------- User Preferences (UI settings; not part of login responses)
+@app.get("/api/v1/users/me/preferences", response_model=UserPreferences)
+@require_role("patient", "doctor", "admin")
+async def get_user_preferences(request: Request):
+    """
+    Get the current user's UI preferences (defaults for anything not set yet).
+    """
+    user = db.get_user(get_user_id(request))
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    return _user_preferences(user)
+
+
+@app.put("/api/v1/users/me/preferences", response_model=UserPreferences)
+@require_role("patient", "doctor", "admin")
+async def update_user_preferences(body: UserPreferences, request: Request):
+    """
+    Replace the current user's UI preferences; omitted fields reset to their defaults.
+    """
+    user_id = get_user_id(request)
+    user = db.get_user(user_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    
+    preferences = body.model_dump()
+    previous = dict(user.get("preferences") or {})
+    if not db.update_user(user_id, {"preferences": preferences, "updatedAt": datetime.now(timezone.utc).isoformat()}):
+        raise HTTPException(500, detail={"code": "PREFERENCES_UPDATE_FAILED", "message": "Failed to save preferences"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_UPDATE,
+        user_id=user_id,
+        user_role=get_user_role(request),
+        resource_type="user_preferences",
+        resource_id=user_id,
+        action="update",
+        details={"updated_fields": sorted(k for k, v in preferences.items() if previous.get(k) != v)}
+    )
+    return body
+
+
+# -------- Push notification tokens
+@app.post("/api/v1/users/me/push-tokens", response_model=PushToken, status_code=201)
+@require_role("patient", "doctor", "admin")
+async def register_push_token(body: PushTokenReq, request: Request):
+    """
+    Register the caller's mobile device token (APNs or FCM) for push
+    notifications; registering a known token again refreshes it.
+    """
+    user_id = get_user_id(request)
+    item = db.put_push_token(user_id, body.token, body.platform, body.deviceName)
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=user_id,
+        user_role=get_user_role(request),
+        resource_type="push_token",
+        resource_id=item["tokenId"],
+        action="register",
+        details={"platform": body.platform}
+    )
+    return PushToken(**item)
+
+
+@app.delete("/api/v1/users/me/push-tokens/{token_id}")
+@require_role("patient", "doctor", "admin")
+async def delete_push_token(token_id: str, request: Request):
+    """Stop push notifications to one of the caller's devices (e.g. on sign-out from the app)"""
+    user_id = get_user_id(request)
+    if not db.delete_push_token(user_id, token_id):
+        raise HTTPException(404, detail={"code": "PUSH_TOKEN_NOT_FOUND", "message": "Push token not found"})
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_DELETE,
+        user_id=user_id,
+        user_role=get_user_role(request),
+        resource_type="push_token",
+        resource_id=token_id,
+        action="delete"
+    )
+    return {"success": True}
 
 
 # -------- Admin - User Management (extended)
@@ -2267,13 +4580,29 @@ async def update_user(request: Request, user_id: str):
             raise HTTPException(404, detail="User not found")
         
         # Only allow updating certain fields
-        allowed_fields = ["name", "role", "emailVerified", "isActive"]
+        allowed_fields = ["name", "role", "emailVerified", "isActive", "dataResidency"]
         updates = {k: v for k, v in body.items() if k in allowed_fields}
+        # Applies to files stored from now on; existing objects stay where they are
+        if updates.get("dataResidency") is not None:
+            residency = str(updates["dataResidency"]).strip().lower()
+            if residency not in storage.DATA_RESIDENCY_TARGETS:
+                allowed = ", ".join(sorted(storage.DATA_RESIDENCY_TARGETS)) or "none configured"
+                raise HTTPException(400, detail={"code": "INVALID_RESIDENCY", "message": f"dataResidency must be one of: {allowed}"})
+            updates["dataResidency"] = residency
+        if "role" in updates:
+            updates["role"] = _assignable_role(str(updates["role"] or ""))
         
-        user.update(updates)
-        user["updatedAt"] = datetime.now(timezone.utc).isoformat()
-        user["updatedBy"] = admin_id
-        db.put_user(user)
+        # Write only the fields that actually changed; clients may pass the
+        # version they read to guard against concurrent modification
+        changed = diff_user(user, {**user, **updates})
+        if changed:
+            changed["updatedAt"] = datetime.now(timezone.utc).isoformat()
+            changed["updatedBy"] = admin_id
+            try:
+                if not db.update_user_fields(user_id, changed, expected_version=body.get("version")):
+                    raise HTTPException(500, detail={"code": "USER_UPDATE_FAILED", "message": "Failed to update user"})
+            except VersionConflictError:
+                raise HTTPException(409, detail={"code": "VERSION_CONFLICT", "message": "User was modified by another request; reload and retry"})
         
         audit_service.log_event(
             event_type=AuditEventType.DATA_UPDATE,
@@ -2282,14 +4611,14 @@ async def update_user(request: Request, user_id: str):
             resource_type="user",
             resource_id=user_id,
             action="update",
-            details={"updated_fields": list(updates.keys())}
+            details={"updated_fields": [k for k in changed if k not in ("updatedAt", "updatedBy")]}
         )
         
         return {"success": True, "message": "User updated successfully"}
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "USER_UPDATE_FAILED", "message": str(e)})
+        raise _server_error(e, "USER_UPDATE_FAILED")
 
 
 @app.delete("/api/v1/admin/users/{user_id}")
@@ -2306,10 +4635,11 @@ async def delete_user(request: Request, user_id: str):
             raise HTTPException(404, detail="User not found")
         
         # Soft delete - mark as inactive rather than deleting
-        user["isActive"] = False
-        user["deletedAt"] = datetime.now(timezone.utc).isoformat()
-        user["deletedBy"] = admin_id
-        db.put_user(user)
+        db.update_user(user_id, {
+            "isActive": False,
+            "deletedAt": datetime.now(timezone.utc).isoformat(),
+            "deletedBy": admin_id
+        })
         
         audit_service.log_event(
             event_type=AuditEventType.DATA_DELETE,
@@ -2324,7 +4654,327 @@ async def delete_user(request: Request, user_id: str):
     except HTTPException:
         raise
     except Exception as e:
-        raise HTTPException(500, detail={"code": "USER_DELETE_FAILED", "message": str(e)})
+        raise _server_error(e, "USER_DELETE_FAILED")
+
+
+# -------- Organizations (multi-tenancy)
+def _organization_out(org: Dict[str, Any]) -> Organization:
+    return Organization(**{k: org[k] for k in Organization.model_fields})
+
+def _require_organization_admin(request: Request, organization_id: str) -> None:
+    """Admins manage their own organization; platform admins manage all"""
+    caller_org = get_organization_id(request)
+    if caller_org != organization_id and not is_platform_admin(get_user_role(request), caller_org):
+        raise HTTPException(404, detail={"code": "ORGANIZATION_NOT_FOUND", "message": "Organization not found"})
+
+@app.post("/api/v1/admin/organizations", response_model=Organization, status_code=201)
+@require_role("admin")
+async def create_organization(body: OrganizationCreateReq, request: Request):
+    """
+    Create an organization (platform admins only, i.e. admins of the default organization).
+    The listed admin users are moved into it.
+    """
+    if not is_platform_admin(get_user_role(request), get_organization_id(request)):
+        raise HTTPException(403, detail={"code": "FORBIDDEN", "message": "Only platform admins can create organizations"})
+    try:
+        org = organization_service.create_organization(
+            body.name, body.subdomain, body.adminUserIds, SubscriptionTier(body.subscriptionTier)
+        )
+    except OrganizationError as e:
+        raise HTTPException(400, detail={"code": "INVALID_ORGANIZATION", "message": str(e)})
+    except db.OrganizationExistsError:
+        raise HTTPException(409, detail={"code": "SUBDOMAIN_TAKEN", "message": "Subdomain is already in use"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=get_user_id(request),
+        user_role="admin",
+        resource_type="organization",
+        resource_id=org["id"],
+        action="create_organization",
+        details={"subdomain": org["subdomain"], "adminUserIds": org["adminUserIds"], "tier": org["subscriptionTier"]}
+    )
+    return _organization_out(org)
+
+@app.get("/api/v1/organizations/{organization_id}", response_model=Organization)
+@require_role("admin")
+async def get_organization(organization_id: str, request: Request):
+    """Get an organization (its admins, or platform admins)"""
+    _require_organization_admin(request, organization_id)
+    org = organization_service.get_organization(organization_id)
+    if not org:
+        raise HTTPException(404, detail={"code": "ORGANIZATION_NOT_FOUND", "message": "Organization not found"})
+    return _organization_out(org)
+
+@app.get("/api/v1/organizations/{organization_id}/users")
+@require_role("admin")
+async def list_organization_users(organization_id: str, request: Request):
+    """Users of an organization (its admins, or platform admins)"""
+    _require_organization_admin(request, organization_id)
+    return [
+        {
+            "id": u["id"],
+            "email": u["email"],
+            "role": role_name(u["role"]),
+            "name": u.get("name"),
+            "createdAt": u.get("createdAt")
+        }
+        for u in organization_service.list_organization_users(organization_id)
+    ]
+
+# -------- Admin - Custom Roles
+@app.post("/api/v1/admin/roles", response_model=RoleDefinition, status_code=201)
+@require_role("admin")
+async def create_role(body: RoleCreateReq, request: Request):
+    """
+    Define a custom role: a named subset of the doctor's permissions (Admin only).
+    Assign it by creating users with POST /api/v1/admin/users and role=<name>.
+    The permissions are copied onto each user, so a role cannot be redefined.
+    """
+    if not CUSTOM_ROLE_NAME_PATTERN.match(body.name) or body.name in USER_ROLES:
+        raise HTTPException(400, detail={"code": "INVALID_ROLE", "message": "Role name must be lowercase letters, digits, - or _ and not a built-in role"})
+    permissions = sorted(set(p.strip().lower() for p in body.permissions))
+    unknown = [p for p in permissions if p not in ROLE_PERMISSIONS["doctor"]]
+    if unknown:
+        raise HTTPException(400, detail={
+            "code": "INVALID_PERMISSION",
+            "message": f"Unknown permissions: {', '.join(unknown)}; allowed: {', '.join(sorted(ROLE_PERMISSIONS['doctor']))}"
+        })
+    
+    role = {
+        "name": body.name,
+        "permissions": permissions,
+        "createdAt": datetime.now(timezone.utc).isoformat(),
+        "createdBy": get_user_id(request),
+    }
+    try:
+        db.create_role(role)
+    except db.RoleExistsError:
+        raise HTTPException(409, detail={"code": "ROLE_EXISTS", "message": "A role with this name already exists"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=get_user_id(request),
+        user_role="admin",
+        resource_type="role",
+        resource_id=body.name,
+        action="create_role",
+        details={"permissions": permissions}
+    )
+    return RoleDefinition(**role)
+
+@app.get("/api/v1/admin/roles", response_model=List[RoleDefinition])
+@require_role("admin")
+async def list_roles(request: Request):
+    """Custom roles (Admin only)"""
+    return [RoleDefinition(**r) for r in db.list_roles()]
+
+# -------- Admin - Data Purge
+PURGE_ACTION = "purge_patient"
+
+@app.post("/api/v1/admin/patients/{patient_id}/purge-token")
+@require_role("admin")
+async def issue_purge_token(request: Request, patient_id: str):
+    """
+    Issue a short-lived confirmation token required to purge a patient (Admin only).
+    """
+    admin_id = get_user_id(request)
+    
+    user = db.get_user(patient_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    if user.get("role") != "patient":
+        raise HTTPException(400, detail={"code": "NOT_A_PATIENT", "message": "Only patient records can be purged"})
+    
+    token, expires_at = issue_confirmation_token(PURGE_ACTION, patient_id, admin_id)
+    return {
+        "success": True,
+        "data": {
+            "confirmationToken": token,
+            "expiresAt": datetime.fromtimestamp(expires_at, timezone.utc).isoformat()
+        }
+    }
+
+@app.delete("/api/v1/admin/patients/{patient_id}/purge")
+@require_role("admin")
+async def purge_patient(request: Request, patient_id: str):
+    """
+    Permanently delete a patient and all associated data (Admin only).
+    Requires the X-Confirmation-Token header from the purge-token endpoint.
+    Safe to retry until the patient's user record is deleted, which happens last.
+    """
+    admin_id = get_user_id(request)
+    
+    token = request.headers.get("X-Confirmation-Token")
+    if not verify_confirmation_token(token, PURGE_ACTION, patient_id, admin_id):
+        raise HTTPException(403, detail={"code": "CONFIRMATION_REQUIRED", "message": "A valid purge confirmation token is required"})
+    
+    user = db.get_user(patient_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    if user.get("role") != "patient":
+        raise HTTPException(400, detail={"code": "NOT_A_PATIENT", "message": "Only patient records can be purged"})
+    
+    try:
+        counts = purge_service.purge_patient(patient_id)
+    except Exception as e:
+        print(f"[Purge] Failed to purge patient {patient_id}: {e}")
+        raise HTTPException(500, detail={"code": "PURGE_FAILED", "message": "Purge did not complete; it is safe to retry"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_PURGE,
+        user_id=admin_id,
+        user_role="admin",
+        resource_type="patient",
+        resource_id=patient_id,
+        action="purge",
+        details={"deleted": counts}
+    )
+    
+    return {"success": True, "data": {"patientId": patient_id, "deleted": counts}}
+
+
+# -------- Admin - Patient Deletion
+DELETE_PATIENT_ACTION = "delete_patient"
+
+@app.post("/api/v1/admin/patients/{patient_id}/deletion-token")
+@require_role("admin")
+async def issue_patient_deletion_token(request: Request, patient_id: str):
+    """
+    Issue a short-lived confirmation token required to delete a patient (Admin only).
+    """
+    admin_id = get_user_id(request)
+    
+    user = db.get_user(patient_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    if user.get("role") != "patient":
+        raise HTTPException(400, detail={"code": "NOT_A_PATIENT", "message": "Only patient records can be deleted here"})
+    
+    token, expires_at = issue_confirmation_token(DELETE_PATIENT_ACTION, patient_id, admin_id)
+    return {
+        "success": True,
+        "data": {
+            "confirmationToken": token,
+            "expiresAt": datetime.fromtimestamp(expires_at, timezone.utc).isoformat()
+        }
+    }
+
+@app.delete("/api/v1/admin/patients/{patient_id}", response_model=PatientDeletionSummary)
+@require_role("admin")
+async def delete_patient(request: Request, patient_id: str):
+    """
+    Delete a patient (Admin only): deactivate the account, unassign their
+    devices, anonymize personal data and soft-delete (flag) their readings.
+    Requires the X-Confirmation-Token header from the deletion-token endpoint.
+    """
+    admin_id = get_user_id(request)
+    
+    token = request.headers.get("X-Confirmation-Token")
+    if not verify_confirmation_token(token, DELETE_PATIENT_ACTION, patient_id, admin_id):
+        raise HTTPException(403, detail={"code": "CONFIRMATION_REQUIRED", "message": "A valid deletion confirmation token is required"})
+    
+    user = db.get_user(patient_id)
+    if not user:
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "Patient not found"})
+    if user.get("role") != "patient":
+        raise HTTPException(400, detail={"code": "NOT_A_PATIENT", "message": "Only patient records can be deleted here"})
+    
+    try:
+        result = purge_service.delete_patient(patient_id, admin_id)
+    except ConditionFailedError:
+        raise HTTPException(409, detail={"code": "DEVICE_ASSIGNMENT_CHANGED", "message": "Device assignments changed during deletion; retry"})
+    except Exception as e:
+        print(f"[Delete] Failed to delete patient {patient_id}: {e}")
+        raise HTTPException(500, detail={"code": "PATIENT_DELETE_FAILED", "message": "Deletion did not complete; it is safe to retry"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.PATIENT_DELETED,
+        user_id=admin_id,
+        user_role="admin",
+        resource_type="patient",
+        resource_id=patient_id,
+        action=DELETE_PATIENT_ACTION,
+        details={
+            "oldValues": result["oldValues"],
+            "unassignedDeviceCount": result["unassignedDeviceCount"],
+            "anonymizedFields": result["anonymizedFields"],
+        }
+    )
+    
+    return PatientDeletionSummary(**result)
+
+
+# -------- Admin - Personal Data Erasure (GDPR Art. 17)
+ERASE_ACTION = "erase_personal_data"
+
+@app.post("/api/v1/admin/users/{user_id}/erasure-token")
+@require_role("admin")
+async def issue_erasure_token(request: Request, user_id: str):
+    """
+    Issue a short-lived confirmation token required to erase a user's personal data (Admin only).
+    The admin re-authenticates with a current MFA code ({"mfaCode": "123456"}), or with
+    their password ({"password": "..."}) when MFA is not enabled.
+    """
+    admin_id = get_user_id(request)
+    admin = db.get_user(admin_id, include_secrets=True) or {}
+    try:
+        body = await request.json() or {}
+    except Exception:
+        body = {}
+    
+    if admin.get("mfaEnabled"):
+        if not verify_mfa_code(admin.get("mfaSecret"), body.get("mfaCode")):
+            raise HTTPException(403, detail={"code": "MFA_REQUIRED", "message": "A valid MFA code is required to erase personal data"})
+    elif not body.get("password") or not verify_pw(body["password"], admin.get("password")):
+        raise HTTPException(403, detail={"code": "PASSWORD_REQUIRED", "message": "Your current password is required to erase personal data"})
+    
+    if not db.get_user(user_id):
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    
+    token, expires_at = issue_confirmation_token(ERASE_ACTION, user_id, admin_id)
+    return {
+        "success": True,
+        "data": {
+            "confirmationToken": token,
+            "expiresAt": datetime.fromtimestamp(expires_at, timezone.utc).isoformat()
+        }
+    }
+
+@app.delete("/api/v1/users/{user_id}/personal-data", response_model=UserAnonymizationResult)
+@require_role("admin")
+async def erase_personal_data(request: Request, user_id: str):
+    """
+    Anonymize a user's personal data (Admin only).
+    Requires the X-Confirmation-Token header from the erasure-token endpoint.
+    Readings stay linked to the pseudonymous user id; audit logs are retained.
+    """
+    admin_id = get_user_id(request)
+    
+    token = request.headers.get("X-Confirmation-Token")
+    if not verify_confirmation_token(token, ERASE_ACTION, user_id, admin_id):
+        raise HTTPException(403, detail={"code": "CONFIRMATION_REQUIRED", "message": "A valid erasure confirmation token is required"})
+    
+    if not db.get_user(user_id):
+        raise HTTPException(404, detail={"code": "USER_NOT_FOUND", "message": "User not found"})
+    
+    try:
+        result = purge_service.anonymize_user(user_id)
+    except Exception as e:
+        print(f"[Erasure] Failed to anonymize user {user_id}: {e}")
+        raise HTTPException(500, detail={"code": "ERASURE_FAILED", "message": "Erasure did not complete; it is safe to retry"})
+    
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_PURGE,
+        user_id=admin_id,
+        user_role="admin",
+        resource_type="user",
+        resource_id=user_id,
+        action=ERASE_ACTION,
+        details={"erasedFields": result["erasedFields"], "deletedFiles": result["deletedFiles"]}
+    )
+    
+    return UserAnonymizationResult(**result)
 
 
 # -------- Tremor Analysis
@@ -2343,34 +4993,27 @@ def get_tremor_analysis(
     user_id = get_user_id(request)
     role = get_user_role(request)
     
-    # Access control
-    # Patients can only access their own data
-    if role == 'patient' and user_id != patient_id:
-        # Log access denied
+    # Access control: the patient themselves, their doctor, or an admin of their organization
+    try:
+        _check_patient_access(user_id, role, patient_id)
+    except HTTPException:
         audit_service.log_access_denied(
             user_id=user_id,
             user_role=role,
             resource_type="tremor_data",
             resource_id=patient_id,
-            required_role="patient (self) or doctor/admin"
+            required_role="patient (self), assigned doctor or admin"
         )
-        raise HTTPException(403, detail="Access denied")
-    
-    items, count = db.get_tremor_analysis(patient_id, start_time, end_time, limit)
+        raise
     
-    # Log patient data access
-    audit_service.log_patient_data_access(
-        user_id=user_id,
-        user_role=role,
-        patient_id=patient_id,
-        data_type="tremor_analysis",
-        action="query"
-    )
+    # Excessive access is refused; allowed access is logged
+    _record_patient_data_access(request, patient_id, "tremor_analysis")
+    items = [_minimum_necessary(request, item) for item in db.get_tremor_analysis(patient_id, start_time, end_time, limit).items]
     
     return {
         "success": True,
         "data": items,
-        "count": count
+        "count": len(items)
     }
 
 # Lambda handler
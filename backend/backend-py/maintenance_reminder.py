@@ -0,0 +1,93 @@
+"""
+MeDUSA Maintenance Reminders
+
+EventBridge Scheduler handler (daily). Publishes one SNS message per open
+maintenance event due within MAINTENANCE_REMINDER_DAYS to the maintenance
+topic. Each message carries a technicianId attribute so technicians
+subscribe with a filter policy on their own user ID; unassigned events
+are sent with technicianId "unassigned".
+
+Key Features:
+- One reminder per event (reminderSentAt is recorded after publishing)
+- Publish failures are logged and retried on the next run
+"""
+
+import os
+import json
+from datetime import datetime, timezone
+from typing import Any, Dict, Optional
+
+import aws_clients
+import db
+from device_maintenance_service import get_upcoming_maintenance, maintenance_label
+
+MAINTENANCE_TOPIC_ARN = os.environ.get("MAINTENANCE_TOPIC_ARN")
+UNASSIGNED_TECHNICIAN = "unassigned"
+
+_sns = None
+
+
+def _sns_client():
+    """Created lazily so importing this module needs no AWS region."""
+    global _sns
+    if _sns is None:
+        _sns = aws_clients.get_client("sns")
+    return _sns
+
+
+def reminder_message(event: Dict[str, Any]) -> Dict[str, Any]:
+    """SNS publish arguments for one maintenance event."""
+    label = maintenance_label(event)
+    payload = {
+        "maintenanceId": event["id"],
+        "deviceId": event["deviceId"],
+        "maintenanceType": event["maintenanceType"],
+        "customType": event.get("customType"),
+        "scheduledAt": event["scheduledAt"],
+        "assignedTo": event.get("assignedTo"),
+        "notes": event.get("notes"),
+    }
+    return {
+        "TopicArn": MAINTENANCE_TOPIC_ARN,
+        "Subject": f"MeDUSA maintenance due: {label} for device {event['deviceId']}"[:100],
+        "Message": json.dumps(payload, default=str),
+        "MessageAttributes": {
+            "technicianId": {"DataType": "String", "StringValue": event.get("assignedTo") or UNASSIGNED_TECHNICIAN},
+            "maintenanceType": {"DataType": "String", "StringValue": event["maintenanceType"]},
+        },
+    }
+
+
+def send_due_reminders(now: Optional[datetime] = None) -> Dict[str, int]:
+    """
+    Remind technicians of maintenance due within MAINTENANCE_REMINDER_DAYS.
+
+    Returns:
+        {"sent": n, "skipped": n, "failed": n}; skipped events were already reminded
+    """
+    now = now or datetime.now(timezone.utc)
+    counts = {"sent": 0, "skipped": 0, "failed": 0}
+    if not MAINTENANCE_TOPIC_ARN:
+        print("[MaintenanceReminder] MAINTENANCE_TOPIC_ARN not set, no reminders sent")
+        return counts
+
+    for event in get_upcoming_maintenance(now):
+        if event.get("reminderSentAt"):
+            counts["skipped"] += 1
+            continue
+        try:
+            _sns_client().publish(**reminder_message(event))
+        except Exception as e:
+            print(f"[MaintenanceReminder] Failed to publish reminder {event['id']}: {e}")
+            counts["failed"] += 1
+            continue
+        db.put_maintenance_event({**event, "reminderSentAt": now.isoformat()})
+        counts["sent"] += 1
+    return counts
+
+
+def run(event, context):
+    """EventBridge Scheduler entry point."""
+    result = send_due_reminders()
+    print(f"[MaintenanceReminder] {result}")
+    return result
@@ -0,0 +1,128 @@
+"""
+MeDUSA Maintenance Mode Service
+
+While maintenance mode is on, authenticated non-admin requests get
+503 Service Unavailable; admins keep access to verify the system.
+
+Key Features:
+- Flag stored as the maintenanceMode system setting (with maintenanceMessage)
+- Flag cached per Lambda container for MAINTENANCE_CACHE_SECONDS
+- Toggling emits MAINTENANCE_MODE_ENABLED / MAINTENANCE_MODE_DISABLED audit events
+- Login, refresh and health endpoints stay reachable so admins can sign in
+"""
+
+import os
+import time
+from typing import Any, Dict, Optional
+
+from fastapi import Request
+from fastapi.responses import JSONResponse
+
+import db
+from audit_service import audit_service, AuditEventType
+
+MAINTENANCE_SETTING_KEY = "maintenanceMode"
+MAINTENANCE_MESSAGE_KEY = "maintenanceMessage"
+MAINTENANCE_CACHE_SECONDS = int(os.environ.get("MAINTENANCE_CACHE_SECONDS", "15"))
+MAINTENANCE_RETRY_AFTER_SECONDS = 300
+DEFAULT_MAINTENANCE_MESSAGE = "MeDUSA is undergoing scheduled maintenance. Please try again later."
+
+# Endpoints reachable during maintenance regardless of role
+MAINTENANCE_OPEN_PATH_SUFFIXES = [
+    "/admin/health",
+    "/auth/login",
+    "/auth/mfa/login",
+    "/auth/refresh",
+    "/auth/logout",
+]
+
+
+def _setting_value(key: str) -> Any:
+    item = db.get_system_setting(key)
+    return item.get("value") if item else None
+
+
+def _as_bool(value: Any) -> bool:
+    return value is True or str(value).strip().lower() == "true"
+
+
+class MaintenanceService:
+    """Reads, caches and toggles the maintenance flag."""
+
+    def __init__(self, cache_seconds: int = MAINTENANCE_CACHE_SECONDS):
+        self.cache_seconds = cache_seconds
+        self._cached: Optional[Dict[str, Any]] = None
+        self._cached_at = 0.0
+
+    def invalidate(self) -> None:
+        self._cached = None
+
+    def status(self) -> Dict[str, Any]:
+        """{"enabled": bool, "message": str}, read at most once per cache period."""
+        now = time.monotonic()
+        if self._cached is None or now - self._cached_at >= self.cache_seconds:
+            try:
+                self._cached = {
+                    "enabled": _as_bool(_setting_value(MAINTENANCE_SETTING_KEY)),
+                    "message": _setting_value(MAINTENANCE_MESSAGE_KEY) or DEFAULT_MAINTENANCE_MESSAGE,
+                }
+            except Exception as e:
+                # Fail open: a settings outage must not take the whole API down
+                print(f"[MaintenanceService] Failed to read maintenance flag: {e}")
+                self._cached = {"enabled": False, "message": DEFAULT_MAINTENANCE_MESSAGE}
+            self._cached_at = now
+        return self._cached
+
+    def is_enabled(self) -> bool:
+        return self.status()["enabled"]
+
+    def set_enabled(
+        self,
+        enabled: Any,
+        user_id: str,
+        user_role: str,
+        message: Optional[str] = None,
+        ip_address: Optional[str] = None
+    ) -> Dict[str, Any]:
+        """Turn maintenance mode on or off (bools or "true"/"false") and audit the change."""
+        enabled = _as_bool(enabled)
+        db.put_system_setting(MAINTENANCE_SETTING_KEY, enabled, user_id)
+        if message is not None:
+            db.put_system_setting(MAINTENANCE_MESSAGE_KEY, message, user_id)
+        self.invalidate()
+
+        audit_service.log_event(
+            event_type=AuditEventType.MAINTENANCE_MODE_ENABLED if enabled else AuditEventType.MAINTENANCE_MODE_DISABLED,
+            user_id=user_id,
+            user_role=user_role,
+            resource_type="system_settings",
+            resource_id=MAINTENANCE_SETTING_KEY,
+            action="enable_maintenance" if enabled else "disable_maintenance",
+            ip_address=ip_address,
+            details={"message": message} if message is not None else {}
+        )
+        return self.status()
+
+    def blocks(self, path: str, role: Optional[str]) -> bool:
+        """Whether a request for path by a user with role is refused."""
+        if role == "admin" or any(path.endswith(suf) for suf in MAINTENANCE_OPEN_PATH_SUFFIXES):
+            return False
+        return self.is_enabled()
+
+
+# Global maintenance service instance
+maintenance_service = MaintenanceService()
+
+
+async def maintenance_middleware(request: Request, call_next):
+    """Runs after auth_middleware, so request.state.claims is set for authenticated requests."""
+    if request.method == "OPTIONS":
+        return await call_next(request)
+    claims = getattr(request.state, "claims", None) or {}
+    if maintenance_service.blocks(request.url.path, claims.get("role")):
+        return JSONResponse(
+            status_code=503,
+            content={"code": "MAINTENANCE_MODE", "message": maintenance_service.status()["message"]},
+            headers={"Retry-After": str(MAINTENANCE_RETRY_AFTER_SECONDS)}
+        )
+    return await call_next(request)
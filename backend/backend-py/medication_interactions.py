@@ -0,0 +1,176 @@
+"""
+MeDUSA Medication Interaction Checks
+
+Warns when two of a patient's active medications are known to interact.
+
+Key Features:
+- check_interactions(medications) -> severity-tagged InteractionWarnings,
+  most severe first
+- Pluggable dataset: an InteractionSource supplies the interaction table
+  - BuiltinInteractionSource: interactions common in Parkinson's care
+    (MAO inhibitors, dopamine antagonists, serotonergic drugs)
+  - S3InteractionSource: a JSON list of {"drugA", "drugB", "severity",
+    "description"} objects at MEDICATION_INTERACTIONS_S3_KEY
+  - set_interaction_source() swaps the dataset (e.g. a licensed drug database)
+- The table is loaded once per container
+- Drugs match medication names case-insensitively by whole word, so
+  "Carbidopa/Levodopa 25/100" matches both carbidopa and levodopa
+"""
+
+import json
+import os
+import re
+from dataclasses import dataclass
+from enum import Enum
+from itertools import combinations
+from typing import Any, Dict, Iterable, List, Optional
+
+import storage
+
+MEDICATION_INTERACTIONS_S3_KEY = os.environ.get("MEDICATION_INTERACTIONS_S3_KEY")
+BLOCK_CRITICAL_INTERACTIONS = os.environ.get("BLOCK_CRITICAL_INTERACTIONS", "true").lower() == "true"
+
+
+class InteractionSeverity(str, Enum):
+    MINOR = "minor"
+    MODERATE = "moderate"
+    MAJOR = "major"
+    CRITICAL = "critical"
+
+
+_SEVERITY_RANK = {s: i for i, s in enumerate(InteractionSeverity)}
+
+
+@dataclass(frozen=True)
+class InteractionRule:
+    """Two drugs (lowercase generic names) that interact."""
+    drug_a: str
+    drug_b: str
+    severity: InteractionSeverity
+    description: str
+
+    @classmethod
+    def from_dict(cls, item: Dict[str, Any]) -> "InteractionRule":
+        return cls(
+            drug_a=str(item["drugA"]).strip().lower(),
+            drug_b=str(item["drugB"]).strip().lower(),
+            severity=InteractionSeverity(str(item["severity"]).lower()),
+            description=str(item.get("description", "")),
+        )
+
+
+@dataclass(frozen=True)
+class InteractionWarning:
+    """Two of the patient's medications that interact."""
+    medication_ids: tuple
+    medication_names: tuple
+    severity: InteractionSeverity
+    description: str
+
+    def to_dict(self) -> Dict[str, Any]:
+        return {
+            "medicationIds": list(self.medication_ids),
+            "medicationNames": list(self.medication_names),
+            "severity": self.severity.value,
+            "description": self.description,
+        }
+
+
+class InteractionSource:
+    """Supplies the interaction table."""
+
+    def load(self) -> List[InteractionRule]:
+        raise NotImplementedError
+
+
+_BUILTIN_RULES = [
+    ("selegiline", "meperidine", InteractionSeverity.CRITICAL, "Risk of serotonin syndrome; avoid combination"),
+    ("rasagiline", "meperidine", InteractionSeverity.CRITICAL, "Risk of serotonin syndrome; avoid combination"),
+    ("safinamide", "meperidine", InteractionSeverity.CRITICAL, "Risk of serotonin syndrome; avoid combination"),
+    ("levodopa", "phenelzine", InteractionSeverity.CRITICAL, "Non-selective MAO inhibitor: risk of hypertensive crisis"),
+    ("levodopa", "tranylcypromine", InteractionSeverity.CRITICAL, "Non-selective MAO inhibitor: risk of hypertensive crisis"),
+    ("selegiline", "tramadol", InteractionSeverity.MAJOR, "Risk of serotonin syndrome and seizures"),
+    ("rasagiline", "tramadol", InteractionSeverity.MAJOR, "Risk of serotonin syndrome and seizures"),
+    ("selegiline", "fluoxetine", InteractionSeverity.MAJOR, "Risk of serotonin syndrome"),
+    ("rasagiline", "fluoxetine", InteractionSeverity.MAJOR, "Risk of serotonin syndrome"),
+    ("levodopa", "haloperidol", InteractionSeverity.MAJOR, "Dopamine antagonist reduces levodopa effect and worsens parkinsonism"),
+    ("levodopa", "metoclopramide", InteractionSeverity.MAJOR, "Dopamine antagonist reduces levodopa effect and worsens parkinsonism"),
+    ("levodopa", "risperidone", InteractionSeverity.MODERATE, "Dopamine antagonist may reduce levodopa effect"),
+    ("amantadine", "bupropion", InteractionSeverity.MODERATE, "Additive CNS effects; lowered seizure threshold"),
+    ("levodopa", "ferrous sulfate", InteractionSeverity.MINOR, "Iron reduces levodopa absorption; separate doses"),
+]
+
+
+class BuiltinInteractionSource(InteractionSource):
+    """A small table of interactions relevant to Parkinson's treatment."""
+
+    def load(self) -> List[InteractionRule]:
+        return [InteractionRule(a, b, severity, description) for a, b, severity, description in _BUILTIN_RULES]
+
+
+class S3InteractionSource(InteractionSource):
+    """A JSON interaction table stored in the data bucket."""
+
+    def __init__(self, key: str):
+        self.key = key
+
+    def load(self) -> List[InteractionRule]:
+        items = json.loads(storage.download_bytes(self.key))
+        return [InteractionRule.from_dict(item) for item in items]
+
+
+_source: InteractionSource = (
+    S3InteractionSource(MEDICATION_INTERACTIONS_S3_KEY) if MEDICATION_INTERACTIONS_S3_KEY else BuiltinInteractionSource()
+)
+_rules: Optional[List[InteractionRule]] = None
+
+
+def set_interaction_source(source: InteractionSource) -> None:
+    """Use another interaction dataset; the table is reloaded on next use."""
+    global _source, _rules
+    _source = source
+    _rules = None
+
+
+def interaction_rules() -> List[InteractionRule]:
+    """The interaction table, loaded on first use and kept for the container's lifetime."""
+    global _rules
+    if _rules is None:
+        _rules = _source.load()
+    return _rules
+
+
+def _drug_pattern(drug: str) -> re.Pattern:
+    return re.compile(rf"(?<![a-z]){re.escape(drug)}(?![a-z])")
+
+
+def _mentions(name: str, drug: str) -> bool:
+    return bool(_drug_pattern(drug).search(name.lower()))
+
+
+def _interaction(first: Dict[str, Any], second: Dict[str, Any], rule: InteractionRule) -> bool:
+    a, b = first.get("name") or "", second.get("name") or ""
+    return ((_mentions(a, rule.drug_a) and _mentions(b, rule.drug_b))
+            or (_mentions(a, rule.drug_b) and _mentions(b, rule.drug_a)))
+
+
+def check_interactions(medications: Iterable[Dict[str, Any]]) -> List[InteractionWarning]:
+    """Warnings for every interacting pair of active medications, most severe first."""
+    active = [m for m in medications if m.get("isActive", True)]
+    rules = interaction_rules()
+    warnings = []
+    for first, second in combinations(active, 2):
+        for rule in rules:
+            if _interaction(first, second, rule):
+                warnings.append(InteractionWarning(
+                    medication_ids=(first.get("medicationId"), second.get("medicationId")),
+                    medication_names=(first.get("name"), second.get("name")),
+                    severity=rule.severity,
+                    description=rule.description,
+                ))
+    warnings.sort(key=lambda w: _SEVERITY_RANK[w.severity], reverse=True)
+    return warnings
+
+
+def critical_interactions(warnings: Iterable[InteractionWarning]) -> List[InteractionWarning]:
+    return [w for w in warnings if w.severity == InteractionSeverity.CRITICAL]
@@ -0,0 +1,92 @@
+"""
+MeDUSA Operational Metrics
+
+Publishes counts and latencies to CloudWatch as Embedded Metric Format
+(EMF) log lines: CloudWatch Logs extracts the metrics, no PutMetricData
+call or extra permission is needed.
+
+Key Features:
+- Zero-config optional: without METRICS_NAMESPACE every call is a no-op
+- Metrics tagged with dimensions (e.g. Endpoint, Reason, Operation)
+- timer() context manager for latencies in milliseconds
+- Emitting never raises; a failed metric must not fail the request
+"""
+
+import os
+import json
+import time
+from contextlib import contextmanager
+from typing import Any, Dict, Iterator, Optional, Tuple
+
+METRICS_NAMESPACE = os.environ.get("METRICS_NAMESPACE")
+
+# Metric names
+AUTH_SUCCESS = "AuthSuccess"
+AUTH_FAILURE = "AuthFailure"
+DB_OPERATION_DURATION = "DbOperationDuration"
+REPORT_GENERATION_DURATION = "ReportGenerationDuration"
+
+
+class MetricsEmitter:
+    """Writes EMF log lines for one CloudWatch namespace."""
+
+    def __init__(self, namespace: Optional[str] = METRICS_NAMESPACE):
+        self.namespace = namespace
+
+    @property
+    def enabled(self) -> bool:
+        return bool(self.namespace)
+
+    def build_payload(
+        self,
+        metrics: Dict[str, Tuple[float, str]],
+        dimensions: Optional[Dict[str, str]] = None,
+        timestamp_ms: Optional[int] = None
+    ) -> Dict[str, Any]:
+        """
+        EMF document for metrics ({name: (value, unit)}) sharing one dimension set.
+        """
+        dimensions = {k: str(v) for k, v in (dimensions or {}).items() if v is not None}
+        return {
+            "_aws": {
+                "Timestamp": timestamp_ms if timestamp_ms is not None else int(time.time() * 1000),
+                "CloudWatchMetrics": [{
+                    "Namespace": self.namespace,
+                    "Dimensions": [list(dimensions)],
+                    "Metrics": [{"Name": name, "Unit": unit} for name, (_, unit) in metrics.items()],
+                }],
+            },
+            **dimensions,
+            **{name: value for name, (value, _) in metrics.items()},
+        }
+
+    def put_metrics(self, metrics: Dict[str, Tuple[float, str]], **dimensions: str) -> Optional[Dict[str, Any]]:
+        """Emit several metrics in one line; returns the payload, or None when disabled."""
+        if not self.enabled:
+            return None
+        try:
+            payload = self.build_payload(metrics, dimensions)
+            print(json.dumps(payload, default=str))
+            return payload
+        except Exception as e:
+            print(f"[Metrics] Failed to emit {list(metrics)}: {e}")
+            return None
+
+    def put_metric(self, name: str, value: float, unit: str = "Count", **dimensions: str) -> Optional[Dict[str, Any]]:
+        return self.put_metrics({name: (value, unit)}, **dimensions)
+
+    def count(self, name: str, value: int = 1, **dimensions: str) -> Optional[Dict[str, Any]]:
+        return self.put_metric(name, value, "Count", **dimensions)
+
+    @contextmanager
+    def timer(self, name: str, **dimensions: str) -> Iterator[None]:
+        """Emit the duration of the block in milliseconds, also when it raises."""
+        start = time.perf_counter()
+        try:
+            yield
+        finally:
+            self.put_metric(name, round((time.perf_counter() - start) * 1000, 3), "Milliseconds", **dimensions)
+
+
+# Global instance
+metrics = MetricsEmitter()
@@ -1,6 +1,22 @@
-from pydantic import BaseModel, Field
-from typing import Optional, List
-from datetime import datetime
+from pydantic import BaseModel, Field, model_validator, field_validator
+from pydantic.alias_generators import to_camel
+from typing import Optional, List, Dict, Any, Literal
+from datetime import datetime, date
+
+from validators import (
+    validate_date_of_birth, validate_height_cm, validate_weight_kg, validate_language, validate_timezone,
+    normalize_case, PhoneNumber
+)
+
+class CamelModel(BaseModel):
+    """
+    Response model whose fields mirror snake_case DynamoDB attributes but are
+    serialized as camelCase, like the rest of the API. Accepts either spelling
+    on input, so stored items can be passed straight in.
+    """
+    class Config:
+        alias_generator = to_camel
+        populate_by_name = True
 
 # ========================================
 # Request Models (API v3 compliant)
@@ -18,11 +34,15 @@ class RegisterReq(BaseModel):
     verificationCode: str  # Required: 6-digit code from email
     role: str = "patient"  # API v3 requires role field
 
+    _normalize_role = field_validator("role", mode="before")(normalize_case)
+
 class RequestVerificationReq(BaseModel):
     """Request verification code - backend generates and sends code"""
     email: str
     type: str = "registration"  # 'registration' or 'password_reset'
 
+    _normalize_type = field_validator("type", mode="before")(normalize_case)
+
 class RefreshReq(BaseModel):
     """Refresh request - API v3 uses camelCase"""
     refreshToken: str = Field(alias="refreshToken")
@@ -33,12 +53,28 @@ class ResetPasswordReq(BaseModel):
     verificationCode: str  # Required: 6-digit code from email
     newPassword: str
 
+class RequestOtpReq(BaseModel):
+    """Request an SMS one-time code for password reset"""
+    email: str
+
+class VerifyOtpReq(BaseModel):
+    """Exchange an SMS one-time code for a password reset verification code"""
+    email: str
+    otp: str
+
+class ChangePasswordReq(BaseModel):
+    """Change password request for a signed-in user"""
+    currentPassword: str
+    newPassword: str
+
 class SendVerificationCodeReq(BaseModel):
     """Send verification code request"""
     email: str
     code: str
     type: str  # 'registration' or 'password_reset'
 
+    _normalize_type = field_validator("type", mode="before")(normalize_case)
+
 # ========================================
 # Auth Response Models (API v3 - flat, no data wrapper)
 # ========================================
@@ -60,6 +96,7 @@ class LoginRes(BaseModel):
     refreshToken: str
     expiresIn: int  # API v3 uses camelCase
     user: dict  # User information
+    passwordExpired: bool = False  # client must send the user through change-password
     
     class Config:
         populate_by_name = True
@@ -72,10 +109,61 @@ class RefreshRes(BaseModel):
     class Config:
         populate_by_name = True
 
+class AuthSession(BaseModel):
+    """An active login session (one unexpired refresh token)"""
+    sessionId: str
+    issuedAt: Optional[int] = None
+    lastRefreshedAt: Optional[int] = None
+    expiresAt: int
+    ipAddress: Optional[str] = None
+    userAgent: Optional[str] = None
+    current: bool = False
+
+class MaintenanceModeReq(BaseModel):
+    """Toggle maintenance mode (admin)"""
+    enabled: bool
+    message: Optional[str] = Field(default=None, max_length=500)
+
 # ========================================
 # User Model (for internal use or other endpoints)
 # ========================================
 
+ITEMS_PER_PAGE_OPTIONS = (10, 20, 50, 100)
+
+class UserPreferences(BaseModel):
+    """Per-user UI settings, stored as the preferences map on the user record"""
+    language: str = "en-US"
+    timezone: str = "UTC"
+    dateFormat: Literal["YYYY-MM-DD", "DD/MM/YYYY", "MM/DD/YYYY", "DD.MM.YYYY"] = "YYYY-MM-DD"
+    itemsPerPage: int = 20
+    enableEmailNotifications: bool = True
+    enableSmsNotifications: bool = False
+    dashboardWidgets: List[str] = Field(default_factory=list, max_length=20)
+
+    _check_language = field_validator("language")(validate_language)
+    _check_timezone = field_validator("timezone")(validate_timezone)
+
+    @field_validator("itemsPerPage")
+    @classmethod
+    def _check_items_per_page(cls, value: int) -> int:
+        if value not in ITEMS_PER_PAGE_OPTIONS:
+            raise ValueError(f"itemsPerPage must be one of: {', '.join(map(str, ITEMS_PER_PAGE_OPTIONS))}")
+        return value
+
+class PushTokenReq(BaseModel):
+    """Register a mobile device token for push notifications"""
+    token: str = Field(..., min_length=1, max_length=4096)
+    platform: Literal["apns", "fcm"]
+    deviceName: Optional[str] = Field(None, max_length=100)
+
+class PushToken(BaseModel):
+    """A registered push notification device token"""
+    tokenId: str
+    platform: Literal["apns", "fcm"]
+    deviceName: Optional[str] = None
+    createdAt: str
+    updatedAt: str
+
 class UserOut(BaseModel):
     """User object - internal use"""
     id: str
@@ -83,12 +171,19 @@ class UserOut(BaseModel):
     role: str
     name: Optional[str] = None
     createdAt: datetime
+    profilePictureUrl: Optional[str] = None  # short-lived presigned URL
+    preferences: Optional[UserPreferences] = None  # omitted from login responses
 
     class Config:
         json_encoders = {
             datetime: lambda v: v.isoformat()
         }
 
+class ProfilePictureRes(BaseModel):
+    """Response after uploading a profile picture"""
+    profilePictureUrl: str
+    expiresIn: int
+
 class PoseCreateReq(BaseModel):
     """Request model for creating a pose"""
     patientId: Optional[str] = None
@@ -97,9 +192,25 @@ class PoseCreateReq(BaseModel):
 class PresignReq(BaseModel):
     filename: str
     contentType: str
-    scope: str  # "pose" | "report"
+    scope: str  # "pose" | "report" | "import" (admin only)
     patientId: Optional[str] = None
 
+class ReadingImportReq(BaseModel):
+    """Import readings from a file uploaded with the "import" presign scope"""
+    fileKey: str
+    format: Optional[Literal["csv", "ndjson"]] = None  # default: from the file extension
+
+    _normalize_format = field_validator("format", mode="before")(normalize_case)
+
+class ReadingImportFailure(BaseModel):
+    line: int
+    error: str
+
+class ReadingImportRes(BaseModel):
+    succeeded: int
+    failed: int
+    failures: List[ReadingImportFailure]  # first 100 failed rows
+
 class PresignRes(BaseModel):
     uploadUrl: str
     fileKey: str
@@ -115,16 +226,102 @@ class PosePage(BaseModel):
     items: List[Pose]
     nextToken: Optional[str] = None
 
+SubscriptionTierName = Literal["basic", "professional", "enterprise"]
+
+class OrganizationCreateReq(BaseModel):
+    name: str = Field(..., min_length=1, max_length=200)
+    subdomain: str = Field(..., min_length=1, max_length=63)
+    adminUserIds: List[str] = Field(..., min_length=1)
+    subscriptionTier: SubscriptionTierName = "basic"
+
+class Organization(BaseModel):
+    """Tenant (hospital or clinic) owning users, patients, devices and reports"""
+    id: str
+    name: str
+    subdomain: str
+    adminUserIds: List[str]
+    subscriptionTier: SubscriptionTierName
+    createdAt: datetime
+
+class RoleCreateReq(BaseModel):
+    name: str = Field(..., min_length=2, max_length=49)
+    permissions: List[str] = Field(..., min_length=1)
+
+    _normalize_name = field_validator("name", mode="before")(normalize_case)
+
+class RoleDefinition(BaseModel):
+    """Custom role: a named subset of the doctor's permissions, assignable to users"""
+    name: str
+    permissions: List[str]
+    createdAt: datetime
+    createdBy: Optional[str] = None
+
 class Report(BaseModel):
     id: str
     patientId: str
     fileKey: str
     createdAt: datetime
+    signature: Optional[str] = None  # hex HMAC-SHA-256 of the report file
 
 class ReportPage(BaseModel):
     items: List[Report]
     nextToken: Optional[str] = None
 
+class ReportSummary(BaseModel):
+    """Report listing entry (no file or signature details)"""
+    reportId: str
+    patientId: str
+    authorId: Optional[str] = None
+    type: Optional[str] = None
+    title: Optional[str] = None
+    status: str
+    createdAt: datetime
+    visibleToPatient: bool = True
+
+class ScheduleFrequency(BaseModel):
+    """How often a scheduled report runs: daily, weekly on a weekday, or monthly on a day"""
+    type: Literal["daily", "weekly", "monthly"]
+    weekday: Optional[Literal["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"]] = None
+    dayOfMonth: Optional[int] = Field(default=None, ge=1, le=31)  # clamped to the month's last day
+
+    _normalize_names = field_validator("type", "weekday", mode="before")(normalize_case)
+
+    @model_validator(mode="after")
+    def _check_fields(self):
+        if self.type == "weekly" and self.weekday is None:
+            raise ValueError("weekday is required for weekly schedules")
+        if self.type == "monthly" and self.dayOfMonth is None:
+            raise ValueError("dayOfMonth is required for monthly schedules")
+        return self
+
+class ReportScheduleCreateReq(BaseModel):
+    """Schedule a recurring report; reportTemplate is the body a report would be created with"""
+    reportTemplate: Dict[str, Any]
+    frequency: ScheduleFrequency
+    startAt: Optional[datetime] = None  # first run no earlier than this (default: now); sets the time of day
+
+    @field_validator("reportTemplate")
+    @classmethod
+    def _require_patient(cls, v):
+        if not v.get("patientId"):
+            raise ValueError("reportTemplate.patientId is required")
+        return v
+
+class ReportSchedule(BaseModel):
+    """Recurring report schedule"""
+    id: str
+    reportTemplate: Dict[str, Any]
+    frequency: ScheduleFrequency
+    nextRunAt: datetime
+    lastRunAt: Optional[datetime] = None
+    createdBy: str
+    isActive: bool
+    createdAt: datetime
+
+class ReportSchedulePage(BaseModel):
+    items: List[ReportSchedule]
+    count: int
+
 # ========================================
 # Device Models
 # ========================================
@@ -136,18 +333,74 @@ class DeviceRegisterReq(BaseModel):
     type: str = "tremor_sensor"
     firmwareVersion: str = "1.0.0"
 
+    _normalize_type = field_validator("type", mode="before")(normalize_case)
+
 class DeviceUpdateReq(BaseModel):
     """Update device request"""
     name: Optional[str] = None
     batteryLevel: Optional[int] = None
     status: Optional[str] = None
     firmwareVersion: Optional[str] = None
+    allowDowngrade: bool = False  # Must be set explicitly to install an older firmware
+
+    _normalize_status = field_validator("status", mode="before")(normalize_case)
 
 class DeviceBindReq(BaseModel):
     """Bind device request"""
     deviceId: str
     patientId: str
 
+class DeviceAssignReq(BaseModel):
+    """Assign a device to a patient; force moves it away from another patient"""
+    patientId: str
+    force: bool = False
+
+class DeviceAssignment(BaseModel):
+    """Entry of a device's assignment history"""
+    action: Literal["assign", "reassign", "unassign"]
+    patientId: str
+    previousPatientId: Optional[str] = None
+    by: str
+    at: str
+
+class FirmwareChange(BaseModel):
+    """Firmware version change recorded on a device"""
+    version: str
+    previousVersion: Optional[str] = None
+    changedAt: str
+    changedBy: str
+
+MaintenanceTypeName = Literal["calibration", "software_update", "hardware_inspection", "cleaning_and_disinfection", "custom"]
+
+class MaintenanceEvent(BaseModel):
+    """Scheduled or completed device maintenance"""
+    id: str
+    deviceId: str
+    maintenanceType: MaintenanceTypeName
+    customType: Optional[str] = None  # Set for custom maintenance
+    scheduledAt: datetime
+    completedAt: Optional[datetime] = None
+    performedBy: Optional[str] = None
+    assignedTo: Optional[str] = None  # Technician reminded before scheduledAt
+    notes: Optional[str] = None
+    nextMaintenanceDue: Optional[datetime] = None
+
+class MaintenanceScheduleReq(BaseModel):
+    """Schedule device maintenance request"""
+    maintenanceType: MaintenanceTypeName
+    customType: Optional[str] = Field(None, max_length=100)  # Required when maintenanceType is custom
+    scheduledAt: datetime
+    assignedTo: Optional[str] = None
+    notes: Optional[str] = Field(None, max_length=2000)
+
+    _normalize_type = field_validator("maintenanceType", mode="before")(normalize_case)
+
+class MaintenanceCompleteReq(BaseModel):
+    """Complete device maintenance request"""
+    completedAt: Optional[datetime] = None  # Defaults to now
+    notes: Optional[str] = Field(None, max_length=2000)
+    nextMaintenanceDue: Optional[datetime] = None  # Schedules a follow-up of the same type
+
 class Device(BaseModel):
     """Device model"""
     id: str
@@ -158,7 +411,13 @@ class Device(BaseModel):
     currentSessionId: Optional[str] = None  # Current active session
     status: str  # online, offline, error
     batteryLevel: int
+    batteryUpdatedAt: Optional[str] = None  # Last battery report
     firmwareVersion: str
+    firmwareHistory: List[FirmwareChange] = []
+    lastCalibratedAt: Optional[str] = None
+    calibrationDueAt: Optional[str] = None
+    calibrationDue: bool = False  # True when the device is overdue for calibration
+    maintenanceSchedule: List[MaintenanceEvent] = []  # Open maintenance (device detail only)
     lastSeen: datetime
     createdAt: datetime
     updatedAt: datetime
@@ -168,16 +427,81 @@ class Device(BaseModel):
             datetime: lambda v: v.isoformat()
         }
 
+class DeviceConnectionReq(BaseModel):
+    """Report a device connection (repeat periodically while connected)"""
+    connectionType: Literal["bluetooth", "wifi", "usb"]
+    signalStrength: Optional[int] = Field(None, ge=-127, le=0)  # RSSI in dBm
+    batteryLevel: Optional[int] = Field(None, ge=0, le=100)  # Percent
+
+    _normalize_type = field_validator("connectionType", mode="before")(normalize_case)
+
+class DeviceConnectionInfo(BaseModel):
+    """Current connection state of a device"""
+    deviceId: str
+    state: Literal["connected", "stale", "disconnected"]  # stale: no report within DEVICE_STALE_SECONDS
+    connected: bool
+    connectionType: Optional[str] = None
+    signalStrength: Optional[int] = None
+    lastConnectedAt: Optional[datetime] = None
+    lastReportedAt: Optional[datetime] = None
+    disconnectedAt: Optional[datetime] = None
+
 class DevicePage(BaseModel):
     """Device list response"""
     items: List[Device]
     nextToken: Optional[str] = None
 
+class CalibrationResult(BaseModel):
+    """Outcome of comparing measured values against reference values"""
+    passed: bool
+    offset: float  # Mean (reference - measured) before correction
+    correctionFactor: float  # Multiplier applied to calibrated values of later readings
+
+class CalibrationCreateReq(BaseModel):
+    """Record a calibration request"""
+    referenceValues: Dict[str, float]
+    measuredValues: Dict[str, float]
+    calibrationDate: Optional[datetime] = None  # Defaults to now
+    nextDueDate: Optional[datetime] = None  # Defaults to calibrationDate + CALIBRATION_INTERVAL_DAYS
+    notes: Optional[str] = Field(None, max_length=2000)
+    certificateUrl: Optional[str] = Field(None, max_length=2048)
+
+class DeviceCalibrationRecord(BaseModel):
+    """A stored device calibration"""
+    id: str
+    deviceId: str
+    calibratedBy: str
+    calibrationDate: datetime
+    nextDueDate: datetime
+    referenceValues: Dict[str, float] = {}
+    measuredValues: Dict[str, float] = {}
+    calibrationResult: Optional[CalibrationResult] = None  # None for records made before results were tracked
+    notes: Optional[str] = None
+    certificateUrl: Optional[str] = None
+
 # ========================================
 # Patient Profile Models
 # ========================================
 
-class PatientProfileCreateReq(BaseModel):
+class EmergencyContact(BaseModel):
+    """Patient emergency contact; phone is stored in E.164 form"""
+    name: str = Field(min_length=1, max_length=200)
+    phone: PhoneNumber
+    relationship: Optional[str] = Field(default=None, max_length=100)
+
+class PatientDemographicsFields(BaseModel):
+    """Date of birth, body measurements and contact details, shared by profile create/update"""
+    dateOfBirth: Optional[date] = None
+    heightCm: Optional[float] = None
+    weightKg: Optional[float] = None
+    phone: Optional[PhoneNumber] = None
+    emergencyContact: Optional[EmergencyContact] = None
+    
+    _check_date_of_birth = field_validator("dateOfBirth")(validate_date_of_birth)
+    _check_height = field_validator("heightCm")(validate_height_cm)
+    _check_weight = field_validator("weightKg")(validate_weight_kg)
+
+class PatientProfileCreateReq(PatientDemographicsFields):
     """Create patient profile request (for admin/doctor)"""
     userId: str
     doctorId: str
@@ -185,7 +509,7 @@ class PatientProfileCreateReq(BaseModel):
     severity: Optional[str] = "mild"  # mild, moderate, severe
     notes: Optional[str] = None
 
-class PatientProfileUpdateReq(BaseModel):
+class PatientProfileUpdateReq(PatientDemographicsFields):
     """Update patient profile request"""
     diagnosis: Optional[str] = None
     severity: Optional[str] = None
@@ -198,6 +522,13 @@ class PatientProfile(BaseModel):
     diagnosis: Optional[str] = None
     severity: str  # mild, moderate, severe
     notes: Optional[str] = None
+    dateOfBirth: Optional[date] = None
+    age: Optional[int] = None  # Derived from dateOfBirth
+    heightCm: Optional[float] = None
+    weightKg: Optional[float] = None
+    phone: Optional[str] = None
+    emergencyContact: Optional[EmergencyContact] = None
+    assignedDevices: List[str] = []
     createdAt: datetime
     updatedAt: datetime
     
@@ -223,10 +554,80 @@ class PatientWithProfile(BaseModel):
             datetime: lambda v: v.isoformat()
         }
 
-class PatientPage(BaseModel):
-    """Patient list response"""
-    items: List[PatientWithProfile]
-    nextToken: Optional[str] = None
+# ========================================
+# Medication Models
+# ========================================
+
+class MedicationReq(BaseModel):
+    """Create / replace medication request"""
+    name: str = Field(min_length=1, max_length=200)
+    dosage: str = Field(min_length=1, max_length=100)  # e.g. "100 mg"
+    frequency: str = Field(min_length=1, max_length=100)  # e.g. "3x daily"
+    route: Optional[str] = Field(default=None, max_length=50)  # oral, transdermal, ...
+    startDate: Optional[str] = Field(default=None, pattern=r"^\d{4}-\d{2}-\d{2}$")
+    endDate: Optional[str] = Field(default=None, pattern=r"^\d{4}-\d{2}-\d{2}$")
+    notes: Optional[str] = Field(default=None, max_length=1000)
+    
+    @model_validator(mode="after")
+    def _check_dates(self):
+        if self.startDate and self.endDate and self.endDate < self.startDate:
+            raise ValueError("endDate must not be before startDate")
+        return self
+
+class Medication(BaseModel):
+    """Medication assigned to a patient"""
+    medicationId: str
+    patientId: str
+    name: str
+    dosage: str
+    frequency: str
+    route: Optional[str] = None
+    startDate: Optional[str] = None
+    endDate: Optional[str] = None
+    notes: Optional[str] = None
+    isActive: bool = True
+    prescribedBy: str
+    createdAt: str
+    updatedAt: str
+
+class ConsentReq(BaseModel):
+    """Grant or revoke a patient consent"""
+    consentType: str  # data_sharing, research_participation, telehealth_services, third_party_access, custom:<name>
+    granted: bool
+    expiresAt: Optional[datetime] = None
+    witness: Optional[str] = Field(default=None, max_length=200)
+    documentUrl: Optional[str] = Field(default=None, max_length=2048)
+
+class ConsentRecord(BaseModel):
+    """A stored consent grant or revocation"""
+    id: str
+    patientId: str
+    consentType: str
+    granted: bool
+    grantedAt: datetime
+    expiresAt: Optional[datetime] = None
+    grantedBy: str
+    witness: Optional[str] = None
+    documentUrl: Optional[str] = None
+    active: bool = False  # True for the newest, granted, unexpired record of its type
+
+class MedicationPage(BaseModel):
+    """Medication list response"""
+    items: List[Medication]
+    count: int
+
+class InteractionWarning(BaseModel):
+    """Two active medications of a patient that interact"""
+    medicationIds: List[Optional[str]]
+    medicationNames: List[Optional[str]]
+    severity: Literal["minor", "moderate", "major", "critical"]
+    description: str
+
+class MedicationInteractionsRes(BaseModel):
+    """Interaction warnings for a patient's active medications, most severe first"""
+    patientId: str
+    items: List[InteractionWarning]
+    count: int
 
 # ========================================
 # Session Models (Device-Patient Dynamic Binding)
@@ -289,7 +690,7 @@ class SessionPage(BaseModel):
 # Tremor Analysis Models
 # ========================================
 
-class TremorDataPoint(BaseModel):
+class TremorDataPoint(CamelModel):
     patient_id: str
     timestamp: int
     device_id: Optional[str] = None
@@ -300,12 +701,202 @@ class TremorDataPoint(BaseModel):
     signal_quality: Optional[float] = None
     tremor_power: Optional[float] = None
     total_power: Optional[float] = None
+    low_quality: Optional[bool] = None  # Set when the device was overdue for calibration
 
 class TremorResponse(BaseModel):
     success: bool
     data: List[TremorDataPoint]
     count: int
 
+class ReadingStats(BaseModel):
+    """Summary statistics for one metric within a period"""
+    mean: float
+    median: float
+    stdDev: float
+    p95: float
+    min: float
+    max: float
+
+class AggregatedReading(BaseModel):
+    """Readings summarized over one aggregation period"""
+    periodStart: str
+    periodEnd: str
+    readingType: str
+    count: int
+    stats: Dict[str, ReadingStats]
+
+class ReadingSummaryRes(BaseModel):
+    """Device readings summary response"""
+    deviceId: str
+    period: str
+    totalReadings: int
+    lowQualityReadings: int = 0  # Counted in totalReadings but left out of items
+    items: List[AggregatedReading]
+
+class ReadingStreamRes(BaseModel):
+    """Long-poll response: readings newer than `since`, possibly empty"""
+    deviceId: str
+    items: List[TremorDataPoint]
+    since: int  # pass as `since` in the next poll (latest returned timestamp, or the request's)
+
+class RollupMetric(BaseModel):
+    """Statistics for one metric within a rollup bucket (null when the bucket has no values)"""
+    count: int
+    min: Optional[float] = None
+    max: Optional[float] = None
+    avg: Optional[float] = None
+
+class ReadingRollup(BaseModel):
+    """One rollup bucket; empty buckets have count 0"""
+    periodStart: str
+    periodEnd: str
+    count: int
+    metrics: Dict[str, RollupMetric]
+
+class ReadingRollupRes(BaseModel):
+    """Daily/weekly/monthly readings rollup for a device or patient"""
+    deviceId: Optional[str] = None
+    patientId: Optional[str] = None
+    period: str
+    startDate: str
+    endDate: str
+    items: List[ReadingRollup]
+
+class GpsCoordinate(CamelModel):
+    """Position a GPS-enabled device reported with a reading (stored as the reading's location map)"""
+    latitude: float = Field(ge=-90, le=90)
+    longitude: float = Field(ge=-180, le=180)
+    accuracy_meters: Optional[float] = Field(default=None, ge=0)
+    altitude_m: Optional[float] = None
+
+class PointGeometry(BaseModel):
+    """GeoJSON Point; coordinates are [longitude, latitude] or [longitude, latitude, altitude]"""
+    type: Literal["Point"] = "Point"
+    coordinates: List[float]
+
+class ReadingMapProperties(CamelModel):
+    reading_type: str
+    values: Dict[str, float]
+    timestamp: int
+    accuracy_meters: Optional[float] = None
+
+class ReadingMapFeature(BaseModel):
+    type: Literal["Feature"] = "Feature"
+    geometry: PointGeometry
+    properties: ReadingMapProperties
+
+class ReadingMapRes(BaseModel):
+    """GeoJSON FeatureCollection of a device's readings that carry a location, oldest first"""
+    type: Literal["FeatureCollection"] = "FeatureCollection"
+    features: List[ReadingMapFeature]
+
+class ReadingWaveformRes(BaseModel):
+    """Raw waveform samples of an ECG reading (fetched from S3)"""
+    deviceId: str
+    timestamp: int
+    readingType: str
+    unit: str = "mV"
+    sampleRateHz: Optional[float] = None
+    samples: List[float]
+
+# ========================================
+# FHIR R4 Models (readings as Observation resources)
+# ========================================
+
+class FhirCoding(BaseModel):
+    system: str
+    code: str
+    display: Optional[str] = None
+
+class FhirCodeableConcept(BaseModel):
+    coding: List[FhirCoding]
+    text: Optional[str] = None
+
+class FhirReference(BaseModel):
+    reference: str
+
+class FhirQuantity(BaseModel):
+    value: float
+    unit: Optional[str] = None
+    system: Optional[str] = None
+    code: Optional[str] = None
+
+class FhirObservationComponent(BaseModel):
+    code: FhirCodeableConcept
+    valueQuantity: FhirQuantity
+
+class FhirObservation(BaseModel):
+    """A device reading as an Observation (fhir_converter.to_fhir_observation)"""
+    resourceType: Literal["Observation"] = "Observation"
+    id: str
+    status: str = "final"
+    category: List[FhirCodeableConcept]
+    code: FhirCodeableConcept
+    subject: FhirReference
+    device: FhirReference
+    effectiveDateTime: str
+    valueQuantity: Optional[FhirQuantity] = None
+    component: Optional[List[FhirObservationComponent]] = None  # Multi-value readings (e.g. blood pressure)
+
+class FhirBundleEntry(BaseModel):
+    fullUrl: str
+    resource: FhirObservation
+
+class FhirBundle(BaseModel):
+    """searchset Bundle of Observations"""
+    resourceType: Literal["Bundle"] = "Bundle"
+    type: str = "searchset"
+    total: int
+    entry: List[FhirBundleEntry] = []
+
+# ========================================
+# Patient Timeline Models
+# ========================================
+
+class AuditLogSummary(BaseModel):
+    """Subset of an audit log entry shown on the patient timeline"""
+    logId: Optional[str] = None
+    eventType: str
+    action: Optional[str] = None
+    resourceType: Optional[str] = None
+    resourceId: Optional[str] = None
+    outcome: Optional[str] = None
+
+class TimelineEvent(BaseModel):
+    """
+    Single patient timeline entry
+    
+    data holds the reading, medication, {"deviceId": ...} or audit summary
+    depending on type.
+    """
+    type: Literal["reading", "medication_change", "device_assignment", "audit"]
+    timestamp: str
+    action: Optional[str] = None
+    data: Dict[str, Any]
+
+class TimelineRes(BaseModel):
+    """Patient timeline response (newest first)"""
+    patientId: str
+    items: List[TimelineEvent]
+    count: int
+
+# ========================================
+# Data Erasure Models
+# ========================================
+
+class UserAnonymizationResult(BaseModel):
+    """Outcome of a right-to-erasure request"""
+    userId: str
+    erasedFields: List[str]
+    retainedFields: List[str]
+    deletedFiles: int
+
+class PatientDeletionSummary(BaseModel):
+    """Outcome of deleting a patient (readings are flagged asynchronously)"""
+    patientId: str
+    unassignedDeviceCount: int
+    anonymizedFields: List[str]
+
 # ========================================
 # Doctor Models
 # ========================================
@@ -314,7 +905,7 @@ class AssignPatientReq(BaseModel):
     doctor_id: str
     patient_email: str
 
-class DoctorPatientItem(BaseModel):
+class DoctorPatientItem(CamelModel):
     patient_id: str
     email: str
     name: Optional[str] = None
@@ -0,0 +1,321 @@
+"""
+MeDUSA Alert Notification Service
+
+Publishes clinically significant reading alerts to an SNS topic so that
+clinicians (email/SMS/webhook subscribers) are notified.
+
+Key Features:
+- Threshold-based classification of readings into alert severities
+- Configurable minimum severity for notification (default: high)
+- Best effort: publish failures are logged and audited, never raised
+- Direct transactional SMS to a phone number (one-time codes)
+- Mobile push (SNS Mobile Push, APNs / FCM) to the device tokens a user
+  registered: critical alerts to the patient's doctor, report completions
+  to the report's author
+"""
+
+import os
+import json
+from dataclasses import dataclass, asdict, field
+from datetime import datetime, timezone
+from enum import Enum
+from typing import Any, Dict, Optional
+
+import aws_clients
+import db
+from audit_service import audit_service, AuditEventType
+
+ALERTS_TOPIC_ARN = os.environ.get("ALERTS_TOPIC_ARN")
+# SNS platform applications; pushes to a platform without one are skipped
+APNS_PLATFORM_APPLICATION_ARN = os.environ.get("APNS_PLATFORM_APPLICATION_ARN")
+FCM_PLATFORM_APPLICATION_ARN = os.environ.get("FCM_PLATFORM_APPLICATION_ARN")
+# Development builds of the iOS app receive pushes from the APNs sandbox
+APNS_SANDBOX = os.environ.get("APNS_SANDBOX", "false").lower() == "true"
+
+
+class AlertSeverity(Enum):
+    LOW = "low"
+    MEDIUM = "medium"
+    HIGH = "high"
+    CRITICAL = "critical"
+
+    @property
+    def rank(self) -> int:
+        return list(AlertSeverity).index(self)
+
+
+ALERT_MIN_SEVERITY = AlertSeverity(os.environ.get("ALERT_MIN_SEVERITY", "high").lower())
+
+# reading_type -> [(key, comparison, limit, severity)], most severe first.
+# Clinical alert thresholds, deliberately tighter than the plausibility
+# ranges in reading_validation.
+ALERT_THRESHOLDS = {
+    "heart_rate": [
+        ("bpm", ">=", 150, AlertSeverity.CRITICAL),
+        ("bpm", "<=", 40, AlertSeverity.CRITICAL),
+        ("bpm", ">=", 120, AlertSeverity.HIGH),
+        ("bpm", "<=", 50, AlertSeverity.HIGH),
+    ],
+    "blood_pressure": [
+        ("systolic", ">=", 180, AlertSeverity.CRITICAL),
+        ("diastolic", ">=", 120, AlertSeverity.CRITICAL),
+        ("systolic", "<=", 80, AlertSeverity.CRITICAL),
+        ("systolic", ">=", 160, AlertSeverity.HIGH),
+        ("diastolic", ">=", 100, AlertSeverity.HIGH),
+    ],
+    "spo2": [
+        ("spo2", "<=", 88, AlertSeverity.CRITICAL),
+        ("spo2", "<=", 92, AlertSeverity.HIGH),
+    ],
+    "temperature": [
+        ("celsius", ">=", 40.0, AlertSeverity.CRITICAL),
+        ("celsius", "<=", 35.0, AlertSeverity.CRITICAL),
+        ("celsius", ">=", 38.5, AlertSeverity.HIGH),
+    ],
+    # Rate from the device's summary; the waveform itself is not classified
+    "ecg": [
+        ("heart_rate_bpm", ">=", 150, AlertSeverity.CRITICAL),
+        ("heart_rate_bpm", "<=", 40, AlertSeverity.CRITICAL),
+        ("heart_rate_bpm", ">=", 120, AlertSeverity.HIGH),
+        ("heart_rate_bpm", "<=", 50, AlertSeverity.HIGH),
+    ],
+    "tremor": [
+        ("tremor_index", ">=", 0.8, AlertSeverity.HIGH),
+        ("tremor_index", ">=", 0.6, AlertSeverity.MEDIUM),
+    ],
+}
+
+
+class PushPlatform(Enum):
+    APNS = "apns"
+    FCM = "fcm"
+
+
+def push_message(platform: PushPlatform, title: str, body: str, data: Optional[Dict[str, Any]] = None) -> str:
+    """SNS message (MessageStructure=json) with the platform-specific payload; data values are sent as strings."""
+    data = {k: str(v) for k, v in (data or {}).items()}
+    if platform == PushPlatform.APNS:
+        key = "APNS_SANDBOX" if APNS_SANDBOX else "APNS"
+        payload = {"aps": {"alert": {"title": title, "body": body}, "sound": "default"}, **data}
+    else:
+        key = "GCM"
+        payload = {"notification": {"title": title, "body": body}, "data": data}
+    return json.dumps({"default": body, key: json.dumps(payload)})
+
+
+@dataclass
+class Alert:
+    """A reading that crossed an alert threshold."""
+    patient_id: Optional[str]
+    device_id: str
+    reading_type: str
+    reading: Dict[str, Any]
+    severity: AlertSeverity
+    reason: str
+    created_at: str = field(default_factory=lambda: datetime.now(timezone.utc).isoformat())
+
+    def to_payload(self) -> Dict[str, Any]:
+        payload = asdict(self)
+        payload["severity"] = self.severity.value
+        return {
+            "type": "READING_ALERT",
+            "patientId": payload["patient_id"],
+            "deviceId": payload["device_id"],
+            "readingType": payload["reading_type"],
+            "reading": payload["reading"],
+            "severity": payload["severity"],
+            "reason": payload["reason"],
+            "createdAt": payload["created_at"],
+        }
+
+
+def classify_reading(reading_type: str, values: Dict[str, Any]) -> Optional[tuple]:
+    """
+    Match a reading against ALERT_THRESHOLDS.
+
+    Returns:
+        (AlertSeverity, reason) for the most severe matching rule, or None
+    """
+    for key, op, limit, severity in ALERT_THRESHOLDS.get(reading_type, []):
+        value = values.get(key)
+        if value is None:
+            continue
+        if (op == ">=" and value >= limit) or (op == "<=" and value <= limit):
+            return severity, f"{key} {value:g} {op} {limit:g}"
+    return None
+
+
+class NotificationService:
+    """Sends alerts to the configured SNS topic."""
+
+    def __init__(
+        self,
+        topic_arn: Optional[str] = ALERTS_TOPIC_ARN,
+        min_severity: AlertSeverity = ALERT_MIN_SEVERITY,
+        push_applications: Optional[Dict[PushPlatform, Optional[str]]] = None
+    ):
+        self.topic_arn = topic_arn
+        self.min_severity = min_severity
+        self.push_applications = push_applications if push_applications is not None else {
+            PushPlatform.APNS: APNS_PLATFORM_APPLICATION_ARN,
+            PushPlatform.FCM: FCM_PLATFORM_APPLICATION_ARN,
+        }
+        self._sns = None
+
+    def _client(self):
+        if self._sns is None:
+            self._sns = aws_clients.get_client("sns")
+        return self._sns
+
+    def should_notify(self, alert: Alert) -> bool:
+        return bool(self.topic_arn) and alert.severity.rank >= self.min_severity.rank
+
+    def notify(self, alert: Alert) -> bool:
+        """
+        Publish an alert if it meets the severity threshold.
+
+        Returns:
+            True if published; False if below threshold, unconfigured or failed
+        """
+        if not self.should_notify(alert):
+            return False
+        payload = alert.to_payload()
+        try:
+            self._client().publish(
+                TopicArn=self.topic_arn,
+                Subject=f"MeDUSA {alert.severity.value} alert: {alert.reading_type}"[:100],
+                Message=json.dumps(payload, default=str),
+                MessageAttributes={
+                    "severity": {"DataType": "String", "StringValue": alert.severity.value},
+                    "readingType": {"DataType": "String", "StringValue": alert.reading_type},
+                },
+            )
+            return True
+        except Exception as e:
+            print(f"[NotificationService] Failed to publish alert for device {alert.device_id}: {e}")
+            audit_service.log_event(
+                event_type=AuditEventType.ALERT_NOTIFICATION_FAILED,
+                user_id="system",
+                user_role="system",
+                resource_type="patient",
+                resource_id=alert.patient_id,
+                action="publish_alert",
+                outcome="failure",
+                details={"deviceId": alert.device_id, "severity": alert.severity.value, "error": str(e)}
+            )
+            return False
+
+    def send_critical_alert(
+        self,
+        patient_id: Optional[str],
+        device_id: str,
+        reading_type: str,
+        reading: Dict[str, Any],
+        reason: str
+    ) -> bool:
+        """Publish a CRITICAL alert regardless of threshold classification (e.g. statistical anomalies)."""
+        return self.notify(Alert(
+            patient_id=patient_id,
+            device_id=device_id,
+            reading_type=reading_type,
+            reading=reading,
+            severity=AlertSeverity.CRITICAL,
+            reason=reason,
+        ))
+
+    def send_sms(self, phone_number: str, message: str) -> bool:
+        """
+        Send a transactional SMS straight to an E.164 phone number (no topic).
+
+        Returns:
+            True if SNS accepted the message
+        """
+        try:
+            self._client().publish(
+                PhoneNumber=phone_number,
+                Message=message,
+                MessageAttributes={
+                    "AWS.SNS.SMS.SMSType": {"DataType": "String", "StringValue": "Transactional"},
+                },
+            )
+            return True
+        except Exception as e:
+            print(f"[NotificationService] Failed to send SMS: {e}")
+            return False
+
+    def send_push_notification(
+        self,
+        device_token: str,
+        platform: PushPlatform,
+        title: str,
+        body: str,
+        data: Optional[Dict[str, Any]] = None
+    ) -> bool:
+        """
+        Push a notification to one mobile device token through its SNS
+        platform application (the endpoint is created on first use).
+
+        Returns:
+            True if SNS accepted the message; False if the platform is unconfigured or publishing failed
+        """
+        application_arn = self.push_applications.get(platform)
+        if not application_arn:
+            return False
+        try:
+            # Idempotent: returns the existing endpoint for a known token
+            endpoint_arn = self._client().create_platform_endpoint(
+                PlatformApplicationArn=application_arn, Token=device_token
+            )["EndpointArn"]
+            self._client().publish(
+                TargetArn=endpoint_arn,
+                MessageStructure="json",
+                Message=push_message(platform, title, body, data),
+            )
+            return True
+        except Exception as e:
+            print(f"[NotificationService] Failed to send {platform.value} push: {e}")
+            return False
+
+    def push_to_user(self, user_id: Optional[str], title: str, body: str, data: Optional[Dict[str, Any]] = None) -> int:
+        """
+        Push to every device token a user registered. Best effort.
+
+        Returns:
+            Number of devices SNS accepted the notification for
+        """
+        if not user_id:
+            return 0
+        try:
+            tokens = db.get_push_tokens_for_user(user_id)
+        except Exception as e:
+            print(f"[NotificationService] Unable to load push tokens of {user_id}: {e}")
+            return 0
+        return sum(
+            self.send_push_notification(t["token"], PushPlatform(t["platform"]), title, body, data)
+            for t in tokens
+        )
+
+    def push_critical_alert(self, alert: Alert) -> int:
+        """Push a critical alert to the doctor assigned to the alert's patient."""
+        if alert.severity != AlertSeverity.CRITICAL or not alert.patient_id:
+            return 0
+        profile = db.get_patient_profile(alert.patient_id) or {}
+        return self.push_to_user(
+            profile.get("doctorId"),
+            f"Critical {alert.reading_type} alert",
+            alert.reason,
+            {"type": "READING_ALERT", "patientId": alert.patient_id, "deviceId": alert.device_id},
+        )
+
+    def push_report_ready(self, report: Dict[str, Any]) -> int:
+        """Tell a report's author that the report has been generated."""
+        return self.push_to_user(
+            report.get("authorId"),
+            "Report ready",
+            f"{report.get('title') or 'Your report'} is ready to download.",
+            {"type": "REPORT_READY", "reportId": report.get("reportId")},
+        )
+
+
+# Global notification service instance
+notification_service = NotificationService()
@@ -0,0 +1,104 @@
+"""
+MeDUSA Organization Service
+
+Multi-tenancy: every user, patient profile, device and report belongs to
+one organization (hospital or clinic).
+
+How isolation works:
+1. Access and refresh tokens carry the user's organization in the "org"
+   claim (tokens without one belong to DEFAULT_ORGANIZATION_ID).
+2. auth_middleware scopes the request to that organization
+   (request_context.organization_scope).
+3. db reads of users, profiles, devices and reports drop rows of other
+   organizations, and new rows are stamped with the caller's organization,
+   so a handler cannot see another tenant's records even by id.
+   Tables keep their existing keys; organizations themselves are stored
+   under pk = "ORG#<id>".
+
+Key Features:
+- Records created before multi-tenancy belong to the default organization
+- Admins of the default organization operate the platform and create
+  organizations; other admins only manage their own
+- Subdomains are unique and DNS-label shaped
+"""
+
+import re
+import uuid
+from datetime import datetime, timezone
+from enum import Enum
+from typing import Any, Dict, List, Optional
+
+import db
+from request_context import DEFAULT_ORGANIZATION_ID, organization_scope
+
+_SUBDOMAIN = re.compile(r"^[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?$")
+
+
+class SubscriptionTier(str, Enum):
+    BASIC = "basic"
+    PROFESSIONAL = "professional"
+    ENTERPRISE = "enterprise"
+
+
+class OrganizationError(ValueError):
+    """Raised when an organization cannot be created as requested."""
+
+
+def is_platform_admin(role: Optional[str], organization_id: Optional[str]) -> bool:
+    """Admins of the default organization manage every organization."""
+    return role == "admin" and (organization_id or DEFAULT_ORGANIZATION_ID) == DEFAULT_ORGANIZATION_ID
+
+
+class OrganizationService:
+    """Creates organizations and lists their members."""
+
+    def create_organization(
+        self,
+        name: str,
+        subdomain: str,
+        admin_user_ids: List[str],
+        subscription_tier: SubscriptionTier = SubscriptionTier.BASIC
+    ) -> Dict[str, Any]:
+        """
+        Create an organization and move its admins into it.
+
+        Raises:
+            OrganizationError: invalid subdomain, or an admin id that is not an admin user
+            db.OrganizationExistsError: if the subdomain is taken
+        """
+        subdomain = subdomain.strip().lower()
+        if not _SUBDOMAIN.match(subdomain):
+            raise OrganizationError("subdomain must be 1-63 lowercase letters, digits or hyphens")
+        if not admin_user_ids:
+            raise OrganizationError("at least one admin user is required")
+
+        # Admins may currently belong to any organization
+        with organization_scope(None):
+            admins = [db.get_user(uid) for uid in admin_user_ids]
+        for uid, admin in zip(admin_user_ids, admins):
+            if not admin or admin.get("role") != "admin":
+                raise OrganizationError(f"{uid} is not an admin user")
+
+        org = {
+            "id": f"org_{uuid.uuid4().hex[:12]}",
+            "name": name.strip(),
+            "subdomain": subdomain,
+            "adminUserIds": list(dict.fromkeys(admin_user_ids)),
+            "subscriptionTier": SubscriptionTier(subscription_tier).value,
+            "createdAt": datetime.now(timezone.utc).isoformat(),
+        }
+        db.create_organization(org)
+        with organization_scope(None):
+            for uid in org["adminUserIds"]:
+                db.update_user(uid, {"organizationId": org["id"]})
+        return org
+
+    def get_organization(self, organization_id: str) -> Optional[Dict[str, Any]]:
+        return db.get_organization(organization_id)
+
+    def list_organization_users(self, organization_id: str) -> List[Dict[str, Any]]:
+        return db.get_users_by_organization(organization_id)
+
+
+# Global organization service instance
+organization_service = OrganizationService()
@@ -0,0 +1,148 @@
+"""
+Pagination helpers
+
+A single result type for list queries, so callers get the same metadata
+regardless of whether the source was a DynamoDB query, a scan or the
+in-memory store, and a matching camelCase response envelope for list
+endpoints.
+"""
+
+import base64
+import json
+from dataclasses import dataclass, field
+from decimal import Decimal
+from typing import Any, Dict, Generic, List, Optional, Tuple, TypeVar
+
+from pydantic import BaseModel
+
+from query_params import QueryParams
+
+T = TypeVar("T")
+
+DEFAULT_PAGE_LIMIT = 50
+MAX_PAGE_LIMIT = 200
+
+
+def _json_default(value: Any):
+    if isinstance(value, Decimal):
+        return int(value) if value % 1 == 0 else float(value)
+    raise TypeError(f"Object of type {type(value).__name__} is not JSON serializable")
+
+
+def encode_cursor(last_key: Optional[Dict[str, Any]]) -> Optional[str]:
+    """Encode a DynamoDB LastEvaluatedKey as an opaque URL-safe cursor."""
+    if not last_key:
+        return None
+    raw = json.dumps(last_key, default=_json_default, separators=(",", ":")).encode()
+    return base64.urlsafe_b64encode(raw).decode().rstrip("=")
+
+
+def decode_cursor(cursor: Optional[str]) -> Optional[Dict[str, Any]]:
+    """
+    Decode a cursor produced by encode_cursor back into an ExclusiveStartKey.
+
+    Raises:
+        ValueError: if the cursor is malformed
+    """
+    if not cursor:
+        return None
+    try:
+        raw = base64.urlsafe_b64decode(cursor + "=" * (-len(cursor) % 4))
+        key = json.loads(raw)
+    except (ValueError, TypeError) as e:
+        raise ValueError("Invalid pagination cursor") from e
+    if not isinstance(key, dict):
+        raise ValueError("Invalid pagination cursor")
+    return key
+
+
+@dataclass
+class PaginatedResult(Generic[T]):
+    """One page of a list query."""
+    items: List[T] = field(default_factory=list)
+    total: Optional[int] = None
+    next_cursor: Optional[str] = None
+    has_more: bool = False
+
+    @classmethod
+    def new(cls, items: List[T], total: Optional[int] = None) -> "PaginatedResult[T]":
+        """A complete (single-page) result."""
+        return cls(items=items, total=total)
+
+    @classmethod
+    def empty(cls) -> "PaginatedResult[T]":
+        return cls(items=[], total=0)
+
+    @classmethod
+    def with_cursor(cls, items: List[T], next_cursor: Optional[str], total: Optional[int] = None) -> "PaginatedResult[T]":
+        """A page that may be followed by another one at next_cursor."""
+        return cls(items=items, total=total, next_cursor=next_cursor, has_more=next_cursor is not None)
+
+    @classmethod
+    def from_dynamo(cls, items: List[T], last_key: Optional[Dict[str, Any]]) -> "PaginatedResult[T]":
+        """Build a page from a DynamoDB query/scan response."""
+        return cls.with_cursor(items, encode_cursor(last_key))
+
+    def to_dict(self) -> Dict[str, Any]:
+        """Response body with pagination metadata under a "pagination" key."""
+        return {
+            "items": self.items,
+            "pagination": {
+                "total": self.total,
+                "nextCursor": self.next_cursor,
+                "hasMore": self.has_more,
+            },
+        }
+
+
+def parse_pagination_params(
+    limit: Optional[Any] = None,
+    offset: Optional[Any] = None,
+    max_limit: int = MAX_PAGE_LIMIT
+) -> Tuple[int, int]:
+    """
+    Validate list query parameters.
+
+    Returns:
+        (limit, offset) with defaults applied
+
+    Raises:
+        QueryParamError: if limit is outside 1..max_limit, offset is negative or either is not an integer
+    """
+    params = QueryParams({"limit": limit, "offset": offset})
+    return (
+        params.get_int_range("limit", 1, max_limit, default=DEFAULT_PAGE_LIMIT),
+        params.get_int_range("offset", 0, default=0),
+    )
+
+
+def paginate_list(items: List[T], limit: int, offset: int = 0) -> PaginatedResult[T]:
+    """Offset-paginate a fully materialized list."""
+    page = items[offset:offset + limit]
+    return PaginatedResult(items=page, total=len(items), has_more=offset + len(page) < len(items))
+
+
+class PaginatedResponse(BaseModel, Generic[T]):
+    """List response envelope shared by paginated endpoints"""
+    items: List[T]
+    total: Optional[int] = None
+    limit: int
+    offset: Optional[int] = None
+    nextCursor: Optional[str] = None
+    hasMore: bool = False
+
+
+def create_paginated_response(
+    result: PaginatedResult,
+    limit: int,
+    offset: Optional[int] = None
+) -> PaginatedResponse:
+    """Wrap a PaginatedResult in the response envelope."""
+    return PaginatedResponse(
+        items=result.items,
+        total=result.total,
+        limit=limit,
+        offset=offset,
+        nextCursor=result.next_cursor,
+        hasMore=result.has_more,
+    )
@@ -1,8 +1,25 @@
 """
 Password validation utility for backend
-Implements the same validation rules as the Flutter frontend
+Implements the same validation rules as the Flutter frontend, plus a
+zxcvbn guessability check (rejects e.g. "Password1!" which passes the
+character-class rules)
 """
 import re
+from dataclasses import dataclass, field
+from typing import List, Optional, Sequence
+
+from zxcvbn import zxcvbn
+
+# Minimum zxcvbn score (0-4) per role; medical staff guard more data than patients
+MIN_STRENGTH_SCORE = {
+    "patient": 2,
+    "doctor": 3,
+    "admin": 3,
+}
+DEFAULT_MIN_STRENGTH_SCORE = 3
+# zxcvbn only looks at the first 100 characters; longer inputs just slow it down
+ZXCVBN_MAX_LENGTH = 100
+
 
 class PasswordValidator:
     """
@@ -85,3 +102,58 @@ class PasswordValidator:
         _, error_msg = cls.validate(password)
         return error_msg
 
+
+@dataclass
+class PasswordValidation:
+    """Result of validate_password_strength"""
+    is_valid: bool
+    score: int  # zxcvbn score 0-4 (0 when the basic rules already fail)
+    errors: List[str] = field(default_factory=list)
+
+    @property
+    def message(self) -> str:
+        return " ".join(self.errors)
+
+
+def _context_terms(user_context: Sequence[Optional[str]]) -> List[str]:
+    """Name and email plus the email's local part, so "jane.doe" penalizes "JaneDoe2024!" """
+    terms = []
+    for value in user_context:
+        if not value:
+            continue
+        value = value.strip().lower()
+        terms.append(value)
+        if "@" in value:
+            terms.append(value.split("@")[0])
+        terms.extend(part for part in re.split(r"[\s._@+-]+", value) if len(part) >= 3)
+    return list(dict.fromkeys(terms))
+
+
+def validate_password_strength(
+    password: str,
+    user_context: Sequence[Optional[str]] = (),
+    role: str = "patient"
+) -> PasswordValidation:
+    """
+    Validate a password against the character rules and zxcvbn.
+
+    Args:
+        password: Password to check
+        user_context: Values the password must not be built from (name, email)
+        role: Decides the minimum zxcvbn score (MIN_STRENGTH_SCORE)
+
+    Returns:
+        PasswordValidation; errors carry zxcvbn's warning and suggestions
+    """
+    is_valid, error = PasswordValidator.validate(password)
+    if not is_valid:
+        return PasswordValidation(is_valid=False, score=0, errors=[error])
+
+    result = zxcvbn(password[:ZXCVBN_MAX_LENGTH], user_inputs=_context_terms(user_context))
+    score = result["score"]
+    if score >= MIN_STRENGTH_SCORE.get(role, DEFAULT_MIN_STRENGTH_SCORE):
+        return PasswordValidation(is_valid=True, score=score)
+
+    feedback = result.get("feedback") or {}
+    errors = [m for m in [feedback.get("warning")] + list(feedback.get("suggestions") or []) if m]
+    return PasswordValidation(is_valid=False, score=score, errors=errors or ["Password is too easy to guess"])
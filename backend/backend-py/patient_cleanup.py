@@ -0,0 +1,76 @@
+"""
+MeDUSA Patient Cleanup Worker
+
+Asynchronous part of deleting a patient. The API marks the patient
+inactive, unassigns their devices and anonymizes their personal data, then
+enqueues a message here; this worker soft-deletes the patient's readings.
+
+- enqueue_cleanup: called by PurgeService.delete_patient
+- process:         SQS handler. Failed messages go to the dead-letter queue
+                   after maxReceiveCount.
+
+Key Features:
+- Readings are flagged (is_flagged = true), never removed, so they remain
+  available for audit retention
+- Idempotent: redelivered messages re-flag the same readings
+- Without PATIENT_CLEANUP_QUEUE_URL (local / memory mode) the readings are
+  flagged inline
+"""
+
+import os
+import json
+from datetime import datetime, timezone
+from typing import Any, Dict
+
+import aws_clients
+import db
+
+PATIENT_CLEANUP_QUEUE_URL = os.environ.get("PATIENT_CLEANUP_QUEUE_URL")
+
+_sqs = None
+
+
+def _sqs_client():
+    """Created lazily so importing this module (e.g. from main) needs no AWS region."""
+    global _sqs
+    if _sqs is None:
+        _sqs = aws_clients.get_client("sqs")
+    return _sqs
+
+
+def enqueue_cleanup(patient_id: str, deleted_by: str) -> bool:
+    """
+    Schedule soft deletion of the patient's readings.
+
+    Returns:
+        True if a message was enqueued, False if the readings were flagged inline
+    """
+    message = {
+        "patientId": patient_id,
+        "deletedBy": deleted_by,
+        "requestedAt": datetime.now(timezone.utc).isoformat(),
+    }
+    if not PATIENT_CLEANUP_QUEUE_URL:
+        cleanup_patient(message)
+        return False
+    _sqs_client().send_message(QueueUrl=PATIENT_CLEANUP_QUEUE_URL, MessageBody=json.dumps(message))
+    return True
+
+
+def cleanup_patient(message: Dict[str, Any]) -> int:
+    """Flag every reading of the patient; returns the number flagged."""
+    flagged = db.flag_patient_readings(message["patientId"])
+    print(f"[PatientCleanup] Flagged {flagged} reading(s) of patient {message['patientId']}")
+    return flagged
+
+
+def process(event, context):
+    """SQS batch handler; returns the messages to retry (ReportBatchItemFailures)."""
+    failures = []
+    for record in event.get("Records", []):
+        try:
+            cleanup_patient(json.loads(record["body"]))
+        except Exception as e:
+            print(f"[PatientCleanup] Failed to process message {record.get('messageId')}: {e}")
+            failures.append({"itemIdentifier": record["messageId"]})
+    return {"batchItemFailures": failures}
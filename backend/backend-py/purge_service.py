@@ -0,0 +1,242 @@
+"""
+MeDUSA Data Purge Service
+
+Hard deletion of a patient and all associated records (right to erasure),
+as opposed to the soft delete performed by the admin user endpoints, and
+anonymization of a user's personal data where records must be retained.
+
+Key Features:
+- Short-lived, HMAC-signed confirmation tokens bound to action, target and actor
+- Per-table deletion counts for the DATA_PURGE audit record
+- Purging a patient also deletes their report, pose, waveform and profile picture files
+- Idempotent: retrying a purge reports zero deletions instead of failing
+- Anonymization keeps readings (keyed by id only) and the audit trail
+- Patient deletion unassigns devices and flags readings instead of removing them
+"""
+
+import os
+import hmac
+import time
+import uuid
+import base64
+import hashlib
+from datetime import datetime, timezone
+from typing import Any, Dict, List, Optional, Tuple
+
+import db
+import storage
+import patient_cleanup
+
+CONFIRMATION_TOKEN_TTL_SECONDS = 300
+
+_secret = os.environ.get("HMAC_SECRET") or os.environ.get("JWT_SECRET")
+if not _secret:
+    raise RuntimeError("HMAC_SECRET or JWT_SECRET environment variable must be set")
+CONFIRMATION_SECRET = _secret.encode()
+
+ERASED_EMAIL_DOMAIN = "erased.local"
+
+# Personal and credential fields removed from a user on erasure
+USER_ERASED_FIELDS = [
+    "name", "firstName", "lastName", "phone", "license", "licenseNumber",
+    "specialty", "department", "hospital", "settings",
+    "password", "mfaSecret", "mfaPendingSecret", "passwordHistory", "validRefreshJti",
+    "profilePictureKey", "otpHash", "otpExpiresAt", "otpIssuedAt", "otpAttempts", "otpLockedUntil",
+    "lastLoginIp", "lastLoginGeo",
+]
+
+# Free-text / identifying fields removed from a patient profile; clinical
+# fields (diagnosis, severity) are kept with the pseudonymous user id
+PATIENT_ERASED_FIELDS = ["notes", "name", "email", "phone", "emergencyContact", "address", "dateOfBirth"]
+
+
+def _sign(payload: str) -> str:
+    return hmac.new(CONFIRMATION_SECRET, payload.encode(), hashlib.sha256).hexdigest()
+
+
+def issue_confirmation_token(
+    action: str,
+    resource_id: str,
+    actor_id: str,
+    ttl_seconds: int = CONFIRMATION_TOKEN_TTL_SECONDS
+) -> Tuple[str, int]:
+    """
+    Issue a confirmation token for a destructive action.
+
+    Returns:
+        (token, expires_at_unix)
+    """
+    expires_at = int(time.time()) + ttl_seconds
+    payload = f"{action}:{resource_id}:{actor_id}:{expires_at}"
+    encoded = base64.urlsafe_b64encode(payload.encode()).decode().rstrip("=")
+    return f"{encoded}.{_sign(payload)}", expires_at
+
+
+def verify_confirmation_token(token: Optional[str], action: str, resource_id: str, actor_id: str) -> bool:
+    """Check a confirmation token was issued for exactly this action, target and actor."""
+    if not token or "." not in token:
+        return False
+    try:
+        encoded, signature = token.rsplit(".", 1)
+        payload = base64.urlsafe_b64decode(encoded + "=" * (-len(encoded) % 4)).decode()
+        token_action, token_resource, token_actor, expires_at = payload.rsplit(":", 3)
+    except (ValueError, UnicodeDecodeError):
+        return False
+
+    if not hmac.compare_digest(signature, _sign(payload)):
+        return False
+    if (token_action, token_resource, token_actor) != (action, resource_id, actor_id):
+        return False
+    return int(expires_at) >= int(time.time())
+
+
+class PurgeService:
+    """Deletes every record belonging to a patient."""
+
+    def purge_patient(self, patient_id: str) -> Dict[str, int]:
+        """
+        Hard-delete a patient and their data across tables.
+
+        Returns:
+            Number of deleted (or unbound) items per table, and of deleted S3 files
+        """
+        # Files go first: a failed purge is retried while the rows referencing them still exist
+        user = db.get_user(patient_id)
+        deleted_files = storage.delete_objects(
+            self._patient_file_keys(patient_id), residency=(user or {}).get("dataResidency")
+        )
+
+        counts = {
+            "readings": db.purge_patient_readings(patient_id),
+            "reports": db.purge_patient_reports(patient_id),
+            "symptoms": db.purge_patient_symptoms(patient_id),
+            "poses": db.purge_patient_poses(patient_id),
+            "medications": db.purge_patient_medications(patient_id),
+            "deviceAssociations": db.unbind_patient_devices(patient_id),
+        }
+
+        profile_exists = db.get_patient_profile(patient_id) is not None
+        if profile_exists:
+            db.delete_patient_profile(patient_id)
+        counts["patientProfiles"] = 1 if profile_exists else 0
+
+        if user:
+            db.delete_user(patient_id)
+        counts["users"] = 1 if user else 0
+
+        counts["files"] = deleted_files
+        return counts
+
+    def _patient_file_keys(self, patient_id: str) -> List[str]:
+        """S3 keys of the patient's report files, pose files, device waveforms and profile picture."""
+        keys = set()
+        for report in db.get_reports(patient_id=patient_id, limit=1000):
+            if report.get("fileKey"):
+                keys.add(report["fileKey"])
+
+        next_token = None
+        while True:
+            page = db.list_poses_by_patient(patient_id, limit=100, next_token=next_token)
+            keys.update(p["fileKey"] for p in page.items if p.get("fileKey"))
+            if not page.has_more or not page.next_cursor:
+                break
+            next_token = page.next_cursor
+
+        # Sensor items are keyed by device; skip waveforms recorded for an earlier patient
+        for device in db.get_devices_by_patient(patient_id):
+            for reading in db.get_sensor_readings(device["id"]):
+                if reading.get("patient_id") == patient_id and reading.get("waveform_key"):
+                    keys.add(reading["waveform_key"])
+
+        user = db.get_user(patient_id) or {}
+        if user.get("profilePictureKey"):
+            keys.add(user["profilePictureKey"])
+        return sorted(keys)
+
+    def _delete_report_files(self, user_id: str) -> int:
+        """Delete S3 objects of reports authored by the user, any files they uploaded and their profile picture."""
+        keys = set()
+        for report in db.get_reports(author_id=user_id, limit=1000):
+            if report.get("fileKey"):
+                keys.add(report["fileKey"])
+                db.update_report(report["reportId"], {"fileKey": None})
+        keys.update(obj["Key"] for obj in storage.list_objects(f"{storage.PREPORT}{user_id}/"))
+        keys.update(obj["Key"] for obj in storage.list_objects(f"{storage.PPROFILE}{user_id}/"))
+        return storage.delete_objects(sorted(keys)) if keys else 0
+
+    def anonymize_user(self, user_id: str) -> Dict[str, Any]:
+        """
+        Erase a user's personal data while keeping pseudonymous records.
+
+        Returns:
+            {userId, erasedFields, retainedFields, deletedFiles}
+        """
+        user = db.get_user(user_id, include_secrets=True)
+        if not user:
+            raise KeyError(user_id)
+
+        updates: Dict[str, Any] = {field: None for field in USER_ERASED_FIELDS if field in user}
+        updates["email"] = f"deleted_{uuid.uuid4().hex}@{ERASED_EMAIL_DOMAIN}"
+        updates["isActive"] = False
+        updates["erasedAt"] = datetime.now(timezone.utc).isoformat()
+        if not db.erase_user_fields(user_id, updates):
+            raise RuntimeError(f"Failed to anonymize user {user_id}")
+        db.revoke_user_refresh_tokens(user_id)
+
+        erased: List[str] = sorted(f for f in updates if f not in ("isActive", "erasedAt"))
+        retained = sorted(f for f in user if f not in updates)
+
+        profile = db.get_patient_profile(user_id)
+        if profile:
+            profile_updates = {f: None for f in PATIENT_ERASED_FIELDS if profile.get(f) is not None}
+            if profile_updates:
+                db.update_patient_profile(user_id, profile_updates)
+            erased += [f"patientProfile.{f}" for f in sorted(profile_updates)]
+            retained += [f"patientProfile.{f}" for f in sorted(profile) if f not in profile_updates]
+
+        return {
+            "userId": user_id,
+            "erasedFields": erased,
+            "retainedFields": retained,
+            "deletedFiles": self._delete_report_files(user_id),
+        }
+
+    def delete_patient(self, patient_id: str, deleted_by: str) -> Dict[str, Any]:
+        """
+        Soft-delete a patient: deactivate them and unassign their devices in
+        one transaction, enqueue flagging of their readings, then anonymize
+        their personal data. Retrying after a partial failure is safe.
+
+        Returns:
+            {patientId, unassignedDeviceCount, anonymizedFields, oldValues}
+
+        Raises:
+            KeyError: if the patient does not exist
+            ConditionFailedError: if a device was reassigned concurrently
+        """
+        user = db.get_user(patient_id)
+        if not user:
+            raise KeyError(patient_id)
+        device_ids = sorted(d["id"] for d in db.get_devices_by_patient(patient_id))
+        has_profile = db.get_patient_profile(patient_id) is not None
+
+        # Only account state goes to the audit log; personal data is erased below
+        old_values = {
+            "isActive": user.get("isActive", True),
+            "deviceIds": device_ids,
+        }
+
+        db.deactivate_patient(patient_id, device_ids, deleted_by, has_profile=has_profile)
+        patient_cleanup.enqueue_cleanup(patient_id, deleted_by)
+        anonymized = self.anonymize_user(patient_id)
+
+        return {
+            "patientId": patient_id,
+            "unassignedDeviceCount": len(device_ids),
+            "anonymizedFields": anonymized["erasedFields"],
+            "oldValues": old_values,
+        }
+
+
+# Global purge service instance
+purge_service = PurgeService()
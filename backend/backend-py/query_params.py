@@ -0,0 +1,130 @@
+"""
+Typed query parameter parsing
+
+FastAPI hands endpoints plain strings (or loosely typed values) for
+optional query parameters; QueryParams validates them into typed values
+in one place. Every failure is a QueryParamError naming the offending
+parameter, so endpoints can answer 400 with the parameter name instead of
+re-validating limit/offset/dates/ids by hand.
+"""
+
+import uuid
+from datetime import datetime, timezone
+from typing import Any, Mapping, Optional, Tuple
+
+TRUE_VALUES = {"true", "1", "yes", "on"}
+FALSE_VALUES = {"false", "0", "no", "off"}
+
+
+class QueryParamError(ValueError):
+    """Raised when a query parameter is malformed or out of range."""
+
+    def __init__(self, param: str, message: str, code: str = "INVALID_QUERY_PARAM"):
+        super().__init__(message)
+        self.param = param
+        self.code = code
+
+
+class QueryParams:
+    """Typed getters over raw query parameter values (e.g. request.query_params)."""
+
+    def __init__(self, values: Mapping[str, Any]):
+        self._values = values
+
+    def _raw(self, name: str) -> Any:
+        value = self._values.get(name)
+        if isinstance(value, str):
+            value = value.strip()
+        return None if value is None or value == "" else value
+
+    def get_str(self, name: str, default: Optional[str] = None, max_length: Optional[int] = None) -> Optional[str]:
+        value = self._raw(name)
+        if value is None:
+            return default
+        value = str(value)
+        if max_length is not None and len(value) > max_length:
+            raise QueryParamError(name, f"{name} must be at most {max_length} characters")
+        return value
+
+    def get_uuid(self, name: str, default: Optional[uuid.UUID] = None) -> Optional[uuid.UUID]:
+        value = self._raw(name)
+        if value is None:
+            return default
+        if isinstance(value, uuid.UUID):
+            return value
+        try:
+            return uuid.UUID(str(value))
+        except ValueError:
+            raise QueryParamError(name, f"{name} must be a UUID")
+
+    def get_int_range(
+        self,
+        name: str,
+        min_value: Optional[int] = None,
+        max_value: Optional[int] = None,
+        default: Optional[int] = None
+    ) -> Optional[int]:
+        """Integer within min_value..max_value (inclusive; None: unbounded)."""
+        value = self._raw(name)
+        if value is None:
+            return default
+        if isinstance(value, bool):
+            raise QueryParamError(name, f"{name} must be an integer")
+        try:
+            number = value if isinstance(value, int) else int(str(value))
+        except ValueError:
+            raise QueryParamError(name, f"{name} must be an integer")
+        if (min_value is not None and number < min_value) or (max_value is not None and number > max_value):
+            if max_value is None:
+                bound = "must not be negative" if min_value == 0 else f"must be at least {min_value}"
+            elif min_value is None:
+                bound = f"must be at most {max_value}"
+            else:
+                bound = f"must be between {min_value} and {max_value}"
+            raise QueryParamError(name, f"{name} {bound}")
+        return number
+
+    def get_bool(self, name: str, default: Optional[bool] = None) -> Optional[bool]:
+        value = self._raw(name)
+        if value is None:
+            return default
+        if isinstance(value, bool):
+            return value
+        text = str(value).lower()
+        if text in TRUE_VALUES:
+            return True
+        if text in FALSE_VALUES:
+            return False
+        raise QueryParamError(name, f"{name} must be true or false")
+
+    def get_datetime(self, name: str, default: Optional[datetime] = None) -> Optional[datetime]:
+        """ISO-8601 date or datetime; values without a timezone are UTC."""
+        value = self._raw(name)
+        if value is None:
+            return default
+        if isinstance(value, datetime):
+            parsed = value
+        else:
+            try:
+                parsed = datetime.fromisoformat(str(value).replace("Z", "+00:00"))
+            except ValueError:
+                raise QueryParamError(name, f"{name} must be an ISO-8601 date", code="INVALID_DATE")
+        return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+
+
+def parse_date_range_params(
+    start_date: Optional[str] = None,
+    end_date: Optional[str] = None
+) -> Tuple[Optional[datetime], Optional[datetime]]:
+    """
+    Validate a start_date / end_date pair; either may be omitted.
+
+    Raises:
+        QueryParamError: if a date is malformed (INVALID_DATE) or start_date is after end_date (INVALID_DATE_RANGE)
+    """
+    params = QueryParams({"start_date": start_date, "end_date": end_date})
+    start = params.get_datetime("start_date")
+    end = params.get_datetime("end_date")
+    if start and end and start > end:
+        raise QueryParamError("start_date", "start_date must be before end_date", code="INVALID_DATE_RANGE")
+    return start, end
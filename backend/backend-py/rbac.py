@@ -1,12 +1,78 @@
 """
 RBAC (Role-Based Access Control) utilities
 Provides decorators and helpers for role-based authorization
+
+Besides the built-in roles (patient, doctor, admin), admins can define
+custom roles (e.g. "radiologist") holding a subset of the doctor's
+permissions. A custom role is stored on the user and carried in the JWT
+"role" claim as "custom:<name>:<comma-separated permissions>", so checks
+need no role lookup. Custom roles only pass require_role on endpoints
+that name a permission they hold, and get_user_role reports their name.
 """
+import re
+from dataclasses import dataclass
 from functools import wraps
 from fastapi import HTTPException, Request
-from typing import Callable, List
+from typing import Callable, Dict, FrozenSet, List, Optional, Tuple
+
+from validators import normalize_case
+from request_context import DEFAULT_ORGANIZATION_ID
+
+
+# Permissions endpoints can grant to custom roles via require_role(..., permission=...)
+READINGS_READ = "readings:read"
+READINGS_EXPORT = "readings:export"
+
+ROLE_PERMISSIONS: Dict[str, FrozenSet[str]] = {
+    "patient": frozenset({READINGS_READ, READINGS_EXPORT}),
+    "doctor": frozenset({READINGS_READ, READINGS_EXPORT}),
+    "admin": frozenset({READINGS_READ, READINGS_EXPORT}),
+}
+
+CUSTOM_ROLE_PREFIX = "custom:"
+CUSTOM_ROLE_NAME_PATTERN = re.compile(r"^[a-z][a-z0-9_-]{1,48}$")
+
+
+@dataclass(frozen=True)
+class CustomRole:
+    """Named permission set assigned to users instead of a built-in role."""
+    name: str
+    permissions: Tuple[str, ...]
+
+    def as_str(self) -> str:
+        """Encoding stored on the user and in the JWT "role" claim"""
+        return f"{CUSTOM_ROLE_PREFIX}{self.name}:{','.join(self.permissions)}"
+
 
-def require_role(*allowed_roles: str):
+def parse_custom_role(role: Optional[str]) -> Optional[CustomRole]:
+    """The custom role encoded in role, or None for built-in (or malformed) roles."""
+    if not role or not role.startswith(CUSTOM_ROLE_PREFIX):
+        return None
+    name, sep, permissions = role[len(CUSTOM_ROLE_PREFIX):].partition(":")
+    if not sep or not CUSTOM_ROLE_NAME_PATTERN.match(name):
+        return None
+    return CustomRole(name, tuple(p for p in permissions.split(",") if p))
+
+
+def role_name(role: Optional[str]) -> Optional[str]:
+    """Name of a role as shown to clients and audited (a custom role's name, not its encoding)."""
+    custom = parse_custom_role(role)
+    return custom.name if custom else role
+
+
+def get_role_permissions(role: Optional[str]) -> FrozenSet[str]:
+    """Permissions of a built-in role, or those embedded in a custom role."""
+    custom = parse_custom_role(role)
+    if custom:
+        return frozenset(custom.permissions)
+    return ROLE_PERMISSIONS.get(role, frozenset())
+
+
+def normalize_role(role: Optional[str]) -> Optional[str]:
+    """Role as compared by RBAC checks; tolerates stored or legacy roles like "Doctor"."""
+    return normalize_case(role) or None
+
+def require_role(*allowed_roles: str, permission: Optional[str] = None):
     """
     Decorator to enforce role-based access control
     
@@ -17,6 +83,7 @@ def require_role(*allowed_roles: str):
     
     Args:
         *allowed_roles: Variable number of allowed role strings
+        permission: Also admit custom roles holding this permission
         
     Raises:
         HTTPException 403: If user role is not in allowed_roles
@@ -41,10 +108,12 @@ def require_role(*allowed_roles: str):
             
             # Get claims from request state (set by auth middleware)
             claims = getattr(request.state, "claims", {})
-            user_role = claims.get("role")
+            user_role = normalize_role(claims.get("role"))
             
             # Check if user role is allowed
-            if user_role not in allowed_roles:
+            custom = parse_custom_role(user_role)
+            granted = custom is not None and permission is not None and permission in custom.permissions
+            if user_role not in allowed_roles and not granted:
                 raise HTTPException(
                     status_code=403,
                     detail={
@@ -93,13 +162,13 @@ def get_user_role(request: Request) -> str:
         request: FastAPI request object
         
     Returns:
-        User role string
+        User role string (the name of a custom role)
         
     Raises:
         HTTPException 401: If no claims found
     """
     claims = getattr(request.state, "claims", {})
-    user_role = claims.get("role")
+    user_role = role_name(normalize_role(claims.get("role")))
     
     if not user_role:
         raise HTTPException(
@@ -172,3 +241,8 @@ def require_ownership_or_role(*allowed_roles: str):
         return wrapper
     return decorator
 
+
+def get_organization_id(request: Request) -> str:
+    """Organization of the caller (the token's "org" claim); legacy tokens belong to the default one."""
+    claims = getattr(request.state, "claims", {})
+    return claims.get("org") or DEFAULT_ORGANIZATION_ID
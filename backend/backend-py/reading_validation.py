@@ -0,0 +1,294 @@
+"""
+Device reading validation
+
+Checks a reading's values before it is stored or queued for analysis.
+
+Key Features:
+- Rejects NaN / infinite values and non-numeric payloads
+- Caps the number of keys and the key length
+- Required keys and plausible ranges per known reading type
+- Device calibration corrections applied before range checks
+- Units canonicalized per reading type ("mm Hg" -> "mmHg"); convertible
+  units (degF, lb, g, rad/s, kPa) are converted so stored values are canonical
+- Optional GPS location: latitude -90..90, longitude -180..180, non-negative accuracy
+- ECG readings carry summary metrics as values; the waveform samples are
+  checked separately (validate_waveform) because they are stored in S3
+"""
+
+import math
+from typing import Any, Callable, Dict, List, Optional, Tuple
+
+MAX_READING_KEYS = 32
+MAX_KEY_LENGTH = 64
+MAX_WAVEFORM_SAMPLES = 300_000  # 5 minutes at 1 kHz
+WAVEFORM_LIMIT_MV = 20.0
+
+# Request field (camelCase) -> stored key, (min, max), required
+LOCATION_FIELDS: Dict[str, Tuple[str, Tuple[Optional[float], Optional[float]], bool]] = {
+    "latitude": ("latitude", (-90.0, 90.0), True),
+    "longitude": ("longitude", (-180.0, 180.0), True),
+    "accuracyMeters": ("accuracy_meters", (0.0, None), False),
+    "altitudeM": ("altitude_m", (None, None), False),
+}
+
+# reading_type -> {key: (min, max, required)}
+# Ranges are physical/physiological plausibility limits, not clinical alert thresholds.
+READING_RULES: Dict[str, Dict[str, Tuple[Optional[float], Optional[float], bool]]] = {
+    "accelerometer": {
+        # m/s^2, +/-16 g sensor range
+        "accel_x": (-160.0, 160.0, True),
+        "accel_y": (-160.0, 160.0, True),
+        "accel_z": (-160.0, 160.0, True),
+        "magnitude": (0.0, 280.0, False),
+    },
+    "gyroscope": {
+        # deg/s
+        "gyro_x": (-2000.0, 2000.0, True),
+        "gyro_y": (-2000.0, 2000.0, True),
+        "gyro_z": (-2000.0, 2000.0, True),
+    },
+    "tremor": {
+        "tremor_index": (0.0, 1.0, True),
+        "dominant_frequency": (0.0, 50.0, False),
+        "rms_value": (0.0, None, False),
+        "signal_quality": (0.0, 1.0, False),
+        "tremor_power": (0.0, None, False),
+        "total_power": (0.0, None, False),
+    },
+    "heart_rate": {
+        "bpm": (20.0, 300.0, True),
+    },
+    "blood_pressure": {
+        # mmHg
+        "systolic": (40.0, 300.0, True),
+        "diastolic": (20.0, 200.0, True),
+    },
+    "spo2": {
+        "spo2": (50.0, 100.0, True),
+    },
+    "temperature": {
+        # degrees Celsius
+        "celsius": (25.0, 45.0, True),
+    },
+    "weight": {
+        # kilograms
+        "kg": (0.5, 500.0, True),
+    },
+    "ecg": {
+        # Summary of the recording; the samples themselves are stored in S3
+        "heart_rate_bpm": (20.0, 300.0, True),
+        "sample_rate_hz": (50.0, 2000.0, True),
+        "duration_s": (0.0, 300.0, False),
+        "pr_interval_ms": (0.0, 400.0, False),
+        "qrs_duration_ms": (0.0, 300.0, False),
+        "qt_interval_ms": (0.0, 700.0, False),
+    },
+}
+
+# reading_type -> unit every stored value of that type is in.
+# Types not listed (e.g. tremor, whose values are dimensionless indices) take no unit.
+CANONICAL_UNITS: Dict[str, str] = {
+    "accelerometer": "m/s^2",
+    "gyroscope": "deg/s",
+    "heart_rate": "bpm",
+    "blood_pressure": "mmHg",
+    "spo2": "%",
+    "temperature": "degC",
+    "weight": "kg",
+}
+
+# Spelling (lowercase, without spaces) -> unit symbol
+UNIT_ALIASES: Dict[str, str] = {
+    "m/s^2": "m/s^2", "m/s2": "m/s^2", "m/s²": "m/s^2", "m/s/s": "m/s^2",
+    "g": "g", "gforce": "g", "g-force": "g",
+    "deg/s": "deg/s", "°/s": "deg/s", "dps": "deg/s", "degrees/s": "deg/s",
+    "rad/s": "rad/s",
+    "bpm": "bpm", "beats/min": "bpm", "/min": "bpm", "1/min": "bpm",
+    "mmhg": "mmHg", "kpa": "kPa",
+    "%": "%", "percent": "%",
+    "degc": "degC", "°c": "degC", "c": "degC", "celsius": "degC",
+    "degf": "degF", "°f": "degF", "f": "degF", "fahrenheit": "degF",
+    "kg": "kg", "kgs": "kg", "kilogram": "kg", "kilograms": "kg",
+    "lb": "lb", "lbs": "lb", "pound": "lb", "pounds": "lb",
+    "mg/dl": "mg/dL", "mmol/l": "mmol/L",
+}
+
+# (from unit, canonical unit) -> conversion of one value
+UNIT_CONVERSIONS: Dict[Tuple[str, str], Callable[[float], float]] = {
+    ("g", "m/s^2"): lambda v: v * 9.80665,
+    ("rad/s", "deg/s"): math.degrees,
+    ("kPa", "mmHg"): lambda v: v * 7.500617,
+    ("degF", "degC"): lambda v: (v - 32.0) * 5.0 / 9.0,
+    ("lb", "kg"): lambda v: v * 0.45359237,
+}
+
+
+class ReadingValidationError(ValueError):
+    """Raised when a device reading fails validation."""
+
+
+def canonical_unit(unit: str) -> Optional[str]:
+    """Unit symbol for a spelling ("MM Hg" -> "mmHg"), or None if unknown."""
+    return UNIT_ALIASES.get("".join(unit.split()).lower())
+
+
+def normalize_units(reading_type: str, unit: Optional[str], values: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    Convert a reading's values to the canonical unit of its type.
+
+    A missing unit means the values are already canonical. Unknown reading
+    types accept any known unit unchanged.
+
+    Raises:
+        ReadingValidationError: for an unknown unit or one that does not fit the reading type
+    """
+    if unit is None:
+        return values
+    if not isinstance(unit, str) or not unit.strip():
+        raise ReadingValidationError("unit must be a non-empty string")
+    symbol = canonical_unit(unit)
+    if symbol is None:
+        raise ReadingValidationError(f"unknown unit {unit!r}")
+    if reading_type not in READING_RULES:
+        return values
+    target = CANONICAL_UNITS.get(reading_type)
+    if target is None:
+        raise ReadingValidationError(f"{reading_type} readings take no unit, got {unit!r}")
+    if symbol == target:
+        return values
+    convert = UNIT_CONVERSIONS.get((symbol, target))
+    if convert is None:
+        raise ReadingValidationError(f"unit {unit!r} is not valid for {reading_type} (expected {target})")
+    return {key: convert(value) for key, value in values.items()}
+
+
+def apply_calibration(values: Dict[str, Any], calibration: Optional[Dict[str, Any]]) -> Dict[str, Any]:
+    """
+    Scale the calibrated keys of a reading by the device's correction factor.
+
+    calibration is the device's calibrationCorrection:
+    {"correctionFactor": float, "keys": [value keys the calibration covered]}
+    """
+    if not calibration:
+        return values
+    factor = float(calibration.get("correctionFactor", 1.0))
+    keys = set(calibration.get("keys") or [])
+    return {key: value * factor if key in keys else value for key, value in values.items()}
+
+
+def validate_reading(
+    reading_type: str,
+    values: Dict[str, Any],
+    calibration: Optional[Dict[str, Any]] = None,
+    unit: Optional[str] = None
+) -> Dict[str, Any]:
+    """
+    Validate a reading's values for its type.
+
+    Unknown reading types only get the generic checks (finite numbers,
+    key count and length). Values given in another unit are converted to
+    the type's canonical unit first; with a calibration, the corrected
+    values are range-checked.
+
+    Returns:
+        The values in the canonical unit, corrected by the calibration if one is given
+
+    Raises:
+        ReadingValidationError: with a message describing the first problem found
+    """
+    if not isinstance(values, dict) or not values:
+        raise ReadingValidationError("values must be a non-empty object")
+    if len(values) > MAX_READING_KEYS:
+        raise ReadingValidationError(f"values may contain at most {MAX_READING_KEYS} keys, got {len(values)}")
+
+    for key, value in values.items():
+        if not isinstance(key, str) or not key or len(key) > MAX_KEY_LENGTH:
+            raise ReadingValidationError(f"value keys must be 1-{MAX_KEY_LENGTH} characters")
+        if isinstance(value, bool) or not isinstance(value, (int, float)):
+            raise ReadingValidationError(f"{key} must be a number")
+        if not math.isfinite(value):
+            raise ReadingValidationError(f"{key} must be a finite number")
+
+    values = normalize_units(reading_type, unit, values)
+    values = apply_calibration(values, calibration)
+    rules = READING_RULES.get(reading_type)
+    if rules is None:
+        return values
+
+    missing = [key for key, (_, _, required) in rules.items() if required and key not in values]
+    if missing:
+        raise ReadingValidationError(f"{reading_type} reading is missing required keys: {', '.join(missing)}")
+
+    for key, (minimum, maximum, _) in rules.items():
+        if key not in values:
+            continue
+        value = values[key]
+        if minimum is not None and value < minimum:
+            raise ReadingValidationError(f"{key} must be >= {minimum:g} for {reading_type}, got {value:g}")
+        if maximum is not None and value > maximum:
+            raise ReadingValidationError(f"{key} must be <= {maximum:g} for {reading_type}, got {value:g}")
+
+    if reading_type == "blood_pressure" and values["diastolic"] >= values["systolic"]:
+        raise ReadingValidationError("diastolic must be lower than systolic")
+    return values
+
+
+def validate_waveform(samples: Any) -> List[float]:
+    """
+    Validate ECG waveform samples (single lead, millivolts).
+
+    Returns:
+        The samples as floats
+
+    Raises:
+        ReadingValidationError: if samples is not a non-empty list of finite
+        numbers within +/-WAVEFORM_LIMIT_MV, or is longer than MAX_WAVEFORM_SAMPLES
+    """
+    if not isinstance(samples, list) or not samples:
+        raise ReadingValidationError("waveform must be a non-empty list of samples")
+    if len(samples) > MAX_WAVEFORM_SAMPLES:
+        raise ReadingValidationError(f"waveform may contain at most {MAX_WAVEFORM_SAMPLES} samples, got {len(samples)}")
+    for sample in samples:
+        if isinstance(sample, bool) or not isinstance(sample, (int, float)) or not math.isfinite(sample):
+            raise ReadingValidationError("waveform samples must be finite numbers")
+        if abs(sample) > WAVEFORM_LIMIT_MV:
+            raise ReadingValidationError(f"waveform samples must be within +/-{WAVEFORM_LIMIT_MV:g} mV")
+    return [float(sample) for sample in samples]
+
+
+def validate_location(location: Any) -> Optional[Dict[str, float]]:
+    """
+    Validate a reading's GPS location.
+
+    location is {"latitude", "longitude", "accuracyMeters"?, "altitudeM"?};
+    None means the device reported no position.
+
+    Returns:
+        The location as stored on the sensor item (snake_case keys), or None
+
+    Raises:
+        ReadingValidationError: for missing, non-numeric or out-of-range coordinates
+    """
+    if location is None:
+        return None
+    if not isinstance(location, dict):
+        raise ReadingValidationError("location must be an object")
+    unknown = set(location) - set(LOCATION_FIELDS)
+    if unknown:
+        raise ReadingValidationError(f"unknown location fields: {', '.join(sorted(map(str, unknown)))}")
+
+    stored: Dict[str, float] = {}
+    for field, (key, (minimum, maximum), required) in LOCATION_FIELDS.items():
+        value = location.get(field)
+        if value is None:
+            if required:
+                raise ReadingValidationError(f"location.{field} is required")
+            continue
+        if isinstance(value, bool) or not isinstance(value, (int, float)) or not math.isfinite(value):
+            raise ReadingValidationError(f"location.{field} must be a finite number")
+        if minimum is not None and value < minimum:
+            raise ReadingValidationError(f"location.{field} must be >= {minimum:g}, got {value:g}")
+        if maximum is not None and value > maximum:
+            raise ReadingValidationError(f"location.{field} must be <= {maximum:g}, got {value:g}")
+        stored[key] = float(value)
+    return stored
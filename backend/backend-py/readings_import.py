@@ -0,0 +1,179 @@
+"""
+MeDUSA Bulk Readings Import
+
+Imports historical device readings (e.g. from a legacy system) from a CSV
+or NDJSON file uploaded to S3 under the imports/ prefix.
+
+File formats (one reading per row / line):
+- CSV:    header row with deviceId, timestamp (unix seconds), readingType,
+          optional unit, optional location columns (latitude, longitude,
+          accuracyMeters, altitudeM) and one column per value key (e.g. bpm)
+- NDJSON: {"deviceId": ..., "timestamp": ..., "readingType": ..., "unit": ...,
+          "location": {...}, "values": {...}}
+
+Key Features:
+- File streamed from S3 line by line; readings written in chunks via BatchWriteItem
+- Same validation as live ingestion (device_data_ingest.to_sensor_item)
+- Malformed or invalid rows are skipped and reported with their line number
+- Readings are attributed to the device's current patient
+- No alerts or anomaly flags: imported readings are historical
+- DATA_IMPORT audit event with succeeded/failed counts
+"""
+
+import csv
+import json
+from typing import Any, Dict, Iterable, Iterator, List, Optional, Tuple
+
+import db
+import storage
+from audit_service import audit_service, AuditEventType
+from device_data_ingest import to_sensor_item
+from reading_validation import ReadingValidationError
+
+IMPORT_FORMATS = ("csv", "ndjson")
+IMPORT_WRITE_CHUNK = 500
+MAX_REPORTED_FAILURES = 100
+
+CSV_META_COLUMNS = ("deviceId", "timestamp", "readingType")
+CSV_OPTIONAL_COLUMNS = ("unit",)
+CSV_LOCATION_COLUMNS = ("latitude", "longitude", "accuracyMeters", "altitudeM")
+
+
+class ImportRowError(ValueError):
+    """A row that could not be parsed into a reading."""
+
+
+def _number(raw: str, column: str) -> float:
+    try:
+        value = float(raw)
+    except ValueError:
+        raise ImportRowError(f"{column} must be a number, got {raw!r}")
+    return int(value) if value.is_integer() else value
+
+
+def parse_csv(lines: Iterable[str]) -> Iterator[Tuple[int, Any]]:
+    """
+    Yield (line_number, reading dict or ImportRowError) for each data row.
+    Empty value cells are omitted from the reading; a row whose location
+    cells are all empty has no location.
+    """
+    reader = csv.reader(lines)
+    header = next(reader, None)
+    if not header:
+        return
+    header = [h.strip() for h in header]
+    missing = [c for c in CSV_META_COLUMNS if c not in header]
+    if missing:
+        raise ImportRowError(f"CSV header is missing columns: {', '.join(missing)}")
+    for row in reader:
+        line = reader.line_num
+        if not any(cell.strip() for cell in row):
+            continue
+        if len(row) != len(header):
+            yield line, ImportRowError(f"expected {len(header)} columns, got {len(row)}")
+            continue
+        cells = dict(zip(header, (cell.strip() for cell in row)))
+        try:
+            location = {k: _number(cells[k], k) for k in CSV_LOCATION_COLUMNS if cells.get(k, "") != ""}
+            yield line, {
+                "deviceId": cells["deviceId"],
+                "timestamp": _number(cells["timestamp"], "timestamp"),
+                "readingType": cells["readingType"] or None,
+                "unit": cells.get("unit") or None,
+                "location": location or None,
+                "values": {k: _number(v, k) for k, v in cells.items()
+                           if k not in CSV_META_COLUMNS + CSV_OPTIONAL_COLUMNS + CSV_LOCATION_COLUMNS and v != ""},
+            }
+        except ImportRowError as e:
+            yield line, e
+
+
+def parse_ndjson(lines: Iterable[str]) -> Iterator[Tuple[int, Any]]:
+    """Yield (line_number, reading dict or ImportRowError) for each non-blank line."""
+    for line_number, line in enumerate(lines, start=1):
+        if not line.strip():
+            continue
+        try:
+            reading = json.loads(line)
+        except json.JSONDecodeError as e:
+            yield line_number, ImportRowError(f"invalid JSON: {e.msg}")
+            continue
+        if not isinstance(reading, dict):
+            yield line_number, ImportRowError("line must be a JSON object")
+            continue
+        yield line_number, reading
+
+
+def detect_format(file_key: str) -> Optional[str]:
+    """Format implied by the file extension (.csv, .ndjson / .jsonl)."""
+    lowered = file_key.lower()
+    if lowered.endswith(".csv"):
+        return "csv"
+    if lowered.endswith((".ndjson", ".jsonl")):
+        return "ndjson"
+    return None
+
+
+def import_lines(lines: Iterable[str], fmt: str) -> Dict[str, Any]:
+    """
+    Validate and store the readings in lines.
+
+    Returns:
+        {"succeeded": n, "failed": n, "failures": [{"line": n, "error": str}, ...]}
+        (failures lists at most MAX_REPORTED_FAILURES rows)
+
+    Raises:
+        ImportRowError: if the file as a whole is unusable (e.g. bad CSV header)
+    """
+    rows = parse_csv(lines) if fmt == "csv" else parse_ndjson(lines)
+    devices: Dict[str, Optional[Dict[str, Any]]] = {}
+    pending: List[Dict[str, Any]] = []
+    failures: List[Dict[str, Any]] = []
+    succeeded = failed = 0
+
+    def fail(line: int, error: str) -> None:
+        nonlocal failed
+        failed += 1
+        if len(failures) < MAX_REPORTED_FAILURES:
+            failures.append({"line": line, "error": error})
+
+    for line, reading in rows:
+        if isinstance(reading, ImportRowError):
+            fail(line, str(reading))
+            continue
+        device_id = reading.get("deviceId")
+        if not isinstance(device_id, str) or not device_id:
+            fail(line, "deviceId is required")
+            continue
+        if device_id not in devices:
+            devices[device_id] = db.get_device(device_id)
+        device = devices[device_id]
+        if not device:
+            fail(line, f"unknown device {device_id}")
+            continue
+        try:
+            pending.append(to_sensor_item(device_id, device.get("patientId"), reading))
+        except ReadingValidationError as e:
+            fail(line, str(e))
+            continue
+        if len(pending) >= IMPORT_WRITE_CHUNK:
+            succeeded += db.batch_write_device_readings(pending)
+            pending = []
+    if pending:
+        succeeded += db.batch_write_device_readings(pending)
+    return {"succeeded": succeeded, "failed": failed, "failures": failures}
+
+
+def import_readings_file(file_key: str, fmt: str, user_id: str, user_role: str) -> Dict[str, Any]:
+    """Import a readings file from S3 and audit the outcome."""
+    summary = import_lines(storage.iter_lines(file_key), fmt)
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_IMPORT,
+        user_id=user_id,
+        user_role=user_role,
+        resource_type="device_readings",
+        resource_id=file_key,
+        action="import",
+        details={"format": fmt, "succeeded": summary["succeeded"], "failed": summary["failed"]}
+    )
+    return summary
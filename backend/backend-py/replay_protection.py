@@ -21,8 +21,9 @@ from functools import wraps
 from fastapi import Request, HTTPException
 
 # DynamoDB client for nonce storage
-import boto3
 from botocore.exceptions import ClientError
+import aws_clients
+from sanitize import sanitize_table_name
 
 # Configuration
 NONCE_TTL_SECONDS = int(os.environ.get("NONCE_TTL_SECONDS", "300"))  # 5 minutes
@@ -52,8 +53,8 @@ class NonceService:
         
         if not USE_MEMORY:
             try:
-                self._dynamodb = boto3.resource('dynamodb')
-                self._table = self._dynamodb.Table(NONCE_TABLE)
+                self._dynamodb = aws_clients.get_resource('dynamodb')
+                self._table = self._dynamodb.Table(sanitize_table_name(NONCE_TABLE))
                 print(f"[NonceService] Initialized with DynamoDB table: {NONCE_TABLE}")
             except Exception as e:
                 print(f"[NonceService] DynamoDB init failed, using memory: {e}")
@@ -0,0 +1,147 @@
+"""
+MeDUSA Expired Report Cleanup
+
+Warns report authors before their reports expire, then purges reports past
+their expiresAt together with their S3 file. Expired reports are hard
+deleted (data retention policy), unlike the soft deletes elsewhere.
+
+- run: EventBridge Scheduler handler (daily). Indexes the expiry of reports
+       stored before the expiresAt-index existed, notifies the authors of
+       reports expiring within REPORT_EXPIRY_WARNING_HOURS (default 48),
+       then pages through expired reports until none are left or the
+       invocation is close to its timeout.
+
+Key Features:
+- Batched and resumable: each page ends with a cursor; pass {"cursor": ...}
+  in the event to continue a run that stopped early
+- S3 file deleted before the record, so a failure never orphans a file
+- Already-missing S3 objects are treated as deleted
+- A report that fails to delete is skipped and retried on the next run
+- REPORT_DELETED audit event per deleted report
+- One expiry warning per report (expiryNotifiedAt); authors who could not be
+  reached are retried on the next run
+"""
+
+import os
+from datetime import datetime, timezone
+from typing import Any, Dict, Optional
+
+import db
+import storage
+from audit_service import audit_service, AuditEventType
+from notification_service import notification_service
+
+REPORT_EXPIRY_WARNING_HOURS = int(os.environ.get("REPORT_EXPIRY_WARNING_HOURS", "48"))
+CLEANUP_BATCH_SIZE = 100
+# Stop paging when less than this is left of the Lambda timeout
+CLEANUP_TIME_MARGIN_MS = 30_000
+
+
+def is_report_expired(report: Dict[str, Any], now: Optional[datetime] = None) -> bool:
+    """Whether a report's ISO expiresAt has passed (reports without one never expire)"""
+    expires_at = report.get("expiresAt")
+    if not expires_at:
+        return False
+    expiry = datetime.fromisoformat(expires_at.replace("Z", "+00:00"))
+    if expiry.tzinfo is None:
+        expiry = expiry.replace(tzinfo=timezone.utc)
+    return expiry <= (now or datetime.now(timezone.utc))
+
+
+def delete_expired_report(report: Dict[str, Any]) -> None:
+    """Delete a report's S3 file, then its record, and audit the deletion."""
+    if report.get("fileKey"):
+        storage.delete_object(report["fileKey"])
+    if not db.delete_report(report["reportId"]):
+        raise RuntimeError(f"Failed to delete report record {report['reportId']}")
+    audit_service.log_event(
+        event_type=AuditEventType.REPORT_DELETED,
+        user_id="system",
+        user_role="system",
+        resource_type="report",
+        resource_id=report["reportId"],
+        action="expire",
+        details={"patientId": report.get("patientId"), "expiresAt": report.get("expiresAt"),
+                 "fileKey": report.get("fileKey")}
+    )
+
+
+def notify_expiring_reports(now: Optional[datetime] = None, within_hours: int = REPORT_EXPIRY_WARNING_HOURS) -> Dict[str, int]:
+    """
+    Push a warning to the author of each report expiring within within_hours.
+
+    Returns:
+        {"notified": n, "skipped": n, "failed": n}; skipped reports were already
+        notified or have no author
+    """
+    now = now or datetime.now(timezone.utc)
+    counts = {"notified": 0, "skipped": 0, "failed": 0}
+    for report in db.get_reports_expiring_soon(within_hours, now):
+        if report.get("expiryNotifiedAt") or not report.get("authorId"):
+            counts["skipped"] += 1
+            continue
+        title = report.get("title") or report["reportId"]
+        sent = notification_service.push_to_user(
+            report["authorId"],
+            "Report expiring soon",
+            f"{title} will be permanently deleted at {report['expiresAt']}.",
+            {"type": "REPORT_EXPIRING", "reportId": report["reportId"], "expiresAt": report["expiresAt"]},
+        )
+        if not sent:
+            counts["failed"] += 1
+            continue
+        db.update_report(report["reportId"], {"expiryNotifiedAt": now.isoformat()})
+        counts["notified"] += 1
+    return counts
+
+
+def cleanup_expired_reports(
+    now: Optional[datetime] = None,
+    cursor: Optional[str] = None,
+    batch_size: int = CLEANUP_BATCH_SIZE
+) -> Dict[str, Any]:
+    """
+    Delete one batch of expired reports.
+
+    Returns:
+        {"deleted": n, "failed": n, "cursor": next cursor or None when done}
+
+    Raises:
+        ValueError: if cursor is not a valid cursor
+    """
+    now = now or datetime.now(timezone.utc)
+    page = db.scan_expired_reports(now.isoformat(), limit=batch_size, cursor=cursor)
+    deleted = failed = 0
+    for report in page.items:
+        # The scan compares ISO strings; re-check with a real timestamp comparison
+        if not is_report_expired(report, now):
+            continue
+        try:
+            delete_expired_report(report)
+            deleted += 1
+        except Exception as e:
+            print(f"[ReportCleanup] Failed to delete report {report.get('reportId')}: {e}")
+            failed += 1
+    return {"deleted": deleted, "failed": failed, "cursor": page.next_cursor}
+
+
+def run(event, context):
+    """EventBridge Scheduler handler"""
+    cursor = (event or {}).get("cursor")
+    backfilled = db.backfill_report_expires_on()
+    if backfilled:
+        print(f"[ReportCleanup] Indexed the expiry of {backfilled} older report(s)")
+    notified = notify_expiring_reports()
+    deleted = failed = 0
+    while True:
+        result = cleanup_expired_reports(cursor=cursor, batch_size=CLEANUP_BATCH_SIZE)
+        deleted += result["deleted"]
+        failed += result["failed"]
+        cursor = result["cursor"]
+        if not cursor:
+            break
+        if context is not None and context.get_remaining_time_in_millis() < CLEANUP_TIME_MARGIN_MS:
+            print(f"[ReportCleanup] Stopping early; resume with cursor {cursor}")
+            break
+    print(f"[ReportCleanup] Warned {notified['notified']} author(s); deleted {deleted} expired report(s), {failed} failed")
+    return {"deleted": deleted, "failed": failed, "cursor": cursor, "notified": notified["notified"]}
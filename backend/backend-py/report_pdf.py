@@ -0,0 +1,377 @@
+"""
+MeDUSA PDF Report Generation
+
+Renders report data into PDF files stored as the report's fileKey. The PDF
+is written directly (PDF 1.4, standard Helvetica fonts), so no third-party
+layout library has to be packaged into the Lambda.
+
+Key Features:
+- ReportGenerator interface: generate(data, parameters) -> bytes
+- Patient summary: cover page (patient name, date of birth, report date,
+  generated by), vital trends, medications and their interaction
+  warnings, assigned devices and a timeline of recent readings; content flows onto as many pages as needed
+- Vital trends leave out low-quality readings and state how many were excluded
+- Optional clinic logo (JPEG) from LOGO_S3_KEY, loaded once per container
+- count_pages() for the report's pageCount after generation
+"""
+
+import os
+import re
+import struct
+from dataclasses import dataclass, field
+from datetime import datetime, timezone
+from typing import Any, Dict, List, Optional, Sequence, Tuple
+
+import db
+import storage
+from medication_interactions import check_interactions
+from analytics_service import READING_METRIC_KEYS, compute_stats, partition_by_quality, reading_time, trend_for_metric
+
+LOGO_S3_KEY = os.environ.get("LOGO_S3_KEY")
+
+REPORT_TYPE_PATIENT_SUMMARY = "patient_summary"
+PDF_CONTENT_TYPE = "application/pdf"
+
+# A4 in points; text uses the 14 standard fonts so nothing is embedded
+PAGE_WIDTH, PAGE_HEIGHT = 595, 842
+MARGIN = 50
+LINE_HEIGHT = 14
+FONT_SIZE = 10
+HEADING_SIZE = 14
+TITLE_SIZE = 22
+CHAR_WIDTH = 0.5  # average Helvetica glyph width per point of font size
+
+RECENT_READINGS_LIMIT = 50
+
+
+@dataclass
+class ReportParameters:
+    """Who generated the report and when; recent_readings caps the timeline."""
+    generated_by: str
+    generated_at: datetime = field(default_factory=lambda: datetime.now(timezone.utc))
+    recent_readings: int = RECENT_READINGS_LIMIT
+
+
+@dataclass
+class ReportData:
+    """Records a patient summary is built from."""
+    patient: Dict[str, Any]
+    profile: Dict[str, Any]
+    medications: List[Dict[str, Any]]
+    devices: List[Dict[str, Any]]
+    readings: List[Dict[str, Any]]
+    reading_devices: Dict[str, Dict[str, Any]] = field(default_factory=dict)  # by id, incl. since-unassigned devices
+    interactions: List[Dict[str, Any]] = field(default_factory=list)  # InteractionWarning.to_dict() of the medications
+
+    @property
+    def low_quality_count(self) -> int:
+        """Readings left out of the vital trends (see analytics_service.is_low_quality)."""
+        return len(partition_by_quality(self.readings)[1])
+
+    @property
+    def low_quality_ratio(self) -> float:
+        return round(self.low_quality_count / len(self.readings), 4) if self.readings else 0.0
+
+
+class ReportGenerator:
+    """Renders ReportData into a file of one format."""
+
+    content_type = "application/octet-stream"
+    extension = "bin"
+
+    def generate(self, data: ReportData, parameters: ReportParameters) -> bytes:
+        raise NotImplementedError
+
+
+def load_patient_summary_data(patient_id: str) -> ReportData:
+    """Collect a patient's records; flagged (soft-deleted) readings are left out."""
+    readings = [r for r in db.get_patient_readings(patient_id) if not r.get("is_flagged")]
+    medications = db.get_medications(patient_id, active_only=True)
+    return ReportData(
+        patient=db.get_user(patient_id) or {"id": patient_id},
+        profile=db.get_patient_profile(patient_id) or {},
+        medications=medications,
+        devices=db.get_devices_by_patient(patient_id),
+        readings=readings,
+        reading_devices=db.get_devices_bulk([r["device_id"] for r in readings if r.get("device_id")]),
+        interactions=[w.to_dict() for w in check_interactions(medications)],
+    )
+
+
+# -------- Logo
+
+_logo: Optional[bytes] = None
+_logo_loaded = False
+
+
+def jpeg_size(data: bytes) -> Optional[Tuple[int, int]]:
+    """(width, height) from a JPEG's SOF marker, or None if data is not a JPEG."""
+    if not data.startswith(b"\xff\xd8"):
+        return None
+    i = 2
+    while i + 9 < len(data):
+        if data[i] != 0xFF:
+            return None
+        marker = data[i + 1]
+        length = struct.unpack(">H", data[i + 2:i + 4])[0]
+        if 0xC0 <= marker <= 0xCF and marker not in (0xC4, 0xC8, 0xCC):
+            height, width = struct.unpack(">HH", data[i + 5:i + 9])
+            return width, height
+        i += 2 + length
+    return None
+
+
+def load_logo() -> Optional[bytes]:
+    """The clinic logo from LOGO_S3_KEY, fetched on first use and kept for the container's lifetime."""
+    global _logo, _logo_loaded
+    if not _logo_loaded:
+        _logo_loaded = True
+        if LOGO_S3_KEY:
+            try:
+                data = storage.download_bytes(LOGO_S3_KEY)
+                if jpeg_size(data):
+                    _logo = data
+                else:
+                    print(f"[ReportPdf] Logo {LOGO_S3_KEY} is not a JPEG; reports are generated without it")
+            except Exception as e:
+                print(f"[ReportPdf] Unable to load logo {LOGO_S3_KEY}: {e}")
+    return _logo
+
+
+# -------- PDF writing
+
+def _escape(text: Any) -> str:
+    """PDF string literal content; characters outside WinAnsi become '?'."""
+    encoded = str(text).encode("cp1252", errors="replace").decode("latin-1")
+    return encoded.replace("\\", "\\\\").replace("(", "\\(").replace(")", "\\)")
+
+
+def _fit(text: Any, width: float, size: int = FONT_SIZE) -> str:
+    """Truncate text to roughly fit width points."""
+    text = "" if text is None else str(text)
+    max_chars = max(1, int(width / (size * CHAR_WIDTH)))
+    return text if len(text) <= max_chars else text[:max_chars - 3] + "..."
+
+
+class _PdfCanvas:
+    """Collects page content streams, starting a new page when text reaches the bottom margin."""
+
+    def __init__(self):
+        self.pages: List[List[str]] = []
+        self.y = 0.0
+
+    def new_page(self):
+        self.pages.append([])
+        self.y = PAGE_HEIGHT - MARGIN
+
+    def _ensure_space(self, height: float):
+        if not self.pages or self.y - height < MARGIN:
+            self.new_page()
+
+    def text(self, x: float, y: float, value: Any, size: int = FONT_SIZE, bold: bool = False):
+        font = "F2" if bold else "F1"
+        self.pages[-1].append(f"BT /{font} {size} Tf {x:.1f} {y:.1f} Td ({_escape(value)}) Tj ET")
+
+    def line(self, value: Any = "", size: int = FONT_SIZE, bold: bool = False):
+        self._ensure_space(LINE_HEIGHT)
+        self.y -= LINE_HEIGHT
+        self.text(MARGIN, self.y, value, size, bold)
+
+    def heading(self, value: str):
+        # Keep a heading together with at least its first two rows
+        self._ensure_space(HEADING_SIZE + 4 * LINE_HEIGHT)
+        self.y -= LINE_HEIGHT
+        self.y -= HEADING_SIZE
+        self.text(MARGIN, self.y, value, HEADING_SIZE, bold=True)
+        self.y -= 4
+
+    def table(self, columns: Sequence[Tuple[str, float]], rows: List[Sequence[Any]], empty: str):
+        """Rows of cells under bold headers; columns are (title, width) and fill the text width."""
+        def row(cells: Sequence[Any], bold: bool = False):
+            self._ensure_space(LINE_HEIGHT)
+            self.y -= LINE_HEIGHT
+            x = MARGIN
+            for (_, width), cell in zip(columns, cells):
+                self.text(x, self.y, _fit(cell, width - 6), bold=bold)
+                x += width
+
+        row([title for title, _ in columns], bold=True)
+        if not rows:
+            self.line(empty)
+        for cells in rows:
+            row(cells)
+
+    def image(self, name: str, x: float, y: float, width: float, height: float):
+        self.pages[-1].append(f"q {width:.1f} 0 0 {height:.1f} {x:.1f} {y:.1f} cm /{name} Do Q")
+
+
+def _write_pdf(pages: List[List[str]], logo: Optional[bytes] = None) -> bytes:
+    """Serialize page content streams (and an optional JPEG XObject /Logo) into a PDF file."""
+    objects: List[bytes] = []
+
+    def add(body: bytes) -> int:
+        objects.append(body)
+        return len(objects)
+
+    catalog = add(b"")  # filled in once the page tree exists
+    pages_obj = add(b"")
+    regular = add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>")
+    bold = add(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>")
+    xobjects = ""
+    if logo:
+        width, height = jpeg_size(logo)
+        image = add(
+            f"<< /Type /XObject /Subtype /Image /Width {width} /Height {height} /ColorSpace /DeviceRGB "
+            f"/BitsPerComponent 8 /Filter /DCTDecode /Length {len(logo)} >>\nstream\n".encode()
+            + logo + b"\nendstream"
+        )
+        xobjects = f" /XObject << /Logo {image} 0 R >>"
+    resources = f"<< /Font << /F1 {regular} 0 R /F2 {bold} 0 R >>{xobjects} >>"
+
+    page_ids = []
+    for content in pages:
+        stream = "\n".join(content).encode("latin-1")
+        content_id = add(f"<< /Length {len(stream)} >>\nstream\n".encode() + stream + b"\nendstream")
+        page_ids.append(add(
+            f"<< /Type /Page /Parent {pages_obj} 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] "
+            f"/Resources {resources} /Contents {content_id} 0 R >>".encode()
+        ))
+    kids = " ".join(f"{page_id} 0 R" for page_id in page_ids)
+    objects[catalog - 1] = f"<< /Type /Catalog /Pages {pages_obj} 0 R >>".encode()
+    objects[pages_obj - 1] = f"<< /Type /Pages /Kids [{kids}] /Count {len(page_ids)} >>".encode()
+
+    out = bytearray(b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n")
+    offsets = []
+    for number, body in enumerate(objects, start=1):
+        offsets.append(len(out))
+        out += f"{number} 0 obj\n".encode() + body + b"\nendobj\n"
+    xref = len(out)
+    out += f"xref\n0 {len(objects) + 1}\n0000000000 65535 f \n".encode()
+    out += b"".join(f"{offset:010d} 00000 n \n".encode() for offset in offsets)
+    out += f"trailer\n<< /Size {len(objects) + 1} /Root {catalog} 0 R >>\nstartxref\n{xref}\n%%EOF\n".encode()
+    return bytes(out)
+
+
+def count_pages(pdf: bytes) -> int:
+    """Number of pages in a PDF written by this module (the page tree's /Count)."""
+    match = re.search(rb"/Type /Pages /Kids \[[^\]]*\] /Count (\d+)", pdf)
+    return int(match.group(1)) if match else 0
+
+
+# -------- Patient summary
+
+def _number(value: Optional[float]) -> str:
+    return "-" if value is None else f"{value:.2f}"
+
+
+def _format_time(reading: Dict[str, Any]) -> str:
+    ts = reading_time(reading)
+    return ts.strftime("%Y-%m-%d %H:%M") if ts else "-"
+
+
+class PdfReportGenerator(ReportGenerator):
+    """Patient summary as a multi-page PDF."""
+
+    content_type = PDF_CONTENT_TYPE
+    extension = "pdf"
+
+    def __init__(self, logo: Optional[bytes] = None):
+        self.logo = logo
+
+    def generate(self, data: ReportData, parameters: ReportParameters) -> bytes:
+        canvas = _PdfCanvas()
+        self._cover_page(canvas, data, parameters)
+        canvas.new_page()
+        self._vital_trends(canvas, data.readings)
+        self._medications(canvas, data.medications)
+        self._interactions(canvas, data.interactions)
+        self._devices(canvas, data.devices)
+        self._timeline(canvas, data.readings, data.reading_devices, parameters.recent_readings)
+        return _write_pdf(canvas.pages, self.logo)
+
+    def _cover_page(self, canvas: _PdfCanvas, data: ReportData, parameters: ReportParameters):
+        canvas.new_page()
+        if self.logo:
+            width, height = jpeg_size(self.logo)
+            scale = min(1.0, 150 / width, 80 / height)
+            canvas.image("Logo", MARGIN, PAGE_HEIGHT - MARGIN - height * scale, width * scale, height * scale)
+            canvas.y -= height * scale + LINE_HEIGHT
+        canvas.y -= 2 * TITLE_SIZE
+        canvas.text(MARGIN, canvas.y, "Patient Summary Report", TITLE_SIZE, bold=True)
+        canvas.y -= LINE_HEIGHT
+
+        patient = data.patient
+        rows = [
+            ("Patient", patient.get("name") or patient.get("id")),
+            ("Date of birth", data.profile.get("dateOfBirth") or "-"),
+            ("Report date", parameters.generated_at.strftime("%Y-%m-%d %H:%M UTC")),
+            ("Generated by", parameters.generated_by),
+        ]
+        for label, value in rows:
+            canvas.y -= LINE_HEIGHT + 4
+            canvas.text(MARGIN, canvas.y, label, bold=True)
+            canvas.text(MARGIN + 120, canvas.y, _fit(value, PAGE_WIDTH - 2 * MARGIN - 120))
+
+    def _vital_trends(self, canvas: _PdfCanvas, readings: List[Dict[str, Any]]):
+        canvas.heading("Vital Trends")
+        readings, low_quality = partition_by_quality(readings)
+        rows = []
+        for metric in READING_METRIC_KEYS:
+            values = [float(r[metric]) for r in readings
+                      if isinstance(r.get(metric), (int, float)) and not isinstance(r.get(metric), bool)]
+            if not values:
+                continue
+            stats = compute_stats(values)
+            trend = trend_for_metric(readings, metric)
+            rows.append((metric, len(values), _number(stats["mean"]), _number(stats["min"]),
+                         _number(stats["max"]), trend.direction.value))
+        canvas.table(
+            [("Metric", 145), ("Readings", 60), ("Mean", 60), ("Min", 60), ("Max", 60), ("Trend", 110)],
+            rows, "No readings recorded."
+        )
+        if low_quality:
+            canvas.line(f"{len(low_quality)} low-quality reading(s) excluded from trends.")
+
+    def _medications(self, canvas: _PdfCanvas, medications: List[Dict[str, Any]]):
+        canvas.heading("Medications")
+        canvas.table(
+            [("Name", 160), ("Dosage", 90), ("Frequency", 110), ("Route", 60), ("Since", 75)],
+            [(m.get("name"), m.get("dosage"), m.get("frequency"), m.get("route") or "-", m.get("startDate") or "-")
+             for m in medications],
+            "No active medications."
+        )
+
+    def _interactions(self, canvas: _PdfCanvas, interactions: List[Dict[str, Any]]):
+        canvas.heading("Medication Interactions")
+        canvas.table(
+            [("Severity", 60), ("Medications", 170), ("Warning", 265)],
+            [(w["severity"].upper(), " + ".join(w["medicationNames"]), w["description"]) for w in interactions],
+            "No known interactions between active medications."
+        )
+
+    def _devices(self, canvas: _PdfCanvas, devices: List[Dict[str, Any]]):
+        canvas.heading("Assigned Devices")
+        canvas.table(
+            [("Device", 95), ("Name", 140), ("Type", 80), ("Status", 70), ("Battery", 55), ("Firmware", 55)],
+            [(d.get("id"), d.get("name"), d.get("type"), d.get("status"),
+              f"{d['batteryLevel']}%" if d.get("batteryLevel") is not None else "-", d.get("firmwareVersion") or "-")
+             for d in devices],
+            "No devices assigned."
+        )
+
+    def _timeline(self, canvas: _PdfCanvas, readings: List[Dict[str, Any]],
+                  devices: Dict[str, Dict[str, Any]], limit: int):
+        canvas.heading("Recent Readings")
+        recent = sorted(readings, key=lambda r: reading_time(r) or datetime.min.replace(tzinfo=timezone.utc),
+                        reverse=True)[:limit]
+
+        def device_label(device_id: Optional[str]) -> str:
+            return (devices.get(device_id) or {}).get("name") or device_id or "-"
+
+        canvas.table(
+            [("Time", 110), ("Device", 95), ("Tremor index", 80), ("Frequency", 80), ("Signal quality", 130)],
+            [(_format_time(r), device_label(r.get("device_id")), _number(r.get("tremor_index")),
+              _number(r.get("dominant_frequency")), _number(r.get("signal_quality")))
+             for r in recent],
+            "No readings recorded."
+        )
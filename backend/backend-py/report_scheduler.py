@@ -0,0 +1,212 @@
+"""
+MeDUSA Report Scheduler
+
+Recurring report generation (e.g. a weekly patient summary every Monday).
+
+- run:     EventBridge Scheduler handler (hourly). Enqueues one SQS message
+           per due schedule (nextRunAt <= now) and advances nextRunAt.
+- process: SQS handler. Creates the report from the schedule's template.
+           Failed messages go to the dead-letter queue after maxReceiveCount.
+
+Key Features:
+- Frequencies: daily, weekly on a weekday, monthly on a day of the month
+  (clamped to the month's last day, so day 31 runs on Feb 28/29)
+- Runs keep the time of day of the first run (UTC)
+- Missed runs (scheduler outage) are collapsed into one report
+- Reports still require the patient's data sharing consent when generated
+- The schedule's creator gets a push notification for each generated report
+"""
+
+import os
+import json
+import uuid
+import calendar
+from datetime import datetime, timezone, timedelta
+from typing import Any, Dict, List, Optional
+
+import aws_clients
+import db
+from audit_service import audit_service, AuditEventType
+from consent_service import ConsentType, get_active_consent
+from metrics_service import metrics, REPORT_GENERATION_DURATION
+from notification_service import notification_service
+
+REPORTS_QUEUE_URL = os.environ.get("REPORTS_QUEUE_URL")
+
+FREQUENCY_DAILY = "daily"
+FREQUENCY_WEEKLY = "weekly"
+FREQUENCY_MONTHLY = "monthly"
+WEEKDAYS = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"]
+
+_sqs = None
+
+
+def _sqs_client():
+    """Created lazily so importing this module (e.g. from main) needs no AWS region."""
+    global _sqs
+    if _sqs is None:
+        _sqs = aws_clients.get_client("sqs")
+    return _sqs
+
+
+def _parse_iso(value: str) -> datetime:
+    parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+    return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+
+
+def _add_month(ts: datetime, day: int) -> datetime:
+    year, month = (ts.year + 1, 1) if ts.month == 12 else (ts.year, ts.month + 1)
+    return ts.replace(year=year, month=month, day=min(day, calendar.monthrange(year, month)[1]))
+
+
+def first_run_on_or_after(frequency: Dict[str, Any], ts: datetime) -> datetime:
+    """Earliest run time >= ts, keeping ts's time of day."""
+    kind = frequency["type"]
+    if kind == FREQUENCY_DAILY:
+        return ts
+    if kind == FREQUENCY_WEEKLY:
+        days_ahead = (WEEKDAYS.index(frequency["weekday"]) - ts.weekday()) % 7
+        return ts + timedelta(days=days_ahead)
+    if kind == FREQUENCY_MONTHLY:
+        day = frequency["dayOfMonth"]
+        candidate = ts.replace(day=min(day, calendar.monthrange(ts.year, ts.month)[1]))
+        return candidate if candidate >= ts else _add_month(ts, day)
+    raise ValueError(f"Unknown schedule frequency: {kind}")
+
+
+def next_run_after(frequency: Dict[str, Any], ts: datetime) -> datetime:
+    """The run following the one at ts."""
+    return first_run_on_or_after(frequency, ts + timedelta(days=1))
+
+
+def next_future_run(frequency: Dict[str, Any], last_scheduled: datetime, now: datetime) -> datetime:
+    """First run strictly after now, stepping from the last scheduled run."""
+    run_at = next_run_after(frequency, last_scheduled)
+    while run_at <= now:
+        run_at = next_run_after(frequency, run_at)
+    return run_at
+
+
+# -------- Schedule CRUD
+
+def create_schedule(
+    report_template: Dict[str, Any],
+    frequency: Dict[str, Any],
+    created_by: str,
+    created_by_role: str,
+    start_at: Optional[datetime] = None
+) -> Dict[str, Any]:
+    """Create an active schedule whose first run is the first occurrence at or after start_at (default: now)."""
+    now = datetime.now(timezone.utc)
+    start = (start_at or now).astimezone(timezone.utc)
+    schedule = {
+        "id": str(uuid.uuid4()),
+        "reportTemplate": report_template,
+        "frequency": frequency,
+        "nextRunAt": first_run_on_or_after(frequency, start).isoformat(),
+        "lastRunAt": None,
+        "createdBy": created_by,
+        "createdByRole": created_by_role,
+        "isActive": True,
+        "createdAt": now.isoformat(),
+    }
+    return db.put_report_schedule(schedule)
+
+
+def list_schedules(created_by: Optional[str] = None) -> List[Dict[str, Any]]:
+    return db.list_report_schedules(created_by)
+
+
+def delete_schedule(schedule_id: str) -> bool:
+    return db.delete_report_schedule(schedule_id)
+
+
+# -------- Scheduler (EventBridge)
+
+def enqueue_due_schedules(now: Optional[datetime] = None) -> int:
+    """
+    Enqueue a report for every due schedule and advance its nextRunAt.
+
+    Returns:
+        Number of reports enqueued
+    """
+    now = now or datetime.now(timezone.utc)
+    enqueued = 0
+    for schedule in db.get_due_report_schedules(now.isoformat()):
+        try:
+            scheduled_for = _parse_iso(schedule["nextRunAt"])
+            _sqs_client().send_message(
+                QueueUrl=REPORTS_QUEUE_URL,
+                MessageBody=json.dumps({
+                    "scheduleId": schedule["id"],
+                    "scheduledFor": schedule["nextRunAt"],
+                    "reportTemplate": schedule["reportTemplate"],
+                    "createdBy": schedule["createdBy"],
+                    "createdByRole": schedule.get("createdByRole", "doctor"),
+                })
+            )
+            db.update_report_schedule_run(
+                schedule["id"],
+                last_run_at=now.isoformat(),
+                next_run_at=next_future_run(schedule["frequency"], scheduled_for, now).isoformat()
+            )
+            enqueued += 1
+        except Exception as e:
+            # Leave nextRunAt unchanged so the next hourly run retries it
+            print(f"[ReportScheduler] Failed to enqueue schedule {schedule.get('id')}: {e}")
+    return enqueued
+
+
+def run(event, context):
+    """EventBridge Scheduler handler"""
+    enqueued = enqueue_due_schedules()
+    print(f"[ReportScheduler] Enqueued {enqueued} scheduled report(s)")
+    return {"enqueued": enqueued}
+
+
+# -------- Worker (SQS)
+
+def generate_scheduled_report(message: Dict[str, Any]) -> Optional[Dict[str, Any]]:
+    """
+    Create the report for one schedule run.
+
+    Returns:
+        The created report, or None if the patient no longer consents to data sharing
+    """
+    template = message["reportTemplate"]
+    patient_id = template.get("patientId")
+    if patient_id and get_active_consent(patient_id, ConsentType.DATA_SHARING.value) is None:
+        print(f"[ReportScheduler] Skipping schedule {message['scheduleId']}: no data sharing consent")
+        return None
+
+    with metrics.timer(REPORT_GENERATION_DURATION, Endpoint="report_scheduler.process"):
+        report = db.create_report({
+            **template,
+            "authorId": message["createdBy"],
+            "authorRole": message["createdByRole"],
+            "scheduleId": message["scheduleId"],
+            "scheduledFor": message["scheduledFor"],
+        })
+    audit_service.log_event(
+        event_type=AuditEventType.DATA_CREATE,
+        user_id=message["createdBy"],
+        user_role=message["createdByRole"],
+        resource_type="report",
+        resource_id=report.get("reportId"),
+        action="create_scheduled",
+        details={"patientId": patient_id, "scheduleId": message["scheduleId"]}
+    )
+    notification_service.push_report_ready(report)
+    return report
+
+
+def process(event, context):
+    """SQS batch handler; returns the messages to retry (ReportBatchItemFailures)."""
+    failures = []
+    for record in event.get("Records", []):
+        try:
+            generate_scheduled_report(json.loads(record["body"]))
+        except Exception as e:
+            print(f"[ReportScheduler] Failed to generate report for message {record.get('messageId')}: {e}")
+            failures.append({"itemIdentifier": record["messageId"]})
+    return {"batchItemFailures": failures}
@@ -0,0 +1,87 @@
+"""
+MeDUSA Request Context
+
+Carries a per-request correlation ID so every audit event written while
+handling one HTTP request can be traced back to it
+(GET /api/v1/admin/audit/trace/{request_id}).
+
+How request_id flows:
+1. API Gateway assigns each request an ID (event.requestContext.requestId,
+   also returned to clients in the x-amzn-RequestId response header).
+2. Mangum passes the raw event to FastAPI as scope["aws.event"]; the
+   middleware reads the ID from there. Outside API Gateway (local runs)
+   a caller-supplied X-Request-Id header is used, else a new UUID.
+3. The ID is stored in a ContextVar for the duration of the request and
+   echoed in the X-Request-Id response header.
+4. audit_service.log_event stores it as requestId on every audit entry
+   (an explicit request_id argument takes precedence), which the
+   requestId-index GSI makes queryable.
+
+Key Features:
+- ContextVar storage, so concurrent requests in one container don't mix IDs
+- Caller-supplied IDs are length/charset checked before being trusted
+- The caller's organization (JWT "org" claim) is carried the same way and
+  scopes db reads and writes to that tenant (see organization_service)
+"""
+
+import re
+import uuid
+from contextlib import contextmanager
+from contextvars import ContextVar
+from typing import Iterator, Optional
+
+from fastapi import Request
+
+REQUEST_ID_HEADER = "X-Request-Id"
+
+_REQUEST_ID_PATTERN = re.compile(r"^[A-Za-z0-9._:-]{1,128}$")
+
+_request_id: ContextVar[Optional[str]] = ContextVar("request_id", default=None)
+
+# Organization of records created before multi-tenancy, and of tokens without an "org" claim
+DEFAULT_ORGANIZATION_ID = "org_default"
+
+_organization_id: ContextVar[Optional[str]] = ContextVar("organization_id", default=None)
+
+
+def current_request_id() -> Optional[str]:
+    """ID of the request being handled, or None outside a request."""
+    return _request_id.get()
+
+
+def current_organization_id() -> Optional[str]:
+    """Organization of the authenticated caller, or None outside a request (unscoped)."""
+    return _organization_id.get()
+
+
+@contextmanager
+def organization_scope(organization_id: Optional[str]) -> Iterator[None]:
+    """Scope db access to an organization (None: every organization) for the block."""
+    token = _organization_id.set(organization_id)
+    try:
+        yield
+    finally:
+        _organization_id.reset(token)
+
+
+def resolve_request_id(request: Request) -> str:
+    """API Gateway request ID, else a valid X-Request-Id header, else a new UUID."""
+    event = request.scope.get("aws.event") or {}
+    gateway_id = (event.get("requestContext") or {}).get("requestId")
+    if gateway_id:
+        return gateway_id
+    supplied = request.headers.get(REQUEST_ID_HEADER)
+    if supplied and _REQUEST_ID_PATTERN.match(supplied):
+        return supplied
+    return str(uuid.uuid4())
+
+
+async def request_id_middleware(request: Request, call_next):
+    request_id = resolve_request_id(request)
+    token = _request_id.set(request_id)
+    try:
+        response = await call_next(request)
+    finally:
+        _request_id.reset(token)
+    response.headers[REQUEST_ID_HEADER] = request_id
+    return response
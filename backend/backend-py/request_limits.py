@@ -0,0 +1,70 @@
+"""
+MeDUSA Request Body Limits
+
+Rejects oversized request bodies with 413 before a handler allocates and
+parses them.
+
+Key Features:
+- MAX_BODY_BYTES (default 1 MiB) applies to every API endpoint
+- Per-endpoint overrides for routes that legitimately carry larger bodies
+  (raw profile picture uploads)
+- Declared Content-Length is checked without reading the body; bodies
+  without one are measured once read
+- The device ingestion Lambda has its own MAX_INGEST_BODY_BYTES
+  (device_data_ingest)
+"""
+
+import os
+import re
+from typing import List, Optional, Pattern, Tuple
+
+from fastapi import Request
+from fastapi.responses import JSONResponse
+
+import storage
+
+MAX_BODY_BYTES = int(os.environ.get("MAX_BODY_BYTES", str(1024 * 1024)))
+
+# (path pattern, limit) checked in order; the first match wins
+BODY_LIMIT_OVERRIDES: List[Tuple[Pattern, int]] = [
+    (re.compile(r"^/api/v1/users/[^/]+/profile-picture$"), storage.MAX_PROFILE_PICTURE_BYTES),
+]
+
+_METHODS_WITH_BODY = {"POST", "PUT", "PATCH", "DELETE"}
+
+
+def body_limit_for(path: str) -> int:
+    """Maximum body size in bytes accepted for a request path."""
+    for pattern, limit in BODY_LIMIT_OVERRIDES:
+        if pattern.match(path):
+            return limit
+    return MAX_BODY_BYTES
+
+
+def exceeds_limit(size: Optional[int], limit: int) -> bool:
+    return size is not None and size > limit
+
+
+def declared_length(value: Optional[str]) -> Optional[int]:
+    """Content-Length header as an int, or None when absent or malformed."""
+    return int(value) if value and value.strip().isdigit() else None
+
+
+def payload_too_large(limit: int) -> JSONResponse:
+    return JSONResponse(
+        status_code=413,
+        content={"detail": {"code": "PAYLOAD_TOO_LARGE", "message": f"Request body must be at most {limit} bytes"}}
+    )
+
+
+async def body_size_middleware(request: Request, call_next):
+    """Runs before auth, so unauthenticated oversized requests are refused just as cheaply."""
+    if request.method not in _METHODS_WITH_BODY:
+        return await call_next(request)
+    limit = body_limit_for(request.url.path)
+    declared = declared_length(request.headers.get("content-length"))
+    if exceeds_limit(declared, limit):
+        return payload_too_large(limit)
+    if declared is None and exceeds_limit(len(await request.body()), limit):
+        return payload_too_large(limit)
+    return await call_next(request)
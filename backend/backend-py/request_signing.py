@@ -0,0 +1,145 @@
+"""
+MeDUSA Device Request Signing
+
+HMAC authentication for devices that cannot hold a JWT but can embed a
+pre-shared key.
+
+Request headers:
+- DEVICE_API_KEY_HEADER (default X-Device-Hardware-Id): the device's
+  hardware id (its MAC address), which looks up the secret in the
+  device API keys table
+- X-Device-Timestamp: Unix seconds when the request was signed
+- X-Device-Signature: hex HMAC-SHA256(device_secret,
+  "METHOD\\nPATH\\nTIMESTAMP\\nBODY_HASH"), BODY_HASH being the hex SHA-256
+  of the raw body (of the empty string for no body)
+
+Key Features:
+- Requests signed more than DEVICE_SIGNATURE_TOLERANCE_SECONDS (default
+  300) before or after the server's clock are refused, so a captured
+  request cannot be replayed later
+- Constant-time signature comparison
+- verify_device_request(event) authenticates an API Gateway (Lambda proxy)
+  event and returns the device id
+"""
+
+import os
+import hmac
+import base64
+import time
+import hashlib
+from typing import Callable, Dict, Mapping, Optional, Union
+
+import db
+
+DEVICE_API_KEY_HEADER = os.environ.get("DEVICE_API_KEY_HEADER", "X-Device-Hardware-Id")
+DEVICE_SIGNATURE_HEADER = "X-Device-Signature"
+DEVICE_TIMESTAMP_HEADER = "X-Device-Timestamp"
+DEVICE_SIGNATURE_TOLERANCE_SECONDS = int(os.environ.get("DEVICE_SIGNATURE_TOLERANCE_SECONDS", "300"))
+
+
+class DeviceSignatureError(Exception):
+    """Raised when a signed device request cannot be authenticated."""
+
+    def __init__(self, code: str, message: str):
+        super().__init__(message)
+        self.code = code
+        self.message = message
+
+
+def _as_bytes(body: Union[str, bytes, None]) -> bytes:
+    if body is None:
+        return b""
+    return body.encode("utf-8") if isinstance(body, str) else body
+
+
+def canonical_request(method: str, path: str, timestamp: str, body: Union[str, bytes, None]) -> str:
+    body_hash = hashlib.sha256(_as_bytes(body)).hexdigest()
+    return f"{method.upper()}\n{path}\n{timestamp}\n{body_hash}"
+
+
+def sign_request(secret: str, method: str, path: str, timestamp: str, body: Union[str, bytes, None] = None) -> str:
+    """The X-Device-Signature value a device sends (also used by device SDKs and tests)."""
+    return hmac.new(secret.encode(), canonical_request(method, path, timestamp, body).encode(), hashlib.sha256).hexdigest()
+
+
+class HmacRequestVerifier:
+    """Verifies device request signatures against the device API keys table."""
+
+    def __init__(
+        self,
+        lookup_key: Optional[Callable[[str], Optional[Dict[str, str]]]] = None,
+        tolerance_seconds: Optional[int] = None
+    ):
+        self.lookup_key = lookup_key or db.get_device_api_key
+        self.tolerance_seconds = DEVICE_SIGNATURE_TOLERANCE_SECONDS if tolerance_seconds is None else tolerance_seconds
+
+    def verify(
+        self,
+        method: str,
+        path: str,
+        headers: Mapping[str, str],
+        body: Union[str, bytes, None],
+        now: Optional[float] = None
+    ) -> str:
+        """
+        Authenticate a signed request.
+
+        Returns:
+            The device id the hardware id is registered to
+
+        Raises:
+            DeviceSignatureError: missing headers, stale timestamp, unknown
+                hardware id or wrong signature
+        """
+        headers = {k.lower(): v for k, v in headers.items()}
+        hardware_id = headers.get(DEVICE_API_KEY_HEADER.lower())
+        timestamp = headers.get(DEVICE_TIMESTAMP_HEADER.lower())
+        signature = headers.get(DEVICE_SIGNATURE_HEADER.lower())
+        if not hardware_id or not timestamp or not signature:
+            raise DeviceSignatureError("DEVICE_SIGNATURE_MISSING", "Signed device request headers are missing")
+
+        try:
+            signed_at = int(timestamp)
+        except ValueError:
+            raise DeviceSignatureError("DEVICE_SIGNATURE_INVALID", "Invalid request timestamp")
+        now = time.time() if now is None else now
+        if abs(now - signed_at) > self.tolerance_seconds:
+            raise DeviceSignatureError("DEVICE_SIGNATURE_EXPIRED", "Request timestamp is outside the allowed window")
+
+        key = self.lookup_key(hardware_id)
+        if not key or not key.get("secret"):
+            raise DeviceSignatureError("DEVICE_SIGNATURE_INVALID", "Invalid device signature")
+        expected = sign_request(key["secret"], method, path, timestamp, body)
+        if not hmac.compare_digest(signature.lower(), expected):
+            raise DeviceSignatureError("DEVICE_SIGNATURE_INVALID", "Invalid device signature")
+        return key["deviceId"]
+
+
+def event_body(event: Dict) -> Optional[str]:
+    """The raw body of an API Gateway proxy event (decoded when API Gateway passed it base64-encoded)."""
+    body = event.get("body")
+    if body is not None and event.get("isBase64Encoded"):
+        return base64.b64decode(body).decode("utf-8")
+    return body
+
+
+def is_signed_request(headers: Mapping[str, str]) -> bool:
+    return any(k.lower() == DEVICE_SIGNATURE_HEADER.lower() for k in headers)
+
+
+def verify_device_request(event: Dict, verifier: Optional[HmacRequestVerifier] = None) -> str:
+    """
+    Authenticate a signed API Gateway proxy event.
+
+    Returns:
+        The device id
+
+    Raises:
+        DeviceSignatureError
+    """
+    return (verifier or HmacRequestVerifier()).verify(
+        event.get("httpMethod") or "",
+        event.get("path") or "",
+        event.get("headers") or {},
+        event_body(event)
+    )
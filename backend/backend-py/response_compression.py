@@ -0,0 +1,114 @@
+"""
+MeDUSA Response Compression
+
+Compresses API response bodies (report listings, audit log queries, ...)
+for clients that accept it.
+
+Key Features:
+- compress_if_accepted(body, accept_encoding) -> (body, content encoding):
+  "br" (when the brotli package is installed), "gzip" or "identity",
+  following the client's Accept-Encoding preferences (q-values)
+- Only bodies larger than COMPRESSION_THRESHOLD_BYTES (default 1024) and of
+  text-like content types (JSON, text, CSV, XML) are compressed
+- Every response carries a Content-Encoding header, and Vary: Accept-Encoding
+  so caches keep the variants apart
+- Compressed bodies are binary; Mangum returns them base64-encoded and the
+  API's BinaryMediaTypes let API Gateway decode them for the client
+"""
+
+import os
+import gzip
+from typing import Dict, Optional, Tuple
+
+from fastapi import Request
+from fastapi.responses import Response
+
+try:
+    import brotli
+except ImportError:  # br is only offered when the package is available
+    brotli = None
+
+COMPRESSION_THRESHOLD_BYTES = int(os.environ.get("COMPRESSION_THRESHOLD_BYTES", "1024"))
+GZIP_LEVEL = 6
+BROTLI_QUALITY = 5  # fast enough for per-request use, close to gzip -9 ratios
+
+IDENTITY = "identity"
+_COMPRESSIBLE_TYPES = ("application/json", "text/", "application/xml", "application/fhir+json")
+
+
+def _accepted_encodings(accept_encoding: str) -> Dict[str, float]:
+    """Accept-Encoding as {coding: q}; unparsable q-values count as 0."""
+    accepted = {}
+    for part in (accept_encoding or "").split(","):
+        coding, _, params = part.strip().partition(";")
+        coding = coding.strip().lower()
+        if not coding:
+            continue
+        q = 1.0
+        params = params.strip()
+        if params.startswith("q="):
+            try:
+                q = float(params[2:])
+            except ValueError:
+                q = 0.0
+        accepted[coding] = q
+    return accepted
+
+
+def select_encoding(accept_encoding: str) -> str:
+    """The best supported encoding the client accepts; br wins ties."""
+    accepted = _accepted_encodings(accept_encoding)
+    wildcard = accepted.get("*", 0.0)
+    candidates = (["br"] if brotli is not None else []) + ["gzip"]
+    best, best_q = IDENTITY, 0.0
+    for coding in candidates:
+        q = accepted.get(coding, wildcard)
+        if q > best_q:
+            best, best_q = coding, q
+    return best
+
+
+def compress_if_accepted(body: bytes, accept_encoding: str, threshold: Optional[int] = None) -> Tuple[bytes, str]:
+    """
+    Compress body with the client's preferred encoding.
+
+    Returns:
+        (body, content encoding); the body is unchanged with "identity" when it
+        is not above the threshold or the client accepts no supported encoding
+    """
+    threshold = COMPRESSION_THRESHOLD_BYTES if threshold is None else threshold
+    if len(body) <= threshold:
+        return body, IDENTITY
+    encoding = select_encoding(accept_encoding)
+    if encoding == "br":
+        return brotli.compress(body, quality=BROTLI_QUALITY), encoding
+    if encoding == "gzip":
+        return gzip.compress(body, compresslevel=GZIP_LEVEL), encoding
+    return body, IDENTITY
+
+
+def _compressible(response) -> bool:
+    content_type = response.headers.get("content-type", "")
+    return (
+        "content-encoding" not in response.headers
+        and response.status_code not in (204, 304)
+        and any(content_type.startswith(t) for t in _COMPRESSIBLE_TYPES)
+    )
+
+
+async def compression_middleware(request: Request, call_next):
+    response = await call_next(request)
+    if not _compressible(response):
+        response.headers.setdefault("Content-Encoding", IDENTITY)
+        return response
+
+    body = b"".join([chunk async for chunk in response.body_iterator])
+    body, encoding = compress_if_accepted(body, request.headers.get("accept-encoding", ""))
+    compressed = Response(content=body, status_code=response.status_code, background=response.background)
+    # raw headers keep repeated ones such as Set-Cookie
+    compressed.raw_headers = [(k, v) for k, v in response.raw_headers if k not in (b"content-length", b"vary")]
+    vary = response.headers.get("vary")
+    compressed.headers["Content-Length"] = str(len(body))
+    compressed.headers["Content-Encoding"] = encoding
+    compressed.headers["Vary"] = f"{vary}, Accept-Encoding" if vary else "Accept-Encoding"
+    return compressed
@@ -0,0 +1,82 @@
+"""
+Retry helper for transient AWS failures
+
+DynamoDB throttling and service hiccups are retried with exponential
+backoff; errors that will not change on retry (validation, conditional
+check failures) are raised immediately.
+
+Key Features:
+- is_retryable() classifies botocore errors by error code (or message),
+  HTTP 5xx status, and connection or read timeouts
+- with_retry() uses exponential backoff with full jitter
+- Used for DynamoDB writes (db) and S3 uploads/deletes (storage)
+"""
+
+import time
+import random
+from typing import Callable, TypeVar
+
+from botocore.exceptions import (
+    ClientError, ConnectionError as BotoConnectionError, ReadTimeoutError, ConnectTimeoutError
+)
+
+T = TypeVar("T")
+
+DEFAULT_MAX_ATTEMPTS = 3
+DEFAULT_BASE_DELAY_MS = 100
+MAX_DELAY_MS = 5000
+
+RETRYABLE_ERROR_CODES = {
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "Throttling",
+    "RequestLimitExceeded",
+    "InternalServerError",
+    "ServiceUnavailable",
+    "TransactionConflictException",
+    "SlowDown",
+    "InternalError",
+    "RequestTimeout",
+    "RequestTimeoutException",
+    "ServiceUnavailableException",
+}
+
+
+def is_retryable(error: BaseException) -> bool:
+    """Whether an error is transient and worth retrying."""
+    if isinstance(error, (BotoConnectionError, ReadTimeoutError, ConnectTimeoutError)):
+        return True
+    if isinstance(error, ClientError):
+        status = error.response.get("ResponseMetadata", {}).get("HTTPStatusCode") or 0
+        return error.response.get("Error", {}).get("Code") in RETRYABLE_ERROR_CODES or status >= 500
+    message = str(error)
+    return any(code in message for code in RETRYABLE_ERROR_CODES)
+
+
+def backoff_delay_ms(attempt: int, base_delay_ms: int = DEFAULT_BASE_DELAY_MS) -> float:
+    """Full jitter: uniform in [0, min(cap, base * 2^attempt)] for attempt 0, 1, ..."""
+    return random.uniform(0, min(MAX_DELAY_MS, base_delay_ms * (2 ** attempt)))
+
+
+def with_retry(
+    fn: Callable[[], T],
+    max_attempts: int = DEFAULT_MAX_ATTEMPTS,
+    base_delay_ms: int = DEFAULT_BASE_DELAY_MS,
+    sleep: Callable[[float], None] = time.sleep
+) -> T:
+    """
+    Call fn, retrying transient failures up to max_attempts calls in total.
+
+    Raises:
+        The last error if every attempt failed, or the first non-retryable error
+    """
+    for attempt in range(max_attempts):
+        try:
+            return fn()
+        except Exception as e:
+            if attempt == max_attempts - 1 or not is_retryable(e):
+                raise
+            delay = backoff_delay_ms(attempt, base_delay_ms)
+            print(f"[retry] Transient error ({e}), attempt {attempt + 1}/{max_attempts}, retrying in {delay:.0f}ms")
+            sleep(delay / 1000)
+    raise RuntimeError("with_retry requires max_attempts >= 1")
@@ -0,0 +1,124 @@
+"""
+Input sanitization helpers
+
+Encoding instead of stripping, so legitimate data such as O'Brien, José,
+µg or ±2 survives.
+
+Key Features:
+- normalize_text(): NFC normalization; removes control and invisible
+  formatting characters (e.g. bidi overrides) while keeping every letter,
+  combining mark, digit and symbol of any script
+- sanitize_input(): normalize_text plus escaping for the output context
+  ("html" entity encoding, or "text" for plain-text sinks), with optional
+  length enforcement
+- sanitize_html(): drops markup (tags, script/style content), keeps encoded text
+- sanitize_identifier(): strict mode for ids and key segments (ASCII letters,
+  digits, _ and -), rejects anything else instead of rewriting it
+- sanitize_filename(): neutralizes path separators and null bytes
+- sanitize_table_name(): validates DynamoDB table names
+
+Values are stored as entered (the Flutter client renders plain text) and
+encoded where they are embedded in HTML, e.g. email templates.
+"""
+
+import re
+import unicodedata
+from typing import Optional
+
+_HTML_ENTITIES = {
+    "&": "&amp;",
+    "<": "&lt;",
+    ">": "&gt;",
+    '"': "&quot;",
+    "'": "&#x27;",
+}
+_HTML_SPECIAL = re.compile(r"[&<>\"']")
+_SCRIPT_OR_STYLE = re.compile(r"<(script|style)\b[^>]*>.*?</\1\s*>", re.IGNORECASE | re.DOTALL)
+_TAG = re.compile(r"<[^>]*>")
+_PATH_CHARS = re.compile(r"[/\\\x00]")
+_TABLE_NAME = re.compile(r"^[A-Za-z0-9_.\-]{3,255}$")
+_IDENTIFIER = re.compile(r"^[A-Za-z0-9_\-]+$")
+
+# Whitespace controls that belong in multi-line text
+_ALLOWED_CONTROLS = {"\t", "\n", "\r"}
+# Format characters (Cf) that scripts need: zero-width (non-)joiner for
+# Persian/Indic text and emoji sequences
+_ALLOWED_FORMAT = {"\u200c", "\u200d"}
+
+OUTPUT_CONTEXTS = ("html", "text")
+
+
+class InputTooLongError(ValueError):
+    """Raised when an input exceeds its maximum length."""
+
+
+def normalize_text(value: str) -> str:
+    """
+    NFC-normalize a value and drop control (Cc) and invisible format (Cf)
+    characters such as NUL, bidi overrides or zero-width spaces, which can hide
+    or reorder text. Letters, combining marks, digits and symbols are kept.
+    """
+    value = unicodedata.normalize("NFC", value)
+    return "".join(
+        ch for ch in value
+        if ch in _ALLOWED_CONTROLS or ch in _ALLOWED_FORMAT or unicodedata.category(ch) not in ("Cc", "Cf")
+    )
+
+
+def sanitize_input(value: str, max_length: Optional[int] = None, context: str = "html") -> str:
+    """
+    Normalize a value (normalize_text) and escape it for where it is output:
+    "html" encodes & < > " ' as entities (safe in element content and quoted
+    attributes); "text" leaves characters as they are, for plain-text sinks
+    such as email text parts or stored values.
+
+    Raises:
+        InputTooLongError: if the normalized value is longer than max_length characters
+        ValueError: for an unknown context
+    """
+    if context not in OUTPUT_CONTEXTS:
+        raise ValueError(f"Unknown output context {context!r}; expected one of {', '.join(OUTPUT_CONTEXTS)}")
+    value = normalize_text(value)
+    if max_length is not None and len(value) > max_length:
+        raise InputTooLongError(f"Input too long (max {max_length} characters)")
+    if context == "text":
+        return value
+    return _HTML_SPECIAL.sub(lambda m: _HTML_ENTITIES[m.group(0)], value)
+
+
+def sanitize_html(value: str) -> str:
+    """Remove markup from a value and encode what remains."""
+    text = _TAG.sub("", _SCRIPT_OR_STYLE.sub("", value))
+    return sanitize_input(text)
+
+
+def sanitize_identifier(value: str, max_length: int = 128) -> str:
+    """
+    Validate an identifier (e.g. a user id used as an S3 key segment): 1 to
+    max_length ASCII letters, digits, _ or -.
+
+    Raises:
+        ValueError: if the value is not a valid identifier
+    """
+    if not isinstance(value, str) or len(value) > max_length or not _IDENTIFIER.match(value):
+        raise ValueError(f"Invalid identifier: {value!r}")
+    return value
+
+
+def sanitize_filename(value: str) -> str:
+    """Replace path separators and null bytes so a filename stays a single path segment."""
+    safe = _PATH_CHARS.sub("_", value).strip()
+    # "." and ".." are valid segments in S3 consoles and local downloads; avoid them
+    return "_" if safe.strip(".") == "" else safe
+
+
+def sanitize_table_name(value: str) -> str:
+    """
+    Validate a DynamoDB table name (3-255 of A-Z a-z 0-9 _ . -).
+
+    Raises:
+        ValueError: if the name is not a valid table name
+    """
+    if not isinstance(value, str) or not _TABLE_NAME.match(value):
+        raise ValueError(f"Invalid DynamoDB table name: {value!r}")
+    return value
@@ -0,0 +1,58 @@
+"""
+MeDUSA Security Headers
+
+Hardening headers added to every API response, including error responses.
+
+Key Features:
+- Strict-Transport-Security, X-Content-Type-Options, X-Frame-Options,
+  Content-Security-Policy and Referrer-Policy
+- SECURITY_HEADERS_ENABLED=false turns them off for local development
+  over plain HTTP
+- HSTS_MAX_AGE_SECONDS, CONTENT_SECURITY_POLICY and REFERRER_POLICY
+  override the defaults
+- security_headers_middleware for the FastAPI app; add_security_headers for
+  Lambda handlers that build API Gateway proxy responses themselves
+  (device_data_ingest)
+- Headers a handler already set are left alone
+"""
+
+import os
+from typing import Any, Dict
+
+from query_params import TRUE_VALUES
+
+SECURITY_HEADERS_ENABLED = os.environ.get("SECURITY_HEADERS_ENABLED", "true").strip().lower() in TRUE_VALUES
+HSTS_MAX_AGE_SECONDS = int(os.environ.get("HSTS_MAX_AGE_SECONDS", str(365 * 24 * 60 * 60)))
+# The API only serves JSON and files: nothing may be loaded, framed or submitted from its responses
+CONTENT_SECURITY_POLICY = os.environ.get(
+    "CONTENT_SECURITY_POLICY", "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'"
+)
+REFERRER_POLICY = os.environ.get("REFERRER_POLICY", "no-referrer")
+
+
+def security_headers(enabled: bool = None) -> Dict[str, str]:
+    """Security response headers, or {} when disabled (enabled defaults to SECURITY_HEADERS_ENABLED)."""
+    if not (SECURITY_HEADERS_ENABLED if enabled is None else enabled):
+        return {}
+    return {
+        "Strict-Transport-Security": f"max-age={HSTS_MAX_AGE_SECONDS}; includeSubDomains",
+        "X-Content-Type-Options": "nosniff",
+        "X-Frame-Options": "DENY",
+        "Content-Security-Policy": CONTENT_SECURITY_POLICY,
+        "Referrer-Policy": REFERRER_POLICY,
+    }
+
+
+def add_security_headers(response: Dict[str, Any], enabled: bool = None) -> Dict[str, Any]:
+    """Return an API Gateway proxy response with the security headers added."""
+    headers = response.get("headers") or {}
+    return {**response, "headers": {**security_headers(enabled), **headers}}
+
+
+async def security_headers_middleware(request, call_next):
+    """Runs outside auth and the body size limit, so their error responses are covered too."""
+    response = await call_next(request)
+    for name, value in security_headers().items():
+        if name not in response.headers:
+            response.headers[name] = value
+    return response
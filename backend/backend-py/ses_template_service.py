@@ -0,0 +1,236 @@
+"""
+MeDUSA SES Template Service
+
+Transactional emails as SES templates instead of inline HTML strings.
+
+Key Features:
+- Templates defined as dataclasses whose fields map to {{variable}} placeholders
+- Idempotent registration (create, or update if the template already exists)
+- render_and_send() uses SendTemplatedEmail, or logs the rendered email in dev mode
+- Run as a deploy step: python ses_template_service.py
+"""
+
+import os
+import re
+import json
+from dataclasses import dataclass, asdict
+from typing import Dict, List, Type
+
+from botocore.exceptions import ClientError
+
+import aws_clients
+from sanitize import sanitize_input
+
+SES_TEMPLATE_PREFIX = os.environ.get("SES_TEMPLATE_PREFIX", "medusa")
+
+_PLACEHOLDER = re.compile(r"\{\{\s*(\w+)\s*\}\}")
+
+
+def _layout(color: str, heading: str, body: str) -> str:
+    """Shared HTML frame matching the existing MeDUSA emails."""
+    return f"""<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <style>
+        body {{ font-family: Arial, sans-serif; line-height: 1.6; color: #333; }}
+        .container {{ max-width: 600px; margin: 0 auto; padding: 20px; }}
+        .header {{ background: {color}; color: white; padding: 20px; text-align: center; }}
+        .content {{ background: #f8f9fa; padding: 30px; border-radius: 5px; }}
+        .button {{ display: inline-block; background: {color}; color: white; padding: 12px 24px;
+                   border-radius: 5px; text-decoration: none; }}
+        .footer {{ text-align: center; margin-top: 20px; color: #666; font-size: 12px; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>MeDUSA Health System</h1>
+            <p>{heading}</p>
+        </div>
+        <div class="content">
+{body}
+        </div>
+        <div class="footer">
+            <p>&copy; 2025 MeDUSA Health System. All rights reserved.</p>
+            <p>This is an automated message, please do not reply.</p>
+        </div>
+    </div>
+</body>
+</html>"""
+
+
+class EmailTemplate:
+    """
+    Base for template dataclasses.
+
+    Subclasses set NAME, SUBJECT, HTML and TEXT using {{field}} placeholders
+    for each of their dataclass fields.
+    """
+    NAME: str = ""
+    SUBJECT: str = ""
+    HTML: str = ""
+    TEXT: str = ""
+
+    @classmethod
+    def template_name(cls) -> str:
+        return f"{SES_TEMPLATE_PREFIX}-{cls.NAME}"
+
+    @classmethod
+    def definition(cls) -> Dict[str, str]:
+        """SES Template structure for CreateTemplate / UpdateTemplate."""
+        return {
+            "TemplateName": cls.template_name(),
+            "SubjectPart": cls.SUBJECT,
+            "HtmlPart": cls.HTML,
+            "TextPart": cls.TEXT,
+        }
+
+    def template_data(self) -> Dict[str, str]:
+        return {key: str(value) for key, value in asdict(self).items()}
+
+    def render(self) -> Dict[str, str]:
+        """Expand placeholders locally (dev mode and tests); values are escaped for each part's context."""
+        data = self.template_data()
+
+        def expand(text: str, context: str) -> str:
+            return _PLACEHOLDER.sub(lambda m: sanitize_input(data.get(m.group(1), ""), context=context), text)
+
+        return {
+            "subject": expand(self.SUBJECT, "text"),
+            "html": expand(self.HTML, "html"),
+            "text": expand(self.TEXT, "text"),
+        }
+
+
+@dataclass
+class PasswordResetTemplate(EmailTemplate):
+    reset_url: str
+    user_name: str
+    expires_in_minutes: int
+
+    NAME = "password-reset"
+    SUBJECT = "Reset your MeDUSA password"
+    HTML = _layout("#D32F2F", "Password Reset Request", """
+            <h2>Hello {{user_name}},</h2>
+            <p>We received a request to reset your password.</p>
+            <p><a class="button" href="{{reset_url}}">Reset password</a></p>
+            <p>This link will expire in {{expires_in_minutes}} minutes.</p>
+            <p>If you didn't request a password reset, please ignore this email.</p>""")
+    TEXT = ("Hello {{user_name}}, reset your MeDUSA password at {{reset_url}}. "
+            "This link will expire in {{expires_in_minutes}} minutes.")
+
+
+@dataclass
+class EmailVerificationTemplate(EmailTemplate):
+    verification_url: str
+    user_name: str
+
+    NAME = "email-verification"
+    SUBJECT = "Verify your MeDUSA email address"
+    HTML = _layout("#1976D2", "Email Verification", """
+            <h2>Hello {{user_name}},</h2>
+            <p>Thank you for registering with MeDUSA Health System.</p>
+            <p><a class="button" href="{{verification_url}}">Verify email address</a></p>
+            <p>If you didn't create an account, please ignore this email.</p>""")
+    TEXT = "Hello {{user_name}}, verify your MeDUSA email address at {{verification_url}}."
+
+
+@dataclass
+class CriticalAlertTemplate(EmailTemplate):
+    patient_name: str
+    reading_type: str
+    value: str
+    threshold: str
+
+    NAME = "critical-alert"
+    SUBJECT = "Critical alert: {{patient_name}} ({{reading_type}})"
+    HTML = _layout("#B71C1C", "Critical Reading Alert", """
+            <h2>Critical {{reading_type}} reading</h2>
+            <p><strong>Patient:</strong> {{patient_name}}</p>
+            <p><strong>Value:</strong> {{value}} (threshold {{threshold}})</p>
+            <p>Please review the patient's record in MeDUSA.</p>""")
+    TEXT = "Critical {{reading_type}} reading for {{patient_name}}: {{value}} (threshold {{threshold}})."
+
+
+ALL_TEMPLATES: List[Type[EmailTemplate]] = [
+    PasswordResetTemplate,
+    EmailVerificationTemplate,
+    CriticalAlertTemplate,
+]
+
+
+class SesTemplateService:
+    """Registers templates with SES and sends templated emails."""
+
+    SENDER_EMAIL = os.environ.get("SENDER_EMAIL", "noreply@medusa-health.com")
+    SENDER_NAME = "MeDUSA Health System"
+
+    def __init__(self):
+        self.use_ses = os.environ.get("USE_SES", "false").lower() == "true"
+        self._ses = None
+
+    def _client(self):
+        if self._ses is None:
+            region = os.environ.get("SES_REGION") or os.environ.get("AWS_REGION", "us-east-1")
+            self._ses = aws_clients.get_client("ses", region_name=region)
+        return self._ses
+
+    def register_templates(self) -> Dict[str, str]:
+        """
+        Create or update every template in ALL_TEMPLATES.
+
+        Returns:
+            {template_name: "created" | "updated"}
+        """
+        results = {}
+        for template in ALL_TEMPLATES:
+            definition = template.definition()
+            try:
+                self._client().create_template(Template=definition)
+                results[definition["TemplateName"]] = "created"
+            except ClientError as e:
+                if e.response["Error"]["Code"] != "AlreadyExists":
+                    raise
+                self._client().update_template(Template=definition)
+                results[definition["TemplateName"]] = "updated"
+        return results
+
+    def render_and_send(self, to: str, template: EmailTemplate) -> bool:
+        """
+        Send a templated email.
+
+        Returns:
+            True if sent (or logged in dev mode), False on SES failure
+        """
+        if not self.use_ses:
+            rendered = template.render()
+            print("\n" + "=" * 60)
+            print("[SesTemplateService] EMAIL (Development Mode - Not Actually Sent)")
+            print(f"To: {to}")
+            print(f"Subject: {rendered['subject']}")
+            print(rendered["text"])
+            print("=" * 60 + "\n")
+            return True
+
+        try:
+            response = self._client().send_templated_email(
+                Source=f"{self.SENDER_NAME} <{self.SENDER_EMAIL}>",
+                Destination={"ToAddresses": [to]},
+                Template=template.template_name(),
+                TemplateData=json.dumps(template.template_data()),
+            )
+            print(f"[SesTemplateService] Sent {template.template_name()} to {to}: {response['MessageId']}")
+            return True
+        except Exception as e:
+            print(f"[SesTemplateService] ERROR: Failed to send {template.template_name()} to {to}: {e}")
+            return False
+
+
+# Global SES template service instance
+ses_template_service = SesTemplateService()
+
+
+if __name__ == "__main__":
+    for name, status in ses_template_service.register_templates().items():
+        print(f"[SesTemplateService] {status}: {name}")
@@ -0,0 +1,138 @@
+"""
+MeDUSA Admin Statistics Service
+
+Aggregate platform metrics for the admin dashboard.
+
+Key Features:
+- Active users by role, active devices by type, reports by status
+- Reading volume over the last 24 hours / 7 days / 30 days
+- Failed logins over the last 24 hours and S3 storage usage
+- Scoped to the caller's organization: readings of its patients and
+  objects under its users' and devices' key prefixes
+- Results cached for five minutes under admin:stats:<organization>
+"""
+
+import time
+from datetime import datetime, timezone
+from typing import Any, Dict, Iterable, List, Optional, Set
+
+import db
+import storage
+from audit_service import AuditEventType
+from cache_service import cache_service
+from request_context import current_organization_id
+
+STATS_CACHE_KEY = "admin:stats"
+STATS_CACHE_TTL_SECONDS = 300
+
+ACTIVE_DEVICE_STATUSES = {"online", "active"}
+
+DAY_SECONDS = 86400
+READING_WINDOWS = {"24h": DAY_SECONDS, "7d": 7 * DAY_SECONDS, "30d": 30 * DAY_SECONDS}
+
+
+def compute_stats(
+    users: List[Dict[str, Any]],
+    devices: List[Dict[str, Any]],
+    reading_timestamps: List[int],
+    failed_logins_24h: int,
+    reports: List[Dict[str, Any]],
+    object_sizes: Iterable[int],
+    now: Optional[int] = None
+) -> Dict[str, Any]:
+    """
+    Reduce raw records to a flat statistics object.
+
+    Keys are dotted where they carry a dimension (e.g. activeUsers.doctor)
+    so the response stays a single flat JSON object.
+    """
+    now = now if now is not None else int(time.time())
+    stats: Dict[str, Any] = {}
+
+    active_users = [u for u in users if u.get("isActive", True)]
+    stats["activeUsers"] = len(active_users)
+    for user in active_users:
+        key = f"activeUsers.{user.get('role', 'unknown')}"
+        stats[key] = stats.get(key, 0) + 1
+    stats["totalPatients"] = len([u for u in users if u.get("role") == "patient"])
+
+    active_devices = [d for d in devices if d.get("status") in ACTIVE_DEVICE_STATUSES]
+    stats["totalDevices"] = len(devices)
+    stats["activeDevices"] = len(active_devices)
+    for device in active_devices:
+        key = f"activeDevices.{device.get('type', 'unknown')}"
+        stats[key] = stats.get(key, 0) + 1
+
+    for label, window in READING_WINDOWS.items():
+        stats[f"readings.{label}"] = len([ts for ts in reading_timestamps if ts >= now - window])
+
+    stats["failedLogins.24h"] = failed_logins_24h
+
+    stats["totalReports"] = len(reports)
+    for report in reports:
+        key = f"reports.{report.get('status', 'unknown')}"
+        stats[key] = stats.get(key, 0) + 1
+
+    stats["storageBytes"] = sum(object_sizes)
+    stats["generatedAt"] = datetime.fromtimestamp(now, timezone.utc).isoformat()
+    return stats
+
+
+class StatsService:
+    """Gathers inputs for compute_stats and caches the result."""
+
+    def _failed_logins_since(self, start_time: int) -> int:
+        start_iso = datetime.fromtimestamp(start_time, timezone.utc).isoformat()
+        count, token = 0, None
+        while True:
+            page = db.get_audit_logs(
+                event_type=AuditEventType.AUTH_LOGIN_FAILURE.value,
+                start_time=start_iso,
+                limit=1000,
+                next_token=token
+            )
+            count += len(page.items)
+            token = page.next_cursor
+            if not token:
+                return count
+
+    def _object_sizes(self, owner_ids: Optional[Set[str]] = None) -> Iterable[int]:
+        """Sizes of all objects, or (owner_ids given) of those with an owner's id as a key segment"""
+        try:
+            return [int(obj.get("Size", 0)) for obj in storage.list_objects("")
+                    if owner_ids is None or not owner_ids.isdisjoint(obj["Key"].split("/"))]
+        except Exception as e:
+            print(f"[StatsService] Unable to list S3 objects: {e}")
+            return []
+
+    def get_stats(self, use_cache: bool = True) -> Dict[str, Any]:
+        organization_id = current_organization_id()
+        cache_key = f"{STATS_CACHE_KEY}:{organization_id or 'all'}"
+        if use_cache:
+            cached = cache_service.get(cache_key)
+            if cached is not None:
+                return cached
+
+        now = int(time.time())
+        users = db.get_all_users()
+        devices = db.get_all_devices()
+        # Readings and S3 objects carry no organizationId: attribute them through their owners
+        patient_ids = owner_ids = None
+        if organization_id is not None:
+            patient_ids = {u["id"] for u in users if u.get("role") == "patient"}
+            owner_ids = {u["id"] for u in users} | {d["id"] for d in devices}
+        stats = compute_stats(
+            users=users,
+            devices=devices,
+            reading_timestamps=db.get_reading_timestamps_since(now - READING_WINDOWS["30d"], patient_ids),
+            failed_logins_24h=self._failed_logins_since(now - DAY_SECONDS),
+            reports=db.get_all_reports(),
+            object_sizes=self._object_sizes(owner_ids),
+            now=now
+        )
+        cache_service.set(cache_key, stats, STATS_CACHE_TTL_SECONDS)
+        return stats
+
+
+# Global stats service instance
+stats_service = StatsService()
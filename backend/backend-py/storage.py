@@ -1,8 +1,28 @@
-import os, boto3, time
-s3 = boto3.client("s3")
+import os, re, time, json, uuid, hashlib
+from dataclasses import dataclass
+from typing import Dict, Optional
+from botocore.exceptions import ClientError
+from sanitize import sanitize_filename
+import encryption_service
+from retry import with_retry
+import aws_clients
+s3 = aws_clients.get_client("s3")
 
 PPOSES = os.environ.get("S3_PREFIX_POSES","poses/")
 PREPORT= os.environ.get("S3_PREFIX_REPORTS","reports/")
+PEXPORT= os.environ.get("S3_PREFIX_EXPORTS","exports/")
+PIMPORT= os.environ.get("S3_PREFIX_IMPORTS","imports/")
+PPROFILE= os.environ.get("S3_PREFIX_PROFILE_PICTURES","profile-pictures/")
+PBACKUP= os.environ.get("S3_PREFIX_BACKUPS","backups/")
+PDEVICE_DATA= os.environ.get("S3_PREFIX_DEVICE_DATA","device-data/")
+PAUDIT_EXPORT= os.environ.get("S3_PREFIX_AUDIT_EXPORTS","audit-exports/")
+
+MAX_PROFILE_PICTURE_BYTES = int(os.environ.get("MAX_PROFILE_PICTURE_BYTES", str(2 * 1024 * 1024)))
+# content type -> (file extension, leading magic bytes)
+PROFILE_PICTURE_TYPES = {
+    "image/jpeg": ("jpg", b"\xff\xd8\xff"),
+    "image/png": ("png", b"\x89PNG\r\n\x1a\n"),
+}
 
 def _bucket() -> str:
     bucket = os.environ.get("S3_BUCKET")
@@ -10,21 +30,299 @@ def _bucket() -> str:
         raise RuntimeError("S3_BUCKET env var must be set for storage access")
     return bucket
 
+@dataclass(frozen=True)
+class ResidencyTarget:
+    """Bucket (and its region / KMS key) holding the files of patients with one data residency"""
+    bucket: str
+    region: Optional[str] = None  # None: the Lambda's own region
+    kms_key_arn: Optional[str] = None  # KMS keys are regional; None uses KMS_KEY_ARN
+
+class DataResidencyError(ValueError):
+    """Raised for a data residency with no configured bucket."""
+
+def _load_residency_targets(raw: str) -> Dict[str, ResidencyTarget]:
+    """
+    Parse DATA_RESIDENCY_TARGETS, e.g.
+    {"eu": {"bucket": "medusa-data-eu", "region": "eu-central-1", "kmsKeyArn": "arn:aws:kms:eu-central-1:..."}}
+    """
+    targets = {}
+    for residency, config in (json.loads(raw) if raw.strip() else {}).items():
+        if not isinstance(config, dict) or not config.get("bucket"):
+            raise ValueError(f"DATA_RESIDENCY_TARGETS[{residency!r}] needs a bucket")
+        targets[residency.strip().lower()] = ResidencyTarget(
+            bucket=config["bucket"], region=config.get("region"), kms_key_arn=config.get("kmsKeyArn")
+        )
+    return targets
+
+# Patients without a data residency (or with one not listed) use S3_BUCKET
+DATA_RESIDENCY_TARGETS = _load_residency_targets(os.environ.get("DATA_RESIDENCY_TARGETS", ""))
+
+def residency_target(residency: Optional[str]) -> Optional[ResidencyTarget]:
+    """
+    The configured target for a residency; None means the default bucket.
+
+    Raises:
+        DataResidencyError: if residency is set but has no target, so the data
+                            is never silently written to the default region
+    """
+    if not residency:
+        return None
+    target = DATA_RESIDENCY_TARGETS.get(residency.strip().lower())
+    if target is None:
+        raise DataResidencyError(f"No storage configured for data residency {residency!r}")
+    return target
+
+def _target(residency: Optional[str] = None):
+    """(client, bucket, KMS key) for an operation on data with this residency"""
+    target = residency_target(residency)
+    if target is None:
+        return s3, _bucket(), None
+    client = aws_clients.get_client("s3", region_name=target.region) if target.region else s3
+    return client, target.bucket, target.kms_key_arn
+
+# User metadata (x-amz-meta-sha256) with the hex SHA-256 of an object written by upload_bytes
+META_SHA256 = "sha256"
+
+class StorageIntegrityError(RuntimeError):
+    """Raised when downloaded content does not match the object's checksum or the requested range."""
+
+@dataclass(frozen=True)
+class ByteRange:
+    """Bytes start..end of an object, both inclusive; end None reads to the end of the object"""
+    start: int
+    end: Optional[int] = None
+
+    def __post_init__(self):
+        if self.start < 0:
+            raise ValueError("range start must be >= 0")
+        if self.end is not None and self.end < self.start:
+            raise ValueError("range end must be >= start")
+
+    def header(self) -> str:
+        """Value of the HTTP Range header"""
+        return f"bytes={self.start}-{'' if self.end is None else self.end}"
+
+_CONTENT_RANGE = re.compile(r"^bytes (\d+)-(\d+)/(\d+|\*)$")
+
+def _verify_range(key: str, obj: Dict, body: bytes, byte_range: ByteRange) -> None:
+    """The Content-Range S3 answered with must start at the requested byte and match the body length"""
+    match = _CONTENT_RANGE.match(obj.get("ContentRange") or "")
+    if not match:
+        raise StorageIntegrityError(f"{key}: response has no valid Content-Range for {byte_range.header()}")
+    start, end = int(match.group(1)), int(match.group(2))
+    if start != byte_range.start or (byte_range.end is not None and end > byte_range.end) or len(body) != end - start + 1:
+        raise StorageIntegrityError(f"{key}: got bytes {start}-{end} ({len(body)} read) for {byte_range.header()}")
+
+def verify_checksum(key: str, obj: Dict, body: bytes) -> None:
+    """
+    Compare a full download with the SHA-256 stored at upload, or else with a
+    plain MD5 ETag (single-part, not KMS-encrypted). Objects with neither
+    (e.g. uploaded through a presigned POST) are not checked.
+
+    Raises:
+        StorageIntegrityError: on a mismatch
+    """
+    expected = (obj.get("Metadata") or {}).get(META_SHA256)
+    if expected:
+        if hashlib.sha256(body).hexdigest() != expected:
+            raise StorageIntegrityError(f"{key}: SHA-256 does not match the stored checksum")
+        return
+    etag = (obj.get("ETag") or "").strip('"')
+    if re.fullmatch(r"[0-9a-f]{32}", etag) and obj.get("ServerSideEncryption") in (None, "AES256"):
+        if hashlib.md5(body).hexdigest() != etag:
+            raise StorageIntegrityError(f"{key}: MD5 does not match the ETag")
+
 def make_file_key(scope: str, owner: str, filename: str) -> str:
-    base = PPOSES if scope=="pose" else PREPORT
+    base = {"pose": PPOSES, "import": PIMPORT}.get(scope, PREPORT)
     ts = int(time.time())
-    # Basic sanitize (Lambda behind WAF; clients still can pass unicode filenames)
-    safe = filename.replace("/", "_")
+    # Lambda is behind WAF, but clients still can pass separators / null bytes in filenames
+    safe = sanitize_filename(filename)
     return f"{base}{owner}/{ts}_{safe}"
 
-def presign_upload(key: str, content_type: str, ttl_sec:int=900):
-    fields = {"Content-Type": content_type}
-    conditions = [["eq","$Content-Type", content_type]]
-    return s3.generate_presigned_post(
-        Bucket=_bucket(), Key=key, Fields=fields, Conditions=conditions, ExpiresIn=ttl_sec
+def key_owner(key: str) -> Optional[str]:
+    """Owner segment of a key made by make_file_key / upload_profile_picture, or None"""
+    for prefix in (PPOSES, PREPORT, PIMPORT, PPROFILE):
+        if key.startswith(prefix):
+            owner = key[len(prefix):].split("/", 1)
+            return owner[0] if len(owner) == 2 and owner[0] else None
+    return None
+
+def presign_upload(key: str, content_type: str, ttl_sec:int=900, residency: Optional[str]=None):
+    client, bucket, key_arn = _target(residency)
+    sse_fields, sse_conditions = encryption_service.presign_sse_fields(key_arn=key_arn)
+    fields = {"Content-Type": content_type, **sse_fields}
+    conditions = [["eq","$Content-Type", content_type], *sse_conditions]
+    return client.generate_presigned_post(
+        Bucket=bucket, Key=key, Fields=fields, Conditions=conditions, ExpiresIn=ttl_sec
     )
 
-def presign_download(key: str, ttl_sec:int=900):
-    return s3.generate_presigned_url(
-        "get_object", Params={"Bucket": _bucket(), "Key": key}, ExpiresIn=ttl_sec
+def presign_download(key: str, ttl_sec:int=900, residency: Optional[str]=None):
+    client, bucket, _ = _target(residency)
+    return client.generate_presigned_url(
+        "get_object", Params={"Bucket": bucket, "Key": key}, ExpiresIn=ttl_sec
     )
+
+def make_export_key(owner: str, extension: str) -> str:
+    ts = int(time.time())
+    return f"{PEXPORT}{owner}/{ts}_export.{extension}"
+
+def make_device_data_key(device_id: str, reading_type: str, timestamp: int) -> str:
+    """Key of a reading's raw payload (e.g. ECG waveform samples)"""
+    return f"{PDEVICE_DATA}{device_id}/{reading_type}/{int(timestamp)}_{uuid.uuid4().hex[:8]}.json"
+
+def is_device_data_key(key: str, device_id: str) -> bool:
+    """True if key was made by make_device_data_key for this device"""
+    return isinstance(key, str) and key.startswith(f"{PDEVICE_DATA}{device_id}/") and ".." not in key
+
+def upload_device_data(device_id: str, reading_type: str, timestamp: int, payload: Dict,
+                       residency: Optional[str]=None) -> str:
+    """Store a reading's raw payload as JSON; returns the key to keep on the reading"""
+    key = make_device_data_key(device_id, reading_type, timestamp)
+    upload_bytes(key, json.dumps(payload, separators=(",", ":")).encode("utf-8"), "application/json", residency)
+    return key
+
+def download_device_data(key: str, residency: Optional[str]=None) -> Dict:
+    """Read back a payload stored by upload_device_data"""
+    return json.loads(download_bytes(key, residency))
+
+def upload_bytes(key: str, body: bytes, content_type: str, residency: Optional[str]=None):
+    """Store body with its SHA-256 as metadata, checked by download_bytes"""
+    client, bucket, key_arn = _target(residency)
+    with_retry(lambda: client.put_object(
+        Bucket=bucket, Key=key, Body=body, ContentType=content_type,
+        Metadata={META_SHA256: hashlib.sha256(body).hexdigest()},
+        **encryption_service.sse_params(key_arn=key_arn)
+    ))
+
+def upload_sensitive(key: str, body: bytes, content_type: str):
+    """
+    Upload with SENSITIVE_ENCRYPTION_MODE (e.g. backups); in envelope mode the
+    body is encrypted client-side and only download_sensitive can read it.
+    """
+    mode = encryption_service.SENSITIVE_ENCRYPTION_MODE
+    metadata = {}
+    if mode == encryption_service.MODE_ENVELOPE:
+        body, metadata = encryption_service.envelope_encrypt(body, key)
+    with_retry(lambda: s3.put_object(
+        Bucket=_bucket(), Key=key, Body=body, ContentType=content_type, Metadata=metadata,
+        **encryption_service.sse_params(mode)
+    ))
+
+def download_sensitive(key: str) -> bytes:
+    """Read an object written by upload_sensitive, decrypting envelope-encrypted bodies"""
+    obj = s3.get_object(Bucket=_bucket(), Key=key)
+    body, metadata = obj["Body"].read(), obj.get("Metadata") or {}
+    if encryption_service.META_WRAPPED_KEY in metadata:
+        return encryption_service.envelope_decrypt(body, metadata, key)
+    return body
+
+MIN_PART_SIZE = 5 * 1024 * 1024  # S3 minimum for every part but the last
+
+def multipart_upload(key: str, chunks, content_type: str) -> int:
+    """
+    Upload an iterable of byte chunks as one object without holding it in
+    memory: chunks are buffered into >= 5 MB parts and sent as they fill.
+    The upload is aborted if the iterable or S3 fails. Returns the object size.
+    """
+    upload_id = with_retry(lambda: s3.create_multipart_upload(
+        Bucket=_bucket(), Key=key, ContentType=content_type, **encryption_service.sse_params()
+    ))["UploadId"]
+    parts, buffer, size = [], bytearray(), 0
+
+    def _send(body: bytes):
+        number = len(parts) + 1
+        etag = with_retry(lambda: s3.upload_part(
+            Bucket=_bucket(), Key=key, UploadId=upload_id, PartNumber=number, Body=body
+        ))["ETag"]
+        parts.append({"ETag": etag, "PartNumber": number})
+
+    try:
+        for chunk in chunks:
+            buffer.extend(chunk)
+            size += len(chunk)
+            if len(buffer) >= MIN_PART_SIZE:
+                _send(bytes(buffer))
+                buffer.clear()
+        if buffer or not parts:
+            _send(bytes(buffer))
+        with_retry(lambda: s3.complete_multipart_upload(
+            Bucket=_bucket(), Key=key, UploadId=upload_id, MultipartUpload={"Parts": parts}
+        ))
+    except Exception:
+        s3.abort_multipart_upload(Bucket=_bucket(), Key=key, UploadId=upload_id)
+        raise
+    return size
+
+def upload_profile_picture(user_id: str, content: bytes, content_type: str, residency: Optional[str]=None) -> str:
+    """
+    Validate and store a user's avatar at profile-pictures/<user_id>/avatar.<ext>.
+    The object stays private (the bucket blocks public ACLs); serve it with
+    presign_download. Returns the object key.
+
+    Raises:
+        ValueError: if the type is not JPEG/PNG, the content does not match it,
+                    or it is empty / larger than MAX_PROFILE_PICTURE_BYTES
+    """
+    if content_type not in PROFILE_PICTURE_TYPES:
+        raise ValueError("content type must be image/jpeg or image/png")
+    if not content or len(content) > MAX_PROFILE_PICTURE_BYTES:
+        raise ValueError(f"picture must be 1-{MAX_PROFILE_PICTURE_BYTES} bytes")
+    extension, magic = PROFILE_PICTURE_TYPES[content_type]
+    if not content.startswith(magic):
+        raise ValueError(f"content is not a valid {content_type} image")
+    key = f"{PPROFILE}{user_id}/avatar.{extension}"
+    upload_bytes(key, content, content_type, residency=residency)
+    return key
+
+def delete_object(key: str, residency: Optional[str]=None):
+    """Delete one object; an already-missing object is not an error"""
+    client, bucket, _ = _target(residency)
+    try:
+        with_retry(lambda: client.delete_object(Bucket=bucket, Key=key))
+    except ClientError as e:
+        if e.response.get("Error", {}).get("Code") not in ("NoSuchKey", "404"):
+            raise
+
+def download_bytes(key: str, residency: Optional[str]=None, byte_range: Optional[ByteRange]=None) -> bytes:
+    """
+    Read an object, or only byte_range of it. Full downloads are checked with
+    verify_checksum; ranged ones against the returned Content-Range.
+
+    Raises:
+        StorageIntegrityError: if the content is corrupt or not the requested range
+    """
+    client, bucket, _ = _target(residency)
+    params = {"Bucket": bucket, "Key": key}
+    if byte_range:
+        params["Range"] = byte_range.header()
+    obj = client.get_object(**params)
+    body = obj["Body"].read()
+    if byte_range:
+        _verify_range(key, obj, body, byte_range)
+    else:
+        verify_checksum(key, obj, body)
+    return body
+
+def iter_lines(key: str):
+    """Stream an object line by line (decoded as UTF-8) without loading it into memory"""
+    body = s3.get_object(Bucket=_bucket(), Key=key)["Body"]
+    for line in body.iter_lines():
+        yield line.decode("utf-8")
+
+def list_objects(prefix: str = ""):
+    """Yield every object (Key, Size, LastModified, ...) under a prefix"""
+    paginator = s3.get_paginator("list_objects_v2")
+    for page in paginator.paginate(Bucket=_bucket(), Prefix=prefix):
+        for obj in page.get("Contents", []):
+            yield obj
+
+def delete_objects(keys, residency: Optional[str]=None) -> int:
+    """Delete objects by key (batches of 1000); returns the number requested"""
+    keys = list(keys)
+    if not keys:
+        return 0
+    client, bucket, _ = _target(residency)
+    for i in range(0, len(keys), 1000):
+        batch = [{"Key": k} for k in keys[i:i + 1000]]
+        with_retry(lambda: client.delete_objects(Bucket=bucket, Delete={"Objects": batch, "Quiet": True}))
+    return len(keys)
@@ -0,0 +1,371 @@
+"""
+Tests for MeDUSA Analytics Service
+
+Run with: python -m pytest test_analytics_service.py -v
+"""
+
+import os
+import random
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import MagicMock, patch
+
+from botocore.exceptions import ClientError
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from analytics_service import (
+    AggregationPeriod,
+    AnomalyDetector,
+    ImprovementPolarity,
+    TrendDirection,
+    TrendPoint,
+    aggregate_readings,
+    compute_stats,
+    compute_trend,
+    get_reading_rollups,
+    partition_by_quality,
+    period_start,
+    rollup_readings,
+    score_reading_quality,
+    trend_for_metric,
+)
+
+
+def _ts(*args) -> int:
+    return int(datetime(*args, tzinfo=timezone.utc).timestamp())
+
+
+class TestAggregateReadings(unittest.TestCase):
+    """Test reading aggregation over time windows."""
+
+    def test_counts_sum_to_total_for_random_inputs(self):
+        """Property: bucket counts always add up to the number of readings."""
+        rng = random.Random(2045)
+        base = _ts(2025, 1, 1)
+        for _ in range(200):
+            readings = [
+                {
+                    "timestamp": base + rng.randint(0, 90 * 86400),
+                    "tremor_index": rng.uniform(0, 1),
+                    "reading_type": rng.choice(["tremor", "accelerometer"]),
+                }
+                for _ in range(rng.randint(0, 60))
+            ]
+            period = rng.choice(list(AggregationPeriod))
+
+            buckets = aggregate_readings(readings, period)
+
+            self.assertEqual(sum(b["count"] for b in buckets), len(readings))
+
+    def test_five_minute_buckets(self):
+        """Test readings are grouped into five-minute windows."""
+        readings = [
+            {"timestamp": _ts(2025, 3, 1, 10, 0, 5), "tremor_index": 0.2},
+            {"timestamp": _ts(2025, 3, 1, 10, 4, 59), "tremor_index": 0.4},
+            {"timestamp": _ts(2025, 3, 1, 10, 5, 0), "tremor_index": 0.9},
+        ]
+
+        buckets = aggregate_readings(readings, AggregationPeriod.FIVE_MINUTES)
+
+        self.assertEqual([b["count"] for b in buckets], [2, 1])
+        self.assertEqual(buckets[0]["periodStart"], "2025-03-01T10:00:00+00:00")
+        self.assertEqual(buckets[0]["periodEnd"], "2025-03-01T10:05:00+00:00")
+        self.assertAlmostEqual(buckets[0]["stats"]["tremor_index"]["mean"], 0.3)
+
+    def test_missing_metrics_are_skipped(self):
+        """Test metrics absent from a bucket are not reported."""
+        readings = [{"timestamp": _ts(2025, 3, 1, 10), "rms_value": 1.5}]
+
+        buckets = aggregate_readings(readings, AggregationPeriod.HOURLY)
+
+        self.assertEqual(list(buckets[0]["stats"].keys()), ["rms_value"])
+
+    def test_weekly_and_monthly_period_start(self):
+        """Test weekly buckets start on Monday and monthly on the 1st."""
+        ts = datetime(2025, 3, 13, 15, 30, tzinfo=timezone.utc)  # Thursday
+        self.assertEqual(period_start(ts, AggregationPeriod.WEEKLY).day, 10)
+        self.assertEqual(period_start(ts, AggregationPeriod.MONTHLY).day, 1)
+
+
+class TestRollupReadings(unittest.TestCase):
+    """Test contiguous rollups over a date range."""
+
+    def test_daily_rollup_includes_gap_day(self):
+        """Test a day without readings is returned as an explicit empty bucket."""
+        readings = [
+            {"timestamp": _ts(2025, 3, 1, 8), "tremor_index": 0.2},
+            {"timestamp": _ts(2025, 3, 1, 20), "tremor_index": 0.6},
+            # No readings on March 2
+            {"timestamp": _ts(2025, 3, 3, 12), "tremor_index": 0.5},
+        ]
+
+        buckets = rollup_readings(
+            readings, AggregationPeriod.DAILY,
+            datetime(2025, 3, 1, tzinfo=timezone.utc), datetime(2025, 3, 3, 23, 59, tzinfo=timezone.utc)
+        )
+
+        self.assertEqual([b["periodStart"][:10] for b in buckets], ["2025-03-01", "2025-03-02", "2025-03-03"])
+        self.assertEqual([b["count"] for b in buckets], [2, 0, 1])
+        first = buckets[0]["metrics"]["tremor_index"]
+        self.assertEqual((first["count"], first["min"], first["max"]), (2, 0.2, 0.6))
+        self.assertAlmostEqual(first["avg"], 0.4)
+        self.assertEqual(
+            buckets[1]["metrics"]["tremor_index"],
+            {"count": 0, "min": None, "max": None, "avg": None}
+        )
+        self.assertEqual(buckets[2]["metrics"]["tremor_index"]["avg"], 0.5)
+
+    def test_empty_range_still_has_buckets(self):
+        buckets = rollup_readings(
+            [], AggregationPeriod.WEEKLY,
+            datetime(2025, 3, 1, tzinfo=timezone.utc), datetime(2025, 3, 20, tzinfo=timezone.utc)
+        )
+
+        self.assertEqual(len(buckets), 4)  # Weeks of Feb 24, Mar 3, Mar 10, Mar 17
+        self.assertTrue(all(b["count"] == 0 and b["metrics"] == {} for b in buckets))
+
+    def test_readings_outside_range_are_ignored(self):
+        readings = [
+            {"timestamp": _ts(2025, 2, 28, 23), "tremor_index": 0.9},
+            {"timestamp": _ts(2025, 3, 1, 1), "tremor_index": 0.1},
+        ]
+
+        buckets = rollup_readings(
+            readings, AggregationPeriod.MONTHLY,
+            datetime(2025, 3, 1, tzinfo=timezone.utc), datetime(2025, 3, 31, tzinfo=timezone.utc)
+        )
+
+        self.assertEqual([b["count"] for b in buckets], [1])
+
+    def test_too_many_buckets_rejected(self):
+        with self.assertRaises(ValueError):
+            rollup_readings(
+                [], AggregationPeriod.FIVE_MINUTES,
+                datetime(2025, 1, 1, tzinfo=timezone.utc), datetime(2025, 12, 31, tzinfo=timezone.utc)
+            )
+
+    def test_fetches_device_readings(self):
+        db._tremor_analysis.clear()
+        db._tremor_analysis.extend([
+            {"device_id": "dev_1", "patient_id": "usr_p1", "timestamp": _ts(2025, 3, 1, 9), "tremor_index": 0.3},
+            {"device_id": "dev_2", "patient_id": "usr_p1", "timestamp": _ts(2025, 3, 2, 9), "tremor_index": 0.7},
+        ])
+        start, end = datetime(2025, 3, 1, tzinfo=timezone.utc), datetime(2025, 3, 2, 23, tzinfo=timezone.utc)
+
+        by_device = get_reading_rollups(AggregationPeriod.DAILY, start, end, device_id="dev_1")
+        by_patient = get_reading_rollups(AggregationPeriod.DAILY, start, end, patient_id="usr_p1")
+
+        self.assertEqual([b["count"] for b in by_device], [1, 0])
+        self.assertEqual([b["count"] for b in by_patient], [1, 1])
+        with self.assertRaises(ValueError):
+            get_reading_rollups(AggregationPeriod.DAILY, start, end)
+
+
+class TestDeviceReadingsQuery(unittest.TestCase):
+    """Test that device readings are queried from the device index, not scanned."""
+
+    def setUp(self):
+        self.table = MagicMock()
+        patcher = patch.multiple(db, USE_MEMORY=False, T_TREMOR_ANALYSIS=self.table, create=True)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def test_queries_device_index(self):
+        self.table.query.side_effect = [
+            {"Items": [{"device_id": "dev_1", "timestamp": "2025-03-01T09:00:00Z"}], "LastEvaluatedKey": {"k": 1}},
+            {"Items": [{"device_id": "dev_1", "timestamp": "2025-03-01T10:00:00Z"}]},
+        ]
+
+        readings = db.get_device_readings("dev_1", _ts(2025, 3, 1), _ts(2025, 3, 2))
+
+        self.assertEqual([r["timestamp"] for r in readings], [_ts(2025, 3, 1, 9), _ts(2025, 3, 1, 10)])
+        self.assertEqual(self.table.query.call_args.kwargs["IndexName"], db.TREMOR_DEVICE_INDEX)
+        self.table.scan.assert_not_called()
+
+    def test_errors_propagate(self):
+        self.table.query.side_effect = ClientError({"Error": {"Code": "InternalServerError"}}, "Query")
+
+        with self.assertRaises(ClientError):
+            db.get_device_readings("dev_1")
+
+
+class TestComputeStats(unittest.TestCase):
+    """Test per-metric statistics."""
+
+    def test_known_values(self):
+        stats = compute_stats([1.0, 2.0, 3.0, 4.0, 5.0])
+
+        self.assertEqual(stats["mean"], 3.0)
+        self.assertEqual(stats["median"], 3.0)
+        self.assertEqual(stats["min"], 1.0)
+        self.assertEqual(stats["max"], 5.0)
+        self.assertAlmostEqual(stats["stdDev"], 2 ** 0.5)
+        self.assertAlmostEqual(stats["p95"], 4.8)
+
+    def test_single_value(self):
+        stats = compute_stats([7.0])
+
+        self.assertEqual(stats["median"], 7.0)
+        self.assertEqual(stats["p95"], 7.0)
+        self.assertEqual(stats["stdDev"], 0.0)
+
+
+DAY = 86400
+
+
+def _series(values, start=_ts(2025, 3, 1)):
+    return [TrendPoint(timestamp=start + i * DAY, value=v) for i, v in enumerate(values)]
+
+
+class TestComputeTrend(unittest.TestCase):
+    """Test trend direction classification."""
+
+    def test_increasing_series(self):
+        trend = compute_trend(_series([1.0, 2.0, 3.0, 4.0, 5.0]))
+
+        self.assertEqual(trend.direction, TrendDirection.DECLINING)
+        self.assertAlmostEqual(trend.slope_per_day, 1.0)
+        self.assertEqual((trend.average, trend.min, trend.max), (3.0, 1.0, 5.0))
+
+    def test_decreasing_series(self):
+        trend = compute_trend(_series([160.0, 150.0, 140.0, 130.0]))
+
+        self.assertEqual(trend.direction, TrendDirection.IMPROVING)
+        self.assertAlmostEqual(trend.slope_per_day, -10.0)
+
+    def test_flat_series(self):
+        trend = compute_trend(_series([0.4, 0.4, 0.4, 0.4]))
+
+        self.assertEqual(trend.direction, TrendDirection.STABLE)
+        self.assertEqual(trend.slope_per_day, 0.0)
+
+    def test_small_noise_is_stable(self):
+        self.assertEqual(compute_trend(_series([120.0, 121.0, 119.0, 120.5])).direction, TrendDirection.STABLE)
+
+    def test_polarity_flips_direction(self):
+        rising = _series([90.0, 92.0, 95.0, 98.0])
+
+        self.assertEqual(compute_trend(rising, ImprovementPolarity.HIGHER_IS_BETTER).direction, TrendDirection.IMPROVING)
+        self.assertEqual(compute_trend(rising, ImprovementPolarity.LOWER_IS_BETTER).direction, TrendDirection.DECLINING)
+
+    def test_too_few_points(self):
+        trend = compute_trend(_series([1.0, 2.0]))
+
+        self.assertEqual(trend.direction, TrendDirection.INSUFFICIENT)
+        self.assertIsNone(trend.average)
+
+    def test_same_timestamp_is_insufficient(self):
+        points = [TrendPoint(timestamp=1000, value=v) for v in (1.0, 2.0, 3.0)]
+
+        self.assertEqual(compute_trend(points).direction, TrendDirection.INSUFFICIENT)
+
+    def test_unordered_points_are_sorted(self):
+        trend = compute_trend(list(reversed(_series([5.0, 4.0, 3.0]))))
+
+        self.assertEqual([p.value for p in trend.points], [5.0, 4.0, 3.0])
+        self.assertEqual(trend.direction, TrendDirection.IMPROVING)
+
+    def test_trend_for_metric_uses_reading_type_polarity(self):
+        readings = [{"timestamp": p.timestamp, "spo2": p.value} for p in _series([88.0, 91.0, 94.0, 97.0])]
+
+        trend = trend_for_metric(readings, "spo2", reading_type="spo2")
+
+        self.assertEqual(trend.direction, TrendDirection.IMPROVING)
+        self.assertEqual(trend.to_dict()["direction"], "improving")
+
+
+
+class TestReadingQuality(unittest.TestCase):
+    """Test low-quality flagging and exclusion from aggregates and trends."""
+
+    def _readings(self):
+        good = [{"timestamp": p.timestamp, "tremor_index": p.value, "signal_quality": 0.9}
+                for p in _series([0.2, 0.2, 0.2])]
+        noisy = {"timestamp": _ts(2025, 3, 2, 12), "tremor_index": 0.95, "signal_quality": 0.3}
+        flagged = {"timestamp": _ts(2025, 3, 3, 12), "tremor_index": 0.9, "low_quality": True}
+        return good + [noisy, flagged]
+
+    def test_low_quality_excluded_from_trend_average(self):
+        trend = trend_for_metric(self._readings(), "tremor_index")
+
+        self.assertEqual(trend.count, 3)
+        self.assertAlmostEqual(trend.average, 0.2)
+        self.assertEqual(trend.direction, TrendDirection.STABLE)
+
+    def test_low_quality_excluded_from_aggregates(self):
+        buckets = aggregate_readings(self._readings(), AggregationPeriod.MONTHLY)
+
+        self.assertEqual(buckets[0]["count"], 3)
+        self.assertAlmostEqual(buckets[0]["stats"]["tremor_index"]["max"], 0.2)
+
+    def test_low_quality_excluded_from_rollups(self):
+        rollups = rollup_readings(self._readings(), AggregationPeriod.MONTHLY, datetime(2025, 3, 1, tzinfo=timezone.utc),
+                                  datetime(2025, 3, 31, tzinfo=timezone.utc))
+
+        self.assertEqual(rollups[0]["count"], 3)
+
+    def test_partition_counts_exclusions(self):
+        usable, low = partition_by_quality(self._readings())
+
+        self.assertEqual((len(usable), len(low)), (3, 2))
+
+    def test_threshold_flags_item(self):
+        self.assertTrue(score_reading_quality({"signal_quality": 0.3})["low_quality"])
+        self.assertNotIn("low_quality", score_reading_quality({"signal_quality": 0.9}))
+        self.assertNotIn("low_quality", score_reading_quality({"accel_x": 0.1}))
+
+    def test_calibration_overdue_down_scores(self):
+        item = score_reading_quality({"signal_quality": 0.7}, calibration_overdue=True)
+
+        self.assertAlmostEqual(item["signal_quality"], 0.525)
+        self.assertTrue(item["low_quality"])
+        self.assertNotIn("low_quality", score_reading_quality({"signal_quality": 1.0}, calibration_overdue=True))
+
+
+class TestAnomalyDetector(unittest.TestCase):
+    """Test IQR anomaly detection with controlled data sets."""
+
+    def setUp(self):
+        self.detector = AnomalyDetector()
+        # 70..79 bpm twice: Q1=72.0, Q3=77.0, IQR=5 -> expected range [57, 92]
+        self.history = [{"bpm": 70 + i % 10, "timestamp": i} for i in range(20)]
+
+    def test_spike_inside_clinical_range_flagged(self):
+        alerts = self.detector.detect_anomalies(self.history, {"bpm": 110, "timestamp": 99})
+        self.assertEqual(len(alerts), 1)
+        self.assertEqual(alerts[0].key, "bpm")
+        self.assertEqual(alerts[0].expected_range, (57.0, 92.0))
+        self.assertGreater(alerts[0].z_score, 3)
+
+    def test_drop_below_range_flagged(self):
+        alerts = self.detector.detect_anomalies(self.history, {"bpm": 50})
+        self.assertEqual([a.key for a in alerts], ["bpm"])
+        self.assertLess(alerts[0].z_score, 0)
+
+    def test_values_at_range_edges_not_flagged(self):
+        self.assertEqual(self.detector.detect_anomalies(self.history, {"bpm": 92}), [])
+        self.assertEqual(self.detector.detect_anomalies(self.history, {"bpm": 57}), [])
+
+    def test_only_last_window_used(self):
+        old_spikes = [{"bpm": 200} for _ in range(10)]
+        alerts = self.detector.detect_anomalies(old_spikes + self.history, {"bpm": 110})
+        self.assertEqual(len(alerts), 1)
+
+    def test_each_key_checked_separately(self):
+        history = [{"systolic": 120 + i % 5, "diastolic": 80 + i % 5} for i in range(20)]
+        alerts = self.detector.detect_anomalies(history, {"systolic": 122, "diastolic": 110, "device_id": "d"})
+        self.assertEqual([a.key for a in alerts], ["diastolic"])
+
+    def test_too_little_history_not_flagged(self):
+        self.assertEqual(self.detector.detect_anomalies(self.history[:3], {"bpm": 500}), [])
+
+    def test_flat_history_has_no_z_score(self):
+        alerts = self.detector.detect_anomalies([{"bpm": 70}] * 10, {"bpm": 71})
+        self.assertEqual(len(alerts), 1)
+        self.assertIsNone(alerts[0].z_score)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,193 @@
+"""
+Tests for the tamper-evident audit hash chain
+
+Run with: python -m pytest test_audit_chain.py -v
+"""
+
+import os
+import copy
+import unittest
+from decimal import Decimal
+from unittest.mock import MagicMock, patch
+
+from botocore.exceptions import ClientError
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from audit_service import (
+    AuditEventType, AuditService, GENESIS_HASH, UNCHAINED, AUDIT_CHAIN_MAX_ATTEMPTS,
+    compute_entry_hash, verify_chain
+)
+from request_context import organization_scope
+
+
+class ChainTestCase(unittest.TestCase):
+
+    def setUp(self):
+        db._audit_logs.clear()
+        db._audit_chain_head.clear()
+        self.service = AuditService()
+
+    def _log(self, n):
+        return [self.service.log_event(AuditEventType.DATA_READ, user_id=f"usr_{i}", details={"score": 0.5 + i})
+                for i in range(n)]
+
+
+class TestChainWrites(ChainTestCase):
+    """Entries link to their predecessor and advance the head."""
+
+    def test_entries_linked(self):
+        logs = self._log(3)
+        self.assertEqual(logs[0]["prev_hash"], GENESIS_HASH)
+        self.assertEqual([e["sequence"] for e in logs], [1, 2, 3])
+        self.assertEqual(logs[2]["prev_hash"], logs[1]["entry_hash"])
+        self.assertEqual(db.get_audit_chain_head(logs[2]["chainId"]), {"entry_hash": logs[2]["entry_hash"], "sequence": 3})
+
+    def test_chain_per_organization(self):
+        first = self._log(1)[0]
+        with organization_scope("org_a"):
+            other = self.service.log_event(AuditEventType.DATA_READ)
+        self.assertNotEqual(other["chainId"], first["chainId"])
+        self.assertTrue(other["chainId"].startswith("org_a#"))
+        self.assertEqual((other["sequence"], other["prev_hash"]), (1, GENESIS_HASH))
+
+    def test_entry_stored_unchained_when_head_keeps_moving(self):
+        with patch.object(db, "append_audit_log", side_effect=db.ConditionFailedError("moved")) as append:
+            entry = self.service.log_event(AuditEventType.DATA_READ, user_id="usr_1")
+
+        self.assertEqual(append.call_count, AUDIT_CHAIN_MAX_ATTEMPTS)
+        self.assertEqual(entry["chainStatus"], UNCHAINED)
+        self.assertNotIn("sequence", entry)
+        self.assertIn(entry, db._audit_logs)
+        self.assertTrue(verify_chain(db._audit_logs).valid)
+        entry["userId"] = "usr_attacker"
+        self.assertFalse(verify_chain(db._audit_logs).valid)
+
+    def test_chain_continues_across_service_instances(self):
+        first = self._log(1)[0]
+        other = AuditService(service_name="medusa-ingest").log_event(AuditEventType.DATA_CREATE)
+        self.assertEqual(other["prev_hash"], first["entry_hash"])
+
+    def test_concurrent_writer_relinks_entry(self):
+        first = self._log(1)[0]
+        real_append = db.append_audit_log
+        calls = []
+
+        def append_after_other_writer(log, expected_prev_hash):
+            if not calls:
+                calls.append(log["sequence"])
+                # another container appends between our head read and our write
+                raced = {"logId": "LOG#other", "chainId": first["chainId"], "sequence": 2, "prev_hash": first["entry_hash"]}
+                raced["entry_hash"] = compute_entry_hash(raced)
+                real_append(raced, first["entry_hash"])
+            return real_append(log, expected_prev_hash)
+
+        with patch.object(db, "append_audit_log", side_effect=append_after_other_writer):
+            entry = self.service.log_event(AuditEventType.DATA_READ)
+
+        self.assertEqual(entry["sequence"], 3)
+        self.assertTrue(verify_chain(db._audit_logs, head_hash=db.get_audit_chain_head(entry["chainId"])["entry_hash"]).valid)
+
+
+class TestVerifyChain(ChainTestCase):
+    """verify_chain detects modification, insertion and deletion."""
+
+    def test_unmodified_chain_verifies(self):
+        logs = self._log(5)
+        result = verify_chain(reversed(logs), head_hash=logs[-1]["entry_hash"])
+        self.assertTrue(result.valid)
+        self.assertEqual(result.checked, 5)
+
+    def test_modified_entry_detected(self):
+        logs = copy.deepcopy(self._log(4))
+        logs[1]["userId"] = "usr_attacker"
+        result = verify_chain(logs)
+        self.assertFalse(result.valid)
+        self.assertEqual(result.logId, logs[1]["logId"])
+        self.assertIn("entry_hash", result.error)
+
+    def test_modified_nested_detail_detected(self):
+        logs = copy.deepcopy(self._log(2))
+        logs[0]["details"]["score"] = 9.9
+        self.assertFalse(verify_chain(logs).valid)
+
+    def test_rehashed_entry_breaks_next_link(self):
+        logs = copy.deepcopy(self._log(3))
+        logs[1]["outcome"] = "failure"
+        logs[1]["entry_hash"] = compute_entry_hash(logs[1])
+        result = verify_chain(logs)
+        self.assertFalse(result.valid)
+        self.assertEqual(result.logId, logs[2]["logId"])
+
+    def test_deleted_entry_detected(self):
+        logs = self._log(4)
+        del logs[2]
+        self.assertFalse(verify_chain(logs).valid)
+
+    def test_inserted_entry_detected(self):
+        logs = copy.deepcopy(self._log(3))
+        forged = {**logs[1], "logId": "LOG#forged", "userId": "usr_forged"}
+        forged["entry_hash"] = compute_entry_hash(forged)
+        self.assertFalse(verify_chain(logs + [forged]).valid)
+
+    def test_truncated_tail_detected_against_head(self):
+        logs = self._log(3)
+        self.assertTrue(verify_chain(logs[:2]).valid)
+        self.assertFalse(verify_chain(logs[:2], head_hash=logs[-1]["entry_hash"]).valid)
+
+    def test_segment_verifies_from_previous_hash(self):
+        logs = self._log(4)
+        self.assertTrue(verify_chain(logs[2:], previous_hash=logs[1]["entry_hash"]).valid)
+        self.assertFalse(verify_chain(logs[2:]).valid)
+
+    def test_entries_read_back_from_dynamodb_verify(self):
+        logs = copy.deepcopy(self._log(2))
+        for entry in logs:
+            entry["details"]["score"] = Decimal(str(entry["details"]["score"]))
+            entry["sequence"] = Decimal(entry["sequence"])
+            for key in [k for k, v in entry.items() if v is None]:
+                del entry[key]
+        self.assertTrue(verify_chain(logs).valid)
+
+
+class TestAppendAuditLogDynamo(unittest.TestCase):
+    """Entry and chain head are written in one transaction."""
+
+    def setUp(self):
+        self.ddb = MagicMock()
+        table = MagicMock()
+        table.name = "medusa-audit-logs-test"
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "ddb", self.ddb, create=True).start()
+        patch.object(db, "T_AUDIT_LOGS", table, create=True).start()
+        self.entry = {"pk": "AUDIT#ALL", "sk": "2025-01-01T00:00:00+00:00", "userId": None, "chainId": "org_default#2025-01-01",
+                      "sequence": 8, "prev_hash": "a" * 64, "entry_hash": "b" * 64, "details": {"score": 0.5}}
+
+    def test_head_update_conditioned_on_previous_hash(self):
+        db.append_audit_log(self.entry, "a" * 64)
+
+        put, update = self.ddb.meta.client.transact_write_items.call_args.kwargs["TransactItems"]
+        self.assertNotIn("userId", put["Put"]["Item"])
+        self.assertEqual(put["Put"]["Item"]["details"]["score"], Decimal("0.5"))
+        self.assertEqual(update["Update"]["Key"], {"pk": "AUDIT#CHAIN", "sk": "HEAD#org_default#2025-01-01"})
+        self.assertEqual(update["Update"]["ConditionExpression"], "#hash = :prev")
+        self.assertEqual(update["Update"]["ExpressionAttributeValues"][":prev"], "a" * 64)
+
+    def test_first_entry_requires_missing_head(self):
+        db.append_audit_log(self.entry, None)
+        update = self.ddb.meta.client.transact_write_items.call_args.kwargs["TransactItems"][1]["Update"]
+        self.assertEqual(update["ConditionExpression"], "attribute_not_exists(#pk)")
+
+    def test_cancelled_transaction_raises_condition_failed(self):
+        self.ddb.meta.client.transact_write_items.side_effect = ClientError(
+            {"Error": {"Code": "TransactionCanceledException", "Message": "ConditionalCheckFailed"}},
+            "TransactWriteItems"
+        )
+        with self.assertRaises(db.ConditionFailedError):
+            db.append_audit_log(self.entry, "a" * 64)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,120 @@
+"""
+Tests for the audit log NDJSON export
+
+Run with: python -m pytest test_audit_export.py -v
+"""
+
+import os
+import json
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import storage
+import audit_export
+from audit_export import export_audit_logs, export_since_high_water_mark, get_high_water_mark
+
+NOW = datetime(2025, 3, 2, 12, 1, tzinfo=timezone.utc)  # window end 12:00:00 with the 60 s lag
+
+
+def _log(ts):
+    db._audit_logs.insert(0, {"logId": f"LOG#{ts}", "sk": ts, "timestamp": ts, "date": ts[:10],
+                              "eventType": "DATA_READ", "sequence": len(db._audit_logs) + 1})
+
+
+class TestAuditExport(unittest.TestCase):
+    """Test NDJSON output, date partitions and the high-water mark."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+        db._system_settings.clear()
+        for ts in ("2025-03-01T22:00:00+00:00", "2025-03-01T23:59:00+00:00", "2025-03-02T08:00:00+00:00"):
+            _log(ts)
+        self.uploads = {}
+
+        def _upload(key, chunks, content_type):
+            self.uploads[key] = b"".join(chunks)
+            return len(self.uploads[key])
+
+        self.addCleanup(patch.stopall)
+        self.upload = patch.object(storage, "multipart_upload", side_effect=_upload).start()
+        self.log_event = patch.object(audit_export.audit_service, "log_event").start()
+
+    def _lines(self, key):
+        return [json.loads(line) for line in self.uploads[key].decode().splitlines()]
+
+    def test_one_line_per_log_partitioned_by_date(self):
+        result = export_since_high_water_mark(NOW)
+
+        self.assertEqual(result["entries"], 3)
+        self.assertEqual(sorted(self.uploads), [
+            "audit-exports/date=2025-03-01/audit-20250302T120000Z.ndjson",
+            "audit-exports/date=2025-03-02/audit-20250302T120000Z.ndjson",
+        ])
+        day1 = self._lines("audit-exports/date=2025-03-01/audit-20250302T120000Z.ndjson")
+        self.assertEqual([e["timestamp"][11:16] for e in day1], ["22:00", "23:59"])
+        self.assertEqual(self.upload.call_args.args[2], "application/x-ndjson")
+
+    def test_high_water_mark_advances_and_next_run_is_incremental(self):
+        export_since_high_water_mark(NOW)
+        self.assertEqual(get_high_water_mark(), datetime(2025, 3, 2, 12, 0, tzinfo=timezone.utc))
+
+        self.uploads.clear()
+        _log("2025-03-02T12:00:00+00:00")  # exactly at the mark: already exported window
+        _log("2025-03-02T12:30:00+00:00")
+        result = export_since_high_water_mark(datetime(2025, 3, 2, 13, 1, tzinfo=timezone.utc))
+
+        self.assertEqual(result["entries"], 1)
+        (key,) = self.uploads
+        self.assertEqual([e["timestamp"] for e in self._lines(key)], ["2025-03-02T12:30:00+00:00"])
+        self.assertEqual(get_high_water_mark(), datetime(2025, 3, 2, 13, 0, tzinfo=timezone.utc))
+
+    def test_no_new_logs_uploads_nothing(self):
+        export_since_high_water_mark(NOW)
+        self.uploads.clear()
+        result = export_since_high_water_mark(NOW)
+        self.assertEqual(result["entries"], 0)
+        self.assertEqual(self.uploads, {})
+
+    def test_export_is_audited(self):
+        export_since_high_water_mark(NOW)
+        kwargs = self.log_event.call_args.kwargs
+        self.assertEqual(kwargs["event_type"].value, "DATA_EXPORT")
+        self.assertEqual(kwargs["action"], "export_audit_logs")
+        self.assertEqual(kwargs["details"]["entries"], 3)
+
+    def test_failed_day_keeps_mark_at_last_uploaded_day(self):
+        def _upload(key, chunks, content_type):
+            if "2025-03-02" in key:
+                raise RuntimeError("s3 unavailable")
+            return len(b"".join(chunks))
+        self.upload.side_effect = _upload
+
+        start = datetime(2025, 3, 1, 0, 0, tzinfo=timezone.utc)
+        with self.assertRaises(RuntimeError):
+            export_audit_logs(start, datetime(2025, 3, 2, 12, 0, tzinfo=timezone.utc))
+        self.assertEqual(get_high_water_mark(), datetime(2025, 3, 2, 0, 0, tzinfo=timezone.utc))
+
+
+class TestAuditLogPagesByDate(unittest.TestCase):
+    """Test the date-index query follows pagination oldest first."""
+
+    def test_dynamo_pages(self):
+        table = MagicMock()
+        table.query.side_effect = [
+            {"Items": [{"timestamp": "2025-03-02T08:00:00+00:00"}], "LastEvaluatedKey": {"logId": "a"}},
+            {"Items": [{"timestamp": "2025-03-02T09:00:00+00:00"}]},
+        ]
+        with patch.object(db, "USE_MEMORY", False), patch.object(db, "T_AUDIT_LOGS", table, create=True):
+            pages = list(db.iter_audit_log_pages_by_date("2025-03-02", "2025-03-02T00:00:00+00:00"))
+
+        self.assertEqual(len(pages), 2)
+        self.assertTrue(table.query.call_args_list[0].kwargs["ScanIndexForward"])
+        self.assertEqual(table.query.call_args_list[1].kwargs["ExclusiveStartKey"], {"logId": "a"})
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,120 @@
+"""
+Tests for audit log retention (TTL) and per-day queries
+
+Run with: python -m pytest test_audit_retention.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timezone
+from decimal import Decimal
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import audit_service
+import db
+from audit_service import AuditEventType, AuditService
+
+
+class TestRetention(unittest.TestCase):
+    """Entries carry the ttl and date attributes."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+        db._audit_chain_head.clear()
+
+    def _entry_time(self, entry):
+        return datetime.fromisoformat(entry["timestamp"])
+
+    def test_default_retention_is_seven_years(self):
+        self.assertEqual(audit_service.AUDIT_LOG_RETENTION_DAYS, 2555)
+        entry = AuditService().log_event(AuditEventType.DATA_READ)
+        written = self._entry_time(entry).timestamp()
+        self.assertEqual(entry["ttl"], int(written + 2555 * 86400))
+
+    def test_configured_retention(self):
+        with patch.object(audit_service, "AUDIT_LOG_RETENTION_DAYS", 30):
+            entry = AuditService().log_event(AuditEventType.DATA_READ)
+        self.assertEqual(entry["ttl"], int(self._entry_time(entry).timestamp() + 30 * 86400))
+
+    def test_date_attribute(self):
+        entry = AuditService().log_event(AuditEventType.DATA_READ)
+        self.assertEqual(entry["date"], entry["timestamp"][:10])
+        self.assertEqual(entry["date"], self._entry_time(entry).astimezone(timezone.utc).strftime("%Y-%m-%d"))
+
+
+class TestAuditLogsByDate(unittest.TestCase):
+    """Per-day queries."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+        for ts in ("2025-03-01T23:59:00+00:00", "2025-03-02T08:00:00+00:00", "2025-03-02T17:30:00+00:00"):
+            db._audit_logs.insert(0, {"logId": f"LOG#{ts}", "sk": ts, "timestamp": ts, "date": ts[:10]})
+
+    def test_memory_day_and_range(self):
+        page = db.get_audit_logs_by_date("2025-03-02")
+        self.assertEqual([i["timestamp"][11:16] for i in page.items], ["17:30", "08:00"])
+        page = db.get_audit_logs_by_date("2025-03-02", end_time="2025-03-02T12:00:00+00:00")
+        self.assertEqual(len(page.items), 1)
+
+    def test_dynamo_queries_date_index(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": [{"timestamp": "2025-03-02T08:00:00+00:00", "sequence": Decimal(4)}]}
+        with patch.object(db, "USE_MEMORY", False), patch.object(db, "T_AUDIT_LOGS", table, create=True):
+            page = db.get_audit_logs_by_date("2025-03-02", start_time="2025-03-02T06:00:00+00:00")
+
+        kwargs = table.query.call_args.kwargs
+        self.assertEqual(kwargs["IndexName"], "date-index")
+        self.assertFalse(kwargs["ScanIndexForward"])
+        self.assertEqual(page.items[0]["sequence"], 4)
+
+    def test_same_day_range_uses_date_index(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": []}
+        with patch.object(db, "USE_MEMORY", False), patch.object(db, "T_AUDIT_LOGS", table, create=True):
+            db.get_audit_logs(start_time="2025-03-02T00:00:00+00:00", end_time="2025-03-02T23:59:59+00:00")
+            self.assertEqual(table.query.call_args.kwargs["IndexName"], "date-index")
+
+            db.get_audit_logs(start_time="2025-03-01T00:00:00+00:00", end_time="2025-03-02T23:59:59+00:00")
+            self.assertNotIn("IndexName", table.query.call_args.kwargs)
+
+
+class TestEnableTableTtl(unittest.TestCase):
+    """enable_table_ttl is idempotent."""
+
+    def setUp(self):
+        self.client = MagicMock()
+        ddb = MagicMock()
+        ddb.meta.client = self.client
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "ddb", ddb, create=True).start()
+
+    def _status(self, status, attribute=None):
+        description = {"TimeToLiveStatus": status}
+        if attribute:
+            description["AttributeName"] = attribute
+        self.client.describe_time_to_live.return_value = {"TimeToLiveDescription": description}
+
+    def test_enables_when_disabled(self):
+        self._status("DISABLED")
+        self.assertTrue(db.enable_table_ttl("medusa-audit-logs-prod", "ttl"))
+        self.client.update_time_to_live.assert_called_once_with(
+            TableName="medusa-audit-logs-prod",
+            TimeToLiveSpecification={"Enabled": True, "AttributeName": "ttl"}
+        )
+
+    def test_already_enabled_is_a_no_op(self):
+        self._status("ENABLED", "ttl")
+        self.assertFalse(db.enable_table_ttl("medusa-audit-logs-prod", "ttl"))
+        self.client.update_time_to_live.assert_not_called()
+
+    def test_other_attribute_rejected(self):
+        self._status("ENABLED", "expiresAt")
+        with self.assertRaises(ValueError):
+            db.enable_table_ttl("medusa-audit-logs-prod", "ttl")
+
+
+if __name__ == '__main__':
+    unittest.main()
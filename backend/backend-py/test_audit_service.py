@@ -5,8 +5,12 @@ Run with: python -m pytest test_audit_service.py -v
 Or simply: python test_audit_service.py
 """
 
+import os
 import json
 import unittest
+
+os.environ['USE_MEMORY'] = 'true'
+
 from audit_service import (
     AuditService, 
     AuditEventType, 
@@ -119,6 +123,11 @@ class TestAuditService(unittest.TestCase):
         # Each entry should have a unique hash
         self.assertNotEqual(entry1["event_hash"], entry2["event_hash"])
         self.assertEqual(len(entry1["event_hash"]), 16)  # SHA-256 truncated
+        
+        # The full hash chains each entry to its predecessor
+        self.assertEqual(entry1["event_hash"], entry1["entry_hash"][:16])
+        self.assertEqual(entry2["prev_hash"], entry1["entry_hash"])
+        self.assertEqual(entry2["sequence"], entry1["sequence"] + 1)
     
     def test_login_success_convenience_method(self):
         """Test login success convenience method"""
@@ -0,0 +1,65 @@
+"""
+Tests for the shared AWS clients
+
+Run with: python -m pytest test_aws_clients.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import aws_clients
+import device_data_ingest
+import report_scheduler
+
+
+class AwsClientsTestCase(unittest.TestCase):
+
+    def setUp(self):
+        aws_clients.reset_clients()
+        self.addCleanup(aws_clients.reset_clients)
+        patcher = patch.object(aws_clients.boto3, "client", side_effect=lambda *a, **kw: object())
+        self.client_factory = patcher.start()
+        self.addCleanup(patcher.stop)
+
+
+class TestGetClient(AwsClientsTestCase):
+    """Test client reuse."""
+
+    def test_client_created_once(self):
+        first = aws_clients.get_client("sqs")
+        self.assertIs(aws_clients.get_client("sqs"), first)
+        self.client_factory.assert_called_once_with("sqs", config=aws_clients.CLIENT_CONFIG)
+
+    def test_arguments_select_separate_clients(self):
+        us = aws_clients.get_client("ses", region_name="us-east-1")
+        eu = aws_clients.get_client("ses", region_name="eu-west-1")
+        self.assertIsNot(us, eu)
+        self.assertIs(aws_clients.get_client("ses", region_name="eu-west-1"), eu)
+
+    def test_pool_config(self):
+        self.assertEqual(aws_clients.CLIENT_CONFIG.max_pool_connections, aws_clients.AWS_MAX_POOL_CONNECTIONS)
+        self.assertTrue(aws_clients.CLIENT_CONFIG.tcp_keepalive)
+
+    def test_reset_clients(self):
+        first = aws_clients.get_client("sqs")
+        aws_clients.reset_clients()
+        self.assertIsNot(aws_clients.get_client("sqs"), first)
+        self.assertEqual(self.client_factory.call_count, 2)
+
+
+class TestWarmInvocations(AwsClientsTestCase):
+    """Handlers of one container share their clients."""
+
+    def test_handlers_share_one_sqs_client(self):
+        with patch.object(device_data_ingest, "_sqs", None), patch.object(report_scheduler, "_sqs", None):
+            ingest = device_data_ingest._sqs_client()
+            self.assertIs(device_data_ingest._sqs_client(), ingest)
+            self.assertIs(report_scheduler._sqs_client(), ingest)
+        self.client_factory.assert_called_once()
+
+
+if __name__ == '__main__':
+    unittest.main()
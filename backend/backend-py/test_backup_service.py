@@ -0,0 +1,145 @@
+"""
+Tests for MeDUSA Backup Service
+
+Run with: python -m pytest test_backup_service.py -v
+"""
+
+import os
+import json
+import hashlib
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import backup_service as backup_module
+from backup_service import BackupService, decode_item, encode_item
+
+TABLES = {"DDB_TABLE_USERS": "medusa-users-test", "DDB_TABLE_DEVICES": "medusa-devices-test"}
+NOW = datetime(2025, 6, 1, 3, 0, tzinfo=timezone.utc)
+
+
+class FakeDynamoDB:
+    """scan in pages of page_size; batch_write_item records what was written."""
+
+    def __init__(self, tables, page_size=2):
+        self.tables = tables
+        self.page_size = page_size
+        self.scans = []
+        self.written = {}
+
+    def scan(self, TableName, ExclusiveStartKey=None):
+        self.scans.append((TableName, ExclusiveStartKey))
+        items = self.tables.get(TableName, [])
+        start = ExclusiveStartKey["offset"] if ExclusiveStartKey else 0
+        resp = {"Items": items[start:start + self.page_size]}
+        if start + self.page_size < len(items):
+            resp["LastEvaluatedKey"] = {"offset": start + self.page_size}
+        return resp
+
+    def batch_write_item(self, RequestItems):
+        for table, requests in RequestItems.items():
+            self.written.setdefault(table, []).extend(r["PutRequest"]["Item"] for r in requests)
+        return {"UnprocessedItems": {}}
+
+
+def _users(n):
+    return [{"id": {"S": f"usr_{i}"}, "age": {"N": str(40 + i)}} for i in range(n)]
+
+
+class BackupTestCase(unittest.TestCase):
+
+    def setUp(self):
+        self.objects = {}
+        self.addCleanup(patch.stopall)
+        patch.object(backup_module, "configured_tables", return_value=dict(sorted(TABLES.items()))).start()
+        patch.object(backup_module.storage, "upload_sensitive",
+                     side_effect=lambda key, body, content_type: self.objects.__setitem__(key, body)).start()
+        patch.object(backup_module.storage, "download_sensitive", side_effect=lambda key: self.objects[key]).start()
+
+
+class TestCreateFullBackup(BackupTestCase):
+    """Test backup files and manifest."""
+
+    def test_backup_writes_each_table_and_manifest(self):
+        fake = FakeDynamoDB({"medusa-users-test": _users(5), "medusa-devices-test": []})
+        manifest = BackupService(fake).create_full_backup(now=NOW)
+
+        self.assertEqual(manifest.backupId, "20250601T030000Z")
+        self.assertIn("backups/20250601T030000Z/manifest.json", self.objects)
+        users = next(t for t in manifest.tables if t.envVar == "DDB_TABLE_USERS")
+        self.assertEqual(users.key, "backups/20250601T030000Z/medusa-users-test.jsonl")
+        self.assertEqual(users.recordCount, 5)
+        body = self.objects[users.key]
+        self.assertEqual(users.sizeBytes, len(body))
+        self.assertEqual(users.sha256, hashlib.sha256(body).hexdigest())
+        self.assertEqual(len(body.decode().splitlines()), 5)
+
+        stored = json.loads(self.objects["backups/20250601T030000Z/manifest.json"])
+        self.assertEqual({t["tableName"] for t in stored["tables"]}, {"medusa-users-test", "medusa-devices-test"})
+
+    def test_scan_follows_exclusive_start_key(self):
+        fake = FakeDynamoDB({"medusa-users-test": _users(5)})
+        items = list(BackupService(fake).scan_table("medusa-users-test"))
+        self.assertEqual(len(items), 5)
+        self.assertEqual([key for _, key in fake.scans], [None, {"offset": 2}, {"offset": 4}])
+
+    def test_binary_values_round_trip(self):
+        item = {"id": {"S": "k"}, "blob": {"B": b"\x00\xff"}, "nested": {"M": {"set": {"BS": [b"a"]}}}}
+        self.assertEqual(decode_item(encode_item(item)), item)
+
+
+class TestRestoreFromBackup(BackupTestCase):
+    """Test restoring a backup."""
+
+    def _backup(self, users=30):
+        fake = FakeDynamoDB({"medusa-users-test": _users(users), "medusa-devices-test": []})
+        BackupService(fake).create_full_backup(now=NOW)
+        return "backups/20250601T030000Z/manifest.json"
+
+    def test_restore_writes_all_items_in_batches(self):
+        manifest_key = self._backup(users=30)
+        target = FakeDynamoDB({})
+        report = BackupService(target).restore_from_backup(manifest_key)
+
+        self.assertEqual(report.errors, {})
+        self.assertEqual(report.restored["medusa-users-test"], 30)
+        self.assertEqual(target.written["medusa-users-test"], _users(30))
+
+    def test_restore_into_other_environment_tables(self):
+        manifest_key = self._backup(users=3)
+        target = FakeDynamoDB({})
+        with patch.object(backup_module, "configured_tables",
+                          return_value={"DDB_TABLE_USERS": "medusa-users-staging"}):
+            report = BackupService(target).restore_from_backup(manifest_key)
+        self.assertEqual(report.restored, {"medusa-users-staging": 3})
+        self.assertIn("DDB_TABLE_DEVICES", report.errors)
+
+    def test_checksum_mismatch_skips_table(self):
+        manifest_key = self._backup(users=3)
+        self.objects["backups/20250601T030000Z/medusa-users-test.jsonl"] += b'{"id": {"S": "forged"}}\n'
+        target = FakeDynamoDB({})
+        report = BackupService(target).restore_from_backup(manifest_key)
+
+        self.assertIn("checksum", report.errors["DDB_TABLE_USERS"])
+        self.assertNotIn("medusa-users-test", target.written)
+
+
+class TestRunHandler(BackupTestCase):
+    """Test the admin Lambda handler."""
+
+    def test_backup_then_restore(self):
+        fake = FakeDynamoDB({"medusa-users-test": _users(2)})
+        with patch.object(backup_module, "backup_service", BackupService(fake)):
+            result = backup_module.run({"action": "backup"}, None)
+            restored = backup_module.run({"action": "restore", "manifestKey": result["manifestKey"]}, None)
+        self.assertEqual(restored["report"]["restored"]["medusa-users-test"], 2)
+
+    def test_restore_requires_manifest_key(self):
+        with self.assertRaises(ValueError):
+            backup_module.run({"action": "restore"}, None)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,105 @@
+"""
+Tests for bulk user and device reads (BatchGetItem)
+
+Run with: python -m pytest test_bulk_reads.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+
+
+class _FakeTable:
+    def __init__(self, name, items):
+        self.name = name
+        self.items = {item["id"]: item for item in items}
+        self.get_item = MagicMock(side_effect=lambda Key: {"Item": self.items.get(Key["id"])})
+
+
+class _FakeDynamo:
+    """BatchGetItem over _FakeTables; the first `throttle` calls leave their last key unprocessed."""
+
+    def __init__(self, tables, throttle=0):
+        self.tables = {t.name: t for t in tables}
+        self.throttle = throttle
+        self.batch_get_item = MagicMock(side_effect=self._batch_get_item)
+
+    def _batch_get_item(self, RequestItems):
+        responses, unprocessed = {}, {}
+        for name, request in RequestItems.items():
+            keys = request["Keys"]
+            assert len(keys) <= db.BATCH_GET_MAX_KEYS
+            if self.throttle:
+                self.throttle -= 1
+                keys, unprocessed[name] = keys[:-1], {"Keys": keys[-1:]}
+            table = self.tables[name]
+            responses[name] = [table.items[k["id"]] for k in keys if k["id"] in table.items]
+        return {"Responses": responses, "UnprocessedKeys": unprocessed}
+
+
+class TestBulkReads(unittest.TestCase):
+    """Test chunking, unprocessed key retries and the returned id maps."""
+
+    def setUp(self):
+        self.users = _FakeTable("medusa-users", [{"id": f"usr_{i}", "role": "patient"} for i in range(250)])
+        self.devices = _FakeTable("medusa-devices", [{"id": f"DEV-{i}", "name": f"Band {i}"} for i in range(50)])
+        self.ddb = _FakeDynamo([self.users, self.devices])
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "USERS_SINGLE_TABLE", False).start()
+        patch.object(db, "USERS_PK_ATTR", "id").start()
+        patch.object(db, "ddb", self.ddb, create=True).start()
+        patch.object(db, "T_USERS", self.users, create=True).start()
+        patch.object(db, "T_DEVICES", self.devices, create=True).start()
+        patch.object(db.cache_service, "get", return_value=None).start()
+        patch.object(db.cache_service, "set").start()
+        patch("db.time.sleep").start()
+
+    def test_users_fetched_in_chunks_of_100(self):
+        ids = [f"usr_{i}" for i in range(250)]
+
+        users = db.get_users_bulk(ids + ["usr_0"])
+
+        self.assertEqual(set(users), set(ids))
+        self.assertEqual(self.ddb.batch_get_item.call_count, 3)
+
+    def test_missing_ids_absent(self):
+        devices = db.get_devices_bulk(["DEV-1", "DEV-missing"])
+        self.assertEqual(devices, {"DEV-1": {"id": "DEV-1", "name": "Band 1"}})
+
+    def test_unprocessed_keys_retried(self):
+        self.ddb.throttle = 2
+
+        devices = db.get_devices_bulk([f"DEV-{i}" for i in range(10)])
+
+        self.assertEqual(len(devices), 10)
+        self.assertEqual(self.ddb.batch_get_item.call_count, 3)
+
+    def test_gives_up_when_keys_stay_unprocessed(self):
+        self.ddb.throttle = db.BATCH_GET_MAX_ATTEMPTS
+
+        with self.assertRaises(RuntimeError):
+            db.get_devices_bulk(["DEV-1", "DEV-2"])
+
+    def test_no_ids_no_calls(self):
+        self.assertEqual(db.get_users_bulk([]), {})
+        self.ddb.batch_get_item.assert_not_called()
+
+    def test_bulk_vs_individual_reads_for_50_patients(self):
+        ids = [f"usr_{i}" for i in range(50)]
+
+        individual = {uid: db.get_user(uid) for uid in ids}
+        bulk = db.get_users_bulk(ids)
+
+        self.assertEqual(bulk, individual)
+        # 50 sequential GetItem round trips vs one BatchGetItem
+        self.assertEqual(self.users.get_item.call_count, 50)
+        self.assertEqual(self.ddb.batch_get_item.call_count, 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
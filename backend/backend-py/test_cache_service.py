@@ -0,0 +1,117 @@
+"""
+Tests for the Redis-backed user cache
+
+Run with: python -m pytest test_cache_service.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from cache_service import CacheService, user_key, user_email_key
+
+USER = {
+    "id": "usr_1", "email": "jane@example.com", "role": "patient",
+    "password": "$argon2id$hash", "passwordHistory": ["$argon2id$old"], "mfaSecret": "JBSWY3DP",
+    "otpHash": "abc", "otpAttempts": 2, "passwordChangedAt": 1700000000,
+}
+
+
+class _FakeRedis:
+    def __init__(self):
+        self.values = {}
+
+    def get(self, key):
+        return self.values.get(key)
+
+    def set(self, key, value, ex=None):
+        self.values[key] = value
+
+    def delete(self, key):
+        self.values.pop(key, None)
+
+
+class _FakeUsersTable:
+    name = "medusa-users"
+
+    def __init__(self):
+        self.get_item = MagicMock(side_effect=lambda Key: {"Item": dict(USER)} if Key["id"] == USER["id"] else {})
+        self.query = MagicMock(side_effect=lambda **kwargs: {"Items": [dict(USER)]})
+        self.update_item = MagicMock()
+        self.delete_item = MagicMock(return_value={"Attributes": dict(USER)})
+
+
+cache = CacheService()
+table = _FakeUsersTable()
+
+
+@patch.object(db, "USE_MEMORY", False)
+@patch.object(db, "USERS_SINGLE_TABLE", False)
+@patch.object(db, "cache_service", cache)
+@patch.object(db, "T_USERS", table, create=True)
+class TestUserCache(unittest.TestCase):
+    """Test cache hits, misses, invalidation and that secrets are never cached."""
+
+    def setUp(self):
+        cache.client = _FakeRedis()
+        table.__init__()
+
+    def test_miss_reads_table_then_hit_does_not(self):
+        self.assertEqual(db.get_user("usr_1")["email"], USER["email"])
+        self.assertEqual(db.get_user("usr_1")["email"], USER["email"])
+
+        self.assertEqual(table.get_item.call_count, 1)
+
+    def test_unknown_user_not_cached(self):
+        self.assertIsNone(db.get_user("usr_missing"))
+        self.assertIsNone(cache.get(user_key("usr_missing")))
+
+    def test_secrets_never_cached_or_returned_by_default(self):
+        user = db.get_user("usr_1")
+        db.get_user_by_email(USER["email"])
+
+        for cached in (cache.get(user_key("usr_1")), cache.get(user_email_key(USER["email"])), user):
+            for field in ("password", "passwordHistory", "mfaSecret", "otpHash", "otpAttempts"):
+                self.assertNotIn(field, cached)
+            self.assertEqual(cached["passwordChangedAt"], USER["passwordChangedAt"])
+
+    def test_include_secrets_bypasses_cache(self):
+        db.get_user("usr_1")
+
+        user = db.get_user("usr_1", include_secrets=True)
+
+        self.assertEqual(user["password"], USER["password"])
+        self.assertEqual(table.get_item.call_count, 2)
+
+    def test_update_invalidates_id_and_email_keys(self):
+        db.get_user("usr_1")
+        db.get_user_by_email(USER["email"])
+
+        db.update_user("usr_1", {"role": "doctor"})
+
+        self.assertIsNone(cache.get(user_key("usr_1")))
+        self.assertIsNone(cache.get(user_email_key(USER["email"])))
+
+    def test_email_change_invalidates_old_and_new_email(self):
+        db.get_user("usr_1")
+        cache.set(user_email_key(USER["email"]), {"id": "usr_1"})
+        cache.set(user_email_key("new@example.com"), {"id": "usr_stale"})
+
+        db.update_user("usr_1", {"email": "new@example.com"})
+
+        self.assertIsNone(cache.get(user_email_key(USER["email"])))
+        self.assertIsNone(cache.get(user_email_key("new@example.com")))
+
+    def test_delete_invalidates(self):
+        db.get_user("usr_1")
+
+        self.assertTrue(db.delete_user("usr_1"))
+
+        self.assertIsNone(cache.get(user_key("usr_1")))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,131 @@
+"""
+Tests for MeDUSA Device Calibration Service
+
+Run with: python -m pytest test_calibration_service.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timezone, timedelta
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from calibration_service import (
+    is_calibration_due,
+    compute_calibration_result,
+    record_calibration,
+    get_calibration_history,
+    get_latest_calibration,
+    CalibrationError,
+    CALIBRATION_INTERVAL_DAYS
+)
+
+REFERENCE = {"accel_x": 9.81, "accel_y": 0.0, "accel_z": 19.62}
+
+
+class TestCalibrationDue(unittest.TestCase):
+    """Test overdue calibration detection."""
+
+    def setUp(self):
+        self.now = datetime(2025, 6, 1, tzinfo=timezone.utc)
+
+    def test_past_due_date_is_overdue(self):
+        device = {"calibrationDueAt": (self.now - timedelta(days=1)).isoformat()}
+        self.assertTrue(is_calibration_due(device, self.now))
+
+    def test_future_due_date_is_not_overdue(self):
+        device = {"calibrationDueAt": (self.now + timedelta(days=1)).isoformat()}
+        self.assertFalse(is_calibration_due(device, self.now))
+
+    def test_untracked_device_is_not_overdue(self):
+        self.assertFalse(is_calibration_due({}, self.now))
+
+
+class TestCalibrationResult(unittest.TestCase):
+    """Test computing a calibration result from reference and measured values."""
+
+    def test_exact_device_passes_with_unit_factor(self):
+        result = compute_calibration_result(REFERENCE, dict(REFERENCE))
+        self.assertEqual(result, {"passed": True, "offset": 0.0, "correctionFactor": 1.0})
+
+    def test_scaled_device_passes_with_correction(self):
+        measured = {k: v / 1.1 for k, v in REFERENCE.items()}
+        result = compute_calibration_result(REFERENCE, measured)
+        self.assertTrue(result["passed"])
+        self.assertAlmostEqual(result["correctionFactor"], 1.1, places=4)
+        self.assertGreater(result["offset"], 0)
+
+    def test_inconsistent_device_fails(self):
+        result = compute_calibration_result({"a": 10.0, "b": 10.0}, {"a": 10.0, "b": 5.0})
+        self.assertFalse(result["passed"])
+
+    def test_only_shared_keys_are_compared(self):
+        result = compute_calibration_result({"a": 2.0, "b": 1.0}, {"a": 1.0, "c": 99.0})
+        self.assertEqual(result["correctionFactor"], 2.0)
+
+    def test_no_shared_keys_rejected(self):
+        with self.assertRaises(CalibrationError):
+            compute_calibration_result({"a": 1.0}, {"b": 1.0})
+
+    def test_all_zero_measurements_rejected(self):
+        with self.assertRaises(CalibrationError):
+            compute_calibration_result({"a": 1.0}, {"a": 0.0})
+
+
+class TestRecordCalibration(unittest.TestCase):
+    """Test calibration recording."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._calibrations.clear()
+        past = (datetime.now(timezone.utc) - timedelta(days=30)).isoformat()
+        db._devices.append({"id": "dev_cal", "calibrationDueAt": past})
+
+    def test_just_calibrated_device_is_not_overdue(self):
+        self.assertTrue(is_calibration_due(db.get_device("dev_cal")))
+
+        calibration = record_calibration("dev_cal", "usr_tech", "admin", REFERENCE, dict(REFERENCE))
+
+        device = db.get_device("dev_cal")
+        self.assertFalse(is_calibration_due(device))
+        self.assertEqual(device["lastCalibratedAt"], calibration["calibratedAt"])
+        due = datetime.fromisoformat(calibration["nextDue"]) - datetime.fromisoformat(calibration["calibratedAt"])
+        self.assertEqual(due, timedelta(days=CALIBRATION_INTERVAL_DAYS))
+
+    def test_passed_calibration_sets_device_correction(self):
+        measured = {k: v / 1.1 for k, v in REFERENCE.items()}
+        record_calibration("dev_cal", "usr_tech", "admin", REFERENCE, measured, notes="bench rig 2")
+
+        correction = db.get_device("dev_cal")["calibrationCorrection"]
+        self.assertAlmostEqual(correction["correctionFactor"], 1.1, places=4)
+        self.assertEqual(correction["keys"], ["accel_x", "accel_y", "accel_z"])
+
+    def test_failed_calibration_is_recorded_but_device_unchanged(self):
+        calibration = record_calibration("dev_cal", "usr_tech", "admin", {"a": 10.0, "b": 10.0}, {"a": 10.0, "b": 5.0})
+
+        device = db.get_device("dev_cal")
+        self.assertFalse(calibration["calibrationResult"]["passed"])
+        self.assertTrue(is_calibration_due(device))
+        self.assertNotIn("calibrationCorrection", device)
+        self.assertEqual(get_latest_calibration("dev_cal")["id"], calibration["id"])
+
+    def test_history_is_newest_first(self):
+        first = datetime(2025, 1, 1, tzinfo=timezone.utc)
+        record_calibration("dev_cal", "usr_tech", "admin", REFERENCE, dict(REFERENCE), calibrated_at=first)
+        latest = record_calibration(
+            "dev_cal", "usr_tech", "admin", REFERENCE, dict(REFERENCE), calibrated_at=first + timedelta(days=90)
+        )
+
+        history = get_calibration_history("dev_cal")
+
+        self.assertEqual([c["calibratedAt"] for c in history],
+                         [latest["calibratedAt"], first.isoformat()])
+        self.assertEqual(get_latest_calibration("dev_cal")["id"], latest["id"])
+
+    def test_latest_without_history(self):
+        self.assertIsNone(get_latest_calibration("dev_cal"))
+
+
+if __name__ == "__main__":
+    unittest.main()
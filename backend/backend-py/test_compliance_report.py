@@ -0,0 +1,187 @@
+"""
+Tests for the HIPAA compliance report (grouping, CSV / PDF output, endpoint)
+
+Run with: python -m pytest test_compliance_report.py -v
+"""
+
+import os
+import sys
+import csv
+import io
+import asyncio
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from compliance_report import (
+    CsvComplianceReportGenerator, PdfComplianceReportGenerator, build_compliance_report, generator_for,
+    load_compliance_report
+)
+from report_pdf import ReportParameters, count_pages
+from fastapi import HTTPException
+
+START = datetime(2025, 6, 1, tzinfo=timezone.utc)
+END = datetime(2025, 6, 30, 23, 59, tzinfo=timezone.utc)
+PARAMS = ReportParameters(generated_by="Ada Admin", generated_at=datetime(2025, 7, 1, 8, 0, tzinfo=timezone.utc))
+
+
+def _log(timestamp, event_type, severity="INFO", **fields):
+    return {"pk": "AUDIT#ALL", "sk": timestamp, "timestamp": timestamp, "eventType": event_type, "severity": severity, **fields}
+
+
+# Synthetic audit log of June 2025
+AUDIT_FIXTURE = [
+    _log("2025-06-02T09:00:00+00:00", "PATIENT_DATA_ACCESS", userId="doc-1", userRole="doctor",
+         resourceType="patient", resourceId="pat-1", ipAddress="10.0.0.1"),
+    _log("2025-06-03T10:00:00+00:00", "PATIENT_DATA_ACCESS", userId="doc-1", userRole="doctor",
+         resourceType="patient", resourceId="pat-1", ipAddress="10.0.0.1"),
+    _log("2025-06-04T11:00:00+00:00", "DATA_EXPORT", userId="doc-1", userRole="doctor",
+         resourceType="patient_data", resourceId="pat-1", ipAddress="10.0.0.1"),
+    _log("2025-06-05T12:00:00+00:00", "REPORT_DOWNLOADED", userId="pat-2", userRole="patient",
+         resourceType="report", resourceId="RPT-1", ipAddress="10.0.0.2"),
+    _log("2025-06-06T13:00:00+00:00", "AUTHZ_ACCESS_DENIED", "ERROR", userId="pat-2", userRole="patient",
+         resourceType="patient", resourceId="pat-1", ipAddress="10.0.0.2"),
+    _log("2025-06-07T14:00:00+00:00", "AUTH_LOGIN_FAILURE", "ERROR", ipAddress="203.0.113.9",
+         details={"email": "ev***@example.com", "reason": "invalid_password"}),
+    _log("2025-06-07T14:01:00+00:00", "AUTH_LOGIN_FAILURE", "ERROR", ipAddress="203.0.113.9",
+         details={"email": "ev***@example.com", "reason": "invalid_password"}),
+    _log("2025-06-08T15:00:00+00:00", "AUTH_MFA_FAILURE", "ERROR", userId="doc-1", ipAddress="10.0.0.1"),
+    _log("2025-06-09T16:00:00+00:00", "DATA_DELETE", "WARNING", userId="adm-1", userRole="admin",
+         resourceType="device", resourceId="dev-1", ipAddress="10.0.0.3"),
+    # Routine events outside the report's sections
+    _log("2025-06-10T17:00:00+00:00", "AUTH_LOGIN_SUCCESS", userId="doc-1", ipAddress="10.0.0.1"),
+    _log("2025-06-11T18:00:00+00:00", "DATA_READ", userId="doc-1", resourceType="device", resourceId="dev-1"),
+]
+
+
+def _csv_rows(content: bytes):
+    return list(csv.reader(io.StringIO(content.decode("utf-8"))))
+
+
+def _section(rows, title):
+    """Data rows of a CSV section (after its title and header row)."""
+    start = rows.index([title]) + 2
+    end = next((i for i in range(start, len(rows)) if not rows[i]), len(rows))
+    return rows[start:end]
+
+
+class TestBuildComplianceReport(unittest.TestCase):
+    """Test grouping of the audit entries into the report's sections."""
+
+    def setUp(self):
+        self.data = build_compliance_report(AUDIT_FIXTURE, START, END)
+
+    def test_access_grouped_by_user_and_resource(self):
+        rows = [(r.user_id, r.resource_type, r.resource_id, r.access_count, r.last_accessed) for r in self.data.access_summary]
+        self.assertEqual(rows, [
+            ("doc-1", "patient", "pat-1", 2, "2025-06-03T10:00:00+00:00"),
+            ("doc-1", "patient_data", "pat-1", 1, "2025-06-04T11:00:00+00:00"),
+            ("pat-2", "patient", "pat-1", 1, "2025-06-06T13:00:00+00:00"),
+            ("pat-2", "report", "RPT-1", 1, "2025-06-05T12:00:00+00:00"),
+        ])
+
+    def test_incidents_warning_or_higher_newest_first(self):
+        self.assertEqual([i.event_type for i in self.data.incidents], [
+            "DATA_DELETE", "AUTH_MFA_FAILURE", "AUTH_LOGIN_FAILURE", "AUTH_LOGIN_FAILURE", "AUTHZ_ACCESS_DENIED",
+        ])
+        self.assertEqual(self.data.incidents[0].resource, "device/dev-1")
+
+    def test_failed_auth_grouped_by_ip_and_user(self):
+        rows = [(r.ip_address, r.user, r.attempts) for r in self.data.failed_auth]
+        self.assertEqual(rows, [("203.0.113.9", "ev***@example.com", 2), ("10.0.0.1", "doc-1", 1)])
+
+    def test_no_logs(self):
+        data = build_compliance_report([], START, END)
+        self.assertEqual((data.access_summary, data.incidents, data.failed_auth), ([], [], []))
+
+
+class TestComplianceReportOutput(unittest.TestCase):
+    """Test the CSV and PDF generators."""
+
+    def setUp(self):
+        self.data = build_compliance_report(AUDIT_FIXTURE, START, END)
+
+    def test_csv_sections(self):
+        rows = _csv_rows(CsvComplianceReportGenerator().generate(self.data, PARAMS))
+        self.assertEqual(rows[0], ["Compliance Report", START.isoformat(), END.isoformat()])
+        self.assertIn(["doc-1", "doctor", "patient", "pat-1", "2", "2025-06-03T10:00:00+00:00"],
+                      _section(rows, "Data Access Summary"))
+        self.assertEqual(len(_section(rows, "Security Incidents")), 5)
+        self.assertEqual(_section(rows, "Failed Authentication Attempts")[0],
+                         ["203.0.113.9", "ev***@example.com", "2", "2025-06-07T14:01:00+00:00"])
+
+    def test_csv_formula_cells_neutralized(self):
+        logs = [_log("2025-06-07T14:00:00+00:00", "AUTH_LOGIN_FAILURE", "ERROR", ipAddress="1.2.3.4",
+                     details={"email": "=HYPERLINK(\"http://x\")"})]
+        rows = _csv_rows(CsvComplianceReportGenerator().generate(build_compliance_report(logs, START, END), PARAMS))
+        self.assertEqual(_section(rows, "Failed Authentication Attempts")[0][1], "'=HYPERLINK(\"http://x\")")
+
+    def test_pdf(self):
+        pdf = PdfComplianceReportGenerator().generate(self.data, PARAMS)
+        self.assertTrue(pdf.startswith(b"%PDF-"))
+        self.assertGreaterEqual(count_pages(pdf), 1)
+        self.assertIn(b"Data Access Summary", pdf)
+        self.assertIn(b"Failed Authentication Attempts", pdf)
+
+    def test_generator_for(self):
+        self.assertIsInstance(generator_for("csv"), CsvComplianceReportGenerator)
+        self.assertIsInstance(generator_for("pdf"), PdfComplianceReportGenerator)
+        self.assertIsNone(generator_for("xlsx"))
+
+
+class TestComplianceReportEndpoint(unittest.TestCase):
+    """Test POST /api/v1/admin/reports/compliance."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+        db._reports.clear()
+        db._audit_logs.extend(AUDIT_FIXTURE)
+        db._audit_logs.append(_log("2025-07-02T09:00:00+00:00", "PATIENT_DATA_ACCESS", userId="doc-9",
+                                   resourceType="patient", resourceId="pat-9"))
+        self.addCleanup(patch.stopall)
+        self.upload = patch.object(main.storage, "upload_bytes").start()
+        self.log_event = patch.object(main.audit_service, "log_event").start()
+        patch.object(main.crypto_service, "_key", b"test-signing-key").start()
+        self.addCleanup(db._audit_logs.clear)
+
+    def _generate(self, start="2025-06-01", end="2025-06-30T23:59:00Z", format="csv"):
+        request = fake_request("adm-1", "admin")
+        return asyncio.run(main.generate_compliance_report.__wrapped__(request, start, end, format))
+
+    def test_load_limited_to_range(self):
+        data = load_compliance_report(START, END)
+        self.assertNotIn("doc-9", [r.user_id for r in data.access_summary])
+        self.assertEqual(len(data.access_summary), 4)
+
+    def test_generates_stored_report(self):
+        report = self._generate()["data"]
+        self.assertEqual(report["type"], "compliance")
+        self.assertEqual(report["format"], "csv")
+        self.assertTrue(report["signature"])
+        key, content, content_type = self.upload.call_args.args
+        self.assertEqual(key, report["fileKey"])
+        self.assertEqual(content_type, "text/csv")
+        self.assertEqual(len(_section(_csv_rows(content), "Data Access Summary")), 4)
+        self.assertEqual(self.log_event.call_args.kwargs["action"], "generate_compliance")
+
+    def test_invalid_format(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._generate(format="xlsx")
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_FORMAT")
+
+    def test_invalid_range(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._generate(start="2025-07-01", end="2025-06-01")
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_DATE_RANGE")
+        self.upload.assert_not_called()
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,91 @@
+"""
+Tests for MeDUSA Compliance Service
+
+Run with: python -m pytest test_compliance_service.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timedelta, timezone
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import compliance_service as compliance_module
+from compliance_service import compliance_service, ComplianceError
+
+RECORD = {
+    "userId": "usr_p1", "name": "Pat", "diagnosis": "Parkinson's", "severity": "mild",
+    "notes": "Clinician note", "doctorId": "usr_d1", "medications": ["levodopa"],
+    "tremor_index": 0.4, "is_parkinsonian": True,
+}
+
+
+class TestMinimumNecessary(unittest.TestCase):
+    """Test field restriction per role."""
+
+    def test_doctor_and_admin_see_full_record(self):
+        for role in ("doctor", "admin"):
+            self.assertEqual(compliance_service.check_minimum_necessary(role, list(RECORD)), list(RECORD))
+
+    def test_patient_cannot_see_clinician_notes(self):
+        fields = compliance_service.check_minimum_necessary("patient", list(RECORD))
+        self.assertNotIn("notes", fields)
+        self.assertNotIn("doctorId", fields)
+        self.assertIn("diagnosis", fields)
+        self.assertIn("tremor_index", fields)
+
+    def test_technician_cannot_see_diagnosis_or_medications(self):
+        record = compliance_service.filter_record("technician", RECORD)
+        self.assertEqual(set(record), {"userId", "name", "doctorId", "tremor_index"})
+
+    def test_unknown_role_rejected(self):
+        with self.assertRaises(ComplianceError):
+            compliance_service.check_minimum_necessary("guest", ["name"])
+        with self.assertRaises(ComplianceError):
+            compliance_service.check_minimum_necessary(None, ["name"])
+
+
+class TestAccessFrequency(unittest.TestCase):
+    """Test excessive access detection from audit logs."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+
+    def _log_accesses(self, count, resource_id="usr_p1", user_id="usr_d1", age=timedelta(minutes=5), event_type="PATIENT_DATA_ACCESS"):
+        sk = (datetime.now(timezone.utc) - age).isoformat()
+        for _ in range(count):
+            db.put_audit_log({"sk": sk, "eventType": event_type, "userId": user_id, "resourceId": resource_id})
+
+    def test_under_limit_allowed(self):
+        with patch.object(compliance_module, "MAX_RESOURCE_ACCESSES_PER_HOUR", 5):
+            self._log_accesses(5)
+            compliance_service.check_access_frequency("usr_d1", "usr_p1")
+
+    def test_over_limit_rejected(self):
+        with patch.object(compliance_module, "MAX_RESOURCE_ACCESSES_PER_HOUR", 5):
+            self._log_accesses(6)
+            with self.assertRaisesRegex(ComplianceError, "Excessive access pattern detected"):
+                compliance_service.check_access_frequency("usr_d1", "usr_p1")
+
+    def test_only_matching_user_resource_and_window_counted(self):
+        with patch.object(compliance_module, "MAX_RESOURCE_ACCESSES_PER_HOUR", 5):
+            self._log_accesses(5)
+            self._log_accesses(3, resource_id="usr_p2")
+            self._log_accesses(3, user_id="usr_d2")
+            self._log_accesses(3, age=timedelta(hours=2))
+            self._log_accesses(3, event_type="DATA_READ")
+            compliance_service.check_access_frequency("usr_d1", "usr_p1")
+
+    def test_window_scales_limit(self):
+        with patch.object(compliance_module, "MAX_RESOURCE_ACCESSES_PER_HOUR", 5):
+            self._log_accesses(8, age=timedelta(minutes=90))
+            compliance_service.check_access_frequency("usr_d1", "usr_p1", window_hours=2)
+            self._log_accesses(3)
+            with self.assertRaises(ComplianceError):
+                compliance_service.check_access_frequency("usr_d1", "usr_p1", window_hours=2)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,86 @@
+"""
+Tests for the low-battery checks of the connectivity monitor
+
+Run with: python -m pytest test_connectivity_monitor.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timezone, timedelta
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import connectivity_monitor
+from audit_service import AuditEventType
+
+NOW = datetime(2025, 6, 1, 9, 0, tzinfo=timezone.utc)
+
+
+def _device(device_id, level, reported=NOW - timedelta(minutes=5), **fields):
+    return {"id": device_id, "name": f"Sensor {device_id}", "patientId": "pat-1", "status": "online",
+            "batteryLevel": level, "batteryUpdatedAt": reported.isoformat(), **fields}
+
+
+class TestLowBatteryDevices(unittest.TestCase):
+    """Test the low-battery device query."""
+
+    def setUp(self):
+        db._devices.clear()
+
+    def test_below_threshold_and_recent_only(self):
+        db._devices.extend([
+            _device("DEV-LOW", 12),
+            _device("DEV-OK", 20),
+            _device("DEV-OLD", 5, reported=NOW - timedelta(hours=2)),
+            {"id": "DEV-NEVER", "batteryLevel": 100},
+        ])
+        since = (NOW - timedelta(hours=1)).isoformat()
+        self.assertEqual([d["id"] for d in db.get_low_battery_devices(20.0, since)], ["DEV-LOW"])
+
+
+class TestCheckLowBattery(unittest.TestCase):
+    """Test alerting with mocked notifications."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._patient_profiles.clear()
+        db.create_patient_profile({"userId": "pat-1", "doctorId": "doc-1"})
+        self.addCleanup(patch.stopall)
+        self.notifications = patch.object(connectivity_monitor, "notification_service").start()
+        self.audit = patch.object(connectivity_monitor, "audit_service").start()
+        self.notifications.push_to_user.return_value = 1
+        self.notifications.send_critical_alert.return_value = True
+
+    def test_patient_pushed_and_doctor_alerted(self):
+        db._devices.append(_device("DEV-1", 12))
+
+        self.assertEqual(connectivity_monitor.check_low_battery(NOW), {"alerted": 1, "skipped": 0, "failed": 0})
+
+        self.assertEqual([c.args[0] for c in self.notifications.push_to_user.call_args_list], ["pat-1", "doc-1"])
+        args = self.notifications.send_critical_alert.call_args.args
+        self.assertEqual(args[:4], ("pat-1", "DEV-1", "battery", {"batteryLevel": 12}))
+        kwargs = self.audit.log_device_event.call_args.kwargs
+        self.assertEqual((kwargs["event_type"], kwargs["action"]), (AuditEventType.DEVICE_LOW_BATTERY, "low_battery_alert"))
+        self.assertEqual(db.get_device("DEV-1")["lowBatteryAlertedAt"], NOW.isoformat())
+
+    def test_alerted_once_per_episode(self):
+        db._devices.append(_device("DEV-1", 12))
+        connectivity_monitor.check_low_battery(NOW)
+
+        result = connectivity_monitor.check_low_battery(NOW + timedelta(minutes=15))
+        self.assertEqual(result, {"alerted": 0, "skipped": 1, "failed": 0})
+        self.assertEqual(self.notifications.send_critical_alert.call_count, 1)
+
+    def test_undelivered_alert_retried(self):
+        db._devices.append(_device("DEV-1", 12))
+        self.notifications.push_to_user.return_value = 0
+        self.notifications.send_critical_alert.return_value = False
+
+        self.assertEqual(connectivity_monitor.check_low_battery(NOW)["failed"], 1)
+        self.assertNotIn("lowBatteryAlertedAt", db.get_device("DEV-1"))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,87 @@
+"""
+Tests for MeDUSA Consent Service
+
+Run with: python -m pytest test_consent_service.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timezone, timedelta
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from audit_service import audit_service, AuditEventType
+from consent_service import (
+    ConsentType,
+    ConsentRequiredError,
+    normalize_consent_type,
+    record_consent,
+    get_active_consent,
+    list_consents,
+    require_consent
+)
+
+SHARING = ConsentType.DATA_SHARING.value
+
+
+class TestConsentTypes(unittest.TestCase):
+    """Test consent type validation."""
+
+    def test_standard_and_custom_types(self):
+        self.assertEqual(normalize_consent_type("Data_Sharing"), SHARING)
+        self.assertEqual(normalize_consent_type("custom:photo_use"), "custom:photo_use")
+
+    def test_unknown_types_rejected(self):
+        for value in ["marketing", "custom:", "custom:a#b"]:
+            with self.assertRaises(ValueError):
+                normalize_consent_type(value)
+
+
+class TestConsentRecords(unittest.TestCase):
+    """Test recording and resolving consents."""
+
+    def setUp(self):
+        db._consents.clear()
+
+    def _record(self, consent_type=SHARING, granted=True, **kwargs):
+        return record_consent("usr_p1", consent_type, granted, "usr_p1", "patient", **kwargs)
+
+    def test_no_consent_by_default(self):
+        self.assertIsNone(get_active_consent("usr_p1", SHARING))
+        with self.assertRaises(ConsentRequiredError):
+            require_consent("usr_p1", SHARING)
+
+    def test_granted_consent_is_active(self):
+        record = self._record(witness="Nurse Joy")
+        self.assertEqual(get_active_consent("usr_p1", SHARING)["id"], record["id"])
+        self.assertEqual(require_consent("usr_p1", SHARING)["witness"], "Nurse Joy")
+
+    def test_revocation_wins_over_earlier_grant(self):
+        self._record()
+        self._record(granted=False)
+        self.assertIsNone(get_active_consent("usr_p1", SHARING))
+        self.assertEqual(len(list_consents("usr_p1")), 2)
+
+    def test_expired_consent_is_inactive(self):
+        self._record(expires_at=datetime.now(timezone.utc) + timedelta(days=1))
+        self.assertIsNotNone(get_active_consent("usr_p1", SHARING))
+        later = datetime.now(timezone.utc) + timedelta(days=2)
+        self.assertIsNone(get_active_consent("usr_p1", SHARING, now=later))
+
+    def test_types_are_independent(self):
+        self._record(ConsentType.RESEARCH_PARTICIPATION.value)
+        self.assertIsNone(get_active_consent("usr_p1", SHARING))
+
+    def test_changes_are_audited_at_warning(self):
+        with patch.object(audit_service, "log_event") as log_event:
+            self._record()
+            self._record(granted=False)
+        events = [c.kwargs["event_type"] for c in log_event.call_args_list]
+        self.assertEqual(events, [AuditEventType.CONSENT_GRANTED, AuditEventType.CONSENT_REVOKED])
+        self.assertEqual(audit_service._get_severity_for_event(AuditEventType.CONSENT_REVOKED).value, "WARNING")
+
+
+if __name__ == '__main__':
+    unittest.main()
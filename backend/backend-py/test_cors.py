@@ -0,0 +1,104 @@
+"""
+Tests for CORS headers on Lambda proxy responses
+
+Run with: python -m pytest test_cors.py -v
+"""
+
+import os
+import json
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+
+from fastapi.testclient import TestClient
+
+import cors
+import device_data_ingest
+import main
+from cors import add_cors_headers, allowed_origins_from_env, handle_options
+
+APP_ORIGIN = "https://app.medusa.example"
+ALLOWED = [APP_ORIGIN, "http://localhost:3000"]
+
+
+class TestAllowedOrigins(unittest.TestCase):
+    """Test parsing ALLOWED_ORIGINS."""
+
+    def test_comma_separated_list(self):
+        self.assertEqual(allowed_origins_from_env(f" {APP_ORIGIN} , http://localhost:3000,"), ALLOWED)
+
+    def test_empty_allows_any(self):
+        self.assertEqual(allowed_origins_from_env(""), ["*"])
+
+
+class TestAddCorsHeaders(unittest.TestCase):
+    """Test headers are added only for allowed origins."""
+
+    def _response(self):
+        return {"statusCode": 400, "headers": {"Content-Type": "application/json"}, "body": "{}"}
+
+    def test_allowed_origin_echoed(self):
+        headers = add_cors_headers(self._response(), APP_ORIGIN, ALLOWED)["headers"]
+        self.assertEqual(headers["Access-Control-Allow-Origin"], APP_ORIGIN)
+        self.assertEqual(headers["Vary"], "Origin")
+        self.assertEqual(headers["Access-Control-Max-Age"], "600")
+        self.assertIn("PATCH", headers["Access-Control-Allow-Methods"])
+        self.assertEqual(headers["Content-Type"], "application/json")
+
+    def test_disallowed_origin_gets_no_cors_headers(self):
+        headers = add_cors_headers(self._response(), "https://evil.example", ALLOWED)["headers"]
+        self.assertEqual(headers, {"Content-Type": "application/json"})
+
+    def test_wildcard_allows_any_origin(self):
+        headers = add_cors_headers(self._response(), "https://anything.example", ["*"])["headers"]
+        self.assertEqual(headers["Access-Control-Allow-Origin"], "*")
+        self.assertNotIn("Vary", headers)
+
+    def test_preflight(self):
+        resp = handle_options(APP_ORIGIN, ALLOWED)
+        self.assertEqual(resp["statusCode"], 200)
+        self.assertEqual(resp["headers"]["Access-Control-Allow-Origin"], APP_ORIGIN)
+
+
+class TestIngestCors(unittest.TestCase):
+    """Test the ingestion Lambda applies CORS on every path."""
+
+    def setUp(self):
+        p = patch.object(cors, "ALLOWED_ORIGINS", ALLOWED)
+        p.start()
+        self.addCleanup(p.stop)
+
+    def test_error_response_has_cors_headers(self):
+        event = {"headers": {"Origin": APP_ORIGIN}, "body": json.dumps({"readings": []})}
+        resp = device_data_ingest.ingest(event, None)
+        self.assertEqual(resp["statusCode"], 401)
+        self.assertEqual(resp["headers"]["Access-Control-Allow-Origin"], APP_ORIGIN)
+
+    def test_options_preflight_answered(self):
+        resp = device_data_ingest.ingest({"httpMethod": "OPTIONS", "headers": {"origin": APP_ORIGIN}}, None)
+        self.assertEqual(resp["statusCode"], 200)
+        self.assertIn("X-Api-Key", resp["headers"]["Access-Control-Allow-Headers"])
+
+
+class TestApiCors(unittest.TestCase):
+    """Test the API's CORS middleware wraps the middleware that answer early."""
+
+    def test_missing_token_401_has_cors_headers(self):
+        resp = TestClient(main.app).get("/api/v1/me/profile", headers={"Origin": APP_ORIGIN})
+        self.assertEqual(resp.status_code, 401)
+        self.assertIn(resp.headers["access-control-allow-origin"], ("*", APP_ORIGIN))
+
+    def test_preflight_answered_without_token(self):
+        resp = TestClient(main.app).options("/api/v1/me/profile", headers={
+            "Origin": APP_ORIGIN,
+            "Access-Control-Request-Method": "GET",
+            "Access-Control-Request-Headers": "Authorization",
+        })
+        self.assertEqual(resp.status_code, 200)
+        self.assertIn("access-control-allow-origin", resp.headers)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,90 @@
+"""
+Tests for report signing and one-time codes
+
+Run with: python -m pytest test_crypto_service.py -v
+"""
+
+import hmac
+import hashlib
+import unittest
+from unittest.mock import patch
+
+import crypto_service
+from crypto_service import CryptoService, SigningKeyMissingError
+
+KEY = "report-signing-key-for-tests"
+REPORT = b"%PDF-1.7 tremor report for usr_p1"
+
+
+class TestCryptoService(unittest.TestCase):
+    """Test HMAC signing and verification."""
+
+    def setUp(self):
+        self.crypto = CryptoService(KEY)
+
+    def test_signature_is_hex_hmac_sha256(self):
+        expected = hmac.new(KEY.encode(), REPORT, hashlib.sha256).hexdigest()
+        self.assertEqual(self.crypto.sign_data(REPORT), expected)
+
+    def test_verify_roundtrip(self):
+        self.assertTrue(self.crypto.verify_signature(REPORT, self.crypto.sign_data(REPORT)))
+
+    def test_modified_data_fails(self):
+        signature = self.crypto.sign_data(REPORT)
+        self.assertFalse(self.crypto.verify_signature(REPORT + b" ", signature))
+
+    def test_other_key_fails(self):
+        signature = CryptoService("another-key").sign_data(REPORT)
+        self.assertFalse(self.crypto.verify_signature(REPORT, signature))
+
+    def test_missing_or_malformed_signature_fails(self):
+        self.assertFalse(self.crypto.verify_signature(REPORT, None))
+        self.assertFalse(self.crypto.verify_signature(REPORT, ""))
+        self.assertFalse(self.crypto.verify_signature(REPORT, "not-hex"))
+
+    def test_uppercase_signature_accepted(self):
+        self.assertTrue(self.crypto.verify_signature(REPORT, self.crypto.sign_data(REPORT).upper()))
+
+    def test_missing_key_raises(self):
+        with self.assertRaises(SigningKeyMissingError):
+            CryptoService(None).sign_data(REPORT)
+
+
+class TestOneTimeCodes(unittest.TestCase):
+    """Test OTP generation, hashing and verification."""
+
+    def setUp(self):
+        self.crypto = CryptoService()
+
+    def test_numeric_of_requested_length(self):
+        for length in (4, 6, 8):
+            otp = self.crypto.generate_otp(length)
+            self.assertEqual(len(otp), length)
+            self.assertTrue(otp.isdigit())
+
+    def test_biased_bytes_are_rejected(self):
+        # 250-255 would make digits 0-5 more likely; they must be redrawn
+        draws = iter([bytes([255, 3, 250]), bytes([9]), bytes([249])])
+        with patch.object(crypto_service.os, "urandom", side_effect=lambda n: next(draws)):
+            self.assertEqual(self.crypto.generate_otp(3), "399")
+
+    def test_invalid_length(self):
+        with self.assertRaises(ValueError):
+            self.crypto.generate_otp(0)
+
+    def test_hash_roundtrip(self):
+        otp_hash = self.crypto.hash_otp("123456")
+        self.assertNotIn("123456", otp_hash)
+        self.assertTrue(self.crypto.verify_otp("123456", otp_hash))
+        self.assertFalse(self.crypto.verify_otp("654321", otp_hash))
+
+    def test_hash_uses_light_parameters(self):
+        self.assertIn("m=4096,t=1", self.crypto.hash_otp("123456"))
+
+    def test_missing_values_fail(self):
+        self.assertFalse(self.crypto.verify_otp("", self.crypto.hash_otp("1")))
+        self.assertFalse(self.crypto.verify_otp("123456", None))
+
+
+if __name__ == '__main__':
+    unittest.main()
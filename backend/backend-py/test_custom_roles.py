@@ -0,0 +1,192 @@
+"""
+Tests for custom roles
+
+Run with: python -m pytest test_custom_roles.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from types import SimpleNamespace
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-custom-roles-0123456789')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from fastapi import HTTPException
+
+import db
+import main
+from models import RoleCreateReq
+from request_context import organization_scope
+from rbac import (
+    CustomRole, parse_custom_role, role_name, get_role_permissions, require_role, get_user_role,
+    READINGS_READ, READINGS_EXPORT
+)
+
+RADIOLOGIST = CustomRole("radiologist", (READINGS_READ,)).as_str()
+
+
+class TestCustomRoleEncoding(unittest.TestCase):
+    """Test the "custom:<name>:<permissions>" encoding."""
+
+    def test_round_trip(self):
+        self.assertEqual(RADIOLOGIST, "custom:radiologist:readings:read")
+        self.assertEqual(parse_custom_role(RADIOLOGIST), CustomRole("radiologist", (READINGS_READ,)))
+
+    def test_builtin_and_malformed_roles_are_not_custom(self):
+        for role in ("doctor", None, "", "custom:", "custom:Bad Name:readings:read"):
+            self.assertIsNone(parse_custom_role(role))
+
+    def test_role_name(self):
+        self.assertEqual(role_name(RADIOLOGIST), "radiologist")
+        self.assertEqual(role_name("doctor"), "doctor")
+
+    def test_permissions(self):
+        self.assertEqual(get_role_permissions(RADIOLOGIST), {READINGS_READ})
+        self.assertEqual(get_role_permissions("doctor"), {READINGS_READ, READINGS_EXPORT})
+        self.assertEqual(get_role_permissions("unknown"), frozenset())
+
+    def test_get_user_role_returns_name(self):
+        self.assertEqual(get_user_role(fake_request("usr_1", RADIOLOGIST)), "radiologist")
+
+
+class TestRequireRolePermission(unittest.TestCase):
+    """Test custom roles pass require_role only through a permission they hold."""
+
+    def _call(self, decorator, role):
+        @decorator
+        async def endpoint(request):
+            return "ok"
+        return asyncio.run(endpoint(request=fake_request("usr_1", role)))
+
+    def test_held_permission_admits_custom_role(self):
+        self.assertEqual(self._call(require_role("doctor", permission=READINGS_READ), RADIOLOGIST), "ok")
+
+    def test_missing_permission_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._call(require_role("doctor", permission=READINGS_EXPORT), RADIOLOGIST)
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_endpoint_without_permission_rejects_custom_role(self):
+        with self.assertRaises(HTTPException):
+            self._call(require_role("doctor", "admin"), RADIOLOGIST)
+
+    def test_builtin_roles_unchanged(self):
+        self.assertEqual(self._call(require_role("doctor", permission=READINGS_READ), "doctor"), "ok")
+        with self.assertRaises(HTTPException):
+            self._call(require_role("admin", permission=READINGS_READ), "patient-ish")
+
+
+class TestRoleEndpoints(unittest.TestCase):
+    """Test defining custom roles and assigning them to users."""
+
+    def setUp(self):
+        db._roles.clear()
+        db._users.clear()
+        patcher = patch.object(main.audit_service, "log_event")
+        self.log_event = patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _create(self, name, permissions):
+        return asyncio.run(main.create_role.__wrapped__(
+            RoleCreateReq(name=name, permissions=permissions), fake_request("usr_admin", "admin")
+        ))
+
+    def test_create_role(self):
+        role = self._create("Radiologist", ["readings:read"])
+        self.assertEqual(role.name, "radiologist")
+        self.assertEqual(role.permissions, ["readings:read"])
+        self.assertEqual(db.get_role("radiologist")["createdBy"], "usr_admin")
+        self.assertEqual(self.log_event.call_args.kwargs["action"], "create_role")
+
+    def test_duplicate_role_rejected(self):
+        self._create("radiologist", ["readings:read"])
+        with self.assertRaises(HTTPException) as ctx:
+            self._create("radiologist", ["readings:export"])
+        self.assertEqual(ctx.exception.status_code, 409)
+
+    def test_permissions_beyond_doctor_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._create("auditor", ["users:delete"])
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_PERMISSION")
+
+    def test_builtin_name_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._create("doctor", ["readings:read"])
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_ROLE")
+
+    def test_user_created_with_custom_role_stores_permissions(self):
+        self._create("radiologist", ["readings:read"])
+        req = main.CreateAdminReq(email="rad@example.com", password="x", role="radiologist")
+        strength = SimpleNamespace(is_valid=True)
+        with patch.object(main, "validate_password_strength", return_value=strength), \
+             patch.object(main, "generate_mfa_secret", return_value="JBSWY3DPEHPK3PXP"), \
+             patch.object(main, "email_service"):
+            res = asyncio.run(main.create_admin_user.__wrapped__(req, fake_request("usr_admin", "admin")))
+
+        self.assertEqual(res.role, "radiologist")
+        self.assertEqual(db.get_user(res.userId)["role"], RADIOLOGIST)
+
+    def test_unknown_role_rejected(self):
+        req = main.CreateAdminReq(email="x@example.com", password="x", role="nurse")
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.create_admin_user.__wrapped__(req, fake_request("usr_admin", "admin")))
+        self.assertEqual(ctx.exception.status_code, 400)
+
+    def test_roles_are_per_organization(self):
+        with organization_scope("org_a"):
+            self._create("radiologist", ["readings:read"])
+        with organization_scope("org_b"):
+            self.assertIsNone(db.get_role("radiologist"))
+            self.assertEqual(db.list_roles(), [])
+            self._create("radiologist", ["readings:export"])
+        with organization_scope("org_a"):
+            self.assertEqual(db.get_role("radiologist")["permissions"], ["readings:read"])
+
+    def _update_role(self, role):
+        db._users["usr_x"] = {"id": "usr_x", "email": "x@example.com", "role": "doctor"}
+        request = fake_request("usr_admin", "admin")
+        request.json = lambda: asyncio.sleep(0, result={"role": role})
+        return asyncio.run(main.update_user.__wrapped__(request, "usr_x"))
+
+    def test_update_user_assigns_custom_role_permissions(self):
+        self._create("radiologist", ["readings:read"])
+        self._update_role("Radiologist")
+        self.assertEqual(db.get_user("usr_x")["role"], RADIOLOGIST)
+
+    def test_update_user_rejects_unknown_role(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._update_role("superuser")
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_ROLE")
+        self.assertEqual(db.get_user("usr_x")["role"], "doctor")
+
+
+class TestCustomRolePatientAccess(unittest.TestCase):
+    """Custom roles reach only patients assigned to them, like doctors."""
+
+    def setUp(self):
+        db._patient_profiles.clear()
+        db._patient_profiles["pat-1"] = {"userId": "pat-1", "doctorId": "usr_rad"}
+
+    def test_assigned_patient_allowed(self):
+        main._check_patient_access("usr_rad", "radiologist", "pat-1")
+
+    def test_unassigned_patient_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            main._check_patient_access("usr_other", "radiologist", "pat-1")
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_device_of_assigned_patient_only(self):
+        with patch.object(main.db, "get_device", return_value={"id": "dev-1", "patientId": "pat-1"}):
+            main._get_visible_device("dev-1", fake_request("usr_rad", RADIOLOGIST))
+            with self.assertRaises(HTTPException) as ctx:
+                main._get_visible_device("dev-1", fake_request("usr_other", RADIOLOGIST))
+        self.assertEqual(ctx.exception.status_code, 403)
+
+
+if __name__ == '__main__':
+    unittest.main()
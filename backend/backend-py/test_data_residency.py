@@ -0,0 +1,148 @@
+"""
+Tests for data residency routing of S3 files
+
+Run with: python -m pytest test_data_residency.py -v
+"""
+
+import os
+import sys
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import aws_clients
+import db
+import encryption_service
+import main
+import storage
+from fastapi import HTTPException
+from models import PresignReq
+
+EU_KEY_ARN = "arn:aws:kms:eu-central-1:123456789012:key/eu"
+EU_TARGETS = storage._load_residency_targets(
+    '{"eu": {"bucket": "medusa-data-eu", "region": "eu-central-1", "kmsKeyArn": "%s"}}' % EU_KEY_ARN
+)
+
+
+class ResidencyTestCase(unittest.TestCase):
+
+    def setUp(self):
+        self.default_s3 = MagicMock()
+        self.eu_s3 = MagicMock()
+        self.regional_clients = {}
+
+        def regional_client(service, region_name=None):
+            self.regional_clients[region_name] = self.eu_s3
+            return self.eu_s3
+
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.default_s3).start()
+        patch.object(storage, "DATA_RESIDENCY_TARGETS", EU_TARGETS).start()
+        patch.object(aws_clients, "get_client", side_effect=regional_client).start()
+        patch.dict(os.environ, {"S3_BUCKET": "medusa-data"}).start()
+        patch.object(encryption_service, "S3_ENCRYPTION_MODE", "sse-kms").start()
+        patch.object(encryption_service, "KMS_KEY_ARN", "arn:aws:kms:us-east-1:123456789012:key/default").start()
+
+
+class TestResidencyTargets(ResidencyTestCase):
+    """Test target resolution."""
+
+    def test_parse_targets(self):
+        self.assertEqual(EU_TARGETS["eu"], storage.ResidencyTarget("medusa-data-eu", "eu-central-1", EU_KEY_ARN))
+        self.assertEqual(storage._load_residency_targets(""), {})
+        with self.assertRaises(ValueError):
+            storage._load_residency_targets('{"eu": {"region": "eu-central-1"}}')
+
+    def test_unconfigured_residency_is_an_error(self):
+        self.assertIsNone(storage.residency_target(None))
+        self.assertEqual(storage.residency_target("EU").bucket, "medusa-data-eu")
+        with self.assertRaises(storage.DataResidencyError):
+            storage.upload_bytes("reports/usr_1/r.pdf", b"%PDF-", "application/pdf", residency="apac")
+        self.default_s3.put_object.assert_not_called()
+
+    def test_key_owner(self):
+        self.assertEqual(storage.key_owner("poses/usr_1/1_a.json"), "usr_1")
+        self.assertEqual(storage.key_owner("profile-pictures/usr_2/avatar.png"), "usr_2")
+        self.assertIsNone(storage.key_owner("exports/usr_1/1_export.csv"))
+        self.assertIsNone(storage.key_owner("reports/orphan.pdf"))
+
+
+class TestResidencyUploads(ResidencyTestCase):
+    """EU-flagged files go to the EU bucket, everything else to the default one."""
+
+    def test_eu_upload_targets_eu_bucket(self):
+        storage.upload_bytes("reports/usr_1/r.pdf", b"%PDF-", "application/pdf", residency="eu")
+
+        kwargs = self.eu_s3.put_object.call_args.kwargs
+        self.assertEqual(kwargs["Bucket"], "medusa-data-eu")
+        self.assertEqual(kwargs["SSEKMSKeyId"], EU_KEY_ARN)
+        self.assertEqual(list(self.regional_clients), ["eu-central-1"])
+        self.default_s3.put_object.assert_not_called()
+
+    def test_default_upload_targets_default_bucket(self):
+        storage.upload_bytes("reports/usr_1/r.pdf", b"%PDF-", "application/pdf")
+
+        kwargs = self.default_s3.put_object.call_args.kwargs
+        self.assertEqual(kwargs["Bucket"], "medusa-data")
+        self.assertEqual(kwargs["SSEKMSKeyId"], encryption_service.KMS_KEY_ARN)
+        self.eu_s3.put_object.assert_not_called()
+
+    def test_eu_presigned_post_uses_regional_key(self):
+        storage.presign_upload("poses/usr_1/1_a.json", "application/json", residency="eu")
+
+        kwargs = self.eu_s3.generate_presigned_post.call_args.kwargs
+        self.assertEqual(kwargs["Bucket"], "medusa-data-eu")
+        self.assertEqual(kwargs["Fields"]["x-amz-server-side-encryption-aws-kms-key-id"], EU_KEY_ARN)
+
+
+class TestResidencyFromPatient(ResidencyTestCase):
+    """main picks the bucket from the patient's dataResidency."""
+
+    def setUp(self):
+        super().setUp()
+        db._users.clear()
+        db.put_user({"id": "usr_eu", "email": "eu@example.com", "role": "patient", "dataResidency": "eu"})
+        db.put_user({"id": "usr_us", "email": "us@example.com", "role": "patient"})
+        self.default_s3.generate_presigned_post.return_value = {"url": "https://default"}
+        self.eu_s3.generate_presigned_post.return_value = {"url": "https://eu"}
+
+    def test_presign_for_eu_patient(self):
+        req = PresignReq(filename="pose.json", contentType="application/json", scope="pose", patientId="usr_eu")
+        res = main.files_presign(req, fake_request("usr_doc", "doctor"))
+        self.assertEqual(res.uploadUrl, "https://eu")
+        self.assertEqual(self.eu_s3.generate_presigned_post.call_args.kwargs["Bucket"], "medusa-data-eu")
+
+    def test_presign_for_default_patient(self):
+        req = PresignReq(filename="pose.json", contentType="application/json", scope="pose")
+        res = main.files_presign(req, fake_request("usr_us", "patient"))
+        self.assertEqual(res.uploadUrl, "https://default")
+        self.eu_s3.generate_presigned_post.assert_not_called()
+
+    def test_download_resolves_owner_residency(self):
+        main.files_get("poses/usr_eu/1_pose.json")
+        self.assertEqual(self.eu_s3.generate_presigned_url.call_args.kwargs["Params"]["Bucket"], "medusa-data-eu")
+
+    def test_admin_sets_residency(self):
+        body = {"dataResidency": "EU"}
+        request = fake_request("usr_admin", "admin")
+        request.json = lambda: _async(body)
+        with patch.object(main.audit_service, "log_event"):
+            main.asyncio.run(main.update_user.__wrapped__(request, "usr_us"))
+        self.assertEqual(db.get_user("usr_us")["dataResidency"], "eu")
+
+        body["dataResidency"] = "apac"
+        with self.assertRaises(HTTPException) as ctx:
+            main.asyncio.run(main.update_user.__wrapped__(request, "usr_us"))
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_RESIDENCY")
+
+
+async def _async(value):
+    return value
+
+
+if __name__ == '__main__':
+    unittest.main()
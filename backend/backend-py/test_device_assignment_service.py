@@ -0,0 +1,153 @@
+"""
+Tests for device assignment
+
+Run with: python -m pytest test_device_assignment_service.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import MagicMock, patch
+
+from botocore.exceptions import ClientError
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from audit_service import audit_service, AuditEventType
+from dynamo_update import ConditionFailedError
+from device_assignment_service import (
+    assign_device_to_patient,
+    unassign_device,
+    get_assignment_history,
+    DeviceAlreadyAssignedError,
+    AssignmentConflictError,
+    PatientNotFoundError,
+)
+
+
+def _reset():
+    db._devices.clear()
+    db._users.clear()
+    db._patient_profiles.clear()
+    db._devices.append({"id": "DEV-1", "name": "Wristband", "patientId": None})
+    for pid in ("usr_p1", "usr_p2"):
+        db._users[pid] = {"id": pid, "role": "patient", "email": f"{pid}@example.com"}
+        db._patient_profiles[pid] = {"userId": pid, "doctorId": "usr_doc"}
+
+
+class TestAssignDevice(unittest.TestCase):
+    """Test assignment keeps device and profile in step."""
+
+    def setUp(self):
+        _reset()
+
+    def test_assign_updates_device_and_profile(self):
+        with patch.object(audit_service, "log_event") as log_event:
+            device = assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        self.assertEqual(device["patientId"], "usr_p1")
+        self.assertEqual(db._patient_profiles["usr_p1"]["assignedDevices"], ["DEV-1"])
+        self.assertEqual(log_event.call_args.kwargs["event_type"], AuditEventType.DEVICE_BIND)
+        self.assertEqual([h["action"] for h in get_assignment_history("DEV-1")], ["assign"])
+
+    def test_assigned_to_other_patient_is_rejected(self):
+        assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        with self.assertRaises(DeviceAlreadyAssignedError) as ctx:
+            assign_device_to_patient("DEV-1", "usr_p2", "usr_doc", "doctor")
+
+        self.assertEqual(ctx.exception.current_patient_id, "usr_p1")
+        self.assertEqual(db.get_device("DEV-1")["patientId"], "usr_p1")
+        self.assertNotIn("assignedDevices", db._patient_profiles["usr_p2"])
+
+    def test_force_moves_device_between_patients(self):
+        assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        with patch.object(audit_service, "log_event") as log_event:
+            assign_device_to_patient("DEV-1", "usr_p2", "usr_admin", "admin", force=True)
+
+        self.assertEqual(db.get_device("DEV-1")["patientId"], "usr_p2")
+        self.assertEqual(db._patient_profiles["usr_p1"]["assignedDevices"], [])
+        self.assertEqual(db._patient_profiles["usr_p2"]["assignedDevices"], ["DEV-1"])
+        events = [c.kwargs["event_type"] for c in log_event.call_args_list]
+        self.assertEqual(events, [AuditEventType.DEVICE_UNBIND, AuditEventType.DEVICE_BIND])
+        last = get_assignment_history("DEV-1")[-1]
+        self.assertEqual((last["action"], last["previousPatientId"]), ("reassign", "usr_p1"))
+
+    def test_reassigning_same_patient_is_a_no_op(self):
+        assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+        assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        self.assertEqual(len(get_assignment_history("DEV-1")), 1)
+
+    def test_unknown_patient(self):
+        with self.assertRaises(PatientNotFoundError):
+            assign_device_to_patient("DEV-1", "usr_missing", "usr_doc", "doctor")
+
+    def test_unassign(self):
+        assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        device = unassign_device("DEV-1", "usr_doc", "doctor")
+
+        self.assertIsNone(device["patientId"])
+        self.assertEqual(db._patient_profiles["usr_p1"]["assignedDevices"], [])
+
+    def test_concurrent_change_leaves_both_sides_untouched(self):
+        """Test a failed condition changes neither the device nor the profile."""
+        real_get_device = db.get_device
+
+        def stale_get_device(device_id):
+            # Another request assigned the device after we read it
+            device = dict(real_get_device(device_id))
+            db._devices[0]["patientId"] = "usr_p2"
+            return device
+
+        with patch.object(db, "get_device", side_effect=stale_get_device):
+            with self.assertRaises(AssignmentConflictError):
+                assign_device_to_patient("DEV-1", "usr_p1", "usr_doc", "doctor")
+
+        self.assertEqual(db._devices[0]["patientId"], "usr_p2")
+        self.assertNotIn("assignedDevices", db._patient_profiles["usr_p1"])
+        self.assertNotIn("assignmentHistory", db._devices[0])
+
+
+class TestAssignmentTransaction(unittest.TestCase):
+    """Test the DynamoDB TransactWriteItems request."""
+
+    def setUp(self):
+        self.ddb = MagicMock()
+        devices, profiles = MagicMock(), MagicMock()
+        devices.name, profiles.name = "medusa-devices", "medusa-patient-profiles"
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "ddb", self.ddb, create=True).start()
+        patch.object(db, "T_DEVICES", devices, create=True).start()
+        patch.object(db, "T_PATIENT_PROFILES", profiles, create=True).start()
+
+    def test_device_and_both_profiles_in_one_transaction(self):
+        entry = {"action": "reassign", "patientId": "usr_p2", "by": "usr_admin", "at": "2025-03-01T00:00:00+00:00"}
+
+        db.set_device_assignment("DEV-1", "usr_p2", "usr_p1", entry, add_to_profile="usr_p2", remove_from_profile="usr_p1")
+
+        items = self.ddb.meta.client.transact_write_items.call_args.kwargs["TransactItems"]
+        self.assertEqual(len(items), 3)
+        device, added, removed = (i["Update"] for i in items)
+        self.assertEqual(device["TableName"], "medusa-devices")
+        self.assertEqual(device["ConditionExpression"], "patientId = :expected")
+        self.assertEqual(device["ExpressionAttributeValues"][":expected"], "usr_p1")
+        self.assertEqual(device["ExpressionAttributeValues"][":entry"], [entry])
+        self.assertEqual((added["Key"], added["UpdateExpression"]), ({"userId": "usr_p2"}, "ADD assignedDevices :device"))
+        self.assertEqual((removed["Key"], removed["UpdateExpression"]), ({"userId": "usr_p1"}, "DELETE assignedDevices :device"))
+
+    def test_cancelled_transaction_raises_condition_failed(self):
+        self.ddb.meta.client.transact_write_items.side_effect = ClientError(
+            {"Error": {"Code": "TransactionCanceledException", "Message": "cancelled"}}, "TransactWriteItems"
+        )
+        entry = {"action": "assign", "patientId": "usr_p1", "by": "usr_doc", "at": "2025-03-01T00:00:00+00:00"}
+
+        with self.assertRaises(ConditionFailedError):
+            db.set_device_assignment("DEV-1", "usr_p1", None, entry, add_to_profile="usr_p1")
+
+
+if __name__ == "__main__":
+    unittest.main()
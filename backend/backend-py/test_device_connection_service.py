@@ -0,0 +1,116 @@
+"""
+Tests for MeDUSA Device Connection Service
+
+Run with: python -m pytest test_device_connection_service.py -v
+"""
+
+import os
+import unittest
+from datetime import datetime, timezone, timedelta
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from audit_service import AuditEventType
+from device_connection_service import (
+    record_connection,
+    record_disconnection,
+    get_connection_state,
+    DeviceConnectionError,
+    DEVICE_STALE_SECONDS
+)
+
+NOW = datetime(2025, 6, 1, 9, 0, tzinfo=timezone.utc)
+
+
+class TestDeviceConnection(unittest.TestCase):
+    """Test connect/disconnect transitions and staleness."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._devices.append({"id": "DEV-001", "patientId": "usr_p1", "status": "offline"})
+        p = patch("device_connection_service.audit_service")
+        self.audit = p.start()
+        self.addCleanup(p.stop)
+
+    def _device(self):
+        return db.get_device("DEV-001")
+
+    def _connect(self, at=NOW, connection_type="bluetooth", signal=-60):
+        return record_connection(self._device(), connection_type, "usr_p1", "patient", signal_strength=signal, now=at)
+
+    def _audited(self):
+        return [c.kwargs["event_type"] for c in self.audit.log_device_event.call_args_list]
+
+    def test_never_connected_device_is_disconnected(self):
+        state = get_connection_state(self._device(), NOW)
+        self.assertEqual((state["state"], state["connected"]), ("disconnected", False))
+
+    def test_connect_then_disconnect(self):
+        state = self._connect()
+        self.assertEqual((state["state"], state["connectionType"], state["signalStrength"]), ("connected", "bluetooth", -60))
+        self.assertEqual(self._device()["status"], "online")
+
+        state = record_disconnection(self._device(), "usr_p1", "patient", now=NOW + timedelta(minutes=1))
+
+        self.assertEqual(state["state"], "disconnected")
+        self.assertEqual(state["disconnectedAt"], (NOW + timedelta(minutes=1)).isoformat())
+        self.assertEqual(self._device()["status"], "offline")
+        self.assertEqual(self._audited(), [AuditEventType.DEVICE_CONNECTED, AuditEventType.DEVICE_DISCONNECTED])
+
+    def test_repeated_reports_audited_once(self):
+        self._connect()
+        state = self._connect(at=NOW + timedelta(seconds=60), signal=-70)
+
+        self.assertEqual(state["lastConnectedAt"], NOW.isoformat())
+        self.assertEqual(state["signalStrength"], -70)
+        self.assertEqual(self._audited(), [AuditEventType.DEVICE_CONNECTED])
+
+    def test_switching_connection_type_is_a_new_connection(self):
+        self._connect()
+        self._connect(at=NOW + timedelta(seconds=30), connection_type="usb")
+        self.assertEqual(self._audited(), [AuditEventType.DEVICE_CONNECTED] * 2)
+
+    def test_silent_device_becomes_stale(self):
+        self._connect()
+        within = get_connection_state(self._device(), NOW + timedelta(seconds=DEVICE_STALE_SECONDS))
+        after = get_connection_state(self._device(), NOW + timedelta(seconds=DEVICE_STALE_SECONDS + 1))
+
+        self.assertEqual(within["state"], "connected")
+        self.assertEqual((after["state"], after["connected"]), ("stale", False))
+
+    def test_report_after_stale_reconnects(self):
+        self._connect()
+        state = self._connect(at=NOW + timedelta(seconds=DEVICE_STALE_SECONDS + 60))
+        self.assertEqual(state["lastConnectedAt"], (NOW + timedelta(seconds=DEVICE_STALE_SECONDS + 60)).isoformat())
+        self.assertEqual(self._audited(), [AuditEventType.DEVICE_CONNECTED] * 2)
+
+    def test_disconnect_when_not_connected_not_audited(self):
+        state = record_disconnection(self._device(), "usr_p1", "patient", now=NOW)
+        self.assertEqual(state["state"], "disconnected")
+        self.audit.log_device_event.assert_not_called()
+
+    def test_unknown_connection_type_rejected(self):
+        with self.assertRaises(DeviceConnectionError):
+            self._connect(connection_type="zigbee")
+
+    def test_battery_level_recorded(self):
+        record_connection(self._device(), "wifi", "usr_p1", "patient", now=NOW, battery_level=15)
+        device = self._device()
+        self.assertEqual((device["batteryLevel"], device["batteryUpdatedAt"]), (15, NOW.isoformat()))
+
+        self._connect(at=NOW + timedelta(seconds=60))
+        self.assertEqual(self._device()["batteryUpdatedAt"], NOW.isoformat())
+
+    def test_recharged_battery_rearms_alert(self):
+        db.update_device("DEV-001", {"lowBatteryAlertedAt": NOW.isoformat()})
+        record_connection(self._device(), "wifi", "usr_p1", "patient", now=NOW, battery_level=10)
+        self.assertEqual(self._device()["lowBatteryAlertedAt"], NOW.isoformat())
+
+        record_connection(self._device(), "wifi", "usr_p1", "patient", now=NOW, battery_level=80)
+        self.assertIsNone(self._device()["lowBatteryAlertedAt"])
+
+
+if __name__ == "__main__":
+    unittest.main()
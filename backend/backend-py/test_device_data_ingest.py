@@ -0,0 +1,206 @@
+"""
+Tests for device reading ingestion
+
+Run with: python -m pytest test_device_data_ingest.py -v
+"""
+
+import io
+import os
+import json
+import uuid
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import device_data_ingest
+import encryption_service
+import storage
+from device_data_ingest import ingest, process, hash_device_api_key, READINGS_PER_MESSAGE
+from notification_service import classify_reading, AlertSeverity
+
+API_KEY = "device-secret-key"
+
+
+def _reading(ts=1735689600, **values):
+    return {"timestamp": ts, "readingType": "accelerometer",
+            "values": values or {"accel_x": 0.1, "accel_y": 0.2, "accel_z": 9.8}}
+
+
+class TestIngest(unittest.TestCase):
+    """Test the API Gateway ingestion handler."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._devices.append({"id": "DEV-1", "patientId": "PAT-1", "apiKeyHash": hash_device_api_key(API_KEY)})
+        patcher = patch.object(device_data_ingest, "_sqs")
+        self.sqs = patcher.start()
+        self.sqs.send_message_batch.return_value = {"Successful": [], "Failed": []}
+        self.addCleanup(patcher.stop)
+
+    def _event(self, body, api_key=API_KEY):
+        headers = {"X-Device-Id": "DEV-1", "X-Api-Key": api_key, "Idempotency-Key": str(uuid.uuid4())}
+        return {"headers": headers, "body": json.dumps(body)}
+
+    def test_accepted_batch_is_queued(self):
+        resp = ingest(self._event({"readings": [_reading()]}), None)
+        self.assertEqual(resp["statusCode"], 202)
+        entries = self.sqs.send_message_batch.call_args.kwargs["Entries"]
+        self.assertEqual(entries[0]["MessageGroupId"], "DEV-1")
+        self.assertEqual(json.loads(entries[0]["MessageBody"])["patientId"], "PAT-1")
+
+    def test_large_batch_split_into_messages(self):
+        readings = [_reading(ts=1735689600 + i) for i in range(READINGS_PER_MESSAGE + 1)]
+        ingest(self._event({"readings": readings}), None)
+        entries = self.sqs.send_message_batch.call_args.kwargs["Entries"]
+        self.assertEqual(len(entries), 2)
+
+    def test_wrong_api_key_rejected(self):
+        resp = ingest(self._event({"readings": [_reading()]}, api_key="wrong"), None)
+        self.assertEqual(resp["statusCode"], 401)
+        self.sqs.send_message_batch.assert_not_called()
+
+    def test_empty_batch_rejected(self):
+        self.assertEqual(ingest(self._event({"readings": []}), None)["statusCode"], 400)
+
+    def test_queue_failure_returns_503(self):
+        self.sqs.send_message_batch.side_effect = Exception("unavailable")
+        self.assertEqual(ingest(self._event({"readings": [_reading()]}), None)["statusCode"], 503)
+
+
+class TestProcess(unittest.TestCase):
+    """Test the SQS processor handler."""
+
+    def setUp(self):
+        db._sensor_data.clear()
+
+    def _record(self, message_id, readings, group="DEV-1", calibration=None, calibration_overdue=False):
+        body = {"deviceId": group, "patientId": "PAT-1", "calibration": calibration,
+                "calibrationOverdue": calibration_overdue, "readings": readings}
+        return {"messageId": message_id, "body": json.dumps(body), "attributes": {"MessageGroupId": group}}
+
+    def test_valid_readings_stored_flat(self):
+        result = process({"Records": [self._record("m1", [_reading()])]}, None)
+        self.assertEqual(result, {"batchItemFailures": []})
+        item = db._sensor_data[0]
+        self.assertEqual((item["device_id"], item["patient_id"], item["accel_z"]), ("DEV-1", "PAT-1", 9.8))
+
+    def test_device_calibration_applied(self):
+        calibration = {"correctionFactor": 1.1, "keys": ["accel_z"]}
+        process({"Records": [self._record("m1", [_reading()], calibration=calibration)]}, None)
+        self.assertAlmostEqual(db._sensor_data[0]["accel_z"], 10.78)
+
+    def test_overdue_device_readings_down_scored(self):
+        tremor = {"timestamp": 1735689600, "readingType": "tremor", "values": {"tremor_index": 0.3, "signal_quality": 0.7}}
+        process({"Records": [self._record("m1", [tremor], calibration_overdue=True)]}, None)
+        item = db._sensor_data[0]
+        self.assertAlmostEqual(item["signal_quality"], 0.525)
+        self.assertTrue(item["low_quality"])
+
+    def test_units_converted_and_recorded(self):
+        reading = {**_reading(), "unit": "g", "values": {"accel_x": 0.0, "accel_y": 0.0, "accel_z": 1.0}}
+        process({"Records": [self._record("m1", [reading])]}, None)
+        item = db._sensor_data[0]
+        self.assertAlmostEqual(item["accel_z"], 9.80665)
+        self.assertEqual(item["unit"], "m/s^2")
+
+    def test_invalid_readings_dropped(self):
+        bad = {"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": -1}}
+        process({"Records": [self._record("m1", [_reading(), bad])]}, None)
+        self.assertEqual(len(db._sensor_data), 1)
+
+    def test_failed_message_reported_with_rest_of_group(self):
+        with patch.object(db, "batch_write_device_readings", side_effect=[Exception("throttled"), 1]):
+            result = process({"Records": [
+                self._record("m1", [_reading()]),
+                self._record("m2", [_reading()]),
+                self._record("m3", [_reading()], group="DEV-2"),
+            ]}, None)
+        self.assertEqual(result["batchItemFailures"], [{"itemIdentifier": "m1"}, {"itemIdentifier": "m2"}])
+
+
+class FakeS3:
+    """put_object / get_object against a dict"""
+
+    def __init__(self):
+        self.objects = {}
+
+    def put_object(self, Bucket, Key, Body, Metadata=None, **kwargs):
+        self.objects[Key] = (Body, Metadata or {})
+
+    def get_object(self, Bucket, Key, **kwargs):
+        body, metadata = self.objects[Key]
+        return {"Body": io.BytesIO(body), "Metadata": metadata}
+
+
+class TestEcgWaveforms(unittest.TestCase):
+    """Test offloading ECG waveforms to S3 and reading them back."""
+
+    SAMPLES = [0.0, 0.12, 1.35, -0.4, 0.05] * 200
+
+    def setUp(self):
+        db._devices.clear()
+        db._sensor_data.clear()
+        db._devices.append({"id": "DEV-1", "patientId": "PAT-1", "apiKeyHash": hash_device_api_key(API_KEY)})
+        self.s3 = FakeS3()
+        self.sqs = MagicMock()
+        self.sqs.send_message_batch.return_value = {"Successful": [], "Failed": []}
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.s3).start()
+        patch.object(device_data_ingest, "_sqs", self.sqs).start()
+        patch.dict(os.environ, {"S3_BUCKET": "bucket"}).start()
+        patch.object(encryption_service, "S3_ENCRYPTION_MODE", "sse-s3").start()
+
+    def _ecg(self, **fields):
+        return {"timestamp": 1735689600, "readingType": "ecg",
+                "values": {"heart_rate_bpm": 72, "sample_rate_hz": 250, "duration_s": 4},
+                "waveform": self.SAMPLES, **fields}
+
+    def _ingest(self, readings):
+        headers = {"X-Device-Id": "DEV-1", "X-Api-Key": API_KEY, "Idempotency-Key": str(uuid.uuid4())}
+        event = {"headers": headers, "body": json.dumps({"readings": readings})}
+        return ingest(event, None)
+
+    def _queued(self):
+        return json.loads(self.sqs.send_message_batch.call_args.kwargs["Entries"][0]["MessageBody"])
+
+    def test_offload_and_retrieve_round_trip(self):
+        self.assertEqual(self._ingest([self._ecg(), _reading()])["statusCode"], 202)
+        message = self._queued()
+        ecg, accel = message["readings"]
+        self.assertNotIn("waveform", ecg)
+        self.assertTrue(storage.is_device_data_key(ecg["waveformKey"], "DEV-1"))
+        self.assertNotIn("waveformKey", accel)
+
+        process({"Records": [{"messageId": "m1", "body": json.dumps(message), "attributes": {"MessageGroupId": "DEV-1"}}]}, None)
+        item = next(i for i in db._sensor_data if i["reading_type"] == "ecg")
+        self.assertEqual((item["heart_rate_bpm"], item["waveform_key"]), (72, ecg["waveformKey"]))
+
+        payload = storage.download_device_data(item["waveform_key"])
+        self.assertEqual(payload["samples"], self.SAMPLES)
+        self.assertEqual((payload["deviceId"], payload["sampleRateHz"], payload["unit"]), ("DEV-1", 250, "mV"))
+
+    def test_malformed_waveform_rejected(self):
+        resp = self._ingest([self._ecg(waveform=[0.1, float("nan")])])
+        self.assertEqual(resp["statusCode"], 400)
+        self.assertEqual(self.s3.objects, {})
+        self.sqs.send_message_batch.assert_not_called()
+
+    def test_waveform_only_on_ecg(self):
+        self.assertEqual(self._ingest([{**_reading(), "waveform": [0.1]}])["statusCode"], 400)
+
+    def test_key_of_other_device_dropped(self):
+        foreign = {k: v for k, v in self._ecg().items() if k != "waveform"}
+        foreign["waveformKey"] = storage.make_device_data_key("DEV-2", "ecg", 1735689600)
+        with self.assertRaises(device_data_ingest.ReadingValidationError):
+            device_data_ingest.to_sensor_item("DEV-1", "PAT-1", foreign)
+
+    def test_summary_classified_waveform_not(self):
+        self.assertIsNone(classify_reading("ecg", {"heart_rate_bpm": 72, "sample_rate_hz": 250}))
+        severity, _ = classify_reading("ecg", {"heart_rate_bpm": 160, "sample_rate_hz": 250})
+        self.assertEqual(severity, AlertSeverity.CRITICAL)
+
+
+if __name__ == '__main__':
+    unittest.main()
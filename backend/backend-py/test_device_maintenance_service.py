@@ -0,0 +1,148 @@
+"""
+Tests for MeDUSA Device Maintenance Service and reminders
+
+Run with: python -m pytest test_device_maintenance_service.py -v
+"""
+
+import os
+import json
+import unittest
+from datetime import datetime, timezone, timedelta
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import maintenance_reminder
+from device_maintenance_service import (
+    schedule_maintenance,
+    complete_maintenance,
+    get_device_maintenance,
+    get_upcoming_maintenance,
+    MaintenanceError
+)
+
+NOW = datetime(2025, 6, 1, 9, 0, tzinfo=timezone.utc)
+
+
+def _schedule(device_id="DEV-001", days=3, maintenance_type="calibration", **kwargs):
+    return schedule_maintenance(
+        device_id, maintenance_type, NOW + timedelta(days=days),
+        created_by="usr_admin", created_by_role="admin", **kwargs
+    )
+
+
+class MaintenanceTestCase(unittest.TestCase):
+    def setUp(self):
+        db._maintenance_events.clear()
+        p = patch("device_maintenance_service.audit_service")
+        self.audit = p.start()
+        self.addCleanup(p.stop)
+
+
+class TestScheduleMaintenance(MaintenanceTestCase):
+    """Test scheduling and validation."""
+
+    def test_schedule_stores_utc_event(self):
+        event = schedule_maintenance(
+            "DEV-001", "hardware_inspection", datetime(2025, 6, 3, 11, 0, tzinfo=timezone(timedelta(hours=2))),
+            created_by="usr_admin", created_by_role="admin", assigned_to="usr_tech"
+        )
+        self.assertEqual(event["scheduledAt"], "2025-06-03T09:00:00+00:00")
+        self.assertEqual(get_device_maintenance("DEV-001"), [event])
+        self.assertNotIn("pendingStatus", event)
+        self.audit.log_device_event.assert_called_once()
+
+    def test_custom_type_required_for_custom(self):
+        with self.assertRaises(MaintenanceError):
+            _schedule(maintenance_type="custom")
+        event = _schedule(maintenance_type="custom", custom_type=" Battery replacement ")
+        self.assertEqual(event["customType"], "Battery replacement")
+
+    def test_unknown_type_rejected(self):
+        with self.assertRaises(MaintenanceError):
+            _schedule(maintenance_type="repainting")
+
+    def test_same_slot_rejected(self):
+        _schedule()
+        with self.assertRaises(MaintenanceError):
+            _schedule(maintenance_type="software_update")
+
+
+class TestCompleteMaintenance(MaintenanceTestCase):
+    """Test completing events and follow-up scheduling."""
+
+    def test_complete_closes_event(self):
+        event = _schedule()
+        done = complete_maintenance("DEV-001", event["id"], "usr_tech", "admin", completed_at=NOW, notes="OK")
+
+        self.assertEqual((done["performedBy"], done["notes"]), ("usr_tech", "OK"))
+        self.assertEqual(get_device_maintenance("DEV-001", pending_only=True), [])
+        with self.assertRaises(MaintenanceError):
+            complete_maintenance("DEV-001", event["id"], "usr_tech", "admin")
+
+    def test_next_due_schedules_follow_up(self):
+        event = _schedule(assigned_to="usr_tech")
+        next_due = NOW + timedelta(days=180)
+        complete_maintenance("DEV-001", event["id"], "usr_tech", "admin", next_maintenance_due=next_due)
+
+        pending = get_device_maintenance("DEV-001", pending_only=True)
+        self.assertEqual(len(pending), 1)
+        self.assertEqual(pending[0]["scheduledAt"], next_due.isoformat())
+        self.assertEqual((pending[0]["maintenanceType"], pending[0]["assignedTo"]), ("calibration", "usr_tech"))
+
+    def test_unknown_event_returns_none(self):
+        self.assertIsNone(complete_maintenance("DEV-001", "mnt_missing", "usr_tech", "admin"))
+
+
+class TestUpcomingMaintenance(MaintenanceTestCase):
+    """Test the cross-device upcoming window."""
+
+    def test_only_open_events_within_window(self):
+        soon = _schedule("DEV-001", days=2)
+        later = _schedule("DEV-002", days=6)
+        _schedule("DEV-003", days=10)
+        done = _schedule("DEV-004", days=1)
+        complete_maintenance("DEV-004", done["id"], "usr_tech", "admin")
+
+        upcoming = get_upcoming_maintenance(NOW, days=7)
+        self.assertEqual([e["id"] for e in upcoming], [soon["id"], later["id"]])
+
+
+class TestMaintenanceReminders(MaintenanceTestCase):
+    """Test the reminder Lambda publishes once per event."""
+
+    def setUp(self):
+        super().setUp()
+        self.sns = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(maintenance_reminder, "_sns_client", return_value=self.sns).start()
+        patch.object(maintenance_reminder, "MAINTENANCE_TOPIC_ARN", "arn:aws:sns:eu-west-1:123:maintenance").start()
+
+    def test_reminder_sent_to_technician_once(self):
+        event = _schedule(assigned_to="usr_tech")
+
+        self.assertEqual(maintenance_reminder.send_due_reminders(NOW), {"sent": 1, "skipped": 0, "failed": 0})
+        kwargs = self.sns.publish.call_args.kwargs
+        self.assertEqual(kwargs["MessageAttributes"]["technicianId"]["StringValue"], "usr_tech")
+        self.assertEqual(json.loads(kwargs["Message"])["maintenanceId"], event["id"])
+
+        self.assertEqual(maintenance_reminder.send_due_reminders(NOW), {"sent": 0, "skipped": 1, "failed": 0})
+        self.assertEqual(self.sns.publish.call_count, 1)
+
+    def test_unassigned_event_published_as_unassigned(self):
+        _schedule()
+        maintenance_reminder.send_due_reminders(NOW)
+        attrs = self.sns.publish.call_args.kwargs["MessageAttributes"]
+        self.assertEqual(attrs["technicianId"]["StringValue"], "unassigned")
+
+    def test_failed_publish_retried_next_run(self):
+        _schedule(assigned_to="usr_tech")
+        self.sns.publish.side_effect = [RuntimeError("throttled"), {}]
+
+        self.assertEqual(maintenance_reminder.send_due_reminders(NOW)["failed"], 1)
+        self.assertEqual(maintenance_reminder.send_due_reminders(NOW)["sent"], 1)
+
+
+if __name__ == "__main__":
+    unittest.main()
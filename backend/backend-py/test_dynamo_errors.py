@@ -0,0 +1,68 @@
+"""
+Tests for DynamoDB error mapping
+
+Run with: python -m pytest test_dynamo_errors.py -v
+"""
+
+import os
+import json
+import unittest
+
+os.environ['USE_MEMORY'] = 'true'
+
+from botocore.exceptions import ClientError
+
+from dynamo_errors import dynamo_error_response, is_dynamo_error, map_dynamo_error
+
+
+def _error(code, operation="PutItem", message="boom"):
+    return ClientError({"Error": {"Code": code, "Message": message}}, operation)
+
+
+class TestMapDynamoError(unittest.TestCase):
+    """Test each DynamoDB error code maps to its HTTP error."""
+
+    def _mapped(self, code):
+        exc = map_dynamo_error(_error(code), "Updating report")
+        return exc.status_code, exc.detail["code"]
+
+    def test_conditional_check_failed_is_conflict(self):
+        self.assertEqual(self._mapped("ConditionalCheckFailedException"), (409, "CONFLICT"))
+
+    def test_resource_not_found(self):
+        self.assertEqual(self._mapped("ResourceNotFoundException"), (404, "NOT_FOUND"))
+
+    def test_throughput_exceeded_is_rate_limited(self):
+        exc = map_dynamo_error(_error("ProvisionedThroughputExceededException"), "Listing readings")
+        self.assertEqual((exc.status_code, exc.detail["code"]), (429, "RATE_LIMITED"))
+        self.assertEqual(exc.headers, {"Retry-After": "5"})
+
+    def test_validation_is_bad_request(self):
+        self.assertEqual(self._mapped("ValidationException"), (400, "BAD_REQUEST"))
+
+    def test_other_errors_are_database_errors(self):
+        self.assertEqual(self._mapped("InternalServerError"), (500, "DATABASE_ERROR"))
+
+    def test_message_includes_context(self):
+        exc = map_dynamo_error(_error("ValidationException", message="Invalid key"), "Updating report")
+        self.assertEqual(exc.detail["message"], "Updating report: Invalid key")
+        self.assertIsNone(exc.headers)
+
+
+class TestDynamoErrorResponse(unittest.TestCase):
+    """Test the app-wide handler response and service detection."""
+
+    def test_only_dynamo_operations_detected(self):
+        self.assertTrue(is_dynamo_error(_error("ValidationException", "Query")))
+        self.assertFalse(is_dynamo_error(_error("NoSuchKey", "GetObject")))
+        self.assertFalse(is_dynamo_error(ValueError("not a ClientError")))
+
+    def test_response_matches_http_exception_shape(self):
+        response = dynamo_error_response(_error("ConditionalCheckFailedException"))
+        self.assertEqual(response.status_code, 409)
+        body = response.body if isinstance(response.body, dict) else json.loads(response.body)
+        self.assertEqual(body["detail"]["code"], "CONFLICT")
+
+
+if __name__ == "__main__":
+    unittest.main()
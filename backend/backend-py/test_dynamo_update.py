@@ -0,0 +1,173 @@
+"""
+Tests for the DynamoDB update-expression builder
+
+Run with: python -m pytest test_dynamo_update.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from dynamo_update import DynamoUpdateBuilder, VersionConflictError, ConditionFailedError, diff_user
+
+
+class TestDiffUser(unittest.TestCase):
+    """Test user change detection."""
+
+    def test_only_changed_fields_returned(self):
+        old = {"id": "usr_1", "name": "Ann", "role": "patient", "isActive": True}
+        new = {"id": "usr_1", "name": "Ann", "role": "doctor", "isActive": True}
+
+        self.assertEqual(diff_user(old, new), {"role": "doctor"})
+
+    def test_removed_field_maps_to_none(self):
+        old = {"id": "usr_1", "name": "Ann", "department": "Neurology"}
+        new = {"id": "usr_1", "name": "Ann"}
+
+        self.assertEqual(diff_user(old, new), {"department": None})
+
+    def test_identity_and_version_ignored(self):
+        old = {"id": "usr_1", "version": 3}
+        new = {"id": "usr_2", "version": 4}
+
+        self.assertEqual(diff_user(old, new), {})
+
+
+class TestUpdateExpression(unittest.TestCase):
+    """Test SET / REMOVE rendering."""
+
+    def test_multi_field_update_with_removal(self):
+        kwargs = (DynamoUpdateBuilder()
+                  .set("name", "Ann")
+                  .set("phone", "+15551234567")
+                  .remove("department")
+                  .set("nickname", None)
+                  .build())
+
+        self.assertEqual(kwargs["UpdateExpression"], "SET #attr0 = :val0, #attr1 = :val1 REMOVE #attr2, #attr3")
+        self.assertEqual(kwargs["ExpressionAttributeNames"],
+                         {"#attr0": "name", "#attr1": "phone", "#attr2": "department", "#attr3": "nickname"})
+        self.assertEqual(kwargs["ExpressionAttributeValues"], {":val0": "Ann", ":val1": "+15551234567"})
+        self.assertNotIn("ConditionExpression", kwargs)
+
+    def test_remove_only_has_no_values(self):
+        kwargs = DynamoUpdateBuilder().remove("otpHash").build()
+        self.assertEqual(kwargs["UpdateExpression"], "REMOVE #attr0")
+        self.assertNotIn("ExpressionAttributeValues", kwargs)
+
+    def test_reserved_words_use_placeholders(self):
+        kwargs = DynamoUpdateBuilder().set("status", "active").set("name", "Ann").build()
+        self.assertNotIn("status", kwargs["UpdateExpression"])
+        self.assertIn("status", kwargs["ExpressionAttributeNames"].values())
+
+
+class TestPatientProfileUpdate(unittest.TestCase):
+    """Patient profiles are updated field by field."""
+
+    def test_none_removes_field_in_memory(self):
+        db._patient_profiles["usr_p1"] = {"userId": "usr_p1", "diagnosis": "PD", "phone": "+15551234567"}
+        db.update_patient_profile("usr_p1", {"diagnosis": "PD stage 2", "phone": None})
+        self.assertEqual(db._patient_profiles["usr_p1"], {"userId": "usr_p1", "diagnosis": "PD stage 2"})
+
+    def test_dynamodb_update_item_with_remove(self):
+        table = MagicMock()
+        with patch.object(db, "USE_MEMORY", False), patch.object(db, "T_PATIENT_PROFILES", table, create=True):
+            db.update_patient_profile("usr_p1", {"weightKg": 71.5, "phone": None})
+
+        kwargs = table.update_item.call_args.kwargs
+        self.assertEqual(kwargs["Key"], {"userId": "usr_p1"})
+        self.assertEqual(kwargs["UpdateExpression"], "SET #attr0 = :val0 REMOVE #attr1")
+        self.assertEqual(str(kwargs["ExpressionAttributeValues"][":val0"]), "71.5")
+        table.put_item.assert_not_called()
+
+
+class TestProfileEndpointsWriteChangedFields(unittest.TestCase):
+    """Profile and settings updates do not rewrite the whole user item."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_1", "email": "a@example.com", "role": "patient", "name": "Ann",
+                     "settings": {"emailNotifications": False}})
+
+    def _request(self, body):
+        return fake_request("usr_1", json=lambda: asyncio.sleep(0, result=body))
+
+    def test_profile_update(self):
+        with patch.object(db, "put_user") as put_user, patch.object(db, "update_user", wraps=db.update_user) as update:
+            asyncio.run(main.update_user_profile.__wrapped__(self._request({"name": "Anne", "email": "x@evil.test"})))
+
+        put_user.assert_not_called()
+        self.assertEqual(set(update.call_args.args[1]), {"name", "updatedAt"})
+        self.assertEqual(db.get_user("usr_1")["name"], "Anne")
+        self.assertEqual(db.get_user("usr_1")["email"], "a@example.com")
+
+    def test_settings_update_merges(self):
+        with patch.object(db, "put_user") as put_user:
+            asyncio.run(main.update_user_settings.__wrapped__(self._request({"pushNotifications": False})))
+
+        put_user.assert_not_called()
+        self.assertEqual(db.get_user("usr_1")["settings"], {"emailNotifications": False, "pushNotifications": False})
+
+
+class TestOptimisticLocking(unittest.TestCase):
+    """Test version-checked user updates."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_1", "email": "a@example.com", "name": "Ann"})
+
+    def test_version_increments_on_each_update(self):
+        db.update_user_fields("usr_1", {"name": "Anne"}, expected_version=0)
+        db.update_user_fields("usr_1", {"name": "Annie"}, expected_version=1)
+
+        user = db.get_user("usr_1")
+        self.assertEqual(user["name"], "Annie")
+        self.assertEqual(user["version"], 2)
+
+    def test_stale_version_rejected(self):
+        db.update_user_fields("usr_1", {"name": "Anne"})
+
+        with self.assertRaises(VersionConflictError):
+            db.update_user_fields("usr_1", {"name": "Stale"}, expected_version=0)
+        self.assertEqual(db.get_user("usr_1")["name"], "Anne")
+
+    def test_version_condition_rendered(self):
+        kwargs = DynamoUpdateBuilder().set("name", "Ann").with_version(2).build()
+
+        self.assertIn("if_not_exists(#attr1, :val1) + :val2", kwargs["UpdateExpression"])
+        self.assertEqual(kwargs["ConditionExpression"], "#attr1 = :val3")
+        self.assertEqual(kwargs["ExpressionAttributeValues"][":val3"], 2)
+
+
+class TestConditionalUpdate(unittest.TestCase):
+    """Test when_equals conditions."""
+
+    def test_condition_rendered(self):
+        kwargs = DynamoUpdateBuilder().set("jti", "new").when_equals("jti", "old").build()
+
+        self.assertEqual(kwargs["ConditionExpression"], "#attr0 = :val1")
+        self.assertEqual(kwargs["ExpressionAttributeValues"][":val1"], "old")
+
+    def test_condition_applied_in_memory(self):
+        item = {"jti": "old"}
+        DynamoUpdateBuilder().set("jti", "new").when_equals("jti", "old").apply_to(item)
+        self.assertEqual(item["jti"], "new")
+
+    def test_failed_condition_leaves_item_unchanged(self):
+        item = {"jti": "other"}
+        with self.assertRaises(ConditionFailedError):
+            DynamoUpdateBuilder().set("jti", "new").when_equals("jti", "old").apply_to(item)
+        self.assertEqual(item["jti"], "other")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,89 @@
+"""
+Tests for the DynamoDB Local test support and db round trips against it
+
+The integration tests only run when DYNAMODB_ENDPOINT_URL points at a
+DynamoDB Local / LocalStack endpoint:
+
+    docker run -p 8000:8000 amazon/dynamodb-local
+    DYNAMODB_ENDPOINT_URL=http://localhost:8000 python -m pytest test_dynamodb_local.py -v
+"""
+
+import os
+import sys
+import uuid
+import importlib
+import unittest
+from unittest.mock import patch
+
+import dynamodb_local
+
+ENDPOINT_URL = os.environ.get("DYNAMODB_ENDPOINT_URL")
+
+
+class TestTableDefinitions(unittest.TestCase):
+    """Table definitions are derived from template.yaml."""
+
+    def setUp(self):
+        self.definitions = dynamodb_local.table_definitions()
+
+    def test_every_table_variable_has_a_definition(self):
+        self.assertIn("DDB_TABLE_USERS", self.definitions)
+        self.assertIn("DDB_TABLE_DEVICES", self.definitions)
+        self.assertIn("DDB_TABLE_IDEMPOTENCY", self.definitions)
+
+    def test_users_table_has_email_index(self):
+        users = self.definitions["DDB_TABLE_USERS"]
+        self.assertEqual(users["KeySchema"], [{"AttributeName": "id", "KeyType": "HASH"}])
+        self.assertIn("email-index", [g["IndexName"] for g in users["GlobalSecondaryIndexes"]])
+
+    def test_only_create_table_properties_are_kept(self):
+        for definition in self.definitions.values():
+            self.assertLessEqual(set(definition), set(dynamodb_local._TABLE_PROPERTIES))
+
+    def test_literal_table_name_gets_default_key(self):
+        self.assertEqual(
+            self.definitions["DDB_TABLE_SENSOR_DATA"]["KeySchema"],
+            [{"AttributeName": "id", "KeyType": "HASH"}]
+        )
+
+
+@unittest.skipUnless(ENDPOINT_URL, "DYNAMODB_ENDPOINT_URL not set")
+class TestDynamoDbLocalRoundTrip(unittest.TestCase):
+    """db against real (local) DynamoDB tables."""
+
+    @classmethod
+    def setUpClass(cls):
+        cls.tables = dynamodb_local.create_tables(ENDPOINT_URL)
+        env = patch.dict(os.environ, dynamodb_local.local_environment(ENDPOINT_URL, cls.tables))
+        env.start()
+        cls.addClassCleanup(env.stop)
+        # Import a separate db module bound to the local tables; other tests
+        # keep the in-memory one
+        original = sys.modules.pop("db", None)
+        try:
+            cls.db = importlib.import_module("db")
+        finally:
+            if original is not None:
+                sys.modules["db"] = original
+
+    @classmethod
+    def tearDownClass(cls):
+        dynamodb_local.delete_tables(ENDPOINT_URL, cls.tables)
+
+    def test_create_and_get_user(self):
+        user_id = f"usr_{uuid.uuid4().hex[:8]}"
+        email = f"{user_id}@example.com"
+        self.db.create_user({"id": user_id, "email": email, "name": "Local User", "role": "patient"})
+
+        self.assertEqual(self.db.get_user(user_id)["email"], email)
+        self.assertEqual(self.db.get_user_by_email(email)["id"], user_id)
+
+    def test_duplicate_email_rejected(self):
+        email = f"dup_{uuid.uuid4().hex[:8]}@example.com"
+        self.db.create_user({"id": f"usr_{uuid.uuid4().hex[:8]}", "email": email, "role": "patient"})
+        with self.assertRaises(self.db.EmailTakenError):
+            self.db.create_user({"id": f"usr_{uuid.uuid4().hex[:8]}", "email": email, "role": "patient"})
+
+
+if __name__ == '__main__':
+    unittest.main()
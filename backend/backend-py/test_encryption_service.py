@@ -0,0 +1,132 @@
+"""
+Tests for S3 encryption modes and KMS envelope encryption
+
+Run with: python -m pytest test_encryption_service.py -v
+"""
+
+import os
+import io
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import encryption_service
+import storage
+from encryption_service import EncryptionConfigError, envelope_decrypt, envelope_encrypt, sse_params
+
+KEY_ARN = "arn:aws:kms:us-east-1:123456789012:key/1111-2222"
+DATA_KEY = bytes(range(32))
+
+
+def _mock_kms():
+    kms = MagicMock()
+    kms.generate_data_key.return_value = {"Plaintext": DATA_KEY, "CiphertextBlob": b"wrapped-key"}
+    kms.decrypt.return_value = {"Plaintext": DATA_KEY}
+    return kms
+
+
+class TestSseParams(unittest.TestCase):
+    """Test the S3 parameters sent per mode."""
+
+    def setUp(self):
+        p = patch.object(encryption_service, "KMS_KEY_ARN", KEY_ARN)
+        p.start()
+        self.addCleanup(p.stop)
+
+    def test_sse_s3(self):
+        self.assertEqual(sse_params("sse-s3"), {"ServerSideEncryption": "AES256"})
+
+    def test_sse_kms_uses_customer_key(self):
+        self.assertEqual(sse_params("sse-kms"), {"ServerSideEncryption": "aws:kms", "SSEKMSKeyId": KEY_ARN})
+
+    def test_kms_mode_without_key_rejected(self):
+        with patch.object(encryption_service, "KMS_KEY_ARN", None):
+            with self.assertRaises(EncryptionConfigError):
+                sse_params("sse-kms")
+
+    def test_unknown_mode_rejected(self):
+        with self.assertRaises(EncryptionConfigError):
+            sse_params("rot13")
+
+    def test_presigned_post_enforces_kms(self):
+        fields, conditions = encryption_service.presign_sse_fields("sse-kms")
+        self.assertEqual(fields["x-amz-server-side-encryption"], "aws:kms")
+        self.assertIn({"x-amz-server-side-encryption-aws-kms-key-id": KEY_ARN}, conditions)
+
+
+class TestEnvelopeEncryption(unittest.TestCase):
+    """Test client-side encryption with a mocked KMS data key."""
+
+    def setUp(self):
+        self.kms = _mock_kms()
+        self.addCleanup(patch.stopall)
+        patch.object(encryption_service, "KMS_KEY_ARN", KEY_ARN).start()
+        patch.object(encryption_service, "_kms_client", return_value=self.kms).start()
+
+    def test_round_trip(self):
+        body, metadata = envelope_encrypt(b"patient backup", "backups/2025-06-01.json")
+
+        self.assertNotIn(b"patient backup", body)
+        self.assertEqual(metadata["medusa-wrapped-key"], "d3JhcHBlZC1rZXk=")
+        self.kms.generate_data_key.assert_called_once_with(
+            KeyId=KEY_ARN, KeySpec="AES_256", EncryptionContext={"s3Key": "backups/2025-06-01.json"}
+        )
+        self.assertEqual(envelope_decrypt(body, metadata, "backups/2025-06-01.json"), b"patient backup")
+        self.assertEqual(self.kms.decrypt.call_args.kwargs["CiphertextBlob"], b"wrapped-key")
+
+    def test_ciphertext_bound_to_object_key(self):
+        body, metadata = envelope_encrypt(b"patient backup", "backups/a.json")
+        with self.assertRaises(Exception):
+            envelope_decrypt(body, metadata, "backups/b.json")
+
+
+class TestStorageModes(unittest.TestCase):
+    """Test uploads send the parameters of the configured mode."""
+
+    def setUp(self):
+        self.s3 = MagicMock()
+        self.kms = _mock_kms()
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.s3).start()
+        patch.dict(os.environ, {"S3_BUCKET": "bucket"}).start()
+        patch.object(encryption_service, "KMS_KEY_ARN", KEY_ARN).start()
+        patch.object(encryption_service, "_kms_client", return_value=self.kms).start()
+
+    def test_default_upload_uses_sse_s3(self):
+        with patch.object(encryption_service, "S3_ENCRYPTION_MODE", "sse-s3"):
+            storage.upload_bytes("reports/r.pdf", b"%PDF-", "application/pdf")
+        self.assertEqual(self.s3.put_object.call_args.kwargs["ServerSideEncryption"], "AES256")
+
+    def test_kms_mode_applies_to_multipart_uploads(self):
+        self.s3.create_multipart_upload.return_value = {"UploadId": "up-1"}
+        self.s3.upload_part.return_value = {"ETag": "e"}
+        with patch.object(encryption_service, "S3_ENCRYPTION_MODE", "sse-kms"):
+            storage.multipart_upload("exports/x.csv", iter([b"a"]), "text/csv")
+
+        kwargs = self.s3.create_multipart_upload.call_args.kwargs
+        self.assertEqual((kwargs["ServerSideEncryption"], kwargs["SSEKMSKeyId"]), ("aws:kms", KEY_ARN))
+
+    def test_sensitive_envelope_upload_and_download(self):
+        with patch.object(encryption_service, "SENSITIVE_ENCRYPTION_MODE", "envelope"):
+            storage.upload_sensitive("backups/b.json", b"{}", "application/json")
+
+        kwargs = self.s3.put_object.call_args.kwargs
+        self.assertNotEqual(kwargs["Body"], b"{}")
+        self.assertEqual(kwargs["ServerSideEncryption"], "aws:kms")
+        self.assertIn("medusa-wrapped-key", kwargs["Metadata"])
+
+        self.s3.get_object.return_value = {"Body": io.BytesIO(kwargs["Body"]), "Metadata": kwargs["Metadata"]}
+        self.assertEqual(storage.download_sensitive("backups/b.json"), b"{}")
+
+    def test_sensitive_sse_upload_stays_plaintext(self):
+        with patch.object(encryption_service, "SENSITIVE_ENCRYPTION_MODE", "sse-kms"):
+            storage.upload_sensitive("backups/b.json", b"{}", "application/json")
+
+        kwargs = self.s3.put_object.call_args.kwargs
+        self.assertEqual((kwargs["Body"], kwargs["Metadata"]), (b"{}", {}))
+        self.kms.generate_data_key.assert_not_called()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,192 @@
+"""
+Tests for MeDUSA Patient Data Export Service
+
+Run with: python -m pytest test_export_service.py -v
+"""
+
+import os
+import json
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import storage
+from export_service import export_service
+
+
+class TestPatientExport(unittest.TestCase):
+    """Test patient export contents."""
+
+    def setUp(self):
+        db._users.clear()
+        db._devices.clear()
+        db._reports.clear()
+        db._tremor_analysis.clear()
+
+        db.put_user({"id": "usr_p1", "email": "p1@example.com", "role": "patient", "password": "hash", "mfaSecret": "S"})
+        db.put_user({"id": "usr_p2", "email": "p2@example.com", "role": "patient"})
+        db._devices.append({"id": "dev_1", "patientId": "usr_p1"})
+        db._devices.append({"id": "dev_2", "patientId": "usr_p2"})
+        for ts in (1000, 2000, 3000, 4000):
+            db._tremor_analysis.append({"patient_id": "usr_p1", "device_id": "dev_1", "timestamp": ts})
+        db._tremor_analysis.append({"patient_id": "usr_p2", "device_id": "dev_2", "timestamp": 2500})
+        db._reports.append({"reportId": "RPT-1", "patientId": "usr_p1"})
+        db._reports.append({"reportId": "RPT-2", "patientId": "usr_p2"})
+
+    def test_export_contains_all_readings_in_range(self):
+        export = export_service.build_patient_export("usr_p1", start_time=2000, end_time=3000)
+
+        self.assertEqual([r["timestamp"] for r in export["readings"]], [2000, 3000])
+
+    def test_export_excludes_other_patients(self):
+        export = export_service.build_patient_export("usr_p1")
+
+        self.assertEqual(len(export["readings"]), 4)
+        self.assertTrue(all(r["patient_id"] == "usr_p1" for r in export["readings"]))
+        self.assertEqual([d["id"] for d in export["devices"]], ["dev_1"])
+        self.assertEqual([r["reportId"] for r in export["reports"]], ["RPT-1"])
+
+    def test_export_omits_credentials(self):
+        export = export_service.build_patient_export("usr_p1")
+
+        self.assertNotIn("password", export["patient"])
+        self.assertNotIn("mfaSecret", export["patient"])
+        self.assertEqual(export["patient"]["email"], "p1@example.com")
+
+
+class TestUserExport(unittest.TestCase):
+    """Test GDPR user data export contents."""
+
+    def setUp(self):
+        db._users.clear()
+        db._patient_profiles.clear()
+        db._reports.clear()
+        db._tremor_analysis.clear()
+        db._audit_logs.clear()
+
+        db.put_user({"id": "usr_p1", "email": "p1@example.com", "role": "patient", "password": "hash"})
+        db.put_user({"id": "usr_d1", "email": "d1@example.com", "role": "doctor", "mfaSecret": "S"})
+        db._patient_profiles["usr_p1"] = {"userId": "usr_p1", "doctorId": "usr_d1"}
+        db._tremor_analysis.append({"patient_id": "usr_p1", "device_id": "dev_1", "timestamp": 1000})
+        db._reports.append({"reportId": "RPT-1", "patientId": "usr_p1", "authorId": "usr_d1"})
+        db._reports.append({"reportId": "RPT-2", "patientId": "usr_p2", "authorId": "usr_d2"})
+        db._audit_logs.append({"pk": "AUDIT#ALL", "sk": "2025-01-01", "userId": "usr_d1", "eventType": "DATA_READ"})
+        db._audit_logs.append({"pk": "AUDIT#ALL", "sk": "2025-01-02", "userId": "usr_p1", "eventType": "AUTH_LOGIN_SUCCESS"})
+
+    def test_export_contains_expected_keys(self):
+        export = export_service.build_user_export("usr_p1")
+
+        self.assertEqual(
+            set(export),
+            {"exportedAt", "userId", "user", "patient", "readings", "reports", "auditLogs"}
+        )
+
+    def test_patient_export(self):
+        export = export_service.build_user_export("usr_p1")
+
+        self.assertNotIn("password", export["user"])
+        self.assertEqual(export["patient"]["doctorId"], "usr_d1")
+        self.assertEqual(len(export["readings"]), 1)
+        self.assertEqual([r["reportId"] for r in export["reports"]], ["RPT-1"])
+        self.assertEqual([a["eventType"] for a in export["auditLogs"]], ["AUTH_LOGIN_SUCCESS"])
+
+    def test_only_allow_listed_user_fields_exported(self):
+        db.update_user("usr_p1", {"name": "Pat", "internalFlag": "x", "deviceKey": "k"})
+
+        user = export_service.build_user_export("usr_p1")["user"]
+
+        self.assertEqual(user, {"id": "usr_p1", "email": "p1@example.com", "role": "patient", "name": "Pat"})
+
+    def test_doctor_export_has_authored_reports_and_no_readings(self):
+        export = export_service.build_user_export("usr_d1")
+
+        self.assertNotIn("mfaSecret", export["user"])
+        self.assertIsNone(export["patient"])
+        self.assertEqual(export["readings"], [])
+        self.assertEqual([r["reportId"] for r in export["reports"]], ["RPT-1"])
+        self.assertEqual(len(export["auditLogs"]), 1)
+
+
+
+class TestDeviceReadingsExport(unittest.TestCase):
+    """Test streamed device reading exports."""
+
+    def setUp(self):
+        db._tremor_analysis.clear()
+        for ts in (1000, 2000, 3000):
+            db._tremor_analysis.append({"patient_id": "usr_p1", "device_id": "dev_1", "timestamp": ts,
+                                        "tremor_index": 0.5, "extra": "x"})
+        db._tremor_analysis.append({"patient_id": "usr_p2", "device_id": "dev_2", "timestamp": 2500})
+        self.uploaded = {}
+
+        def _upload(key, chunks, content_type):
+            self.uploaded["body"] = b"".join(chunks)
+            self.uploaded["contentType"] = content_type
+            return len(self.uploaded["body"])
+
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "multipart_upload", side_effect=_upload).start()
+        patch.object(storage, "presign_download", return_value="https://example.com/export").start()
+
+    def test_csv_export(self):
+        result = export_service.export_device_readings("dev_1", start_time=2000, export_format="csv")
+
+        lines = self.uploaded["body"].decode().splitlines()
+        self.assertEqual(lines[0].split(",")[:4], ["timestamp", "device_id", "patient_id", "tremor_index"])
+        self.assertEqual([l.split(",")[0] for l in lines[1:]], ["2000", "3000"])
+        self.assertEqual(result["readingCount"], 2)
+        self.assertEqual(self.uploaded["contentType"], "text/csv")
+
+    def test_jsonl_export_keeps_all_fields(self):
+        result = export_service.export_device_readings("dev_1", export_format="jsonl")
+
+        rows = [json.loads(l) for l in self.uploaded["body"].decode().splitlines()]
+        self.assertEqual(len(rows), 3)
+        self.assertEqual(rows[0]["extra"], "x")
+        self.assertEqual(result["sizeBytes"], len(self.uploaded["body"]))
+
+    def test_unsupported_format(self):
+        with self.assertRaises(ValueError):
+            export_service.export_device_readings("dev_1", export_format="xml")
+
+
+class TestMultipartUpload(unittest.TestCase):
+    """Test chunk buffering into S3 parts."""
+
+    def setUp(self):
+        self.s3 = MagicMock()
+        self.s3.create_multipart_upload.return_value = {"UploadId": "up-1"}
+        self.s3.upload_part.side_effect = lambda **kw: {"ETag": f"etag-{kw['PartNumber']}"}
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.s3).start()
+        patch.object(storage, "MIN_PART_SIZE", 10).start()
+        patch.dict(os.environ, {"S3_BUCKET": "bucket"}).start()
+
+    def test_chunks_buffered_into_parts(self):
+        size = storage.multipart_upload("k", iter([b"12345", b"678901", b"abc"]), "text/csv")
+
+        self.assertEqual(size, 14)
+        bodies = [c.kwargs["Body"] for c in self.s3.upload_part.call_args_list]
+        self.assertEqual(bodies, [b"12345678901", b"abc"])
+        parts = self.s3.complete_multipart_upload.call_args.kwargs["MultipartUpload"]["Parts"]
+        self.assertEqual([p["PartNumber"] for p in parts], [1, 2])
+
+    def test_empty_upload_sends_one_part(self):
+        storage.multipart_upload("k", iter([]), "text/csv")
+        self.assertEqual(self.s3.upload_part.call_count, 1)
+
+    def test_failure_aborts_upload(self):
+        def _chunks():
+            yield b"data"
+            raise RuntimeError("dynamo failed")
+
+        with self.assertRaises(RuntimeError):
+            storage.multipart_upload("k", _chunks(), "text/csv")
+        self.s3.abort_multipart_upload.assert_called_once()
+        self.s3.complete_multipart_upload.assert_not_called()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,139 @@
+"""
+Tests for FHIR R4 Observation conversion and the FHIR reading endpoints
+
+Run with: python -m pytest test_fhir_converter.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from fastapi import HTTPException
+from fhir_converter import LOINC_CODE_MAP, LOINC_SYSTEM, UCUM_SYSTEM, observation_id, to_fhir_bundle, to_fhir_observation
+
+BASE = "https://ehr.example.org/fhir"
+TS = 1735689600  # 2025-01-01T00:00:00Z
+
+
+def _item(reading_type, device_id="dev_1", timestamp=TS, **values):
+    return {"device_id": device_id, "patient_id": "usr_p1", "timestamp": timestamp, "reading_type": reading_type, **values}
+
+
+class TestToFhirObservation(unittest.TestCase):
+    """Test the Observation built for each kind of reading."""
+
+    def test_single_value_vital(self):
+        obs = to_fhir_observation(_item("heart_rate", bpm=72, unit="bpm"), "usr_p1", BASE)
+
+        self.assertEqual((obs.resourceType, obs.status), ("Observation", "final"))
+        self.assertEqual((obs.code.coding[0].system, obs.code.coding[0].code), (LOINC_SYSTEM, "8867-4"))
+        self.assertEqual(obs.category[0].coding[0].code, "vital-signs")
+        self.assertEqual((obs.subject.reference, obs.device.reference), ("Patient/usr_p1", "Device/dev_1"))
+        self.assertEqual(obs.effectiveDateTime, "2025-01-01T00:00:00+00:00")
+        self.assertEqual((obs.valueQuantity.value, obs.valueQuantity.code, obs.valueQuantity.system), (72, "/min", UCUM_SYSTEM))
+        self.assertIsNone(obs.component)
+
+    def test_blood_pressure_components(self):
+        obs = to_fhir_observation(_item("blood_pressure", systolic=128, diastolic=84), "usr_p1", BASE)
+
+        self.assertEqual(obs.code.coding[0].code, LOINC_CODE_MAP["blood_pressure"][0])
+        self.assertIsNone(obs.valueQuantity)
+        components = {c.code.coding[0].code: c.valueQuantity for c in obs.component}
+        self.assertEqual((components["8480-6"].value, components["8462-4"].value), (128, 84))
+        self.assertEqual(components["8480-6"].code, "mm[Hg]")
+
+    def test_unmapped_type_uses_local_code_system(self):
+        obs = to_fhir_observation(_item("tremor", tremor_index=0.42, signal_quality=0.9, low_quality=False), "usr_p1", BASE)
+
+        self.assertEqual(obs.code.coding[0].system, f"{BASE}/CodeSystem/reading-type")
+        self.assertEqual(obs.category[0].coding[0].code, "activity")
+        components = {c.code.coding[0].code: c for c in obs.component}
+        self.assertEqual(set(components), {"tremor_index", "signal_quality"})
+        self.assertEqual(components["tremor_index"].code.coding[0].system, f"{BASE}/CodeSystem/reading-value")
+        self.assertIsNone(components["tremor_index"].valueQuantity.unit)
+
+    def test_ecg_summary_without_waveform(self):
+        item = _item("ecg", heart_rate_bpm=64, sample_rate_hz=250, qt_interval_ms=410,
+                     waveform_key="device-data/dev_1/ecg/1735689600_ab.json")
+        obs = to_fhir_observation(item, "usr_p1", BASE)
+
+        self.assertEqual(obs.code.coding[0].code, "11524-6")
+        codes = [c.code.coding[0].code for c in obs.component]
+        self.assertEqual(codes, ["8867-4", "sample_rate_hz", "8634-8"])
+        self.assertNotIn("device-data/", obs.model_dump_json())
+
+    def test_observation_id_is_fhir_safe(self):
+        self.assertEqual(observation_id(_item("spo2", device_id="dev_A/1")), "dev-A-1-1735689600-spo2")
+
+
+class TestToFhirBundle(unittest.TestCase):
+    """Test the searchset Bundle."""
+
+    def test_entries_and_total(self):
+        bundle = to_fhir_bundle([_item("spo2", spo2=97), _item("weight", timestamp=TS + 60, kg=70.5)], "usr_p1", BASE + "/", total=5)
+
+        self.assertEqual((bundle.resourceType, bundle.type, bundle.total), ("Bundle", "searchset", 5))
+        self.assertEqual(bundle.entry[0].fullUrl, f"{BASE}/Observation/dev-1-1735689600-spo2")
+        self.assertEqual(bundle.entry[1].resource.valueQuantity.value, 70.5)
+
+    def test_empty(self):
+        self.assertEqual(to_fhir_bundle([], "usr_p1", BASE).model_dump(exclude_none=True)["entry"], [])
+
+
+class TestFhirEndpoints(unittest.TestCase):
+    """Test GET .../readings/{timestamp}/fhir and GET /patients/{id}/readings/fhir."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._sensor_data.clear()
+        db._patient_profiles.clear()
+        db._devices.append({"id": "dev_1", "patientId": "usr_p1"})
+        db.create_patient_profile({"userId": "usr_p1", "doctorId": "usr_d1"})
+        db._sensor_data.extend([
+            _item("heart_rate", bpm=70),
+            _item("spo2", spo2=98),
+            _item("heart_rate", timestamp=TS + 60, bpm=75),
+        ])
+        self.addCleanup(patch.stopall)
+        patch.object(main.audit_service, "log_event").start()
+        patch.object(main.compliance_service, "check_access_frequency").start()
+        patch("time.time", return_value=TS + 3600).start()
+
+    def test_single_reading(self):
+        obs = asyncio.run(main.get_reading_fhir.__wrapped__("dev_1", TS, fake_request("usr_d1", "doctor"), reading_type="spo2"))
+        self.assertEqual(obs.valueQuantity.value, 98)
+
+    def test_ambiguous_timestamp_needs_reading_type(self):
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.get_reading_fhir.__wrapped__("dev_1", TS, fake_request("usr_d1", "doctor")))
+        self.assertEqual(ctx.exception.detail["code"], "READING_TYPE_REQUIRED")
+
+    def test_patient_bundle_filtered_and_limited(self):
+        bundle = asyncio.run(main.get_patient_readings_fhir.__wrapped__(
+            "usr_p1", fake_request("usr_d1", "doctor"), reading_type="heart_rate", limit="1"
+        ))
+        self.assertEqual(bundle.total, 2)
+        self.assertEqual([e.resource.valueQuantity.value for e in bundle.entry], [75])
+
+    def test_unassigned_doctor_forbidden(self):
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.get_patient_readings_fhir.__wrapped__("usr_p1", fake_request("usr_d2", "doctor")))
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_invalid_limit(self):
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.get_patient_readings_fhir.__wrapped__("usr_p1", fake_request("usr_d1", "doctor"), limit="5000"))
+        self.assertEqual(ctx.exception.detail["param"], "limit")
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,220 @@
+"""
+Tests for login geolocation and impossible-travel detection
+
+Run with: python -m pytest test_geo_service.py -v
+"""
+
+import os
+import json
+import unittest
+from datetime import datetime, timedelta, timezone
+from types import SimpleNamespace
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+
+import db
+import geo_service
+import main
+from fastapi import HTTPException
+from geo_service import (
+    GeoPoint, assess_travel, cloudfront_resolver, haversine_km, ipapi_resolver, location_denial_reason, resolve_location
+)
+
+LONDON = GeoPoint(51.5074, -0.1278, "GB", "London")
+PARIS = GeoPoint(48.8566, 2.3522, "FR", "Paris")
+NEW_YORK = GeoPoint(40.7128, -74.0060, "US", "New York")
+T0 = datetime(2025, 1, 1, 12, 0, tzinfo=timezone.utc)
+
+
+class TestAssessTravel(unittest.TestCase):
+    """Test distance and implied speed between two logins."""
+
+    def test_haversine_distance(self):
+        self.assertAlmostEqual(haversine_km(LONDON, PARIS), 344, delta=2)
+        self.assertAlmostEqual(haversine_km(LONDON, NEW_YORK), 5570, delta=10)
+        self.assertEqual(haversine_km(LONDON, LONDON), 0)
+
+    def test_transatlantic_in_thirty_minutes_is_impossible(self):
+        travel = assess_travel(LONDON, T0, NEW_YORK, T0 + timedelta(minutes=30))
+        self.assertTrue(travel.impossible)
+        self.assertAlmostEqual(travel.speed_kmh, 11140, delta=30)
+
+    def test_transatlantic_in_ten_hours_is_possible(self):
+        travel = assess_travel(LONDON, T0, NEW_YORK, T0 + timedelta(hours=10))
+        self.assertFalse(travel.impossible)
+        self.assertAlmostEqual(travel.elapsed_hours, 10)
+
+    def test_short_hop_ignored(self):
+        # London-Paris in 10 minutes is too fast, but within geo-IP accuracy
+        travel = assess_travel(LONDON, T0, PARIS, T0 + timedelta(minutes=10))
+        self.assertFalse(travel.impossible)
+
+    def test_simultaneous_distant_logins_are_impossible(self):
+        travel = assess_travel(LONDON, T0, NEW_YORK, T0)
+        self.assertTrue(travel.impossible)
+        self.assertIsNone(travel.speed_kmh)
+        self.assertIsNone(travel.to_dict()["speedKmh"])
+
+
+class TestResolveLocation(unittest.TestCase):
+    """Test the pluggable resolver fails open."""
+
+    def tearDown(self):
+        geo_service.set_geo_resolver(geo_service.null_resolver)
+
+    def test_cloudfront_headers(self):
+        point = cloudfront_resolver("203.0.113.9", {
+            "cloudfront-viewer-latitude": "51.5074", "cloudfront-viewer-longitude": "-0.1278",
+            "cloudfront-viewer-country": "GB",
+        })
+        self.assertEqual((point.latitude, point.longitude, point.country_code), (51.5074, -0.1278, "GB"))
+        self.assertIsNone(cloudfront_resolver("203.0.113.9", {"cloudfront-viewer-latitude": "x"}))
+
+    def test_headers_matched_case_insensitively(self):
+        geo_service.set_geo_resolver(cloudfront_resolver)
+        point = resolve_location(None, {"CloudFront-Viewer-Latitude": "1", "CloudFront-Viewer-Longitude": "2"})
+        self.assertEqual((point.latitude, point.longitude), (1.0, 2.0))
+
+    def test_resolver_error_returns_none(self):
+        def broken(ip, headers):
+            raise RuntimeError("geo database unavailable")
+        geo_service.set_geo_resolver(broken)
+        self.assertIsNone(resolve_location("203.0.113.9", {}))
+
+
+class _DictCache:
+    def __init__(self):
+        self.values = {}
+
+    def get(self, key):
+        return self.values.get(key)
+
+    def set(self, key, value, ttl_secs=None):
+        self.values[key] = value
+        self.ttl = ttl_secs
+
+
+def _ipapi_response(payload):
+    resp = MagicMock()
+    resp.read.return_value = json.dumps(payload).encode()
+    resp.__enter__.return_value = resp
+    return resp
+
+
+class TestIpapiResolver(unittest.TestCase):
+    """Test the HTTP geolocation provider and per-IP caching."""
+
+    def tearDown(self):
+        geo_service.set_geo_resolver(geo_service.null_resolver)
+
+    def test_response_parsed(self):
+        payload = {"latitude": 51.5, "longitude": -0.12, "country_code": "GB", "region": "England",
+                   "city": "London", "security": {"vpn": True}}
+        with patch.object(geo_service.urllib.request, "urlopen", return_value=_ipapi_response(payload)) as urlopen:
+            point = ipapi_resolver("8.8.8.8", {})
+        self.assertEqual(urlopen.call_args.args[0], "https://ipapi.co/8.8.8.8/json/")
+        self.assertEqual((point.country_code, point.region, point.city, point.is_vpn), ("GB", "England", "London", True))
+
+    def test_private_and_reserved_addresses_not_looked_up(self):
+        with patch.object(geo_service.urllib.request, "urlopen") as urlopen:
+            for ip in ("10.0.0.1", "127.0.0.1", "203.0.113.9", None):
+                self.assertIsNone(ipapi_resolver(ip, {}))
+        urlopen.assert_not_called()
+
+    def test_error_response_is_unknown(self):
+        with patch.object(geo_service.urllib.request, "urlopen", return_value=_ipapi_response({"error": True, "reason": "RateLimited"})):
+            self.assertIsNone(ipapi_resolver("8.8.8.8", {}))
+
+    def test_lookup_cached_per_ip_for_a_day(self):
+        resolver = MagicMock(return_value=LONDON)
+        geo_service.set_geo_resolver(resolver)
+        cache = _DictCache()
+        with patch.object(geo_service, "cache_service", cache):
+            self.assertEqual(resolve_location("8.8.8.8", {}).city, "London")
+            self.assertEqual(resolve_location("8.8.8.8", {}).city, "London")
+        self.assertEqual(resolver.call_count, 1)
+        self.assertEqual(cache.ttl, 24 * 3600)
+
+
+class TestGeographicAccessControls(unittest.TestCase):
+    """Test ALLOWED_COUNTRIES and BLOCK_VPN."""
+
+    def test_no_restrictions_by_default(self):
+        self.assertIsNone(location_denial_reason(NEW_YORK))
+
+    def test_country_outside_allow_list_denied(self):
+        with patch.object(geo_service, "ALLOWED_COUNTRIES", frozenset({"GB", "FR"})):
+            self.assertIsNone(location_denial_reason(LONDON))
+            self.assertEqual(location_denial_reason(NEW_YORK), "country")
+            self.assertIsNone(location_denial_reason(None))
+
+    def test_vpn_denied_when_blocked(self):
+        vpn = GeoPoint(51.5, -0.12, "GB", is_vpn=True)
+        self.assertIsNone(location_denial_reason(vpn))
+        with patch.object(geo_service, "BLOCK_VPN", True):
+            self.assertEqual(location_denial_reason(vpn), "vpn")
+
+    def test_country_list_parsed(self):
+        self.assertEqual(geo_service._parse_countries(" gb, fr ,"), frozenset({"GB", "FR"}))
+        self.assertIsNone(geo_service._parse_countries(""))
+
+    def test_login_refused_and_audited(self):
+        request = SimpleNamespace(client=SimpleNamespace(host="8.8.8.8"), headers={})
+        with patch.object(main, "resolve_location", return_value=NEW_YORK), \
+                patch.object(geo_service, "ALLOWED_COUNTRIES", frozenset({"GB"})), \
+                patch.object(main.audit_service, "log_login_failure") as log_failure:
+            with self.assertRaises(HTTPException) as ctx:
+                main._login_geo(request, "jane@example.com", main.LOGIN_ENDPOINT)
+        self.assertEqual(ctx.exception.status_code, 403)
+        self.assertEqual(ctx.exception.detail["message"], "Access not permitted from your location")
+        self.assertEqual(log_failure.call_args.kwargs["reason"], "country_not_allowed")
+        self.assertEqual(log_failure.call_args.kwargs["geo"]["country_code"], "US")
+
+
+class TestLoginLocationCheck(unittest.TestCase):
+    """Test main._check_login_location audits and optionally blocks."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({
+            "id": "usr_1", "email": "jane@example.com", "role": "patient",
+            "lastLoginIp": "198.51.100.1", "lastLoginGeo": LONDON.to_dict(),
+            "lastLoginAt": (datetime.now(timezone.utc) - timedelta(minutes=30)).isoformat(),
+        })
+        self.addCleanup(patch.stopall)
+        patch.object(main, "resolve_location", return_value=NEW_YORK).start()
+        self.log_event = patch.object(main.audit_service, "log_event").start()
+        self.request = SimpleNamespace(client=SimpleNamespace(host="203.0.113.9"), headers={})
+
+    def test_impossible_travel_audited_and_location_updated(self):
+        main._check_login_location(db.get_user("usr_1"), self.request, mfa_verified=False)
+
+        kwargs = self.log_event.call_args.kwargs
+        self.assertEqual(kwargs["event_type"], main.AuditEventType.SECURITY_SUSPICIOUS_ACTIVITY)
+        self.assertEqual(kwargs["action"], "impossible_travel")
+        self.assertEqual(kwargs["details"]["previousCountry"], "GB")
+        user = db.get_user("usr_1")
+        self.assertEqual(user["lastLoginIp"], "203.0.113.9")
+        self.assertEqual(GeoPoint.from_dict(user["lastLoginGeo"]).country_code, "US")
+
+    def test_step_up_blocks_login_without_mfa(self):
+        with patch.object(main, "IMPOSSIBLE_TRAVEL_STEP_UP", True):
+            with self.assertRaises(HTTPException) as ctx:
+                main._check_login_location(db.get_user("usr_1"), self.request, mfa_verified=False)
+            self.assertEqual(ctx.exception.detail["code"], "STEP_UP_REQUIRED")
+            self.assertEqual(db.get_user("usr_1")["lastLoginIp"], "198.51.100.1")
+
+            main._check_login_location(db.get_user("usr_1"), self.request, mfa_verified=True)
+        self.assertEqual(db.get_user("usr_1")["lastLoginIp"], "203.0.113.9")
+
+    def test_unknown_location_not_flagged(self):
+        with patch.object(main, "resolve_location", return_value=None):
+            main._check_login_location(db.get_user("usr_1"), self.request, mfa_verified=False)
+        self.log_event.assert_not_called()
+        self.assertEqual(GeoPoint.from_dict(db.get_user("usr_1")["lastLoginGeo"]).country_code, "GB")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,186 @@
+"""
+Tests for idempotency keys
+
+Run with: python -m pytest test_idempotency_service.py -v
+"""
+
+import os
+import json
+import time
+import threading
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import device_data_ingest
+from device_data_ingest import ingest, hash_device_api_key
+from idempotency_service import (
+    idempotent, check_or_store, FirstCall, Duplicate, IdempotencyKeyInUseError, IdempotencyKeyMismatchError,
+    InvalidIdempotencyKeyError, MissingIdempotencyKeyError, IDEMPOTENCY_TTL_SECONDS, IDEMPOTENCY_LEASE_SECONDS
+)
+
+
+class TestIdempotent(unittest.TestCase):
+    """Test the idempotent() helper against the in-memory store."""
+
+    def setUp(self):
+        db._idempotency.clear()
+        self.calls = 0
+
+    def _op(self):
+        self.calls += 1
+        return {"id": f"rec_{self.calls}"}
+
+    def test_repeated_key_returns_original_result(self):
+        first = idempotent("k1", self._op, scope="report:u1", payload={"a": 1})
+        second = idempotent("k1", self._op, scope="report:u1", payload={"a": 1})
+        self.assertEqual(first, second)
+        self.assertEqual(self.calls, 1)
+
+    def test_no_key_always_runs(self):
+        idempotent(None, self._op, scope="report:u1")
+        idempotent(None, self._op, scope="report:u1")
+        self.assertEqual(self.calls, 2)
+
+    def test_keys_are_scoped(self):
+        a = idempotent("k1", self._op, scope="report:u1")
+        b = idempotent("k1", self._op, scope="report:u2")
+        self.assertNotEqual(a, b)
+
+    def test_required_key_missing_rejected(self):
+        with self.assertRaises(MissingIdempotencyKeyError) as ctx:
+            idempotent(None, self._op, scope="report:u1", required=True)
+        self.assertEqual(ctx.exception.status_code, 400)
+        self.assertEqual(self.calls, 0)
+
+    def test_check_or_store_claims_then_replays(self):
+        first = check_or_store("k1", "report:u1", {"a": 1})
+        self.assertEqual(first, FirstCall("report:u1:k1"))
+        stored = db._idempotency["report:u1:k1"]
+        self.assertAlmostEqual(stored["expiresAt"] - stored["createdAt"], IDEMPOTENCY_TTL_SECONDS, delta=1)
+        self.assertEqual(IDEMPOTENCY_TTL_SECONDS, 24 * 60 * 60)
+
+        db.complete_idempotency_key(first.storage_key, json.dumps({"id": "rec_1"}), first.locked_until)
+        self.assertEqual(check_or_store("k1", "report:u1", {"a": 1}), Duplicate({"id": "rec_1"}))
+
+    def test_different_payload_rejected(self):
+        idempotent("k1", self._op, scope="s", payload={"a": 1})
+        with self.assertRaises(IdempotencyKeyMismatchError):
+            idempotent("k1", self._op, scope="s", payload={"a": 2})
+
+    def test_invalid_key_rejected(self):
+        with self.assertRaises(InvalidIdempotencyKeyError):
+            idempotent("  ", self._op, scope="s")
+        with self.assertRaises(InvalidIdempotencyKeyError):
+            idempotent("x" * 256, self._op, scope="s")
+
+    def test_failed_op_releases_key(self):
+        def failing():
+            raise RuntimeError("boom")
+        with self.assertRaises(RuntimeError):
+            idempotent("k1", failing, scope="s")
+        self.assertEqual(idempotent("k1", self._op, scope="s"), {"id": "rec_1"})
+
+    def test_expired_key_can_be_reused(self):
+        idempotent("k1", self._op, scope="s")
+        db._idempotency["s:k1"]["expiresAt"] = 0
+        self.assertEqual(idempotent("k1", self._op, scope="s"), {"id": "rec_2"})
+
+    def test_concurrent_request_is_rejected_while_in_progress(self):
+        started, release = threading.Event(), threading.Event()
+        results = {}
+
+        def slow_op():
+            started.set()
+            release.wait(5)
+            return {"id": "slow"}
+
+        worker = threading.Thread(target=lambda: results.update(first=idempotent("k1", slow_op, scope="s")))
+        worker.start()
+        started.wait(5)
+        with self.assertRaises(IdempotencyKeyInUseError):
+            idempotent("k1", self._op, scope="s")
+        release.set()
+        worker.join(5)
+
+        self.assertEqual(results["first"], {"id": "slow"})
+        self.assertEqual(idempotent("k1", self._op, scope="s"), {"id": "slow"})
+        self.assertEqual(self.calls, 0)
+
+    def test_abandoned_claim_taken_over_after_lease(self):
+        first = check_or_store("k1", "s")
+        self.assertIsInstance(first, FirstCall)
+        with self.assertRaises(IdempotencyKeyInUseError):
+            idempotent("k1", self._op, scope="s")
+
+        # The first request never completed or released its claim (e.g. Lambda timeout)
+        db._idempotency["s:k1"]["lockedUntil"] = 0
+        self.assertEqual(idempotent("k1", self._op, scope="s"), {"id": "rec_1"})
+        self.assertEqual(idempotent("k1", self._op, scope="s"), {"id": "rec_1"})
+
+    def test_stale_claim_cannot_complete_or_release(self):
+        with patch("idempotency_service.time.time", return_value=time.time() - 2 * IDEMPOTENCY_LEASE_SECONDS):
+            first = check_or_store("k1", "s")
+        second = check_or_store("k1", "s")
+        self.assertIsInstance(second, FirstCall)
+
+        self.assertFalse(db.complete_idempotency_key("s:k1", json.dumps({"id": "stale"}), first.locked_until))
+        db.release_idempotency_key("s:k1", first.locked_until)
+        self.assertEqual(db._idempotency["s:k1"]["status"], "in_progress")
+
+        self.assertTrue(db.complete_idempotency_key("s:k1", json.dumps({"id": "rec_9"}), second.locked_until))
+        self.assertEqual(check_or_store("k1", "s"), Duplicate({"id": "rec_9"}))
+
+
+class TestIdempotentIngest(unittest.TestCase):
+    """Retried device uploads must be queued only once."""
+
+    def setUp(self):
+        db._idempotency.clear()
+        db._devices.clear()
+        db._devices.append({"id": "DEV-1", "patientId": "PAT-1", "apiKeyHash": hash_device_api_key("key")})
+        patcher = patch.object(device_data_ingest, "_sqs")
+        self.sqs = patcher.start()
+        self.sqs.send_message_batch.return_value = {"Successful": [], "Failed": []}
+        self.addCleanup(patcher.stop)
+
+    def _event(self, key, readings):
+        headers = {"X-Device-Id": "DEV-1", "X-Api-Key": "key", "Idempotency-Key": key}
+        return {"headers": headers, "body": json.dumps({"readings": readings})}
+
+    def test_same_key_enqueues_once_and_returns_same_batch(self):
+        readings = [{"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": 70}}]
+        first = ingest(self._event("upload-1", readings), None)
+        second = ingest(self._event("upload-1", readings), None)
+
+        self.assertEqual(first["statusCode"], 202)
+        self.assertEqual(second["statusCode"], 202)
+        self.assertEqual(json.loads(first["body"])["batchId"], json.loads(second["body"])["batchId"])
+        self.assertEqual(self.sqs.send_message_batch.call_count, 1)
+
+    def test_same_key_different_readings_rejected(self):
+        ingest(self._event("upload-1", [{"timestamp": 1, "values": {"bpm": 70}}]), None)
+        resp = ingest(self._event("upload-1", [{"timestamp": 2, "values": {"bpm": 70}}]), None)
+        self.assertEqual(resp["statusCode"], 422)
+        self.assertEqual(json.loads(resp["body"])["code"], "IDEMPOTENCY_KEY_MISMATCH")
+
+    def test_missing_key_rejected_before_queueing(self):
+        event = self._event("upload-1", [{"timestamp": 1735689600, "values": {"bpm": 70}}])
+        del event["headers"]["Idempotency-Key"]
+        resp = ingest(event, None)
+        self.assertEqual(resp["statusCode"], 400)
+        self.assertEqual(json.loads(resp["body"])["code"], "IDEMPOTENCY_KEY_REQUIRED")
+        self.sqs.send_message_batch.assert_not_called()
+
+    def test_queue_failure_allows_retry(self):
+        readings = [{"timestamp": 1735689600, "values": {"bpm": 70}}]
+        self.sqs.send_message_batch.side_effect = Exception("unavailable")
+        self.assertEqual(ingest(self._event("upload-1", readings), None)["statusCode"], 503)
+        self.sqs.send_message_batch.side_effect = None
+        self.assertEqual(ingest(self._event("upload-1", readings), None)["statusCode"], 202)
+
+
+if __name__ == '__main__':
+    unittest.main()
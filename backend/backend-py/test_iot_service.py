@@ -0,0 +1,123 @@
+"""
+Tests for the AWS IoT Core service
+
+Run with: python -m pytest test_iot_service.py -v
+"""
+
+import io
+import json
+import unittest
+from unittest.mock import MagicMock
+
+from botocore.exceptions import ClientError
+
+from iot_service import IoTService, IoTNotConfiguredError
+
+THING_ARN = "arn:aws:iot:us-east-1:123456789012:thing/medusa-dev_1"
+CERT_ARN = "arn:aws:iot:us-east-1:123456789012:cert/abc123"
+
+
+def _client_error(code):
+    return ClientError({"Error": {"Code": code, "Message": code}}, "op")
+
+
+def _service(endpoint="example-ats.iot.us-east-1.amazonaws.com"):
+    service = IoTService(endpoint=endpoint, policy_name="device-policy", thing_prefix="medusa-")
+    service._iot = MagicMock()
+    service._iot_data = MagicMock() if endpoint else None
+    return service
+
+
+class TestDeviceThing(unittest.TestCase):
+    """Test Thing creation."""
+
+    def test_creates_thing_with_attributes(self):
+        service = _service()
+        service._iot.create_thing.return_value = {"thingArn": THING_ARN}
+
+        self.assertEqual(service.create_device_thing("dev_1", "tremor_sensor"), THING_ARN)
+        service._iot.create_thing.assert_called_once_with(
+            thingName="medusa-dev_1",
+            attributePayload={"attributes": {"deviceId": "dev_1", "deviceType": "tremor_sensor"}}
+        )
+
+    def test_existing_thing_returns_its_arn(self):
+        service = _service()
+        service._iot.create_thing.side_effect = _client_error("ResourceAlreadyExistsException")
+        service._iot.describe_thing.return_value = {"thingArn": THING_ARN}
+
+        self.assertEqual(service.create_device_thing("dev_1", "tremor_sensor"), THING_ARN)
+
+    def test_other_errors_propagate(self):
+        service = _service()
+        service._iot.create_thing.side_effect = _client_error("InvalidRequestException")
+
+        with self.assertRaises(ClientError):
+            service.create_device_thing("dev_1", "tremor_sensor")
+
+
+class TestDeviceCertificate(unittest.TestCase):
+    """Test certificate registration."""
+
+    def setUp(self):
+        self.service = _service()
+        self.service._iot.create_certificate_from_csr.return_value = {
+            "certificateArn": CERT_ARN,
+            "certificateId": "abc123",
+            "certificatePem": "-----BEGIN CERTIFICATE-----\n...",
+        }
+
+    def test_issues_and_attaches_certificate(self):
+        pem = self.service.register_device_certificate("dev_1", "-----BEGIN CERTIFICATE REQUEST-----")
+
+        self.assertTrue(pem.startswith("-----BEGIN CERTIFICATE-----"))
+        self.service._iot.create_certificate_from_csr.assert_called_once_with(
+            certificateSigningRequest="-----BEGIN CERTIFICATE REQUEST-----", setAsActive=True
+        )
+        self.service._iot.attach_policy.assert_called_once_with(policyName="device-policy", target=CERT_ARN)
+        self.service._iot.attach_thing_principal.assert_called_once_with(
+            thingName="medusa-dev_1", principal=CERT_ARN
+        )
+
+    def test_failed_attach_deactivates_certificate(self):
+        self.service._iot.attach_thing_principal.side_effect = _client_error("ResourceNotFoundException")
+
+        with self.assertRaises(ClientError):
+            self.service.register_device_certificate("dev_1", "csr")
+        self.service._iot.update_certificate.assert_called_once_with(certificateId="abc123", newStatus="INACTIVE")
+
+
+class TestDeviceShadow(unittest.TestCase):
+    """Test shadow access."""
+
+    def test_get_shadow_parses_payload(self):
+        service = _service()
+        doc = {"state": {"reported": {"samplingHz": 50}}, "version": 3}
+        service._iot_data.get_thing_shadow.return_value = {"payload": io.BytesIO(json.dumps(doc).encode())}
+
+        self.assertEqual(service.get_device_shadow("dev_1"), doc)
+        service._iot_data.get_thing_shadow.assert_called_once_with(thingName="medusa-dev_1")
+
+    def test_missing_shadow_is_empty(self):
+        service = _service()
+        service._iot_data.get_thing_shadow.side_effect = _client_error("ResourceNotFoundException")
+
+        self.assertEqual(service.get_device_shadow("dev_1"), {})
+
+    def test_update_sets_desired_state(self):
+        service = _service()
+        service.update_device_shadow("dev_1", {"samplingHz": 100})
+
+        kwargs = service._iot_data.update_thing_shadow.call_args.kwargs
+        self.assertEqual(kwargs["thingName"], "medusa-dev_1")
+        self.assertEqual(json.loads(kwargs["payload"]), {"state": {"desired": {"samplingHz": 100}}})
+
+    def test_shadow_requires_endpoint(self):
+        service = _service(endpoint="")
+
+        with self.assertRaises(IoTNotConfiguredError):
+            service.get_device_shadow("dev_1")
+
+
+if __name__ == "__main__":
+    unittest.main()
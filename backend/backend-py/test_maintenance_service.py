@@ -0,0 +1,77 @@
+"""
+Tests for MeDUSA Maintenance Mode Service
+
+Run with: python -m pytest test_maintenance_service.py -v
+"""
+
+import os
+import asyncio
+import unittest
+from types import SimpleNamespace
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from maintenance_service import MaintenanceService, maintenance_middleware
+import maintenance_service as maintenance_module
+
+
+def _request(path, role=None):
+    state = SimpleNamespace(claims={"sub": "usr_1", "role": role} if role else None)
+    return SimpleNamespace(method="GET", url=SimpleNamespace(path=path), state=state)
+
+
+async def _ok(request):
+    return SimpleNamespace(status_code=200)
+
+
+class TestMaintenanceMode(unittest.TestCase):
+    """Test the maintenance gate for patients and admins."""
+
+    def setUp(self):
+        db._system_settings.clear()
+        self.service = MaintenanceService(cache_seconds=0)
+        self._original = maintenance_module.maintenance_service
+        maintenance_module.maintenance_service = self.service
+
+    def tearDown(self):
+        maintenance_module.maintenance_service = self._original
+
+    def _status(self, path, role=None):
+        return asyncio.run(maintenance_middleware(_request(path, role), _ok)).status_code
+
+    def test_everyone_allowed_when_off(self):
+        self.assertEqual(self._status("/api/v1/poses", "patient"), 200)
+        self.assertEqual(self._status("/api/v1/admin/stats", "admin"), 200)
+
+    def test_patient_blocked_when_on(self):
+        self.service.set_enabled(True, "usr_admin", "admin", message="Back at 10:00 UTC")
+        response = asyncio.run(maintenance_middleware(_request("/api/v1/poses", "patient"), _ok))
+        self.assertEqual(response.status_code, 503)
+        self.assertEqual(response.headers["Retry-After"], "300")
+
+    def test_admin_allowed_when_on(self):
+        self.service.set_enabled(True, "usr_admin", "admin")
+        self.assertEqual(self._status("/api/v1/admin/stats", "admin"), 200)
+
+    def test_login_reachable_when_on(self):
+        self.service.set_enabled(True, "usr_admin", "admin")
+        self.assertEqual(self._status("/api/v1/auth/login"), 200)
+        self.assertEqual(self._status("/api/v1/auth/register"), 503)
+
+    def test_disable_reopens(self):
+        self.service.set_enabled(True, "usr_admin", "admin")
+        self.service.set_enabled("false", "usr_admin", "admin")
+        self.assertEqual(self._status("/api/v1/poses", "patient"), 200)
+
+    def test_flag_is_cached(self):
+        cached = MaintenanceService(cache_seconds=60)
+        self.assertFalse(cached.is_enabled())
+        db.put_system_setting("maintenanceMode", True, "usr_admin")
+        self.assertFalse(cached.is_enabled())
+        cached.invalidate()
+        self.assertTrue(cached.is_enabled())
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,144 @@
+"""
+Tests for MeDUSA medication interaction checks
+
+Run with: python -m pytest test_medication_interactions.py -v
+"""
+
+import os
+import sys
+import json
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from fastapi import HTTPException
+
+import db
+import main
+import medication_interactions
+from medication_interactions import (
+    BuiltinInteractionSource, InteractionSeverity, InteractionSource, S3InteractionSource,
+    check_interactions, set_interaction_source,
+)
+from models import MedicationReq
+from report_pdf import load_patient_summary_data
+
+
+def _med(medication_id, name, active=True):
+    return {"medicationId": medication_id, "name": name, "isActive": active}
+
+
+class TestCheckInteractions(unittest.TestCase):
+    """Test interaction detection against the built-in table."""
+
+    def setUp(self):
+        set_interaction_source(BuiltinInteractionSource())
+
+    def test_interacting_pair(self):
+        warnings = check_interactions([_med("MED-1", "Selegiline 5 mg"), _med("MED-2", "Meperidine")])
+        self.assertEqual(len(warnings), 1)
+        self.assertEqual(warnings[0].severity, InteractionSeverity.CRITICAL)
+        self.assertEqual(warnings[0].medication_ids, ("MED-1", "MED-2"))
+
+    def test_non_interacting_pair(self):
+        self.assertEqual(check_interactions([_med("MED-1", "Levodopa"), _med("MED-2", "Pramipexole")]), [])
+
+    def test_combination_product_matches_each_drug(self):
+        warnings = check_interactions([_med("MED-1", "Carbidopa/Levodopa 25/100"), _med("MED-2", "Haloperidol")])
+        self.assertEqual([w.severity for w in warnings], [InteractionSeverity.MAJOR])
+
+    def test_partial_word_does_not_match(self):
+        self.assertEqual(check_interactions([_med("MED-1", "Xlevodopax"), _med("MED-2", "Haloperidol")]), [])
+
+    def test_discontinued_medications_ignored(self):
+        meds = [_med("MED-1", "Selegiline"), _med("MED-2", "Meperidine", active=False)]
+        self.assertEqual(check_interactions(meds), [])
+
+    def test_most_severe_first(self):
+        meds = [_med("MED-1", "Levodopa"), _med("MED-2", "Ferrous Sulfate"), _med("MED-3", "Phenelzine")]
+        severities = [w.severity for w in check_interactions(meds)]
+        self.assertEqual(severities, [InteractionSeverity.CRITICAL, InteractionSeverity.MINOR])
+
+
+class TestInteractionSources(unittest.TestCase):
+    """Test the pluggable interaction dataset."""
+
+    def tearDown(self):
+        set_interaction_source(BuiltinInteractionSource())
+
+    def test_custom_source(self):
+        class Source(InteractionSource):
+            def load(self):
+                return [medication_interactions.InteractionRule("drug x", "drug y", InteractionSeverity.MODERATE, "test")]
+
+        set_interaction_source(Source())
+        warnings = check_interactions([_med("MED-1", "Drug X"), _med("MED-2", "Drug Y")])
+        self.assertEqual([w.description for w in warnings], ["test"])
+        self.assertEqual(check_interactions([_med("MED-1", "Selegiline"), _med("MED-2", "Meperidine")]), [])
+
+    def test_s3_source(self):
+        table = [{"drugA": "Warfarin", "drugB": "Aspirin", "severity": "MAJOR", "description": "Bleeding risk"}]
+        with patch("medication_interactions.storage.download_bytes", return_value=json.dumps(table).encode()) as download:
+            set_interaction_source(S3InteractionSource("config/interactions.json"))
+            warnings = check_interactions([_med("MED-1", "aspirin"), _med("MED-2", "warfarin")])
+            check_interactions([])
+        download.assert_called_once_with("config/interactions.json")
+        self.assertEqual(warnings[0].severity, InteractionSeverity.MAJOR)
+
+
+class TestMedicationInteractionEndpoints(unittest.TestCase):
+    """Test interaction warnings when prescribing and in the summary report."""
+
+    def setUp(self):
+        set_interaction_source(BuiltinInteractionSource())
+        db._medications.clear()
+        db._users.pop("usr_int_p1", None)
+        db.create_user({"id": "usr_int_p1", "email": "intp1@example.com", "role": "patient", "name": "Pat"})
+        db.create_medication("usr_int_p1", {"name": "Selegiline", "dosage": "5 mg", "frequency": "daily"})
+        self.request = fake_request("usr_admin", "admin")
+
+    def tearDown(self):
+        db._medications.clear()
+        db._users.pop("usr_int_p1", None)
+
+    def _add(self, name, override=False):
+        body = MedicationReq(name=name, dosage="50 mg", frequency="as needed")
+        return asyncio.run(main.add_medication.__wrapped__("usr_int_p1", body, self.request, override))
+
+    def test_critical_interaction_blocked(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._add("Meperidine")
+        self.assertEqual(ctx.exception.status_code, 409)
+        self.assertEqual(ctx.exception.detail["code"], "MEDICATION_INTERACTION")
+        self.assertEqual(ctx.exception.detail["interactions"][0]["severity"], "critical")
+        self.assertEqual(len(db.get_medications("usr_int_p1")), 1)
+
+    def test_override_allows_critical_interaction(self):
+        self.assertEqual(self._add("Meperidine", override=True).name, "Meperidine")
+
+    def test_non_critical_interaction_allowed(self):
+        self.assertEqual(self._add("Tramadol").name, "Tramadol")
+
+    def test_blocking_can_be_disabled(self):
+        with patch.object(medication_interactions, "BLOCK_CRITICAL_INTERACTIONS", False):
+            self.assertEqual(self._add("Meperidine").name, "Meperidine")
+
+    def test_list_interactions(self):
+        self._add("Tramadol")
+        res = asyncio.run(main.list_medication_interactions.__wrapped__("usr_int_p1", self.request))
+        self.assertEqual(res.count, 1)
+        self.assertEqual(res.items[0].severity, "major")
+
+    def test_summary_report_includes_interactions(self):
+        self._add("Meperidine", override=True)
+        data = load_patient_summary_data("usr_int_p1")
+        self.assertEqual([w["severity"] for w in data.interactions], ["critical"])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,124 @@
+"""
+Tests for MeDUSA patient medication records
+
+Run with: python -m pytest test_medications.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from fastapi import HTTPException
+from pydantic import ValidationError
+
+import db
+import main
+from models import MedicationReq
+
+
+class TestMedicationValidation(unittest.TestCase):
+    """Test medication request validation."""
+
+    def test_valid_request(self):
+        req = MedicationReq(name="Levodopa", dosage="100 mg", frequency="3x daily", startDate="2025-01-01")
+        self.assertEqual(req.name, "Levodopa")
+
+    def test_empty_name_rejected(self):
+        with self.assertRaises(ValidationError):
+            MedicationReq(name="", dosage="100 mg", frequency="daily")
+
+    def test_bad_date_format_rejected(self):
+        with self.assertRaises(ValidationError):
+            MedicationReq(name="Levodopa", dosage="100 mg", frequency="daily", startDate="01/02/2025")
+
+    def test_end_before_start_rejected(self):
+        with self.assertRaises(ValidationError):
+            MedicationReq(
+                name="Levodopa", dosage="100 mg", frequency="daily",
+                startDate="2025-02-01", endDate="2025-01-01"
+            )
+
+
+class TestMedicationStore(unittest.TestCase):
+    """Test medication persistence in memory mode."""
+
+    def setUp(self):
+        db._medications.clear()
+
+    def _create(self, patient_id="PAT-1", name="Levodopa"):
+        return db.create_medication(patient_id, {"name": name, "dosage": "100 mg", "frequency": "daily"})
+
+    def test_create_assigns_id_and_active(self):
+        med = self._create()
+        self.assertTrue(med["medicationId"].startswith("MED-"))
+        self.assertTrue(med["isActive"])
+        self.assertEqual(med["patientId"], "PAT-1")
+
+    def test_get_medications_scoped_to_patient(self):
+        self._create("PAT-1")
+        self._create("PAT-2")
+        self.assertEqual(len(db.get_medications("PAT-1")), 1)
+
+    def test_update_medication(self):
+        med = self._create()
+        updated = db.update_medication("PAT-1", med["medicationId"], {"dosage": "200 mg"})
+        self.assertEqual(updated["dosage"], "200 mg")
+        self.assertEqual(db.get_medication("PAT-1", med["medicationId"])["dosage"], "200 mg")
+
+    def test_update_missing_medication_returns_none(self):
+        self.assertIsNone(db.update_medication("PAT-1", "MED-MISSING", {"dosage": "1 mg"}))
+
+    def test_active_only_excludes_discontinued(self):
+        med = self._create()
+        self._create(name="Rasagiline")
+        db.update_medication("PAT-1", med["medicationId"], {"isActive": False})
+        active = db.get_medications("PAT-1", active_only=True)
+        self.assertEqual([m["name"] for m in active], ["Rasagiline"])
+        self.assertEqual(len(db.get_medications("PAT-1")), 2)
+
+    def test_purge_removes_patient_medications(self):
+        self._create("PAT-1")
+        self._create("PAT-2")
+        self.assertEqual(db.purge_patient_medications("PAT-1"), 1)
+        self.assertEqual(db.get_medications("PAT-1"), [])
+        self.assertEqual(len(db.get_medications("PAT-2")), 1)
+
+
+class TestReplaceMedication(unittest.TestCase):
+    """Test the PUT medication endpoint."""
+
+    def setUp(self):
+        db._medications.clear()
+        db._users["PAT-1"] = {"id": "PAT-1", "email": "pat1@example.com", "role": "patient"}
+        self.med = db.create_medication("PAT-1", {"name": "Levodopa", "dosage": "100 mg", "frequency": "daily"})
+        self.request = fake_request("usr_admin", "admin")
+
+    def tearDown(self):
+        db._medications.clear()
+        db._users.pop("PAT-1", None)
+
+    def _replace(self):
+        body = MedicationReq(name="Levodopa", dosage="200 mg", frequency="daily")
+        return asyncio.run(main.replace_medication.__wrapped__("PAT-1", self.med["medicationId"], body, self.request))
+
+    def test_replace_active_medication(self):
+        self.assertEqual(self._replace().dosage, "200 mg")
+
+    def test_discontinued_medication_rejected(self):
+        db.update_medication("PAT-1", self.med["medicationId"], {"isActive": False})
+
+        with self.assertRaises(HTTPException) as ctx:
+            self._replace()
+        self.assertEqual(ctx.exception.status_code, 409)
+        self.assertEqual(ctx.exception.detail["code"], "MEDICATION_DISCONTINUED")
+        self.assertEqual(db.get_medication("PAT-1", self.med["medicationId"])["dosage"], "100 mg")
+
+
+if __name__ == '__main__':
+    unittest.main()
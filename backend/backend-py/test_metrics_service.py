@@ -0,0 +1,103 @@
+"""
+Tests for CloudWatch EMF metrics
+
+Run with: python -m pytest test_metrics_service.py -v
+"""
+
+import os
+import json
+import unittest
+from types import SimpleNamespace
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+
+import db
+import main
+from fastapi import HTTPException
+from metrics_service import MetricsEmitter, AUTH_FAILURE, DB_OPERATION_DURATION
+from models import LoginReq
+
+
+def _printed_payloads(mock_print):
+    payloads = []
+    for call in mock_print.call_args_list:
+        try:
+            payloads.append(json.loads(call.args[0]))
+        except (ValueError, IndexError, TypeError):
+            continue
+    return [p for p in payloads if isinstance(p, dict) and "_aws" in p]
+
+
+class TestMetricsEmitter(unittest.TestCase):
+    """Test EMF payloads and the no-op default."""
+
+    def test_payload_shape(self):
+        payload = MetricsEmitter("MeDUSA/Test").build_payload(
+            {AUTH_FAILURE: (1, "Count")}, {"Endpoint": "/api/v1/auth/login", "Reason": "invalid_credentials"},
+            timestamp_ms=1735689600000
+        )
+        self.assertEqual(payload, {
+            "_aws": {
+                "Timestamp": 1735689600000,
+                "CloudWatchMetrics": [{
+                    "Namespace": "MeDUSA/Test",
+                    "Dimensions": [["Endpoint", "Reason"]],
+                    "Metrics": [{"Name": "AuthFailure", "Unit": "Count"}],
+                }],
+            },
+            "Endpoint": "/api/v1/auth/login",
+            "Reason": "invalid_credentials",
+            "AuthFailure": 1,
+        })
+
+    @patch("builtins.print")
+    def test_no_namespace_is_noop(self, mock_print):
+        emitter = MetricsEmitter(None)
+        self.assertIsNone(emitter.count(AUTH_FAILURE, Endpoint="/x"))
+        with emitter.timer(DB_OPERATION_DURATION, Operation="get_user"):
+            pass
+        mock_print.assert_not_called()
+
+    @patch("builtins.print")
+    def test_timer_emits_milliseconds_even_on_error(self, mock_print):
+        emitter = MetricsEmitter("MeDUSA/Test")
+        with self.assertRaises(RuntimeError):
+            with emitter.timer(DB_OPERATION_DURATION, Operation="get_user"):
+                raise RuntimeError("db down")
+        payload = _printed_payloads(mock_print)[0]
+        self.assertEqual(payload["_aws"]["CloudWatchMetrics"][0]["Metrics"],
+                         [{"Name": "DbOperationDuration", "Unit": "Milliseconds"}])
+        self.assertGreaterEqual(payload["DbOperationDuration"], 0)
+
+
+class TestLoginMetrics(unittest.TestCase):
+    """Test the login handler emits auth outcome and DB latency metrics."""
+
+    def setUp(self):
+        db._users.clear()
+        self.addCleanup(patch.stopall)
+        patch.object(main, "metrics", MetricsEmitter("MeDUSA/Test")).start()
+        patch.object(main.audit_service, "log_login_failure").start()
+
+    @patch("builtins.print")
+    def test_login_failure_metric(self, mock_print):
+        request = SimpleNamespace(client=SimpleNamespace(host="203.0.113.9"), headers={})
+        with self.assertRaises(HTTPException):
+            main.login(LoginReq(email="nobody@example.com", password="wrong"), request)
+
+        payloads = _printed_payloads(mock_print)
+        failure = next(p for p in payloads if AUTH_FAILURE in p)
+        emf = failure["_aws"]["CloudWatchMetrics"][0]
+        self.assertEqual(emf["Namespace"], "MeDUSA/Test")
+        self.assertEqual(emf["Metrics"], [{"Name": "AuthFailure", "Unit": "Count"}])
+        self.assertEqual(emf["Dimensions"], [["Endpoint", "Reason"]])
+        self.assertEqual((failure["Endpoint"], failure["Reason"]), ("/api/v1/auth/login", "invalid_credentials"))
+
+        db_timing = next(p for p in payloads if DB_OPERATION_DURATION in p)
+        self.assertEqual(db_timing["Operation"], "get_user_by_email")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,173 @@
+"""
+Tests for reading alert notifications
+
+Run with: python -m pytest test_notification_service.py -v
+"""
+
+import os
+import json
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import device_data_ingest
+from audit_service import AuditEventType
+from notification_service import (
+    NotificationService, Alert, AlertSeverity, classify_reading, notification_service
+)
+
+TOPIC_ARN = "arn:aws:sns:us-east-1:123456789012:medusa-reading-alerts"
+
+
+def _alert(severity=AlertSeverity.CRITICAL):
+    return Alert(
+        patient_id="usr_patient",
+        device_id="dev_1",
+        reading_type="heart_rate",
+        reading={"bpm": 165, "timestamp": 1735689600},
+        severity=severity,
+        reason="bpm 165 >= 150",
+    )
+
+
+class TestClassifyReading(unittest.TestCase):
+    """Test threshold classification."""
+
+    def test_normal_reading_has_no_alert(self):
+        self.assertIsNone(classify_reading("heart_rate", {"bpm": 72}))
+
+    def test_most_severe_rule_wins(self):
+        severity, reason = classify_reading("blood_pressure", {"systolic": 190, "diastolic": 95})
+        self.assertEqual(severity, AlertSeverity.CRITICAL)
+        self.assertIn("systolic", reason)
+
+    def test_low_values(self):
+        self.assertEqual(classify_reading("spo2", {"spo2": 91})[0], AlertSeverity.HIGH)
+        self.assertEqual(classify_reading("heart_rate", {"bpm": 35})[0], AlertSeverity.CRITICAL)
+
+    def test_unknown_type_has_no_alert(self):
+        self.assertIsNone(classify_reading("accelerometer", {"accel_x": 150.0}))
+
+
+class TestNotificationService(unittest.TestCase):
+    """Test SNS publishing with a mocked client."""
+
+    def setUp(self):
+        self.service = NotificationService(topic_arn=TOPIC_ARN, min_severity=AlertSeverity.HIGH)
+        self.sns = MagicMock()
+        self.service._sns = self.sns
+
+    def test_payload_shape(self):
+        self.assertTrue(self.service.notify(_alert()))
+
+        kwargs = self.sns.publish.call_args.kwargs
+        self.assertEqual(kwargs["TopicArn"], TOPIC_ARN)
+        payload = json.loads(kwargs["Message"])
+        self.assertEqual(payload["type"], "READING_ALERT")
+        self.assertEqual(payload["patientId"], "usr_patient")
+        self.assertEqual(payload["deviceId"], "dev_1")
+        self.assertEqual(payload["readingType"], "heart_rate")
+        self.assertEqual(payload["reading"]["bpm"], 165)
+        self.assertEqual(payload["severity"], "critical")
+        self.assertIn("createdAt", payload)
+        self.assertEqual(kwargs["MessageAttributes"]["severity"]["StringValue"], "critical")
+
+    def test_below_threshold_does_not_publish(self):
+        self.assertFalse(self.service.notify(_alert(AlertSeverity.MEDIUM)))
+        self.assertFalse(self.service.notify(_alert(AlertSeverity.LOW)))
+        self.sns.publish.assert_not_called()
+
+    def test_threshold_is_inclusive(self):
+        self.assertTrue(self.service.notify(_alert(AlertSeverity.HIGH)))
+        self.sns.publish.assert_called_once()
+
+    def test_no_topic_does_not_publish(self):
+        self.service.topic_arn = None
+        self.assertFalse(self.service.notify(_alert()))
+        self.sns.publish.assert_not_called()
+
+    def test_publish_failure_is_audited_not_raised(self):
+        self.sns.publish.side_effect = RuntimeError("SNS unavailable")
+        with patch("notification_service.audit_service.log_event") as log_event:
+            self.assertFalse(self.service.notify(_alert()))
+
+        kwargs = log_event.call_args.kwargs
+        self.assertEqual(kwargs["event_type"], AuditEventType.ALERT_NOTIFICATION_FAILED)
+        self.assertEqual(kwargs["outcome"], "failure")
+        self.assertEqual(kwargs["resource_id"], "usr_patient")
+
+
+class TestIngestionAlerts(unittest.TestCase):
+    """Test alerts raised while processing queued readings."""
+
+    def setUp(self):
+        db._sensor_data.clear()
+        self.sns = MagicMock()
+        self._saved = (notification_service.topic_arn, notification_service.min_severity, notification_service._sns)
+        notification_service.topic_arn = TOPIC_ARN
+        notification_service.min_severity = AlertSeverity.HIGH
+        notification_service._sns = self.sns
+
+    def tearDown(self):
+        notification_service.topic_arn, notification_service.min_severity, notification_service._sns = self._saved
+
+    def _message(self, *readings):
+        return {"deviceId": "dev_1", "patientId": "usr_patient", "readings": list(readings)}
+
+    def test_critical_reading_publishes(self):
+        ingested, flagged = device_data_ingest.process_message(self._message(
+            {"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": 72}},
+            {"timestamp": 1735689601, "readingType": "heart_rate", "values": {"bpm": 170}},
+        ))
+
+        self.assertEqual((ingested, flagged), (2, 0))
+        self.sns.publish.assert_called_once()
+        payload = json.loads(self.sns.publish.call_args.kwargs["Message"])
+        self.assertEqual(payload["reading"]["timestamp"], 1735689601)
+
+    def test_publish_failure_does_not_fail_ingestion(self):
+        self.sns.publish.side_effect = RuntimeError("SNS unavailable")
+        with patch("notification_service.audit_service.log_event"):
+            ingested, _ = device_data_ingest.process_message(self._message(
+                {"timestamp": 1735689600, "readingType": "spo2", "values": {"spo2": 85}},
+            ))
+
+        self.assertEqual(ingested, 1)
+        self.assertEqual(len(db._sensor_data), 1)
+
+    def test_invalid_reading_does_not_alert(self):
+        device_data_ingest.process_message(self._message(
+            {"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": 400}},
+        ))
+        self.sns.publish.assert_not_called()
+
+    def test_anomalous_reading_flagged_and_sent_as_critical(self):
+        history = [{"timestamp": 1735689000 + i, "readingType": "heart_rate", "values": {"bpm": 70 + i % 5}}
+                   for i in range(20)]
+        device_data_ingest.process_message(self._message(*history))
+        self.sns.publish.assert_not_called()
+
+        device_data_ingest.process_message(self._message(
+            {"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": 110}},
+        ))
+
+        stored = db._sensor_data[-1]
+        self.assertTrue(stored["is_flagged"])
+        self.assertEqual(stored["anomalies"][0]["key"], "bpm")
+        self.sns.publish.assert_called_once()
+        payload = json.loads(self.sns.publish.call_args.kwargs["Message"])
+        self.assertEqual(payload["severity"], "critical")
+        self.assertTrue(payload["reason"].startswith("anomaly: bpm 110"))
+
+    def test_anomaly_within_one_batch(self):
+        readings = [{"timestamp": 1735689000 + i, "readingType": "heart_rate", "values": {"bpm": 72}} for i in range(10)]
+        readings.append({"timestamp": 1735689100, "readingType": "heart_rate", "values": {"bpm": 95}})
+        device_data_ingest.process_message(self._message(*readings))
+
+        self.assertEqual([bool(r.get("is_flagged")) for r in db._sensor_data], [False] * 10 + [True])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,278 @@
+"""
+Tests for MeDUSA Organization Service and tenant isolation
+
+Run with: python -m pytest test_organization_service.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from boto3.dynamodb.conditions import Attr
+from fastapi import HTTPException
+
+import db
+import main
+from auth import issue_tokens, verify_jwt
+from models import OrganizationCreateReq
+from organization_service import organization_service, OrganizationError, SubscriptionTier, is_platform_admin
+from request_context import DEFAULT_ORGANIZATION_ID, organization_scope
+
+
+class OrganizationTestCase(unittest.TestCase):
+
+    def setUp(self):
+        db._organizations.clear()
+        for uid in ("usr_org_admin", "usr_org_admin2", "usr_org_doc"):
+            db._users.pop(uid, None)
+        db.create_user({"id": "usr_org_admin", "email": "orgadmin@example.com", "role": "admin"})
+        db.create_user({"id": "usr_org_admin2", "email": "orgadmin2@example.com", "role": "admin"})
+        db.create_user({"id": "usr_org_doc", "email": "orgdoc@example.com", "role": "doctor"})
+
+    def tearDown(self):
+        db._organizations.clear()
+        for uid in ("usr_org_admin", "usr_org_admin2", "usr_org_doc"):
+            db._users.pop(uid, None)
+
+
+class TestCreateOrganization(OrganizationTestCase):
+    """Test organization creation and validation."""
+
+    def test_create_moves_admins_into_organization(self):
+        org = organization_service.create_organization(
+            "St. Mary's", "St-Marys", ["usr_org_admin"], SubscriptionTier.ENTERPRISE
+        )
+        self.assertTrue(org["id"].startswith("org_"))
+        self.assertEqual(org["subdomain"], "st-marys")
+        self.assertEqual(org["subscriptionTier"], "enterprise")
+        self.assertEqual(db.get_organization(org["id"])["name"], "St. Mary's")
+        self.assertEqual(db.organization_of(db._users["usr_org_admin"]), org["id"])
+
+    def test_invalid_subdomain_rejected(self):
+        for subdomain in ("", "-clinic", "clinic_1", "a" * 64):
+            with self.assertRaises(OrganizationError):
+                organization_service.create_organization("Clinic", subdomain, ["usr_org_admin"])
+
+    def test_admin_ids_must_be_admin_users(self):
+        with self.assertRaises(OrganizationError):
+            organization_service.create_organization("Clinic", "clinic", [])
+        with self.assertRaises(OrganizationError):
+            organization_service.create_organization("Clinic", "clinic", ["usr_org_doc"])
+        with self.assertRaises(OrganizationError):
+            organization_service.create_organization("Clinic", "clinic", ["usr_missing"])
+
+    def test_subdomain_must_be_unique(self):
+        organization_service.create_organization("Clinic", "clinic", ["usr_org_admin"])
+        with self.assertRaises(db.OrganizationExistsError):
+            organization_service.create_organization("Other Clinic", "clinic", ["usr_org_admin2"])
+
+    def test_list_organization_users(self):
+        org = organization_service.create_organization("Clinic", "clinic", ["usr_org_admin"])
+        ids = [u["id"] for u in organization_service.list_organization_users(org["id"])]
+        self.assertEqual(ids, ["usr_org_admin"])
+
+
+class TestTenantIsolation(OrganizationTestCase):
+    """Test that records of one organization are invisible to another."""
+
+    def test_records_stamped_with_caller_organization(self):
+        with organization_scope("org_a"):
+            db.create_device({"id": "dev_org_a", "patientId": "usr_org_doc", "status": "active"})
+        try:
+            self.assertEqual(db.get_device("dev_org_a")["organizationId"], "org_a")
+        finally:
+            db.delete_device("dev_org_a")
+
+    def test_other_organization_cannot_read_by_id(self):
+        with organization_scope("org_a"):
+            db.create_device({"id": "dev_org_a", "patientId": "usr_org_doc", "status": "active"})
+        try:
+            with organization_scope("org_b"):
+                self.assertIsNone(db.get_device("dev_org_a"))
+                self.assertNotIn("dev_org_a", [d["id"] for d in db.get_all_devices()])
+            with organization_scope("org_a"):
+                self.assertIsNotNone(db.get_device("dev_org_a"))
+        finally:
+            db.delete_device("dev_org_a")
+
+    def test_legacy_records_belong_to_default_organization(self):
+        with organization_scope(DEFAULT_ORGANIZATION_ID):
+            self.assertIsNotNone(db.get_user("usr_org_doc"))
+        with organization_scope("org_b"):
+            self.assertIsNone(db.get_user("usr_org_doc"))
+
+    def test_patient_records_scoped(self):
+        with organization_scope("org_a"):
+            db.create_symptom_record("usr_org_doc", {"severity": 3})
+            db.create_medication("usr_org_doc", {"name": "Levodopa"})
+        try:
+            with organization_scope("org_a"):
+                self.assertEqual(len(db.get_symptom_records("usr_org_doc")), 1)
+                self.assertEqual(len(db.get_medications("usr_org_doc")), 1)
+            with organization_scope("org_b"):
+                self.assertEqual(db.get_symptom_records("usr_org_doc"), [])
+                self.assertEqual(db.get_medications("usr_org_doc"), [])
+        finally:
+            db._symptoms[:] = [s for s in db._symptoms if s.get("patientId") != "usr_org_doc"]
+            db._medications[:] = [m for m in db._medications if m.get("patientId") != "usr_org_doc"]
+
+
+    def test_reports_of_other_organization_cannot_be_changed(self):
+        with organization_scope("org_a"):
+            report = db.create_report({"patientId": "usr_org_doc", "authorId": "usr_org_doc", "title": "A"})
+        rid = report["reportId"]
+        try:
+            with organization_scope("org_b"):
+                self.assertIsNone(db.update_report(rid, {"title": "B"}))
+                self.assertFalse(db.delete_report(rid))
+                with self.assertRaises(HTTPException) as ctx:
+                    asyncio.run(main.delete_report.__wrapped__(fake_request("usr_x", "admin", "org_b"), rid))
+                self.assertEqual(ctx.exception.status_code, 404)
+            with organization_scope("org_a"):
+                self.assertEqual(db.get_report(rid)["title"], "A")
+        finally:
+            db._reports[:] = [r for r in db._reports if r["reportId"] != rid]
+
+    def test_report_update_keeps_owner(self):
+        with organization_scope("org_a"):
+            report = db.create_report({"patientId": "usr_org_doc", "authorId": "usr_org_doc", "title": "A"})
+        rid = report["reportId"]
+        request = fake_request("usr_org_doc", "doctor", "org_a")
+        request.json = lambda: asyncio.sleep(0, result={"title": "B", "organizationId": "org_b", "authorId": "usr_x"})
+        try:
+            with organization_scope("org_a"):
+                updated = asyncio.run(main.update_report.__wrapped__(request, rid))["data"]
+            self.assertEqual((updated["title"], updated["organizationId"], updated["authorId"]), ("B", "org_a", "usr_org_doc"))
+        finally:
+            db._reports[:] = [r for r in db._reports if r["reportId"] != rid]
+
+    def test_tremor_data_of_other_organization_not_readable(self):
+        with patch.object(main.audit_service, "log_access_denied"), organization_scope("org_b"):
+            with self.assertRaises(HTTPException) as ctx:
+                main.get_tremor_analysis(fake_request("usr_x", "admin", "org_b"), "usr_org_doc")
+            self.assertEqual(ctx.exception.status_code, 404)
+            with self.assertRaises(HTTPException) as ctx:
+                main.get_tremor_analysis(fake_request("usr_y", "doctor", "org_b"), "usr_org_doc")
+            self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_purge_cannot_reach_other_organization(self):
+        db.put_user({"id": "usr_org_pat", "email": "orgpat@example.com", "role": "patient"})
+        db._reports.append({"reportId": "RPT-org", "patientId": "usr_org_pat"})
+        try:
+            with organization_scope("org_b"):
+                with self.assertRaises(HTTPException) as ctx:
+                    asyncio.run(main.issue_purge_token.__wrapped__(fake_request("usr_x", "admin", "org_b"), "usr_org_pat"))
+                self.assertEqual(ctx.exception.status_code, 404)
+                self.assertEqual(db.purge_patient_reports("usr_org_pat"), 0)
+            self.assertEqual(len([r for r in db._reports if r["reportId"] == "RPT-org"]), 1)
+        finally:
+            db._users.pop("usr_org_pat", None)
+            db._reports[:] = [r for r in db._reports if r["reportId"] != "RPT-org"]
+
+
+class TestTenantIsolationDynamo(unittest.TestCase):
+    """Test that DynamoDB reads filter by organization in the query, not after Limit."""
+
+    def setUp(self):
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+
+    def test_query_filters_by_organization(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": []}
+        with patch.object(db, "T_SYMPTOMS", table, create=True), organization_scope("org_a"):
+            db.get_symptom_records("usr_1", limit=10)
+        kwargs = table.query.call_args.kwargs
+        self.assertEqual(kwargs["FilterExpression"], Attr("organizationId").eq("org_a"))
+        self.assertEqual(kwargs["Limit"], 10)
+
+    def test_purge_filters_by_organization(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": []}
+        with patch.object(db, "T_SYMPTOMS", table, create=True), organization_scope("org_a"):
+            db.purge_patient_symptoms("usr_1")
+        self.assertEqual(table.query.call_args.kwargs["FilterExpression"], Attr("organizationId").eq("org_a"))
+
+    def test_unscoped_query_has_no_filter(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": []}
+        with patch.object(db, "T_SYMPTOMS", table, create=True):
+            db.get_symptom_records("usr_1")
+        self.assertNotIn("FilterExpression", table.query.call_args.kwargs)
+
+    def test_filtered_page_keeps_reading_until_full(self):
+        table = MagicMock()
+        table.query.side_effect = [
+            {"Items": [{"recordId": "a"}], "LastEvaluatedKey": {"recordId": "x"}},
+            {"Items": [], "LastEvaluatedKey": {"recordId": "y"}},
+            {"Items": [{"recordId": "b"}], "LastEvaluatedKey": {"recordId": "b"}},
+        ]
+        with patch.object(db, "T_SYMPTOMS", table, create=True), organization_scope("org_a"):
+            items = db.get_symptom_records("usr_1", limit=2)
+        self.assertEqual([i["recordId"] for i in items], ["a", "b"])
+        self.assertEqual([c.kwargs["Limit"] for c in table.query.call_args_list], [2, 1, 1])
+        self.assertEqual(table.query.call_args_list[2].kwargs["ExclusiveStartKey"], {"recordId": "y"})
+
+
+class TestOrganizationClaim(unittest.TestCase):
+    """Test the organization claim in tokens."""
+
+    def test_tokens_carry_organization(self):
+        tokens = issue_tokens("usr_1", "doctor", organization_id="org_a")
+        self.assertEqual(tokens["organizationId"], "org_a")
+        self.assertEqual(verify_jwt(tokens["accessJwt"])["org"], "org_a")
+
+    def test_default_organization_when_unset(self):
+        tokens = issue_tokens("usr_1", "doctor")
+        self.assertEqual(verify_jwt(tokens["accessJwt"])["org"], DEFAULT_ORGANIZATION_ID)
+
+    def test_platform_admin(self):
+        self.assertTrue(is_platform_admin("admin", None))
+        self.assertTrue(is_platform_admin("admin", DEFAULT_ORGANIZATION_ID))
+        self.assertFalse(is_platform_admin("admin", "org_a"))
+        self.assertFalse(is_platform_admin("doctor", DEFAULT_ORGANIZATION_ID))
+
+
+class TestOrganizationEndpoints(OrganizationTestCase):
+    """Test the organization API endpoints."""
+
+    def test_platform_admin_creates_organization(self):
+        body = OrganizationCreateReq(name="Clinic", subdomain="clinic", adminUserIds=["usr_org_admin"])
+        org = asyncio.run(main.create_organization.__wrapped__(body, fake_request("usr_root", "admin")))
+        self.assertEqual(org.subdomain, "clinic")
+
+        duplicate = OrganizationCreateReq(name="Clinic 2", subdomain="clinic", adminUserIds=["usr_org_admin2"])
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.create_organization.__wrapped__(duplicate, fake_request("usr_root", "admin")))
+        self.assertEqual(ctx.exception.status_code, 409)
+
+    def test_organization_admin_cannot_create_organizations(self):
+        body = OrganizationCreateReq(name="Clinic", subdomain="clinic", adminUserIds=["usr_org_admin"])
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.create_organization.__wrapped__(body, fake_request("usr_x", "admin", "org_a")))
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_admin_reads_only_own_organization(self):
+        org = organization_service.create_organization("Clinic", "clinic", ["usr_org_admin"])
+        own = asyncio.run(main.get_organization.__wrapped__(org["id"], fake_request("usr_org_admin", "admin", org["id"])))
+        self.assertEqual(own.id, org["id"])
+
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.get_organization.__wrapped__(org["id"], fake_request("usr_x", "admin", "org_other")))
+        self.assertEqual(ctx.exception.status_code, 404)
+
+    def test_list_users_endpoint(self):
+        org = organization_service.create_organization("Clinic", "clinic", ["usr_org_admin"])
+        users = asyncio.run(main.list_organization_users.__wrapped__(org["id"], fake_request("usr_root", "admin")))
+        self.assertEqual([u["id"] for u in users], ["usr_org_admin"])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,128 @@
+"""
+Tests for pagination helpers
+
+Run with: python -m pytest test_pagination.py -v
+"""
+
+import os
+import unittest
+from decimal import Decimal
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from pagination import (
+    PaginatedResult,
+    encode_cursor,
+    decode_cursor,
+    paginate_list,
+    parse_pagination_params,
+    create_paginated_response
+)
+from query_params import QueryParamError
+
+
+class TestCursor(unittest.TestCase):
+    """Test cursor encoding."""
+
+    def test_round_trip(self):
+        key = {"pk": "AUDIT#ALL", "sk": "2025-01-01T00:00:00+00:00"}
+        self.assertEqual(decode_cursor(encode_cursor(key)), key)
+
+    def test_decimal_key_values(self):
+        cursor = encode_cursor({"patient_id": "p1", "timestamp": Decimal("1735689600")})
+        self.assertEqual(decode_cursor(cursor)["timestamp"], 1735689600)
+
+    def test_no_key_means_no_cursor(self):
+        self.assertIsNone(encode_cursor(None))
+        self.assertIsNone(decode_cursor(None))
+
+    def test_malformed_cursor_rejected(self):
+        with self.assertRaises(ValueError):
+            decode_cursor("not*a*cursor")
+
+
+class TestPaginatedResult(unittest.TestCase):
+    """Test PaginatedResult constructors."""
+
+    def test_from_dynamo_with_last_key(self):
+        page = PaginatedResult.from_dynamo([1, 2], {"id": "u2"})
+        self.assertTrue(page.has_more)
+        self.assertEqual(decode_cursor(page.next_cursor), {"id": "u2"})
+
+    def test_from_dynamo_last_page(self):
+        page = PaginatedResult.from_dynamo([1, 2], None)
+        self.assertFalse(page.has_more)
+        self.assertIsNone(page.next_cursor)
+
+    def test_empty(self):
+        page = PaginatedResult.empty()
+        self.assertEqual((page.items, page.total, page.has_more), ([], 0, False))
+
+    def test_to_dict(self):
+        body = PaginatedResult.with_cursor(["a"], "abc", total=3).to_dict()
+        self.assertEqual(body["pagination"], {"total": 3, "nextCursor": "abc", "hasMore": True})
+
+
+class TestPaginatedResponse(unittest.TestCase):
+    """Test the list response envelope."""
+
+    def setUp(self):
+        self.items = list(range(10))
+
+    def _envelope(self, limit, offset):
+        return create_paginated_response(paginate_list(self.items, limit, offset), limit, offset)
+
+    def test_has_more_before_boundary(self):
+        body = self._envelope(4, 4)
+        self.assertEqual(body.items, [4, 5, 6, 7])
+        self.assertTrue(body.hasMore)
+
+    def test_no_more_at_boundary(self):
+        body = self._envelope(5, 5)
+        self.assertEqual(body.items, [5, 6, 7, 8, 9])
+        self.assertFalse(body.hasMore)
+
+    def test_no_more_past_boundary(self):
+        body = self._envelope(5, 12)
+        self.assertEqual(body.items, [])
+        self.assertFalse(body.hasMore)
+        self.assertEqual(body.total, 10)
+
+    def test_camel_case_shape(self):
+        body = self._envelope(3, 0).model_dump()
+        self.assertEqual(set(body), {"items", "total", "limit", "offset", "nextCursor", "hasMore"})
+
+    def test_limit_bounds(self):
+        self.assertEqual(parse_pagination_params(None, None), (50, 0))
+        with self.assertRaises(ValueError):
+            parse_pagination_params(0)
+        with self.assertRaises(ValueError):
+            parse_pagination_params(10, -1)
+
+    def test_string_params_parsed(self):
+        self.assertEqual(parse_pagination_params("20", "40"), (20, 40))
+        with self.assertRaises(QueryParamError) as ctx:
+            parse_pagination_params("20", "abc")
+        self.assertEqual(ctx.exception.param, "offset")
+
+
+class TestListUsersPagination(unittest.TestCase):
+    """Test list_users returns consistent pages in memory mode."""
+
+    def setUp(self):
+        db._users.clear()
+        for i in range(3):
+            db._users[f"u{i}"] = {"id": f"u{i}", "email": f"u{i}@example.com", "role": "patient"}
+
+    def test_pages_until_exhausted(self):
+        first = db.list_users(limit=2)
+        self.assertEqual(len(first.items), 2)
+        self.assertTrue(first.has_more)
+        second = db.list_users(limit=2, next_token=first.next_cursor)
+        self.assertEqual([u["id"] for u in second.items], ["u2"])
+        self.assertFalse(second.has_more)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,135 @@
+"""
+Tests for password history (no reuse of recent passwords) and password expiry
+
+Run with: python -m pytest test_password_history.py -v
+"""
+
+import os
+import sys
+import unittest
+from datetime import datetime, timedelta, timezone
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import auth
+import db
+import main
+from auth import hash_pw, is_password_expired, is_password_reused, password_change_fields, verify_pw
+from fastapi import HTTPException
+from models import ChangePasswordReq, LoginReq
+
+OLD = "Old-correct-horse-9"
+NEW = "New-battery-staple-7"
+
+
+class TestPasswordHistoryHelpers(unittest.TestCase):
+    """Test reuse detection and history rotation."""
+
+    def test_history_is_trimmed_newest_first(self):
+        user = {"password": "h3", "passwordHistory": ["h2", "h1"]}
+        with patch.object(auth, "PASSWORD_HISTORY_SIZE", 2):
+            fields = password_change_fields(NEW, user)
+        self.assertEqual(fields["passwordHistory"], ["h3", "h2"])
+        self.assertTrue(verify_pw(NEW, fields["password"]))
+
+    def test_older_password_in_history_is_reused(self):
+        user = {"password": hash_pw(NEW), "passwordHistory": [hash_pw(OLD)]}
+        self.assertTrue(is_password_reused(OLD, user))
+
+    def test_disabled_history_allows_reuse(self):
+        user = {"password": hash_pw(OLD)}
+        with patch.object(auth, "PASSWORD_HISTORY_SIZE", 0):
+            self.assertFalse(is_password_reused(OLD, user))
+            self.assertEqual(password_change_fields(NEW, user)["passwordHistory"], [])
+
+
+class TestChangePassword(unittest.TestCase):
+    """Test the change-password endpoint."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_1", "email": "jane@example.com", "role": "patient",
+                     "password": hash_pw(OLD), "createdAt": "2025-01-01T00:00:00+00:00"})
+        patcher = patch.object(main.db, "revoke_user_refresh_tokens", return_value=0)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _change(self, current, new):
+        return main.change_password(ChangePasswordReq(currentPassword=current, newPassword=new), fake_request("usr_1"))
+
+    def test_previous_password_rejected(self):
+        self._change(OLD, NEW)
+
+        with self.assertRaises(HTTPException) as ctx:
+            self._change(NEW, OLD)
+        self.assertEqual(ctx.exception.status_code, 400)
+        self.assertEqual(ctx.exception.detail["code"], "PASSWORD_REUSED")
+
+    def test_unused_password_accepted(self):
+        self._change(OLD, NEW)
+
+        user = db.get_user("usr_1", include_secrets=True)
+        self.assertTrue(verify_pw(NEW, user["password"]))
+        self.assertEqual(len(user["passwordHistory"]), 1)
+        self.assertTrue(verify_pw(OLD, user["passwordHistory"][0]))
+
+    def test_wrong_current_password(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._change("Wrong-password-123", NEW)
+        self.assertEqual(ctx.exception.status_code, 401)
+
+    def test_history_not_in_profile(self):
+        self._change(OLD, NEW)
+        profile = main.me(fake_request("usr_1"))
+        self.assertNotIn("passwordHistory", profile.model_dump())
+
+
+def _days_ago(days):
+    return (datetime.now(timezone.utc) - timedelta(days=days)).isoformat()
+
+
+class TestPasswordExpiry(unittest.TestCase):
+    """Test the password max-age policy."""
+
+    def setUp(self):
+        db._users.clear()
+        self.addCleanup(patch.stopall)
+        patch.object(auth, "PASSWORD_MAX_AGE_DAYS", 90).start()
+        patch.object(main, "_start_session", return_value={"accessJwt": "a", "refreshToken": "r", "expiresIn": 3600}).start()
+
+    def _login(self, changed_days_ago):
+        db.put_user({"id": "usr_1", "email": "jane@example.com", "role": "patient", "password": hash_pw(OLD),
+                     "createdAt": _days_ago(400), "passwordChangedAt": _days_ago(changed_days_ago)})
+        return main.login(LoginReq(email="jane@example.com", password=OLD), fake_request(None))
+
+    def test_old_password_sets_flag(self):
+        self.assertTrue(self._login(91).passwordExpired)
+
+    def test_recent_password_not_flagged(self):
+        self.assertFalse(self._login(10).passwordExpired)
+
+    def test_block_mode_refuses_login(self):
+        with patch.object(main, "PASSWORD_EXPIRY_BLOCK", True):
+            with self.assertRaises(HTTPException) as ctx:
+                self._login(91)
+        self.assertEqual(ctx.exception.detail["code"], "PASSWORD_EXPIRED")
+
+    def test_falls_back_to_created_at(self):
+        self.assertTrue(is_password_expired({"createdAt": _days_ago(100)}))
+        self.assertFalse(is_password_expired({"createdAt": _days_ago(100), "passwordChangedAt": _days_ago(1)}))
+
+    def test_disabled_by_zero_max_age(self):
+        with patch.object(auth, "PASSWORD_MAX_AGE_DAYS", 0):
+            self.assertFalse(is_password_expired({"createdAt": _days_ago(1000)}))
+
+    def test_change_resets_age(self):
+        fields = password_change_fields(NEW, {"password": hash_pw(OLD), "passwordChangedAt": _days_ago(200)})
+        self.assertFalse(is_password_expired(fields))
+
+
+if __name__ == "__main__":
+    unittest.main()
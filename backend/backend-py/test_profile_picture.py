@@ -0,0 +1,124 @@
+"""
+Tests for profile picture upload and deletion
+
+Run with: python -m pytest test_profile_picture.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+import storage
+from fastapi import HTTPException
+
+PNG = b"\x89PNG\r\n\x1a\n" + b"\x00" * 32
+JPEG = b"\xff\xd8\xff\xe0" + b"\x00" * 32
+
+
+class TestUploadProfilePicture(unittest.TestCase):
+    """Test storage.upload_profile_picture validation and key layout."""
+
+    def setUp(self):
+        patcher = patch.object(storage, "upload_bytes")
+        self.upload_bytes = patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def test_key_uses_type_extension(self):
+        self.assertEqual(storage.upload_profile_picture("usr_1", PNG, "image/png"), "profile-pictures/usr_1/avatar.png")
+        self.assertEqual(storage.upload_profile_picture("usr_1", JPEG, "image/jpeg"), "profile-pictures/usr_1/avatar.jpg")
+        self.upload_bytes.assert_called_with("profile-pictures/usr_1/avatar.jpg", JPEG, "image/jpeg", residency=None)
+
+    def test_unsupported_type_rejected(self):
+        with self.assertRaises(ValueError):
+            storage.upload_profile_picture("usr_1", b"GIF89a", "image/gif")
+
+    def test_content_must_match_type(self):
+        with self.assertRaises(ValueError):
+            storage.upload_profile_picture("usr_1", JPEG, "image/png")
+
+    def test_too_large_rejected(self):
+        with patch.object(storage, "MAX_PROFILE_PICTURE_BYTES", 16):
+            with self.assertRaises(ValueError):
+                storage.upload_profile_picture("usr_1", PNG, "image/png")
+        self.upload_bytes.assert_not_called()
+
+
+class TestProfilePictureEndpoints(unittest.TestCase):
+    """Test POST/DELETE /api/v1/users/{user_id}/profile-picture."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_1", "email": "jane@example.com", "role": "patient",
+                     "createdAt": "2025-01-01T00:00:00+00:00"})
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "upload_bytes").start()
+        self.delete_object = patch.object(storage, "delete_object").start()
+        patch.object(storage, "presign_download", side_effect=lambda key, ttl_sec, residency=None: f"https://s3.example/{key}").start()
+        self.log_event = patch.object(main.audit_service, "log_event").start()
+
+    def _upload(self, user_id, body, content_type="image/png", role="patient"):
+        # __wrapped__ skips require_role; the roles used here are all allowed
+        request = fake_request(user_id, role, headers={"content-type": content_type}, body=lambda: asyncio.sleep(0, result=body))
+        return asyncio.run(main.upload_profile_picture.__wrapped__(request, "usr_1"))
+
+    def _delete(self, user_id, role="patient"):
+        return asyncio.run(main.delete_profile_picture.__wrapped__(fake_request(user_id, role), "usr_1"))
+
+    def test_upload_stores_key_and_shows_in_me(self):
+        res = self._upload("usr_1", PNG)
+
+        self.assertEqual(res.profilePictureUrl, "https://s3.example/profile-pictures/usr_1/avatar.png")
+        self.assertEqual(db.get_user("usr_1")["profilePictureKey"], "profile-pictures/usr_1/avatar.png")
+        self.assertEqual(main.me(fake_request("usr_1")).profilePictureUrl, res.profilePictureUrl)
+
+    def test_replacing_with_other_type_deletes_old_object(self):
+        self._upload("usr_1", PNG)
+        self._upload("usr_1", JPEG, "image/jpeg")
+
+        self.delete_object.assert_called_once_with("profile-pictures/usr_1/avatar.png", residency=None)
+
+    def test_other_user_forbidden_admin_allowed(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._upload("usr_2", PNG)
+        self.assertEqual(ctx.exception.status_code, 403)
+
+        self._upload("usr_admin", PNG, role="admin")
+        self.assertIn("profilePictureKey", db.get_user("usr_1"))
+
+    def test_invalid_type_is_415(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._upload("usr_1", b"GIF89a", "image/gif")
+        self.assertEqual(ctx.exception.status_code, 415)
+
+    def test_too_large_is_413(self):
+        with patch.object(storage, "MAX_PROFILE_PICTURE_BYTES", 16):
+            with self.assertRaises(HTTPException) as ctx:
+                self._upload("usr_1", PNG)
+        self.assertEqual(ctx.exception.status_code, 413)
+
+    def test_delete_removes_object_and_field(self):
+        self._upload("usr_1", PNG)
+
+        self._delete("usr_1")
+
+        self.delete_object.assert_called_once_with("profile-pictures/usr_1/avatar.png", residency=None)
+        self.assertNotIn("profilePictureKey", db.get_user("usr_1"))
+        self.assertIsNone(main.me(fake_request("usr_1")).profilePictureUrl)
+
+    def test_delete_without_picture_is_404(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._delete("usr_1")
+        self.assertEqual(ctx.exception.status_code, 404)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,318 @@
+"""
+Tests for MeDUSA Data Purge Service
+
+Run with: python -m pytest test_purge_service.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from fastapi import HTTPException
+
+import db
+import main
+from auth import hash_pw
+import patient_cleanup
+from purge_service import purge_service, issue_confirmation_token, verify_confirmation_token
+
+
+class TestPurgePatient(unittest.TestCase):
+    """Test hard deletion of patient data."""
+
+    def setUp(self):
+        db._users.clear()
+        db._patient_profiles.clear()
+        db._tremor_analysis.clear()
+        db._reports.clear()
+        db._devices.clear()
+        db._poses.clear()
+        db._sensor_data.clear()
+
+        for pid in ("usr_p1", "usr_p2"):
+            db.put_user({"id": pid, "email": f"{pid}@example.com", "role": "patient"})
+            db.create_patient_profile({"userId": pid, "doctorId": "usr_d1"})
+            for ts in (1000, 2000, 3000):
+                db._tremor_analysis.append({"patient_id": pid, "timestamp": ts})
+            db._reports.append({"reportId": f"RPT-{pid}", "patientId": pid})
+        db._devices.append({"id": "dev_1", "patientId": "usr_p1"})
+
+    def test_readings_under_patient_partition_removed(self):
+        counts = purge_service.purge_patient("usr_p1")
+
+        self.assertEqual(counts["readings"], 3)
+        self.assertEqual(db.get_patient_readings("usr_p1"), [])
+        self.assertEqual(len(db.get_patient_readings("usr_p2")), 3)
+
+    def test_all_patient_records_removed(self):
+        counts = purge_service.purge_patient("usr_p1")
+
+        self.assertEqual(counts["users"], 1)
+        self.assertEqual(counts["reports"], 1)
+        self.assertEqual(counts["deviceAssociations"], 1)
+        self.assertIsNone(db.get_user("usr_p1"))
+        self.assertIsNone(db.get_patient_profile("usr_p1"))
+        self.assertIsNone(db.get_device("dev_1")["patientId"])
+        self.assertIsNotNone(db.get_user("usr_p2"))
+
+    def test_purge_is_idempotent(self):
+        purge_service.purge_patient("usr_p1")
+        counts = purge_service.purge_patient("usr_p1")
+
+        self.assertTrue(all(count == 0 for count in counts.values()))
+
+    @patch("purge_service.storage.delete_objects", side_effect=lambda keys, residency=None: len(keys))
+    def test_patient_files_deleted_from_storage(self, mock_delete):
+        db.update_user("usr_p1", {"profilePictureKey": "profile-pictures/usr_p1/avatar.png", "dataResidency": "eu"})
+        db._reports[0]["fileKey"] = "reports/usr_d1/summary.pdf"
+        db._poses.append({"id": "pose_1", "patientId": "usr_p1", "fileKey": "poses/usr_p1/pose_1.json"})
+        db._poses.append({"id": "pose_2", "patientId": "usr_p2", "fileKey": "poses/usr_p2/pose_2.json"})
+        db._sensor_data.append({"device_id": "dev_1", "timestamp": 1000, "patient_id": "usr_p1",
+                                "waveform_key": "device-data/dev_1/ecg/1000_a.json"})
+        db._sensor_data.append({"device_id": "dev_1", "timestamp": 500, "patient_id": "usr_old",
+                                "waveform_key": "device-data/dev_1/ecg/500_b.json"})
+
+        counts = purge_service.purge_patient("usr_p1")
+
+        mock_delete.assert_called_once_with([
+            "device-data/dev_1/ecg/1000_a.json",
+            "poses/usr_p1/pose_1.json",
+            "profile-pictures/usr_p1/avatar.png",
+            "reports/usr_d1/summary.pdf",
+        ], residency="eu")
+        self.assertEqual(counts["files"], 4)
+
+
+class TestConfirmationToken(unittest.TestCase):
+    """Test purge confirmation tokens."""
+
+    def test_valid_token_accepted(self):
+        token, _ = issue_confirmation_token("purge_patient", "usr_p1", "usr_admin")
+        self.assertTrue(verify_confirmation_token(token, "purge_patient", "usr_p1", "usr_admin"))
+
+    def test_token_bound_to_target_and_actor(self):
+        token, _ = issue_confirmation_token("purge_patient", "usr_p1", "usr_admin")
+        self.assertFalse(verify_confirmation_token(token, "purge_patient", "usr_p2", "usr_admin"))
+        self.assertFalse(verify_confirmation_token(token, "purge_patient", "usr_p1", "usr_other"))
+
+    def test_expired_token_rejected(self):
+        token, _ = issue_confirmation_token("purge_patient", "usr_p1", "usr_admin", ttl_seconds=-1)
+        self.assertFalse(verify_confirmation_token(token, "purge_patient", "usr_p1", "usr_admin"))
+
+    def test_tampered_token_rejected(self):
+        token, _ = issue_confirmation_token("purge_patient", "usr_p1", "usr_admin")
+        tampered = token[:-1] + ("1" if token.endswith("0") else "0")
+        self.assertFalse(verify_confirmation_token(tampered, "purge_patient", "usr_p1", "usr_admin"))
+        self.assertFalse(verify_confirmation_token(None, "purge_patient", "usr_p1", "usr_admin"))
+
+
+class TestAnonymizeUser(unittest.TestCase):
+    """Test right-to-erasure anonymization."""
+
+    def setUp(self):
+        db._users.clear()
+        db._patient_profiles.clear()
+        db._tremor_analysis.clear()
+        db._reports.clear()
+        db._audit_logs.clear()
+
+        db.put_user({"id": "usr_p1", "email": "ann@example.com", "role": "patient", "name": "Ann",
+                     "phone": "+15550100", "password": "hash", "mfaSecret": "S", "isActive": True,
+                     "createdAt": "2025-01-01T00:00:00+00:00"})
+        db.put_user({"id": "usr_d1", "email": "doc@example.com", "role": "doctor", "name": "Dr Bob",
+                     "license": "MD-1", "department": "Neurology"})
+        db.create_patient_profile({"userId": "usr_p1", "doctorId": "usr_d1", "diagnosis": "PD", "notes": "Lives with Carl"})
+        db._tremor_analysis.append({"patient_id": "usr_p1", "timestamp": 1000, "tremor_index": 0.4})
+        db._reports.append({"reportId": "RPT-1", "patientId": "usr_p1", "authorId": "usr_d1", "fileKey": "reports/usr_d1/1_r.pdf"})
+        db._audit_logs.append({"pk": "AUDIT#ALL", "sk": "2025-01-01", "userId": "usr_p1"})
+
+        self.storage = patch("purge_service.storage").start()
+        self.storage.PREPORT = "reports/"
+        self.storage.PPROFILE = "profile-pictures/"
+        self.storage.list_objects.return_value = []
+        self.storage.delete_objects.side_effect = lambda keys: len(keys)
+        self.addCleanup(patch.stopall)
+
+    def test_user_pii_erased(self):
+        result = purge_service.anonymize_user("usr_p1")
+        user = db.get_user("usr_p1", include_secrets=True)
+
+        self.assertTrue(user["email"].startswith("deleted_"))
+        self.assertTrue(user["email"].endswith("@erased.local"))
+        self.assertFalse(user["isActive"])
+        for field in ("name", "phone", "password", "mfaSecret"):
+            self.assertNotIn(field, user)
+        self.assertIn("email", result["erasedFields"])
+        self.assertIn("name", result["erasedFields"])
+        self.assertIn("role", result["retainedFields"])
+        self.assertIn("createdAt", result["retainedFields"])
+
+    def test_patient_profile_notes_erased_and_readings_kept(self):
+        result = purge_service.anonymize_user("usr_p1")
+
+        profile = db.get_patient_profile("usr_p1")
+        self.assertNotIn("notes", profile)
+        self.assertEqual(profile["diagnosis"], "PD")
+        self.assertIn("patientProfile.notes", result["erasedFields"])
+        self.assertIn("patientProfile.diagnosis", result["retainedFields"])
+        self.assertEqual(len(db.get_patient_readings("usr_p1")), 1)
+
+    def test_audit_logs_retained(self):
+        purge_service.anonymize_user("usr_p1")
+
+        self.assertEqual(len(db._audit_logs), 1)
+
+    def test_report_files_of_author_deleted(self):
+        self.storage.list_objects.return_value = [{"Key": "reports/usr_d1/2_upload.pdf"}]
+
+        result = purge_service.anonymize_user("usr_d1")
+
+        self.assertEqual(result["deletedFiles"], 2)
+        self.storage.list_objects.assert_any_call("reports/usr_d1/")
+        self.assertEqual(
+            self.storage.delete_objects.call_args.args[0],
+            ["reports/usr_d1/1_r.pdf", "reports/usr_d1/2_upload.pdf"]
+        )
+        self.assertIsNone(db.get_report("RPT-1")["fileKey"])
+        self.assertIn("license", result["erasedFields"])
+        self.assertIn("department", result["erasedFields"])
+
+    def test_profile_picture_deleted(self):
+        db.update_user("usr_p1", {"profilePictureKey": "profile-pictures/usr_p1/avatar.png"})
+        self.storage.list_objects.side_effect = lambda prefix: (
+            [{"Key": "profile-pictures/usr_p1/avatar.png"}] if prefix == "profile-pictures/usr_p1/" else []
+        )
+
+        result = purge_service.anonymize_user("usr_p1")
+
+        self.assertNotIn("profilePictureKey", db.get_user("usr_p1"))
+        self.assertIn("profile-pictures/usr_p1/avatar.png", self.storage.delete_objects.call_args.args[0])
+        self.assertEqual(result["deletedFiles"], 1)
+
+    def test_unknown_user(self):
+        with self.assertRaises(KeyError):
+            purge_service.anonymize_user("usr_missing")
+
+
+class TestDeletePatient(unittest.TestCase):
+    """Test soft deletion of a patient with cascade cleanup."""
+
+    def setUp(self):
+        db._users.clear()
+        db._patient_profiles.clear()
+        db._tremor_analysis.clear()
+        db._reports.clear()
+        db._devices.clear()
+
+        db.put_user({"id": "usr_p1", "email": "ann@example.com", "role": "patient", "name": "Ann", "isActive": True})
+        db.create_patient_profile({"userId": "usr_p1", "doctorId": "usr_d1", "notes": "Lives with Carl",
+                                   "assignedDevices": ["dev_1", "dev_2"]})
+        db._devices.extend([
+            {"id": "dev_1", "patientId": "usr_p1"},
+            {"id": "dev_2", "patientId": "usr_p1"},
+            {"id": "dev_3", "patientId": "usr_p2"},
+        ])
+        db._tremor_analysis.extend([
+            {"patient_id": "usr_p1", "timestamp": 1000},
+            {"patient_id": "usr_p2", "timestamp": 1000},
+        ])
+
+        patch("purge_service.storage").start().list_objects.return_value = []
+        self.addCleanup(patch.stopall)
+
+    def test_devices_unassigned_and_patient_deactivated(self):
+        summary = purge_service.delete_patient("usr_p1", "usr_admin")
+
+        self.assertEqual(summary["unassignedDeviceCount"], 2)
+        self.assertIsNone(db.get_device("dev_1")["patientId"])
+        self.assertEqual(db.get_device("dev_3")["patientId"], "usr_p2")
+        self.assertEqual(db.get_patient_profile("usr_p1")["assignedDevices"], [])
+        user = db.get_user("usr_p1")
+        self.assertFalse(user["isActive"])
+        self.assertEqual(user["deletedBy"], "usr_admin")
+
+    def test_pii_anonymized(self):
+        summary = purge_service.delete_patient("usr_p1", "usr_admin")
+
+        self.assertNotIn("name", db.get_user("usr_p1"))
+        self.assertIn("name", summary["anonymizedFields"])
+        self.assertIn("patientProfile.notes", summary["anonymizedFields"])
+
+    def test_old_values_hold_no_pii(self):
+        summary = purge_service.delete_patient("usr_p1", "usr_admin")
+
+        self.assertEqual(summary["oldValues"], {"isActive": True, "deviceIds": ["dev_1", "dev_2"]})
+
+    def test_readings_flagged_not_removed(self):
+        purge_service.delete_patient("usr_p1", "usr_admin")
+
+        readings = {r["patient_id"]: r for r in db._tremor_analysis}
+        self.assertEqual(len(db._tremor_analysis), 2)
+        self.assertTrue(readings["usr_p1"]["is_flagged"])
+        self.assertNotIn("is_flagged", readings["usr_p2"])
+
+    def test_cleanup_enqueued_when_queue_configured(self):
+        sqs = patch("patient_cleanup._sqs_client").start().return_value
+        patch.object(patient_cleanup, "PATIENT_CLEANUP_QUEUE_URL", "https://sqs.example/cleanup").start()
+
+        purge_service.delete_patient("usr_p1", "usr_admin")
+
+        sqs.send_message.assert_called_once()
+        self.assertNotIn("is_flagged", db._tremor_analysis[0])
+
+    def test_worker_reports_failed_messages(self):
+        event = {"Records": [
+            {"messageId": "m1", "body": '{"patientId": "usr_p1"}'},
+            {"messageId": "m2", "body": "not json"},
+        ]}
+
+        result = patient_cleanup.process(event, None)
+
+        self.assertEqual(result, {"batchItemFailures": [{"itemIdentifier": "m2"}]})
+        self.assertTrue(db._tremor_analysis[0]["is_flagged"])
+
+    def test_unknown_patient(self):
+        with self.assertRaises(KeyError):
+            purge_service.delete_patient("usr_missing", "usr_admin")
+
+
+class TestIssueErasureToken(unittest.TestCase):
+    """Test the admin re-authenticates before an erasure token is issued."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_admin", "email": "admin@example.com", "role": "admin",
+                     "password": hash_pw("Lantern-Quasar-Orchid-84!"), "mfaEnabled": False})
+        db.put_user({"id": "usr_p1", "email": "ann@example.com", "role": "patient"})
+
+    def _issue(self, body):
+        request = fake_request("usr_admin", "admin", json=lambda: asyncio.sleep(0, result=body))
+        return asyncio.run(main.issue_erasure_token.__wrapped__(request, "usr_p1"))
+
+    def test_password_accepted_without_mfa(self):
+        res = self._issue({"password": "Lantern-Quasar-Orchid-84!"})
+        self.assertTrue(verify_confirmation_token(res["data"]["confirmationToken"], main.ERASE_ACTION, "usr_p1", "usr_admin"))
+
+    def test_missing_or_wrong_password_rejected_without_mfa(self):
+        for body in ({}, {"password": "wrong"}):
+            with self.assertRaises(HTTPException) as ctx:
+                self._issue(body)
+            self.assertEqual(ctx.exception.status_code, 403)
+            self.assertEqual(ctx.exception.detail["code"], "PASSWORD_REQUIRED")
+
+    def test_mfa_admin_needs_code_not_password(self):
+        db._users["usr_admin"].update({"mfaEnabled": True, "mfaSecret": "JBSWY3DPEHPK3PXP"})
+        with self.assertRaises(HTTPException) as ctx:
+            self._issue({"password": "Lantern-Quasar-Orchid-84!"})
+        self.assertEqual(ctx.exception.detail["code"], "MFA_REQUIRED")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,157 @@
+"""
+Tests for mobile push notifications (SNS Mobile Push) and push token endpoints
+
+Run with: python -m pytest test_push_notifications.py -v
+"""
+
+import os
+import sys
+import json
+import asyncio
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from fastapi import HTTPException
+from models import PushTokenReq
+from notification_service import NotificationService, PushPlatform, Alert, AlertSeverity, push_message
+
+APNS_APP = "arn:aws:sns:us-east-1:123456789012:app/APNS/medusa-ios"
+FCM_APP = "arn:aws:sns:us-east-1:123456789012:app/GCM/medusa-android"
+
+
+class TestPushMessage(unittest.TestCase):
+    """Test the platform-specific payloads."""
+
+    def test_apns_payload(self):
+        message = json.loads(push_message(PushPlatform.APNS, "Title", "Body", {"reportId": "RPT-1"}))
+        payload = json.loads(message["APNS"])
+        self.assertEqual(message["default"], "Body")
+        self.assertEqual(payload["aps"]["alert"], {"title": "Title", "body": "Body"})
+        self.assertEqual(payload["reportId"], "RPT-1")
+
+    def test_fcm_payload_data_as_strings(self):
+        message = json.loads(push_message(PushPlatform.FCM, "Title", "Body", {"count": 3}))
+        payload = json.loads(message["GCM"])
+        self.assertEqual(payload["notification"], {"title": "Title", "body": "Body"})
+        self.assertEqual(payload["data"], {"count": "3"})
+
+
+class TestSendPushNotification(unittest.TestCase):
+    """Test SNS Mobile Push publishing with a mocked client."""
+
+    def setUp(self):
+        db._push_tokens.clear()
+        db._patient_profiles.clear()
+        self.service = NotificationService(push_applications={PushPlatform.APNS: APNS_APP, PushPlatform.FCM: None})
+        self.sns = MagicMock()
+        self.sns.create_platform_endpoint.return_value = {"EndpointArn": "arn:aws:sns:us-east-1:123456789012:endpoint/APNS/x"}
+        self.service._sns = self.sns
+
+    def test_publishes_to_platform_endpoint(self):
+        self.assertTrue(self.service.send_push_notification("tok-1", PushPlatform.APNS, "Title", "Body", {"a": 1}))
+
+        self.sns.create_platform_endpoint.assert_called_once_with(PlatformApplicationArn=APNS_APP, Token="tok-1")
+        kwargs = self.sns.publish.call_args.kwargs
+        self.assertEqual(kwargs["TargetArn"], "arn:aws:sns:us-east-1:123456789012:endpoint/APNS/x")
+        self.assertEqual(kwargs["MessageStructure"], "json")
+
+    def test_unconfigured_platform_skipped(self):
+        self.assertFalse(self.service.send_push_notification("tok-1", PushPlatform.FCM, "Title", "Body"))
+        self.sns.publish.assert_not_called()
+
+    def test_failure_is_not_raised(self):
+        self.sns.publish.side_effect = RuntimeError("EndpointDisabled")
+        self.assertFalse(self.service.send_push_notification("tok-1", PushPlatform.APNS, "Title", "Body"))
+
+    def test_push_to_user_sends_to_every_token(self):
+        db.put_push_token("usr_1", "tok-1", "apns")
+        db.put_push_token("usr_1", "tok-2", "apns")
+        db.put_push_token("usr_2", "tok-3", "apns")
+
+        self.assertEqual(self.service.push_to_user("usr_1", "Title", "Body"), 2)
+        self.assertEqual([c.kwargs["Token"] for c in self.sns.create_platform_endpoint.call_args_list], ["tok-1", "tok-2"])
+
+    def test_critical_alert_pushed_to_assigned_doctor(self):
+        db.create_patient_profile({"userId": "pat-1", "doctorId": "doc-1"})
+        db.put_push_token("doc-1", "doc-token", "apns")
+        alert = Alert(patient_id="pat-1", device_id="dev-1", reading_type="spo2", reading={"spo2": 85},
+                      severity=AlertSeverity.CRITICAL, reason="spo2 85 <= 88")
+
+        self.assertEqual(self.service.push_critical_alert(alert), 1)
+        self.assertEqual(self.sns.create_platform_endpoint.call_args.kwargs["Token"], "doc-token")
+        alert.severity = AlertSeverity.HIGH
+        self.assertEqual(self.service.push_critical_alert(alert), 0)
+
+    def test_report_ready_pushed_to_author(self):
+        db.put_push_token("doc-1", "doc-token", "apns")
+
+        self.assertEqual(self.service.push_report_ready({"reportId": "RPT-1", "authorId": "doc-1", "title": "Patient Summary"}), 1)
+        message = json.loads(self.sns.publish.call_args.kwargs["Message"])
+        self.assertEqual(json.loads(message["APNS"])["reportId"], "RPT-1")
+
+
+class TestPushTokenStore(unittest.TestCase):
+    """Test the push_tokens table helpers."""
+
+    def setUp(self):
+        db._push_tokens.clear()
+
+    def test_reregistering_token_keeps_id(self):
+        first = db.put_push_token("usr_1", "tok-1", "fcm", "Pixel")
+        again = db.put_push_token("usr_1", "tok-1", "fcm", "Pixel 8")
+
+        self.assertEqual(first["tokenId"], again["tokenId"])
+        self.assertEqual([t["deviceName"] for t in db.get_push_tokens_for_user("usr_1")], ["Pixel 8"])
+
+    def test_delete_only_own_token(self):
+        token = db.put_push_token("usr_1", "tok-1", "fcm")
+
+        self.assertFalse(db.delete_push_token("usr_2", token["tokenId"]))
+        self.assertTrue(db.delete_push_token("usr_1", token["tokenId"]))
+        self.assertEqual(db.get_push_tokens_for_user("usr_1"), [])
+
+
+class TestPushTokenEndpoints(unittest.TestCase):
+    """Test POST / DELETE /api/v1/users/me/push-tokens."""
+
+    def setUp(self):
+        db._push_tokens.clear()
+        patcher = patch.object(main.audit_service, "log_event")
+        self.log_event = patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _register(self, user_id="usr_1"):
+        body = PushTokenReq(token="tok-1", platform="apns", deviceName="iPhone")
+        return asyncio.run(main.register_push_token.__wrapped__(body, fake_request(user_id, "doctor")))
+
+    def test_register_does_not_echo_token(self):
+        token = self._register()
+
+        self.assertEqual((token.platform, token.deviceName), ("apns", "iPhone"))
+        self.assertNotIn("token", token.model_dump())
+        self.assertEqual(db.get_push_tokens_for_user("usr_1")[0]["token"], "tok-1")
+
+    def test_delete(self):
+        token = self._register()
+
+        asyncio.run(main.delete_push_token.__wrapped__(token.tokenId, fake_request("usr_1", "doctor")))
+        self.assertEqual(db.get_push_tokens_for_user("usr_1"), [])
+
+    def test_delete_other_users_token_not_found(self):
+        token = self._register()
+
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.delete_push_token.__wrapped__(token.tokenId, fake_request("usr_2", "doctor")))
+        self.assertEqual(ctx.exception.status_code, 404)
+        self.assertEqual(len(db.get_push_tokens_for_user("usr_1")), 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
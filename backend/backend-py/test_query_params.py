@@ -0,0 +1,90 @@
+"""
+Tests for typed query parameter parsing
+
+Run with: python -m pytest test_query_params.py -v
+"""
+
+import uuid
+import unittest
+from datetime import datetime, timezone
+
+from query_params import QueryParams, QueryParamError, parse_date_range_params
+
+ID = "0f8fad5b-d9cb-469f-a165-70867728950e"
+
+
+class TestQueryParams(unittest.TestCase):
+    """Test each typed getter, including malformed and out-of-range input."""
+
+    def assertRejected(self, params, getter, name, *args, code="INVALID_QUERY_PARAM"):
+        with self.assertRaises(QueryParamError) as ctx:
+            getattr(QueryParams(params), getter)(name, *args)
+        self.assertEqual((ctx.exception.param, ctx.exception.code), (name, code))
+        self.assertIn(name, str(ctx.exception))
+
+    def test_missing_and_blank_use_default(self):
+        params = QueryParams({"a": None, "b": "  "})
+        self.assertEqual(params.get_int_range("a", 1, 10, default=5), 5)
+        self.assertEqual(params.get_str("b", default="x"), "x")
+        self.assertIsNone(params.get_bool("missing"))
+
+    def test_get_str(self):
+        self.assertEqual(QueryParams({"q": " tremor "}).get_str("q"), "tremor")
+        self.assertRejected({"q": "x" * 11}, "get_str", "q", None, 10)
+
+    def test_get_uuid(self):
+        self.assertEqual(QueryParams({"id": ID.upper()}).get_uuid("id"), uuid.UUID(ID))
+        self.assertRejected({"id": "not-a-uuid"}, "get_uuid", "id")
+
+    def test_get_int_range(self):
+        params = QueryParams({"limit": "25", "typed": 7})
+        self.assertEqual(params.get_int_range("limit", 1, 100), 25)
+        self.assertEqual(params.get_int_range("typed", 1, 100), 7)
+        self.assertEqual(QueryParams({"limit": "100"}).get_int_range("limit", 1, 100), 100)
+
+    def test_get_int_range_out_of_range(self):
+        self.assertRejected({"limit": "0"}, "get_int_range", "limit", 1, 100)
+        self.assertRejected({"limit": "101"}, "get_int_range", "limit", 1, 100)
+        self.assertRejected({"offset": "-1"}, "get_int_range", "offset", 0)
+        self.assertRejected({"n": "1000"}, "get_int_range", "n", None, 999)
+
+    def test_get_int_range_malformed(self):
+        self.assertRejected({"limit": "1.5"}, "get_int_range", "limit", 1, 100)
+        self.assertRejected({"limit": "ten"}, "get_int_range", "limit", 1, 100)
+        self.assertRejected({"limit": True}, "get_int_range", "limit", 1, 100)
+
+    def test_get_bool(self):
+        params = QueryParams({"a": "TRUE", "b": "0", "c": "yes", "d": False})
+        self.assertEqual([params.get_bool(n) for n in "abcd"], [True, False, True, False])
+        self.assertRejected({"pending": "maybe"}, "get_bool", "pending")
+
+    def test_get_datetime(self):
+        params = QueryParams({"date": "2025-06-01", "at": "2025-06-01T09:30:00Z", "local": "2025-06-01T11:30:00+02:00"})
+        self.assertEqual(params.get_datetime("date"), datetime(2025, 6, 1, tzinfo=timezone.utc))
+        self.assertEqual(params.get_datetime("at"), datetime(2025, 6, 1, 9, 30, tzinfo=timezone.utc))
+        self.assertEqual(params.get_datetime("local"), params.get_datetime("at"))
+        self.assertRejected({"date": "01/06/2025"}, "get_datetime", "date", code="INVALID_DATE")
+        self.assertRejected({"date": "2025-13-01"}, "get_datetime", "date", code="INVALID_DATE")
+
+
+class TestDateRangeParams(unittest.TestCase):
+    """Test start_date / end_date validation."""
+
+    def test_open_and_closed_ranges(self):
+        self.assertEqual(parse_date_range_params(), (None, None))
+        start, end = parse_date_range_params("2025-06-01", "2025-06-30")
+        self.assertLess(start, end)
+
+    def test_inverted_range(self):
+        with self.assertRaises(QueryParamError) as ctx:
+            parse_date_range_params("2025-07-01", "2025-06-01")
+        self.assertEqual((ctx.exception.param, ctx.exception.code), ("start_date", "INVALID_DATE_RANGE"))
+
+    def test_malformed_end_date(self):
+        with self.assertRaises(QueryParamError) as ctx:
+            parse_date_range_params("2025-06-01", "soon")
+        self.assertEqual((ctx.exception.param, ctx.exception.code), ("end_date", "INVALID_DATE"))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,168 @@
+"""
+Tests for GPS locations on device readings
+
+Run with: python -m pytest test_reading_location.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from boto3.dynamodb.types import TypeDeserializer, TypeSerializer
+from pydantic import ValidationError
+
+import db
+import main
+import readings_import
+from device_data_ingest import to_sensor_item
+from fastapi import HTTPException
+from models import GpsCoordinate
+from reading_validation import ReadingValidationError, validate_location
+
+NOW = 1_760_000_000
+
+
+class TestValidateLocation(unittest.TestCase):
+    """Test coordinate validation."""
+
+    def test_stored_with_snake_case_keys(self):
+        self.assertEqual(
+            validate_location({"latitude": 52.52, "longitude": 13.405, "accuracyMeters": 4.5, "altitudeM": 34}),
+            {"latitude": 52.52, "longitude": 13.405, "accuracy_meters": 4.5, "altitude_m": 34.0}
+        )
+
+    def test_optional_fields(self):
+        self.assertEqual(validate_location({"latitude": -33.9, "longitude": 151.2}),
+                         {"latitude": -33.9, "longitude": 151.2})
+        self.assertIsNone(validate_location(None))
+
+    def test_range_boundaries_accepted(self):
+        validate_location({"latitude": 90, "longitude": -180})
+        validate_location({"latitude": -90, "longitude": 180})
+
+    def test_invalid_locations_rejected(self):
+        for location in (
+            {"latitude": 90.1, "longitude": 0},
+            {"latitude": 0, "longitude": -180.5},
+            {"latitude": 0, "longitude": 0, "accuracyMeters": -1},
+            {"latitude": float("nan"), "longitude": 0},
+            {"latitude": True, "longitude": 0},
+            {"latitude": "52.5", "longitude": 13.4},
+            {"longitude": 13.4},
+            {"latitude": 0, "longitude": 0, "speed": 3},
+            [52.5, 13.4],
+        ):
+            with self.assertRaises(ReadingValidationError, msg=location):
+                validate_location(location)
+
+    def test_model_ranges(self):
+        self.assertEqual(GpsCoordinate(latitude=1, longitude=2, accuracyMeters=3).accuracy_meters, 3)
+        for kwargs in ({"latitude": -91, "longitude": 0}, {"latitude": 0, "longitude": 181}):
+            with self.assertRaises(ValidationError):
+                GpsCoordinate(**kwargs)
+
+
+class TestDynamoRoundTrip(unittest.TestCase):
+    """A location is stored as a DynamoDB map and reads back unchanged."""
+
+    def test_gps_coordinate_round_trip(self):
+        coordinate = GpsCoordinate(latitude=47.3769, longitude=8.5417, accuracyMeters=3.5, altitudeM=408.0)
+        item = to_sensor_item("dev_1", "usr_1", {
+            "timestamp": NOW, "readingType": "heart_rate", "values": {"bpm": 72},
+            "location": coordinate.model_dump(by_alias=True, exclude_none=True),
+        })
+
+        wire = TypeSerializer().serialize(db._to_decimal(item))
+        self.assertEqual(wire["M"]["location"]["M"]["latitude"], {"N": "47.3769"})
+        stored = db._from_decimal(TypeDeserializer().deserialize(wire))
+
+        self.assertEqual(GpsCoordinate(**stored["location"]), coordinate)
+
+    def test_reading_without_location_has_no_attribute(self):
+        item = to_sensor_item("dev_1", "usr_1", {"timestamp": NOW, "readingType": "heart_rate", "values": {"bpm": 72}})
+        self.assertNotIn("location", item)
+
+    def test_invalid_location_rejects_reading(self):
+        with self.assertRaises(ReadingValidationError):
+            to_sensor_item("dev_1", "usr_1", {"timestamp": NOW, "readingType": "heart_rate", "values": {"bpm": 72},
+                                              "location": {"latitude": 120, "longitude": 0}})
+
+    def test_csv_location_columns(self):
+        rows = list(readings_import.parse_csv([
+            "deviceId,timestamp,readingType,latitude,longitude,accuracyMeters,bpm",
+            f"dev_1,{NOW},heart_rate,52.5,13.4,5,72",
+            f"dev_1,{NOW + 60},heart_rate,,,,74",
+        ]))
+        self.assertEqual(rows[0][1]["location"], {"latitude": 52.5, "longitude": 13.4, "accuracyMeters": 5})
+        self.assertEqual(rows[0][1]["values"], {"bpm": 72})
+        self.assertIsNone(rows[1][1]["location"])
+
+
+class TestReadingsMapEndpoint(unittest.TestCase):
+    """Test GET /api/v1/devices/{device_id}/readings/map."""
+
+    def setUp(self):
+        db._sensor_data.clear()
+        db._devices.clear()
+        db._devices.append({"id": "dev_1", "patientId": "usr_1"})
+        db.batch_write_device_readings([
+            to_sensor_item("dev_1", "usr_1", {"timestamp": NOW - 120, "readingType": "heart_rate", "values": {"bpm": 72},
+                                              "location": {"latitude": 52.5, "longitude": 13.4, "altitudeM": 34}}),
+            to_sensor_item("dev_1", "usr_1", {"timestamp": NOW - 60, "readingType": "spo2", "values": {"spo2": 97},
+                                              "location": {"latitude": 52.6, "longitude": 13.5, "accuracyMeters": 8}}),
+            to_sensor_item("dev_1", "usr_1", {"timestamp": NOW, "readingType": "heart_rate", "values": {"bpm": 75}}),
+        ])
+
+    def _map(self, request=None, **params):
+        with patch.object(main.audit_service, "log_event") as audit, patch.object(main.time, "time", return_value=NOW):
+            res = asyncio.run(main.get_device_readings_map.__wrapped__("dev_1", request or fake_request("usr_doc", "doctor"), **params))
+        return res, audit
+
+    def test_feature_collection(self):
+        res, audit = self._map()
+        body = res.model_dump(by_alias=True)
+
+        self.assertEqual(body["type"], "FeatureCollection")
+        self.assertEqual(len(body["features"]), 2)
+        first = body["features"][0]
+        self.assertEqual(first["type"], "Feature")
+        self.assertEqual(first["geometry"], {"type": "Point", "coordinates": [13.4, 52.5, 34.0]})
+        self.assertEqual(first["properties"], {"readingType": "heart_rate", "values": {"bpm": 72},
+                                               "timestamp": NOW - 120, "accuracyMeters": None})
+        self.assertEqual(body["features"][1]["geometry"]["coordinates"], [13.5, 52.6])
+        self.assertEqual(audit.call_args.kwargs["action"], "map")
+
+    def test_reading_type_filter(self):
+        res, _ = self._map(reading_type="spo2")
+        self.assertEqual([f.properties.reading_type for f in res.features], ["spo2"])
+
+    def test_default_range_excludes_old_readings(self):
+        db.batch_write_device_readings([to_sensor_item("dev_1", "usr_1", {
+            "timestamp": NOW - 30 * 86400, "readingType": "heart_rate", "values": {"bpm": 70},
+            "location": {"latitude": 0, "longitude": 0}})])
+        res, _ = self._map()
+        self.assertEqual(len(res.features), 2)
+
+    def test_patient_only_sees_own_device(self):
+        res, _ = self._map(fake_request("usr_1", "patient"))
+        self.assertEqual(len(res.features), 2)
+        with self.assertRaises(HTTPException) as ctx:
+            self._map(fake_request("usr_other", "patient"))
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_unknown_device(self):
+        db._devices.clear()
+        with self.assertRaises(HTTPException) as ctx:
+            self._map()
+        self.assertEqual(ctx.exception.detail["code"], "DEVICE_NOT_FOUND")
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,132 @@
+"""
+Tests for device reading validation
+
+Run with: python -m pytest test_reading_validation.py -v
+"""
+
+import unittest
+
+from reading_validation import (
+    validate_reading, validate_waveform, ReadingValidationError, MAX_READING_KEYS, MAX_WAVEFORM_SAMPLES, canonical_unit
+)
+
+
+class TestValidateReading(unittest.TestCase):
+    """Test reading value validation."""
+
+    def test_valid_blood_pressure(self):
+        validate_reading("blood_pressure", {"systolic": 120, "diastolic": 80})
+
+    def test_valid_accelerometer(self):
+        validate_reading("accelerometer", {"accel_x": 0.1, "accel_y": -9.8, "accel_z": 0.3})
+
+    def test_nan_rejected(self):
+        with self.assertRaisesRegex(ReadingValidationError, "finite"):
+            validate_reading("heart_rate", {"bpm": float("nan")})
+
+    def test_infinity_rejected(self):
+        with self.assertRaisesRegex(ReadingValidationError, "finite"):
+            validate_reading("custom", {"value": float("inf")})
+
+    def test_missing_required_key_rejected(self):
+        with self.assertRaisesRegex(ReadingValidationError, "diastolic"):
+            validate_reading("blood_pressure", {"systolic": 120})
+
+    def test_out_of_range_rejected(self):
+        with self.assertRaisesRegex(ReadingValidationError, "bpm"):
+            validate_reading("heart_rate", {"bpm": -5})
+
+    def test_too_many_keys_rejected(self):
+        values = {f"k{i}": 1.0 for i in range(MAX_READING_KEYS + 1)}
+        with self.assertRaisesRegex(ReadingValidationError, "at most"):
+            validate_reading("custom", values)
+
+    def test_non_numeric_rejected(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("heart_rate", {"bpm": "72"})
+
+    def test_diastolic_above_systolic_rejected(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("blood_pressure", {"systolic": 80, "diastolic": 120})
+
+    def test_unknown_type_gets_generic_checks_only(self):
+        validate_reading("custom", {"anything": -1000.0})
+
+
+class TestCalibrationCorrection(unittest.TestCase):
+    """Test applying a device calibration correction."""
+
+    CALIBRATION = {"correctionFactor": 1.5, "keys": ["bpm"]}
+
+    def test_calibrated_keys_are_scaled(self):
+        values = validate_reading("heart_rate", {"bpm": 60, "rr": 1.0}, self.CALIBRATION)
+        self.assertEqual(values, {"bpm": 90.0, "rr": 1.0})
+
+    def test_without_calibration_values_unchanged(self):
+        self.assertEqual(validate_reading("heart_rate", {"bpm": 60}), {"bpm": 60})
+
+    def test_range_checked_after_correction(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("heart_rate", {"bpm": 250}, self.CALIBRATION)
+
+
+
+class TestUnitNormalization(unittest.TestCase):
+    """Test unit canonicalization, conversion and rejection."""
+
+    def test_spellings_canonicalized(self):
+        for spelling in ("mmHg", "mm Hg", "MMHG", " mmhg "):
+            self.assertEqual(canonical_unit(spelling), "mmHg")
+        self.assertEqual(canonical_unit("°F"), "degF")
+        self.assertIsNone(canonical_unit("furlongs"))
+
+    def test_canonical_unit_keeps_values(self):
+        values = validate_reading("blood_pressure", {"systolic": 120, "diastolic": 80}, unit="MM HG")
+        self.assertEqual(values, {"systolic": 120, "diastolic": 80})
+
+    def test_fahrenheit_converted_to_celsius(self):
+        values = validate_reading("temperature", {"celsius": 98.6}, unit="°F")
+        self.assertAlmostEqual(values["celsius"], 37.0)
+
+    def test_pounds_converted_to_kilograms(self):
+        values = validate_reading("weight", {"kg": 154.0}, unit="lbs")
+        self.assertAlmostEqual(values["kg"], 69.85, places=2)
+
+    def test_converted_value_range_checked(self):
+        # 98.6 taken as Celsius would pass the unit check but not the range
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("temperature", {"celsius": 130.0}, unit="degF")
+
+    def test_incompatible_unit_rejected(self):
+        with self.assertRaises(ReadingValidationError) as ctx:
+            validate_reading("temperature", {"celsius": 37.0}, unit="mg/dL")
+        self.assertIn("not valid for temperature", str(ctx.exception))
+
+    def test_unknown_unit_rejected(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("heart_rate", {"bpm": 70}, unit="furlongs")
+
+    def test_dimensionless_type_rejects_unit(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("tremor", {"tremor_index": 0.4}, unit="%")
+
+
+class TestWaveform(unittest.TestCase):
+    """Test ECG summary values and waveform samples."""
+
+    def test_ecg_summary_requires_rate_and_sample_rate(self):
+        with self.assertRaises(ReadingValidationError):
+            validate_reading("ecg", {"heart_rate_bpm": 72})
+
+    def test_samples_returned_as_floats(self):
+        self.assertEqual(validate_waveform([0, 1.5, -0.2]), [0.0, 1.5, -0.2])
+
+    def test_invalid_samples_rejected(self):
+        for samples in ([], "0.1,0.2", [0.1, None], [0.1, True], [0.1, float("inf")], [25.0],
+                        [0.0] * (MAX_WAVEFORM_SAMPLES + 1)):
+            with self.assertRaises(ReadingValidationError):
+                validate_waveform(samples)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,114 @@
+"""
+Tests for bulk readings import
+
+Run with: python -m pytest test_readings_import.py -v
+"""
+
+import os
+import json
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import readings_import
+from readings_import import ImportRowError, detect_format, import_lines
+
+CSV_FILE = """deviceId,timestamp,readingType,bpm
+DEV-1,1735689600,heart_rate,72
+DEV-1,1735689660,heart_rate,not-a-number
+DEV-1,1735689720,heart_rate,75
+"""
+
+
+class TestImportLines(unittest.TestCase):
+    """Test parsing, validation and storage of import rows."""
+
+    def setUp(self):
+        db._sensor_data.clear()
+        db._devices.clear()
+        db.create_device({"id": "DEV-1", "patientId": "PAT-1"})
+
+    def test_csv_good_rows_imported_bad_row_reported(self):
+        summary = import_lines(CSV_FILE.splitlines(), "csv")
+
+        self.assertEqual(summary["succeeded"], 2)
+        self.assertEqual(summary["failed"], 1)
+        self.assertEqual(summary["failures"][0]["line"], 3)
+        self.assertIn("bpm", summary["failures"][0]["error"])
+        self.assertEqual([r["bpm"] for r in db._sensor_data], [72, 75])
+        self.assertEqual(db._sensor_data[0]["patient_id"], "PAT-1")
+
+    def test_out_of_range_and_unknown_device_rows_skipped(self):
+        lines = [
+            "deviceId,timestamp,readingType,bpm",
+            "DEV-1,1735689600,heart_rate,900",
+            "DEV-9,1735689600,heart_rate,70",
+            "DEV-1,1735689600,heart_rate",
+            "DEV-1,1735689660,heart_rate,70",
+        ]
+        summary = import_lines(lines, "csv")
+
+        self.assertEqual(summary["succeeded"], 1)
+        self.assertEqual([f["line"] for f in summary["failures"]], [2, 3, 4])
+        self.assertIn("unknown device", summary["failures"][1]["error"])
+
+    def test_ndjson(self):
+        lines = [
+            json.dumps({"deviceId": "DEV-1", "timestamp": 1735689600, "readingType": "spo2", "values": {"spo2": 97}}),
+            "",
+            "{not json",
+            json.dumps([1, 2]),
+        ]
+        summary = import_lines(lines, "ndjson")
+
+        self.assertEqual(summary["succeeded"], 1)
+        self.assertEqual([f["line"] for f in summary["failures"]], [3, 4])
+
+    def test_missing_header_columns_rejects_file(self):
+        with self.assertRaises(ImportRowError):
+            import_lines(["deviceId,bpm", "DEV-1,70"], "csv")
+
+    def test_reported_failures_capped(self):
+        lines = ["deviceId,timestamp,readingType,bpm"] + ["DEV-1,x,heart_rate,70"] * 150
+        summary = import_lines(lines, "csv")
+
+        self.assertEqual(summary["failed"], 150)
+        self.assertEqual(len(summary["failures"]), readings_import.MAX_REPORTED_FAILURES)
+
+    def test_written_in_chunks(self):
+        lines = ["deviceId,timestamp,readingType,bpm"] + [f"DEV-1,{1735689600 + i},heart_rate,70" for i in range(5)]
+        with patch.object(readings_import, "IMPORT_WRITE_CHUNK", 2), \
+                patch.object(db, "batch_write_device_readings", side_effect=len) as write:
+            summary = import_lines(lines, "csv")
+
+        self.assertEqual(summary["succeeded"], 5)
+        self.assertEqual([len(c.args[0]) for c in write.call_args_list], [2, 2, 1])
+
+    def test_detect_format(self):
+        self.assertEqual(detect_format("imports/a/1_readings.CSV"), "csv")
+        self.assertEqual(detect_format("imports/a/1_readings.jsonl"), "ndjson")
+        self.assertIsNone(detect_format("imports/a/1_readings.xlsx"))
+
+
+class TestImportReadingsFile(unittest.TestCase):
+    """Test the S3-backed import and its audit event."""
+
+    def setUp(self):
+        db._sensor_data.clear()
+        db._devices.clear()
+        db.create_device({"id": "DEV-1", "patientId": "PAT-1"})
+
+    def test_streams_file_and_audits_counts(self):
+        with patch.object(readings_import.storage, "iter_lines", return_value=iter(CSV_FILE.splitlines())), \
+                patch.object(readings_import.audit_service, "log_event") as log_event:
+            summary = readings_import.import_readings_file("imports/admin/1_r.csv", "csv", "usr_admin", "admin")
+
+        self.assertEqual(summary["succeeded"], 2)
+        details = log_event.call_args.kwargs["details"]
+        self.assertEqual((details["succeeded"], details["failed"]), (2, 1))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,116 @@
+"""
+Tests for the live readings long-poll endpoint
+
+Run with: python -m pytest test_readings_stream.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from fastapi import HTTPException
+
+
+def _reading(timestamp, device_id="dev-1"):
+    return {"patient_id": "pat-1", "device_id": device_id, "timestamp": timestamp, "tremor_index": 0.2}
+
+
+class TestReadingsStream(unittest.TestCase):
+    """Test GET /api/v1/devices/{device_id}/readings/stream."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._tremor_analysis.clear()
+        db._devices.append({"id": "dev-1", "patientId": "pat-1"})
+        self.sleeps = []
+
+        async def _sleep(seconds):
+            self.sleeps.append(seconds)
+
+        self.addCleanup(patch.stopall)
+        patch.object(main.asyncio, "sleep", side_effect=_sleep).start()
+        self.log_event = patch.object(main.audit_service, "log_event").start()
+
+    def _poll(self, since, user_id="doc-1", role="doctor", wait=main.READINGS_STREAM_MAX_WAIT_SECONDS):
+        # __wrapped__ skips require_role; the roles used here are all allowed
+        return asyncio.run(main.stream_device_readings.__wrapped__("dev-1", fake_request(user_id, role), since, wait))
+
+    def test_only_readings_after_since_returned(self):
+        db._tremor_analysis.extend([_reading(1000), _reading(2000), _reading(3000), _reading(4000, device_id="dev-2")])
+
+        res = self._poll(since=2000)
+
+        self.assertEqual([r.timestamp for r in res.items], [3000])
+        self.assertEqual(res.since, 3000)
+        self.assertEqual(self.sleeps, [])
+        self.log_event.assert_called_once()
+
+    def test_holds_until_new_reading_arrives(self):
+        db._tremor_analysis.append(_reading(1000))
+        original_sleep = main.asyncio.sleep.side_effect
+
+        async def _arrive(seconds):
+            await original_sleep(seconds)
+            if len(self.sleeps) == 2:
+                db._tremor_analysis.append(_reading(1500))
+
+        main.asyncio.sleep.side_effect = _arrive
+        res = self._poll(since=1000)
+
+        self.assertEqual([r.timestamp for r in res.items], [1500])
+        self.assertEqual(len(self.sleeps), 2)
+
+    def test_empty_when_nothing_new(self):
+        db._tremor_analysis.append(_reading(1000))
+        res = self._poll(since=1000, wait=0)
+
+        self.assertEqual(res.items, [])
+        self.assertEqual(res.since, 1000)
+        self.assertEqual(self.sleeps, [])
+        self.log_event.assert_not_called()
+
+    def test_wait_capped_below_gateway_timeout(self):
+        self.assertLess(main.READINGS_STREAM_MAX_WAIT_SECONDS, 29)
+        with patch.object(main, "READINGS_STREAM_MAX_WAIT_SECONDS", 0):
+            res = self._poll(since=0, wait=600)
+        self.assertEqual(res.items, [])
+        self.assertEqual(self.sleeps, [])
+
+    def test_polls_query_device_index(self):
+        table = MagicMock()
+        table.query.return_value = {"Items": []}
+        with patch.object(db, "USE_MEMORY", False), patch.object(db, "T_TREMOR_ANALYSIS", table, create=True), \
+                patch.object(db, "get_device", return_value={"id": "dev-1", "patientId": "pat-1"}):
+            self._poll(since=1000, wait=0)
+
+        self.assertEqual(table.query.call_args.kwargs["IndexName"], db.TREMOR_DEVICE_INDEX)
+        table.scan.assert_not_called()
+
+    def test_other_patients_device_denied(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._poll(since=0, user_id="pat-2", role="patient")
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_own_device_allowed(self):
+        db._tremor_analysis.append(_reading(1000))
+        res = self._poll(since=0, user_id="pat-1", role="patient")
+        self.assertEqual(len(res.items), 1)
+
+    def test_unknown_device(self):
+        db._devices.clear()
+        with self.assertRaises(HTTPException) as ctx:
+            self._poll(since=0)
+        self.assertEqual(ctx.exception.status_code, 404)
+
+
+if __name__ == '__main__':
+    unittest.main()
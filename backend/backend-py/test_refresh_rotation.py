@@ -0,0 +1,151 @@
+"""
+Tests for single-use refresh token rotation and session management
+
+Run with: python -m pytest test_refresh_rotation.py -v
+"""
+
+import os
+import sys
+import time
+import unittest
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from auth import issue_tokens, decode_refresh_token, verify_jwt
+from fastapi import HTTPException
+
+
+class TestRefreshTokens(unittest.TestCase):
+    """Test refresh token claims."""
+
+    def test_refresh_token_carries_jti(self):
+        tokens = issue_tokens("USR-1", "patient")
+        claims = decode_refresh_token(tokens["refreshToken"])
+        self.assertEqual(claims["jti"], tokens["refreshJti"])
+
+    def test_each_issue_gets_new_jti(self):
+        self.assertNotEqual(issue_tokens("USR-1", "patient")["refreshJti"],
+                            issue_tokens("USR-1", "patient")["refreshJti"])
+
+    def test_access_token_rejected_as_refresh(self):
+        tokens = issue_tokens("USR-1", "patient")
+        self.assertIsNone(decode_refresh_token(tokens["accessJwt"]))
+        self.assertEqual(verify_jwt(tokens["accessJwt"])["sub"], "USR-1")
+
+    def test_session_id_shared_by_access_and_refresh_token(self):
+        tokens = issue_tokens("USR-1", "patient")
+        self.assertEqual(decode_refresh_token(tokens["refreshToken"])["sid"], tokens["sessionId"])
+        self.assertEqual(verify_jwt(tokens["accessJwt"])["sid"], tokens["sessionId"])
+
+    def test_rotation_keeps_session_id(self):
+        first = issue_tokens("USR-1", "patient")
+        second = issue_tokens("USR-1", "patient", session_id=first["sessionId"])
+        self.assertEqual(second["sessionId"], first["sessionId"])
+        self.assertNotEqual(second["refreshJti"], first["refreshJti"])
+
+    def test_garbage_rejected(self):
+        self.assertIsNone(decode_refresh_token("not-a-token"))
+
+
+class TestRefreshSessions(unittest.TestCase):
+    """Test session-scoped rotation, listing and revocation in memory mode."""
+
+    def setUp(self):
+        db._refresh.clear()
+
+    def _save(self, tokens, user_id="USR-1", expires_at=None):
+        db.save_refresh(tokens["refreshToken"], {
+            "userId": user_id,
+            "role": "patient",
+            "sessionId": tokens["sessionId"],
+            "expiresAt": expires_at or int(time.time()) + 3600,
+            "userAgent": "test-agent",
+        })
+        return tokens
+
+    def _login(self, user_id="USR-1"):
+        return self._save(issue_tokens(user_id, "patient"), user_id)
+
+    def test_taken_token_cannot_be_taken_again(self):
+        tokens = self._login()
+        self.assertIsNotNone(db.take_refresh(tokens["refreshToken"]))
+        self.assertIsNone(db.take_refresh(tokens["refreshToken"]))
+
+    def test_replayed_token_detected_while_session_active(self):
+        first = self._login()
+        db.take_refresh(first["refreshToken"])
+        self._save(issue_tokens("USR-1", "patient", session_id=first["sessionId"]))
+        self.assertIsNone(db.take_refresh(first["refreshToken"]))
+        self.assertTrue(db.has_refresh_session("USR-1", first["sessionId"]))
+
+    def test_list_sessions(self):
+        a, b = self._login(), self._login()
+        self._login("USR-2")
+        sessions = db.list_refresh_sessions("USR-1")
+        self.assertEqual({s["sessionId"] for s in sessions}, {a["sessionId"], b["sessionId"]})
+        self.assertEqual(sessions[0]["userAgent"], "test-agent")
+
+    def test_list_skips_expired_sessions(self):
+        self._save(issue_tokens("USR-1", "patient"), expires_at=int(time.time()) - 1)
+        self.assertEqual(db.list_refresh_sessions("USR-1"), [])
+
+    def test_revoke_one_session_keeps_others(self):
+        a, b = self._login(), self._login()
+        self.assertEqual(db.revoke_refresh_session("USR-1", a["sessionId"]), 1)
+        self.assertIsNone(db.take_refresh(a["refreshToken"]))
+        self.assertIsNotNone(db.take_refresh(b["refreshToken"]))
+
+    def test_revoke_session_of_other_user_is_noop(self):
+        other = self._login("USR-2")
+        self.assertEqual(db.revoke_refresh_session("USR-1", other["sessionId"]), 0)
+        self.assertTrue(db.has_refresh_session("USR-2", other["sessionId"]))
+
+    def test_revoke_all_but_current(self):
+        current, _, _ = self._login(), self._login(), self._login()
+        self.assertEqual(db.revoke_user_refresh_tokens("USR-1", except_session_id=current["sessionId"]), 2)
+        self.assertEqual([s["sessionId"] for s in db.list_refresh_sessions("USR-1")], [current["sessionId"]])
+
+    def test_revoke_signs_out_all_sessions(self):
+        self._login()
+        self._login()
+        other = self._login("USR-2")
+        self.assertEqual(db.revoke_user_refresh_tokens("USR-1"), 2)
+        self.assertEqual(list(db._refresh), [other["refreshToken"]])
+
+
+class TestRefreshEndpoint(unittest.TestCase):
+    """Test that refresh reflects the user's current account state."""
+
+    def setUp(self):
+        db._refresh.clear()
+        db._users["USR-R"] = {"id": "USR-R", "email": "r@example.com", "role": "patient", "isActive": True}
+        self.addCleanup(db._users.pop, "USR-R", None)
+        self.request = fake_request()
+        self.tokens = issue_tokens("USR-R", "patient")
+        main._save_refresh_session(self.tokens, "USR-R", "patient", self.request)
+
+    def _refresh(self):
+        return main.refresh(main.RefreshReq(refreshToken=self.tokens["refreshToken"]), self.request)
+
+    def test_refresh_picks_up_role_change(self):
+        db._users["USR-R"]["role"] = "doctor"
+        res = self._refresh()
+        self.assertEqual(verify_jwt(res.accessJwt)["role"], "doctor")
+        self.assertEqual(db._refresh[res.refreshToken]["role"], "doctor")
+
+    def test_deactivated_user_cannot_refresh(self):
+        db._users["USR-R"]["isActive"] = False
+        with self.assertRaises(HTTPException) as ctx:
+            self._refresh()
+        self.assertEqual(ctx.exception.status_code, 401)
+        self.assertEqual(ctx.exception.detail["code"], "ACCOUNT_DISABLED")
+        self.assertFalse(db.has_refresh_session("USR-R", self.tokens["sessionId"]))
+
+
+if __name__ == '__main__':
+    unittest.main()
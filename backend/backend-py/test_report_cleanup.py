@@ -0,0 +1,255 @@
+"""
+Tests for expired report cleanup
+
+Run with: python -m pytest test_report_cleanup.py -v
+"""
+
+import os
+import unittest
+from types import SimpleNamespace
+from datetime import datetime, timedelta, timezone
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+from botocore.exceptions import ClientError
+
+import db
+import storage
+import report_cleanup
+from audit_service import AuditEventType
+from report_cleanup import cleanup_expired_reports, is_report_expired, notify_expiring_reports
+
+NOW = datetime(2025, 6, 1, tzinfo=timezone.utc)
+
+
+def _iso(days):
+    return (NOW + timedelta(days=days)).isoformat()
+
+
+class TestCleanupExpiredReports(unittest.TestCase):
+    """Test deletion of expired reports and their files."""
+
+    def setUp(self):
+        db._reports.clear()
+        self.addCleanup(patch.stopall)
+        self.delete_object = patch.object(report_cleanup.storage, "delete_object").start()
+        self.log_event = patch.object(report_cleanup.audit_service, "log_event").start()
+
+    def _report(self, expires_in_days=None, file_key="reports/pat-1/r.pdf"):
+        fields = {"patientId": "pat-1", "fileKey": file_key}
+        if expires_in_days is not None:
+            fields["expiresAt"] = _iso(expires_in_days)
+        return db.create_report(fields)["reportId"]
+
+    def test_expired_report_and_file_deleted(self):
+        expired = self._report(-1)
+
+        result = cleanup_expired_reports(now=NOW)
+
+        self.assertEqual(result, {"deleted": 1, "failed": 0, "cursor": None})
+        self.assertIsNone(db.get_report(expired))
+        self.delete_object.assert_called_once_with("reports/pat-1/r.pdf")
+        kwargs = self.log_event.call_args.kwargs
+        self.assertEqual(kwargs["event_type"], AuditEventType.REPORT_DELETED)
+        self.assertEqual(kwargs["resource_id"], expired)
+
+    def test_unexpired_reports_left_intact(self):
+        future = self._report(1)
+        no_expiry = self._report()
+
+        result = cleanup_expired_reports(now=NOW)
+
+        self.assertEqual(result["deleted"], 0)
+        self.assertIsNotNone(db.get_report(future))
+        self.assertIsNotNone(db.get_report(no_expiry))
+        self.delete_object.assert_not_called()
+
+    def test_resumes_from_cursor(self):
+        ids = {self._report(-1) for _ in range(5)}
+
+        first = cleanup_expired_reports(now=NOW, batch_size=2)
+        self.assertEqual(first["deleted"], 2)
+        self.assertIsNotNone(first["cursor"])
+        second = cleanup_expired_reports(now=NOW, cursor=first["cursor"], batch_size=10)
+
+        self.assertEqual(second["deleted"], 3)
+        self.assertIsNone(second["cursor"])
+        self.assertTrue(all(db.get_report(i) is None for i in ids))
+
+    def test_s3_failure_keeps_record_for_retry(self):
+        report_id = self._report(-1)
+        self.delete_object.side_effect = RuntimeError("S3 unavailable")
+
+        result = cleanup_expired_reports(now=NOW)
+
+        self.assertEqual((result["deleted"], result["failed"]), (0, 1))
+        self.assertIsNotNone(db.get_report(report_id))
+        self.log_event.assert_not_called()
+
+    def test_report_without_file(self):
+        report_id = self._report(-1, file_key=None)
+
+        self.assertEqual(cleanup_expired_reports(now=NOW)["deleted"], 1)
+        self.assertIsNone(db.get_report(report_id))
+        self.delete_object.assert_not_called()
+
+    def test_run_stops_near_timeout_with_cursor(self):
+        for _ in range(3):
+            self._report(-1)
+        context = SimpleNamespace(get_remaining_time_in_millis=lambda: 1000)
+
+        with patch.object(report_cleanup, "CLEANUP_BATCH_SIZE", 1):
+            result = report_cleanup.run({}, context)
+
+        self.assertEqual(result["deleted"], 1)
+        self.assertIsNotNone(result["cursor"])
+
+    def test_is_report_expired(self):
+        self.assertTrue(is_report_expired({"expiresAt": "2025-05-31T23:59:59Z"}, NOW))
+        self.assertFalse(is_report_expired({"expiresAt": "2025-06-01T00:00:01"}, NOW))
+        self.assertFalse(is_report_expired({}, NOW))
+
+
+class TestNotifyExpiringReports(unittest.TestCase):
+    """Test authors are warned once before their reports expire."""
+
+    def setUp(self):
+        db._reports.clear()
+        self.addCleanup(patch.stopall)
+        self.push = patch.object(report_cleanup.notification_service, "push_to_user", return_value=1).start()
+        patch.object(report_cleanup.storage, "delete_object").start()
+        patch.object(report_cleanup.audit_service, "log_event").start()
+
+    def _report(self, expires_in_hours, author="usr_doc", now=NOW):
+        fields = {"patientId": "pat-1", "authorId": author, "title": "Monthly summary",
+                  "expiresAt": (now + timedelta(hours=expires_in_hours)).isoformat()}
+        return db.create_report(fields)["reportId"]
+
+    def test_expires_on_stamped(self):
+        report_id = self._report(30)
+        self.assertEqual(db.get_report(report_id)["expiresOn"], "2025-06-02")
+        db.update_report(report_id, {"expiresAt": "2025-07-01T10:00:00+00:00"})
+        self.assertEqual(db.get_report(report_id)["expiresOn"], "2025-07-01")
+        self.assertEqual(db.get_report(report_id)["expiresAt"], "2025-07-01T10:00:00Z")
+
+    def test_older_report_backfilled_and_notified(self):
+        db._reports.append({"reportId": "RPT-OLD", "patientId": "pat-1", "authorId": "usr_doc",
+                            "expiresAt": (NOW + timedelta(hours=24)).isoformat()})
+
+        self.assertEqual(db.backfill_report_expires_on(), 1)
+        self.assertEqual(db.get_report("RPT-OLD")["expiresOn"], "2025-06-02")
+        self.assertEqual(db.backfill_report_expires_on(), 0)
+        self.assertEqual(notify_expiring_reports(now=NOW)["notified"], 1)
+
+    def test_author_notified_once(self):
+        report_id = self._report(24)
+
+        self.assertEqual(notify_expiring_reports(now=NOW)["notified"], 1)
+        self.assertEqual(notify_expiring_reports(now=NOW)["skipped"], 1)
+
+        self.push.assert_called_once()
+        user_id, _, _, data = self.push.call_args.args
+        self.assertEqual(user_id, "usr_doc")
+        self.assertEqual(data["type"], "REPORT_EXPIRING")
+        self.assertEqual(data["reportId"], report_id)
+        self.assertEqual(db.get_report(report_id)["expiryNotifiedAt"], NOW.isoformat())
+
+    def test_reports_outside_window_ignored(self):
+        self._report(72)
+        self._report(-1)
+
+        self.assertEqual(notify_expiring_reports(now=NOW)["notified"], 0)
+        self.push.assert_not_called()
+
+    def test_unreachable_author_retried_next_run(self):
+        report_id = self._report(24)
+        self.push.return_value = 0
+
+        self.assertEqual(notify_expiring_reports(now=NOW)["failed"], 1)
+        self.assertNotIn("expiryNotifiedAt", db.get_report(report_id))
+
+    def test_run_warns_then_deletes_expired(self):
+        now = datetime.now(timezone.utc)
+        expiring = self._report(12, now=now)
+        expired = self._report(-1, now=now)
+        context = SimpleNamespace(get_remaining_time_in_millis=lambda: 300000)
+
+        result = report_cleanup.run({}, context)
+
+        self.assertEqual((result["notified"], result["deleted"]), (1, 1))
+        self.assertIsNotNone(db.get_report(expiring))
+        self.assertIsNone(db.get_report(expired))
+
+
+class TestDeleteObject(unittest.TestCase):
+    """Test storage.delete_object tolerates missing objects."""
+
+    def setUp(self):
+        self.addCleanup(patch.stopall)
+        self.s3 = patch.object(storage, "s3", MagicMock()).start()
+        patch.dict(os.environ, {"S3_BUCKET": "medusa-test"}).start()
+
+    def _error(self, code):
+        return ClientError({"Error": {"Code": code, "Message": code}}, "DeleteObject")
+
+    def test_missing_object_ignored(self):
+        self.s3.delete_object.side_effect = self._error("NoSuchKey")
+        storage.delete_object("reports/pat-1/gone.pdf")
+
+    def test_other_errors_raised(self):
+        self.s3.delete_object.side_effect = self._error("AccessDenied")
+        with self.assertRaises(ClientError):
+            storage.delete_object("reports/pat-1/r.pdf")
+
+
+class TestScanExpiredReportsDynamo(unittest.TestCase):
+    """Test the scan sent to DynamoDB."""
+
+    def setUp(self):
+        self.table = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "T_REPORTS", self.table, create=True).start()
+
+    def test_scan_filters_and_returns_cursor(self):
+        self.table.scan.return_value = {"Items": [{"reportId": "RPT-1"}], "LastEvaluatedKey": {"reportId": "RPT-1"}}
+
+        page = db.scan_expired_reports(NOW.isoformat(), limit=1)
+
+        self.assertEqual([r["reportId"] for r in page.items], ["RPT-1"])
+        self.assertTrue(page.has_more)
+        self.assertIn("FilterExpression", self.table.scan.call_args.kwargs)
+        self.assertEqual(self.table.scan.call_args.kwargs["Limit"], 1)
+
+    def test_expiring_soon_queries_index_per_day(self):
+        self.table.query.side_effect = [
+            {"Items": [{"reportId": "RPT-1"}], "LastEvaluatedKey": {"reportId": "RPT-1"}},
+            {"Items": [{"reportId": "RPT-2"}]},
+            {"Items": []},
+            {"Items": [{"reportId": "RPT-3"}]},
+        ]
+
+        reports = db.get_reports_expiring_soon(48, NOW)
+
+        self.assertEqual([r["reportId"] for r in reports], ["RPT-1", "RPT-2", "RPT-3"])
+        calls = self.table.query.call_args_list
+        self.assertEqual(len(calls), 4)  # 2025-06-01 (two pages), 06-02, 06-03
+        self.assertTrue(all(c.kwargs["IndexName"] == "expiresAt-index" for c in calls))
+        self.assertEqual(calls[1].kwargs["ExclusiveStartKey"], {"reportId": "RPT-1"})
+        bounds = calls[0].kwargs["KeyConditionExpression"].get_expression()["values"][1].get_expression()["values"][1:]
+        self.assertEqual(bounds, ("2025-06-01T00:00:00Z", "2025-06-03T00:00:00Z"))
+
+    def test_backfill_sets_expires_on_conditionally(self):
+        self.table.scan.return_value = {"Items": [{"reportId": "RPT-1", "expiresAt": "2025-06-02T08:30:00.250000+02:00"}]}
+
+        self.assertEqual(db.backfill_report_expires_on(), 1)
+
+        update = self.table.update_item.call_args.kwargs
+        self.assertEqual(update["Key"], {"reportId": "RPT-1"})
+        self.assertEqual(update["ExpressionAttributeValues"], {":at": "2025-06-02T06:30:00Z", ":on": "2025-06-02"})
+        self.assertIn("ConditionExpression", update)
+
+
+if __name__ == "__main__":
+    unittest.main()
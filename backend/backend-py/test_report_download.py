@@ -0,0 +1,87 @@
+"""
+Tests for presigned report downloads (access checks, expiry, audit)
+
+Run with: python -m pytest test_report_download.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from datetime import datetime, timedelta, timezone
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from audit_service import AuditEventType
+from fastapi import HTTPException
+
+
+class TestReportDownload(unittest.TestCase):
+    """Test GET /api/v1/reports/{report_id}/download."""
+
+    def setUp(self):
+        db._reports.clear()
+        db._patient_profiles.clear()
+        db.create_patient_profile({"userId": "pat-1", "doctorId": "doc-1"})
+        self.addCleanup(patch.stopall)
+        self.presign = patch.object(main.storage, "presign_download", return_value="https://s3.example/report.pdf?sig=x").start()
+        self.log_event = patch.object(main.audit_service, "log_event").start()
+
+    def _report(self, **fields):
+        return db.create_report({"patientId": "pat-1", "authorId": "doc-1", "fileKey": "reports/pat-1/r.pdf", **fields})
+
+    def _download(self, report_id, user_id, role):
+        # __wrapped__ skips require_role; the roles used here are all allowed
+        return asyncio.run(main.download_report.__wrapped__(fake_request(user_id, role), report_id))
+
+    def _assert_status(self, report_id, user_id, role, status):
+        with self.assertRaises(HTTPException) as ctx:
+            self._download(report_id, user_id, role)
+        self.assertEqual(ctx.exception.status_code, status)
+        self.presign.assert_not_called()
+        return ctx.exception
+
+    def test_other_patient_denied(self):
+        report = self._report()
+        self._assert_status(report["reportId"], "pat-2", "patient", 403)
+
+    def test_unassigned_doctor_denied(self):
+        report = self._report()
+        self._assert_status(report["reportId"], "doc-2", "doctor", 403)
+
+    def test_hidden_report_denied_to_patient(self):
+        report = self._report(visibleToPatient=False)
+        self._assert_status(report["reportId"], "pat-1", "patient", 403)
+
+    def test_expired_report_gone(self):
+        expired = (datetime.now(timezone.utc) - timedelta(hours=1)).isoformat()
+        report = self._report(expiresAt=expired)
+        error = self._assert_status(report["reportId"], "pat-1", "patient", 410)
+        self.assertEqual(error.detail["code"], "REPORT_EXPIRED")
+
+    def test_access_checked_before_expiry(self):
+        report = self._report(expiresAt="2000-01-01T00:00:00Z")
+        self._assert_status(report["reportId"], "pat-2", "patient", 403)
+
+    def test_download_redirects_and_audits(self):
+        future = (datetime.now(timezone.utc) + timedelta(days=1)).isoformat()
+        report = self._report(expiresAt=future)
+
+        self._download(report["reportId"], "pat-1", "patient")
+
+        self.presign.assert_called_once_with(
+            "reports/pat-1/r.pdf", ttl_sec=main.REPORT_DOWNLOAD_URL_TTL_SECONDS, residency=None
+        )
+        kwargs = self.log_event.call_args.kwargs
+        self.assertEqual(kwargs["event_type"], AuditEventType.REPORT_DOWNLOADED)
+        self.assertEqual((kwargs["user_id"], kwargs["resource_id"]), ("pat-1", report["reportId"]))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,102 @@
+"""
+Tests for listing a patient's reports with access control
+
+Run with: python -m pytest test_report_listing.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from pagination import encode_cursor
+
+
+class TestListReportsForPatientMemory(unittest.TestCase):
+    """Test role filtering and paging in memory mode."""
+
+    def setUp(self):
+        db._reports.clear()
+        db._patient_profiles.clear()
+        db.create_patient_profile({"userId": "pat-1", "doctorId": "doc-1"})
+        for i, (author, visible) in enumerate([("doc-1", True), ("doc-2", None), ("doc-1", False), ("doc-2", True)]):
+            report = {"patientId": "pat-1", "authorId": author, "title": f"r{i}"}
+            if visible is not None:
+                report["visibleToPatient"] = visible
+            created = db.create_report(report)
+            created["createdAt"] = f"2025-01-0{i + 1}T00:00:00+00:00"
+        db.create_report({"patientId": "pat-2", "authorId": "doc-1", "title": "other"})
+
+    def _titles(self, requester_id, role, **kw):
+        return [r["title"] for r in db.list_reports_for_patient("pat-1", requester_id, role, **kw).items]
+
+    def test_patient_does_not_see_hidden_reports(self):
+        self.assertEqual(self._titles("pat-1", "patient"), ["r3", "r1", "r0"])
+
+    def test_assigned_doctor_sees_all(self):
+        self.assertEqual(self._titles("doc-1", "doctor"), ["r3", "r2", "r1", "r0"])
+
+    def test_other_doctor_sees_only_own_reports(self):
+        self.assertEqual(self._titles("doc-2", "doctor"), ["r3", "r1"])
+
+    def test_admin_sees_all(self):
+        self.assertEqual(len(self._titles("admin-1", "admin")), 4)
+
+    def test_pages_follow_cursor(self):
+        first = db.list_reports_for_patient("pat-1", "admin-1", "admin", limit=3)
+        self.assertTrue(first.has_more)
+        second = db.list_reports_for_patient("pat-1", "admin-1", "admin", limit=3, cursor=first.next_cursor)
+        self.assertEqual([r["title"] for r in first.items + second.items], ["r3", "r2", "r1", "r0"])
+        self.assertFalse(second.has_more)
+
+    def test_invalid_cursor(self):
+        with self.assertRaises(ValueError):
+            db.list_reports_for_patient("pat-1", "admin-1", "admin", cursor="not-a-cursor")
+
+
+class TestListReportsForPatientDynamo(unittest.TestCase):
+    """Test the query sent to DynamoDB."""
+
+    def setUp(self):
+        self.table = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "T_REPORTS", self.table, create=True).start()
+        patch.object(db, "get_patient_profile", return_value={"userId": "pat-1", "doctorId": "doc-1"}).start()
+
+    def test_patient_query_filters_hidden_reports(self):
+        self.table.query.return_value = {"Items": [{"reportId": "RPT-1"}]}
+        page = db.list_reports_for_patient("pat-1", "pat-1", "patient", limit=10)
+        kwargs = self.table.query.call_args.kwargs
+        self.assertEqual(kwargs["IndexName"], "patientId-index")
+        self.assertIn("FilterExpression", kwargs)
+        self.assertEqual(len(page.items), 1)
+        self.assertFalse(page.has_more)
+
+    def test_assigned_doctor_query_has_no_filter(self):
+        self.table.query.return_value = {"Items": []}
+        db.list_reports_for_patient("pat-1", "doc-1", "doctor")
+        self.assertNotIn("FilterExpression", self.table.query.call_args.kwargs)
+
+    def test_keeps_reading_until_page_is_full(self):
+        self.table.query.side_effect = [
+            {"Items": [{"reportId": "RPT-1"}], "LastEvaluatedKey": {"reportId": "RPT-2"}},
+            {"Items": [{"reportId": "RPT-3"}], "LastEvaluatedKey": {"reportId": "RPT-3"}},
+        ]
+        page = db.list_reports_for_patient("pat-1", "doc-2", "doctor", limit=2)
+        self.assertEqual([r["reportId"] for r in page.items], ["RPT-1", "RPT-3"])
+        second = self.table.query.call_args_list[1].kwargs
+        self.assertEqual(second["Limit"], 1)
+        self.assertEqual(second["ExclusiveStartKey"], {"reportId": "RPT-2"})
+        self.assertTrue(page.has_more)
+
+    def test_cursor_becomes_start_key(self):
+        self.table.query.return_value = {"Items": []}
+        db.list_reports_for_patient("pat-1", "admin-1", "admin", cursor=encode_cursor({"reportId": "RPT-9"}))
+        self.assertEqual(self.table.query.call_args.kwargs["ExclusiveStartKey"], {"reportId": "RPT-9"})
+
+
+if __name__ == "__main__":
+    unittest.main()
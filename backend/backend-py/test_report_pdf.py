@@ -0,0 +1,101 @@
+"""
+Tests for PDF report generation
+
+Run with: python -m pytest test_report_pdf.py -v
+"""
+
+import os
+import struct
+import unittest
+from datetime import datetime, timezone
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from report_pdf import PdfReportGenerator, ReportData, ReportParameters, count_pages, jpeg_size, load_patient_summary_data
+
+PARAMS = ReportParameters(generated_by="Dr Bob", generated_at=datetime(2025, 6, 1, 9, 0, tzinfo=timezone.utc))
+
+
+def _jpeg(width: int, height: int) -> bytes:
+    """Just enough of a JPEG (SOI + SOF0) for jpeg_size and embedding."""
+    sof = b"\x08" + struct.pack(">HH", height, width) + b"\x03\x01\x11\x00\x02\x11\x01\x03\x11\x01"
+    return b"\xff\xd8" + b"\xff\xe0" + struct.pack(">H", 4) + b"JF" + b"\xff\xc0" + struct.pack(">H", len(sof) + 2) + sof + b"\xff\xd9"
+
+
+def _data(readings=(), medications=(), devices=()):
+    return ReportData(
+        patient={"id": "usr_p1", "name": "Ann Lee"},
+        profile={"dateOfBirth": "1950-03-02"},
+        medications=list(medications),
+        devices=list(devices),
+        readings=list(readings),
+    )
+
+
+class TestPdfReportGenerator(unittest.TestCase):
+    """Test the generated PDF byte stream."""
+
+    def test_minimal_pdf(self):
+        pdf = PdfReportGenerator().generate(_data(), PARAMS)
+
+        self.assertTrue(pdf.startswith(b"%PDF-"))
+        self.assertTrue(pdf.rstrip().endswith(b"%%EOF"))
+        self.assertEqual(count_pages(pdf), 2)  # cover page + sections
+        self.assertIn(b"(Ann Lee)", pdf)
+        self.assertIn(b"(1950-03-02)", pdf)
+        self.assertIn(b"(Dr Bob)", pdf)
+
+    def test_sections_flow_onto_more_pages(self):
+        readings = [{"patient_id": "usr_p1", "device_id": "DEV-1", "timestamp": 1735689600 + i * 60,
+                     "tremor_index": 0.1 * (i % 10)} for i in range(200)]
+        pdf = PdfReportGenerator().generate(
+            _data(readings=readings,
+                  medications=[{"name": "Levodopa", "dosage": "100 mg", "frequency": "3x daily"}],
+                  devices=[{"id": "DEV-1", "name": "Wristband", "type": "wearable", "status": "online", "batteryLevel": 80}]),
+            ReportParameters(generated_by="Dr Bob", recent_readings=120)
+        )
+
+        self.assertGreater(count_pages(pdf), 3)
+        self.assertIn(b"(Levodopa)", pdf)
+        self.assertIn(b"(80%)", pdf)
+
+    def test_special_characters_escaped(self):
+        data = _data()
+        data.patient["name"] = "O'Neil (Jr) \\ Zoë 中"
+        pdf = PdfReportGenerator().generate(data, PARAMS)
+
+        self.assertIn(b"(O'Neil \\(Jr\\) \\\\ Zo\xeb ?)", pdf)
+
+    def test_logo_embedded(self):
+        logo = _jpeg(300, 100)
+        self.assertEqual(jpeg_size(logo), (300, 100))
+
+        pdf = PdfReportGenerator(logo=logo).generate(_data(), PARAMS)
+
+        self.assertIn(b"/Subtype /Image /Width 300 /Height 100", pdf)
+        self.assertIn(b"/Logo Do", pdf)
+
+    def test_low_quality_readings_counted_and_excluded_from_trends(self):
+        readings = [{"timestamp": 1735689600 + i * 3600, "tremor_index": 0.2, "signal_quality": 0.9} for i in range(3)]
+        readings.append({"timestamp": 1735700400 + 3600, "tremor_index": 0.9, "signal_quality": 0.1})
+        data = _data(readings=readings)
+
+        self.assertEqual((data.low_quality_count, data.low_quality_ratio), (1, 0.25))
+        pdf = PdfReportGenerator().generate(data, PARAMS)
+        self.assertIn(b"(1 low-quality reading\\(s\\) excluded from trends.)", pdf)
+
+    def test_flagged_readings_excluded_from_summary_data(self):
+        db._tremor_analysis.clear()
+        db._tremor_analysis.extend([
+            {"patient_id": "usr_p1", "timestamp": 1000, "tremor_index": 0.2},
+            {"patient_id": "usr_p1", "timestamp": 2000, "tremor_index": 0.9, "is_flagged": True},
+        ])
+
+        data = load_patient_summary_data("usr_p1")
+
+        self.assertEqual([r["timestamp"] for r in data.readings], [1000])
+
+
+if __name__ == "__main__":
+    unittest.main()
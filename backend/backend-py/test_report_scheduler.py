@@ -0,0 +1,148 @@
+"""
+Tests for scheduled report generation
+
+Run with: python -m pytest test_report_scheduler.py -v
+"""
+
+import os
+import json
+import unittest
+from datetime import datetime, timezone
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import report_scheduler
+from consent_service import ConsentType, record_consent
+from report_scheduler import (
+    create_schedule,
+    enqueue_due_schedules,
+    first_run_on_or_after,
+    next_future_run,
+    next_run_after,
+    process,
+)
+
+DAILY = {"type": "daily", "weekday": None, "dayOfMonth": None}
+MONDAYS = {"type": "weekly", "weekday": "monday", "dayOfMonth": None}
+MONTH_END = {"type": "monthly", "weekday": None, "dayOfMonth": 31}
+
+
+def _utc(*args):
+    return datetime(*args, tzinfo=timezone.utc)
+
+
+class TestFrequencies(unittest.TestCase):
+    """Test run time calculation."""
+
+    def test_daily(self):
+        self.assertEqual(next_run_after(DAILY, _utc(2025, 3, 31, 8)), _utc(2025, 4, 1, 8))
+
+    def test_weekly_first_and_next_run(self):
+        wednesday = _utc(2025, 3, 12, 7, 30)
+
+        first = first_run_on_or_after(MONDAYS, wednesday)
+
+        self.assertEqual(first, _utc(2025, 3, 17, 7, 30))
+        self.assertEqual(first_run_on_or_after(MONDAYS, first), first)
+        self.assertEqual(next_run_after(MONDAYS, first), _utc(2025, 3, 24, 7, 30))
+
+    def test_monthly_clamps_to_month_end(self):
+        runs = [first_run_on_or_after(MONTH_END, _utc(2025, 1, 15, 6))]
+        for _ in range(3):
+            runs.append(next_run_after(MONTH_END, runs[-1]))
+
+        self.assertEqual([r.date().isoformat() for r in runs], ["2025-01-31", "2025-02-28", "2025-03-31", "2025-04-30"])
+
+    def test_missed_runs_collapse(self):
+        """Test a schedule several runs behind moves to its next future run."""
+        self.assertEqual(
+            next_future_run(DAILY, _utc(2025, 3, 1, 9), now=_utc(2025, 3, 5, 12)),
+            _utc(2025, 3, 6, 9)
+        )
+
+
+class TestScheduler(unittest.TestCase):
+    """Test enqueueing due schedules and generating their reports."""
+
+    def setUp(self):
+        db._report_schedules.clear()
+        db._reports.clear()
+        db._consents.clear()
+        self.sqs = MagicMock()
+        patcher = patch.object(report_scheduler, "_sqs", self.sqs)
+        patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _schedule(self, start_at, patient_id="usr_p1"):
+        return create_schedule(
+            {"patientId": patient_id, "type": "weekly_summary"}, MONDAYS,
+            created_by="usr_doc", created_by_role="doctor", start_at=start_at
+        )
+
+    def test_only_due_schedules_are_enqueued_and_advanced(self):
+        due = self._schedule(_utc(2025, 3, 17, 6))
+        later = self._schedule(_utc(2025, 3, 24, 6))
+
+        enqueued = enqueue_due_schedules(now=_utc(2025, 3, 17, 7))
+
+        self.assertEqual(enqueued, 1)
+        body = json.loads(self.sqs.send_message.call_args.kwargs["MessageBody"])
+        self.assertEqual(body["scheduleId"], due["id"])
+        self.assertEqual(body["reportTemplate"]["patientId"], "usr_p1")
+        stored = db.get_report_schedule(due["id"])
+        self.assertEqual(stored["nextRunAt"], _utc(2025, 3, 24, 6).isoformat())
+        self.assertEqual(stored["lastRunAt"], _utc(2025, 3, 17, 7).isoformat())
+        self.assertEqual(db.get_report_schedule(later["id"])["lastRunAt"], None)
+
+    def test_failed_enqueue_keeps_schedule_due(self):
+        schedule = self._schedule(_utc(2025, 3, 17, 6))
+        self.sqs.send_message.side_effect = RuntimeError("queue unavailable")
+
+        self.assertEqual(enqueue_due_schedules(now=_utc(2025, 3, 17, 7)), 0)
+        self.assertEqual(db.get_report_schedule(schedule["id"])["nextRunAt"], schedule["nextRunAt"])
+
+    def test_inactive_schedule_is_not_due(self):
+        schedule = self._schedule(_utc(2025, 3, 17, 6))
+        db.put_report_schedule({**schedule, "isActive": False})
+
+        self.assertEqual(enqueue_due_schedules(now=_utc(2025, 3, 17, 7)), 0)
+
+    def test_worker_creates_report_with_consent(self):
+        record_consent("usr_p1", ConsentType.DATA_SHARING.value, True, "usr_p1", "patient")
+        message = {
+            "scheduleId": "sch_1",
+            "scheduledFor": "2025-03-17T06:00:00+00:00",
+            "reportTemplate": {"patientId": "usr_p1", "type": "weekly_summary"},
+            "createdBy": "usr_doc",
+            "createdByRole": "doctor",
+        }
+
+        result = process({"Records": [{"messageId": "m1", "body": json.dumps(message)}]}, None)
+
+        self.assertEqual(result, {"batchItemFailures": []})
+        reports = db.get_reports(patient_id="usr_p1")
+        self.assertEqual(len(reports), 1)
+        self.assertEqual((reports[0]["authorId"], reports[0]["scheduleId"]), ("usr_doc", "sch_1"))
+
+    def test_worker_skips_without_consent_and_reports_bad_messages(self):
+        message = {
+            "scheduleId": "sch_1",
+            "scheduledFor": "2025-03-17T06:00:00+00:00",
+            "reportTemplate": {"patientId": "usr_p1"},
+            "createdBy": "usr_doc",
+            "createdByRole": "doctor",
+        }
+
+        result = process({"Records": [
+            {"messageId": "m1", "body": json.dumps(message)},
+            {"messageId": "m2", "body": "not json"},
+        ]}, None)
+
+        self.assertEqual(result, {"batchItemFailures": [{"itemIdentifier": "m2"}]})
+        self.assertEqual(db.get_reports(patient_id="usr_p1"), [])
+
+
+if __name__ == "__main__":
+    unittest.main()
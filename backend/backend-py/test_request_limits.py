@@ -0,0 +1,97 @@
+"""
+Tests for request body size limits
+
+Run with: python -m pytest test_request_limits.py -v
+"""
+
+import os
+import json
+import asyncio
+import unittest
+from types import SimpleNamespace
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import device_data_ingest
+import storage
+from request_limits import MAX_BODY_BYTES, body_limit_for, body_size_middleware
+
+
+class _FakeRequest:
+    def __init__(self, method="POST", path="/api/v1/reports", headers=None, body=b""):
+        self.method = method
+        self.url = SimpleNamespace(path=path)
+        self.headers = headers or {}
+        self._body = body
+        self.body_read = False
+
+    async def body(self):
+        self.body_read = True
+        return self._body
+
+
+class TestBodySizeMiddleware(unittest.TestCase):
+    """Test the 413 boundary for declared and undeclared body sizes."""
+
+    def _run(self, request):
+        async def call_next(req):
+            return SimpleNamespace(status_code=200)
+        return asyncio.run(body_size_middleware(request, call_next))
+
+    def test_body_at_limit_accepted(self):
+        request = _FakeRequest(headers={"content-length": str(MAX_BODY_BYTES)})
+        self.assertEqual(self._run(request).status_code, 200)
+        self.assertFalse(request.body_read)
+
+    def test_declared_length_over_limit_rejected_without_reading(self):
+        request = _FakeRequest(headers={"content-length": str(MAX_BODY_BYTES + 1)})
+        response = self._run(request)
+
+        self.assertEqual(response.status_code, 413)
+        self.assertFalse(request.body_read)
+
+    def test_undeclared_body_measured(self):
+        self.assertEqual(self._run(_FakeRequest(body=b"x" * MAX_BODY_BYTES)).status_code, 200)
+        self.assertEqual(self._run(_FakeRequest(body=b"x" * (MAX_BODY_BYTES + 1))).status_code, 413)
+
+    def test_get_requests_not_checked(self):
+        request = _FakeRequest(method="GET", headers={"content-length": str(MAX_BODY_BYTES + 1)})
+        self.assertEqual(self._run(request).status_code, 200)
+
+    def test_profile_picture_uses_its_own_limit(self):
+        path = "/api/v1/users/usr_1/profile-picture"
+        self.assertEqual(body_limit_for(path), storage.MAX_PROFILE_PICTURE_BYTES)
+        self.assertEqual(body_limit_for("/api/v1/users/usr_1"), MAX_BODY_BYTES)
+
+
+class TestIngestBodyLimit(unittest.TestCase):
+    """Test the ingestion Lambda refuses oversized batches before parsing."""
+
+    def setUp(self):
+        p = patch.object(device_data_ingest, "authenticate_device", return_value={"id": "DEV-1"})
+        p.start()
+        self.addCleanup(p.stop)
+
+    def _event(self, body):
+        return {"headers": {"X-Device-Id": "DEV-1", "X-Api-Key": "k"}, "body": body}
+
+    def test_oversized_body_rejected(self):
+        with patch.object(device_data_ingest, "MAX_INGEST_BODY_BYTES", 64), \
+             patch.object(device_data_ingest.json, "loads") as loads:
+            resp = device_data_ingest.ingest(self._event("x" * 65), None)
+
+        self.assertEqual(resp["statusCode"], 413)
+        self.assertEqual(json.loads(resp["body"])["code"], "PAYLOAD_TOO_LARGE")
+        loads.assert_not_called()
+
+    def test_body_at_limit_parsed(self):
+        body = json.dumps({"readings": []})
+        with patch.object(device_data_ingest, "MAX_INGEST_BODY_BYTES", len(body)):
+            resp = device_data_ingest.ingest(self._event(body), None)
+
+        self.assertEqual(resp["statusCode"], 400)  # past the size check, empty batch rejected
+
+
+if __name__ == "__main__":
+    unittest.main()
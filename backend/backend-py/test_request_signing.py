@@ -0,0 +1,120 @@
+"""
+Tests for HMAC device request signing
+
+Run with: python -m pytest test_request_signing.py -v
+"""
+
+import os
+import json
+import time
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import device_data_ingest
+from device_data_ingest import ingest
+from request_signing import (
+    DeviceSignatureError, HmacRequestVerifier, canonical_request, sign_request, verify_device_request,
+)
+
+SECRET = "0f" * 32
+HARDWARE_ID = "AA:BB:CC:DD:EE:01"
+PATH = "/api/v1/devices/readings/ingest"
+
+
+def _signed_event(body, secret=SECRET, timestamp=None, hardware_id=HARDWARE_ID, path=PATH):
+    timestamp = str(int(time.time()) if timestamp is None else timestamp)
+    return {
+        "httpMethod": "POST",
+        "path": path,
+        "headers": {
+            "X-Device-Hardware-Id": hardware_id,
+            "X-Device-Timestamp": timestamp,
+            "X-Device-Signature": sign_request(secret, "POST", PATH, timestamp, body),
+        },
+        "body": body,
+    }
+
+
+class TestHmacRequestVerifier(unittest.TestCase):
+    """Test signature verification."""
+
+    def setUp(self):
+        db._device_api_keys.clear()
+        db.put_device_api_key(HARDWARE_ID, "DEV-1", SECRET)
+
+    def test_canonical_request(self):
+        self.assertEqual(
+            canonical_request("post", "/p", "1700000000", ""),
+            "POST\n/p\n1700000000\ne3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        )
+
+    def test_valid_signature_returns_device_id(self):
+        self.assertEqual(verify_device_request(_signed_event('{"readings": []}')), "DEV-1")
+
+    def test_tampered_body_rejected(self):
+        event = _signed_event('{"readings": []}')
+        event["body"] = '{"readings": [1]}'
+        with self.assertRaises(DeviceSignatureError) as ctx:
+            verify_device_request(event)
+        self.assertEqual(ctx.exception.code, "DEVICE_SIGNATURE_INVALID")
+
+    def test_wrong_secret_rejected(self):
+        with self.assertRaises(DeviceSignatureError):
+            verify_device_request(_signed_event("{}", secret="other"))
+
+    def test_unknown_hardware_id_rejected(self):
+        with self.assertRaises(DeviceSignatureError):
+            verify_device_request(_signed_event("{}", hardware_id="AA:BB:CC:DD:EE:99"))
+
+    def test_stale_timestamp_rejected(self):
+        with self.assertRaises(DeviceSignatureError) as ctx:
+            verify_device_request(_signed_event("{}", timestamp=int(time.time()) - 301))
+        self.assertEqual(ctx.exception.code, "DEVICE_SIGNATURE_EXPIRED")
+
+    def test_tolerance_is_configurable(self):
+        event = _signed_event("{}", timestamp=int(time.time()) - 301)
+        self.assertEqual(verify_device_request(event, HmacRequestVerifier(tolerance_seconds=600)), "DEV-1")
+
+    def test_missing_headers_rejected(self):
+        event = _signed_event("{}")
+        del event["headers"]["X-Device-Timestamp"]
+        with self.assertRaises(DeviceSignatureError) as ctx:
+            verify_device_request(event)
+        self.assertEqual(ctx.exception.code, "DEVICE_SIGNATURE_MISSING")
+
+
+class TestSignedIngest(unittest.TestCase):
+    """Test HMAC authentication on the ingestion Lambda."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._devices.append({"id": "DEV-1", "patientId": "PAT-1", "macAddress": HARDWARE_ID})
+        db._device_api_keys.clear()
+        db._idempotency.clear()
+        db.put_device_api_key(HARDWARE_ID, "DEV-1", SECRET)
+        patcher = patch.object(device_data_ingest, "_sqs")
+        self.sqs = patcher.start()
+        self.sqs.send_message_batch.return_value = {"Successful": [], "Failed": []}
+        self.addCleanup(patcher.stop)
+
+    def test_signed_request_accepted_without_api_key(self):
+        body = json.dumps({"readings": [{"timestamp": 1735689600, "values": {"accel_x": 0.1}}]})
+        event = _signed_event(body)
+        event["headers"]["Idempotency-Key"] = "upload-1"
+        resp = ingest(event, None)
+        self.assertEqual(resp["statusCode"], 202)
+        entries = self.sqs.send_message_batch.call_args.kwargs["Entries"]
+        self.assertEqual(entries[0]["MessageGroupId"], "DEV-1")
+
+    def test_invalid_signature_rejected(self):
+        resp = ingest(_signed_event(json.dumps({"readings": [{}]}), secret="other"), None)
+        self.assertEqual(resp["statusCode"], 401)
+        self.assertEqual(json.loads(resp["body"])["code"], "DEVICE_SIGNATURE_INVALID")
+        self.sqs.send_message_batch.assert_not_called()
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,109 @@
+"""
+Tests for request ID propagation and audit trace lookup
+
+Run with: python -m pytest test_request_trace.py -v
+"""
+
+import os
+import unittest
+from types import SimpleNamespace
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import request_context
+from audit_service import audit_service, AuditEventType
+from request_context import current_request_id, resolve_request_id
+
+
+def _request(headers=None, event=None):
+    scope = {"aws.event": event} if event else {}
+    return SimpleNamespace(headers=headers or {}, scope=scope)
+
+
+class TestResolveRequestId(unittest.TestCase):
+    """Test where the request ID comes from."""
+
+    def test_api_gateway_id_preferred(self):
+        request = _request({"X-Request-Id": "client-id"}, {"requestContext": {"requestId": "gw-123"}})
+        self.assertEqual(resolve_request_id(request), "gw-123")
+
+    def test_header_used_outside_api_gateway(self):
+        self.assertEqual(resolve_request_id(_request({"X-Request-Id": "client-id"})), "client-id")
+
+    def test_invalid_header_replaced(self):
+        request_id = resolve_request_id(_request({"X-Request-Id": "bad id\n" * 3}))
+        self.assertNotIn(" ", request_id)
+        self.assertEqual(len(request_id), 36)
+
+
+class TestAuditCorrelation(unittest.TestCase):
+    """Test audit events carry the current request ID."""
+
+    def setUp(self):
+        db._audit_logs.clear()
+
+    def test_log_event_uses_current_request_id(self):
+        token = request_context._request_id.set("req-1")
+        try:
+            entry = audit_service.log_event(event_type=AuditEventType.DATA_READ, user_id="usr_1")
+        finally:
+            request_context._request_id.reset(token)
+        self.assertEqual(entry["requestId"], "req-1")
+        self.assertIsNone(current_request_id())
+
+    def test_explicit_request_id_wins(self):
+        token = request_context._request_id.set("req-1")
+        try:
+            entry = audit_service.log_event(event_type=AuditEventType.DATA_READ, request_id="req-2")
+        finally:
+            request_context._request_id.reset(token)
+        self.assertEqual(entry["requestId"], "req-2")
+
+    def test_trace_returns_events_oldest_first(self):
+        first = audit_service.log_event(event_type=AuditEventType.AUTH_LOGIN_SUCCESS, request_id="req-1")
+        audit_service.log_event(event_type=AuditEventType.DATA_READ, request_id="req-other")
+        second = audit_service.log_event(event_type=AuditEventType.DATA_READ, request_id="req-1")
+
+        trace = db.get_audit_logs_by_request_id("req-1")
+        self.assertEqual([e["logId"] for e in trace], [first["logId"], second["logId"]])
+
+    def test_correlation_filter(self):
+        audit_service.log_event(event_type=AuditEventType.DATA_READ, request_id="req-1")
+        audit_service.log_event(event_type=AuditEventType.DATA_READ, request_id="req-2")
+
+        page = db.get_audit_logs(request_id="req-2")
+        self.assertEqual([e["requestId"] for e in page.items], ["req-2"])
+
+
+class TestAuditTraceDynamo(unittest.TestCase):
+    """Test the DynamoDB requestId-index paths."""
+
+    def setUp(self):
+        self.table = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "T_AUDIT_LOGS", self.table, create=True).start()
+
+    def test_trace_follows_pages(self):
+        self.table.query.side_effect = [
+            {"Items": [{"logId": "a"}], "LastEvaluatedKey": {"pk": "AUDIT#ALL", "sk": "1"}},
+            {"Items": [{"logId": "b"}]},
+        ]
+        trace = db.get_audit_logs_by_request_id("req-1")
+        self.assertEqual([e["logId"] for e in trace], ["a", "b"])
+        self.assertEqual(self.table.query.call_args_list[0].kwargs["IndexName"], "requestId-index")
+        self.assertTrue(self.table.query.call_args_list[0].kwargs["ScanIndexForward"])
+
+    def test_unset_index_keys_not_written(self):
+        db.put_audit_log({"pk": "AUDIT#ALL", "sk": "1", "eventType": "DATA_READ",
+                          "userId": None, "requestId": None, "details": None})
+        item = self.table.put_item.call_args.kwargs["Item"]
+        self.assertNotIn("userId", item)
+        self.assertNotIn("requestId", item)
+        self.assertIn("details", item)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,94 @@
+"""
+Tests for response compression
+
+Run with: python -m pytest test_response_compression.py -v
+"""
+
+import os
+import gzip
+import json
+import base64
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import response_compression
+from response_compression import compress_if_accepted, select_encoding
+from request_signing import event_body
+
+BODY = json.dumps({
+    "items": [{"reportId": f"RPT-{i:04d}", "patientId": "usr_p1", "title": "Weekly summary", "status": "completed"}
+              for i in range(50)],
+    "count": 50,
+}).encode()
+
+
+class TestSelectEncoding(unittest.TestCase):
+    """Test Accept-Encoding negotiation."""
+
+    def test_gzip(self):
+        self.assertEqual(select_encoding("gzip, deflate"), "gzip")
+
+    def test_nothing_supported(self):
+        self.assertEqual(select_encoding(""), "identity")
+        self.assertEqual(select_encoding("deflate"), "identity")
+
+    def test_q_zero_refuses_encoding(self):
+        self.assertEqual(select_encoding("gzip;q=0"), "identity")
+        self.assertEqual(select_encoding("*;q=0"), "identity")
+
+    def test_wildcard(self):
+        self.assertIn(select_encoding("*"), ("br", "gzip"))
+
+    def test_brotli_preferred_when_available(self):
+        with patch.object(response_compression, "brotli", object()):
+            self.assertEqual(select_encoding("gzip, br"), "br")
+            self.assertEqual(select_encoding("gzip;q=1.0, br;q=0.5"), "gzip")
+
+    def test_brotli_not_offered_without_package(self):
+        with patch.object(response_compression, "brotli", None):
+            self.assertEqual(select_encoding("br, gzip;q=0.5"), "gzip")
+            self.assertEqual(select_encoding("br"), "identity")
+
+
+class TestCompressIfAccepted(unittest.TestCase):
+    """Test body compression."""
+
+    def test_gzip_round_trip_is_identical(self):
+        compressed, encoding = compress_if_accepted(BODY, "gzip")
+        self.assertEqual(encoding, "gzip")
+        self.assertLess(len(compressed), len(BODY))
+        self.assertEqual(gzip.decompress(compressed), BODY)
+        self.assertEqual(json.loads(gzip.decompress(compressed)), json.loads(BODY))
+
+    @unittest.skipUnless(response_compression.brotli, "brotli not installed")
+    def test_brotli_round_trip_is_identical(self):
+        compressed, encoding = compress_if_accepted(BODY, "br")
+        self.assertEqual(encoding, "br")
+        self.assertEqual(response_compression.brotli.decompress(compressed), BODY)
+
+    def test_small_body_not_compressed(self):
+        self.assertEqual(compress_if_accepted(b'{"ok": true}', "gzip"), (b'{"ok": true}', "identity"))
+
+    def test_threshold_configurable(self):
+        self.assertEqual(compress_if_accepted(BODY, "gzip", threshold=len(BODY))[1], "identity")
+        with patch.object(response_compression, "COMPRESSION_THRESHOLD_BYTES", 10):
+            self.assertEqual(compress_if_accepted(b'{"ok": true}', "gzip")[1], "gzip")
+
+    def test_not_accepted_returns_original(self):
+        self.assertEqual(compress_if_accepted(BODY, "identity"), (BODY, "identity"))
+
+
+class TestBase64EventBody(unittest.TestCase):
+    """API Gateway passes bodies base64-encoded for binary media types."""
+
+    def test_event_body_decoded(self):
+        event = {"body": base64.b64encode(b'{"readings": []}').decode(), "isBase64Encoded": True}
+        self.assertEqual(event_body(event), '{"readings": []}')
+        self.assertEqual(event_body({"body": "{}"}), "{}")
+        self.assertIsNone(event_body({}))
+
+
+if __name__ == '__main__':
+    unittest.main()
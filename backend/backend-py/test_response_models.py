@@ -0,0 +1,73 @@
+"""
+Tests for response model JSON field names (frontend contract: camelCase)
+
+Run with: python -m pytest test_response_models.py -v
+"""
+
+import os
+import json
+import unittest
+
+os.environ['USE_MEMORY'] = 'true'
+
+from models import DoctorPatientItem, ReportSummary, TremorDataPoint, TremorResponse, UserOut
+
+# DynamoDB attribute names written by the tremor processing Lambda
+TREMOR_ITEM = {
+    "patient_id": "PAT-001", "device_id": "DEV-001", "timestamp": 1731881500,
+    "tremor_index": 0.72, "rms_value": 0.1455, "dominant_frequency": 5.8,
+    "tremor_power": 111.2, "total_power": 2646.9, "is_parkinsonian": True,
+    "signal_quality": 0.96, "low_quality": False,
+}
+
+
+def _fields(model):
+    """Top-level JSON keys as FastAPI serializes a response_model (by alias)"""
+    return set(json.loads(model.model_dump_json(by_alias=True)))
+
+
+class TestResponseFieldNames(unittest.TestCase):
+    """Test summary types serialize with the documented camelCase names."""
+
+    def test_tremor_data_point(self):
+        self.assertEqual(_fields(TremorDataPoint(**TREMOR_ITEM)), {
+            "patientId", "deviceId", "timestamp", "tremorIndex", "rmsValue", "dominantFrequency",
+            "tremorPower", "totalPower", "isParkinsonian", "signalQuality", "lowQuality",
+        })
+
+    def test_tremor_response_nests_camel_case(self):
+        res = TremorResponse(success=True, data=[TREMOR_ITEM], count=1)
+        body = json.loads(res.model_dump_json(by_alias=True))
+        self.assertEqual(body["data"][0]["tremorIndex"], 0.72)
+        self.assertNotIn("tremor_index", body["data"][0])
+
+    def test_doctor_patient_item(self):
+        item = DoctorPatientItem(patient_id="PAT-001", email="p@example.com", assigned_at="2025-11-15T09:00:00Z")
+        self.assertEqual(_fields(item), {"patientId", "email", "name", "assignedAt", "status"})
+
+    def test_report_summary(self):
+        summary = ReportSummary(reportId="RPT-1", patientId="PAT-001", status="final", createdAt="2025-01-01T00:00:00Z")
+        self.assertEqual(_fields(summary), {
+            "reportId", "patientId", "authorId", "type", "title", "status", "createdAt", "visibleToPatient",
+        })
+
+    def test_user_out(self):
+        user = UserOut(id="usr_1", email="p@example.com", role="patient", createdAt="2025-01-01T00:00:00Z")
+        self.assertEqual(_fields(user), {"id", "email", "role", "name", "createdAt", "profilePictureUrl", "preferences"})
+
+
+class TestStoredItemNames(unittest.TestCase):
+    """Test snake_case DynamoDB items still load and keep their attribute names."""
+
+    def test_item_loads_by_attribute_name(self):
+        point = TremorDataPoint(**TREMOR_ITEM)
+        self.assertEqual(point.tremor_index, 0.72)
+        self.assertEqual(point.model_dump(exclude_none=True), TREMOR_ITEM)
+
+    def test_camel_case_input_accepted(self):
+        point = TremorDataPoint(patientId="PAT-001", timestamp=1, tremorIndex=0.5)
+        self.assertEqual((point.patient_id, point.tremor_index), ("PAT-001", 0.5))
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,115 @@
+"""
+Tests for the transient-error retry helper
+
+Run with: python -m pytest test_retry.py -v
+"""
+
+import os
+import unittest
+from unittest.mock import MagicMock, patch
+
+from botocore.exceptions import ClientError, ConnectTimeoutError
+
+import retry
+import storage
+from retry import is_retryable, with_retry, backoff_delay_ms, MAX_DELAY_MS
+
+
+def _client_error(code, status=400):
+    return ClientError({"Error": {"Code": code, "Message": code}, "ResponseMetadata": {"HTTPStatusCode": status}}, "PutItem")
+
+
+class FlakyCall:
+    """Fails with the given errors, then returns "ok"."""
+
+    def __init__(self, *errors):
+        self.errors = list(errors)
+        self.calls = 0
+
+    def __call__(self):
+        self.calls += 1
+        if self.errors:
+            raise self.errors.pop(0)
+        return "ok"
+
+
+class TestIsRetryable(unittest.TestCase):
+    """Test transient error classification."""
+
+    def test_throttling_is_retryable(self):
+        self.assertTrue(is_retryable(_client_error("ProvisionedThroughputExceededException")))
+        self.assertTrue(is_retryable(_client_error("ThrottlingException")))
+
+    def test_conditional_check_failure_is_not_retryable(self):
+        self.assertFalse(is_retryable(_client_error("ConditionalCheckFailedException")))
+        self.assertFalse(is_retryable(_client_error("ValidationException")))
+
+    def test_server_errors_and_timeouts_are_retryable(self):
+        self.assertTrue(is_retryable(_client_error("SomethingUnexpected", status=503)))
+        self.assertTrue(is_retryable(_client_error("InternalError", status=500)))
+        self.assertTrue(is_retryable(ConnectTimeoutError(endpoint_url="https://s3.amazonaws.com")))
+        self.assertFalse(is_retryable(_client_error("AccessDenied", status=403)))
+
+    def test_error_code_in_message(self):
+        self.assertTrue(is_retryable(RuntimeError("An error occurred (ThrottlingException)")))
+        self.assertFalse(is_retryable(ValueError("bad input")))
+
+
+class TestWithRetry(unittest.TestCase):
+    """Test retrying with backoff."""
+
+    def setUp(self):
+        self.sleeps = []
+
+    def test_two_transient_failures_then_success(self):
+        call = FlakyCall(_client_error("ProvisionedThroughputExceededException"),
+                         _client_error("ThrottlingException"))
+        self.assertEqual(with_retry(call, 3, 100, sleep=self.sleeps.append), "ok")
+        self.assertEqual(call.calls, 3)
+        self.assertEqual(len(self.sleeps), 2)
+
+    def test_gives_up_after_max_attempts(self):
+        call = FlakyCall(*[_client_error("ThrottlingException")] * 3)
+        with self.assertRaises(ClientError):
+            with_retry(call, 3, 100, sleep=self.sleeps.append)
+        self.assertEqual(call.calls, 3)
+
+    def test_non_retryable_error_raised_immediately(self):
+        call = FlakyCall(_client_error("ConditionalCheckFailedException"))
+        with self.assertRaises(ClientError):
+            with_retry(call, 3, 100, sleep=self.sleeps.append)
+        self.assertEqual(call.calls, 1)
+        self.assertEqual(self.sleeps, [])
+
+    def test_full_jitter_bounds(self):
+        for attempt in range(10):
+            delay = backoff_delay_ms(attempt, 100)
+            self.assertGreaterEqual(delay, 0)
+            self.assertLessEqual(delay, min(MAX_DELAY_MS, 100 * 2 ** attempt))
+
+
+
+class TestStorageRetry(unittest.TestCase):
+    """S3 writes are retried on transient errors only."""
+
+    def setUp(self):
+        self.s3 = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.s3).start()
+        patch.dict(os.environ, {"S3_BUCKET": "test-bucket"}).start()
+        patch.object(retry, "backoff_delay_ms", return_value=0).start()
+
+    def test_upload_succeeds_after_two_throttled_attempts(self):
+        self.s3.put_object.side_effect = [_client_error("SlowDown", 503), _client_error("SlowDown", 503), {}]
+        storage.upload_bytes("reports/a.pdf", b"%PDF", "application/pdf")
+        self.assertEqual(self.s3.put_object.call_count, 3)
+
+    def test_upload_access_denied_not_retried(self):
+        self.s3.put_object.side_effect = _client_error("AccessDenied", 403)
+        with self.assertRaises(ClientError):
+            storage.upload_bytes("reports/a.pdf", b"%PDF", "application/pdf")
+        self.assertEqual(self.s3.put_object.call_count, 1)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,130 @@
+"""
+Tests for input sanitization helpers
+
+Run with: python -m pytest test_sanitize.py -v
+"""
+
+import unittest
+
+from sanitize import (
+    normalize_text,
+    sanitize_input,
+    sanitize_html,
+    sanitize_identifier,
+    sanitize_filename,
+    sanitize_table_name,
+    InputTooLongError
+)
+
+
+class TestSanitizeInput(unittest.TestCase):
+    """Test HTML entity encoding."""
+
+    def test_apostrophe_preserved_as_entity(self):
+        self.assertEqual(sanitize_input("O'Brien"), "O&#x27;Brien")
+
+    def test_all_special_characters_encoded(self):
+        self.assertEqual(sanitize_input('<a href="x">&</a>'),
+                         "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;")
+
+    def test_plain_text_unchanged(self):
+        self.assertEqual(sanitize_input("Tremor after 2pm"), "Tremor after 2pm")
+
+    def test_max_length_enforced(self):
+        self.assertEqual(sanitize_input("abc", max_length=3), "abc")
+        with self.assertRaises(InputTooLongError):
+            sanitize_input("abcd", max_length=3)
+
+    def test_medical_and_accented_text_intact(self):
+        self.assertEqual(sanitize_input("José µg ±2"), "José µg ±2")
+        self.assertEqual(sanitize_input("李明 · Ørsted · 0.5 mg/kg ≤ 3 °C"), "李明 · Ørsted · 0.5 mg/kg ≤ 3 °C")
+
+    def test_script_injection_neutralized(self):
+        payload = 'José"><script>alert(document.cookie)</script>'
+        encoded = sanitize_input(payload)
+        self.assertNotIn("<", encoded)
+        self.assertNotIn('"', encoded)
+        self.assertEqual(encoded, "José&quot;&gt;&lt;script&gt;alert(document.cookie)&lt;/script&gt;")
+
+    def test_text_context_does_not_encode(self):
+        self.assertEqual(sanitize_input("O'Brien <3", context="text"), "O'Brien <3")
+        with self.assertRaises(ValueError):
+            sanitize_input("x", context="sql")
+
+    def test_length_counts_normalized_characters(self):
+        self.assertEqual(sanitize_input("Jose\u0301", max_length=4), "José")
+
+
+class TestNormalizeText(unittest.TestCase):
+    """Test Unicode normalization and invisible character removal."""
+
+    def test_decomposed_accents_composed(self):
+        self.assertEqual(normalize_text("Jose\u0301"), "José")
+
+    def test_controls_and_bidi_overrides_removed(self):
+        self.assertEqual(normalize_text("abc\x00\x1b[31m"), "abc[31m")
+        self.assertEqual(normalize_text("invoice\u202efdp.exe"), "invoicefdp.exe")
+        self.assertEqual(normalize_text("ad\u200bmin"), "admin")
+
+    def test_line_breaks_and_joiners_kept(self):
+        self.assertEqual(normalize_text("line 1\r\nline 2\tend"), "line 1\r\nline 2\tend")
+        self.assertEqual(normalize_text("\u0645\u06cc\u200c\u062e\u0648\u0627\u0647\u0645"),
+                         "\u0645\u06cc\u200c\u062e\u0648\u0627\u0647\u0645")
+
+
+class TestSanitizeHtml(unittest.TestCase):
+    """Test markup removal."""
+
+    def test_tags_removed_text_kept(self):
+        self.assertEqual(sanitize_html("<b>Hello</b> <i>world</i>"), "Hello world")
+
+    def test_script_content_removed(self):
+        self.assertEqual(sanitize_html("Hi<script>alert('x')</script>!"), "Hi!")
+
+    def test_remaining_text_encoded(self):
+        self.assertEqual(sanitize_html("<p>O'Brien & Sons</p>"), "O&#x27;Brien &amp; Sons")
+
+
+class TestSanitizeIdentifier(unittest.TestCase):
+    """Test strict identifier validation."""
+
+    def test_valid_identifiers(self):
+        self.assertEqual(sanitize_identifier("usr_1a2b3c4d"), "usr_1a2b3c4d")
+        self.assertEqual(sanitize_identifier("dev-7"), "dev-7")
+
+    def test_invalid_identifiers_rejected(self):
+        for value in ["", "usr/../x", "José", "usr 1", "usr_1<", "x" * 129, None]:
+            with self.assertRaises(ValueError):
+                sanitize_identifier(value)
+
+
+class TestSanitizeFilename(unittest.TestCase):
+    """Test filename neutralization."""
+
+    def test_path_separators_and_null_bytes_replaced(self):
+        self.assertEqual(sanitize_filename("../etc/passwd"), ".._etc_passwd")
+        self.assertEqual(sanitize_filename("a\\b\x00c.csv"), "a_b_c.csv")
+
+    def test_dot_segments_replaced(self):
+        self.assertEqual(sanitize_filename(".."), "_")
+        self.assertEqual(sanitize_filename(""), "_")
+
+    def test_unicode_kept(self):
+        self.assertEqual(sanitize_filename("Bericht Müller.pdf"), "Bericht Müller.pdf")
+
+
+class TestSanitizeTableName(unittest.TestCase):
+    """Test DynamoDB table name validation."""
+
+    def test_valid_names(self):
+        self.assertEqual(sanitize_table_name("medusa-users-prod"), "medusa-users-prod")
+        self.assertEqual(sanitize_table_name("Table_1.v2"), "Table_1.v2")
+
+    def test_invalid_names_rejected(self):
+        for name in ["ab", "users; DROP", "users/../x", "", "x" * 256]:
+            with self.assertRaises(ValueError):
+                sanitize_table_name(name)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -5,7 +5,7 @@ This module contains comprehensive tests for all security features:
 - Replay protection (nonce validation)
 - Firmware update verification
 - Audit logging
-- Password validation
+- Password validation and rehashing
 - Request signing
 
 Run with: python -m pytest test_security_features.py -v
@@ -38,9 +38,14 @@ from firmware_service import (
     FirmwareVerificationService,
     FirmwareStatus,
     FirmwareManifest,
-    firmware_service
+    firmware_service,
+    is_valid_firmware_version,
+    is_firmware_downgrade
 )
-from password_validator import PasswordValidator
+from password_validator import PasswordValidator, validate_password_strength
+import jwt
+import auth
+from auth import hash_pw, needs_rehash, ARGON2_MEMORY_COST, ARGON2_TIME_COST, ARGON2_PARALLELISM
 from audit_service import AuditService, AuditEventType, AuditSeverity
 
 
@@ -378,6 +383,32 @@ class TestFirmwareVerificationService(unittest.TestCase):
         self.assertEqual(result.status, FirmwareStatus.VERSION_ROLLBACK)
 
 
+class TestFirmwareVersionValidation(unittest.TestCase):
+    """Test cases for device firmware version validation."""
+    
+    def test_valid_versions(self):
+        """Test that semantic versions are accepted."""
+        for version in ["1.0.0", "0.9.12", "2.10.3-beta.1", "1.2.3+build.7"]:
+            self.assertTrue(is_valid_firmware_version(version), version)
+    
+    def test_invalid_versions_rejected(self):
+        """Test that non-semver strings are rejected."""
+        for version in ["", None, "1", "1.2", "v1.2.3", "1.2.3.4", "01.2.3", "latest"]:
+            self.assertFalse(is_valid_firmware_version(version), repr(version))
+    
+    def test_downgrade_detection(self):
+        """Test that older versions are detected as downgrades."""
+        self.assertTrue(is_firmware_downgrade("1.0.0", "1.1.0"))
+        self.assertTrue(is_firmware_downgrade("1.9.9", "2.0.0"))
+        self.assertFalse(is_firmware_downgrade("1.1.0", "1.0.0"))
+        self.assertFalse(is_firmware_downgrade("1.0.0", "1.0.0"))
+    
+    def test_legacy_current_version_not_downgrade(self):
+        """Test that an unparseable installed version never blocks an update."""
+        self.assertFalse(is_firmware_downgrade("1.0.0", "unknown"))
+        self.assertFalse(is_firmware_downgrade("1.0.0", None))
+
+
 class TestPasswordValidator(unittest.TestCase):
     """Test cases for password validation."""
     
@@ -430,6 +461,136 @@ class TestPasswordValidator(unittest.TestCase):
         self.assertFalse(is_valid, "Empty password should be rejected")
 
 
+class TestPasswordStrength(unittest.TestCase):
+    """Test cases for zxcvbn-based password strength."""
+    
+    def _zxcvbn(self, score, warning="", suggestions=()):
+        return patch("password_validator.zxcvbn", return_value={
+            "score": score, "feedback": {"warning": warning, "suggestions": list(suggestions)}
+        })
+    
+    def test_common_password_rejected(self):
+        """Test that a password passing the character rules can still be too guessable."""
+        result = validate_password_strength("Password1!", ["jane@example.com"], "patient")
+        self.assertFalse(result.is_valid)
+        self.assertTrue(result.errors)
+    
+    def test_long_passphrase_accepted_for_staff(self):
+        result = validate_password_strength("Lantern-Quasar-Orchid-84!", ["dr.smith@example.com"], "doctor")
+        self.assertTrue(result.is_valid, result.errors)
+        self.assertEqual(result.errors, [])
+    
+    def test_user_context_passed_to_zxcvbn(self):
+        """Test that name and email (and its local part) are penalized."""
+        with self._zxcvbn(4) as zxcvbn:
+            validate_password_strength("Whatever-42!", ["jane.doe@example.com", "Jane Doe"])
+        user_inputs = zxcvbn.call_args.kwargs["user_inputs"]
+        for term in ["jane.doe@example.com", "jane.doe", "jane", "doe"]:
+            self.assertIn(term, user_inputs)
+    
+    def test_score_threshold_depends_on_role(self):
+        """Test that score 2 is enough for patients but not for medical staff."""
+        with self._zxcvbn(2, "Short keyboard patterns are easy to guess", ["Use a longer keyboard pattern"]):
+            self.assertTrue(validate_password_strength("Qwer!2345tyu", [], "patient").is_valid)
+            for role in ["doctor", "admin"]:
+                result = validate_password_strength("Qwer!2345tyu", [], role)
+                self.assertFalse(result.is_valid)
+                self.assertEqual(result.score, 2)
+                self.assertEqual(result.errors, [
+                    "Short keyboard patterns are easy to guess", "Use a longer keyboard pattern"
+                ])
+    
+    def test_character_rules_checked_first(self):
+        with self._zxcvbn(4) as zxcvbn:
+            result = validate_password_strength("alllowercase-but-long", [], "patient")
+        self.assertFalse(result.is_valid)
+        self.assertIn("uppercase", result.message)
+        zxcvbn.assert_not_called()
+    
+    def test_missing_feedback_gets_generic_error(self):
+        with self._zxcvbn(1):
+            result = validate_password_strength("Abcdefg1!", [], "patient")
+        self.assertEqual(result.errors, ["Password is too easy to guess"])
+
+
+class TestPasswordRehash(unittest.TestCase):
+    """Test cases for detecting outdated password hashes."""
+    
+    def _hash_with(self, memory_cost, time_cost, parallelism):
+        return f"$argon2id$v=19$m={memory_cost},t={time_cost},p={parallelism}$c2FsdHNhbHQ$aGFzaGhhc2g"
+    
+    def test_current_hash_not_flagged(self):
+        """Test that a hash made with the current parameters is kept."""
+        self.assertFalse(needs_rehash(hash_pw("SecurePass123!")))
+    
+    def test_lower_time_cost_flagged(self):
+        """Test that a hash with a lower time cost needs rehashing."""
+        hashed = self._hash_with(ARGON2_MEMORY_COST, ARGON2_TIME_COST - 1, ARGON2_PARALLELISM)
+        self.assertTrue(needs_rehash(hashed))
+    
+    def test_lower_memory_cost_flagged(self):
+        """Test that a hash with a lower memory cost needs rehashing."""
+        hashed = self._hash_with(ARGON2_MEMORY_COST // 2, ARGON2_TIME_COST, ARGON2_PARALLELISM)
+        self.assertTrue(needs_rehash(hashed))
+    
+    def test_stronger_hash_not_flagged(self):
+        """Test that a hash stronger than the config is not downgraded."""
+        hashed = self._hash_with(ARGON2_MEMORY_COST * 2, ARGON2_TIME_COST + 1, ARGON2_PARALLELISM)
+        self.assertFalse(needs_rehash(hashed))
+    
+    def test_non_argon2id_hash_flagged(self):
+        """Test that hashes from other algorithms are flagged."""
+        self.assertTrue(needs_rehash("$argon2i$v=19$m=65536,t=3,p=4$c2FsdA$aGFzaA"))
+        self.assertTrue(needs_rehash(""))
+
+
+class TestPerRoleTokenExpiry(unittest.TestCase):
+    """Test cases for role-scoped access and refresh token lifetimes."""
+    
+    def setUp(self):
+        patch.dict(auth.JWT_EXPIRE_SECONDS_BY_ROLE, {"patient": 900, "admin": 7200}, clear=True).start()
+        patch.dict(auth.REFRESH_TTL_SECONDS_BY_ROLE, {"admin": 8 * 3600}, clear=True).start()
+        self.addCleanup(patch.stopall)
+    
+    def _claims(self, token):
+        return jwt.decode(token, os.environ['JWT_SECRET'], algorithms=["HS256"],
+                          issuer=auth.JWT_ISSUER, audience=auth.JWT_AUDIENCE)
+    
+    def test_admin_and_patient_tokens_have_different_exp(self):
+        """Test that access token exp follows the per-role config."""
+        before = int(time.time())
+        admin = auth.issue_tokens("usr_admin", "admin")
+        patient = auth.issue_tokens("usr_patient", "patient")
+        
+        self.assertEqual(admin["expiresIn"], 7200)
+        self.assertEqual(patient["expiresIn"], 900)
+        self.assertAlmostEqual(self._claims(admin["accessJwt"])["exp"] - before, 7200, delta=2)
+        self.assertAlmostEqual(self._claims(patient["accessJwt"])["exp"] - before, 900, delta=2)
+    
+    def test_refresh_lifetime_is_role_scoped(self):
+        """Test that refresh token exp follows the per-role config with a global fallback."""
+        before = int(time.time())
+        admin = auth.issue_tokens("usr_admin", "admin")
+        patient = auth.issue_tokens("usr_patient", "patient")
+        
+        self.assertAlmostEqual(self._claims(admin["refreshToken"])["exp"] - before, 8 * 3600, delta=2)
+        self.assertAlmostEqual(self._claims(patient["refreshToken"])["exp"] - before, auth.REFRESH_TTL_SECONDS, delta=2)
+        self.assertEqual(admin["refreshExpiresAt"], self._claims(admin["refreshToken"])["exp"])
+    
+    def test_unconfigured_role_uses_global_default(self):
+        """Test that roles without an override get JWT_EXPIRE_SECONDS."""
+        self.assertEqual(auth.access_ttl_for("doctor"), auth.JWT_EXPIRE_SECONDS)
+        self.assertEqual(auth.access_ttl_for(None), auth.JWT_EXPIRE_SECONDS)
+        self.assertEqual(auth.access_ttl_for("PATIENT"), 900)
+    
+    def test_env_overrides_parsed(self):
+        """Test that JWT_EXPIRE_SECONDS_<ROLE> variables are collected per role."""
+        with patch.dict(os.environ, {"JWT_EXPIRE_SECONDS_TECHNICIAN": "28800", "JWT_EXPIRE_SECONDS_DOCTOR": ""}):
+            overrides = auth._per_role_seconds("JWT_EXPIRE_SECONDS")
+        self.assertEqual(overrides.get("technician"), 28800)
+        self.assertNotIn("doctor", overrides)
+
+
 class TestAuditService(unittest.TestCase):
     """Test cases for audit logging service."""
     
@@ -450,7 +611,7 @@ class TestAuditService(unittest.TestCase):
         self.assertEqual(event["log_type"], "AUDIT")
         self.assertIn("timestamp", event)
         self.assertIn("event_type", event)
-        self.assertIn("event_hash", event)
+        self.assertIn("entry_hash", event)
     
     def test_pii_masking_password(self):
         """Test that passwords are fully masked."""
@@ -506,11 +667,12 @@ class TestAuditService(unittest.TestCase):
             user_id="usr_002"
         )
         
-        # Hashes should be different (due to chaining)
+        # Hashes should be different and linked (due to chaining)
         self.assertNotEqual(
-            event1["event_hash"],
-            event2["event_hash"]
+            event1["entry_hash"],
+            event2["entry_hash"]
         )
+        self.assertEqual(event2["prev_hash"], event1["entry_hash"])
 
 
 class TestSecurityIntegration(unittest.TestCase):
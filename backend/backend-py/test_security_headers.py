@@ -0,0 +1,97 @@
+"""
+Tests for security response headers
+
+Run with: python -m pytest test_security_headers.py -v
+"""
+
+import os
+import json
+import asyncio
+import unittest
+from types import SimpleNamespace
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+import device_data_ingest
+import security_headers
+from device_data_ingest import hash_device_api_key
+from security_headers import add_security_headers, security_headers_middleware
+
+EXPECTED = {
+    "Strict-Transport-Security": "max-age=31536000; includeSubDomains",
+    "X-Content-Type-Options": "nosniff",
+    "X-Frame-Options": "DENY",
+    "Content-Security-Policy": "default-src 'none'; frame-ancestors 'none'; base-uri 'none'; form-action 'none'",
+    "Referrer-Policy": "no-referrer",
+}
+
+
+class TestSecurityHeadersMiddleware(unittest.TestCase):
+    """Test the FastAPI middleware on success and error responses."""
+
+    def _run(self, status_code, headers=None):
+        async def call_next(request):
+            return SimpleNamespace(status_code=status_code, headers=dict(headers or {}))
+        return asyncio.run(security_headers_middleware(SimpleNamespace(), call_next))
+
+    def test_success_response_has_headers(self):
+        response = self._run(200, {"content-type": "application/json"})
+        for name, value in EXPECTED.items():
+            self.assertEqual(response.headers[name], value)
+        self.assertEqual(response.headers["content-type"], "application/json")
+
+    def test_error_response_has_headers(self):
+        for status_code in (400, 401, 413, 500):
+            response = self._run(status_code)
+            for name, value in EXPECTED.items():
+                self.assertEqual(response.headers[name], value)
+
+    def test_handler_header_not_overridden(self):
+        response = self._run(200, {"Content-Security-Policy": "default-src 'self'"})
+        self.assertEqual(response.headers["Content-Security-Policy"], "default-src 'self'")
+
+    def test_disabled_for_local_development(self):
+        with patch.object(security_headers, "SECURITY_HEADERS_ENABLED", False):
+            response = self._run(200)
+        self.assertEqual(response.headers, {})
+
+
+class TestLambdaSecurityHeaders(unittest.TestCase):
+    """Test the ingestion Lambda's proxy responses carry the headers."""
+
+    def setUp(self):
+        db._devices.clear()
+        db._idempotency.clear()
+        db._devices.append({"id": "DEV-1", "patientId": "PAT-1", "apiKeyHash": hash_device_api_key("key")})
+        patcher = patch.object(device_data_ingest, "_sqs")
+        self.sqs = patcher.start()
+        self.sqs.send_message_batch.return_value = {"Successful": [], "Failed": []}
+        self.addCleanup(patcher.stop)
+
+    def _ingest(self, api_key):
+        headers = {"X-Device-Id": "DEV-1", "X-Api-Key": api_key, "Idempotency-Key": "upload-1"}
+        readings = [{"timestamp": 1735689600, "readingType": "heart_rate", "values": {"bpm": 70}}]
+        return device_data_ingest.ingest({"headers": headers, "body": json.dumps({"readings": readings})}, None)
+
+    def test_accepted_response_has_headers(self):
+        resp = self._ingest("key")
+        self.assertEqual(resp["statusCode"], 202)
+        for name, value in EXPECTED.items():
+            self.assertEqual(resp["headers"][name], value)
+        self.assertEqual(resp["headers"]["Content-Type"], "application/json")
+
+    def test_error_response_has_headers(self):
+        resp = self._ingest("wrong")
+        self.assertEqual(resp["statusCode"], 401)
+        for name, value in EXPECTED.items():
+            self.assertEqual(resp["headers"][name], value)
+
+    def test_add_security_headers_disabled(self):
+        response = {"statusCode": 200, "headers": {"Content-Type": "application/json"}, "body": "{}"}
+        self.assertEqual(add_security_headers(response, enabled=False), response)
+
+
+if __name__ == '__main__':
+    unittest.main()
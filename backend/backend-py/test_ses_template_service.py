@@ -0,0 +1,105 @@
+"""
+Tests for SES email templates
+
+Run with: python -m pytest test_ses_template_service.py -v
+"""
+
+import json
+import unittest
+from unittest.mock import MagicMock
+
+from botocore.exceptions import ClientError
+
+from ses_template_service import (
+    SesTemplateService, PasswordResetTemplate, EmailVerificationTemplate,
+    CriticalAlertTemplate, ALL_TEMPLATES, _PLACEHOLDER
+)
+
+
+def _already_exists():
+    return ClientError({"Error": {"Code": "AlreadyExists", "Message": "exists"}}, "CreateTemplate")
+
+
+class TestTemplates(unittest.TestCase):
+    """Test template definitions and local rendering."""
+
+    def test_placeholders_match_fields(self):
+        for template in ALL_TEMPLATES:
+            fields = set(template.__dataclass_fields__)
+            definition = template.definition()
+            used = set()
+            for part in ("SubjectPart", "HtmlPart", "TextPart"):
+                used |= set(_PLACEHOLDER.findall(definition[part]))
+            self.assertEqual(used - fields, set(), template.__name__)
+
+    def test_render_password_reset(self):
+        rendered = PasswordResetTemplate(
+            reset_url="https://app.medusa/reset?t=abc", user_name="Ann", expires_in_minutes=15
+        ).render()
+        self.assertIn('href="https://app.medusa/reset?t=abc"', rendered["html"])
+        self.assertIn("15 minutes", rendered["text"])
+        self.assertNotIn("{{", rendered["html"])
+
+    def test_template_data_is_strings(self):
+        data = PasswordResetTemplate("https://x", "Ann", 30).template_data()
+        self.assertEqual(data, {"reset_url": "https://x", "user_name": "Ann", "expires_in_minutes": "30"})
+
+    def test_critical_alert_subject(self):
+        rendered = CriticalAlertTemplate("Ann Lee", "heart_rate", "170 bpm", "150 bpm").render()
+        self.assertEqual(rendered["subject"], "Critical alert: Ann Lee (heart_rate)")
+
+    def test_html_values_encoded(self):
+        rendered = EmailVerificationTemplate("https://x", "O'Brien <b>").render()
+        self.assertIn("O&#x27;Brien &lt;b&gt;", rendered["html"])
+        self.assertIn("O'Brien <b>", rendered["text"])
+
+
+class TestSesTemplateService(unittest.TestCase):
+    """Test registration and sending with a mocked SES client."""
+
+    def setUp(self):
+        self.service = SesTemplateService()
+        self.ses = MagicMock()
+        self.service._ses = self.ses
+
+    def test_register_creates_all_templates(self):
+        results = self.service.register_templates()
+        self.assertEqual(self.ses.create_template.call_count, len(ALL_TEMPLATES))
+        self.assertEqual(set(results.values()), {"created"})
+
+    def test_register_updates_existing(self):
+        self.ses.create_template.side_effect = _already_exists()
+        results = self.service.register_templates()
+        self.assertEqual(self.ses.update_template.call_count, len(ALL_TEMPLATES))
+        self.assertEqual(set(results.values()), {"updated"})
+
+    def test_register_raises_other_errors(self):
+        self.ses.create_template.side_effect = ClientError(
+            {"Error": {"Code": "AccessDenied", "Message": "no"}}, "CreateTemplate")
+        with self.assertRaises(ClientError):
+            self.service.register_templates()
+
+    def test_render_and_send_uses_template(self):
+        self.service.use_ses = True
+        self.ses.send_templated_email.return_value = {"MessageId": "m-1"}
+        template = EmailVerificationTemplate(verification_url="https://v", user_name="Ann")
+
+        self.assertTrue(self.service.render_and_send("ann@example.com", template))
+        kwargs = self.ses.send_templated_email.call_args.kwargs
+        self.assertEqual(kwargs["Template"], template.template_name())
+        self.assertEqual(kwargs["Destination"], {"ToAddresses": ["ann@example.com"]})
+        self.assertEqual(json.loads(kwargs["TemplateData"])["user_name"], "Ann")
+
+    def test_send_failure_returns_false(self):
+        self.service.use_ses = True
+        self.ses.send_templated_email.side_effect = RuntimeError("throttled")
+        self.assertFalse(self.service.render_and_send("a@b.c", EmailVerificationTemplate("https://v", "Ann")))
+
+    def test_dev_mode_does_not_call_ses(self):
+        self.service.use_ses = False
+        self.assertTrue(self.service.render_and_send("a@b.c", EmailVerificationTemplate("https://v", "Ann")))
+        self.ses.send_templated_email.assert_not_called()
+
+
+if __name__ == '__main__':
+    unittest.main()
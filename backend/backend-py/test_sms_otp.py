@@ -0,0 +1,154 @@
+"""
+Tests for SMS one-time codes for password reset
+
+Run with: python -m pytest test_sms_otp.py -v
+"""
+
+import os
+import time
+import asyncio
+import unittest
+from types import SimpleNamespace
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+
+import db
+import main
+from auth import auth_middleware
+from fastapi import HTTPException
+from models import RequestOtpReq, VerifyOtpReq
+
+EMAIL = "jane@example.com"
+
+
+class TestSmsOtp(unittest.TestCase):
+    """Test /auth/request-otp and /auth/verify-otp."""
+
+    def setUp(self):
+        db._users.clear()
+        db._verification_codes.clear()
+        db.put_user({"id": "usr_1", "email": EMAIL, "role": "patient", "phone": "+15550100",
+                     "createdAt": "2025-01-01T00:00:00+00:00"})
+        self.addCleanup(patch.stopall)
+        patch.object(main.crypto_service, "generate_otp", return_value="482913").start()
+        self.send_sms = patch.object(main.notification_service, "send_sms", return_value=True).start()
+        patch.object(main.audit_service, "log_event").start()
+
+    def _verify(self, otp):
+        return main.verify_otp(VerifyOtpReq(email=EMAIL, otp=otp))
+
+    def _assert_invalid(self, otp):
+        with self.assertRaises(HTTPException) as ctx:
+            self._verify(otp)
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_CODE")
+
+    def test_code_sent_and_only_hash_stored(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+
+        phone, message = self.send_sms.call_args.args
+        self.assertEqual(phone, "+15550100")
+        self.assertIn("482913", message)
+        user = db.get_user("usr_1", include_secrets=True)
+        self.assertNotIn("482913", user["otpHash"])
+        self.assertGreater(user["otpExpiresAt"], time.time())
+
+    def test_valid_code_yields_reset_code(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+
+        res = self._verify("482913")
+
+        self.assertTrue(db.verify_and_consume_code(EMAIL, res["verificationCode"], "password_reset"))
+        self.assertNotIn("otpHash", db.get_user("usr_1", include_secrets=True))
+
+    def test_code_is_single_use(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        self._verify("482913")
+
+        self._assert_invalid("482913")
+
+    def test_wrong_code_counts_attempts_and_locks(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        for _ in range(main.OTP_MAX_ATTEMPTS):
+            self._assert_invalid("000000")
+
+        self._assert_invalid("482913")
+
+    def test_resend_does_not_reset_attempts(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        for _ in range(main.OTP_MAX_ATTEMPTS - 1):
+            self._assert_invalid("000000")
+        db.update_user("usr_1", {"otpIssuedAt": int(time.time()) - main.OTP_RESEND_SECONDS})
+        main.request_otp(RequestOtpReq(email=EMAIL))
+
+        self._assert_invalid("000000")
+        self._assert_invalid("482913")
+
+    def test_locked_account_gets_no_new_code(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        for _ in range(main.OTP_MAX_ATTEMPTS):
+            self._assert_invalid("000000")
+        db.update_user("usr_1", {"otpIssuedAt": int(time.time()) - main.OTP_RESEND_SECONDS})
+
+        res = main.request_otp(RequestOtpReq(email=EMAIL))
+
+        self.assertTrue(res["success"])
+        self.assertEqual(self.send_sms.call_count, 1)
+        self._assert_invalid("482913")
+
+    def test_attempts_counted_before_code_checked(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        db.update_user("usr_1", {"otpAttempts": main.OTP_MAX_ATTEMPTS})
+
+        # A guess racing the last counted attempt is rejected even if correct
+        self._assert_invalid("482913")
+
+    def test_expired_code_rejected(self):
+        main.request_otp(RequestOtpReq(email=EMAIL))
+        db.update_user("usr_1", {"otpExpiresAt": int(time.time()) - 1})
+
+        self._assert_invalid("482913")
+
+    def test_unknown_email_and_missing_phone_not_revealed(self):
+        sent = main.request_otp(RequestOtpReq(email=EMAIL))
+        db.update_user("usr_1", {"phone": None})
+        for email in (EMAIL, "nobody@example.com"):
+            self.assertEqual(main.request_otp(RequestOtpReq(email=email)), sent)
+        self.assertEqual(self.send_sms.call_count, 1)
+
+    def test_resend_throttled_without_revealing_account(self):
+        sent = main.request_otp(RequestOtpReq(email=EMAIL))
+
+        self.assertEqual(main.request_otp(RequestOtpReq(email=EMAIL)), sent)
+        self.assertEqual(main.request_otp(RequestOtpReq(email="nobody@example.com")), sent)
+        self.assertEqual(self.send_sms.call_count, 1)
+
+    def test_sms_failure_clears_code(self):
+        self.send_sms.return_value = False
+        with self.assertRaises(HTTPException) as ctx:
+            main.request_otp(RequestOtpReq(email=EMAIL))
+        self.assertEqual(ctx.exception.status_code, 500)
+        self.assertNotIn("otpHash", db.get_user("usr_1", include_secrets=True))
+
+
+class TestOtpRoutesOpen(unittest.TestCase):
+    """Test that logged-out users get through auth_middleware to the OTP routes."""
+
+    def _run(self, path):
+        request = SimpleNamespace(method="POST", url=SimpleNamespace(path=path), headers={}, state=SimpleNamespace())
+
+        async def call_next(req):
+            return SimpleNamespace(status_code=200)
+        return asyncio.run(auth_middleware(request, call_next))
+
+    def test_otp_routes_need_no_bearer_token(self):
+        for path in ("/api/v1/auth/request-otp", "/api/v1/auth/verify-otp"):
+            self.assertEqual(self._run(path).status_code, 200)
+
+    def test_other_routes_still_require_token(self):
+        self.assertEqual(self._run("/api/v1/auth/change-password").status_code, 401)
+
+
+if __name__ == "__main__":
+    unittest.main()
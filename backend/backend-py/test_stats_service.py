@@ -0,0 +1,111 @@
+"""
+Tests for MeDUSA Admin Statistics Service
+
+Run with: python -m pytest test_stats_service.py -v
+"""
+
+import os
+import time
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from request_context import organization_scope
+from stats_service import compute_stats, stats_service, DAY_SECONDS
+
+NOW = 1_750_000_000
+
+
+class TestComputeStats(unittest.TestCase):
+    """Test statistics arithmetic."""
+
+    def setUp(self):
+        self.stats = compute_stats(
+            users=[
+                {"role": "patient"},
+                {"role": "patient", "isActive": False},
+                {"role": "doctor"},
+                {"role": "admin", "isActive": True},
+            ],
+            devices=[
+                {"type": "tremor_sensor", "status": "online"},
+                {"type": "tremor_sensor", "status": "offline"},
+                {"type": "wearable", "status": "active"},
+            ],
+            reading_timestamps=[NOW - 60, NOW - 2 * DAY_SECONDS, NOW - 10 * DAY_SECONDS, NOW - 40 * DAY_SECONDS],
+            failed_logins_24h=3,
+            reports=[{"status": "pending"}, {"status": "pending"}, {"status": "final"}],
+            object_sizes=[100, 250, 50],
+            now=NOW
+        )
+
+    def test_users_by_role(self):
+        self.assertEqual(self.stats["activeUsers"], 3)
+        self.assertEqual(self.stats["activeUsers.patient"], 1)
+        self.assertEqual(self.stats["activeUsers.doctor"], 1)
+        self.assertEqual(self.stats["totalPatients"], 2)
+
+    def test_devices_by_type(self):
+        self.assertEqual(self.stats["totalDevices"], 3)
+        self.assertEqual(self.stats["activeDevices"], 2)
+        self.assertEqual(self.stats["activeDevices.tremor_sensor"], 1)
+        self.assertEqual(self.stats["activeDevices.wearable"], 1)
+
+    def test_reading_windows(self):
+        self.assertEqual(self.stats["readings.24h"], 1)
+        self.assertEqual(self.stats["readings.7d"], 2)
+        self.assertEqual(self.stats["readings.30d"], 3)
+
+    def test_reports_failed_logins_and_storage(self):
+        self.assertEqual(self.stats["reports.pending"], 2)
+        self.assertEqual(self.stats["reports.final"], 1)
+        self.assertEqual(self.stats["failedLogins.24h"], 3)
+        self.assertEqual(self.stats["storageBytes"], 400)
+
+
+class TestStatsCaching(unittest.TestCase):
+    """Test that cached statistics are served without recomputation."""
+
+    def test_cached_value_returned(self):
+        cached = {"activeUsers": 42}
+        with patch("stats_service.cache_service") as cache, patch("stats_service.compute_stats") as compute:
+            cache.get.return_value = cached
+            self.assertEqual(stats_service.get_stats(), cached)
+            compute.assert_not_called()
+
+    def test_cache_keyed_by_organization(self):
+        with patch("stats_service.cache_service") as cache, patch("stats_service.compute_stats", return_value={}), \
+             patch.object(stats_service, "_object_sizes", return_value=[]), organization_scope("org_a"):
+            cache.get.return_value = None
+            stats_service.get_stats()
+        cache.get.assert_called_once_with("admin:stats:org_a")
+        self.assertEqual(cache.set.call_args.args[0], "admin:stats:org_a")
+
+
+class TestStatsScoping(unittest.TestCase):
+    """Test that readings and storage count only the caller's organization."""
+
+    def setUp(self):
+        db._users.clear()
+        db._tremor_analysis.clear()
+        db.put_user({"id": "usr_a", "email": "a@example.com", "role": "patient", "organizationId": "org_a"})
+        db.put_user({"id": "usr_b", "email": "b@example.com", "role": "patient", "organizationId": "org_b"})
+        for pid in ("usr_a", "usr_b"):
+            db._tremor_analysis.append({"patient_id": pid, "timestamp": int(time.time())})
+        self.addCleanup(db._users.clear)
+        self.addCleanup(db._tremor_analysis.clear)
+
+    def test_readings_and_storage_of_own_patients(self):
+        objects = [{"Key": "poses/usr_a/1.json", "Size": 100}, {"Key": "poses/usr_b/1.json", "Size": 50}]
+        with patch("stats_service.cache_service") as cache, \
+             patch("stats_service.storage.list_objects", return_value=objects), organization_scope("org_a"):
+            cache.get.return_value = None
+            stats = stats_service.get_stats()
+        self.assertEqual(stats["readings.24h"], 1)
+        self.assertEqual(stats["storageBytes"], 100)
+
+
+if __name__ == "__main__":
+    unittest.main()
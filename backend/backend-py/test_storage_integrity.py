@@ -0,0 +1,107 @@
+"""
+Tests for ranged S3 downloads and checksum verification
+
+Run with: python -m pytest test_storage_integrity.py -v
+"""
+
+import os
+import io
+import hashlib
+import unittest
+from unittest.mock import MagicMock, patch
+
+os.environ['USE_MEMORY'] = 'true'
+
+import encryption_service
+import storage
+from storage import ByteRange, StorageIntegrityError
+
+CONTENT = bytes(range(256)) * 4  # 1024 bytes
+
+
+class TestByteRange(unittest.TestCase):
+    """Test the Range header built from start / end bytes."""
+
+    def test_header(self):
+        self.assertEqual(ByteRange(0, 99).header(), "bytes=0-99")
+        self.assertEqual(ByteRange(512).header(), "bytes=512-")
+
+    def test_invalid_ranges_rejected(self):
+        with self.assertRaises(ValueError):
+            ByteRange(-1)
+        with self.assertRaises(ValueError):
+            ByteRange(10, 5)
+
+
+class TestDownloadIntegrity(unittest.TestCase):
+    """Test downloads against the stored checksum and the returned Content-Range."""
+
+    def setUp(self):
+        self.s3 = MagicMock()
+        self.addCleanup(patch.stopall)
+        patch.object(storage, "s3", self.s3).start()
+        patch.dict(os.environ, {"S3_BUCKET": "bucket"}).start()
+        patch.object(encryption_service, "S3_ENCRYPTION_MODE", "sse-s3").start()
+
+    def _stored(self, body, **fields):
+        self.s3.get_object.return_value = {"Body": io.BytesIO(body), **fields}
+
+    def test_upload_stores_sha256(self):
+        storage.upload_bytes("reports/r.pdf", CONTENT, "application/pdf")
+
+        metadata = self.s3.put_object.call_args.kwargs["Metadata"]
+        self.assertEqual(metadata[storage.META_SHA256], hashlib.sha256(CONTENT).hexdigest())
+
+    def test_full_download_verified(self):
+        self._stored(CONTENT, Metadata={storage.META_SHA256: hashlib.sha256(CONTENT).hexdigest()})
+
+        self.assertEqual(storage.download_bytes("reports/r.pdf"), CONTENT)
+
+    def test_checksum_mismatch_rejected(self):
+        corrupted = b"\xff" + CONTENT[1:]
+        self._stored(corrupted, Metadata={storage.META_SHA256: hashlib.sha256(CONTENT).hexdigest()})
+
+        with self.assertRaises(StorageIntegrityError):
+            storage.download_bytes("reports/r.pdf")
+
+    def test_md5_etag_checked_without_sha256(self):
+        self._stored(b"tampered", ETag=f'"{hashlib.md5(CONTENT).hexdigest()}"', ServerSideEncryption="AES256")
+
+        with self.assertRaises(StorageIntegrityError):
+            storage.download_bytes("imports/i.csv")
+
+    def test_kms_and_multipart_etags_not_checked(self):
+        self._stored(b"data", ETag='"0123456789abcdef0123456789abcdef"', ServerSideEncryption="aws:kms")
+        self.assertEqual(storage.download_bytes("imports/i.csv"), b"data")
+
+        self._stored(b"data", ETag='"0123456789abcdef0123456789abcdef-3"')
+        self.assertEqual(storage.download_bytes("imports/i.csv"), b"data")
+
+    def test_range_download(self):
+        self._stored(CONTENT[100:200], ContentRange="bytes 100-199/1024")
+
+        body = storage.download_bytes("reports/r.pdf", byte_range=ByteRange(100, 199))
+
+        self.assertEqual(body, CONTENT[100:200])
+        self.assertEqual(self.s3.get_object.call_args.kwargs["Range"], "bytes=100-199")
+
+    def test_open_range_past_end(self):
+        self._stored(CONTENT[1000:], ContentRange="bytes 1000-1023/1024")
+
+        self.assertEqual(storage.download_bytes("reports/r.pdf", byte_range=ByteRange(1000, 5000)), CONTENT[1000:])
+
+    def test_truncated_range_rejected(self):
+        self._stored(CONTENT[100:150], ContentRange="bytes 100-199/1024")
+
+        with self.assertRaises(StorageIntegrityError):
+            storage.download_bytes("reports/r.pdf", byte_range=ByteRange(100, 199))
+
+    def test_wrong_range_rejected(self):
+        self._stored(CONTENT[:100], ContentRange="bytes 0-99/1024")
+
+        with self.assertRaises(StorageIntegrityError):
+            storage.download_bytes("reports/r.pdf", byte_range=ByteRange(100, 199))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,119 @@
+"""
+Tests for MeDUSA Patient Timeline Service
+
+Run with: python -m pytest test_timeline_service.py -v
+"""
+
+import os
+import asyncio
+import unittest
+from datetime import datetime, timezone, timedelta
+
+os.environ['USE_MEMORY'] = 'true'
+
+import db
+from device_assignment_service import assign_device_to_patient, unassign_device
+from timeline_service import (
+    reading_events,
+    medication_events,
+    audit_events,
+    merge_timeline,
+    get_patient_timeline
+)
+
+
+class TestTimelineEvents(unittest.TestCase):
+    """Test conversion of source records into timeline events."""
+
+    def test_reading_unix_timestamp(self):
+        events = list(reading_events([{"timestamp": 1735689600, "tremor_index": 0.2}]))
+        self.assertEqual(events[0]["type"], "reading")
+        self.assertEqual(events[0]["at"], datetime(2025, 1, 1, tzinfo=timezone.utc))
+
+    def test_medication_prescribed_and_discontinued(self):
+        medication = {
+            "medicationId": "MED-1",
+            "createdAt": "2025-01-01T00:00:00+00:00",
+            "updatedAt": "2025-02-01T00:00:00+00:00",
+            "discontinuedAt": "2025-02-01T00:00:00+00:00",
+        }
+        actions = [e["action"] for e in medication_events([medication])]
+        self.assertEqual(actions, ["prescribed", "discontinued"])
+
+    def test_medication_updated(self):
+        medication = {"createdAt": "2025-01-01T00:00:00+00:00", "updatedAt": "2025-01-05T00:00:00+00:00"}
+        actions = [e["action"] for e in medication_events([medication])]
+        self.assertEqual(actions, ["prescribed", "updated"])
+
+    def test_device_bind_becomes_assignment(self):
+        log = {"eventType": "DEVICE_BIND", "resourceId": "DEV-1", "timestamp": "2025-01-01T00:00:00+00:00"}
+        event = next(audit_events([log]))
+        self.assertEqual(event["type"], "device_assignment")
+        self.assertEqual(event["action"], "bind")
+        self.assertEqual(event["data"], {"deviceId": "DEV-1"})
+
+    def test_other_audit_entries_are_summarized(self):
+        log = {"eventType": "AUTH_LOGIN_SUCCESS", "action": "login", "details": {"ip": "x"},
+               "timestamp": "2025-01-01T00:00:00+00:00"}
+        event = next(audit_events([log]))
+        self.assertEqual(event["type"], "audit")
+        self.assertNotIn("details", event["data"])
+
+
+class TestMergeTimeline(unittest.TestCase):
+    """Test merging, range filtering and ordering."""
+
+    def setUp(self):
+        self.base = datetime(2025, 1, 10, tzinfo=timezone.utc)
+
+    def _event(self, days):
+        return {"type": "reading", "at": self.base + timedelta(days=days), "action": None, "data": {}}
+
+    def test_sorted_newest_first(self):
+        merged = merge_timeline(iter([self._event(1), self._event(3), self._event(2)]),
+                                self.base, self.base + timedelta(days=5), 10)
+        self.assertEqual([e["timestamp"][:10] for e in merged], ["2025-01-13", "2025-01-12", "2025-01-11"])
+
+    def test_out_of_range_and_untimed_dropped(self):
+        untimed = {"type": "audit", "at": None, "action": None, "data": {}}
+        merged = merge_timeline(iter([self._event(-1), self._event(1), untimed]),
+                                self.base, self.base + timedelta(days=5), 10)
+        self.assertEqual(len(merged), 1)
+
+    def test_limit_applied(self):
+        events = iter([self._event(i) for i in range(5)])
+        self.assertEqual(len(merge_timeline(events, self.base, self.base + timedelta(days=5), 2)), 2)
+
+
+class TestGetPatientTimeline(unittest.TestCase):
+    """Test the combined timeline in memory mode."""
+
+    def setUp(self):
+        db._medications.clear()
+        db._audit_logs.clear()
+
+    def test_includes_medications(self):
+        db.create_medication("PAT-T", {"name": "Levodopa", "dosage": "100 mg", "frequency": "daily"})
+        now = datetime.now(timezone.utc)
+        events = asyncio.run(get_patient_timeline("PAT-T", now - timedelta(days=1), now + timedelta(minutes=1)))
+        self.assertEqual([e["type"] for e in events], ["medication_change"])
+
+    def test_includes_assignments_made_by_doctor(self):
+        db._devices.append({"id": "DEV-T", "name": "Wristband", "patientId": None})
+        self.addCleanup(db._devices.remove, db._devices[-1])
+        for pid in ("PAT-T", "PAT-U"):
+            db._users[pid] = {"id": pid, "role": "patient", "email": f"{pid}@example.com"}
+            self.addCleanup(db._users.pop, pid)
+        now = datetime.now(timezone.utc)
+        assign_device_to_patient("DEV-T", "PAT-T", "usr_doc", "doctor")
+        assign_device_to_patient("DEV-T", "PAT-U", "usr_doc", "doctor", force=True)
+        unassign_device("DEV-T", "usr_admin", "admin")
+
+        events = asyncio.run(get_patient_timeline("PAT-T", now - timedelta(minutes=1), now + timedelta(minutes=1)))
+        self.assertEqual(sorted(e["action"] for e in events if e["type"] == "device_assignment"), ["bind", "unbind"])
+        events = asyncio.run(get_patient_timeline("PAT-U", now - timedelta(minutes=1), now + timedelta(minutes=1)))
+        self.assertEqual(sorted(e["action"] for e in events if e["type"] == "device_assignment"), ["bind", "unbind"])
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,118 @@
+"""
+Tests for JWT validation (issuer, audience, expiry, not-before, issued-at
+and clock-skew leeway)
+
+Run with: python -m pytest test_token_validation.py -v
+"""
+
+import os
+import time
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+
+import jwt
+from fastapi import HTTPException
+
+import auth
+from auth import JWT_AUDIENCE, JWT_ISSUER, JWT_SECRET, decode_refresh_token, issue_tokens, verify_jwt, verify_temp_token
+
+LEEWAY = 30
+
+
+def _token(**claims):
+    claims = {"sub": "USR-1", "role": "patient", "iss": JWT_ISSUER, "aud": JWT_AUDIENCE, **claims}
+    return jwt.encode({k: v for k, v in claims.items() if v is not None}, JWT_SECRET, algorithm="HS256")
+
+
+@patch.object(auth, "JWT_LEEWAY_SECONDS", LEEWAY)
+class TestTokenLeeway(unittest.TestCase):
+    """exp and iat are checked with the same leeway."""
+
+    def test_issued_tokens_carry_iat(self):
+        tokens = issue_tokens("USR-1", "patient")
+        self.assertLessEqual(verify_jwt(tokens["accessJwt"])["iat"], time.time())
+        self.assertIn("iat", decode_refresh_token(tokens["refreshToken"]))
+
+    def test_expired_just_inside_leeway_accepted(self):
+        now = int(time.time())
+        self.assertEqual(verify_jwt(_token(exp=now - LEEWAY + 5))["sub"], "USR-1")
+
+    def test_expired_just_outside_leeway_rejected(self):
+        now = int(time.time())
+        with self.assertRaises(HTTPException) as ctx:
+            verify_jwt(_token(exp=now - LEEWAY - 5))
+        self.assertEqual(ctx.exception.detail["code"], "AUTH_EXPIRED")
+
+    def test_future_iat_just_inside_leeway_accepted(self):
+        now = int(time.time())
+        self.assertEqual(verify_jwt(_token(iat=now + LEEWAY - 5, exp=now + 3600))["sub"], "USR-1")
+
+    def test_future_iat_just_outside_leeway_rejected(self):
+        now = int(time.time())
+        with self.assertRaises(HTTPException) as ctx:
+            verify_jwt(_token(iat=now + LEEWAY + 5, exp=now + 3600))
+        self.assertEqual(ctx.exception.detail["code"], "AUTH_INVALID")
+
+    def test_token_without_iat_accepted(self):
+        self.assertEqual(verify_jwt(_token(exp=int(time.time()) + 60))["sub"], "USR-1")
+
+    def test_refresh_token_uses_same_leeway(self):
+        now = int(time.time())
+        self.assertIsNotNone(decode_refresh_token(_token(typ="refresh", exp=now - LEEWAY + 5)))
+        self.assertIsNone(decode_refresh_token(_token(typ="refresh", exp=now - LEEWAY - 5)))
+
+    def test_zero_leeway(self):
+        with patch.object(auth, "JWT_LEEWAY_SECONDS", 0):
+            with self.assertRaises(HTTPException):
+                verify_jwt(_token(exp=int(time.time()) - 5))
+
+
+class TestIssuerAndAudience(unittest.TestCase):
+    """Tokens must be minted for this environment."""
+
+    def test_issued_tokens_carry_iss_aud_nbf(self):
+        claims = verify_jwt(issue_tokens("USR-1", "patient")["accessJwt"])
+        self.assertEqual(claims["iss"], JWT_ISSUER)
+        self.assertEqual(claims["aud"], JWT_AUDIENCE)
+        self.assertEqual(claims["nbf"], claims["iat"])
+
+    def test_correct_issuer_and_audience_accepted(self):
+        self.assertEqual(verify_jwt(_token(exp=int(time.time()) + 60))["sub"], "USR-1")
+
+    def test_wrong_audience_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            verify_jwt(_token(aud="medusa-staging", exp=int(time.time()) + 60))
+        self.assertEqual(ctx.exception.detail["code"], "AUTH_INVALID")
+
+    def test_wrong_issuer_rejected(self):
+        with self.assertRaises(HTTPException):
+            verify_jwt(_token(iss="medusa-api-staging", exp=int(time.time()) + 60))
+
+    def test_missing_issuer_or_audience_rejected(self):
+        for claim in ("iss", "aud"):
+            with self.assertRaises(HTTPException):
+                verify_jwt(_token(**{claim: None}, exp=int(time.time()) + 60))
+
+    def test_token_from_other_environment_rejected(self):
+        with patch.object(auth, "JWT_AUDIENCE", "medusa-staging"):
+            token = issue_tokens("USR-1", "patient")["accessJwt"]
+        with self.assertRaises(HTTPException):
+            verify_jwt(token)
+
+    def test_not_before_in_future_rejected(self):
+        now = int(time.time())
+        with self.assertRaises(HTTPException):
+            verify_jwt(_token(nbf=now + 300, exp=now + 3600))
+
+    def test_mfa_temp_token_checked_too(self):
+        with patch.object(auth, "JWT_ISSUER", "medusa-api-staging"):
+            token = auth.issue_temp_token("USR-1", "patient")
+        with self.assertRaises(HTTPException):
+            verify_temp_token(token)
+
+
+if __name__ == '__main__':
+    unittest.main()
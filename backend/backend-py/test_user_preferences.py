@@ -0,0 +1,105 @@
+"""
+Tests for per-user UI preferences
+
+Run with: python -m pytest test_user_preferences.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import patch
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+from pydantic import ValidationError
+
+import db
+import main
+from fastapi import HTTPException
+from models import UserPreferences
+
+
+class TestUserPreferencesModel(unittest.TestCase):
+    """Test preference validation."""
+
+    def test_defaults(self):
+        prefs = UserPreferences()
+        self.assertEqual((prefs.language, prefs.timezone, prefs.itemsPerPage), ("en-US", "UTC", 20))
+        self.assertEqual(prefs.dashboardWidgets, [])
+
+    def test_language_canonicalized(self):
+        self.assertEqual(UserPreferences(language="de_de").language, "de-DE")
+
+    def test_unsupported_language_rejected(self):
+        with self.assertRaises(ValidationError):
+            UserPreferences(language="xx-YY")
+
+    def test_timezone_must_be_iana(self):
+        self.assertEqual(UserPreferences(timezone="Europe/Berlin").timezone, "Europe/Berlin")
+        for tz in ("Mars/Olympus_Mons", "GMT+25", "../etc/passwd"):
+            with self.assertRaises(ValidationError):
+                UserPreferences(timezone=tz)
+
+    def test_items_per_page_options(self):
+        self.assertEqual(UserPreferences(itemsPerPage=50).itemsPerPage, 50)
+        with self.assertRaises(ValidationError):
+            UserPreferences(itemsPerPage=25)
+
+
+class TestPreferencesEndpoints(unittest.TestCase):
+    """Test GET / PUT /api/v1/users/me/preferences."""
+
+    def setUp(self):
+        db._users.clear()
+        db.put_user({"id": "usr_1", "email": "jane@example.com", "role": "patient",
+                     "password": "hash", "createdAt": "2025-01-01T00:00:00+00:00"})
+
+    def test_get_returns_defaults(self):
+        prefs = asyncio.run(main.get_user_preferences.__wrapped__(fake_request("usr_1")))
+        self.assertEqual(prefs, UserPreferences())
+
+    def test_put_stores_map_on_user(self):
+        body = UserPreferences(language="fr-FR", timezone="Europe/Paris", itemsPerPage=50,
+                               dashboardWidgets=["tremor-trend", "medications"])
+        with patch.object(main.audit_service, "log_event") as audit:
+            asyncio.run(main.update_user_preferences.__wrapped__(body, fake_request("usr_1")))
+
+        stored = db.get_user("usr_1")["preferences"]
+        self.assertEqual(stored["language"], "fr-FR")
+        self.assertEqual(stored["dashboardWidgets"], ["tremor-trend", "medications"])
+        self.assertEqual(asyncio.run(main.get_user_preferences.__wrapped__(fake_request("usr_1"))), body)
+        self.assertIn("timezone", audit.call_args.kwargs["details"]["updated_fields"])
+
+    def test_audit_lists_changed_fields_only(self):
+        db.update_user("usr_1", {"preferences": UserPreferences().model_dump()})
+        with patch.object(main.audit_service, "log_event") as audit:
+            asyncio.run(main.update_user_preferences.__wrapped__(UserPreferences(itemsPerPage=100), fake_request("usr_1")))
+        self.assertEqual(audit.call_args.kwargs["details"]["updated_fields"], ["itemsPerPage"])
+
+    def test_invalid_stored_value_falls_back_to_default(self):
+        db.update_user("usr_1", {"preferences": {"language": "pt-BR", "itemsPerPage": 100}})
+        prefs = asyncio.run(main.get_user_preferences.__wrapped__(fake_request("usr_1")))
+        self.assertEqual((prefs.language, prefs.itemsPerPage), ("en-US", 100))
+
+    def test_unknown_user(self):
+        db._users.clear()
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.get_user_preferences.__wrapped__(fake_request("usr_1")))
+        self.assertEqual(ctx.exception.status_code, 404)
+
+    def test_preferences_in_me_but_not_login(self):
+        db.update_user("usr_1", {"preferences": {"timezone": "Asia/Tokyo"}})
+        self.assertEqual(main.me(fake_request("usr_1")).preferences.timezone, "Asia/Tokyo")
+
+        with patch.object(main, "verify_pw", return_value=True), \
+             patch.object(main, "_start_session", return_value={"accessJwt": "a", "refreshToken": "r", "expiresIn": 3600}):
+            res = main.login(main.LoginReq(email="jane@example.com", password="x"), fake_request("usr_1"))
+        self.assertNotIn("preferences", res.user)
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,181 @@
+"""
+Tests for race-free user registration
+
+Run with: python -m pytest test_user_registration.py -v
+"""
+
+import os
+import sys
+import asyncio
+import unittest
+from unittest.mock import MagicMock, patch
+
+from botocore.exceptions import ClientError
+
+os.environ['USE_MEMORY'] = 'true'
+os.environ.setdefault('JWT_SECRET', 'test-secret-key-for-testing')
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, "test_support"))
+from fakes import fake_request
+
+import db
+import main
+from fastapi import HTTPException
+from models import RegisterReq
+
+
+def _user(uid="usr_1", email="jane@example.com"):
+    return {"id": uid, "email": email, "role": "patient"}
+
+
+class TestCreateUserMemory(unittest.TestCase):
+    """Test the in-memory uniqueness check."""
+
+    def setUp(self):
+        db._users.clear()
+
+    def test_second_user_with_same_email_rejected(self):
+        db.create_user(_user("usr_1"))
+
+        with self.assertRaises(db.EmailTakenError):
+            db.create_user(_user("usr_2"))
+        self.assertEqual(list(db._users), ["usr_1"])
+
+
+class TestCreateUserTransaction(unittest.TestCase):
+    """Test the DynamoDB email guard transaction."""
+
+    def setUp(self):
+        self.ddb = MagicMock()
+        users = MagicMock()
+        users.name = "medusa-users"
+        self.addCleanup(patch.stopall)
+        patch.object(db, "USE_MEMORY", False).start()
+        patch.object(db, "USERS_SINGLE_TABLE", False).start()
+        patch.object(db, "USERS_PK_ATTR", "id").start()
+        patch.object(db, "ddb", self.ddb, create=True).start()
+        patch.object(db, "T_USERS", users, create=True).start()
+
+    def test_user_and_email_guard_written_together(self):
+        db.create_user(_user())
+
+        items = self.ddb.meta.client.transact_write_items.call_args.kwargs["TransactItems"]
+        user_put, guard_put = items[0]["Put"], items[1]["Put"]
+        self.assertEqual(user_put["Item"]["id"], "usr_1")
+        self.assertEqual(guard_put["Item"], {"id": "EMAIL#jane@example.com", "userId": "usr_1"})
+        for put in (user_put, guard_put):
+            self.assertEqual(put["ConditionExpression"], "attribute_not_exists(#pk)")
+            self.assertEqual(put["TableName"], "medusa-users")
+
+    def test_cancelled_transaction_raises_email_taken(self):
+        self.ddb.meta.client.transact_write_items.side_effect = ClientError(
+            {"Error": {"Code": "TransactionCanceledException", "Message": "ConditionalCheckFailed"}},
+            "TransactWriteItems"
+        )
+
+        with self.assertRaises(db.EmailTakenError):
+            db.create_user(_user())
+
+    def test_other_errors_propagate(self):
+        self.ddb.meta.client.transact_write_items.side_effect = ClientError(
+            {"Error": {"Code": "ValidationException", "Message": "bad"}}, "TransactWriteItems"
+        )
+
+        with self.assertRaises(ClientError):
+            db.create_user(_user())
+
+
+class TestRegisterRace(unittest.TestCase):
+    """Test a concurrent registration that slips past the email lookup."""
+
+    def test_lost_race_returns_conflict(self):
+        req = RegisterReq(email="jane@example.com", password="Lantern-Quasar-Orchid-84!", verificationCode="123456")
+        request = fake_request()
+
+        with patch.object(db, "verify_and_consume_code", return_value=True), \
+             patch.object(db, "get_user_by_email", return_value=None), \
+             patch.object(db, "create_user", side_effect=db.EmailTakenError("jane@example.com")):
+            with self.assertRaises(HTTPException) as ctx:
+                main.register(req, request)
+
+        self.assertEqual(ctx.exception.status_code, 409)
+        self.assertEqual(ctx.exception.detail["code"], "EMAIL_TAKEN")
+
+
+class TestSelfRegistrationRoles(unittest.TestCase):
+    """Only SELF_REGISTRATION_ROLES may be self-registered."""
+
+    def setUp(self):
+        db._users.clear()
+        self.request = fake_request()
+        patcher = patch.object(db, "verify_and_consume_code", return_value=True)
+        self.verify = patcher.start()
+        self.addCleanup(patcher.stop)
+
+    def _register(self, role):
+        req = RegisterReq(email="jane@example.com", password="Lantern-Quasar-Orchid-84!",
+                          verificationCode="123456", role=role)
+        return main.register(req, self.request)
+
+    def test_patient_registration_succeeds(self):
+        res = self._register("Patient")
+        self.assertEqual(db.get_user(res.userId)["role"], "patient")
+
+    def test_admin_registration_forbidden(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._register("Admin")
+        self.assertEqual(ctx.exception.status_code, 403)
+        self.assertEqual(ctx.exception.detail["code"], "ADMIN_RESTRICTED")
+        self.assertEqual(db._users, {})
+
+    def test_doctor_registration_forbidden_by_default(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._register("doctor")
+        self.assertEqual(ctx.exception.status_code, 403)
+        self.assertEqual(ctx.exception.detail["code"], "ROLE_RESTRICTED")
+        self.verify.assert_not_called()
+
+    def test_doctor_registration_allowed_when_configured(self):
+        with patch.object(main, "SELF_REGISTRATION_ROLES", ("patient", "doctor")):
+            res = self._register("doctor")
+        self.assertEqual(db.get_user(res.userId)["role"], "doctor")
+
+    def test_admin_never_self_registered(self):
+        with patch.object(main, "SELF_REGISTRATION_ROLES", ("patient", "admin")):
+            with self.assertRaises(HTTPException) as ctx:
+                self._register("admin")
+        self.assertEqual(ctx.exception.status_code, 403)
+
+    def test_unknown_role_rejected(self):
+        with self.assertRaises(HTTPException) as ctx:
+            self._register("superuser")
+        self.assertEqual(ctx.exception.status_code, 400)
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_ROLE")
+        self.assertIn("patient, doctor, admin", ctx.exception.detail["message"])
+
+
+class TestAdminCreatesPrivilegedUser(unittest.TestCase):
+    """Admins create accounts for roles closed to self-registration."""
+
+    def setUp(self):
+        db._users.clear()
+
+    def test_admin_creates_doctor(self):
+        request = fake_request("usr_admin", "admin")
+        req = main.CreateAdminReq(email="dr.who@example.com", password="Lantern-Quasar-Orchid-84!", role="doctor")
+        with patch.object(main.email_service, "send_welcome_with_mfa") as welcome:
+            res = asyncio.run(main.create_admin_user.__wrapped__(req, request))
+
+        self.assertEqual(res.role, "doctor")
+        self.assertEqual(db.get_user(res.userId)["role"], "doctor")
+        self.assertEqual(welcome.call_args.args[2], "doctor")
+
+    def test_unknown_role_rejected(self):
+        request = fake_request("usr_admin", "admin")
+        req = main.CreateAdminReq(email="x@example.com", password="Lantern-Quasar-Orchid-84!", role="root")
+        with self.assertRaises(HTTPException) as ctx:
+            asyncio.run(main.create_admin_user.__wrapped__(req, request))
+        self.assertEqual(ctx.exception.detail["code"], "INVALID_ROLE")
+
+
+if __name__ == "__main__":
+    unittest.main()
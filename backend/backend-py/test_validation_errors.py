@@ -0,0 +1,63 @@
+"""
+Tests for structured request validation errors
+
+Run with: python -m pytest test_validation_errors.py -v
+"""
+
+import json
+import unittest
+
+from pydantic import ValidationError
+
+from models import PatientProfileUpdateReq, MedicationReq
+from validation_errors import field_errors, validation_error_response
+
+
+def _request_errors(model, **data):
+    """Pydantic errors with the body location prefix FastAPI adds."""
+    try:
+        model(**data)
+    except ValidationError as e:
+        return [{**err, "loc": ("body", *err["loc"])} for err in e.errors()]
+    raise AssertionError("expected validation to fail")
+
+
+class TestFieldErrors(unittest.TestCase):
+    """Test grouping errors by field."""
+
+    def test_two_invalid_fields_listed_separately(self):
+        errors = _request_errors(PatientProfileUpdateReq, heightCm=-5, phone="call me")
+        details = field_errors(errors)
+        self.assertEqual(set(details), {"heightCm", "phone"})
+        self.assertEqual(details["heightCm"], ["heightCm must be positive"])
+        self.assertIn("digits", details["phone"][0])
+
+    def test_nested_field_path(self):
+        errors = _request_errors(PatientProfileUpdateReq, emergencyContact={"name": "Carl", "phone": "x"})
+        self.assertIn("emergencyContact.phone", field_errors(errors))
+
+    def test_model_level_error_reported_under_body(self):
+        errors = _request_errors(MedicationReq, name="L-Dopa", dosage="100 mg", frequency="daily",
+                                 startDate="2025-02-01", endDate="2025-01-01")
+        self.assertEqual(field_errors(errors), {"body": ["endDate must not be before startDate"]})
+
+    def test_query_parameter_location_dropped(self):
+        errors = [{"loc": ("query", "limit"), "msg": "Input should be a valid integer"}]
+        self.assertEqual(field_errors(errors), {"limit": ["Input should be a valid integer"]})
+
+
+class TestValidationErrorResponse(unittest.TestCase):
+    """Test the 422 response body."""
+
+    def test_response_lists_both_fields(self):
+        response = validation_error_response(
+            _request_errors(PatientProfileUpdateReq, heightCm=-5, weightKg=5000)
+        )
+        body = response.body if isinstance(response.body, dict) else json.loads(response.body)
+        self.assertEqual(response.status_code, 422)
+        self.assertEqual(body["detail"]["code"], "VALIDATION_ERROR")
+        self.assertEqual(sorted(body["detail"]["details"]), ["heightCm", "weightKg"])
+
+
+if __name__ == '__main__':
+    unittest.main()
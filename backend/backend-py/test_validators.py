@@ -0,0 +1,193 @@
+"""
+Tests for shared field validators
+
+Run with: python -m pytest test_validators.py -v
+"""
+
+import unittest
+from datetime import date, timedelta
+
+from pydantic import TypeAdapter, ValidationError
+
+from validators import (
+    calculate_age, validate_date_of_birth, validate_height_cm, validate_weight_kg,
+    normalize_phone_e164, normalize_case, MAX_AGE_YEARS, PhoneNumber
+)
+from models import (
+    PatientProfileCreateReq, PatientProfileUpdateReq, EmergencyContact, RegisterReq, DeviceRegisterReq,
+    DeviceUpdateReq, ReadingImportReq, ScheduleFrequency, MaintenanceScheduleReq, DeviceConnectionReq
+)
+from rbac import normalize_role
+
+
+class TestCalculateAge(unittest.TestCase):
+    """Test age calculation."""
+
+    def test_before_and_after_birthday(self):
+        dob = date(1980, 6, 15)
+        self.assertEqual(calculate_age(dob, date(2025, 6, 14)), 44)
+        self.assertEqual(calculate_age(dob, date(2025, 6, 15)), 45)
+
+
+class TestDateOfBirth(unittest.TestCase):
+    """Test date of birth plausibility."""
+
+    def test_future_date_rejected(self):
+        with self.assertRaisesRegex(ValueError, "future"):
+            validate_date_of_birth(date.today() + timedelta(days=2))
+
+    def test_implausibly_old_rejected(self):
+        with self.assertRaisesRegex(ValueError, str(MAX_AGE_YEARS)):
+            validate_date_of_birth(date(date.today().year - MAX_AGE_YEARS - 2, 1, 1))
+
+    def test_plausible_date_accepted(self):
+        self.assertEqual(validate_date_of_birth(date(1950, 1, 1)), date(1950, 1, 1))
+        self.assertIsNone(validate_date_of_birth(None))
+
+
+class TestBodyMeasurements(unittest.TestCase):
+    """Test height and weight ranges."""
+
+    def test_negative_height_rejected(self):
+        with self.assertRaisesRegex(ValueError, "positive"):
+            validate_height_cm(-170.0)
+
+    def test_out_of_range_rejected(self):
+        with self.assertRaises(ValueError):
+            validate_height_cm(400.0)
+        with self.assertRaises(ValueError):
+            validate_weight_kg(1000.0)
+
+    def test_plausible_values_accepted(self):
+        self.assertEqual(validate_height_cm(172.5), 172.5)
+        self.assertEqual(validate_weight_kg(68.0), 68.0)
+
+
+class TestPhoneNormalization(unittest.TestCase):
+    """Test E.164 phone number normalization."""
+
+    def test_formatting_stripped(self):
+        self.assertEqual(normalize_phone_e164("+1 (555) 123-4567"), "+15551234567")
+
+    def test_international_prefix_converted(self):
+        self.assertEqual(normalize_phone_e164("0044 20 7946 0958"), "+442079460958")
+
+    def test_letters_rejected(self):
+        with self.assertRaisesRegex(ValueError, "digits"):
+            normalize_phone_e164("aaaaaaaaaa")
+
+    def test_missing_country_code_rejected(self):
+        with self.assertRaisesRegex(ValueError, "country code"):
+            normalize_phone_e164("555-123-4567")
+
+    def test_invalid_length_rejected(self):
+        with self.assertRaises(ValueError):
+            normalize_phone_e164("+1234")
+        with self.assertRaises(ValueError):
+            normalize_phone_e164("+1234567890123456")
+
+
+class TestPhoneNumberType(unittest.TestCase):
+    """Test the PhoneNumber field type across regions."""
+
+    phone = TypeAdapter(PhoneNumber)
+
+    def test_us_numbers(self):
+        for raw in ("+1 (415) 555-2671", "+1.415.555.2671", "001 415 555 2671"):
+            self.assertEqual(self.phone.validate_python(raw), "+14155552671")
+
+    def test_uk_numbers(self):
+        self.assertEqual(self.phone.validate_python("+44 7911 123456"), "+447911123456")
+        self.assertEqual(self.phone.validate_python("0044 20 7946 0958"), "+442079460958")
+
+    def test_international_numbers(self):
+        self.assertEqual(self.phone.validate_python("+81 3-1234-5678"), "+81312345678")  # Japan
+        self.assertEqual(self.phone.validate_python("+55 11 91234-5678"), "+5511912345678")  # Brazil, 13 digits
+        self.assertEqual(self.phone.validate_python("+49 30 901820"), "+4930901820")  # Germany, 10 digits
+
+    def test_national_format_rejected(self):
+        for raw in ("07911 123456", "(415) 555-2671"):
+            with self.assertRaises(ValidationError):
+                self.phone.validate_python(raw)
+
+    def test_serialized_as_plain_string(self):
+        req = PatientProfileUpdateReq(phone="+44 7911 123456")
+        self.assertEqual(req.model_dump(exclude_unset=True), {"phone": "+447911123456"})
+        self.assertIn('"phone":"+447911123456"', req.model_dump_json())
+
+
+class TestPatientProfileModels(unittest.TestCase):
+    """Test the validators are applied by both create and update requests."""
+
+    def test_create_rejects_future_dob(self):
+        with self.assertRaises(ValidationError):
+            PatientProfileCreateReq(userId="usr_p1", doctorId="usr_d1",
+                                    dateOfBirth=(date.today() + timedelta(days=2)).isoformat())
+
+    def test_update_rejects_negative_height(self):
+        with self.assertRaises(ValidationError):
+            PatientProfileUpdateReq(heightCm=-1)
+
+    def test_phone_numbers_stored_normalized(self):
+        req = PatientProfileUpdateReq(
+            phone="+1 555.123.4567",
+            emergencyContact={"name": "Carl", "phone": "+44 (20) 7946-0958", "relationship": "spouse"}
+        )
+        self.assertEqual(req.phone, "+15551234567")
+        self.assertEqual(req.emergencyContact.phone, "+442079460958")
+
+    def test_emergency_contact_rejects_letters(self):
+        with self.assertRaises(ValidationError):
+            EmergencyContact(name="Carl", phone="call me")
+
+    def test_update_accepts_valid_fields(self):
+        req = PatientProfileUpdateReq(dateOfBirth="1960-03-01", heightCm=165, weightKg=60.5)
+        self.assertEqual(req.dateOfBirth, date(1960, 3, 1))
+
+
+
+class TestCaseInsensitiveEnums(unittest.TestCase):
+    """Test enum-like request fields accept any letter case (JSON round trip)."""
+
+    VARIANTS = ("doctor", "Doctor", "DOCTOR", " dOcToR ")
+
+    def test_normalize_case(self):
+        self.assertEqual(normalize_case(" Admin "), "admin")
+        self.assertIsNone(normalize_case(None))
+        self.assertEqual(normalize_case(3), 3)
+
+    def test_register_role(self):
+        for variant in self.VARIANTS:
+            req = RegisterReq.model_validate_json(
+                f'{{"email": "d@example.com", "password": "x", "verificationCode": "123456", "role": "{variant}"}}'
+            )
+            self.assertEqual(req.role, "doctor")
+            self.assertEqual(RegisterReq.model_validate_json(req.model_dump_json()).role, "doctor")
+
+    def test_device_type_and_status(self):
+        for variant in ("Tremor_Sensor", "TREMOR_SENSOR"):
+            self.assertEqual(DeviceRegisterReq.model_validate_json(
+                f'{{"macAddress": "AA:BB", "name": "d", "type": "{variant}"}}').type, "tremor_sensor")
+        for variant in ("Online", "ONLINE"):
+            self.assertEqual(DeviceUpdateReq.model_validate_json(f'{{"status": "{variant}"}}').status, "online")
+
+    def test_literal_fields(self):
+        self.assertEqual(ReadingImportReq.model_validate_json('{"fileKey": "k", "format": "CSV"}').format, "csv")
+        freq = ScheduleFrequency.model_validate_json('{"type": "Weekly", "weekday": "MONDAY"}')
+        self.assertEqual((freq.type, freq.weekday), ("weekly", "monday"))
+        self.assertEqual(MaintenanceScheduleReq.model_validate_json(
+            '{"maintenanceType": "Software_Update", "scheduledAt": "2025-06-01T00:00:00Z"}').maintenanceType,
+            "software_update")
+        self.assertEqual(DeviceConnectionReq.model_validate_json('{"connectionType": "WiFi"}').connectionType, "wifi")
+
+    def test_unknown_value_still_rejected(self):
+        with self.assertRaises(ValidationError):
+            ReadingImportReq.model_validate_json('{"fileKey": "k", "format": "XLSX"}')
+
+    def test_rbac_role_normalized(self):
+        self.assertEqual(normalize_role("Admin"), "admin")
+        self.assertIsNone(normalize_role(""))
+
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,143 @@
+"""
+MeDUSA Patient Timeline Service
+
+Merges a patient's records from several tables into one chronological view.
+
+Key Features:
+- Readings, medication changes, device assignments and audit events
+- Sources are fetched concurrently and merged newest first
+- Time range and result limit applied across all sources
+"""
+
+import asyncio
+from datetime import datetime, timezone
+from itertools import chain
+from typing import Any, Dict, Iterator, List, Optional
+
+import db
+from audit_service import AuditEventType
+
+DEVICE_ASSIGNMENT_EVENTS = {
+    AuditEventType.DEVICE_BIND.value: "bind",
+    AuditEventType.DEVICE_UNBIND.value: "unbind",
+}
+
+
+def _parse_time(value: Any) -> Optional[datetime]:
+    """Timestamps are stored as unix seconds (readings) or ISO-8601 strings."""
+    if isinstance(value, (int, float)):
+        return datetime.fromtimestamp(value, timezone.utc)
+    if isinstance(value, str):
+        try:
+            parsed = datetime.fromisoformat(value.replace("Z", "+00:00"))
+        except ValueError:
+            return None
+        return parsed if parsed.tzinfo else parsed.replace(tzinfo=timezone.utc)
+    return None
+
+
+def _event(event_type: str, at: Optional[datetime], data: Dict[str, Any], action: Optional[str] = None):
+    return {"type": event_type, "at": at, "action": action, "data": data}
+
+
+def reading_events(readings: List[Dict[str, Any]]) -> Iterator[Dict[str, Any]]:
+    for reading in readings:
+        yield _event("reading", _parse_time(reading.get("timestamp")), reading)
+
+
+def medication_events(medications: List[Dict[str, Any]]) -> Iterator[Dict[str, Any]]:
+    """
+    Derive change events from medication records.
+
+    A record yields "prescribed" at createdAt, "discontinued" at
+    discontinuedAt, and "updated" when it was edited afterwards.
+    """
+    for medication in medications:
+        yield _event("medication_change", _parse_time(medication.get("createdAt")), medication, "prescribed")
+        if medication.get("discontinuedAt"):
+            yield _event("medication_change", _parse_time(medication["discontinuedAt"]), medication, "discontinued")
+        elif medication.get("updatedAt") and medication.get("updatedAt") != medication.get("createdAt"):
+            yield _event("medication_change", _parse_time(medication["updatedAt"]), medication, "updated")
+
+
+def audit_events(logs: List[Dict[str, Any]]) -> Iterator[Dict[str, Any]]:
+    """Device bind/unbind entries become assignments; everything else is an audit entry."""
+    for log in logs:
+        at = _parse_time(log.get("timestamp") or log.get("sk"))
+        assignment = DEVICE_ASSIGNMENT_EVENTS.get(log.get("eventType"))
+        if assignment:
+            yield _event("device_assignment", at, {"deviceId": log.get("resourceId")}, assignment)
+        else:
+            yield _event("audit", at, {
+                "logId": log.get("logId"),
+                "eventType": log.get("eventType"),
+                "action": log.get("action"),
+                "resourceType": log.get("resourceType"),
+                "resourceId": log.get("resourceId"),
+                "outcome": log.get("outcome"),
+            }, log.get("action"))
+
+
+def merge_timeline(
+    events: Iterator[Dict[str, Any]],
+    start: datetime,
+    end: datetime,
+    limit: int
+) -> List[Dict[str, Any]]:
+    """Keep events inside [start, end], newest first, at most limit entries."""
+    in_range = [e for e in events if e["at"] is not None and start <= e["at"] <= end]
+    in_range.sort(key=lambda e: e["at"], reverse=True)
+    return [
+        {"type": e["type"], "timestamp": e["at"].isoformat(), "action": e["action"], "data": e["data"]}
+        for e in in_range[:limit]
+    ]
+
+
+def _audit_logs(start_iso: str, end_iso: str, **filters) -> List[Dict[str, Any]]:
+    logs, token = [], None
+    while True:
+        page = db.get_audit_logs(
+            start_time=start_iso,
+            end_time=end_iso,
+            limit=1000,
+            next_token=token,
+            **filters
+        )
+        logs.extend(page.items)
+        token = page.next_cursor
+        if not token:
+            return logs
+
+
+def _patient_audit_logs(patient_id: str, start_iso: str, end_iso: str) -> List[Dict[str, Any]]:
+    """
+    The patient's own audit events plus device binds/unbinds concerning the
+    patient, which are usually made by a doctor or admin.
+    """
+    logs = {log.get("logId"): log for log in _audit_logs(start_iso, end_iso, user_id=patient_id)}
+    for event_type in DEVICE_ASSIGNMENT_EVENTS:
+        for log in _audit_logs(start_iso, end_iso, event_type=event_type):
+            if (log.get("details") or {}).get("patient_id") == patient_id:
+                logs.setdefault(log.get("logId"), log)
+    return list(logs.values())
+
+
+async def get_patient_timeline(
+    patient_id: str,
+    start: datetime,
+    end: datetime,
+    limit: int = 50
+) -> List[Dict[str, Any]]:
+    """
+    Build a patient's timeline between start and end (timezone-aware).
+
+    Returns:
+        Timeline event dicts {type, timestamp, action, data}, newest first
+    """
+    readings, medications, logs = await asyncio.gather(
+        asyncio.to_thread(db.get_patient_readings, patient_id, int(start.timestamp()), int(end.timestamp())),
+        asyncio.to_thread(db.get_medications, patient_id),
+        asyncio.to_thread(_patient_audit_logs, patient_id, start.isoformat(), end.isoformat()),
+    )
+    events = chain(reading_events(readings), medication_events(medications), audit_events(logs))
+    return merge_timeline(events, start, end, limit)
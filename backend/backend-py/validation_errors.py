@@ -0,0 +1,56 @@
+"""
+Structured request validation errors
+
+Turns FastAPI/Pydantic validation errors into per-field messages so
+forms can highlight each invalid input.
+
+Key Features:
+- Field paths joined with dots (emergencyContact.phone, items.0.name)
+- Request-location prefixes (body/query/path/header/cookie) dropped
+- Model-level errors reported under the "body" key
+- Response body matches HTTPException errors: {"detail": {code, message, details}}
+"""
+
+from typing import Any, Dict, Iterable, List
+
+from fastapi.responses import JSONResponse
+
+VALIDATION_ERROR_STATUS = 422
+_LOCATIONS = {"body", "query", "path", "header", "cookie"}
+_PYDANTIC_PREFIXES = ("Value error, ", "Assertion failed, ")
+
+
+def _field_name(loc: Iterable[Any]) -> str:
+    parts = [str(p) for p in loc]
+    if parts and parts[0] in _LOCATIONS:
+        parts = parts[1:]
+    return ".".join(parts) or "body"
+
+
+def _message(error: Dict[str, Any]) -> str:
+    message = str(error.get("msg", "Invalid value"))
+    for prefix in _PYDANTIC_PREFIXES:
+        if message.startswith(prefix):
+            return message[len(prefix):]
+    return message
+
+
+def field_errors(errors: Iterable[Dict[str, Any]]) -> Dict[str, List[str]]:
+    """Group validation errors by field: {"heightCm": ["heightCm must be positive"], ...}"""
+    grouped: Dict[str, List[str]] = {}
+    for error in errors:
+        grouped.setdefault(_field_name(error.get("loc", ())), []).append(_message(error))
+    return grouped
+
+
+def validation_error_response(errors: Iterable[Dict[str, Any]]) -> JSONResponse:
+    details = field_errors(errors)
+    fields = ", ".join(details)
+    return JSONResponse(
+        status_code=VALIDATION_ERROR_STATUS,
+        content={"detail": {
+            "code": "VALIDATION_ERROR",
+            "message": f"Invalid request: {fields}",
+            "details": details,
+        }}
+    )
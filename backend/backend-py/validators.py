@@ -0,0 +1,132 @@
+"""
+Shared field validators for request models
+
+Plausibility checks that apply to more than one model (e.g. both the
+create and update variants of a patient profile).
+
+Key Features:
+- Date of birth: not in the future, age at most MAX_AGE_YEARS
+- Height / weight: positive and within human ranges
+- calculate_age() for deriving ages from a validated date of birth
+- Phone numbers normalized to E.164 (+<country code><number>); the
+  PhoneNumber field type applies this to any model field
+- Enum-like strings (role, device type, formats) matched case-insensitively
+- User preferences: language from SUPPORTED_LANGUAGES (BCP 47 tags),
+  timezone from the IANA timezone database
+"""
+
+import os
+import re
+from datetime import date, datetime, timezone
+from typing import Annotated, Any, Optional
+from zoneinfo import ZoneInfo, ZoneInfoNotFoundError
+
+from pydantic import AfterValidator
+
+MAX_AGE_YEARS = 150
+HEIGHT_CM_RANGE = (20.0, 280.0)
+WEIGHT_KG_RANGE = (0.5, 700.0)
+
+_PHONE_FORMATTING = re.compile(r"[\s().\-/]")
+_E164 = re.compile(r"^\+[1-9][0-9]{7,14}$")
+_DIGITS = re.compile(r"^[0-9]+$")
+
+# BCP 47 language tags the frontend is translated into
+SUPPORTED_LANGUAGES = tuple(
+    tag.strip() for tag in os.environ.get("SUPPORTED_LANGUAGES", "en-US,en-GB,de-DE,fr-FR,es-ES,zh-CN").split(",")
+    if tag.strip()
+)
+
+
+def _today() -> date:
+    return datetime.now(timezone.utc).date()
+
+
+def calculate_age(date_of_birth: date, today: Optional[date] = None) -> int:
+    """Age in full years on the given day (default today, UTC)."""
+    today = today or _today()
+    had_birthday = (today.month, today.day) >= (date_of_birth.month, date_of_birth.day)
+    return today.year - date_of_birth.year - (0 if had_birthday else 1)
+
+
+def validate_date_of_birth(value: Optional[date]) -> Optional[date]:
+    """Reject birth dates in the future or implying an age over MAX_AGE_YEARS."""
+    if value is None:
+        return value
+    today = _today()
+    if value > today:
+        raise ValueError("dateOfBirth must not be in the future")
+    if calculate_age(value, today) > MAX_AGE_YEARS:
+        raise ValueError(f"dateOfBirth implies an age over {MAX_AGE_YEARS} years")
+    return value
+
+
+def _validate_range(value: Optional[float], name: str, bounds: tuple) -> Optional[float]:
+    if value is None:
+        return value
+    minimum, maximum = bounds
+    if value <= 0:
+        raise ValueError(f"{name} must be positive")
+    if not minimum <= value <= maximum:
+        raise ValueError(f"{name} must be between {minimum:g} and {maximum:g}")
+    return value
+
+
+def validate_height_cm(value: Optional[float]) -> Optional[float]:
+    return _validate_range(value, "heightCm", HEIGHT_CM_RANGE)
+
+
+def validate_weight_kg(value: Optional[float]) -> Optional[float]:
+    return _validate_range(value, "weightKg", WEIGHT_KG_RANGE)
+
+
+def normalize_phone_e164(value: Optional[str]) -> Optional[str]:
+    """
+    Strip formatting from a phone number and verify it is valid E.164.
+
+    "+1 (555) 123-4567" -> "+15551234567"; a leading 00 is accepted in place of +.
+    """
+    if value is None:
+        return value
+    number = _PHONE_FORMATTING.sub("", value)
+    if number.startswith("00"):
+        number = "+" + number[2:]
+    if not _DIGITS.match(number.lstrip("+")):
+        raise ValueError("phone number may only contain digits and formatting characters ( ) - . /")
+    if not number.startswith("+"):
+        raise ValueError("phone number must include the country code, e.g. +15551234567")
+    if not _E164.match(number):
+        raise ValueError("phone number must have 8-15 digits after + and not start with 0")
+    return number
+
+
+# Phone number field: validated and stored in E.164 form, serialized as a plain string
+PhoneNumber = Annotated[str, AfterValidator(normalize_phone_e164)]
+
+
+def normalize_case(value: Any) -> Any:
+    """
+    Lowercase and trim an enum-like string before it is matched ("Doctor" -> "doctor").
+    Non-strings are returned unchanged for the field's own validation to reject.
+    """
+    return value.strip().lower() if isinstance(value, str) else value
+
+
+def validate_language(value: str) -> str:
+    """
+    Match a BCP 47 tag against SUPPORTED_LANGUAGES case-insensitively and
+    return its canonical spelling ("en-us" -> "en-US").
+    """
+    for tag in SUPPORTED_LANGUAGES:
+        if tag.lower() == value.strip().lower().replace("_", "-"):
+            return tag
+    raise ValueError(f"language must be one of: {', '.join(SUPPORTED_LANGUAGES)}")
+
+
+def validate_timezone(value: str) -> str:
+    """Accept only IANA timezone names such as "Europe/Berlin" or "UTC"."""
+    try:
+        ZoneInfo(value)
+    except (ZoneInfoNotFoundError, ValueError):
+        raise ValueError("timezone must be an IANA timezone name, e.g. Europe/Berlin")
+    return value
@@ -46,6 +46,13 @@ if (Test-Path "samconfig.toml") {
 }
 
 if ($LASTEXITCODE -eq 0) {
+    Write-Host ""
+    Write-Host "✉️  Registering SES email templates..." -ForegroundColor Cyan
+    python backend-py/ses_template_service.py
+    if ($LASTEXITCODE -ne 0) {
+        Write-Host "⚠️  SES template registration failed (emails fall back to inline HTML)" -ForegroundColor Yellow
+    }
+
     Write-Host ""
     Write-Host "✅ Deployment successful!" -ForegroundColor Green
     Write-Host ""
@@ -3,7 +3,7 @@ import boto3
 import os
 import numpy as np
 from decimal import Decimal
-from datetime import datetime
+from datetime import datetime, timezone
 from scipy.signal import butter, filtfilt
 from scipy.fft import rfft, rfftfreq
 
@@ -175,22 +175,43 @@ def lambda_handler(event, context):
     return process_device_window(device_id, start_timestamp, end_timestamp, window_size, sampling_rate, patient_id)
 
 
+def is_calibration_overdue(device):
+    """Check the device registry's calibrationDueAt against the current time."""
+    due_at = device.get('calibrationDueAt') if device else None
+    if not due_at:
+        return False
+    try:
+        due = datetime.fromisoformat(due_at.replace('Z', '+00:00'))
+        if due.tzinfo is None:
+            due = due.replace(tzinfo=timezone.utc)
+        return due <= datetime.now(timezone.utc)
+    except ValueError:
+        return False
+
+
 def process_device_window(device_id, start_timestamp, end_timestamp, window_size=100, sampling_rate=100, patient_id=None):
     """Helper function to process a specific time window for a device"""
     
     if not device_id:
         return {'statusCode': 400, 'body': 'Missing device_id'}
     
-    # Lookup patient_id from device registry if not provided
+    # Lookup device registry entry for ownership and calibration status
+    device = {}
+    try:
+        device = devices_table.get_item(Key={'id': device_id}).get('Item', {})
+    except Exception as e:
+        print(f"Error looking up device: {e}")
+    
+    # Resolve patient_id if not provided
     # This ensures data is correctly attributed to the current owner
-    if not patient_id or patient_id == "UNASSIGNED":
-        try:
-            device_record = devices_table.get_item(Key={'id': device_id})
-            if 'Item' in device_record:
-                patient_id = device_record['Item'].get('patientId')
-                print(f"Resolved patient_id {patient_id} for device {device_id}")
-        except Exception as e:
-            print(f"Error looking up device owner: {e}")
+    if (not patient_id or patient_id == "UNASSIGNED") and device:
+        patient_id = device.get('patientId')
+        print(f"Resolved patient_id {patient_id} for device {device_id}")
+    
+    # Devices overdue for calibration still produce results, flagged as low quality
+    calibration_overdue = is_calibration_overdue(device)
+    if calibration_overdue:
+        print(f"Device {device_id} is overdue for calibration; flagging readings as low quality")
     
     try:
         # Query sensor data from DynamoDB
@@ -280,6 +301,10 @@ def process_device_window(device_id, start_timestamp, end_timestamp, window_size
             'tremor_score': Decimal(str(features['tremor_index'] * 100)),
             'is_parkinsonian': features['is_parkinsonian'],
             
+            # Quality flags
+            'calibration_overdue': calibration_overdue,
+            'low_quality': calibration_overdue,
+            
             # Metadata
             'processed_at': int(datetime.utcnow().timestamp()),
             'ttl': int(datetime.utcnow().timestamp()) + (90 * 24 * 60 * 60)  # 90 days retention
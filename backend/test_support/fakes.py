@@ -0,0 +1,21 @@
+"""
+Fakes shared by the backend-py unit tests.
+
+Kept outside backend-py so that they are not packaged into the Lambda functions.
+"""
+
+from types import SimpleNamespace
+
+
+def fake_request(sub=None, role="patient", org=None, headers=None, **attrs):
+    """Request as the handlers see it after the auth middleware.
+
+    The JWT claims sit on request.state (None without sub) and there is no
+    client address. Further attributes such as json or body are set as given.
+    """
+    claims = None
+    if sub is not None:
+        claims = {"sub": sub, "role": role}
+        if org:
+            claims["org"] = org
+    return SimpleNamespace(headers=dict(headers or {}), client=None, state=SimpleNamespace(claims=claims), **attrs)
@@ -48,8 +48,8 @@ class AdminApiService {
         return AuditLogsResponse(
           success: true,
           items: items,
-          count: data['count'] as int? ?? items.length,
-          nextToken: data['nextToken'] as String?,
+          count: data['total'] as int? ?? items.length,
+          nextToken: data['nextCursor'] as String?,
         );
       }
       
@@ -170,7 +170,7 @@ class AdminApiService {
         return UsersResponse(
           success: true,
           users: items,
-          nextToken: data['nextToken'] as String?,
+          nextToken: data['nextCursor'] as String?,
         );
       }
       
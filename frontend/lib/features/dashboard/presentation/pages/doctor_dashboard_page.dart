@@ -83,7 +83,7 @@ class _DoctorDashboardPageState extends State<DoctorDashboardPage> {
         
         // Auto-select first patient if none selected
         if (_availablePatients.isNotEmpty && _selectedPatientId == null) {
-          _selectedPatientId = _availablePatients.first['patient_id'];
+          _selectedPatientId = _availablePatients.first['patientId'];
           _selectedPatientName = _availablePatients.first['name'] ?? _availablePatients.first['email'];
           _loadData();
         }
@@ -162,7 +162,7 @@ class _DoctorDashboardPageState extends State<DoctorDashboardPage> {
     
     if (patientId != null) {
       final patient = _availablePatients.firstWhere(
-        (p) => p['patient_id'] == patientId,
+        (p) => p['patientId'] == patientId,
         orElse: () => _availablePatients.first,
       );
       setState(() {
@@ -682,7 +682,7 @@ class _DoctorDashboardPageState extends State<DoctorDashboardPage> {
                     icon: Icon(Icons.arrow_drop_down, color: AppColors.primary),
                     items: _availablePatients.map((patient) {
                       return DropdownMenuItem<String>(
-                        value: patient['patient_id'],
+                        value: patient['patientId'],
                         child: Row(
                           children: [
                             Container(
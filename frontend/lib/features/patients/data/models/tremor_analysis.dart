@@ -37,23 +37,25 @@ class TremorAnalysis {
   });
 
   factory TremorAnalysis.fromJson(Map<String, dynamic> json) {
-    // API returns: patient_id, timestamp, device_id, tremor_score, tremor_frequency,
-    // tremor_amplitude, features.rms, is_parkinsonian, signal_quality
+    // API returns camelCase: patientId, timestamp, deviceId, tremorIndex, rmsValue,
+    // dominantFrequency, tremorPower, isParkinsonian, signalQuality
+    // (snake_case keys are still read for older responses and local data)
     final timestamp = _parseTimestamp(json['timestamp']);
     
     // Extract RMS from features object if it exists, or directly from json
     final features = json['features'] as Map<String, dynamic>?;
     final rmsValue = features != null 
         ? _parseDouble(features['rms']) 
-        : _parseDouble(json['rms_value'] ?? json['rms']);
+        : _parseDouble(json['rmsValue'] ?? json['rms_value'] ?? json['rms']);
     
     // Handle tremor_index (0-1)
     // We expect the API to return tremor_index in 0-1 range.
     // If it returns > 1 (legacy data), we normalize it.
     double tremorIndexVal = 0.0;
     
-    if (json['tremor_index'] != null) {
-      tremorIndexVal = _parseDouble(json['tremor_index']);
+    final tremorIndexRaw = json['tremorIndex'] ?? json['tremor_index'];
+    if (tremorIndexRaw != null) {
+      tremorIndexVal = _parseDouble(tremorIndexRaw);
       // Ensure it's in 0-1 range
       if (tremorIndexVal > 1.0) {
         tremorIndexVal = tremorIndexVal / 100.0;
@@ -73,7 +75,7 @@ class TremorAnalysis {
       sampleCount: json['sample_count'] ?? json['sampleCount'] ?? 0,
       samplingRate: json['sampling_rate'] ?? json['samplingRate'] ?? 100,
       rms: rmsValue,
-      dominantFreq: _parseDouble(json['tremor_frequency'] ?? json['dominant_frequency'] ?? json['dominantFreq']),
+      dominantFreq: _parseDouble(json['tremor_frequency'] ?? json['dominant_frequency'] ?? json['dominantFrequency'] ?? json['dominantFreq']),
       tremorPower: _parseDouble(json['tremor_amplitude'] ?? json['tremor_power'] ?? json['tremorPower']),
       tremorIndex: tremorIndexVal,  // Store as 0-1
       isParkinsonian: json['is_parkinsonian'] ?? json['isParkinsonian'] ?? false,
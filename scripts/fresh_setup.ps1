@@ -133,6 +133,19 @@ aws dynamodb update-time-to-live --table-name medusa-sensor-data --time-to-live-
 aws dynamodb update-time-to-live --table-name medusa-tremor-analysis --time-to-live-specification "Enabled=true,AttributeName=ttl" --region $Region 2>$null
 Write-Host "  [OK] TTL enabled" -ForegroundColor Green
 
+# device_id-timestamp-index lets the API query one device's readings instead of scanning the table
+Write-Host "  Adding device_id-timestamp-index to medusa-tremor-analysis..." -ForegroundColor Gray
+try {
+    aws dynamodb update-table `
+        --table-name medusa-tremor-analysis `
+        --attribute-definitions AttributeName=device_id,AttributeType=S AttributeName=timestamp,AttributeType=S `
+        --global-secondary-index-updates '[{"Create":{"IndexName":"device_id-timestamp-index","KeySchema":[{"AttributeName":"device_id","KeyType":"HASH"},{"AttributeName":"timestamp","KeyType":"RANGE"}],"Projection":{"ProjectionType":"ALL"}}}]' `
+        --region $Region 2>$null
+    Write-Host "  [OK] device_id-timestamp-index created" -ForegroundColor Green
+} catch {
+    Write-Host "  [INFO] device_id-timestamp-index may already exist" -ForegroundColor Yellow
+}
+
 # Step 4: Setup SES
 Write-Host ""
 Write-Host "Step 4: Setting up AWS SES for email..." -ForegroundColor Yellow